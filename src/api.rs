@@ -1,4 +1,96 @@
+use crate::types::BoxFuture;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+/// Characters of an error response body kept for display
+const BODY_SNIPPET_LEN: usize = 200;
+
+/// IDs looked up by one `id:` metatag; e621 takes at most 100 in one
+pub const MAX_IDS_PER_LOOKUP: usize = 100;
+
+/// Why an API request failed
+#[derive(Debug)]
+pub enum ApiError {
+	/// Told to slow down (429/503); `retry_after` comes from the header when sent
+	RateLimited { retry_after: Option<Duration> },
+	/// Any other non-success status
+	Http {
+		status: reqwest::StatusCode,
+		body_snippet: String,
+	},
+	/// Connection failed, timed out or broke off mid-response
+	Network(reqwest::Error),
+	/// The response didn't match our types, e.g. the API schema changed.
+	/// `serde_path` points at the offending value, like `posts[3].file.size`.
+	Decode { serde_path: String, message: String },
+	/// Never sent: `action` needs an account and no API key was given
+	MissingCredentials { action: &'static str },
+}
+
+/// Coarse kind of an `ApiError`, cheap to pass around in events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+	RateLimited,
+	Http,
+	Network,
+	Decode,
+	Credentials,
+}
+
+impl ApiError {
+	pub fn category(&self) -> ErrorCategory {
+		match self {
+			ApiError::RateLimited { .. } => ErrorCategory::RateLimited,
+			ApiError::Http { .. } => ErrorCategory::Http,
+			ApiError::Network(_) => ErrorCategory::Network,
+			ApiError::Decode { .. } => ErrorCategory::Decode,
+			ApiError::MissingCredentials { .. } => ErrorCategory::Credentials,
+		}
+	}
+}
+
+impl fmt::Display for ApiError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ApiError::RateLimited {
+				retry_after: Some(after),
+			} => write!(f, "Rate limited, retry after {}s", after.as_secs()),
+			ApiError::RateLimited { retry_after: None } => f.write_str("Rate limited"),
+			ApiError::Http {
+				status,
+				body_snippet,
+			} if body_snippet.is_empty() => write!(f, "Request failed with status: {}", status),
+			ApiError::Http {
+				status,
+				body_snippet,
+			} => write!(
+				f,
+				"Request failed with status: {} ({})",
+				status, body_snippet
+			),
+			ApiError::Network(e) => write!(f, "Network error: {}", e),
+			ApiError::Decode {
+				serde_path,
+				message,
+			} => write!(f, "Unexpected response at '{}': {}", serde_path, message),
+			ApiError::MissingCredentials { action } => {
+				write!(f, "{} requires API credentials", action)
+			}
+		}
+	}
+}
+
+impl std::error::Error for ApiError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			ApiError::Network(e) => Some(e),
+			_ => None,
+		}
+	}
+}
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Post {
@@ -71,6 +163,25 @@ pub struct Tags {
 	pub lore: Vec<String>,
 }
 
+impl Tags {
+	/// Every tag on the post regardless of category
+	pub fn iter(&self) -> impl Iterator<Item = &str> {
+		[
+			&self.artist,
+			&self.copyright,
+			&self.character,
+			&self.species,
+			&self.general,
+			&self.meta,
+			&self.lore,
+			&self.invalid,
+		]
+		.into_iter()
+		.flatten()
+		.map(String::as_str)
+	}
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Flags {
 	pub pending: bool,
@@ -89,22 +200,233 @@ pub struct Relationships {
 	pub children: Vec<u64>,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+	pub id: u64,
+	pub post_id: u64,
+	pub x: i64,
+	pub y: i64,
+	pub width: i64,
+	pub height: i64,
+	pub body: String,
+	pub is_active: bool,
+}
+
+/// The notes endpoint returns a bare array, or `{"notes": []}` when empty
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum NotesResponse {
+	List(Vec<Note>),
+	Empty { notes: Vec<Note> },
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PostsResponse {
 	pub posts: Vec<Post>,
 }
 
+/// A tag and how many posts carry it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Tag {
+	pub name: String,
+	pub post_count: u64,
+}
+
+/// Like notes, an empty tag search comes back as an object
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum TagsResponse {
+	List(Vec<Tag>),
+	Empty { tags: Vec<Tag> },
+}
+
+/// The fields of a pool we use; names come with underscores for spaces
+#[derive(Debug, Deserialize)]
+pub struct Pool {
+	pub name: String,
+}
+
+/// A user-curated post set; `post_ids` are in the set's own order
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PostSet {
+	pub id: u64,
+	pub name: String,
+	#[serde(default)]
+	pub post_ids: Vec<u64>,
+}
+
+/// The fields of a user we use; the blacklist is only sent for our own account
+#[derive(Debug, Deserialize)]
+pub struct User {
+	#[serde(default)]
+	pub blacklisted_tags: String,
+}
+
+/// Totals returned after casting a vote
+#[derive(Debug, Deserialize)]
+pub struct VoteResponse {
+	pub score: i64,
+	pub up: i64,
+	pub down: i64,
+	pub our_score: i64,
+}
+
+/// e621 account name and API key, used for authenticated endpoints
+#[derive(Debug, Clone)]
+pub struct Credentials {
+	pub username: String,
+	pub api_key: String,
+}
+
+/// The booru API as used by the gateway; implemented by `E621Client`
+/// and swappable for canned data in tests
+pub trait BooruClient: Send + Sync {
+	fn search_posts<'a>(
+		&'a self,
+		tags: &'a str,
+		limit: u32,
+		page: u32,
+	) -> BoxFuture<'a, Result<Vec<Post>, ApiError>>;
+
+	fn get_notes(&self, post_id: u64) -> BoxFuture<'_, Result<Vec<Note>, ApiError>>;
+
+	fn vote(&self, post_id: u64, score: i8) -> BoxFuture<'_, Result<VoteResponse, ApiError>>;
+
+	fn get_pool_name(&self, pool_id: u64) -> BoxFuture<'_, Result<String, ApiError>>;
+
+	fn get_set(&self, set_id: u64) -> BoxFuture<'_, Result<PostSet, ApiError>>;
+
+	/// Post counts of the named tags; names that aren't tags are left out
+	fn get_tags<'a>(&'a self, names: &'a [String]) -> BoxFuture<'a, Result<Vec<Tag>, ApiError>>;
+
+	fn get_blacklist(&self) -> BoxFuture<'_, Result<String, ApiError>>;
+
+	/// Posts with these IDs, at most `MAX_IDS_PER_LOOKUP`, in the API's order;
+	/// deleted or unknown ones are left out. `order_by_ids` sorts them out.
+	fn get_posts_by_ids<'a>(&'a self, ids: &'a [u64])
+	-> BoxFuture<'a, Result<Vec<Post>, ApiError>>;
+
+	fn credentials(&self) -> Option<&Credentials>;
+
+	/// Reach the site with a bare request, for the self-check. Clients that
+	/// don't talk to a site are always reachable.
+	fn ping(&self) -> BoxFuture<'_, Result<reqwest::StatusCode, ApiError>> {
+		Box::pin(async { Ok(reqwest::StatusCode::OK) })
+	}
+}
+
+/// `ids` split into lookups of at most `MAX_IDS_PER_LOOKUP`, in order.
+/// Repeated IDs are asked for once.
+pub fn id_chunks(ids: &[u64]) -> Vec<Vec<u64>> {
+	let mut seen = HashSet::new();
+	let unique: Vec<u64> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+	unique
+		.chunks(MAX_IDS_PER_LOOKUP)
+		.map(<[u64]>::to_vec)
+		.collect()
+}
+
+/// The search query looking up `ids`, as `id:1,2,3`
+fn id_query(ids: &[u64]) -> String {
+	let list: Vec<String> = ids.iter().map(u64::to_string).collect();
+	format!("id:{}", list.join(","))
+}
+
+/// `posts` in the order of `ids`, and the IDs none of them has
+pub fn order_by_ids(ids: &[u64], posts: Vec<Post>) -> (Vec<Post>, Vec<u64>) {
+	let mut by_id: HashMap<u64, Post> = posts.into_iter().map(|post| (post.id, post)).collect();
+	let mut seen = HashSet::new();
+	let mut ordered = Vec::with_capacity(by_id.len());
+	let mut missing = Vec::new();
+	for id in ids.iter().filter(|id| seen.insert(**id)) {
+		match by_id.remove(id) {
+			Some(post) => ordered.push(post),
+			None => missing.push(*id),
+		}
+	}
+	(ordered, missing)
+}
+
 pub struct E621Client {
 	client: reqwest::Client,
+	credentials: Option<Credentials>,
 }
 
 impl E621Client {
-	pub fn new() -> Self {
+	pub fn new(credentials: Option<Credentials>) -> Self {
 		let client = reqwest::Client::builder()
 			.user_agent("Sodglumate/0.1 (by furikeno)")
 			.build()
 			.expect("Failed to build reqwest client");
-		Self { client }
+		Self {
+			client,
+			credentials,
+		}
+	}
+
+	pub fn credentials(&self) -> Option<&Credentials> {
+		self.credentials.as_ref()
+	}
+
+	/// Reach the site without asking the API for anything, returning the status
+	pub async fn ping(&self) -> Result<reqwest::StatusCode, ApiError> {
+		let response = self
+			.client
+			.head("https://e621.net/")
+			.send()
+			.await
+			.map_err(ApiError::Network)?;
+		let status = response.status();
+		if !status.is_success() {
+			return Err(ApiError::Http {
+				status,
+				body_snippet: String::new(),
+			});
+		}
+		Ok(status)
+	}
+
+	/// Send a request and decode a successful JSON response
+	async fn fetch<T: DeserializeOwned>(
+		&self,
+		request: reqwest::RequestBuilder,
+	) -> Result<T, ApiError> {
+		let response = request.send().await.map_err(ApiError::Network)?;
+
+		let status = response.status();
+		log::debug!("Response status: {}", status);
+		if matches!(
+			status,
+			reqwest::StatusCode::TOO_MANY_REQUESTS | reqwest::StatusCode::SERVICE_UNAVAILABLE
+		) {
+			let retry_after = response
+				.headers()
+				.get(reqwest::header::RETRY_AFTER)
+				.and_then(|v| v.to_str().ok())
+				.and_then(|v| v.trim().parse::<u64>().ok())
+				.map(Duration::from_secs);
+			log::warn!(
+				"Rate limited (status {}), retry after {:?}",
+				status,
+				retry_after
+			);
+			return Err(ApiError::RateLimited { retry_after });
+		}
+		if !status.is_success() {
+			let error_text = response
+				.text()
+				.await
+				.unwrap_or_else(|_| "<failed to read error text>".into());
+			log::error!("Request failed. Status: {}, Body: {}", status, error_text);
+			return Err(ApiError::Http {
+				status,
+				body_snippet: error_text.trim().chars().take(BODY_SNIPPET_LEN).collect(),
+			});
+		}
+
+		let text = response.text().await.map_err(ApiError::Network)?;
+		log::debug!("Response body length: {}", text.len());
+		decode(&text)
 	}
 
 	pub async fn search_posts(
@@ -112,7 +434,7 @@ impl E621Client {
 		tags: &str,
 		limit: u32,
 		page: u32,
-	) -> anyhow::Result<Vec<Post>> {
+	) -> Result<Vec<Post>, ApiError> {
 		let url = "https://e621.net/posts.json";
 		log::info!(
 			"Searching posts with tags: '{}', limit: {}, page: {}",
@@ -127,24 +449,7 @@ impl E621Client {
 			("page", &page.to_string()),
 		];
 
-		let response = self.client.get(url).query(&query).send().await?;
-
-		let status = response.status();
-		log::info!("Search response status: {}", status);
-
-		if !status.is_success() {
-			let error_text = response
-				.text()
-				.await
-				.unwrap_or_else(|_| "<failed to read error text>".into());
-			log::error!("Search failed. Status: {}, Body: {}", status, error_text);
-			anyhow::bail!("Request failed with status: {}", status);
-		}
-
-		let text = response.text().await?;
-		log::debug!("Search response body length: {}", text.len());
-
-		let resp_json: PostsResponse = serde_json::from_str(&text)?;
+		let resp_json: PostsResponse = self.fetch(self.client.get(url).query(&query)).await?;
 		let original_len = resp_json.posts.len();
 		let valid_posts: Vec<Post> = resp_json
 			.posts
@@ -160,4 +465,240 @@ impl E621Client {
 
 		Ok(valid_posts)
 	}
+
+	pub async fn get_notes(&self, post_id: u64) -> Result<Vec<Note>, ApiError> {
+		let url = "https://e621.net/notes.json";
+		log::info!("Fetching notes for post {}", post_id);
+
+		let query = [("search[post_id]", post_id.to_string())];
+		let notes = match self.fetch(self.client.get(url).query(&query)).await? {
+			NotesResponse::List(notes) => notes,
+			NotesResponse::Empty { notes } => notes,
+		};
+		let active: Vec<Note> = notes.into_iter().filter(|n| n.is_active).collect();
+
+		log::info!("Found {} active notes for post {}", active.len(), post_id);
+
+		Ok(active)
+	}
+
+	/// Display name of a pool, with underscores turned back into spaces
+	pub async fn get_pool_name(&self, pool_id: u64) -> Result<String, ApiError> {
+		let url = format!("https://e621.net/pools/{}.json", pool_id);
+		log::info!("Fetching name of pool {}", pool_id);
+
+		let pool: Pool = self.fetch(self.client.get(&url)).await?;
+		Ok(pool.name.replace('_', " "))
+	}
+
+	/// A set's name and post IDs; the posts themselves come from `get_posts_by_ids`
+	pub async fn get_set(&self, set_id: u64) -> Result<PostSet, ApiError> {
+		let url = format!("https://e621.net/post_sets/{}.json", set_id);
+		log::info!("Fetching set {}", set_id);
+
+		let set: PostSet = self.fetch(self.client.get(&url)).await?;
+		log::info!(
+			"Set {} '{}' has {} posts",
+			set.id,
+			set.name,
+			set.post_ids.len()
+		);
+		Ok(set)
+	}
+
+	pub async fn get_tags(&self, names: &[String]) -> Result<Vec<Tag>, ApiError> {
+		let url = "https://e621.net/tags.json";
+		log::info!("Fetching post counts of {} tags", names.len());
+
+		let query = [
+			("search[name]", names.join(",")),
+			("limit", names.len().to_string()),
+		];
+		let tags = match self.fetch(self.client.get(url).query(&query)).await? {
+			TagsResponse::List(tags) => tags,
+			TagsResponse::Empty { tags } => tags,
+		};
+		Ok(tags)
+	}
+
+	/// Posts with these IDs in one request, so at most `MAX_IDS_PER_LOOKUP` of them
+	pub async fn get_posts_by_ids(&self, ids: &[u64]) -> Result<Vec<Post>, ApiError> {
+		debug_assert!(ids.len() <= MAX_IDS_PER_LOOKUP);
+		log::info!("Fetching {} posts by ID", ids.len());
+		self.search_posts(&id_query(ids), ids.len() as u32, 1).await
+	}
+
+	/// The account's server-side blacklist, one rule per line
+	pub async fn get_blacklist(&self) -> Result<String, ApiError> {
+		let Some(credentials) = &self.credentials else {
+			return Err(ApiError::MissingCredentials {
+				action: "The account blacklist",
+			});
+		};
+		let url = format!("https://e621.net/users/{}.json", credentials.username);
+		log::info!("Fetching blacklist of {}", credentials.username);
+
+		let request = self
+			.client
+			.get(&url)
+			.basic_auth(&credentials.username, Some(&credentials.api_key));
+		let user: User = self.fetch(request).await?;
+		Ok(user.blacklisted_tags)
+	}
+
+	/// Vote on a post; voting the same way twice removes the vote
+	pub async fn vote(&self, post_id: u64, score: i8) -> Result<VoteResponse, ApiError> {
+		let Some(credentials) = &self.credentials else {
+			return Err(ApiError::MissingCredentials { action: "Voting" });
+		};
+		let url = format!("https://e621.net/posts/{}/votes.json", post_id);
+		log::info!("Voting on post {}: {}", post_id, score);
+
+		let request = self
+			.client
+			.post(&url)
+			.basic_auth(&credentials.username, Some(&credentials.api_key))
+			.form(&[("score", score.to_string())]);
+		self.fetch(request).await
+	}
+}
+
+impl BooruClient for E621Client {
+	fn search_posts<'a>(
+		&'a self,
+		tags: &'a str,
+		limit: u32,
+		page: u32,
+	) -> BoxFuture<'a, Result<Vec<Post>, ApiError>> {
+		Box::pin(E621Client::search_posts(self, tags, limit, page))
+	}
+
+	fn get_notes(&self, post_id: u64) -> BoxFuture<'_, Result<Vec<Note>, ApiError>> {
+		Box::pin(E621Client::get_notes(self, post_id))
+	}
+
+	fn vote(&self, post_id: u64, score: i8) -> BoxFuture<'_, Result<VoteResponse, ApiError>> {
+		Box::pin(E621Client::vote(self, post_id, score))
+	}
+
+	fn get_pool_name(&self, pool_id: u64) -> BoxFuture<'_, Result<String, ApiError>> {
+		Box::pin(E621Client::get_pool_name(self, pool_id))
+	}
+
+	fn get_set(&self, set_id: u64) -> BoxFuture<'_, Result<PostSet, ApiError>> {
+		Box::pin(E621Client::get_set(self, set_id))
+	}
+
+	fn get_tags<'a>(&'a self, names: &'a [String]) -> BoxFuture<'a, Result<Vec<Tag>, ApiError>> {
+		Box::pin(E621Client::get_tags(self, names))
+	}
+
+	fn get_blacklist(&self) -> BoxFuture<'_, Result<String, ApiError>> {
+		Box::pin(E621Client::get_blacklist(self))
+	}
+
+	fn get_posts_by_ids<'a>(
+		&'a self,
+		ids: &'a [u64],
+	) -> BoxFuture<'a, Result<Vec<Post>, ApiError>> {
+		Box::pin(E621Client::get_posts_by_ids(self, ids))
+	}
+
+	fn credentials(&self) -> Option<&Credentials> {
+		E621Client::credentials(self)
+	}
+
+	fn ping(&self) -> BoxFuture<'_, Result<reqwest::StatusCode, ApiError>> {
+		Box::pin(E621Client::ping(self))
+	}
+}
+
+/// Decode a JSON body, locating the offending value on failure
+fn decode<T: DeserializeOwned>(text: &str) -> Result<T, ApiError> {
+	let deserializer = &mut serde_json::Deserializer::from_str(text);
+	serde_path_to_error::deserialize(deserializer).map_err(|e| {
+		let serde_path = e.path().to_string();
+		let message = e.inner().to_string();
+		log::error!("Failed to decode response at '{}': {}", serde_path, message);
+		ApiError::Decode {
+			serde_path,
+			message,
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_error_points_at_the_bad_field() {
+		let json = r#"{"posts": [{"id": 1}, {"id": "two"}]}"#;
+		#[derive(Debug, Deserialize)]
+		struct Item {
+			#[allow(dead_code)]
+			id: u64,
+		}
+		#[derive(Debug, Deserialize)]
+		struct Page {
+			#[allow(dead_code)]
+			posts: Vec<Item>,
+		}
+		match decode::<Page>(json) {
+			Err(ApiError::Decode { serde_path, .. }) => assert_eq!(serde_path, "posts[1].id"),
+			other => panic!("expected decode error, got {:?}", other),
+		}
+	}
+
+	fn post(id: u64) -> Post {
+		Post {
+			id,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn id_lookups_are_chunked_in_order_without_repeats() {
+		let ids: Vec<u64> = (1..=250).chain([7, 3]).collect();
+		let chunks = id_chunks(&ids);
+		assert_eq!(
+			chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+			[100, 100, 50]
+		);
+		assert_eq!(chunks[0][..3], [1, 2, 3]);
+		assert_eq!(chunks[2].last(), Some(&250));
+		assert!(id_query(&chunks[0]).starts_with("id:1,2,3,"));
+		assert!(id_query(&chunks[0]).ends_with(",100"));
+		assert!(id_chunks(&[]).is_empty());
+	}
+
+	#[test]
+	fn posts_come_back_in_the_order_asked_with_the_missing_ids() {
+		// The API sorts by ID descending, and leaves deleted posts out
+		let fetched = vec![post(9), post(5), post(2)];
+		let (posts, missing) = order_by_ids(&[5, 4, 9, 2, 5, 4], fetched);
+		assert_eq!(posts.iter().map(|p| p.id).collect::<Vec<_>>(), [5, 9, 2]);
+		assert_eq!(missing, [4]);
+	}
+
+	#[test]
+	fn missing_field_points_at_its_object() {
+		let json = "{\n  \"posts\": [\n    {\"id\": 1, \"created_at\": \"x\"}\n  ]\n}";
+		#[derive(Debug, Deserialize)]
+		struct Item {
+			#[allow(dead_code)]
+			id: u64,
+			#[allow(dead_code)]
+			file: String,
+		}
+		#[derive(Debug, Deserialize)]
+		struct Page {
+			#[allow(dead_code)]
+			posts: Vec<Item>,
+		}
+		match decode::<Page>(json) {
+			Err(ApiError::Decode { serde_path, .. }) => assert_eq!(serde_path, "posts[0]"),
+			other => panic!("expected decode error, got {:?}", other),
+		}
+	}
 }
@@ -0,0 +1,382 @@
+use std::collections::VecDeque;
+
+/// Number of history windows for rolling average
+const HISTORY_LEN: usize = 43;
+
+/// Energy threshold multiplier over rolling average to trigger a beat
+const BEAT_THRESHOLD: f32 = 1.5;
+
+/// Minimum time between beats to avoid double-triggers
+const BEAT_COOLDOWN_SECS: f32 = 0.2;
+
+/// Candidate beats whose spacing is checked before beats are let through
+const LOCK_CANDIDATES: usize = 8;
+
+/// Interval coefficient of variation needed to lock on
+const LOCK_CV: f32 = 0.06;
+
+/// Looser limit for staying locked, so one sloppy hit doesn't drop the lock
+const KEEP_LOCK_CV: f32 = 0.2;
+
+/// Lock is dropped after this many beat intervals without a candidate
+const LOCK_TIMEOUT_BEATS: f32 = 2.5;
+
+/// Candidates older than this are forgotten while not locked
+const CANDIDATE_TIMEOUT_SECS: f32 = 2.0;
+
+/// Mean window energy below this is treated as silence (about -60 dBFS)
+const SILENCE_FLOOR: f32 = 1e-6;
+
+/// Seconds the average energy must stay under the floor before silence is reported
+const SILENCE_SECS: f32 = 5.0;
+
+/// How sure the detector is that it's hearing a steady beat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockState {
+	/// No recent candidates
+	Unlocked,
+	/// Collecting candidates, nothing is emitted yet
+	Locking,
+	/// Candidates are evenly spaced; beats are emitted
+	Locked { bpm: f32 },
+}
+
+/// What a processed window produced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Detection {
+	Beat {
+		scale: f32,
+	},
+	/// Was locked, but the candidates stopped being periodic
+	LockLost,
+	/// The input went quiet; reported once until sound comes back
+	Silence,
+}
+
+/// Energy-based onset detection, gated on the onsets being periodic.
+/// Speech and other irregular sound produce plenty of onsets, but not evenly spaced ones.
+pub struct BeatDetector {
+	energy_history: Vec<f32>,
+	history_index: usize,
+	/// Audible windows seen, until the history is full
+	warmup: usize,
+	/// Seconds of audio processed so far
+	clock: f32,
+	/// Times of the most recent candidates, oldest first
+	candidates: VecDeque<f32>,
+	locked_bpm: Option<f32>,
+	/// Seconds the average energy has been under `silence_floor`
+	quiet_for: f32,
+	/// Silence was reported and no sound came since
+	silent: bool,
+	pub silence_floor: f32,
+	pub silence_secs: f32,
+}
+
+impl BeatDetector {
+	pub fn new() -> Self {
+		Self {
+			energy_history: vec![0.0; HISTORY_LEN],
+			history_index: 0,
+			warmup: 0,
+			clock: 0.0,
+			candidates: VecDeque::with_capacity(LOCK_CANDIDATES),
+			locked_bpm: None,
+			quiet_for: 0.0,
+			silent: false,
+			silence_floor: SILENCE_FLOOR,
+			silence_secs: SILENCE_SECS,
+		}
+	}
+
+	/// Forget everything heard, e.g. after switching devices. The thresholds stay.
+	pub fn reset(&mut self) {
+		*self = Self {
+			silence_floor: self.silence_floor,
+			silence_secs: self.silence_secs,
+			..Self::new()
+		};
+	}
+
+	pub fn is_silent(&self) -> bool {
+		self.silent
+	}
+
+	pub fn lock_state(&self) -> LockState {
+		match self.locked_bpm {
+			Some(bpm) => LockState::Locked { bpm },
+			None if self.candidates.is_empty() => LockState::Unlocked,
+			None => LockState::Locking,
+		}
+	}
+
+	/// Feed the mean energy of one window lasting `window_secs`
+	pub fn process(&mut self, energy: f32, window_secs: f32) -> Option<Detection> {
+		self.clock += window_secs;
+
+		// Compute rolling average
+		let avg_energy: f32 =
+			self.energy_history.iter().sum::<f32>() / self.energy_history.len() as f32;
+
+		// Update history ring buffer
+		self.energy_history[self.history_index] = energy;
+		self.history_index = (self.history_index + 1) % HISTORY_LEN;
+
+		let quiet =
+			self.energy_history.iter().sum::<f32>() / (HISTORY_LEN as f32) < self.silence_floor;
+		if let Some(silence) = self.track_silence(quiet, window_secs) {
+			return Some(silence);
+		}
+
+		if self.warmup < HISTORY_LEN {
+			// The average isn't meaningful until the history is full. A device that just
+			// opened often starts with zeros; those don't count, or the first sound would
+			// stand out against them as a beat.
+			if energy >= self.silence_floor {
+				self.warmup += 1;
+			}
+			return None;
+		}
+
+		let since_candidate = self
+			.candidates
+			.back()
+			.map_or(f32::INFINITY, |last| self.clock - last);
+
+		// Beat detection with cooldown
+		if energy > avg_energy * BEAT_THRESHOLD
+			&& avg_energy > 1e-8 // Avoid triggering on silence
+			&& since_candidate > BEAT_COOLDOWN_SECS
+		{
+			let scale = (energy / (avg_energy * BEAT_THRESHOLD)).min(3.0);
+			return self.candidate(scale);
+		}
+
+		match self.locked_bpm {
+			Some(bpm) if since_candidate > LOCK_TIMEOUT_BEATS * 60.0 / bpm => {
+				self.lose_lock();
+				Some(Detection::LockLost)
+			}
+			None if since_candidate > CANDIDATE_TIMEOUT_SECS => {
+				self.candidates.clear();
+				None
+			}
+			_ => None,
+		}
+	}
+
+	/// Report silence once the average has stayed under the floor long enough
+	fn track_silence(&mut self, quiet: bool, window_secs: f32) -> Option<Detection> {
+		if !quiet {
+			if self.silent {
+				log::info!("Audio input is no longer silent");
+			}
+			self.quiet_for = 0.0;
+			self.silent = false;
+			return None;
+		}
+		self.quiet_for += window_secs;
+		if self.silent || self.quiet_for < self.silence_secs {
+			return None;
+		}
+		log::info!("Audio input silent for {:.0}s", self.quiet_for);
+		self.silent = true;
+		Some(Detection::Silence)
+	}
+
+	fn candidate(&mut self, scale: f32) -> Option<Detection> {
+		if self.candidates.len() == LOCK_CANDIDATES {
+			self.candidates.pop_front();
+		}
+		self.candidates.push_back(self.clock);
+		if self.candidates.len() < LOCK_CANDIDATES {
+			return None;
+		}
+
+		let (mean, cv) = self.interval_stats();
+		let limit = if self.locked_bpm.is_some() {
+			KEEP_LOCK_CV
+		} else {
+			LOCK_CV
+		};
+		if cv < limit {
+			if self.locked_bpm.is_none() {
+				log::info!("Beat locked at {:.0} BPM (cv={:.3})", 60.0 / mean, cv);
+			}
+			self.locked_bpm = Some(60.0 / mean);
+			Some(Detection::Beat { scale })
+		} else if self.locked_bpm.is_some() {
+			log::info!("Beat lock lost (cv={:.3})", cv);
+			self.lose_lock();
+			Some(Detection::LockLost)
+		} else {
+			None
+		}
+	}
+
+	/// Mean and coefficient of variation of the gaps between candidates
+	fn interval_stats(&self) -> (f32, f32) {
+		let intervals: Vec<f32> = self
+			.candidates
+			.iter()
+			.zip(self.candidates.iter().skip(1))
+			.map(|(a, b)| b - a)
+			.collect();
+		let mean = intervals.iter().sum::<f32>() / intervals.len() as f32;
+		let variance =
+			intervals.iter().map(|i| (i - mean).powi(2)).sum::<f32>() / intervals.len() as f32;
+		(mean, variance.sqrt() / mean)
+	}
+
+	/// Start over, keeping only the most recent candidate
+	fn lose_lock(&mut self) {
+		self.locked_bpm = None;
+		while self.candidates.len() > 1 {
+			self.candidates.pop_front();
+		}
+	}
+}
+
+impl Default for BeatDetector {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// 10ms windows, as with 441 samples at 44.1kHz
+	const WINDOW: f32 = 0.01;
+
+	/// Small deterministic pseudo-random numbers in 0..1
+	fn lcg(seed: &mut u32) -> f32 {
+		*seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+		(*seed >> 8) as f32 / (1u32 << 24) as f32
+	}
+
+	/// Quiet background with a loud window at each onset, `gaps` windows apart
+	fn trace(gaps: &[usize], seed: u32) -> Vec<f32> {
+		let mut seed = seed;
+		let mut energy = Vec::new();
+		for gap in gaps {
+			for _ in 1..*gap {
+				energy.push(0.01 + 0.002 * lcg(&mut seed));
+			}
+			energy.push(0.1);
+		}
+		energy
+	}
+
+	fn run(detector: &mut BeatDetector, energy: &[f32]) -> Vec<Detection> {
+		energy
+			.iter()
+			.filter_map(|e| detector.process(*e, WINDOW))
+			.collect()
+	}
+
+	fn beats(detections: &[Detection]) -> usize {
+		detections
+			.iter()
+			.filter(|d| matches!(d, Detection::Beat { .. }))
+			.count()
+	}
+
+	/// Onsets every ~0.5s with a few ms of jitter
+	fn periodic_gaps(count: usize) -> Vec<usize> {
+		(0..count)
+			.map(|i| [50, 49, 51, 50, 52, 48][i % 6])
+			.collect()
+	}
+
+	/// Syllable-like onsets 0.21 to 0.9s apart
+	fn speech_gaps(count: usize, seed: u32) -> Vec<usize> {
+		let mut seed = seed;
+		(0..count)
+			.map(|_| 21 + (lcg(&mut seed) * 70.0) as usize)
+			.collect()
+	}
+
+	#[test]
+	fn periodic_trace_locks_after_enough_candidates() {
+		let mut detector = BeatDetector::new();
+		let detections = run(&mut detector, &trace(&periodic_gaps(20), 1));
+
+		// The first onsets are only collected; the rest come through
+		assert_eq!(beats(&detections), 20 - LOCK_CANDIDATES + 1);
+		assert!(!detections.contains(&Detection::LockLost));
+		match detector.lock_state() {
+			LockState::Locked { bpm } => assert!((bpm - 120.0).abs() < 2.0, "bpm {}", bpm),
+			state => panic!("expected a lock, got {:?}", state),
+		}
+	}
+
+	#[test]
+	fn aperiodic_trace_never_emits_beats() {
+		for seed in 1..20 {
+			let mut detector = BeatDetector::new();
+			let detections = run(&mut detector, &trace(&speech_gaps(60, seed), seed));
+			assert_eq!(beats(&detections), 0, "seed {}", seed);
+			assert_eq!(detector.lock_state(), LockState::Locking);
+		}
+	}
+
+	#[test]
+	fn lock_is_lost_once_periodicity_collapses() {
+		let mut detector = BeatDetector::new();
+		run(&mut detector, &trace(&periodic_gaps(12), 1));
+		assert!(matches!(detector.lock_state(), LockState::Locked { .. }));
+
+		// A stray onset or two can still pass while the lock holds, nothing after it drops
+		let detections = run(&mut detector, &trace(&speech_gaps(30, 7), 7));
+		let lost = detections
+			.iter()
+			.position(|d| *d == Detection::LockLost)
+			.expect("lock should be lost");
+		assert!(lost <= 2, "lock held for {} onsets", lost);
+		assert_eq!(beats(&detections[lost..]), 0);
+	}
+
+	#[test]
+	fn lock_is_lost_when_candidates_stop() {
+		let mut detector = BeatDetector::new();
+		run(&mut detector, &trace(&periodic_gaps(12), 1));
+
+		let detections = run(&mut detector, &[0.01; 300]);
+		assert_eq!(detections, vec![Detection::LockLost]);
+		assert_eq!(detector.lock_state(), LockState::Unlocked);
+	}
+
+	#[test]
+	fn leading_zeros_from_a_new_device_dont_warm_up() {
+		let mut detector = BeatDetector::new();
+		assert!(run(&mut detector, &[0.0; 200]).is_empty());
+		assert_eq!(detector.warmup, 0);
+
+		let detections = run(&mut detector, &trace(&periodic_gaps(20), 1));
+		assert_eq!(beats(&detections), 20 - LOCK_CANDIDATES + 1);
+	}
+
+	#[test]
+	fn silence_is_reported_once_until_sound_returns() {
+		let mut detector = BeatDetector::new();
+		detector.silence_secs = 0.5;
+		run(&mut detector, &trace(&periodic_gaps(12), 1));
+
+		let detections = run(&mut detector, &[0.0; 300]);
+		let silences = detections
+			.iter()
+			.filter(|d| **d == Detection::Silence)
+			.count();
+		assert_eq!(silences, 1);
+		assert!(detector.is_silent());
+
+		run(&mut detector, &[0.01; 10]);
+		assert!(!detector.is_silent());
+
+		detector.reset();
+		assert_eq!(detector.silence_secs, 0.5);
+		assert_eq!(detector.lock_state(), LockState::Unlocked);
+	}
+}
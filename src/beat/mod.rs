@@ -1,72 +1,126 @@
+mod detector;
+
+pub use detector::LockState;
+
 use crate::reactor::{BeatEvent, ComponentResponse, Event, ViewEvent};
+use anyhow::Context;
+use cpal::Sample;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use std::sync::mpsc;
-use std::time::Instant;
+use detector::{BeatDetector, Detection};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
 
 /// Size of energy analysis window in samples
 const WINDOW_SIZE: usize = 441;
 
-/// Number of history windows for rolling average
-const HISTORY_LEN: usize = 43;
+/// Assumed until a stream reports its own
+const DEFAULT_SAMPLE_RATE: u32 = 44_100;
 
-/// Energy threshold multiplier over rolling average to trigger a beat
-const BEAT_THRESHOLD: f32 = 1.5;
+/// The stream callback's end of the sample channel. Flags each send, so polls
+/// can tell nothing arrived without touching the channel.
+#[derive(Clone)]
+struct SampleSender {
+	tx: mpsc::Sender<Vec<f32>>,
+	ready: Arc<AtomicBool>,
+}
 
-/// Minimum time between beats to avoid double-triggers
-const BEAT_COOLDOWN_MS: u128 = 200;
+impl SampleSender {
+	fn send(&self, samples: Vec<f32>) {
+		if self.tx.send(samples).is_ok() {
+			self.ready.store(true, Ordering::Release);
+		}
+	}
+}
 
 pub struct SystemBeat {
 	/// Raw audio samples from cpal stream
 	sample_rx: mpsc::Receiver<Vec<f32>>,
 	/// Sender cloned into cpal stream callback
-	sample_tx: mpsc::Sender<Vec<f32>>,
+	sample_tx: SampleSender,
+	/// Set by the callback when samples were sent since the last poll
+	samples_ready: Arc<AtomicBool>,
 	/// Active cpal stream (must be kept alive)
 	stream: Option<cpal::Stream>,
+	/// Sample rate of the active stream
+	sample_rate: u32,
 	/// Available device names
 	device_names: Vec<String>,
 	/// Currently selected device name (None = default)
 	selected_device: Option<String>,
 	/// Energy detection state
 	sample_buffer: Vec<f32>,
-	energy_history: Vec<f32>,
-	history_index: usize,
-	last_beat: Instant,
+	detector: BeatDetector,
 }
 
 impl SystemBeat {
 	pub fn new(selected_device: Option<String>) -> Self {
-		let (sample_tx, sample_rx) = mpsc::channel();
+		let (sample_tx, sample_rx, samples_ready) = Self::sample_channel();
 
 		let device_names = Self::enumerate_devices();
-		let stream = match selected_device.as_deref() {
+		let started = match selected_device.as_deref() {
 			Some(name) => Self::start_stream_named(name, &sample_tx),
 			None => Self::start_stream_default(&sample_tx),
 		};
+		let (stream, sample_rate) = match started {
+			Some((stream, rate)) => (Some(stream), rate),
+			None => (None, DEFAULT_SAMPLE_RATE),
+		};
 
 		Self {
 			sample_rx,
 			sample_tx,
+			samples_ready,
 			stream,
+			sample_rate,
 			device_names,
 			selected_device,
 			sample_buffer: Vec::with_capacity(WINDOW_SIZE * 2),
-			energy_history: vec![0.0; HISTORY_LEN],
-			history_index: 0,
-			last_beat: Instant::now(),
+			detector: BeatDetector::new(),
 		}
 	}
 
+	/// No devices and no capture stream, for running without audio hardware or with `--no-audio`
+	pub fn new_disabled() -> Self {
+		let (sample_tx, sample_rx, samples_ready) = Self::sample_channel();
+		Self {
+			sample_rx,
+			sample_tx,
+			samples_ready,
+			stream: None,
+			sample_rate: DEFAULT_SAMPLE_RATE,
+			device_names: Vec::new(),
+			selected_device: None,
+			sample_buffer: Vec::new(),
+			detector: BeatDetector::new(),
+		}
+	}
+
+	fn sample_channel() -> (SampleSender, mpsc::Receiver<Vec<f32>>, Arc<AtomicBool>) {
+		let (tx, rx) = mpsc::channel();
+		let ready = Arc::new(AtomicBool::new(false));
+		let sender = SampleSender {
+			tx,
+			ready: ready.clone(),
+		};
+		(sender, rx, ready)
+	}
+
+	/// Names of the input devices of the default host; devices without a readable
+	/// name are left out
+	pub fn input_device_names() -> Result<Vec<String>, cpal::DevicesError> {
+		let host = cpal::default_host();
+		Ok(host
+			.input_devices()?
+			.filter_map(|device| device.name().ok())
+			.collect())
+	}
+
 	/// Enumerate all available input devices
 	fn enumerate_devices() -> Vec<String> {
-		let host = cpal::default_host();
-		let mut names = Vec::new();
-		if let Ok(devices) = host.input_devices() {
-			for device in devices {
-				if let Ok(name) = device.name() {
-					names.push(name);
-				}
-			}
-		}
+		let names = Self::input_device_names().unwrap_or_else(|e| {
+			log::error!("Failed to enumerate devices: {}", e);
+			Vec::new()
+		});
 		log::info!("Enumerated {} audio input devices", names.len());
 		for name in &names {
 			log::debug!("  Audio device: {}", name);
@@ -74,8 +128,8 @@ impl SystemBeat {
 		names
 	}
 
-	/// Start capture on the default input device
-	fn start_stream_default(tx: &mpsc::Sender<Vec<f32>>) -> Option<cpal::Stream> {
+	/// Start capture on the default input device, returning the stream and its sample rate
+	fn start_stream_default(tx: &SampleSender) -> Option<(cpal::Stream, u32)> {
 		let host = cpal::default_host();
 		let device = match host.default_input_device() {
 			Some(d) => {
@@ -92,7 +146,7 @@ impl SystemBeat {
 	}
 
 	/// Start capture on a named device
-	fn start_stream_named(name: &str, tx: &mpsc::Sender<Vec<f32>>) -> Option<cpal::Stream> {
+	fn start_stream_named(name: &str, tx: &SampleSender) -> Option<(cpal::Stream, u32)> {
 		let host = cpal::default_host();
 		let devices = match host.input_devices() {
 			Ok(d) => d,
@@ -113,19 +167,104 @@ impl SystemBeat {
 		Self::start_stream_default(tx)
 	}
 
-	/// Start a cpal input stream on a specific device
-	fn start_stream_on_device(
-		device: &cpal::Device,
-		tx: &mpsc::Sender<Vec<f32>>,
-	) -> Option<cpal::Stream> {
-		let config = match device.default_input_config() {
+	/// The best config the device supports with a sample format we can convert,
+	/// for when its default is unusable
+	fn best_supported_config(device: &cpal::Device) -> Option<cpal::SupportedStreamConfig> {
+		let configs = match device.supported_input_configs() {
 			Ok(c) => c,
 			Err(e) => {
-				log::error!("Failed to get input config: {}", e);
+				log::error!("Failed to query supported input configs: {}", e);
 				return None;
 			}
 		};
 
+		// Prefer float samples, then anything we know how to convert
+		let mut best: Option<cpal::SupportedStreamConfigRange> = None;
+		for range in configs {
+			if !Self::is_supported_format(range.sample_format()) {
+				continue;
+			}
+			let is_better = match &best {
+				None => true,
+				Some(b) => {
+					range.sample_format() == cpal::SampleFormat::F32
+						&& b.sample_format() != cpal::SampleFormat::F32
+				}
+			};
+			if is_better {
+				best = Some(range);
+			}
+		}
+
+		match best {
+			Some(range) => Some(range.with_max_sample_rate()),
+			None => {
+				log::error!("No supported input config with a usable sample format");
+				None
+			}
+		}
+	}
+
+	fn is_supported_format(format: cpal::SampleFormat) -> bool {
+		matches!(
+			format,
+			cpal::SampleFormat::F32
+				| cpal::SampleFormat::I16
+				| cpal::SampleFormat::U16
+				| cpal::SampleFormat::U8
+		)
+	}
+
+	/// Start a cpal input stream on a specific device, on its default config if
+	/// that works and on the best supported one otherwise
+	fn start_stream_on_device(
+		device: &cpal::Device,
+		tx: &SampleSender,
+	) -> Option<(cpal::Stream, u32)> {
+		let default = match device.default_input_config() {
+			Ok(config) if Self::is_supported_format(config.sample_format()) => {
+				match Self::start_stream_with(device, config.clone(), tx) {
+					Ok(started) => return Some(started),
+					Err(e) => {
+						log::warn!("Default input config failed: {:#}", e);
+						Some(config)
+					}
+				}
+			}
+			Ok(config) => {
+				log::warn!(
+					"Default input config has unhandled {:?} samples",
+					config.sample_format()
+				);
+				None
+			}
+			Err(e) => {
+				log::warn!("Failed to get default input config: {}", e);
+				None
+			}
+		};
+
+		let config = Self::best_supported_config(device)?;
+		if default.as_ref() == Some(&config) {
+			log::error!("No other supported input config to fall back to");
+			return None;
+		}
+		log::info!("Falling back to a supported input config");
+		match Self::start_stream_with(device, config, tx) {
+			Ok(started) => Some(started),
+			Err(e) => {
+				log::error!("Audio input unavailable: {:#}", e);
+				None
+			}
+		}
+	}
+
+	/// Build and play a stream on `config`, returning it with its sample rate
+	fn start_stream_with(
+		device: &cpal::Device,
+		config: cpal::SupportedStreamConfig,
+		tx: &SampleSender,
+	) -> anyhow::Result<(cpal::Stream, u32)> {
 		log::info!(
 			"Audio config: {} channels, {}Hz, {:?}",
 			config.channels(),
@@ -133,50 +272,83 @@ impl SystemBeat {
 			config.sample_format()
 		);
 
-		let tx = tx.clone();
 		let channels = config.channels() as usize;
+		let sample_rate = config.sample_rate().0;
+		let sample_format = config.sample_format();
+		let stream_config: cpal::StreamConfig = config.into();
 
-		let stream = match device.build_input_stream(
-			&config.into(),
-			move |data: &[f32], _: &cpal::InputCallbackInfo| {
+		let stream = match sample_format {
+			cpal::SampleFormat::F32 => {
+				Self::build_stream::<f32>(device, &stream_config, channels, tx)
+			}
+			cpal::SampleFormat::I16 => {
+				Self::build_stream::<i16>(device, &stream_config, channels, tx)
+			}
+			cpal::SampleFormat::U16 => {
+				Self::build_stream::<u16>(device, &stream_config, channels, tx)
+			}
+			cpal::SampleFormat::U8 => {
+				Self::build_stream::<u8>(device, &stream_config, channels, tx)
+			}
+			other => anyhow::bail!("Unsupported audio sample format: {:?}", other),
+		}
+		.context("Failed to build audio stream")?;
+
+		stream.play().context("Failed to start audio stream")?;
+		Ok((stream, sample_rate))
+	}
+
+	/// Build an input stream for sample type `T`, converting to mono f32 in the callback
+	fn build_stream<T>(
+		device: &cpal::Device,
+		config: &cpal::StreamConfig,
+		channels: usize,
+		tx: &SampleSender,
+	) -> Result<cpal::Stream, cpal::BuildStreamError>
+	where
+		T: cpal::SizedSample,
+		f32: cpal::FromSample<T>,
+	{
+		let tx = tx.clone();
+		device.build_input_stream(
+			config,
+			move |data: &[T], _: &cpal::InputCallbackInfo| {
 				// Mix down to mono
 				let mono: Vec<f32> = if channels > 1 {
 					data.chunks(channels)
-						.map(|frame| frame.iter().sum::<f32>() / channels as f32)
+						.map(|frame| {
+							frame.iter().map(|s| f32::from_sample(*s)).sum::<f32>()
+								/ channels as f32
+						})
 						.collect()
 				} else {
-					data.to_vec()
+					data.iter().map(|s| f32::from_sample(*s)).collect()
 				};
-				let _ = tx.send(mono);
+				tx.send(mono);
 			},
 			move |err| {
 				log::error!("Audio stream error: {}", err);
 			},
 			None,
-		) {
-			Ok(s) => s,
-			Err(e) => {
-				log::error!("Failed to build audio stream: {}", e);
-				return None;
-			}
-		};
-
-		if let Err(e) = stream.play() {
-			log::error!("Failed to start audio stream: {}", e);
-			return None;
-		}
-
-		Some(stream)
+		)
 	}
 
 	/// Poll for new audio data and detect beats
 	pub fn poll(&mut self) -> ComponentResponse {
+		// Every complete window was analysed last time, so without new samples there's nothing to do
+		if !self.samples_ready.swap(false, Ordering::AcqRel) {
+			return ComponentResponse::none();
+		}
+
 		// Drain all available samples
 		while let Ok(samples) = self.sample_rx.try_recv() {
 			self.sample_buffer.extend(samples);
 		}
 
+		let window_secs = WINDOW_SIZE as f32 / self.sample_rate as f32;
 		let mut beat_detected = None;
+		let mut lock_lost = false;
+		let mut silence = false;
 
 		// Process complete windows
 		while self.sample_buffer.len() >= WINDOW_SIZE {
@@ -185,34 +357,27 @@ impl SystemBeat {
 			// Compute energy for this window
 			let energy: f32 = window.iter().map(|s| s * s).sum::<f32>() / WINDOW_SIZE as f32;
 
-			// Compute rolling average
-			let avg_energy: f32 =
-				self.energy_history.iter().sum::<f32>() / self.energy_history.len() as f32;
-
-			// Update history ring buffer
-			self.energy_history[self.history_index] = energy;
-			self.history_index = (self.history_index + 1) % HISTORY_LEN;
-
-			// Beat detection with cooldown
-			if energy > avg_energy * BEAT_THRESHOLD
-				&& avg_energy > 1e-8 // Avoid triggering on silence
-				&& self.last_beat.elapsed().as_millis() > BEAT_COOLDOWN_MS
-			{
-				let scale = (energy / (avg_energy * BEAT_THRESHOLD)).min(3.0);
-				beat_detected = Some(scale);
-				self.last_beat = Instant::now();
+			match self.detector.process(energy, window_secs) {
+				Some(Detection::Beat { scale }) => beat_detected = Some(scale),
+				Some(Detection::LockLost) => lock_lost = true,
+				Some(Detection::Silence) => silence = true,
+				None => {}
 			}
 		}
 
+		let mut events = Vec::new();
+		if lock_lost {
+			events.push(Event::Beat(BeatEvent::LockLost));
+		}
+		if silence {
+			events.push(Event::Beat(BeatEvent::SilenceDetected));
+		}
 		if let Some(scale) = beat_detected {
 			log::debug!("Beat detected! scale={:.2}", scale);
-			ComponentResponse::emit_many(vec![
-				Event::Beat(BeatEvent::Beat { scale }),
-				Event::View(ViewEvent::BeatPulse { scale }),
-			])
-		} else {
-			ComponentResponse::none()
+			events.push(Event::Beat(BeatEvent::Beat { scale }));
+			events.push(Event::View(ViewEvent::BeatPulse { scale }));
 		}
+		ComponentResponse::emit_many(events)
 	}
 
 	pub fn handle(&mut self, event: &Event) -> ComponentResponse {
@@ -225,20 +390,27 @@ impl SystemBeat {
 
 				// Reset detection state
 				self.sample_buffer.clear();
-				self.energy_history = vec![0.0; HISTORY_LEN];
-				self.history_index = 0;
+				self.detector.reset();
 
 				// Start new stream
-				self.stream = match name.as_deref() {
+				let started = match name.as_deref() {
 					Some(device_name) => Self::start_stream_named(device_name, &self.sample_tx),
 					None => Self::start_stream_default(&self.sample_tx),
 				};
+				if let Some((stream, rate)) = started {
+					self.stream = Some(stream);
+					self.sample_rate = rate;
+				}
 
 				// Re-enumerate in case device list changed
 				self.device_names = Self::enumerate_devices();
 
 				ComponentResponse::none()
 			}
+			Event::Beat(BeatEvent::RefreshDevices) => {
+				self.device_names = Self::enumerate_devices();
+				ComponentResponse::none()
+			}
 			_ => ComponentResponse::none(),
 		}
 	}
@@ -256,9 +428,31 @@ impl SystemBeat {
 		self.selected_device.as_deref().unwrap_or("Default")
 	}
 
+	/// Samples arrived that the next poll would analyse
+	pub fn has_pending(&self) -> bool {
+		self.samples_ready.load(Ordering::Acquire)
+	}
+
 	pub fn is_active(&self) -> bool {
 		self.stream.is_some()
 	}
+
+	/// Stop capturing; the stream must not outlive the audio host on exit
+	pub fn shutdown(&mut self) {
+		if self.stream.take().is_some() {
+			log::info!("Audio capture stopped");
+		}
+	}
+
+	/// Nothing audible has come in for a while
+	pub fn is_silent(&self) -> bool {
+		self.detector.is_silent()
+	}
+
+	/// Whether beats are currently being let through, and at what tempo
+	pub fn lock_state(&self) -> LockState {
+		self.detector.lock_state()
+	}
 }
 
 impl Default for SystemBeat {
@@ -1,84 +1,263 @@
+use crate::config::SavedSettings;
 use crate::reactor::{BreathingEvent, ComponentResponse, Event};
-use crate::types::{BreathingPhase, BreathingStyle};
+use crate::types::{BreathingPalette, BreathingPhase, BreathingStyle, OverlayAnchor};
 use rand::Rng;
+use std::ops::RangeInclusive;
 use std::time::{Duration, Instant};
 
+/// Rest after a cycle was ended early with the panic key
+const FORCED_IDLE: Duration = Duration::from_secs(90);
+
+/// Shortest inhale + hold cap that can be set
+const MIN_INHALE_HOLD_SECS: f32 = 4.0;
+
+/// Idle multipliers the settings and keyboard can pick from
+pub const IDLE_MULTIPLIER_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+
+/// Largest zoom the image can breathe with
+pub const MAX_IMAGE_ZOOM: f32 = 0.04;
+
+#[derive(Clone)]
 pub struct BreathingState {
 	pub phase: BreathingPhase,
 	pub start_time: Instant,
 	pub duration: Duration,
 }
 
+#[derive(Clone)]
 pub struct BreathingOverlay {
 	state: BreathingState,
 	show_overlay: bool,
 	idle_multiplier: f32,
 	style: BreathingStyle,
+	palette: BreathingPalette,
+	anchor: OverlayAnchor,
+	overlay_scale: f32,
+	/// How long the classic phase pulse stays on screen
+	pulse_duration_secs: f32,
+	/// Classic pulse text height as a fraction of the screen height
+	pulse_scale: f32,
+	/// Immersive style: the image grows by this fraction over an inhale
+	image_zoom: f32,
+	/// How far in the image was zoomed when the current phase started, from 0 to 1
+	depth_at_start: f32,
+	/// Bumped whenever a phase starts; completions of cut-short phases are dropped
+	phase_generation: u64,
+	/// Inhale and Hold together never run longer than this
+	max_inhale_hold: Duration,
+	/// The cycle was ended early; the Release underway leads to a long Idle
+	forced_release: bool,
+	/// Inhales started since the overlay was created
+	cycles: u32,
 }
 
 impl BreathingOverlay {
-	pub fn new(show_overlay: bool, idle_multiplier: f32, style: BreathingStyle) -> Self {
+	/// Restore preferences from saved settings; the overlay itself always starts hidden
+	pub fn new(settings: &SavedSettings) -> Self {
 		Self {
 			state: BreathingState {
 				phase: BreathingPhase::Prepare,
 				start_time: Instant::now(),
 				duration: Duration::from_secs(5),
 			},
-			show_overlay,
-			idle_multiplier,
-			style,
+			show_overlay: false,
+			idle_multiplier: settings.breathing_idle_multiplier,
+			style: settings.breathing_style,
+			palette: settings.breathing_palette,
+			anchor: settings.breathing_overlay_anchor,
+			overlay_scale: settings.breathing_overlay_scale,
+			pulse_duration_secs: settings.breathing_pulse_duration_secs,
+			pulse_scale: settings.breathing_pulse_scale,
+			image_zoom: settings.breathing_image_zoom.clamp(0.0, MAX_IMAGE_ZOOM),
+			depth_at_start: 0.0,
+			phase_generation: 0,
+			max_inhale_hold: Duration::from_secs_f32(
+				settings
+					.breathing_max_inhale_hold_secs
+					.max(MIN_INHALE_HOLD_SECS),
+			),
+			forced_release: false,
+			cycles: 0,
 		}
 	}
 
 	pub fn init(&self) -> ComponentResponse {
-		let mut response = ComponentResponse::emit(Event::Breathing(BreathingEvent::PhaseStarted(
-			self.state.phase,
-		)));
+		let mut response =
+			ComponentResponse::emit(Event::Breathing(BreathingEvent::PhaseStarted {
+				phase: self.state.phase,
+				duration: self.state.duration,
+			}));
 		response.scheduled.push((
-			Event::Breathing(BreathingEvent::PhaseComplete),
+			Event::Breathing(BreathingEvent::PhaseComplete {
+				generation: self.phase_generation,
+			}),
 			self.state.duration,
 		));
 		response
 	}
 
+	/// Enter `phase` now and schedule its completion
+	fn start_phase(&mut self, phase: BreathingPhase, duration: Duration) -> ComponentResponse {
+		let duration = self.capped(phase, duration);
+		// A phase cut short hands over the zoom where it got to
+		self.depth_at_start = self.depth_at(Instant::now());
+		self.state = BreathingState {
+			phase,
+			start_time: Instant::now(),
+			duration,
+		};
+		self.phase_generation = self.phase_generation.wrapping_add(1);
+		if phase == BreathingPhase::Inhale {
+			self.cycles = self.cycles.wrapping_add(1);
+		}
+
+		let mut response =
+			ComponentResponse::emit(Event::Breathing(BreathingEvent::PhaseStarted {
+				phase,
+				duration,
+			}));
+		response.scheduled.push((
+			Event::Breathing(BreathingEvent::PhaseComplete {
+				generation: self.phase_generation,
+			}),
+			duration,
+		));
+		response
+	}
+
+	/// Shorten an Inhale or Hold that would take Inhale + Hold past the cap.
+	/// Hold lasts as long as Inhale, so Inhale gets half of it.
+	fn capped(&self, phase: BreathingPhase, duration: Duration) -> Duration {
+		let limit = match phase {
+			BreathingPhase::Inhale => self.max_inhale_hold / 2,
+			BreathingPhase::Hold if self.state.phase == BreathingPhase::Inhale => self
+				.max_inhale_hold
+				.saturating_sub(self.phase_elapsed().min(self.state.duration)),
+			BreathingPhase::Hold => self.max_inhale_hold / 2,
+			_ => return duration,
+		};
+		if duration > limit {
+			log::warn!(
+				"{:?} of {:.1}s would pass the {:.0}s inhale + hold cap, cut to {:.1}s",
+				phase,
+				duration.as_secs_f32(),
+				self.max_inhale_hold.as_secs_f32(),
+				limit.as_secs_f32()
+			);
+			return limit;
+		}
+		duration
+	}
+
 	pub fn handle(&mut self, event: &Event) -> ComponentResponse {
 		match event {
 			Event::Breathing(BreathingEvent::Toggle) => {
 				self.show_overlay = !self.show_overlay;
 				ComponentResponse::none()
 			}
-			Event::Breathing(BreathingEvent::PhaseComplete) => {
+			Event::Breathing(BreathingEvent::PhaseComplete { generation }) => {
+				if *generation != self.phase_generation {
+					return ComponentResponse::none();
+				}
 				// Transition to next phase
 				let (next_phase, duration) = self.transition_phase();
-				self.state = BreathingState {
-					phase: next_phase,
-					start_time: Instant::now(),
-					duration,
-				};
-
-				let mut response = ComponentResponse::emit(Event::Breathing(
-					BreathingEvent::PhaseStarted(next_phase),
-				));
-				response
-					.scheduled
-					.push((Event::Breathing(BreathingEvent::PhaseComplete), duration));
-				response
+				self.start_phase(next_phase, duration)
+			}
+			Event::Breathing(BreathingEvent::TriggerCycle) => {
+				if !matches!(
+					self.state.phase,
+					BreathingPhase::Idle | BreathingPhase::Release
+				) {
+					log::debug!("TriggerCycle ignored mid-cycle ({:?})", self.state.phase);
+					return ComponentResponse::none();
+				}
+				self.forced_release = false;
+				self.start_phase(BreathingPhase::Prepare, Duration::from_secs(3))
+			}
+			Event::Breathing(BreathingEvent::ForceRelease) => match self.state.phase {
+				BreathingPhase::Idle => {
+					log::debug!("ForceRelease ignored while idle");
+					ComponentResponse::none()
+				}
+				BreathingPhase::Release => {
+					log::info!("Cycle ended early, resting after this release");
+					self.forced_release = true;
+					ComponentResponse::none()
+				}
+				phase => {
+					log::info!("Cycle ended early from {:?}", phase);
+					self.forced_release = true;
+					self.start_phase(BreathingPhase::Release, Duration::from_secs(4))
+				}
+			},
+			Event::Breathing(BreathingEvent::SkipPhase) => {
+				let (next_phase, duration) = self.transition_phase();
+				log::debug!("Skipping {:?} -> {:?}", self.state.phase, next_phase);
+				self.start_phase(next_phase, duration)
+			}
+			Event::Breathing(BreathingEvent::SetMaxInhaleHold { secs }) => {
+				self.max_inhale_hold = Duration::from_secs_f32(secs.max(MIN_INHALE_HOLD_SECS));
+				ComponentResponse::none()
 			}
 			Event::Breathing(BreathingEvent::SetIdleMultiplier { value }) => {
 				self.idle_multiplier = *value;
 				ComponentResponse::none()
 			}
+			Event::Breathing(BreathingEvent::AdjustIdleMultiplier { delta }) => {
+				// Round so repeated steps land on tenths
+				let value = ((self.idle_multiplier + delta) * 10.0).round() / 10.0;
+				self.idle_multiplier =
+					value.clamp(*IDLE_MULTIPLIER_RANGE.start(), *IDLE_MULTIPLIER_RANGE.end());
+				ComponentResponse::none()
+			}
 			Event::Breathing(BreathingEvent::SetStyle { style }) => {
 				self.style = *style;
 				ComponentResponse::none()
 			}
+			Event::Breathing(BreathingEvent::SetPalette {
+				prepare,
+				inhale,
+				hold,
+				release,
+			}) => {
+				self.palette = BreathingPalette {
+					prepare: *prepare,
+					inhale: *inhale,
+					hold: *hold,
+					release: *release,
+				};
+				ComponentResponse::none()
+			}
+			Event::Breathing(BreathingEvent::SetOverlayAnchor { corner }) => {
+				self.anchor = *corner;
+				ComponentResponse::none()
+			}
+			Event::Breathing(BreathingEvent::SetOverlayScale { scale }) => {
+				self.overlay_scale = scale.clamp(0.25, 4.0);
+				ComponentResponse::none()
+			}
+			Event::Breathing(BreathingEvent::SetPulseParams {
+				duration_secs,
+				scale,
+			}) => {
+				self.pulse_duration_secs = duration_secs.max(0.1);
+				self.pulse_scale = scale.max(0.01);
+				ComponentResponse::none()
+			}
+			Event::Breathing(BreathingEvent::SetImageZoom { strength }) => {
+				self.image_zoom = strength.clamp(0.0, MAX_IMAGE_ZOOM);
+				ComponentResponse::none()
+			}
 			_ => ComponentResponse::none(),
 		}
 	}
 
-	fn transition_phase(&self) -> (BreathingPhase, Duration) {
+	fn transition_phase(&mut self) -> (BreathingPhase, Duration) {
 		let mut rng = rand::rng();
 
+		if std::mem::take(&mut self.forced_release) {
+			return (BreathingPhase::Idle, FORCED_IDLE);
+		}
 		match self.state.phase {
 			BreathingPhase::Prepare => {
 				// -> Inhale (5-10s)
@@ -119,6 +298,78 @@ impl BreathingOverlay {
 		&self.state
 	}
 
+	/// Time since the current phase started; may run past its duration
+	/// until the completion event is handled
+	pub fn phase_elapsed(&self) -> Duration {
+		self.state.start_time.elapsed()
+	}
+
+	/// How far through the current phase, from 0 to 1. A zero-length phase is done.
+	pub fn phase_progress(&self) -> f32 {
+		self.phase_progress_at(Instant::now())
+	}
+
+	fn phase_progress_at(&self, now: Instant) -> f32 {
+		if self.state.duration.is_zero() {
+			return 1.0;
+		}
+		let elapsed = now.saturating_duration_since(self.state.start_time);
+		(elapsed.as_secs_f32() / self.state.duration.as_secs_f32()).clamp(0.0, 1.0)
+	}
+
+	/// Scale of the image with the breath: it grows by `image_zoom` over the
+	/// inhale, stays through the hold and goes back over the release. 1 in the
+	/// classic style and while the overlay is hidden.
+	pub fn image_scale(&self) -> f32 {
+		self.image_scale_at(Instant::now())
+	}
+
+	fn image_scale_at(&self, now: Instant) -> f32 {
+		if !self.show_overlay || self.style != BreathingStyle::Immersive {
+			return 1.0;
+		}
+		1.0 + self.image_zoom * self.depth_at(now)
+	}
+
+	/// How far in the image is zoomed, from 0 to 1, eased from where the phase started
+	fn depth_at(&self, now: Instant) -> f32 {
+		let target = match self.state.phase {
+			BreathingPhase::Inhale => 1.0,
+			BreathingPhase::Hold => self.depth_at_start,
+			BreathingPhase::Release | BreathingPhase::Idle | BreathingPhase::Prepare => 0.0,
+		};
+		let t = self.phase_progress_at(now);
+		let eased = t * t * (3.0 - 2.0 * t);
+		self.depth_at_start + (target - self.depth_at_start) * eased
+	}
+
+	/// Time left in the current phase, zero once it's over
+	pub fn phase_remaining(&self) -> Duration {
+		self.phase_remaining_at(Instant::now())
+	}
+
+	fn phase_remaining_at(&self, now: Instant) -> Duration {
+		let elapsed = now.saturating_duration_since(self.state.start_time);
+		self.state.duration.saturating_sub(elapsed)
+	}
+
+	/// Inhales started so far, counting the one underway
+	pub fn cycle_count(&self) -> u32 {
+		self.cycles
+	}
+
+	/// Whether an Inhale, Hold or Release is underway, rather than a countdown or rest
+	pub fn is_in_active_cycle(&self) -> bool {
+		matches!(
+			self.state.phase,
+			BreathingPhase::Inhale | BreathingPhase::Hold | BreathingPhase::Release
+		)
+	}
+
+	pub fn max_inhale_hold(&self) -> Duration {
+		self.max_inhale_hold
+	}
+
 	pub fn idle_multiplier(&self) -> f32 {
 		self.idle_multiplier
 	}
@@ -126,10 +377,166 @@ impl BreathingOverlay {
 	pub fn style(&self) -> BreathingStyle {
 		self.style
 	}
+
+	pub fn palette(&self) -> BreathingPalette {
+		self.palette
+	}
+
+	pub fn anchor(&self) -> OverlayAnchor {
+		self.anchor
+	}
+
+	pub fn overlay_scale(&self) -> f32 {
+		self.overlay_scale
+	}
+
+	pub fn pulse_duration_secs(&self) -> f32 {
+		self.pulse_duration_secs
+	}
+
+	pub fn pulse_scale(&self) -> f32 {
+		self.pulse_scale
+	}
+
+	pub fn image_zoom(&self) -> f32 {
+		self.image_zoom
+	}
 }
 
 impl Default for BreathingOverlay {
 	fn default() -> Self {
-		Self::new(false, 1.0, BreathingStyle::default())
+		Self::new(&SavedSettings::default())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn in_phase(
+		phase: BreathingPhase,
+		start_time: Instant,
+		duration: Duration,
+	) -> BreathingOverlay {
+		BreathingOverlay {
+			state: BreathingState {
+				phase,
+				start_time,
+				duration,
+			},
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn progress_and_remaining_follow_the_phase_clock() {
+		let start = Instant::now();
+		let breathing = in_phase(BreathingPhase::Inhale, start, Duration::from_secs(8));
+		assert_eq!(breathing.phase_progress_at(start), 0.0);
+		assert_eq!(
+			breathing.phase_progress_at(start + Duration::from_secs(2)),
+			0.25
+		);
+		assert_eq!(
+			breathing.phase_remaining_at(start + Duration::from_secs(2)),
+			Duration::from_secs(6)
+		);
+		// Past the end, waiting on the completion event
+		assert_eq!(
+			breathing.phase_progress_at(start + Duration::from_secs(9)),
+			1.0
+		);
+		assert_eq!(
+			breathing.phase_remaining_at(start + Duration::from_secs(9)),
+			Duration::ZERO
+		);
+
+		let breathing = in_phase(BreathingPhase::Hold, start, Duration::ZERO);
+		assert_eq!(breathing.phase_progress_at(start), 1.0);
+		assert_eq!(breathing.phase_remaining_at(start), Duration::ZERO);
+	}
+
+	#[test]
+	fn cycles_count_inhales() {
+		let mut breathing = BreathingOverlay::default();
+		assert_eq!(breathing.cycle_count(), 0);
+		assert!(!breathing.is_in_active_cycle());
+
+		for phase in [
+			BreathingPhase::Inhale,
+			BreathingPhase::Hold,
+			BreathingPhase::Release,
+		] {
+			breathing.start_phase(phase, Duration::from_secs(4));
+			assert!(breathing.is_in_active_cycle());
+		}
+		breathing.start_phase(BreathingPhase::Idle, Duration::from_secs(20));
+		assert!(!breathing.is_in_active_cycle());
+		breathing.start_phase(BreathingPhase::Prepare, Duration::from_secs(5));
+		assert!(!breathing.is_in_active_cycle());
+		breathing.start_phase(BreathingPhase::Inhale, Duration::from_secs(4));
+		assert_eq!(breathing.cycle_count(), 2);
+	}
+
+	#[test]
+	fn idle_multiplier_steps_by_tenths_within_range() {
+		let mut breathing = BreathingOverlay::default();
+		let step = |breathing: &mut BreathingOverlay, delta: f32| {
+			breathing.handle(&Event::Breathing(BreathingEvent::AdjustIdleMultiplier {
+				delta,
+			}));
+			breathing.idle_multiplier()
+		};
+		breathing.handle(&Event::Breathing(BreathingEvent::SetIdleMultiplier {
+			value: 0.67,
+		}));
+		assert_eq!(step(&mut breathing, 0.1), 0.8);
+		for _ in 0..5 {
+			step(&mut breathing, -0.1);
+		}
+		assert_eq!(breathing.idle_multiplier(), 0.5);
+		for _ in 0..30 {
+			step(&mut breathing, 0.1);
+		}
+		assert_eq!(breathing.idle_multiplier(), 3.0);
+	}
+
+	#[test]
+	fn image_zooms_in_with_the_inhale_and_out_with_the_release() {
+		// Long enough ago for each phase to be over by now
+		let start = Instant::now() - Duration::from_secs(10);
+		let at = |secs: f32| start + Duration::from_secs_f32(secs);
+		let near = |scale: f32, expected: f32| (scale - expected).abs() < 1e-6;
+		let mut breathing = in_phase(BreathingPhase::Inhale, start, Duration::from_secs(4));
+		breathing.show_overlay = true;
+		breathing.handle(&Event::Breathing(BreathingEvent::SetImageZoom {
+			strength: 0.03,
+		}));
+		assert_eq!(breathing.image_scale_at(at(0.0)), 1.0);
+		assert!(near(breathing.image_scale_at(at(2.0)), 1.015));
+		assert!(near(breathing.image_scale_at(at(4.0)), 1.03));
+
+		// Held where the inhale got to, then released from there
+		breathing.start_phase(BreathingPhase::Hold, Duration::from_secs(4));
+		assert!(near(breathing.image_scale_at(Instant::now()), 1.03));
+		breathing.state.start_time = start;
+		assert!(near(breathing.image_scale_at(at(3.0)), 1.03));
+		breathing.start_phase(BreathingPhase::Release, Duration::from_secs(4));
+		breathing.state.start_time = start;
+		assert_eq!(breathing.image_scale_at(at(4.0)), 1.0);
+
+		// The classic style, a hidden overlay and no strength leave the image alone
+		breathing.state.phase = BreathingPhase::Inhale;
+		assert!(breathing.image_scale_at(at(4.0)) > 1.0);
+		breathing.style = BreathingStyle::Classic;
+		assert_eq!(breathing.image_scale_at(at(4.0)), 1.0);
+		breathing.style = BreathingStyle::Immersive;
+		breathing.show_overlay = false;
+		assert_eq!(breathing.image_scale_at(at(4.0)), 1.0);
+		breathing.show_overlay = true;
+		breathing.handle(&Event::Breathing(BreathingEvent::SetImageZoom {
+			strength: 0.0,
+		}));
+		assert_eq!(breathing.image_scale_at(at(4.0)), 1.0);
 	}
 }
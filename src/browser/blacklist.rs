@@ -0,0 +1,138 @@
+use crate::api::Post;
+
+/// One condition of a blacklist line
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+	Tag(String),
+	/// First letter of the rating: `s`, `q` or `e`
+	Rating(char),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+	condition: Condition,
+	/// Written with a leading `-`: the post must not match
+	negated: bool,
+}
+
+/// One line of an e621 account blacklist. A post is hidden when it matches
+/// every term on the line, e.g. `mud rating:e -solo`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlacklistRule {
+	terms: Vec<Term>,
+}
+
+impl BlacklistRule {
+	pub fn matches(&self, post: &Post) -> bool {
+		self.terms.iter().all(|term| {
+			let matched = match &term.condition {
+				Condition::Tag(tag) => post.tags.iter().any(|t| t == tag.as_str()),
+				Condition::Rating(rating) => post.rating.starts_with(*rating),
+			};
+			matched != term.negated
+		})
+	}
+}
+
+/// Parse the line-based blacklist stored on an e621 account.
+/// Blank lines and `#` comments are skipped, and so are lines using
+/// metatags other than `rating:`, rather than hiding more than the site would.
+pub fn parse(text: &str) -> Vec<BlacklistRule> {
+	text.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.filter_map(|line| {
+			let terms: Option<Vec<Term>> = line.split_whitespace().map(parse_term).collect();
+			match terms {
+				Some(terms) if !terms.is_empty() => Some(BlacklistRule { terms }),
+				_ => {
+					log::debug!("Skipping unsupported blacklist line '{}'", line);
+					None
+				}
+			}
+		})
+		.collect()
+}
+
+fn parse_term(token: &str) -> Option<Term> {
+	let token = token.to_lowercase();
+	let (negated, body) = match token.strip_prefix('-') {
+		Some(rest) => (true, rest),
+		None => (false, token.as_str()),
+	};
+	if body.is_empty() {
+		return None;
+	}
+	let condition = match body.split_once(':') {
+		Some(("rating", value)) => match value.chars().next()? {
+			rating @ ('s' | 'q' | 'e') => Condition::Rating(rating),
+			_ => return None,
+		},
+		// Other metatags like `score:<0` aren't evaluated locally
+		Some((name, _)) if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphabetic()) => {
+			return None;
+		}
+		_ => Condition::Tag(body.to_string()),
+	};
+	Some(Term { condition, negated })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn post(rating: &str, general: &[&str]) -> Post {
+		let mut post = Post {
+			rating: rating.to_string(),
+			..Default::default()
+		};
+		post.tags.general = general.iter().map(|t| t.to_string()).collect();
+		post
+	}
+
+	#[test]
+	fn parses_an_account_blacklist_with_comments_and_whitespace() {
+		let text = "# things I never want to see\r\n\
+			gore\r\n\
+			\r\n\
+			  scat   rating:e  \n\
+			\t# indented comment\n\
+			young -rating:s\n\
+			score:<0\n\
+			Mud\n";
+		let rules = parse(text);
+		assert_eq!(rules.len(), 4, "{:#?}", rules);
+
+		assert!(
+			rules
+				.iter()
+				.any(|r| r.matches(&post("s", &["gore", "solo"])))
+		);
+		// Lines are ANDed: scat alone isn't enough
+		assert!(!rules.iter().any(|r| r.matches(&post("s", &["scat"]))));
+		assert!(rules.iter().any(|r| r.matches(&post("e", &["scat"]))));
+		// Negated rating keeps safe posts
+		assert!(!rules.iter().any(|r| r.matches(&post("s", &["young"]))));
+		assert!(rules.iter().any(|r| r.matches(&post("q", &["young"]))));
+		// Tags are matched case-insensitively
+		assert!(rules.iter().any(|r| r.matches(&post("s", &["mud"]))));
+		// The score line was dropped instead of hiding everything
+		assert!(!rules.iter().any(|r| r.matches(&post("s", &["solo"]))));
+	}
+
+	#[test]
+	fn negated_tags_and_full_rating_names() {
+		let rules = parse("feral -solo rating:explicit\n- \n");
+		assert_eq!(rules.len(), 1);
+		assert!(rules[0].matches(&post("e", &["feral", "duo"])));
+		assert!(!rules[0].matches(&post("e", &["feral", "solo"])));
+		assert!(!rules[0].matches(&post("q", &["feral", "duo"])));
+	}
+
+	#[test]
+	fn tags_with_colons_are_not_metatags() {
+		let rules = parse(":3\n");
+		assert_eq!(rules.len(), 1);
+		assert!(rules[0].matches(&post("s", &[":3"])));
+	}
+}
@@ -1,65 +1,340 @@
 use crate::api::Post;
-use crate::reactor::{BrowserEvent, ComponentResponse, Event, GatewayEvent, MediaEvent};
-use crate::types::NavDirection;
+use crate::reactor::{
+	BrowserEvent, ComponentResponse, Event, GatewayEvent, MediaEvent, PageSource, SettingsEvent,
+	ViewEvent,
+};
+use crate::types::{NavDirection, SEARCH_STACK_DEPTH, SortKey};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+mod blacklist;
+
+use blacklist::BlacklistRule;
+
+/// Posts left after the current one when the next page is requested, unless set otherwise
+pub const DEFAULT_FETCH_AHEAD: usize = 10;
+
+/// Posts after the current one named in each prefetch hint
+pub const PREFETCH_AHEAD: usize = 30;
+
+/// Posts of an appended page prefetched as soon as it lands, even past `PREFETCH_AHEAD`
+const PREFETCH_NEW_POSTS: usize = 10;
+
+/// Results of one sub-query waiting to be interleaved into the feed
+#[derive(Default, Clone)]
+struct SourceQueue {
+	query: String,
+	pending: VecDeque<Post>,
+	exhausted: bool,
+	/// Position of each post in the order the sub-query delivered them
+	delivered: HashMap<u64, usize>,
+}
+
+/// Where a post hidden by the account blacklist goes back to
+#[derive(Clone, Copy)]
+enum HiddenSlot {
+	/// The feed, at the post's arrival index
+	Feed,
+	/// The queue of this sub-query, at the post's delivery position
+	Source(usize),
+}
+
+/// Posts dropped by one blacklist addition, with their original positions
+struct RemovedByTag {
+	tag: String,
+	posts: Vec<(usize, Post)>,
+	previous_index: usize,
+}
+
+/// Result list saved by "More like this" so going back needs no refetch
+struct BrowserSnapshot {
+	posts: Vec<Post>,
+	current_index: usize,
+	current_page: u32,
+	end_reached: bool,
+	sources: Vec<SourceQueue>,
+	post_sources: HashMap<u64, usize>,
+	arrival: HashMap<u64, usize>,
+	post_pages: HashMap<u64, u32>,
+	account_hidden: Vec<(HiddenSlot, Post)>,
+	blacklist_hidden: usize,
+}
 
 pub struct ContentBrowser {
 	posts: Vec<Post>,
 	current_index: usize,
 	current_page: u32,
+	/// The last page fetched came back empty, so the list won't grow
+	end_reached: bool,
+	/// Next at the last post goes back to the first, and Prev at the first to the last
+	wrap_navigation: bool,
+	/// The next page is requested once this few posts are left after the current one
+	fetch_ahead: usize,
+	/// Local order of `posts`; later pages are inserted where they belong
+	sort_key: SortKey,
+	sort_descending: bool,
+	/// Position of each post in the order the API delivered them, for going back to it
+	arrival: HashMap<u64, usize>,
+	/// API page each post was delivered on, kept through sorting and hiding
+	post_pages: HashMap<u64, u32>,
+	/// Our vote per post id (-1, 0, 1), as far as we know
+	votes: HashMap<u64, i8>,
+	/// Per sub-query queues; empty unless the search is compound
+	sources: Vec<SourceQueue>,
+	/// Sub-query index each interleaved post came from
+	post_sources: HashMap<u64, usize>,
+	/// Keep posts awaiting approval; deleted posts are always dropped
+	show_pending: bool,
+	/// Posts carrying any of these tags are dropped
+	blacklist: Vec<String>,
+	/// Posts removed by the last blacklist addition, kept for undo
+	last_removed: Option<RemovedByTag>,
+	/// Rules of the blacklist stored on the e621 account, if any were loaded
+	account_rules: Vec<BlacklistRule>,
+	/// Off while the user temporarily looks past the account blacklist
+	account_blacklist_enabled: bool,
+	/// Posts of this search hidden by the account blacklist, restored when it's turned off
+	account_hidden: Vec<(HiddenSlot, Post)>,
+	/// Posts of this search dropped by the local blacklist
+	blacklist_hidden: usize,
+	/// Posts hidden one by one; dropped from every search until the app closes
+	session_hidden: HashSet<u64>,
+	/// The last post hidden and its position, kept for undo
+	last_hidden: Option<(usize, Post)>,
+	/// Most recent last, at most `SEARCH_STACK_DEPTH` entries
+	search_stack: Vec<BrowserSnapshot>,
+	/// Drop every post not rated safe, in case one slips past the rewritten query
+	safe_only: bool,
 }
 
 impl ContentBrowser {
-	pub fn new() -> Self {
+	pub fn new(show_pending: bool, blacklist: Vec<String>) -> Self {
 		log::info!("Initializing");
 		Self {
 			posts: Vec::new(),
 			current_index: 0,
 			current_page: 1,
+			end_reached: false,
+			wrap_navigation: false,
+			fetch_ahead: DEFAULT_FETCH_AHEAD,
+			sort_key: SortKey::ApiOrder,
+			sort_descending: true,
+			arrival: HashMap::new(),
+			post_pages: HashMap::new(),
+			votes: HashMap::new(),
+			sources: Vec::new(),
+			post_sources: HashMap::new(),
+			show_pending,
+			blacklist,
+			last_removed: None,
+			account_rules: Vec::new(),
+			account_blacklist_enabled: true,
+			account_hidden: Vec::new(),
+			blacklist_hidden: 0,
+			session_hidden: HashSet::new(),
+			last_hidden: None,
+			search_stack: Vec::new(),
+			safe_only: false,
 		}
 	}
 
+	pub fn with_wrap_navigation(mut self, enabled: bool) -> Self {
+		self.wrap_navigation = enabled;
+		self
+	}
+
+	pub fn with_safe_mode(mut self, enabled: bool) -> Self {
+		self.safe_only = enabled;
+		self
+	}
+
+	pub fn with_fetch_ahead(mut self, posts: usize) -> Self {
+		self.fetch_ahead = posts.clamp(1, PREFETCH_AHEAD);
+		self
+	}
+
 	pub fn handle(&mut self, event: &Event) -> ComponentResponse {
 		match event {
 			Event::Browser(BrowserEvent::PostsReceived {
 				posts,
 				page,
 				is_new,
+				source,
 			}) => {
-				let filtered_posts: Vec<Post> = posts
+				if *is_new {
+					// Hidden posts are counted per search
+					self.account_hidden.clear();
+					self.blacklist_hidden = 0;
+				}
+				let playable: Vec<Post> = posts
 					.iter()
 					.filter(|p| {
 						let ext = p.file.ext.to_lowercase();
 						ext != "mp4" && ext != "webm"
 					})
+					// Files of deleted and pending posts usually 404
+					.filter(|p| !p.flags.deleted && (self.show_pending || !p.flags.pending))
+					.filter(|p| !self.safe_only || p.rating == "s")
 					.cloned()
 					.collect();
+				let slot = source
+					.as_ref()
+					.map_or(HiddenSlot::Feed, |source| HiddenSlot::Source(source.index));
+				let filtered_posts = self.hide_blacklisted(playable, slot);
+				let filtered_out = posts.len() - filtered_posts.len();
+				if filtered_out > 0 {
+					log::debug!("Filtered {} posts from page {}", filtered_out, page);
+				}
 
-				if *is_new {
+				if let Some(source) = source {
+					return self.receive_compound_page(
+						source,
+						posts,
+						filtered_posts,
+						source.exhausted || posts.is_empty(),
+						*page,
+						*is_new,
+					);
+				}
+
+				// Posts of a later page to load ahead of time
+				let appended: Vec<u64> = if *is_new {
+					self.sources.clear();
+					self.post_sources.clear();
 					log::info!(
 						"New search results: page={}, posts={}",
 						page,
 						filtered_posts.len(),
 					);
-					self.posts = filtered_posts;
+					self.posts.clear();
+					self.arrival.clear();
+					self.post_pages.clear();
+					self.record_page(posts, *page);
+					self.record_arrival(posts);
+					for post in filtered_posts {
+						self.add_post(post);
+					}
 					self.current_index = 0;
 					self.current_page = *page;
+					self.end_reached = posts.is_empty();
+					Vec::new()
 				} else {
 					log::info!(
 						"Appended results: page={}, new_posts={}",
 						page,
 						filtered_posts.len(),
 					);
-					self.posts.extend(filtered_posts);
+					let appended = filtered_posts
+						.iter()
+						.take(PREFETCH_NEW_POSTS)
+						.map(|p| p.id)
+						.collect();
+					self.record_page(posts, *page);
+					self.record_arrival(posts);
+					for post in filtered_posts {
+						self.add_post(post);
+					}
 					self.current_page = *page;
-				}
+					// Fan-out pages can land after the end was reported
+					self.end_reached |= posts.is_empty();
+					appended
+				};
 
+				// Nothing usable on this page, but there may be more after it
+				let fetch_more = !posts.is_empty() && filtered_out == posts.len();
 				if !self.posts.is_empty() {
-					self.emit_current_post_changed()
+					let mut response = self.emit_post_changed_with(&appended);
+					if fetch_more {
+						response
+							.events
+							.push(Event::Gateway(GatewayEvent::FetchNextPage));
+					}
+					response
+				} else if fetch_more {
+					log::info!("Page {} fully filtered, requesting the next one", page);
+					ComponentResponse::emit(Event::Gateway(GatewayEvent::FetchNextPage))
 				} else {
 					log::warn!("Received empty posts");
 					ComponentResponse::none()
 				}
 			}
+			Event::Browser(BrowserEvent::EndOfResults) => {
+				self.end_reached = true;
+				ComponentResponse::none()
+			}
+			Event::Gateway(GatewayEvent::ClearEndOfResults) => {
+				self.end_reached = false;
+				for source in &mut self.sources {
+					source.exhausted = false;
+				}
+				ComponentResponse::none()
+			}
+			Event::Browser(BrowserEvent::SortLoaded { key, descending }) => {
+				log::info!(
+					"Sorting {} loaded posts by {:?} (descending={})",
+					self.posts.len(),
+					key,
+					descending
+				);
+				self.sort_key = *key;
+				self.sort_descending = *descending;
+				self.apply_sort();
+				// The current post stays, but what comes after it changed
+				ComponentResponse::emit_many(self.prefetch_hint().into_iter().collect())
+			}
+			Event::Browser(BrowserEvent::BlacklistTag { tag }) => self.blacklist_tag(tag),
+			Event::Browser(BrowserEvent::UnblacklistTag { tag }) => self.unblacklist_tag(tag),
+			Event::Browser(BrowserEvent::SetBlacklist { tags }) => self.set_blacklist(tags),
+			Event::Browser(BrowserEvent::HidePost { id }) => self.hide_post(*id),
+			Event::Browser(BrowserEvent::UnhidePost { id }) => self.unhide_post(*id),
+			Event::Browser(BrowserEvent::AccountBlacklistReceived { text }) => {
+				let current_id = self.current_post().map(|p| p.id);
+				self.restore_account_hidden();
+				self.account_rules = blacklist::parse(text);
+				log::info!("Account blacklist has {} rules", self.account_rules.len());
+				if self.account_blacklist_enabled {
+					self.hide_account_blacklisted();
+				}
+				self.current_post_moved(current_id)
+			}
+			Event::Browser(BrowserEvent::ToggleAccountBlacklist) => {
+				let current_id = self.current_post().map(|p| p.id);
+				self.account_blacklist_enabled = !self.account_blacklist_enabled;
+				let message = if self.account_blacklist_enabled {
+					self.hide_account_blacklisted();
+					format!(
+						"Account blacklist on ({} posts hidden)",
+						self.account_hidden.len()
+					)
+				} else {
+					let restored = self.account_hidden.len();
+					self.restore_account_hidden();
+					format!("Account blacklist off ({} posts shown)", restored)
+				};
+				log::info!("{}", message);
+				let mut response = self.current_post_moved(current_id);
+				response
+					.events
+					.push(Event::View(ViewEvent::ShowToast { message }));
+				response
+			}
+			Event::Settings(SettingsEvent::SetShowPending { enabled }) => {
+				self.show_pending = *enabled;
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetWrapNavigation { enabled }) => {
+				self.wrap_navigation = *enabled;
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetFetchAhead { posts }) => {
+				self.fetch_ahead = (*posts).clamp(1, PREFETCH_AHEAD);
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SafeModeChanged { enabled }) => {
+				self.set_safe_only(*enabled)
+			}
 			Event::Browser(BrowserEvent::Navigate { direction }) => {
 				if self.posts.is_empty() {
 					log::debug!("Navigate ignored: no posts");
@@ -67,16 +342,24 @@ impl ContentBrowser {
 				}
 
 				let old_index = self.current_index;
+				let last = self.posts.len() - 1;
 				match direction {
+					NavDirection::Next if self.current_index < last => self.current_index += 1,
+					NavDirection::Next if self.wrap_navigation => self.current_index = 0,
 					NavDirection::Next => {
-						self.current_index = (self.current_index + 1) % self.posts.len();
+						log::debug!("Navigate Next ignored: at the last post");
+						// More may still arrive; ask for it rather than going anywhere
+						return if self.is_end_of_results() {
+							ComponentResponse::none()
+						} else {
+							ComponentResponse::emit(Event::Gateway(GatewayEvent::FetchNextPage))
+						};
 					}
+					NavDirection::Prev if self.current_index > 0 => self.current_index -= 1,
+					NavDirection::Prev if self.wrap_navigation => self.current_index = last,
 					NavDirection::Prev => {
-						if self.current_index == 0 {
-							self.current_index = self.posts.len().saturating_sub(1);
-						} else {
-							self.current_index -= 1;
-						}
+						log::debug!("Navigate Prev ignored: at the first post");
+						return ComponentResponse::none();
 					}
 					NavDirection::Skip(count) => {
 						let count = *count;
@@ -88,6 +371,26 @@ impl ContentBrowser {
 								self.current_index.saturating_sub((-count) as usize);
 						}
 					}
+					NavDirection::ToPosition(position) => {
+						match position.checked_sub(1).filter(|index| *index <= last) {
+							Some(index) => self.current_index = index,
+							None => {
+								let message = format!("Only {} posts are loaded", self.posts.len());
+								return ComponentResponse::emit(Event::View(
+									ViewEvent::ShowToast { message },
+								));
+							}
+						}
+					}
+					NavDirection::ToPage(page) => match self.first_index_of_page(*page) {
+						Some(index) => self.current_index = index,
+						None => {
+							let message = format!("No posts from page {} are loaded", page);
+							return ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+								message,
+							}));
+						}
+					},
 				}
 				log::info!(
 					"Navigate {:?}: {} -> {} (of {})",
@@ -99,11 +402,414 @@ impl ContentBrowser {
 
 				self.emit_current_post_changed()
 			}
+			Event::Browser(BrowserEvent::VoteUpdated {
+				post_id,
+				vote,
+				score,
+			}) => {
+				let previous = self.votes.insert(*post_id, *vote).unwrap_or(0);
+				if let Some(post) = self.posts.iter_mut().find(|p| p.id == *post_id) {
+					match score {
+						Some(score) => post.score = score.clone(),
+						None => Self::adjust_score(&mut post.score, previous, *vote),
+					}
+				}
+				ComponentResponse::none()
+			}
+			Event::Media(MediaEvent::QueueDownloadRange {
+				from_index,
+				count,
+				dest,
+			}) => {
+				let items: Vec<(String, PathBuf)> = self
+					.posts
+					.iter()
+					.skip(*from_index)
+					.take(*count)
+					.filter_map(|p| {
+						let url = p.file.url.clone()?;
+						Some((url, dest.join(Self::download_file_name(p))))
+					})
+					.collect();
+
+				log::info!(
+					"Queueing {} downloads from index {} into {:?}",
+					items.len(),
+					from_index,
+					dest
+				);
+				if items.is_empty() {
+					return ComponentResponse::none();
+				}
+				ComponentResponse::emit(Event::Media(MediaEvent::QueueDownloads { items }))
+			}
 			_ => ComponentResponse::none(),
 		}
 	}
 
+	fn is_blacklisted(&self, post: &Post) -> bool {
+		!self.blacklist.is_empty()
+			&& post
+				.tags
+				.iter()
+				.any(|t| self.blacklist.iter().any(|b| b == t))
+	}
+
+	fn is_account_blacklisted(&self, post: &Post) -> bool {
+		self.account_blacklist_enabled && self.account_rules.iter().any(|r| r.matches(post))
+	}
+
+	/// Drop posts of a new page caught by either blacklist, keeping the
+	/// account blacklist's catch so it can be shown again in `slot`
+	fn hide_blacklisted(&mut self, posts: Vec<Post>, slot: HiddenSlot) -> Vec<Post> {
+		let mut kept = Vec::with_capacity(posts.len());
+		for post in posts {
+			if self.session_hidden.contains(&post.id) {
+				continue;
+			} else if self.is_blacklisted(&post) {
+				self.blacklist_hidden += 1;
+			} else if self.is_account_blacklisted(&post) {
+				self.account_hidden.push((slot, post));
+			} else {
+				kept.push(post);
+			}
+		}
+		kept
+	}
+
+	/// Move loaded and queued posts caught by the account blacklist aside,
+	/// staying on the next remaining post if the current one goes
+	fn hide_account_blacklisted(&mut self) {
+		let previous_index = self.current_index;
+		let mut removed_before = 0;
+		let mut kept = Vec::with_capacity(self.posts.len());
+		for (index, post) in std::mem::take(&mut self.posts).into_iter().enumerate() {
+			if self.is_account_blacklisted(&post) {
+				removed_before += usize::from(index < previous_index);
+				self.account_hidden.push((HiddenSlot::Feed, post));
+			} else {
+				kept.push(post);
+			}
+		}
+		self.posts = kept;
+		for index in 0..self.sources.len() {
+			let pending = std::mem::take(&mut self.sources[index].pending);
+			for post in pending {
+				if self.is_account_blacklisted(&post) {
+					self.account_hidden.push((HiddenSlot::Source(index), post));
+				} else {
+					self.sources[index].pending.push_back(post);
+				}
+			}
+		}
+		self.current_index =
+			(previous_index - removed_before).min(self.posts.len().saturating_sub(1));
+	}
+
+	/// Put posts hidden by the account blacklist back where they arrived, or where
+	/// the sort places them
+	fn restore_account_hidden(&mut self) {
+		for (slot, post) in std::mem::take(&mut self.account_hidden) {
+			match slot {
+				HiddenSlot::Feed => self.insert_post(post),
+				HiddenSlot::Source(index) => self.requeue(index, post),
+			}
+		}
+		self.release_interleaved();
+	}
+
+	/// Put `post` back in the queue of sub-query `index`, ahead of the posts it
+	/// delivered later. With those already in the feed, it's next from that sub-query.
+	fn requeue(&mut self, index: usize, post: Post) {
+		let Some(queue) = self.sources.get_mut(index) else {
+			return;
+		};
+		let order = queue.delivered.get(&post.id).copied().unwrap_or(usize::MAX);
+		let position = queue
+			.pending
+			.iter()
+			.position(|p| queue.delivered.get(&p.id).is_some_and(|&o| o > order))
+			.unwrap_or(queue.pending.len());
+		queue.pending.insert(position, post);
+	}
+
+	/// Load the current post if a blacklist change moved away from `previous_id`
+	fn current_post_moved(&self, previous_id: Option<u64>) -> ComponentResponse {
+		if self.current_post().map(|p| p.id) != previous_id && !self.posts.is_empty() {
+			self.emit_current_post_changed()
+		} else {
+			ComponentResponse::none()
+		}
+	}
+
+	/// Add `tag` to the blacklist and drop matching posts right away,
+	/// moving on to the next remaining post if the current one is gone
+	fn blacklist_tag(&mut self, tag: &str) -> ComponentResponse {
+		let tag = tag.trim().to_lowercase();
+		if tag.is_empty() || self.blacklist.contains(&tag) {
+			return ComponentResponse::none();
+		}
+		log::info!("Blacklisting tag '{}'", tag);
+		self.blacklist.push(tag.clone());
+
+		let previous_index = self.current_index;
+		let current_id = self.current_post().map(|p| p.id);
+		let mut removed = Vec::new();
+		let mut kept = Vec::with_capacity(self.posts.len());
+		for (index, post) in std::mem::take(&mut self.posts).into_iter().enumerate() {
+			if post.tags.iter().any(|t| t == tag) {
+				removed.push((index, post));
+			} else {
+				kept.push(post);
+			}
+		}
+		self.posts = kept;
+		self.blacklist_hidden += removed.len();
+		for queue in &mut self.sources {
+			queue.pending.retain(|p| !p.tags.iter().any(|t| t == tag));
+		}
+
+		// Survivors before the old position; lands on the next survivor if current was removed
+		let before = previous_index - removed.iter().filter(|(i, _)| *i < previous_index).count();
+		self.current_index = before.min(self.posts.len().saturating_sub(1));
+
+		let mut response =
+			if self.current_post().map(|p| p.id) != current_id && !self.posts.is_empty() {
+				self.emit_current_post_changed()
+			} else {
+				ComponentResponse::none()
+			};
+		response.events.push(Event::View(ViewEvent::ShowUndoToast {
+			message: format!("Blacklisted '{}' ({} posts hidden)", tag, removed.len()),
+			undo: Box::new(Event::Browser(BrowserEvent::UnblacklistTag {
+				tag: tag.clone(),
+			})),
+		}));
+		self.last_removed = Some(RemovedByTag {
+			tag,
+			posts: removed,
+			previous_index,
+		});
+		response
+	}
+
+	fn unblacklist_tag(&mut self, tag: &str) -> ComponentResponse {
+		self.blacklist.retain(|t| t != tag);
+		log::info!("Removed '{}' from blacklist", tag);
+
+		let Some(removed) = self.last_removed.take_if(|r| r.tag == tag) else {
+			return ComponentResponse::none();
+		};
+		let current_id = self.current_post().map(|p| p.id);
+		self.blacklist_hidden = self.blacklist_hidden.saturating_sub(removed.posts.len());
+		for (index, post) in removed.posts {
+			let index = index.min(self.posts.len());
+			self.posts.insert(index, post);
+		}
+		self.current_index = removed
+			.previous_index
+			.min(self.posts.len().saturating_sub(1));
+		if self.current_post().map(|p| p.id) != current_id {
+			self.emit_current_post_changed()
+		} else {
+			ComponentResponse::none()
+		}
+	}
+
+	/// Replace the blacklist wholesale, e.g. from an import. Posts it now
+	/// catches are dropped without undo; posts it no longer catches return with the next page.
+	fn set_blacklist(&mut self, tags: &[String]) -> ComponentResponse {
+		log::info!("Blacklist set to {} tags", tags.len());
+		self.blacklist = tags.to_vec();
+		self.last_removed = None;
+
+		let current_id = self.current_post().map(|p| p.id);
+		let previous_index = self.current_index;
+		let mut removed_before = 0;
+		let mut kept = Vec::with_capacity(self.posts.len());
+		for (index, post) in std::mem::take(&mut self.posts).into_iter().enumerate() {
+			if self.is_blacklisted(&post) {
+				removed_before += usize::from(index < previous_index);
+				self.blacklist_hidden += 1;
+			} else {
+				kept.push(post);
+			}
+		}
+		self.posts = kept;
+		for index in 0..self.sources.len() {
+			let pending = std::mem::take(&mut self.sources[index].pending);
+			let kept: VecDeque<Post> = pending
+				.into_iter()
+				.filter(|post| !self.is_blacklisted(post))
+				.collect();
+			self.sources[index].pending = kept;
+		}
+		self.current_index =
+			(previous_index - removed_before).min(self.posts.len().saturating_sub(1));
+		self.current_post_moved(current_id)
+	}
+
+	/// Turning safe mode on drops the loaded posts that aren't rated safe, and the
+	/// remembered searches, which would bring them back
+	fn set_safe_only(&mut self, enabled: bool) -> ComponentResponse {
+		self.safe_only = enabled;
+		if !enabled {
+			return ComponentResponse::none();
+		}
+		self.search_stack.clear();
+		let current_id = self.current_post().map(|p| p.id);
+		let previous_index = self.current_index;
+		let removed_before = self.posts[..previous_index.min(self.posts.len())]
+			.iter()
+			.filter(|p| p.rating != "s")
+			.count();
+		let before = self.posts.len();
+		self.posts.retain(|p| p.rating == "s");
+		for queue in &mut self.sources {
+			queue.pending.retain(|p| p.rating == "s");
+		}
+		log::info!(
+			"Safe mode dropped {} loaded posts",
+			before - self.posts.len()
+		);
+		self.current_index =
+			(previous_index - removed_before).min(self.posts.len().saturating_sub(1));
+		self.current_post_moved(current_id)
+	}
+
+	/// Drop a post for the session, landing on the one after it
+	fn hide_post(&mut self, id: u64) -> ComponentResponse {
+		if !self.session_hidden.insert(id) {
+			return ComponentResponse::none();
+		}
+		log::info!("Hiding post {} for this session", id);
+		for queue in &mut self.sources {
+			queue.pending.retain(|p| p.id != id);
+		}
+		let Some(index) = self.posts.iter().position(|p| p.id == id) else {
+			return ComponentResponse::none();
+		};
+		let current_id = self.current_post().map(|p| p.id);
+		let post = self.posts.remove(index);
+		if index < self.current_index {
+			self.current_index -= 1;
+		}
+		self.current_index = self.current_index.min(self.posts.len().saturating_sub(1));
+		self.last_hidden = Some((index, post));
+
+		let mut response = self.current_post_moved(current_id);
+		response.events.push(Event::View(ViewEvent::ShowUndoToast {
+			message: format!("Post {} hidden for this session", id),
+			undo: Box::new(Event::Browser(BrowserEvent::UnhidePost { id })),
+		}));
+		response
+	}
+
+	fn unhide_post(&mut self, id: u64) -> ComponentResponse {
+		if !self.session_hidden.remove(&id) {
+			return ComponentResponse::none();
+		}
+		log::info!("Showing post {} again", id);
+		let Some((index, post)) = self.last_hidden.take_if(|(_, p)| p.id == id) else {
+			return ComponentResponse::none();
+		};
+		let current_id = self.current_post().map(|p| p.id);
+		let index = index.min(self.posts.len());
+		self.posts.insert(index, post);
+		self.current_index = index;
+		self.current_post_moved(current_id)
+	}
+
+	/// Queue a page from one sub-query and release whatever can be interleaved
+	fn receive_compound_page(
+		&mut self,
+		source: &PageSource,
+		delivered: &[Post],
+		posts: Vec<Post>,
+		exhausted: bool,
+		page: u32,
+		is_new: bool,
+	) -> ComponentResponse {
+		if is_new || self.sources.len() != source.count {
+			self.posts.clear();
+			self.arrival.clear();
+			self.post_pages.clear();
+			self.current_index = 0;
+			self.post_sources.clear();
+			self.sources = (0..source.count).map(|_| SourceQueue::default()).collect();
+		}
+		log::info!(
+			"Compound results: sub-query={} '{}', page={}, posts={}",
+			source.index,
+			source.query,
+			page,
+			posts.len()
+		);
+		self.current_page = page;
+		self.record_page(&posts, page);
+
+		let Some(queue) = self.sources.get_mut(source.index) else {
+			return ComponentResponse::none();
+		};
+		queue.query = source.query.clone();
+		for post in delivered {
+			let next = queue.delivered.len();
+			queue.delivered.entry(post.id).or_insert(next);
+		}
+		queue.pending.extend(posts);
+		queue.exhausted = exhausted;
+
+		let starved = !exhausted && self.sources[source.index].pending.is_empty();
+		self.release_interleaved();
+
+		if !self.posts.is_empty() {
+			let mut response = self.emit_current_post_changed();
+			if starved {
+				// Everything on the page was filtered; keep this sub-query flowing
+				response
+					.events
+					.push(Event::Gateway(GatewayEvent::FetchNextPage));
+			}
+			response
+		} else if self.sources.iter().all(|q| q.exhausted) {
+			log::warn!("Received empty posts");
+			ComponentResponse::none()
+		} else {
+			// Nothing to show until every sub-query has delivered a page
+			ComponentResponse::emit(Event::Gateway(GatewayEvent::FetchNextPage))
+		}
+	}
+
+	/// Move posts from the queues into the feed one per sub-query at a time,
+	/// stopping as soon as a live sub-query has nothing pending
+	fn release_interleaved(&mut self) {
+		loop {
+			let live: Vec<usize> = (0..self.sources.len())
+				.filter(|&i| !self.sources[i].exhausted || !self.sources[i].pending.is_empty())
+				.collect();
+			if live.is_empty() || live.iter().any(|&i| self.sources[i].pending.is_empty()) {
+				break;
+			}
+			for i in live {
+				// A post matching several sub-queries is shown once
+				while let Some(post) = self.sources[i].pending.pop_front() {
+					if self.post_sources.contains_key(&post.id) {
+						continue;
+					}
+					self.post_sources.insert(post.id, i);
+					self.add_post(post);
+					break;
+				}
+			}
+		}
+	}
+
 	fn emit_current_post_changed(&self) -> ComponentResponse {
+		self.emit_post_changed_with(&[])
+	}
+
+	/// Like `emit_current_post_changed`, with `new_posts` added to the prefetch hint
+	fn emit_post_changed_with(&self, new_posts: &[u64]) -> ComponentResponse {
 		let post = self.posts.get(self.current_index).cloned();
 		let mut events = Vec::new();
 
@@ -133,7 +839,7 @@ impl ContentBrowser {
 
 			// Check if near end for prefetching
 			let remaining = self.posts.len().saturating_sub(self.current_index + 1);
-			if remaining < 5 {
+			if remaining < self.fetch_ahead {
 				log::debug!(
 					"Near end of results (remaining={}), requesting next page",
 					remaining
@@ -141,37 +847,316 @@ impl ContentBrowser {
 				events.push(Event::Gateway(GatewayEvent::FetchNextPage));
 			}
 
-			// Emit prefetch hints for next 30 posts
-			let prefetch_urls: Vec<(Option<String>, Option<String>, bool)> = (1..=30)
-				.filter_map(|i| {
-					let idx = (self.current_index + i) % self.posts.len();
-					self.posts.get(idx).map(|p| {
-						let is_video = false;
-						let sample_url = if p.sample.has {
-							p.sample.url.clone()
-						} else {
-							None
-						};
-						(sample_url, p.file.url.clone(), is_video)
-					})
-				})
-				.collect();
+			events.extend(self.prefetch_hint_with(new_posts));
+		}
 
-			if !prefetch_urls.is_empty() {
-				log::debug!("Requesting prefetch for {} URLs", prefetch_urls.len());
-				events.push(Event::Media(MediaEvent::Prefetch {
-					urls: prefetch_urls,
-				}));
+		ComponentResponse::emit_many(events)
+	}
+
+	/// Prefetch hints for the next `PREFETCH_AHEAD` posts
+	fn prefetch_hint(&self) -> Option<Event> {
+		self.prefetch_hint_with(&[])
+	}
+
+	/// Prefetch hints for the next `PREFETCH_AHEAD` posts, then the samples of any of
+	/// `extra` further on. The media cache loads samples of posts it has nothing of first.
+	fn prefetch_hint_with(&self, extra: &[u64]) -> Option<Event> {
+		let indices: Vec<usize> = (1..=PREFETCH_AHEAD)
+			.filter_map(|i| {
+				let mut idx = self.current_index + i;
+				if self.wrap_navigation {
+					idx %= self.posts.len();
+				}
+				(idx < self.posts.len()).then_some(idx)
+			})
+			.collect();
+		let beyond: Vec<usize> = extra
+			.iter()
+			.filter_map(|id| self.posts.iter().position(|p| p.id == *id))
+			.filter(|idx| *idx != self.current_index && !indices.contains(idx))
+			.collect();
+		if !beyond.is_empty() {
+			log::debug!(
+				"Prefetching {} posts of the new page past the usual range",
+				beyond.len()
+			);
+		}
+
+		let sample_url = |p: &Post| {
+			if p.sample.has {
+				p.sample.url.clone()
+			} else {
+				None
 			}
+		};
+		let mut prefetch_urls: Vec<(Option<String>, Option<String>, bool)> = indices
+			.into_iter()
+			.map(|idx| {
+				let p = &self.posts[idx];
+				let is_video = false;
+				(sample_url(p), p.file.url.clone(), is_video)
+			})
+			.collect();
+		// Far from the current post, a sample is enough for now
+		prefetch_urls.extend(
+			beyond
+				.into_iter()
+				.filter_map(|idx| sample_url(&self.posts[idx]))
+				.map(|sample| (Some(sample), None, false)),
+		);
+
+		if prefetch_urls.is_empty() {
+			return None;
 		}
+		log::debug!("Requesting prefetch for {} URLs", prefetch_urls.len());
+		Some(Event::Media(MediaEvent::Prefetch {
+			urls: prefetch_urls,
+		}))
+	}
 
-		ComponentResponse::emit_many(events)
+	/// Remember which page delivered `posts`, hidden ones too so they have one if
+	/// shown again. A post delivered twice keeps its first page.
+	fn record_page(&mut self, posts: &[Post], page: u32) {
+		for post in posts {
+			self.post_pages.entry(post.id).or_insert(page);
+		}
+	}
+
+	/// Give the delivered posts their place in API order, hidden ones too so they
+	/// go back there if shown again
+	fn record_arrival(&mut self, posts: &[Post]) {
+		for post in posts {
+			let next = self.arrival.len();
+			self.arrival.entry(post.id).or_insert(next);
+		}
+	}
+
+	/// Add a newly delivered post, at the end or where the local sort puts it
+	fn add_post(&mut self, post: Post) {
+		self.record_arrival(std::slice::from_ref(&post));
+		self.insert_post(post);
+	}
+
+	/// Insert `post` where the local sort puts it, keeping the current post selected
+	fn insert_post(&mut self, post: Post) {
+		// After its equals, as a stable sort would place it
+		let index = self
+			.posts
+			.partition_point(|p| self.compare_posts(p, &post) != Ordering::Greater);
+		if index <= self.current_index && !self.posts.is_empty() {
+			self.current_index += 1;
+		}
+		self.posts.insert(index, post);
+	}
+
+	/// Stably reorder the posts by the current sort, keeping the current post selected
+	fn apply_sort(&mut self) {
+		let current_id = self.current_post().map(|p| p.id);
+		let mut posts = std::mem::take(&mut self.posts);
+		posts.sort_by(|a, b| self.compare_posts(a, b));
+		self.posts = posts;
+		if let Some(id) = current_id {
+			self.current_index = self.posts.iter().position(|p| p.id == id).unwrap_or(0);
+		}
+	}
+
+	fn compare_posts(&self, a: &Post, b: &Post) -> Ordering {
+		let order = self.sort_value(a).cmp(&self.sort_value(b));
+		if self.sort_descending && self.sort_key != SortKey::ApiOrder {
+			order.reverse()
+		} else {
+			order
+		}
+	}
+
+	fn sort_value(&self, post: &Post) -> i64 {
+		match self.sort_key {
+			SortKey::ApiOrder => self
+				.arrival
+				.get(&post.id)
+				.map_or(i64::MAX, |&index| index as i64),
+			SortKey::Score => post.score.total,
+			SortKey::Favorites => post.fav_count as i64,
+			SortKey::Newest => post.id as i64,
+			SortKey::FileSize => post.file.size as i64,
+		}
+	}
+
+	/// Apply a local vote change to the displayed totals (downvotes count negative)
+	fn adjust_score(score: &mut crate::api::Score, previous: i8, vote: i8) {
+		match previous {
+			1 => score.up -= 1,
+			-1 => score.down += 1,
+			_ => {}
+		}
+		match vote {
+			1 => score.up += 1,
+			-1 => score.down -= 1,
+			_ => {}
+		}
+		score.total += (vote - previous) as i64;
+	}
+
+	/// `{artist}_{id}.{ext}`, sanitized for the filesystem
+	fn download_file_name(post: &Post) -> String {
+		let artist = post
+			.tags
+			.artist
+			.iter()
+			.find(|a| {
+				!matches!(
+					a.as_str(),
+					"invalid_artist" | "unknown_artist" | "conditional_dnp" | "sound_warning"
+				)
+			})
+			.map(String::as_str)
+			.unwrap_or("unknown_artist");
+		let artist: String = artist
+			.chars()
+			.map(|c| {
+				if c.is_alphanumeric() || c == '-' || c == '_' {
+					c
+				} else {
+					'_'
+				}
+			})
+			.collect();
+		format!("{}_{}.{}", artist, post.id, post.file.ext)
+	}
+
+	pub fn blacklist(&self) -> &[String] {
+		&self.blacklist
+	}
+
+	/// Posts hidden by either blacklist since the last search
+	pub fn blacklist_hidden(&self) -> usize {
+		self.blacklist_hidden + self.account_hidden.len()
+	}
+
+	/// Posts hidden one by one this session
+	pub fn session_hidden(&self) -> usize {
+		self.session_hidden.len()
+	}
+
+	/// None until an account blacklist with rules was loaded
+	pub fn account_blacklist_enabled(&self) -> Option<bool> {
+		(!self.account_rules.is_empty()).then_some(self.account_blacklist_enabled)
+	}
+
+	pub fn show_pending(&self) -> bool {
+		self.show_pending
+	}
+
+	pub fn wrap_navigation(&self) -> bool {
+		self.wrap_navigation
+	}
+
+	pub fn fetch_ahead(&self) -> usize {
+		self.fetch_ahead
+	}
+
+	/// Local sort of the loaded posts, as (key, descending)
+	pub fn sort(&self) -> (SortKey, bool) {
+		(self.sort_key, self.sort_descending)
+	}
+
+	/// Whether every source has run dry, so no more posts will be appended
+	pub fn is_end_of_results(&self) -> bool {
+		if self.sources.is_empty() {
+			self.end_reached
+		} else {
+			self.sources
+				.iter()
+				.all(|q| q.exhausted && q.pending.is_empty())
+		}
+	}
+
+	/// Remember the result list and position before a "More like this" search
+	pub fn push_snapshot(&mut self) {
+		if self.search_stack.len() >= SEARCH_STACK_DEPTH {
+			self.search_stack.remove(0);
+		}
+		self.search_stack.push(BrowserSnapshot {
+			posts: self.posts.clone(),
+			current_index: self.current_index,
+			current_page: self.current_page,
+			end_reached: self.end_reached,
+			sources: self.sources.clone(),
+			post_sources: self.post_sources.clone(),
+			arrival: self.arrival.clone(),
+			post_pages: self.post_pages.clone(),
+			account_hidden: self.account_hidden.clone(),
+			blacklist_hidden: self.blacklist_hidden,
+		});
+	}
+
+	/// Restore the last remembered result list and reload its current post
+	pub fn pop_snapshot(&mut self) -> ComponentResponse {
+		let Some(snapshot) = self.search_stack.pop() else {
+			return ComponentResponse::none();
+		};
+		self.posts = snapshot.posts;
+		self.current_index = snapshot.current_index;
+		self.current_page = snapshot.current_page;
+		self.end_reached = snapshot.end_reached;
+		self.sources = snapshot.sources;
+		self.post_sources = snapshot.post_sources;
+		self.arrival = snapshot.arrival;
+		self.post_pages = snapshot.post_pages;
+		self.account_hidden = snapshot.account_hidden;
+		self.blacklist_hidden = snapshot.blacklist_hidden;
+		// Posts hidden since the snapshot was taken stay hidden
+		let hidden = &self.session_hidden;
+		let removed_before = self.posts[..self.current_index.min(self.posts.len())]
+			.iter()
+			.filter(|p| hidden.contains(&p.id))
+			.count();
+		self.posts.retain(|p| !hidden.contains(&p.id));
+		for queue in &mut self.sources {
+			queue.pending.retain(|p| !hidden.contains(&p.id));
+		}
+		self.current_index =
+			(self.current_index - removed_before).min(self.posts.len().saturating_sub(1));
+		// The sort may have changed since the snapshot was taken
+		self.apply_sort();
+		// Positions recorded for undo refer to the replaced list
+		self.last_removed = None;
+		self.last_hidden = None;
+		log::info!(
+			"Restored {} posts at index {}",
+			self.posts.len(),
+			self.current_index
+		);
+		self.emit_current_post_changed()
 	}
 
 	pub fn current_post(&self) -> Option<&Post> {
 		self.posts.get(self.current_index)
 	}
 
+	/// Sub-query a post came from, when browsing a compound search
+	pub fn source_query_for(&self, post_id: u64) -> Option<&str> {
+		let index = *self.post_sources.get(&post_id)?;
+		self.sources.get(index).map(|q| q.query.as_str())
+	}
+
+	/// API page a loaded post was delivered on
+	pub fn page_of(&self, post_id: u64) -> Option<u32> {
+		self.post_pages.get(&post_id).copied()
+	}
+
+	/// Where the posts of `page` start in the current order, which after sorting
+	/// is wherever the first of them ended up
+	fn first_index_of_page(&self, page: u32) -> Option<usize> {
+		self.posts
+			.iter()
+			.position(|post| self.page_of(post.id) == Some(page))
+	}
+
+	pub fn vote_for(&self, post_id: u64) -> i8 {
+		self.votes.get(&post_id).copied().unwrap_or(0)
+	}
+
 	pub fn current_index(&self) -> usize {
 		self.current_index
 	}
@@ -180,13 +1165,57 @@ impl ContentBrowser {
 		self.posts.len()
 	}
 
+	/// Up to `count` posts after the current one, in the order they'll be shown.
+	/// Never comes back around to the current post.
+	pub fn peek_next(&self, count: usize) -> Vec<&Post> {
+		(1..=count.min(self.posts.len().saturating_sub(1)))
+			.map_while(|offset| self.get_post_relative(offset as isize))
+			.collect()
+	}
+
+	/// Posts at offsets from the current one (0), e.g. `-2..=5`, with their index, in
+	/// the order they're shown. Offsets past the ends are left out unless navigation
+	/// wraps; a short list that wraps shows each post once, nearest offset first.
+	pub fn peek_range(&self, range: RangeInclusive<isize>) -> Vec<(usize, &Post)> {
+		let len = self.posts.len() as isize;
+		let mut offsets: Vec<isize> = range.collect();
+		offsets.sort_by_key(|offset| (offset.abs(), *offset));
+		let mut seen = HashSet::new();
+		let mut found: Vec<(isize, usize)> = offsets
+			.into_iter()
+			.filter_map(|offset| {
+				let index = self.current_index as isize + offset;
+				let index = if self.wrap_navigation && len > 0 {
+					index.rem_euclid(len)
+				} else {
+					index
+				};
+				let index = usize::try_from(index)
+					.ok()
+					.filter(|i| *i < self.posts.len())?;
+				seen.insert(index).then_some((offset, index))
+			})
+			.collect();
+		found.sort_by_key(|(offset, _)| *offset);
+		found
+			.into_iter()
+			.map(|(_, index)| (index, &self.posts[index]))
+			.collect()
+	}
+
 	pub fn get_post_relative(&self, offset: isize) -> Option<&Post> {
 		if self.posts.is_empty() {
 			return None;
 		}
 		let len = self.posts.len() as isize;
-		let idx = (self.current_index as isize + offset).rem_euclid(len) as usize;
-		self.posts.get(idx)
+		let idx = self.current_index as isize + offset;
+		if self.wrap_navigation {
+			self.posts.get(idx.rem_euclid(len) as usize)
+		} else {
+			usize::try_from(idx)
+				.ok()
+				.and_then(|idx| self.posts.get(idx))
+		}
 	}
 
 	pub fn is_empty(&self) -> bool {
@@ -196,6 +1225,6 @@ impl ContentBrowser {
 
 impl Default for ContentBrowser {
 	fn default() -> Self {
-		Self::new()
+		Self::new(false, Vec::new())
 	}
 }
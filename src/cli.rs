@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: sodglumate [OPTIONS]
+
+Options:
+  --query <TAGS>     Search for TAGS on startup
+  --page <N>         Start the search at page N (default 1, needs --query)
+  --autoplay <SECS>  Start the slideshow, advancing every SECS seconds (1-60)
+  --fullscreen       Open the window fullscreen
+  --no-audio         Don't capture system audio for beat detection
+  --sfw              Safe mode: only rating:s posts, and it can't be turned off
+  --diagnose         Check the connection, audio and settings folder, then exit
+  -h, --help         Print this help";
+
+/// State to start in, given on the command line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StartupOptions {
+	pub query: Option<String>,
+	pub page: u32,
+	pub autoplay: Option<Duration>,
+	pub fullscreen: bool,
+	pub no_audio: bool,
+	/// Safe mode, locked on for the session
+	pub sfw: bool,
+}
+
+/// What the command line asked for
+#[derive(Debug, PartialEq)]
+pub enum Command {
+	Run(StartupOptions),
+	Help,
+	/// Print the self-check's report instead of opening the window
+	Diagnose,
+}
+
+/// Parse the arguments after the program name. Both `--page 3` and `--page=3` work.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+	let mut options = StartupOptions {
+		page: 1,
+		..Default::default()
+	};
+	let mut page_given = false;
+	let mut args = args.into_iter();
+	while let Some(arg) = args.next() {
+		let (name, inline) = match arg.split_once('=') {
+			Some((name, value)) if name.starts_with("--") => {
+				(name.to_string(), Some(value.to_string()))
+			}
+			_ => (arg, None),
+		};
+		let mut value = || {
+			inline
+				.clone()
+				.or_else(|| args.next())
+				.ok_or_else(|| format!("{} needs a value", name))
+		};
+		match name.as_str() {
+			"-h" | "--help" => return Ok(Command::Help),
+			"--diagnose" if inline.is_none() => return Ok(Command::Diagnose),
+			"--query" => options.query = Some(value()?),
+			"--page" => {
+				let text = value()?;
+				options.page = text
+					.parse()
+					.ok()
+					.filter(|page| *page >= 1)
+					.ok_or_else(|| format!("--page expects a number from 1, got '{}'", text))?;
+				page_given = true;
+			}
+			"--autoplay" => {
+				let text = value()?;
+				let secs = text
+					.parse::<f32>()
+					.ok()
+					.filter(|secs| (1.0..=60.0).contains(secs))
+					.ok_or_else(|| {
+						format!("--autoplay expects seconds from 1 to 60, got '{}'", text)
+					})?;
+				options.autoplay = Some(Duration::from_secs_f32(secs));
+			}
+			"--fullscreen" if inline.is_none() => options.fullscreen = true,
+			"--no-audio" if inline.is_none() => options.no_audio = true,
+			"--sfw" if inline.is_none() => options.sfw = true,
+			_ => return Err(format!("Unknown argument '{}'", name)),
+		}
+	}
+	if page_given && options.query.is_none() {
+		return Err("--page needs --query".to_string());
+	}
+	Ok(Command::Run(options))
+}
+
+/// Release builds have no console of their own on Windows, so attach to the
+/// one the program was started from to make usage errors visible there
+#[cfg(windows)]
+pub fn attach_console() {
+	const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+	#[link(name = "kernel32")]
+	unsafe extern "system" {
+		fn AttachConsole(process_id: u32) -> i32;
+	}
+	// Fails harmlessly when started from Explorer, which has no console
+	unsafe {
+		AttachConsole(ATTACH_PARENT_PROCESS);
+	}
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse_args(args: &[&str]) -> Result<Command, String> {
+		parse(args.iter().map(|a| a.to_string()))
+	}
+
+	#[test]
+	fn parses_a_scripted_launch() {
+		let command = parse_args(&[
+			"--query",
+			"wolf order:score",
+			"--page=3",
+			"--autoplay",
+			"20",
+			"--fullscreen",
+			"--no-audio",
+			"--sfw",
+		]);
+		assert_eq!(
+			command,
+			Ok(Command::Run(StartupOptions {
+				query: Some("wolf order:score".to_string()),
+				page: 3,
+				autoplay: Some(Duration::from_secs(20)),
+				fullscreen: true,
+				no_audio: true,
+				sfw: true,
+			}))
+		);
+		assert_eq!(
+			parse_args(&[]),
+			Ok(Command::Run(StartupOptions {
+				page: 1,
+				..Default::default()
+			}))
+		);
+	}
+
+	#[test]
+	fn rejects_bad_arguments() {
+		for args in [
+			&["--query"][..],
+			&["--query", "wolf", "--page", "0"],
+			&["--page", "2"],
+			&["--autoplay", "soon"],
+			&["--autoplay", "600"],
+			&["--fullscreen=yes"],
+			&["--sfw=1"],
+			&["wolf"],
+		] {
+			assert!(parse_args(args).is_err(), "{:?}", args);
+		}
+		assert_eq!(parse_args(&["--query", "x", "-h"]), Ok(Command::Help));
+		assert_eq!(parse_args(&["--diagnose"]), Ok(Command::Diagnose));
+		assert!(parse_args(&["--diagnose=1"]).is_err());
+	}
+}
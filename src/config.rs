@@ -1,47 +1,164 @@
-use crate::types::{BreathingStyle, ImageFillMode};
-use directories::{BaseDirs, ProjectDirs};
+use crate::browser::DEFAULT_FETCH_AHEAD;
+use crate::types::{
+	BreathingPalette, BreathingStyle, CreditOverlay, ImageFillMode, InfoFieldSetting,
+	InfoVerbosity, OverlayAnchor, PanMode, SearchWatch, StartupSearch, Theme,
+};
+use directories::{BaseDirs, ProjectDirs, UserDirs};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SavedSettings {
 	pub search_query: String,
+	/// Search run once the terms are accepted at startup
+	pub startup_search: StartupSearch,
+	/// Query for `StartupSearch::DefaultQuery`
+	pub default_query: String,
 	pub search_page_input: String,
 	pub auto_play: bool,
 	pub auto_play_delay_secs: f32,
 	pub cap_by_breathing: bool,
+	/// Don't change the image while breathing in or holding
+	pub hold_slideshow_during_breath: bool,
+	/// Pause auto-play while the user pans, zooms or navigates
+	pub auto_pause_on_interaction: bool,
+	pub auto_pause_idle_secs: f32,
+	/// Info overlay lines in display order
+	pub info_fields: Vec<InfoFieldSetting>,
+	pub info_verbosity: InfoVerbosity,
+	/// "art by ..." pill in a corner, for screen sharing
+	pub credit_overlay: CreditOverlay,
+	/// Accent color, top panel opacity, dark or light
+	pub theme: Theme,
 	pub breathing_idle_multiplier: f32,
 	pub breathing_style: BreathingStyle,
+	pub breathing_palette: BreathingPalette,
+	pub breathing_overlay_anchor: OverlayAnchor,
+	pub breathing_overlay_scale: f32,
+	pub breathing_pulse_duration_secs: f32,
+	pub breathing_pulse_scale: f32,
+	/// Immersive style: how far the image zooms in on an inhale, 0 to leave it still
+	pub breathing_image_zoom: f32,
+	/// Safety cap on Inhale and Hold together
+	pub breathing_max_inhale_hold_secs: f32,
 	pub auto_pan_cycle_duration: f32,
+	/// Arrow/WASD panning speed in points per second
+	pub key_pan_speed: f32,
+	pub pan_mode: PanMode,
 	pub selected_audio_device: Option<String>,
 	pub beat_pulse_enabled: bool,
 	pub beat_pulse_scale: f32,
 	pub image_fill_mode: ImageFillMode,
+	pub show_pending: bool,
+	/// Next/Prev wrap around the ends of the result list
+	pub wrap_navigation: bool,
+	/// The next page is requested once this few posts are left
+	pub fetch_ahead_posts: usize,
+	/// Pages a new search fetches up front, 1 fetches just the first
+	pub search_fanout_pages: u32,
+	/// Tags whose posts are never shown
+	pub blacklist: Vec<String>,
+	/// Searches checked in the background for new posts
+	pub watches: Vec<SearchWatch>,
+	/// Minutes between two polls of the same watch
+	pub watch_interval_mins: u32,
+	/// Static breathing text, instant transitions, slower auto-pan, no beat zoom
+	pub reduce_motion: bool,
+	/// Pure white overlay text with a thick solid outline
+	pub high_contrast_overlays: bool,
+	/// Only rating:s posts. `--sfw` forces it on without changing this.
+	pub safe_mode: bool,
+	/// Restore pan/zoom when revisiting an image
+	pub remember_view_positions: bool,
+	/// Mouse wheel steps through images instead of scrolling them
+	pub wheel_navigation: bool,
+	pub show_status_bar: bool,
+	/// Thumbnails of the posts around the current one
+	pub show_filmstrip: bool,
+	/// Blur new images until revealed
+	pub reveal_mode: bool,
+	/// The slideshow reveals a blurred image after this long
+	pub reveal_auto_secs: f32,
 
 	pub coach_enabled: bool,
 	pub coach_model: Option<String>,
 	pub coach_preset: Option<String>,
+
+	pub api_username: Option<String>,
+	pub api_key: Option<String>,
 }
 
 impl Default for SavedSettings {
 	fn default() -> Self {
 		Self {
-			search_query: "~gay ~male solo abs wolf order:score".to_owned(),
+			search_query: String::new(),
+			startup_search: StartupSearch::Nothing,
+			default_query: "order:score".to_owned(),
 			search_page_input: "1".to_owned(),
 			auto_play: false,
 			auto_play_delay_secs: 16.0,
 			cap_by_breathing: false,
+			hold_slideshow_during_breath: false,
+			auto_pause_on_interaction: true,
+			auto_pause_idle_secs: 30.0,
+			info_fields: InfoFieldSetting::defaults(),
+			info_verbosity: InfoVerbosity::Full,
+			credit_overlay: CreditOverlay::default(),
+			theme: Theme::default(),
 			breathing_idle_multiplier: 1.0,
 			breathing_style: BreathingStyle::Immersive,
+			breathing_palette: BreathingPalette::CLASSIC,
+			breathing_overlay_anchor: OverlayAnchor::BottomRight,
+			breathing_overlay_scale: 1.0,
+			breathing_pulse_duration_secs: 1.5,
+			breathing_pulse_scale: 0.15,
+			breathing_image_zoom: 0.0,
+			breathing_max_inhale_hold_secs: 25.0,
 			auto_pan_cycle_duration: 10.0,
+			key_pan_speed: 1200.0,
+			pan_mode: PanMode::PingPong,
 			selected_audio_device: None,
 			beat_pulse_enabled: false,
 			beat_pulse_scale: 0.03,
 			image_fill_mode: ImageFillMode::Fit,
+			show_pending: false,
+			wrap_navigation: false,
+			fetch_ahead_posts: DEFAULT_FETCH_AHEAD,
+			search_fanout_pages: 1,
+			blacklist: Vec::new(),
+			watches: Vec::new(),
+			watch_interval_mins: 15,
+			reduce_motion: false,
+			high_contrast_overlays: false,
+			safe_mode: false,
+			remember_view_positions: true,
+			wheel_navigation: false,
+			show_status_bar: false,
+			show_filmstrip: false,
+			reveal_mode: false,
+			reveal_auto_secs: 3.0,
 			coach_enabled: false,
 			coach_model: None,
 			coach_preset: None,
+			api_username: None,
+			api_key: None,
+		}
+	}
+}
+
+impl SavedSettings {
+	/// API login, if both halves were filled in
+	pub fn credentials(&self) -> Option<crate::api::Credentials> {
+		match (&self.api_username, &self.api_key) {
+			(Some(username), Some(api_key)) if !username.is_empty() && !api_key.is_empty() => {
+				Some(crate::api::Credentials {
+					username: username.clone(),
+					api_key: api_key.clone(),
+				})
+			}
+			_ => None,
 		}
 	}
 }
@@ -62,6 +179,12 @@ pub fn get_presets_dir() -> Option<PathBuf> {
 	get_config_dir().map(|p| p.join("presets"))
 }
 
+pub fn get_downloads_dir() -> Option<PathBuf> {
+	UserDirs::new()
+		.and_then(|u| u.download_dir().map(|d| d.join("sodglumate")))
+		.or_else(|| get_config_dir().map(|p| p.join("downloads")))
+}
+
 pub fn load_settings() -> SavedSettings {
 	if let Some(dir) = get_config_dir() {
 		let path = dir.join("settings.toml");
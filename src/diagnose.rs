@@ -0,0 +1,389 @@
+use crate::api::{BooruClient, Post};
+use crate::beat::SystemBeat;
+use crate::gateway::rate_limit::RateLimiter;
+use crate::media::loader::{Fetched, MediaLoader};
+use crate::reactor::component::EventMask;
+use crate::reactor::{Component, ComponentResponse, Event, ModalKind, SettingsEvent, ViewEvent};
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Asked of the API by the search check; safe posts, so any account can see one
+const SEARCH_QUERY: &str = "rating:s";
+
+/// Written to the config folder and removed again by the disk check
+const WRITE_TEST_FILE: &str = "diagnostics-write-test.tmp";
+
+/// One step of the self-check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+	Reach,
+	Search,
+	Image,
+	Audio,
+	Disk,
+}
+
+impl Check {
+	pub fn label(self) -> &'static str {
+		match self {
+			Check::Reach => "Reach e621",
+			Check::Search => "Search",
+			Check::Image => "Sample image",
+			Check::Audio => "Audio devices",
+			Check::Disk => "Write settings folder",
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+	pub check: Check,
+	/// What was found, or why it failed
+	pub outcome: Result<String, String>,
+	pub took: Duration,
+}
+
+/// Results of every check, in the order they ran
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsReport {
+	pub results: Vec<CheckResult>,
+}
+
+impl DiagnosticsReport {
+	pub fn passed(&self) -> bool {
+		self.results.iter().all(|result| result.outcome.is_ok())
+	}
+
+	/// Plain text of the report, for the clipboard or the terminal
+	pub fn to_text(&self) -> String {
+		let mut lines = vec![format!(
+			"Sodglumate {} on {}",
+			env!("CARGO_PKG_VERSION"),
+			std::env::consts::OS
+		)];
+		for result in &self.results {
+			let (mark, detail) = match &result.outcome {
+				Ok(detail) => ("ok  ", detail),
+				Err(error) => ("FAIL", error),
+			};
+			lines.push(format!(
+				"[{}] {} ({} ms): {}",
+				mark,
+				result.check.label(),
+				result.took.as_millis(),
+				detail
+			));
+		}
+		lines.join("\n")
+	}
+}
+
+/// What the self-check runs against: the client and rate limiter searches use,
+/// the loader the media workers use, and the folder settings are saved in
+#[derive(Clone)]
+pub struct Diagnostics {
+	client: Arc<dyn BooruClient>,
+	limiter: Arc<RateLimiter>,
+	loader: Arc<dyn MediaLoader>,
+	config_dir: Option<PathBuf>,
+}
+
+impl Diagnostics {
+	pub fn new(
+		client: Arc<dyn BooruClient>,
+		limiter: Arc<RateLimiter>,
+		loader: Arc<dyn MediaLoader>,
+		config_dir: Option<PathBuf>,
+	) -> Self {
+		Self {
+			client,
+			limiter,
+			loader,
+			config_dir,
+		}
+	}
+
+	/// Run every check. A failed check is recorded and the rest still run;
+	/// the image check loads the post the search found.
+	pub async fn run(&self) -> DiagnosticsReport {
+		log::info!("Running diagnostics");
+		let mut report = DiagnosticsReport::default();
+
+		let (result, _) = timed(Check::Reach, self.reach()).await;
+		report.results.push(result);
+
+		let (result, post) = timed(Check::Search, self.search()).await;
+		report.results.push(result);
+
+		let (result, _) = match post {
+			Some(post) => timed(Check::Image, self.load_image(post)).await,
+			None => (
+				CheckResult {
+					check: Check::Image,
+					outcome: Err("Skipped, the search found no post to load".to_string()),
+					took: Duration::ZERO,
+				},
+				None,
+			),
+		};
+		report.results.push(result);
+
+		let (result, _) = timed(Check::Audio, audio_devices()).await;
+		report.results.push(result);
+
+		let (result, _) = timed(Check::Disk, write_test(self.config_dir.clone())).await;
+		report.results.push(result);
+
+		for result in &report.results {
+			if let Err(error) = &result.outcome {
+				log::warn!("Diagnostics: {} failed: {}", result.check.label(), error);
+			}
+		}
+		report
+	}
+
+	async fn reach(&self) -> Result<(String, ()), String> {
+		let status = self.client.ping().await.map_err(|e| e.to_string())?;
+		Ok((format!("HTTP {}", status), ()))
+	}
+
+	async fn search(&self) -> Result<(String, Post), String> {
+		self.limiter.acquire().await;
+		let posts = self
+			.client
+			.search_posts(SEARCH_QUERY, 1, 1)
+			.await
+			.map_err(|e| e.to_string())?;
+		let post = posts
+			.into_iter()
+			.next()
+			.ok_or_else(|| format!("No posts for '{}'", SEARCH_QUERY))?;
+		Ok((format!("Found post #{}", post.id), post))
+	}
+
+	/// The sample of `post` if it has one, else the original, fetched and
+	/// decoded the way the workers do
+	async fn load_image(&self, post: Post) -> Result<(String, ()), String> {
+		let url = post
+			.sample
+			.url
+			.or(post.file.url)
+			.ok_or_else(|| format!("Post #{} has no file URL", post.id))?;
+		let fetched = self
+			.loader
+			.fetch(&url)
+			.await
+			.map_err(|e| format!("{:#}", e))?;
+		let (image, bytes) = match fetched {
+			Fetched::Raw { bytes, .. } => {
+				let loader = self.loader.clone();
+				tokio::task::spawn_blocking(move || {
+					loader.decode(&bytes).map(|image| (image, bytes))
+				})
+				.await
+				.map_err(|e| e.to_string())?
+				.map_err(|e| format!("{:#}", e))?
+			}
+			Fetched::Decoded { image, bytes, .. } => (image, bytes),
+		};
+		Ok((
+			format!(
+				"{}×{}, {} KB",
+				image.size[0],
+				image.size[1],
+				bytes.len().div_ceil(1024)
+			),
+			(),
+		))
+	}
+}
+
+/// Run `check`, timing it. Its value comes back along with the result when it passed.
+async fn timed<T>(
+	check: Check,
+	run: impl Future<Output = Result<(String, T), String>>,
+) -> (CheckResult, Option<T>) {
+	let started = Instant::now();
+	let outcome = run.await;
+	let took = started.elapsed();
+	match outcome {
+		Ok((detail, value)) => (
+			CheckResult {
+				check,
+				outcome: Ok(detail),
+				took,
+			},
+			Some(value),
+		),
+		Err(error) => (
+			CheckResult {
+				check,
+				outcome: Err(error),
+				took,
+			},
+			None,
+		),
+	}
+}
+
+/// Lists input devices the way the beat detector does; some hosts take a while
+async fn audio_devices() -> Result<(String, ()), String> {
+	let names = tokio::task::spawn_blocking(SystemBeat::input_device_names)
+		.await
+		.map_err(|e| e.to_string())?
+		.map_err(|e| e.to_string())?;
+	if names.is_empty() {
+		return Err("No input devices found".to_string());
+	}
+	Ok((
+		format!("{} input device(s): {}", names.len(), names.join(", ")),
+		(),
+	))
+}
+
+/// Write a file where settings are saved, read it back and remove it
+async fn write_test(dir: Option<PathBuf>) -> Result<(String, ()), String> {
+	let dir = dir.ok_or("No settings folder on this system")?;
+	tokio::task::spawn_blocking(move || {
+		std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+		let path = dir.join(WRITE_TEST_FILE);
+		let written = b"sodglumate";
+		std::fs::write(&path, written).map_err(|e| format!("{}: {}", path.display(), e))?;
+		let read = std::fs::read(&path);
+		let removed = std::fs::remove_file(&path);
+		match read {
+			Ok(read) if read == written => {}
+			Ok(_) => return Err(format!("{}: read back different bytes", path.display())),
+			Err(e) => return Err(format!("{}: {}", path.display(), e)),
+		}
+		removed.map_err(|e| format!("{}: {}", path.display(), e))?;
+		Ok((dir.display().to_string(), ()))
+	})
+	.await
+	.map_err(|e| e.to_string())?
+}
+
+/// Runs the self-check when asked from the settings and shows its report in a modal
+pub struct DiagnosticsRunner {
+	diagnostics: Diagnostics,
+	sender: mpsc::Sender<DiagnosticsReport>,
+	receiver: mpsc::Receiver<DiagnosticsReport>,
+	running: bool,
+	egui_ctx: egui::Context,
+}
+
+impl DiagnosticsRunner {
+	pub fn new(diagnostics: Diagnostics, ctx: &egui::Context) -> Self {
+		let (sender, receiver) = mpsc::channel(1);
+		Self {
+			diagnostics,
+			sender,
+			receiver,
+			running: false,
+			egui_ctx: ctx.clone(),
+		}
+	}
+}
+
+impl Component for DiagnosticsRunner {
+	fn handle(&mut self, event: &Event) -> ComponentResponse {
+		let Event::Settings(SettingsEvent::RunDiagnostics) = event else {
+			return ComponentResponse::none();
+		};
+		if self.running {
+			return ComponentResponse::none();
+		}
+		self.running = true;
+		let diagnostics = self.diagnostics.clone();
+		let sender = self.sender.clone();
+		let ctx = self.egui_ctx.clone();
+		tokio::spawn(async move {
+			let report = diagnostics.run().await;
+			let _ = sender.send(report).await;
+			ctx.request_repaint();
+		});
+		ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+			message: "Running diagnostics...".to_string(),
+		}))
+	}
+
+	fn poll(&mut self) -> ComponentResponse {
+		match self.receiver.try_recv() {
+			Ok(report) => {
+				self.running = false;
+				ComponentResponse::emit(Event::View(ViewEvent::ShowModal {
+					kind: ModalKind::Diagnostics { report },
+				}))
+			}
+			Err(_) => ComponentResponse::none(),
+		}
+	}
+
+	fn has_pending(&self) -> bool {
+		self.running
+	}
+
+	fn interests(&self) -> EventMask {
+		EventMask::SETTINGS
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::{FakeClient, FakeLoader};
+
+	fn diagnostics(config_dir: Option<PathBuf>) -> Diagnostics {
+		Diagnostics::new(
+			Arc::new(FakeClient::default().down()),
+			Arc::new(RateLimiter::default()),
+			Arc::new(FakeLoader::new()),
+			config_dir,
+		)
+	}
+
+	#[tokio::test]
+	async fn failed_checks_are_reported_and_the_rest_still_run() {
+		let diagnostics = diagnostics(None);
+		let (reach, _) = timed(Check::Reach, diagnostics.reach()).await;
+		assert_eq!(
+			reach.outcome.as_ref().unwrap_err(),
+			"Request failed with status: 502 Bad Gateway"
+		);
+
+		let (search, post) = timed(Check::Search, diagnostics.search()).await;
+		assert!(post.is_none());
+		assert_eq!(
+			search.outcome.as_ref().unwrap_err(),
+			"Request failed with status: 502 Bad Gateway"
+		);
+
+		let (disk, _) = timed(Check::Disk, write_test(None)).await;
+		assert!(disk.outcome.is_err());
+
+		let report = DiagnosticsReport {
+			results: vec![reach, search, disk],
+		};
+		assert!(!report.passed());
+		let text = report.to_text();
+		assert!(text.contains("[FAIL] Reach e621 ("), "{}", text);
+		assert!(text.contains("[FAIL] Search ("), "{}", text);
+		assert!(
+			text.ends_with("No settings folder on this system"),
+			"{}",
+			text
+		);
+	}
+
+	#[tokio::test]
+	async fn the_write_test_leaves_nothing_behind() {
+		let dir = std::env::temp_dir().join(format!("sodglumate-diagnose-{}", std::process::id()));
+		let (result, _) = timed(Check::Disk, write_test(Some(dir.clone()))).await;
+		assert!(result.outcome.is_ok(), "{:?}", result.outcome);
+		assert!(!dir.join(WRITE_TEST_FILE).exists());
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+}
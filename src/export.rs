@@ -0,0 +1,237 @@
+use crate::config::SavedSettings;
+use anyhow::{Context, bail};
+use serde_json::{Map, Value, json};
+use std::path::Path;
+
+/// Format written by this build. Older exports are upgraded when imported.
+const EXPORT_VERSION: u64 = 1;
+
+/// Session state and settings tied to this machine or account; never exported
+const LOCAL_ONLY: [&str; 5] = [
+	"search_query",
+	"search_page_input",
+	"api_username",
+	"api_key",
+	"selected_audio_device",
+];
+
+/// Write the blacklist and general settings to `path` as one JSON document
+pub fn write(path: &Path, settings: &SavedSettings) -> anyhow::Result<()> {
+	let Value::Object(mut fields) = serde_json::to_value(settings)? else {
+		bail!("Settings didn't serialize to a JSON object");
+	};
+	for key in LOCAL_ONLY {
+		fields.remove(key);
+	}
+	// Kept at the top level; it's what most people move around
+	let blacklist = fields
+		.remove("blacklist")
+		.unwrap_or(Value::Array(Vec::new()));
+	let document = json!({
+		"version": EXPORT_VERSION,
+		"blacklist": blacklist,
+		"settings": fields,
+	});
+	std::fs::write(path, serde_json::to_string_pretty(&document)?)
+		.with_context(|| format!("Couldn't write {}", path.display()))
+}
+
+/// Settings after an import, and what was taken from the file
+pub struct Imported {
+	pub settings: SavedSettings,
+	/// Blacklist tags that weren't in the blacklist before
+	pub new_tags: usize,
+	pub fields: usize,
+	/// Fields this build doesn't know or can't read
+	pub skipped: Vec<String>,
+}
+
+impl Imported {
+	/// E.g. "Imported 14 blacklist tags, 12 settings, skipped 1 unknown field"
+	pub fn summary(&self) -> String {
+		let mut text = format!(
+			"Imported {} blacklist tag{}, {} setting{}",
+			self.new_tags,
+			plural(self.new_tags),
+			self.fields,
+			plural(self.fields)
+		);
+		if !self.skipped.is_empty() {
+			text.push_str(&format!(
+				", skipped {} unknown field{}",
+				self.skipped.len(),
+				plural(self.skipped.len())
+			));
+		}
+		text
+	}
+}
+
+fn plural(count: usize) -> &'static str {
+	if count == 1 { "" } else { "s" }
+}
+
+/// Read an export from `path` and apply it on top of `current`.
+/// With `replace`, the blacklist and settings missing from the file start from defaults.
+pub fn read(path: &Path, current: &SavedSettings, replace: bool) -> anyhow::Result<Imported> {
+	let text = std::fs::read_to_string(path)
+		.with_context(|| format!("Couldn't read {}", path.display()))?;
+	let document: Value = serde_json::from_str(&text).context("Not a JSON file")?;
+	apply(document, current, replace)
+}
+
+fn apply(document: Value, current: &SavedSettings, replace: bool) -> anyhow::Result<Imported> {
+	let Value::Object(mut document) = document else {
+		bail!("Not a settings export");
+	};
+	let Some(version) = document.remove("version").and_then(|v| v.as_u64()) else {
+		bail!("Not a settings export");
+	};
+	if version > EXPORT_VERSION {
+		bail!("Exported by a newer version (format {})", version);
+	}
+	// Version 1 is the first format; upgrades from older ones go here
+
+	let mut skipped = Vec::new();
+	let base = if replace {
+		SavedSettings::default()
+	} else {
+		current.clone()
+	};
+	let Value::Object(mut fields) = serde_json::to_value(&base)? else {
+		bail!("Settings didn't serialize to a JSON object");
+	};
+
+	let known_tags = if replace {
+		Vec::new()
+	} else {
+		current.blacklist.clone()
+	};
+	let mut blacklist = known_tags.clone();
+	match document.remove("blacklist") {
+		Some(Value::Array(tags)) => {
+			for tag in tags {
+				match tag.as_str().map(|t| t.trim().to_lowercase()) {
+					Some(tag) if !tag.is_empty() && !blacklist.contains(&tag) => {
+						blacklist.push(tag)
+					}
+					Some(_) => {}
+					None => skipped.push("blacklist entry".to_string()),
+				}
+			}
+		}
+		Some(_) => skipped.push("blacklist".to_string()),
+		None => {}
+	}
+	let new_tags = blacklist.iter().filter(|t| !known_tags.contains(t)).count();
+
+	let mut imported = 0;
+	match document.remove("settings") {
+		Some(Value::Object(settings)) => {
+			for (key, value) in settings {
+				if key == "blacklist" || LOCAL_ONLY.contains(&key.as_str()) {
+					skipped.push(key);
+				} else if fields.contains_key(&key) && set_field(&mut fields, &key, value) {
+					imported += 1;
+				} else {
+					skipped.push(key);
+				}
+			}
+		}
+		Some(_) => skipped.push("settings".to_string()),
+		None => {}
+	}
+	// Sections added by later formats
+	skipped.extend(document.into_iter().map(|(key, _)| key));
+
+	let mut settings: SavedSettings = serde_json::from_value(Value::Object(fields))?;
+	settings.blacklist = blacklist;
+	// Whatever was replaced, this machine's search, account and audio device stay
+	settings.search_query = current.search_query.clone();
+	settings.search_page_input = current.search_page_input.clone();
+	settings.api_username = current.api_username.clone();
+	settings.api_key = current.api_key.clone();
+	settings.selected_audio_device = current.selected_audio_device.clone();
+	Ok(Imported {
+		settings,
+		new_tags,
+		fields: imported,
+		skipped,
+	})
+}
+
+/// Take `value` for `key` if the settings still read back with it
+fn set_field(fields: &mut Map<String, Value>, key: &str, value: Value) -> bool {
+	let previous = fields.insert(key.to_string(), value);
+	if serde_json::from_value::<SavedSettings>(Value::Object(fields.clone())).is_ok() {
+		return true;
+	}
+	if let Some(previous) = previous {
+		fields.insert(key.to_string(), previous);
+	}
+	false
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn current() -> SavedSettings {
+		SavedSettings {
+			blacklist: vec!["mud".to_string()],
+			api_username: Some("me".to_string()),
+			api_key: Some("secret".to_string()),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn export_round_trips_without_credentials() {
+		let path =
+			std::env::temp_dir().join(format!("sodglumate-export-{}.json", std::process::id()));
+		let mut exported = current();
+		exported.auto_play_delay_secs = 7.0;
+		exported.blacklist.push("scat".to_string());
+		write(&path, &exported).unwrap();
+		let text = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert!(!text.contains("secret"));
+
+		let imported = apply(serde_json::from_str(&text).unwrap(), &current(), false).unwrap();
+		assert_eq!(imported.settings.blacklist, ["mud", "scat"]);
+		assert_eq!(imported.new_tags, 1);
+		assert_eq!(imported.settings.auto_play_delay_secs, 7.0);
+		assert_eq!(imported.settings.api_key.as_deref(), Some("secret"));
+		assert!(imported.skipped.is_empty(), "{:?}", imported.skipped);
+	}
+
+	#[test]
+	fn merges_or_replaces_and_skips_what_it_cant_read() {
+		let document = json!({
+			"version": 1,
+			"blacklist": ["Gore", "mud"],
+			"settings": { "auto_play": true, "pan_mode": 5, "future_option": 1 },
+			"saved_searches": [],
+		});
+
+		let merged = apply(document.clone(), &current(), false).unwrap();
+		assert_eq!(merged.settings.blacklist, ["mud", "gore"]);
+		assert_eq!(merged.new_tags, 1);
+		assert!(merged.settings.auto_play);
+		assert_eq!(merged.fields, 1);
+		let mut skipped = merged.skipped.clone();
+		skipped.sort();
+		assert_eq!(skipped, ["future_option", "pan_mode", "saved_searches"]);
+		assert_eq!(
+			merged.summary(),
+			"Imported 1 blacklist tag, 1 setting, skipped 3 unknown fields"
+		);
+
+		let replaced = apply(document, &current(), true).unwrap();
+		assert_eq!(replaced.settings.blacklist, ["gore", "mud"]);
+		assert_eq!(replaced.new_tags, 2);
+
+		assert!(apply(json!({ "version": 2 }), &current(), false).is_err());
+		assert!(apply(json!({ "blacklist": [] }), &current(), false).is_err());
+	}
+}
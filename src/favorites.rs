@@ -0,0 +1,226 @@
+use crate::api::Post;
+use crate::reactor::{
+	BrowserEvent, ComponentResponse, Event, FavoritesEvent, GatewayEvent, LookupPurpose, ViewEvent,
+};
+use anyhow::Context;
+use indexmap::IndexMap;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "favorites.json";
+
+/// Posts kept on this machine, for browsing without an e621 account.
+/// Whole posts are stored, so the list can be shown without searching for them again.
+pub struct LocalFavorites {
+	/// Oldest first
+	posts: IndexMap<u64, Post>,
+	/// Where changes are written; `None` keeps them in memory, e.g. in tests
+	path: Option<PathBuf>,
+}
+
+impl LocalFavorites {
+	/// Read the favorites saved in the config directory
+	pub fn load() -> Self {
+		let path = crate::config::get_config_dir().map(|dir| dir.join(FILE_NAME));
+		let posts = path
+			.as_deref()
+			.filter(|path| path.exists())
+			.map(|path| match Self::read(path) {
+				Ok(posts) => {
+					log::info!("Loaded {} local favorites", posts.len());
+					posts
+				}
+				Err(e) => {
+					log::warn!("Failed to read {}: {:#}", path.display(), e);
+					Vec::new()
+				}
+			})
+			.unwrap_or_default();
+		Self {
+			posts: posts.into_iter().map(|post| (post.id, post)).collect(),
+			path,
+		}
+	}
+
+	#[cfg(test)]
+	pub fn in_memory() -> Self {
+		Self {
+			posts: IndexMap::new(),
+			path: None,
+		}
+	}
+
+	fn read(path: &Path) -> anyhow::Result<Vec<Post>> {
+		let text = std::fs::read_to_string(path)?;
+		serde_json::from_str(&text).context("Not a favorites list")
+	}
+
+	fn save(&self) {
+		let Some(path) = &self.path else {
+			return;
+		};
+		if let Some(dir) = path.parent()
+			&& let Err(e) = std::fs::create_dir_all(dir)
+		{
+			log::warn!("Failed to create config directory: {}", e);
+			return;
+		}
+		let posts: Vec<&Post> = self.posts.values().collect();
+		match serde_json::to_string(&posts) {
+			Ok(text) => {
+				if let Err(e) = std::fs::write(path, text) {
+					log::warn!("Failed to write {}: {}", path.display(), e);
+				}
+			}
+			Err(e) => log::warn!("Failed to serialize favorites: {}", e),
+		}
+	}
+
+	/// Newest first, as the browser shows them
+	pub fn posts(&self) -> Vec<Post> {
+		self.posts.values().rev().cloned().collect()
+	}
+
+	/// Add `post`, or remove it if it's already there. Returns whether it's now a favorite.
+	pub fn toggle(&mut self, post: &Post) -> bool {
+		let added = if self.posts.shift_remove(&post.id).is_some() {
+			false
+		} else {
+			self.posts.insert(post.id, post.clone());
+			true
+		};
+		self.save();
+		added
+	}
+
+	/// Swap in fresh copies of the posts that are still favorites. Deleted ones are
+	/// kept as they were, so nothing disappears from the list behind the user's back.
+	fn refresh(&mut self, posts: &[Post]) -> usize {
+		let mut refreshed = 0;
+		for post in posts {
+			if let Some(stored) = self.posts.get_mut(&post.id) {
+				*stored = post.clone();
+				refreshed += 1;
+			}
+		}
+		self.save();
+		refreshed
+	}
+
+	/// One post page URL per line, oldest first
+	fn export_urls(&self, path: &Path) -> anyhow::Result<()> {
+		let text: String = self
+			.posts
+			.keys()
+			.map(|id| format!("https://e621.net/posts/{}\n", id))
+			.collect();
+		std::fs::write(path, text).with_context(|| format!("Couldn't write {}", path.display()))
+	}
+
+	pub fn handle(&mut self, event: &Event, current: Option<&Post>) -> ComponentResponse {
+		let message = match event {
+			Event::Favorites(FavoritesEvent::ToggleCurrent) => {
+				let Some(post) = current else {
+					return ComponentResponse::none();
+				};
+				let added = self.toggle(post);
+				log::info!(
+					"Post {} {} local favorites ({} total)",
+					post.id,
+					if added { "added to" } else { "removed from" },
+					self.posts.len()
+				);
+				if added {
+					format!("Added to favorites ({})", self.posts.len())
+				} else {
+					format!("Removed from favorites ({} left)", self.posts.len())
+				}
+			}
+			Event::Favorites(FavoritesEvent::Export { path }) => match self.export_urls(path) {
+				Ok(()) => {
+					log::info!(
+						"Exported {} favorites to {}",
+						self.posts.len(),
+						path.display()
+					);
+					format!("Exported {} favorites", self.posts.len())
+				}
+				Err(e) => {
+					log::warn!("Favorites export failed: {:#}", e);
+					format!("Export failed: {:#}", e)
+				}
+			},
+			Event::Favorites(FavoritesEvent::Refresh) => {
+				if self.posts.is_empty() {
+					"No favorites to refresh".to_string()
+				} else {
+					return ComponentResponse::emit(Event::Gateway(GatewayEvent::FetchByIds {
+						ids: self.posts.keys().copied().collect(),
+						purpose: LookupPurpose::RefreshFavorites,
+					}));
+				}
+			}
+			Event::Browser(BrowserEvent::PostsResolved {
+				purpose: LookupPurpose::RefreshFavorites,
+				posts,
+				missing,
+			}) => {
+				let refreshed = self.refresh(posts);
+				log::info!(
+					"Refreshed {} local favorites, {} no longer on e621: {:?}",
+					refreshed,
+					missing.len(),
+					missing
+				);
+				if missing.is_empty() {
+					format!("Refreshed {} favorites", refreshed)
+				} else {
+					format!(
+						"Refreshed {} favorites; {} no longer on e621",
+						refreshed,
+						missing.len()
+					)
+				}
+			}
+			_ => return ComponentResponse::none(),
+		};
+		ComponentResponse::emit(Event::View(ViewEvent::ShowToast { message }))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn post(id: u64) -> Post {
+		Post {
+			id,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn favorites_survive_a_restart_and_export_as_urls() {
+		let dir = std::env::temp_dir().join(format!("sodglumate-favorites-{}", std::process::id()));
+		let mut favorites = LocalFavorites {
+			posts: IndexMap::new(),
+			path: Some(dir.join(FILE_NAME)),
+		};
+		favorites.toggle(&post(1));
+		favorites.toggle(&post(2));
+		favorites.toggle(&post(3));
+		assert!(!favorites.toggle(&post(2)));
+
+		let posts = LocalFavorites::read(&dir.join(FILE_NAME)).unwrap();
+		assert_eq!(posts.iter().map(|p| p.id).collect::<Vec<_>>(), [1, 3]);
+		assert_eq!(
+			favorites.posts().iter().map(|p| p.id).collect::<Vec<_>>(),
+			[3, 1]
+		);
+
+		let export = dir.join("favorites.txt");
+		favorites.export_urls(&export).unwrap();
+		let text = std::fs::read_to_string(&export).unwrap();
+		std::fs::remove_dir_all(&dir).unwrap();
+		assert_eq!(text, "https://e621.net/posts/1\nhttps://e621.net/posts/3\n");
+	}
+}
@@ -1,64 +1,369 @@
-use crate::api::E621Client;
-use crate::reactor::{BrowserEvent, ComponentResponse, Event, GatewayEvent};
-use std::collections::VecDeque;
-use std::sync::Arc;
-use std::time::Instant;
+use crate::api::{ApiError, BooruClient, Credentials, E621Client, Score};
+use crate::reactor::{
+	BrowserEvent, ComponentResponse, Event, GatewayEvent, LookupPurpose, PageSource, SettingsEvent,
+	ViewEvent,
+};
+use crate::types::{SEARCH_STACK_DEPTH, SearchWatch};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+pub mod query;
+pub mod rate_limit;
+
+use rate_limit::RateLimiter;
+
+/// Wait before retrying a throttled search when the server gave no Retry-After
+const DEFAULT_THROTTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// Throttled retries in a row before the search is reported as failed
+const MAX_THROTTLE_RETRIES: u32 = 5;
+
+/// Most pages a new search may fetch up front
+pub const MAX_FANOUT_PAGES: u32 = 5;
+
+/// e621 serves numbered pages up to this one; deeper results need `id:<N` paging
+pub const MAX_PAGE: u32 = 750;
+
+/// Posts of a set resolved at a time, like a page of search results
+const SET_CHUNK: usize = 50;
+
+/// Gap between fan-out fetches, matching the 2 req/sec budget
+const FANOUT_SPACING: Duration = Duration::from_millis(500);
+
+/// Shortest time between two polls of the same watched search
+pub const MIN_WATCH_INTERVAL_MINS: u32 = 5;
+
+/// Posts a watch poll asks for; more new ones than this show as "20+"
+pub const WATCH_POLL_LIMIT: u32 = 20;
+
 /// Message from async tasks back to the component
 pub enum GatewayMessage {
 	SearchComplete {
 		posts: Vec<crate::api::Post>,
 		page: u32,
+		/// Posts asked for; fewer means this was the last page
+		limit: u32,
 		is_new: bool,
+		/// Index into the compound sub-queries, None for plain searches
+		source: Option<usize>,
+		generation: u64,
 	},
 	SearchError {
+		error: ApiError,
+		page: u32,
+		limit: u32,
+		is_new: bool,
+		generation: u64,
+	},
+	NotesComplete {
+		post_id: u64,
+		notes: Vec<crate::api::Note>,
+	},
+	/// None when the lookup failed
+	PoolNameComplete {
+		pool_id: u64,
+		name: Option<String>,
+	},
+	/// None when the lookup failed
+	TagCountsComplete {
+		names: Vec<String>,
+		tags: Option<Vec<crate::api::Tag>>,
+	},
+	/// Every chunk came back; `posts` are in no particular order yet
+	PostsByIdsComplete {
+		ids: Vec<u64>,
+		purpose: LookupPurpose,
+		posts: Vec<crate::api::Post>,
+	},
+	PostsByIdsFailed {
+		purpose: LookupPurpose,
+		error: ApiError,
+	},
+	SetComplete {
+		set: crate::api::PostSet,
+		generation: u64,
+	},
+	SetFailed {
+		set_id: u64,
+		error: ApiError,
+		generation: u64,
+	},
+	BlacklistComplete {
+		text: String,
+	},
+	BlacklistFailed {
+		message: String,
+	},
+	VoteComplete {
+		post_id: u64,
+		vote: i8,
+		score: Score,
+	},
+	VoteFailed {
+		post_id: u64,
+		previous: i8,
 		message: String,
 	},
+	/// Newest posts of a watched search, newest first; failures aren't reported
+	WatchPollComplete {
+		query: String,
+		ids: Vec<u64>,
+	},
+}
+
+/// One part of a compound search with its own page cursor
+#[derive(Clone)]
+struct SubQuery {
+	query: String,
+	/// Last page fetched (0 = none yet)
+	page: u32,
+	exhausted: bool,
+}
+
+/// Counters the spawned API tasks update, read by the status bar
+#[derive(Default)]
+struct ApiStats {
+	/// Calls spawned but not answered, including those queued on the limiter
+	pending: AtomicUsize,
+	/// Round trip of the last answered call, not counting time in the queue
+	last_latency: Mutex<Option<Duration>>,
+}
+
+impl ApiStats {
+	/// Run an API call that was counted as pending when it was spawned
+	async fn track<T>(&self, call: impl Future<Output = T>) -> T {
+		let started = Instant::now();
+		let result = call.await;
+		*self.last_latency.lock().unwrap() = Some(started.elapsed());
+		self.pending.fetch_sub(1, Ordering::Relaxed);
+		result
+	}
+}
+
+/// What we know about a pool's name, looked up once per session
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolName {
+	Loading,
+	Named(String),
+	/// The lookup failed; shown by its ID
+	Unknown,
+}
+
+/// Rough size of the current search's results, for the hint by the page field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResultEstimate {
+	/// The first page came back short, so this is every post there is
+	Exact(usize),
+	/// Post count of the query's rarest tag; other filters can only leave fewer
+	AtMost(u64),
+}
+
+impl fmt::Display for ResultEstimate {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match *self {
+			ResultEstimate::Exact(1) => write!(f, "only 1 post"),
+			ResultEstimate::Exact(count) => write!(f, "only {} posts", count),
+			ResultEstimate::AtMost(count) if count >= 1_000_000 => {
+				write!(f, "~{:.1}M posts", count as f64 / 1_000_000.0)
+			}
+			ResultEstimate::AtMost(count) if count >= 1_000 => {
+				write!(f, "~{:.1}k posts", count as f64 / 1_000.0)
+			}
+			ResultEstimate::AtMost(count) => write!(f, "~{} posts", count),
+		}
+	}
+}
+
+/// Pages of a new search being fetched up front
+struct Fanout {
+	/// Pages received so far, including the first
+	loaded: u32,
+	total: u32,
+}
+
+/// The set being browsed; its posts are looked up by ID a chunk at a time
+#[derive(Clone)]
+struct SetCursor {
+	id: u64,
+	/// None until the set itself has loaded
+	name: Option<String>,
+	post_ids: Vec<u64>,
+	/// IDs handed to the lookup so far
+	resolved: usize,
+}
+
+/// Query state saved by "More like this" so going back needs no refetch
+struct SearchSnapshot {
+	query: String,
+	page: u32,
+	sub_queries: Vec<SubQuery>,
+	next_sub_query: usize,
+	random_seed: Option<u64>,
+	set: Option<SetCursor>,
 }
 
 pub struct BooruGateway {
-	client: Arc<E621Client>,
+	client: Arc<dyn BooruClient>,
 	sender: mpsc::Sender<GatewayMessage>,
 	receiver: mpsc::Receiver<GatewayMessage>,
 	current_query: String,
 	current_page: u32,
 	fetch_pending: bool,
-	last_request_times: VecDeque<Instant>,
+	/// The plain query came back short; no next pages are fetched until it changes
+	exhausted: bool,
+	/// Every API call spawned here waits on this first
+	limiter: Arc<RateLimiter>,
+	stats: Arc<ApiStats>,
+	/// Populated only when the current query is compound (`a || b`)
+	sub_queries: Vec<SubQuery>,
+	next_sub_query: usize,
+	/// Bumped on every new search so results of a replaced query are dropped
+	search_generation: u64,
+	/// Seed of the current `order:random` search, sent with every page of it
+	random_seed: Option<u64>,
+	/// Most recent last, at most `SEARCH_STACK_DEPTH` entries
+	search_stack: Vec<SearchSnapshot>,
+	/// False after a network failure until a search gets through again
+	online: bool,
+	/// Throttled retries since results last got through
+	throttle_retries: u32,
+	/// Pages a new plain search fetches up front (1 = just the first)
+	fanout_pages: u32,
+	/// Set while the pages of the current search's fan-out are still arriving
+	fanout: Option<Fanout>,
+	/// Pool names requested so far, never dropped
+	pool_names: HashMap<u64, PoolName>,
+	/// Post counts of tags looked up this session; None while the lookup is
+	/// out, or after it failed (not retried)
+	tag_counts: HashMap<String, Option<u64>>,
+	/// Size of the current search, when its first page was all there is
+	first_page_total: Option<usize>,
+	/// Set while the results are an e621 set rather than a search
+	set: Option<SetCursor>,
+	/// Every search goes out as `rating:s`, whatever rating terms it was typed with
+	safe_mode: bool,
+	/// Searches polled in the background for new posts
+	watches: Vec<SearchWatch>,
+	watch_interval: Duration,
+	/// Bumped whenever the watches or their interval change, so older poll chains stop
+	watch_generation: u64,
 }
 
 impl BooruGateway {
-	pub fn new() -> Self {
+	pub fn new(credentials: Option<Credentials>) -> Self {
+		Self::new_with_client(Box::new(E621Client::new(credentials)))
+	}
+
+	/// Construct on top of any client, e.g. a fake serving canned posts
+	pub fn new_with_client(client: Box<dyn BooruClient>) -> Self {
 		log::info!("Initializing Gateway with rate limiting (2 req/sec)");
 		let (sender, receiver) = mpsc::channel(100);
 		Self {
-			client: Arc::new(E621Client::new()),
+			client: Arc::from(client),
 			sender,
 			receiver,
 			current_query: String::new(),
 			current_page: 1,
 			fetch_pending: false,
-			last_request_times: VecDeque::new(),
+			exhausted: false,
+			limiter: Arc::new(RateLimiter::default()),
+			stats: Arc::new(ApiStats::default()),
+			sub_queries: Vec::new(),
+			next_sub_query: 0,
+			search_generation: 0,
+			random_seed: None,
+			search_stack: Vec::new(),
+			online: true,
+			throttle_retries: 0,
+			fanout_pages: 1,
+			fanout: None,
+			pool_names: HashMap::new(),
+			tag_counts: HashMap::new(),
+			first_page_total: None,
+			set: None,
+			safe_mode: false,
+			watches: Vec::new(),
+			watch_interval: Duration::from_secs(60 * 15),
+			watch_generation: 0,
 		}
 	}
 
-	/// Check if we can make an API request (hard limit: 2 req/sec)
-	fn can_request(&self) -> bool {
-		if self.last_request_times.len() < 2 {
-			return true;
+	/// Fetch this many pages up front on new plain searches
+	pub fn with_fanout_pages(mut self, pages: u32) -> Self {
+		self.fanout_pages = pages.clamp(1, MAX_FANOUT_PAGES);
+		self
+	}
+
+	pub fn with_safe_mode(mut self, enabled: bool) -> Self {
+		self.safe_mode = enabled;
+		self
+	}
+
+	/// Poll these searches every `interval_mins` once `schedule_watches` starts them
+	pub fn with_watches(mut self, watches: Vec<SearchWatch>, interval_mins: u32) -> Self {
+		self.watches = watches;
+		self.set_watch_interval(interval_mins);
+		self
+	}
+
+	fn set_watch_interval(&mut self, minutes: u32) {
+		let minutes = minutes.max(MIN_WATCH_INTERVAL_MINS);
+		self.watch_interval = Duration::from_secs(60 * u64::from(minutes));
+	}
+
+	/// Start a new chain of polls, one per watch, spread evenly over the interval
+	/// so they don't all reach the rate limiter at once
+	pub fn schedule_watches(&mut self) -> ComponentResponse {
+		self.watch_generation += 1;
+		let count = self.watches.len() as u32;
+		let mut response = ComponentResponse::none();
+		for (index, watch) in self.watches.iter().enumerate() {
+			response.scheduled.push((
+				Event::Gateway(GatewayEvent::PollWatch {
+					query: watch.query.clone(),
+					generation: self.watch_generation,
+				}),
+				self.watch_interval * (index as u32 + 1) / count,
+			));
 		}
-		if let Some(oldest) = self.last_request_times.front() {
-			oldest.elapsed().as_secs_f32() >= 1.0
+		response
+	}
+
+	/// The current results came from `LocalResults`; there are no pages to fetch
+	fn is_local(&self) -> bool {
+		self.current_query == query::LOCAL_FAVORITES
+	}
+
+	fn is_compound(&self) -> bool {
+		!self.sub_queries.is_empty()
+	}
+
+	/// Whether every part of the current search has come back short
+	fn is_exhausted(&self) -> bool {
+		if self.is_compound() {
+			self.sub_queries.iter().all(|sub| sub.exhausted)
 		} else {
-			true
+			self.exhausted
 		}
 	}
 
-	fn record_request(&mut self) {
-		self.last_request_times.push_back(Instant::now());
-		if self.last_request_times.len() > 2 {
-			self.last_request_times.pop_front();
-		}
+	/// Next sub-query in round-robin order that still has pages
+	fn next_compound_source(&mut self) -> Option<usize> {
+		let count = self.sub_queries.len();
+		let index = (0..count)
+			.map(|offset| (self.next_sub_query + offset) % count)
+			.find(|&i| !self.sub_queries[i].exhausted)?;
+		self.next_sub_query = (index + 1) % count;
+		Some(index)
+	}
+
+	/// Whether API results are waiting for `poll`
+	pub fn has_pending(&self) -> bool {
+		!self.receiver.is_empty()
 	}
 
 	pub fn poll(&mut self) -> ComponentResponse {
@@ -68,8 +373,15 @@ impl BooruGateway {
 				GatewayMessage::SearchComplete {
 					posts,
 					page,
+					limit,
 					is_new,
+					source,
+					generation,
 				} => {
+					if generation != self.search_generation {
+						log::debug!("Dropping results of a replaced search: page={}", page);
+						continue;
+					}
 					log::info!(
 						"Search complete: page={}, posts={}, is_new={}",
 						page,
@@ -77,17 +389,266 @@ impl BooruGateway {
 						is_new
 					);
 					self.fetch_pending = false;
-					self.current_page = page;
+					self.throttle_retries = 0;
+					if let Some(fanout) = &mut self.fanout {
+						// Fan-out pages may land out of order
+						self.current_page = self.current_page.max(page);
+						fanout.loaded += 1;
+						responses.push(Event::Gateway(GatewayEvent::PageProgress {
+							loaded: fanout.loaded,
+							total: fanout.total,
+						}));
+						if fanout.loaded >= fanout.total {
+							self.fanout = None;
+						}
+					} else {
+						self.current_page = page;
+					}
+					if !self.online {
+						log::info!("Connection restored");
+						self.online = true;
+						responses.push(Event::Gateway(GatewayEvent::ConnectionState {
+							online: true,
+						}));
+					}
+					let was_exhausted = self.is_exhausted();
+					let short = posts.len() < limit as usize;
+					if is_new && !self.is_compound() {
+						if short && page == 1 {
+							self.first_page_total = Some(posts.len());
+						} else {
+							self.spawn_tag_counts();
+						}
+					}
+					let source = match source {
+						Some(index) => {
+							let count = self.sub_queries.len();
+							self.sub_queries.get_mut(index).map(|sub| {
+								sub.page = page;
+								sub.exhausted = short;
+								PageSource {
+									index,
+									count,
+									query: sub.query.clone(),
+									exhausted: short,
+								}
+							})
+						}
+						None => {
+							// Fan-out pages after the last one are short too; one is enough
+							self.exhausted |= short;
+							None
+						}
+					};
 					responses.push(Event::Browser(BrowserEvent::PostsReceived {
 						posts,
 						page,
 						is_new,
+						source,
 					}));
+					if self.is_exhausted() && !was_exhausted {
+						log::info!(
+							"End of results for '{}' at page {}",
+							self.current_query,
+							page
+						);
+						responses.push(Event::Browser(BrowserEvent::EndOfResults));
+					}
+				}
+				GatewayMessage::SearchError {
+					error,
+					page,
+					limit,
+					is_new,
+					generation,
+				} => {
+					if generation != self.search_generation {
+						continue;
+					}
+					log::error!("Search error: {}", error);
+					self.fetch_pending = false;
+					if self.fanout.take().is_some() {
+						// Give up on the rest; paging carries on from the last page received
+						log::warn!("Abandoning fan-out after page {} failed", page);
+						self.search_generation += 1;
+						responses.push(Event::Gateway(GatewayEvent::PageProgress {
+							loaded: 0,
+							total: 0,
+						}));
+					}
+					responses.extend(self.search_failed(error, page, limit, is_new));
+				}
+				GatewayMessage::NotesComplete { post_id, notes } => {
+					log::info!("Notes complete: post={}, notes={}", post_id, notes.len());
+					responses.push(Event::View(ViewEvent::NotesReceived { post_id, notes }));
+				}
+				GatewayMessage::PoolNameComplete { pool_id, name } => {
+					let name = name.map_or(PoolName::Unknown, PoolName::Named);
+					self.pool_names.insert(pool_id, name);
 				}
-				GatewayMessage::SearchError { message } => {
-					log::error!("Search error: {}", message);
+				GatewayMessage::TagCountsComplete { names, tags } => {
+					let Some(tags) = tags else {
+						continue;
+					};
+					for name in names {
+						// Names that aren't tags have no posts
+						let count = tags
+							.iter()
+							.find(|tag| tag.name == name)
+							.map_or(0, |tag| tag.post_count);
+						self.tag_counts.insert(name, Some(count));
+					}
+				}
+				GatewayMessage::PostsByIdsComplete {
+					ids,
+					purpose: LookupPurpose::SetPage { generation, offset },
+					posts,
+				} => {
+					if generation != self.search_generation {
+						continue;
+					}
+					responses.extend(self.set_page_received(&ids, posts, offset));
+				}
+				GatewayMessage::PostsByIdsFailed {
+					purpose: LookupPurpose::SetPage { generation, offset },
+					error,
+				} => {
+					if generation != self.search_generation {
+						continue;
+					}
+					log::error!("Set chunk at {} failed: {}", offset, error);
 					self.fetch_pending = false;
-					responses.push(Event::Gateway(GatewayEvent::SearchError { message }));
+					if let Some(set) = &mut self.set {
+						// Asked for again by the next FetchNextPage
+						set.resolved = offset;
+					}
+					responses.extend(self.api_failed(error, GatewayEvent::FetchNextPage));
+				}
+				GatewayMessage::SetComplete { set, generation } => {
+					if generation != self.search_generation {
+						continue;
+					}
+					let Some(cursor) = &mut self.set else {
+						continue;
+					};
+					log::info!(
+						"Set {} '{}': {} posts",
+						set.id,
+						set.name,
+						set.post_ids.len()
+					);
+					cursor.name = Some(set.name);
+					cursor.post_ids = set.post_ids;
+					self.first_page_total = Some(cursor.post_ids.len());
+					self.fetch_pending = false;
+					if cursor.post_ids.is_empty() {
+						self.exhausted = true;
+						responses.push(Event::Browser(BrowserEvent::PostsReceived {
+							posts: Vec::new(),
+							page: 1,
+							is_new: true,
+							source: None,
+						}));
+						responses.push(Event::Browser(BrowserEvent::EndOfResults));
+					} else {
+						self.resolve_set_chunk();
+					}
+				}
+				GatewayMessage::SetFailed {
+					set_id,
+					error,
+					generation,
+				} => {
+					if generation != self.search_generation {
+						continue;
+					}
+					log::error!("Set {} failed to load: {}", set_id, error);
+					self.fetch_pending = false;
+					responses.extend(self.api_failed(error, GatewayEvent::OpenSet { id: set_id }));
+				}
+				GatewayMessage::PostsByIdsComplete {
+					ids,
+					purpose,
+					posts,
+				} => {
+					let (posts, missing) = crate::api::order_by_ids(&ids, posts);
+					log::info!(
+						"Resolved {} posts by ID for {:?}, {} missing",
+						posts.len(),
+						purpose,
+						missing.len()
+					);
+					responses.push(Event::Browser(BrowserEvent::PostsResolved {
+						purpose,
+						posts,
+						missing,
+					}));
+				}
+				GatewayMessage::PostsByIdsFailed { purpose, error } => {
+					log::warn!("Post lookup by ID for {:?} failed: {}", purpose, error);
+					responses.push(Event::View(ViewEvent::ShowToast {
+						message: format!("Couldn't fetch posts: {}", error),
+					}));
+				}
+				GatewayMessage::BlacklistComplete { text } => {
+					log::info!("Account blacklist received: {} lines", text.lines().count());
+					responses.push(Event::Browser(BrowserEvent::AccountBlacklistReceived {
+						text,
+					}));
+				}
+				GatewayMessage::BlacklistFailed { message } => {
+					log::warn!("Account blacklist fetch failed: {}", message);
+					responses.push(Event::View(ViewEvent::ShowToast {
+						message: format!("Couldn't load your account blacklist: {}", message),
+					}));
+				}
+				GatewayMessage::VoteComplete {
+					post_id,
+					vote,
+					score,
+				} => {
+					log::info!("Vote complete: post={}, vote={}", post_id, vote);
+					responses.push(Event::Browser(BrowserEvent::VoteUpdated {
+						post_id,
+						vote,
+						score: Some(score),
+					}));
+				}
+				GatewayMessage::WatchPollComplete { query, ids } => {
+					let Some(watch) = self.watches.iter_mut().find(|watch| watch.query == query)
+					else {
+						continue;
+					};
+					let Some(newest) = ids.iter().copied().max() else {
+						continue;
+					};
+					// The first poll only sets the baseline
+					let count = watch
+						.last_seen
+						.map_or(0, |seen| ids.iter().filter(|id| **id > seen).count());
+					watch.last_seen = Some(watch.last_seen.map_or(newest, |seen| seen.max(newest)));
+					log::info!("Watch '{}': {} new post(s)", query, count);
+					if count > 0 {
+						responses.push(Event::Gateway(GatewayEvent::NewPostsAvailable {
+							query,
+							count,
+						}));
+					}
+				}
+				GatewayMessage::VoteFailed {
+					post_id,
+					previous,
+					message,
+				} => {
+					log::error!("Vote failed: post={}, error={}", post_id, message);
+					responses.push(Event::Browser(BrowserEvent::VoteUpdated {
+						post_id,
+						vote: previous,
+						score: None,
+					}));
+					responses.push(Event::View(ViewEvent::ShowToast {
+						message: format!("Vote failed: {}", message),
+					}));
 				}
 			}
 		}
@@ -99,50 +660,384 @@ impl BooruGateway {
 		}
 	}
 
+	/// Turn a failed search into events: throttling is retried, everything else is reported
+	fn search_failed(
+		&mut self,
+		error: ApiError,
+		page: u32,
+		limit: u32,
+		is_new: bool,
+	) -> Vec<Event> {
+		let retry = if is_new {
+			GatewayEvent::SearchRequest {
+				query: self.current_query.clone(),
+				page,
+				limit,
+			}
+		} else {
+			GatewayEvent::FetchNextPage
+		};
+		self.api_failed(error, retry)
+	}
+
+	/// Turn a failed request for results into events: throttling sends `retry` again
+	/// later, up to `MAX_THROTTLE_RETRIES` times, everything else is reported
+	fn api_failed(&mut self, error: ApiError, retry: GatewayEvent) -> Vec<Event> {
+		let mut events = Vec::new();
+		match error {
+			ApiError::RateLimited { retry_after }
+				if self.throttle_retries < MAX_THROTTLE_RETRIES =>
+			{
+				self.throttle_retries += 1;
+				let retry_after = retry_after.unwrap_or(DEFAULT_THROTTLE_DELAY);
+				log::warn!("Throttled by the API, retrying in {:?}", retry_after);
+				events.push(Event::Gateway(GatewayEvent::Throttled {
+					retry_after,
+					retry: Box::new(Event::Gateway(retry)),
+				}));
+			}
+			error => {
+				if matches!(error, ApiError::RateLimited { .. }) {
+					log::warn!(
+						"Still throttled after {} retries, giving up",
+						self.throttle_retries
+					);
+					self.throttle_retries = 0;
+				}
+				if matches!(error, ApiError::Network(_)) && self.online {
+					log::warn!("Connection lost");
+					self.online = false;
+					events.push(Event::Gateway(GatewayEvent::ConnectionState {
+						online: false,
+					}));
+				}
+				events.push(Event::Gateway(GatewayEvent::SearchError {
+					message: error.to_string(),
+					category: error.category(),
+				}));
+			}
+		}
+		events
+	}
+
 	pub fn handle(&mut self, event: &Event) -> ComponentResponse {
 		match event {
 			Event::Gateway(GatewayEvent::SearchRequest { query, page, limit }) => {
-				if !self.can_request() {
-					log::warn!("API rate limit exceeded, dropping search request");
-					return ComponentResponse::none();
-				}
 				log::info!(
 					"SearchRequest: query='{}', page={}, limit={}",
 					query,
 					page,
 					limit
 				);
-				self.record_request();
 				self.current_query = query.clone();
 				self.current_page = *page;
 				self.fetch_pending = true;
-				self.spawn_search(query.clone(), *page, *limit, true);
+				self.exhausted = false;
+				self.search_generation += 1;
+				self.first_page_total = None;
+				self.set = None;
+				// A fresh shuffle for every new search, unless the query names one
+				self.random_seed = query::is_random(query).then(|| {
+					query::seed(query).unwrap_or_else(|| u64::from(rand::random::<u32>()))
+				});
+				let had_fanout = self.fanout.take().is_some();
+
+				let parts = query::split_compound(query);
+				if parts.len() > 1 {
+					log::info!("Compound search with {} sub-queries", parts.len());
+					self.sub_queries = parts
+						.into_iter()
+						.map(|query| SubQuery {
+							query,
+							page: 0,
+							exhausted: false,
+						})
+						.collect();
+					self.next_sub_query = 1;
+					let first = self.sub_queries[0].query.clone();
+					self.spawn_search(first, *page, *limit, true, Some(0));
+				} else {
+					self.sub_queries.clear();
+					self.next_sub_query = 0;
+					self.spawn_search(query.clone(), *page, *limit, true, None);
+					if self.fanout_pages > 1 {
+						return self.start_fanout(*page, *limit);
+					}
+				}
+				if had_fanout {
+					return ComponentResponse::emit(Event::Gateway(GatewayEvent::PageProgress {
+						loaded: 0,
+						total: 0,
+					}));
+				}
+			}
+			Event::Gateway(GatewayEvent::LocalResults { query, posts }) => {
+				log::info!("Local results for '{}': {} posts", query, posts.len());
+				self.current_query = query.clone();
+				self.current_page = 1;
+				self.fetch_pending = false;
+				self.exhausted = true;
+				// Whatever is still in flight belongs to the search being replaced
+				self.search_generation += 1;
+				self.first_page_total = Some(posts.len());
+				self.random_seed = None;
+				self.sub_queries.clear();
+				self.next_sub_query = 0;
+				self.set = None;
+				let mut events = Vec::new();
+				if self.fanout.take().is_some() {
+					events.push(Event::Gateway(GatewayEvent::PageProgress {
+						loaded: 0,
+						total: 0,
+					}));
+				}
+				events.push(Event::Browser(BrowserEvent::PostsReceived {
+					posts: posts.clone(),
+					page: 1,
+					is_new: true,
+					source: None,
+				}));
+				events.push(Event::Browser(BrowserEvent::EndOfResults));
+				return ComponentResponse::emit_many(events);
+			}
+			Event::Gateway(GatewayEvent::FetchFanoutPage {
+				page,
+				limit,
+				generation,
+			}) => {
+				if *generation != self.search_generation {
+					log::debug!("Dropping fan-out fetch of a replaced search: page={}", page);
+					return ComponentResponse::none();
+				}
+				log::info!(
+					"FetchFanoutPage: query='{}', page={}",
+					self.current_query,
+					page
+				);
+				self.fetch_pending = true;
+				self.spawn_search(self.current_query.clone(), *page, *limit, false, None);
+			}
+			Event::Gateway(GatewayEvent::OpenSet { id }) => {
+				log::info!("Opening set {}", id);
+				self.current_query = format!("{}{}", query::SET_PREFIX, id);
+				self.current_page = 1;
+				self.fetch_pending = true;
+				self.exhausted = false;
+				self.search_generation += 1;
+				self.first_page_total = None;
+				self.random_seed = None;
+				self.sub_queries.clear();
+				self.next_sub_query = 0;
+				self.set = Some(SetCursor {
+					id: *id,
+					name: None,
+					post_ids: Vec::new(),
+					resolved: 0,
+				});
+				self.spawn_set(*id);
+				if self.fanout.take().is_some() {
+					return ComponentResponse::emit(Event::Gateway(GatewayEvent::PageProgress {
+						loaded: 0,
+						total: 0,
+					}));
+				}
+			}
+			Event::Gateway(GatewayEvent::FetchNextPage) if self.set.is_some() => {
+				if self.fetch_pending {
+					log::debug!("FetchNextPage ignored: fetch already pending");
+				} else if self.exhausted {
+					log::debug!("FetchNextPage ignored: end of the set");
+				} else if let Some(set) = self.set.as_ref().filter(|set| set.name.is_none()) {
+					// The set itself failed to load; try it again
+					let id = set.id;
+					self.fetch_pending = true;
+					self.spawn_set(id);
+				} else {
+					self.resolve_set_chunk();
+				}
 			}
 			Event::Gateway(GatewayEvent::FetchNextPage) => {
-				if !self.can_request() {
-					log::debug!("API rate limit: delaying FetchNextPage");
+				if self.fanout.is_some() {
+					log::debug!("FetchNextPage ignored: fan-out still fetching");
 					return ComponentResponse::none();
 				}
-				if !self.fetch_pending && !self.current_query.is_empty() {
+				if !self.fetch_pending && self.is_compound() {
+					let Some(index) = self.next_compound_source() else {
+						log::debug!("FetchNextPage ignored: all sub-queries exhausted");
+						return ComponentResponse::none();
+					};
+					let sub = &self.sub_queries[index];
+					let (sub_query, next_page) = (sub.query.clone(), sub.page + 1);
+					log::info!(
+						"FetchNextPage: sub-query {} '{}', page={}",
+						index,
+						sub_query,
+						next_page
+					);
+					self.fetch_pending = true;
+					self.spawn_search(sub_query, next_page, 50, false, Some(index));
+				} else if !self.fetch_pending && (self.exhausted || self.is_local()) {
+					log::debug!("FetchNextPage ignored: end of results");
+				} else if !self.fetch_pending && !self.current_query.is_empty() {
 					let next_page = self.current_page + 1;
 					log::info!(
 						"FetchNextPage: query='{}', page={}",
 						self.current_query,
 						next_page
 					);
-					self.record_request();
 					self.fetch_pending = true;
-					self.spawn_search(self.current_query.clone(), next_page, 50, false);
+					self.spawn_search(self.current_query.clone(), next_page, 50, false, None);
 				} else if self.fetch_pending {
 					log::debug!("FetchNextPage ignored: fetch already pending");
 				}
 			}
+			Event::Gateway(GatewayEvent::ClearEndOfResults) => {
+				log::info!("Checking '{}' for more results", self.current_query);
+				self.exhausted = false;
+				for sub in &mut self.sub_queries {
+					sub.exhausted = false;
+				}
+				return ComponentResponse::emit(Event::Gateway(GatewayEvent::FetchNextPage));
+			}
+			Event::Gateway(GatewayEvent::Throttled { retry_after, retry }) => {
+				return ComponentResponse::schedule((**retry).clone(), *retry_after);
+			}
+			Event::Gateway(GatewayEvent::SetFanoutPages { pages }) => {
+				self.fanout_pages = (*pages).clamp(1, MAX_FANOUT_PAGES);
+				log::info!(
+					"Fetching {} page(s) up front on new searches",
+					self.fanout_pages
+				);
+			}
+			Event::Gateway(GatewayEvent::FetchNotes { post_id }) => {
+				self.spawn_notes(*post_id);
+			}
+			Event::Gateway(GatewayEvent::FetchPoolNames { pool_ids }) => {
+				for pool_id in pool_ids {
+					if !self.pool_names.contains_key(pool_id) {
+						self.pool_names.insert(*pool_id, PoolName::Loading);
+						self.spawn_pool_name(*pool_id);
+					}
+				}
+			}
+			Event::Gateway(GatewayEvent::FetchByIds { ids, purpose }) => {
+				self.spawn_posts_by_ids(ids.clone(), purpose.clone());
+			}
+			Event::Gateway(GatewayEvent::FetchAccountBlacklist)
+				if self.client.credentials().is_some() =>
+			{
+				self.spawn_blacklist();
+			}
+			Event::Gateway(GatewayEvent::Vote {
+				post_id,
+				score,
+				previous,
+			}) => {
+				if self.client.credentials().is_none() {
+					return ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+						message: "Voting requires api_username and api_key in settings.toml"
+							.to_string(),
+					}));
+				}
+				self.spawn_vote(*post_id, *score, *previous);
+
+				// Optimistically show the result; voting the same way again unvotes
+				let vote = if previous == score { 0 } else { *score };
+				return ComponentResponse::emit(Event::Browser(BrowserEvent::VoteUpdated {
+					post_id: *post_id,
+					vote,
+					score: None,
+				}));
+			}
+			Event::Settings(SettingsEvent::SafeModeChanged { enabled }) => {
+				// Takes effect from the next request; the browser drops what's already loaded
+				self.safe_mode = *enabled;
+			}
+			Event::Gateway(GatewayEvent::WatchSearch { query, watched }) => {
+				let query = query.trim();
+				let known = self.watches.iter().any(|watch| watch.query == query);
+				if query.is_empty() || known == *watched {
+					return ComponentResponse::none();
+				}
+				let message = if *watched {
+					log::info!("Watching '{}' for new posts", query);
+					self.watches.push(SearchWatch {
+						query: query.to_string(),
+						last_seen: None,
+					});
+					format!("Watching '{}' for new posts", query)
+				} else {
+					log::info!("No longer watching '{}'", query);
+					self.watches.retain(|watch| watch.query != query);
+					format!("No longer watching '{}'", query)
+				};
+				let mut response = self.schedule_watches();
+				response
+					.events
+					.push(Event::View(ViewEvent::ShowToast { message }));
+				return response;
+			}
+			Event::Gateway(GatewayEvent::SetWatches { watches }) => {
+				self.watches = watches.clone();
+				return self.schedule_watches();
+			}
+			Event::Gateway(GatewayEvent::SetWatchInterval { minutes }) => {
+				self.set_watch_interval(*minutes);
+				log::info!("Polling watched searches every {:?}", self.watch_interval);
+				return self.schedule_watches();
+			}
+			Event::Gateway(GatewayEvent::PollWatch { query, generation }) => {
+				if *generation != self.watch_generation {
+					return ComponentResponse::none();
+				}
+				let Some(watch) = self.watches.iter().find(|watch| watch.query == *query) else {
+					return ComponentResponse::none();
+				};
+				self.spawn_watch_poll(watch.query.clone());
+				return ComponentResponse::schedule(event.clone(), self.watch_interval);
+			}
 			_ => {}
 		}
 		ComponentResponse::none()
 	}
 
-	fn spawn_search(&self, mut query: String, page: u32, limit: u32, is_new: bool) {
+	/// Schedule the pages after `first_page` of a new search, one per `FANOUT_SPACING`
+	fn start_fanout(&mut self, first_page: u32, limit: u32) -> ComponentResponse {
+		let total = self.fanout_pages;
+		log::info!("Fetching {} pages up front", total);
+		self.fanout = Some(Fanout { loaded: 0, total });
+
+		let mut response = ComponentResponse::emit(Event::Gateway(GatewayEvent::PageProgress {
+			loaded: 0,
+			total,
+		}));
+		for offset in 1..total {
+			response.scheduled.push((
+				Event::Gateway(GatewayEvent::FetchFanoutPage {
+					page: first_page + offset,
+					limit,
+					generation: self.search_generation,
+				}),
+				FANOUT_SPACING * offset,
+			));
+		}
+		response
+	}
+
+	fn spawn_search(
+		&self,
+		mut query: String,
+		page: u32,
+		limit: u32,
+		is_new: bool,
+		source: Option<usize>,
+	) {
+		if let Some(seed) = self.random_seed {
+			query = query::with_seed(&query, seed);
+		}
+		if self.safe_mode {
+			query = query::safe_only(&query);
+		}
 		// TODO: This is a hack
 		if !query.contains("-video") {
 			query.push_str(" -video");
@@ -155,10 +1050,15 @@ impl BooruGateway {
 		);
 		let client = self.client.clone();
 		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		let generation = self.search_generation;
+		stats.pending.fetch_add(1, Ordering::Relaxed);
 
 		tokio::spawn(async move {
+			limiter.acquire().await;
 			log::debug!("API request started: page={}", page);
-			match client.search_posts(&query, limit, page).await {
+			match stats.track(client.search_posts(&query, limit, page)).await {
 				Ok(posts) => {
 					log::info!(
 						"API response: page={}, received {} posts",
@@ -169,7 +1069,10 @@ impl BooruGateway {
 						.send(GatewayMessage::SearchComplete {
 							posts,
 							page,
+							limit,
 							is_new,
+							source,
+							generation,
 						})
 						.await;
 				}
@@ -177,7 +1080,11 @@ impl BooruGateway {
 					log::error!("API error: page={}, error={}", page, e);
 					let _ = sender
 						.send(GatewayMessage::SearchError {
-							message: e.to_string(),
+							error: e,
+							page,
+							limit,
+							is_new,
+							generation,
 						})
 						.await;
 				}
@@ -185,13 +1092,420 @@ impl BooruGateway {
 		});
 	}
 
+	fn spawn_notes(&self, post_id: u64) {
+		let client = self.client.clone();
+		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		stats.pending.fetch_add(1, Ordering::Relaxed);
+
+		tokio::spawn(async move {
+			limiter.acquire().await;
+			match stats.track(client.get_notes(post_id)).await {
+				Ok(notes) => {
+					let _ = sender
+						.send(GatewayMessage::NotesComplete { post_id, notes })
+						.await;
+				}
+				Err(e) => {
+					// Notes are optional decoration, don't surface as a search error
+					log::warn!("Notes fetch failed: post={}, error={}", post_id, e);
+				}
+			}
+		});
+	}
+
+	fn spawn_pool_name(&self, pool_id: u64) {
+		let client = self.client.clone();
+		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		stats.pending.fetch_add(1, Ordering::Relaxed);
+
+		tokio::spawn(async move {
+			limiter.acquire().await;
+			let name = match stats.track(client.get_pool_name(pool_id)).await {
+				Ok(name) => Some(name),
+				Err(e) => {
+					// Not retried; the pool is shown by its ID instead
+					log::warn!("Pool name fetch failed: pool={}, error={}", pool_id, e);
+					None
+				}
+			};
+			let _ = sender
+				.send(GatewayMessage::PoolNameComplete { pool_id, name })
+				.await;
+		});
+	}
+
+	/// Look up the post counts of the current query's plain tags not seen this
+	/// session, behind every other API call
+	fn spawn_tag_counts(&mut self) {
+		let names: Vec<String> = query::plain_tags(&self.current_query)
+			.into_iter()
+			.filter(|name| !self.tag_counts.contains_key(name))
+			.collect();
+		if names.is_empty() {
+			return;
+		}
+		for name in &names {
+			self.tag_counts.insert(name.clone(), None);
+		}
+		let client = self.client.clone();
+		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		stats.pending.fetch_add(1, Ordering::Relaxed);
+
+		tokio::spawn(async move {
+			limiter.acquire_background().await;
+			let tags = match stats.track(client.get_tags(&names)).await {
+				Ok(tags) => Some(tags),
+				Err(e) => {
+					// Not retried; there's just no hint for these tags
+					log::warn!("Tag count lookup failed: {}", e);
+					None
+				}
+			};
+			let _ = sender
+				.send(GatewayMessage::TagCountsComplete { names, tags })
+				.await;
+		});
+	}
+
+	/// First page of a watched search, behind every other API call
+	fn spawn_watch_poll(&self, query: String) {
+		let mut search = query.clone();
+		if self.safe_mode {
+			search = query::safe_only(&search);
+		}
+		if !search.contains("-video") {
+			search.push_str(" -video");
+		}
+		log::debug!("Polling watched search '{}'", search);
+		let client = self.client.clone();
+		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		stats.pending.fetch_add(1, Ordering::Relaxed);
+
+		tokio::spawn(async move {
+			limiter.acquire_background().await;
+			match stats
+				.track(client.search_posts(&search, WATCH_POLL_LIMIT, 1))
+				.await
+			{
+				Ok(posts) => {
+					let ids = posts.iter().map(|post| post.id).collect();
+					let _ = sender
+						.send(GatewayMessage::WatchPollComplete { query, ids })
+						.await;
+				}
+				// Not retried; the next poll looks again
+				Err(e) => log::warn!("Watch poll for '{}' failed: {}", query, e),
+			}
+		});
+	}
+
+	/// One request per `MAX_IDS_PER_LOOKUP` IDs, each waiting its turn at the rate limiter
+	fn spawn_posts_by_ids(&self, ids: Vec<u64>, purpose: LookupPurpose) {
+		let client = self.client.clone();
+		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		let chunks = crate::api::id_chunks(&ids);
+		stats.pending.fetch_add(chunks.len(), Ordering::Relaxed);
+
+		tokio::spawn(async move {
+			let mut posts = Vec::new();
+			for (index, chunk) in chunks.iter().enumerate() {
+				limiter.acquire().await;
+				match stats.track(client.get_posts_by_ids(chunk)).await {
+					Ok(found) => posts.extend(found),
+					Err(e) => {
+						// The chunks after this one are never sent
+						stats
+							.pending
+							.fetch_sub(chunks.len() - index - 1, Ordering::Relaxed);
+						let _ = sender
+							.send(GatewayMessage::PostsByIdsFailed { purpose, error: e })
+							.await;
+						return;
+					}
+				}
+			}
+			let _ = sender
+				.send(GatewayMessage::PostsByIdsComplete {
+					ids,
+					purpose,
+					posts,
+				})
+				.await;
+		});
+	}
+
+	fn spawn_set(&self, set_id: u64) {
+		let client = self.client.clone();
+		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		let generation = self.search_generation;
+		stats.pending.fetch_add(1, Ordering::Relaxed);
+
+		tokio::spawn(async move {
+			limiter.acquire().await;
+			let msg = match stats.track(client.get_set(set_id)).await {
+				Ok(set) => GatewayMessage::SetComplete { set, generation },
+				Err(error) => GatewayMessage::SetFailed {
+					set_id,
+					error,
+					generation,
+				},
+			};
+			let _ = sender.send(msg).await;
+		});
+	}
+
+	/// Look up the open set's next `SET_CHUNK` posts
+	fn resolve_set_chunk(&mut self) {
+		let Some(set) = &mut self.set else {
+			return;
+		};
+		let offset = set.resolved;
+		if offset >= set.post_ids.len() {
+			self.exhausted = true;
+			return;
+		}
+		let end = (offset + SET_CHUNK).min(set.post_ids.len());
+		let ids = set.post_ids[offset..end].to_vec();
+		set.resolved = end;
+		log::info!("Set {}: resolving posts {}..{}", set.id, offset, end);
+		self.fetch_pending = true;
+		self.spawn_posts_by_ids(
+			ids,
+			LookupPurpose::SetPage {
+				generation: self.search_generation,
+				offset,
+			},
+		);
+	}
+
+	/// Pass a resolved chunk of the open set on as a page of results
+	fn set_page_received(
+		&mut self,
+		ids: &[u64],
+		posts: Vec<crate::api::Post>,
+		offset: usize,
+	) -> Vec<Event> {
+		self.fetch_pending = false;
+		let (posts, missing) = crate::api::order_by_ids(ids, posts);
+		if !missing.is_empty() {
+			log::info!("{} posts of the set are gone: {:?}", missing.len(), missing);
+		}
+		let Some(set) = &self.set else {
+			return Vec::new();
+		};
+		let page = (offset / SET_CHUNK) as u32 + 1;
+		self.current_page = page;
+		let done = set.resolved >= set.post_ids.len();
+		let mut events = Vec::new();
+		let empty = posts.is_empty();
+		events.push(Event::Browser(BrowserEvent::PostsReceived {
+			posts,
+			page,
+			is_new: offset == 0,
+			source: None,
+		}));
+		if done {
+			log::info!("End of set {}", set.id);
+			self.exhausted = true;
+			events.push(Event::Browser(BrowserEvent::EndOfResults));
+		} else if empty {
+			// Every post of the chunk is gone; nothing to bring the end closer, so go on
+			self.resolve_set_chunk();
+		}
+		events
+	}
+
+	fn spawn_blacklist(&self) {
+		let client = self.client.clone();
+		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		stats.pending.fetch_add(1, Ordering::Relaxed);
+
+		tokio::spawn(async move {
+			limiter.acquire().await;
+			let msg = match stats.track(client.get_blacklist()).await {
+				Ok(text) => GatewayMessage::BlacklistComplete { text },
+				Err(e) => GatewayMessage::BlacklistFailed {
+					message: e.to_string(),
+				},
+			};
+			let _ = sender.send(msg).await;
+		});
+	}
+
+	fn spawn_vote(&self, post_id: u64, score: i8, previous: i8) {
+		let client = self.client.clone();
+		let sender = self.sender.clone();
+		let limiter = self.limiter.clone();
+		let stats = self.stats.clone();
+		stats.pending.fetch_add(1, Ordering::Relaxed);
+
+		tokio::spawn(async move {
+			limiter.acquire().await;
+			let msg = match stats.track(client.vote(post_id, score)).await {
+				Ok(resp) => GatewayMessage::VoteComplete {
+					post_id,
+					vote: resp.our_score.clamp(-1, 1) as i8,
+					score: Score {
+						up: resp.up,
+						down: resp.down,
+						total: resp.score,
+					},
+				},
+				Err(e) => GatewayMessage::VoteFailed {
+					post_id,
+					previous,
+					message: e.to_string(),
+				},
+			};
+			let _ = sender.send(msg).await;
+		});
+	}
+
+	/// With credentials, start by loading the account's blacklist
+	pub fn init(&mut self) -> ComponentResponse {
+		let mut response = self.schedule_watches();
+		if self.client.credentials().is_some() {
+			response
+				.events
+				.push(Event::Gateway(GatewayEvent::FetchAccountBlacklist));
+		}
+		response
+	}
+
+	/// The client and rate limiter searches go through, for checks that should
+	/// take the same way
+	pub fn api(&self) -> (Arc<dyn BooruClient>, Arc<RateLimiter>) {
+		(self.client.clone(), self.limiter.clone())
+	}
+
+	pub fn current_query(&self) -> &str {
+		&self.current_query
+	}
+
+	/// Seed the current search is shuffled with, if it is `order:random`
+	pub fn random_seed(&self) -> Option<u64> {
+		self.random_seed
+	}
+
+	/// How many posts the current search has, once known
+	pub fn result_estimate(&self) -> Option<ResultEstimate> {
+		if let Some(total) = self.first_page_total {
+			return Some(ResultEstimate::Exact(total));
+		}
+		if self.is_compound() {
+			return None;
+		}
+		query::plain_tags(&self.current_query)
+			.iter()
+			.map(|name| self.tag_counts.get(name).copied().flatten())
+			.collect::<Option<Vec<u64>>>()?
+			.into_iter()
+			.min()
+			.map(ResultEstimate::AtMost)
+	}
+
+	/// Name of the set being browsed, once it has loaded
+	pub fn set_name(&self) -> Option<&str> {
+		self.set.as_ref()?.name.as_deref()
+	}
+
+	/// None until the name has been requested with `FetchPoolNames`
+	pub fn pool_name(&self, pool_id: u64) -> Option<&PoolName> {
+		self.pool_names.get(&pool_id)
+	}
+
+	/// Remember the current query and page cursors before a "More like this" search
+	pub fn push_snapshot(&mut self) {
+		if self.search_stack.len() >= SEARCH_STACK_DEPTH {
+			self.search_stack.remove(0);
+		}
+		self.search_stack.push(SearchSnapshot {
+			query: self.current_query.clone(),
+			page: self.current_page,
+			sub_queries: self.sub_queries.clone(),
+			next_sub_query: self.next_sub_query,
+			random_seed: self.random_seed,
+			set: self.set.clone(),
+		});
+	}
+
+	/// Go back to the last remembered query, returning it.
+	/// Anything still in flight belongs to the abandoned search and is dropped.
+	pub fn pop_snapshot(&mut self) -> Option<String> {
+		let snapshot = self.search_stack.pop()?;
+		log::info!(
+			"Restoring search: query='{}', page={}",
+			snapshot.query,
+			snapshot.page
+		);
+		self.current_query = snapshot.query;
+		self.current_page = snapshot.page;
+		self.sub_queries = snapshot.sub_queries;
+		self.next_sub_query = snapshot.next_sub_query;
+		self.random_seed = snapshot.random_seed;
+		self.first_page_total = snapshot.set.as_ref().map(|set| set.post_ids.len());
+		self.set = snapshot.set;
+		self.fetch_pending = false;
+		self.fanout = None;
+		self.search_generation += 1;
+		Some(self.current_query.clone())
+	}
+
+	pub fn credentials(&self) -> Option<&Credentials> {
+		self.client.credentials()
+	}
+
 	pub fn is_loading(&self) -> bool {
 		self.fetch_pending
 	}
+
+	/// API calls spawned but not answered yet
+	pub fn pending_requests(&self) -> usize {
+		self.stats.pending.load(Ordering::Relaxed)
+	}
+
+	pub fn last_latency(&self) -> Option<Duration> {
+		*self.stats.last_latency.lock().unwrap()
+	}
+
+	/// Rate limit tokens free right now, or None while calls are queued for one
+	pub fn rate_limit_free(&self) -> Option<usize> {
+		self.limiter.free_tokens()
+	}
+
+	pub fn fanout_pages(&self) -> u32 {
+		self.fanout_pages
+	}
+
+	pub fn watches(&self) -> &[SearchWatch] {
+		&self.watches
+	}
+
+	pub fn is_watched(&self, query: &str) -> bool {
+		self.watches.iter().any(|watch| watch.query == query.trim())
+	}
+
+	pub fn watch_interval_mins(&self) -> u32 {
+		(self.watch_interval.as_secs() / 60) as u32
+	}
 }
 
 impl Default for BooruGateway {
 	fn default() -> Self {
-		Self::new()
+		Self::new(None)
 	}
 }
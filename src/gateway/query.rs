@@ -0,0 +1,468 @@
+use crate::api::Post;
+use std::fmt;
+
+/// e621 rejects searches with more tags than this
+pub const MAX_TAGS: usize = 40;
+
+/// Separates the sub-queries of a compound search, e.g. `artist_a || artist_b`
+pub const COMPOUND_SEPARATOR: &str = "||";
+
+/// Pseudo-query listing the posts favorited on this machine, answered without the API
+pub const LOCAL_FAVORITES: &str = "local:favorites";
+
+/// Prefix of a query that opens a set by ID, e.g. `set:12345`
+pub const SET_PREFIX: &str = "set:";
+
+/// Artist-category tags that don't name an actual artist
+pub(crate) const NON_ARTIST_TAGS: &[&str] = &[
+	"conditional_dnp",
+	"invalid_artist",
+	"avoid_posting",
+	"sound_warning",
+	"epilepsy_warning",
+	"unknown_artist",
+	"anonymous_artist",
+	"third-party_edit",
+];
+
+/// Metatags whose values we know how to sanity check
+const KNOWN_METATAGS: &[&str] = &[
+	"order",
+	"score",
+	"rating",
+	"id",
+	"user",
+	"fav",
+	"favcount",
+	"date",
+	"width",
+	"height",
+	"mpixels",
+	"ratio",
+	"filesize",
+	"type",
+	"status",
+	"source",
+	"pool",
+	"set",
+	"parent",
+	"md5",
+	"tagcount",
+	"comment_count",
+	"duration",
+	"limit",
+	"randseed",
+];
+
+/// A syntax or limit problem found in a search query
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryWarning {
+	/// More tags than the API accepts
+	TooManyTags { count: usize },
+	/// A `-` or `~` prefix with no tag after it
+	DanglingPrefix { token: String },
+	/// Known metatag with nothing after the colon
+	EmptyMetatag { name: String },
+	/// Known metatag written with the wrong separator, e.g. `order;score`
+	MalformedMetatag { token: String, suggestion: String },
+	/// Value that the metatag can never match
+	InvalidValue { name: String, value: String },
+}
+
+impl fmt::Display for QueryWarning {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			QueryWarning::TooManyTags { count } => {
+				write!(f, "Too many tags ({} of max {})", count, MAX_TAGS)
+			}
+			QueryWarning::DanglingPrefix { token } => {
+				write!(f, "'{}' is missing a tag after it", token)
+			}
+			QueryWarning::EmptyMetatag { name } => write!(f, "'{}:' is missing a value", name),
+			QueryWarning::MalformedMetatag { token, suggestion } => {
+				write!(
+					f,
+					"'{}' looks like a typo, did you mean '{}'?",
+					token, suggestion
+				)
+			}
+			QueryWarning::InvalidValue { name, value } => {
+				write!(f, "'{}' is not a valid value for '{}:'", value, name)
+			}
+		}
+	}
+}
+
+/// Split a compound query into its trimmed, non-empty sub-queries.
+/// A plain query yields a single entry.
+pub fn split_compound(query: &str) -> Vec<String> {
+	query
+		.split(COMPOUND_SEPARATOR)
+		.map(str::trim)
+		.filter(|part| !part.is_empty())
+		.map(str::to_string)
+		.collect()
+}
+
+/// Tags every result must carry: no metatags, negations, `~` alternatives or wildcards
+pub fn plain_tags(query: &str) -> Vec<String> {
+	let mut tags: Vec<String> = Vec::new();
+	for token in query.split_whitespace() {
+		if token.starts_with(['-', '~'])
+			|| token.contains([':', '*'])
+			|| token == COMPOUND_SEPARATOR
+		{
+			continue;
+		}
+		let tag = token.to_lowercase();
+		if !tags.contains(&tag) {
+			tags.push(tag);
+		}
+	}
+	tags
+}
+
+/// ID of the set a `set:12345` query opens; other queries, including `set:` with
+/// further tags or a set's short name, are searched normally
+pub fn set_id(query: &str) -> Option<u64> {
+	let query = query.trim();
+	let (prefix, id) = query.split_at_checked(SET_PREFIX.len())?;
+	if !prefix.eq_ignore_ascii_case(SET_PREFIX) {
+		return None;
+	}
+	id.parse().ok()
+}
+
+/// A pool's posts in reading order
+pub fn pool(pool_id: u64) -> String {
+	format!("pool:{} order:id", pool_id)
+}
+
+/// Whether the query browses a single pool
+pub fn is_pool(query: &str) -> bool {
+	query.split_whitespace().any(|token| {
+		token.split_once(':').is_some_and(|(name, id)| {
+			name.eq_ignore_ascii_case("pool") && id.parse::<u64>().is_ok()
+		})
+	})
+}
+
+/// The query as safe mode sends it: whatever `rating:` terms were typed, with or
+/// without `-`/`~`, give way to a single `rating:s`
+pub fn safe_only(query: &str) -> String {
+	let mut tokens: Vec<&str> = query
+		.split_whitespace()
+		.filter(|token| {
+			!token
+				.trim_start_matches(['-', '~'])
+				.split_once(':')
+				.is_some_and(|(name, _)| name.eq_ignore_ascii_case("rating"))
+		})
+		.collect();
+	tokens.push("rating:s");
+	tokens.join(" ")
+}
+
+/// Whether the query asks for random order, which e621 reshuffles on every request
+pub fn is_random(query: &str) -> bool {
+	query
+		.split_whitespace()
+		.any(|token| token.eq_ignore_ascii_case("order:random"))
+}
+
+/// Seed written into the query with `randseed:`
+pub fn seed(query: &str) -> Option<u64> {
+	query.split_whitespace().find_map(|token| {
+		let (name, value) = token.split_once(':')?;
+		if name.eq_ignore_ascii_case("randseed") {
+			value.parse().ok()
+		} else {
+			None
+		}
+	})
+}
+
+/// Pin a random-order query to one shuffle, so its pages don't overlap.
+/// Other queries, and ones carrying their own seed, are returned as they are.
+pub fn with_seed(query: &str, seed: u64) -> String {
+	if !is_random(query) || self::seed(query).is_some() {
+		return query.to_string();
+	}
+	format!("{} randseed:{}", query, seed)
+}
+
+/// The query without its `randseed:` tags, so searching it again reshuffles
+pub fn without_seed(query: &str) -> String {
+	query
+		.split_whitespace()
+		.filter(|token| {
+			!token
+				.split_once(':')
+				.is_some_and(|(name, _)| name.eq_ignore_ascii_case("randseed"))
+		})
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// Build a follow-up search for posts like `post`: its first artist, or else
+/// its first two character/species tags. Rating filters of `current_query` are kept.
+pub fn similar_query(post: &Post, current_query: &str) -> Option<String> {
+	let artist = post
+		.tags
+		.artist
+		.iter()
+		.find(|tag| !NON_ARTIST_TAGS.contains(&tag.as_str()));
+	let mut tokens: Vec<&str> = match artist {
+		Some(artist) => vec![artist.as_str()],
+		None => post
+			.tags
+			.character
+			.iter()
+			.chain(&post.tags.species)
+			.take(2)
+			.map(String::as_str)
+			.collect(),
+	};
+	if tokens.is_empty() {
+		return None;
+	}
+
+	for token in current_query.split_whitespace() {
+		let tag = token.trim_start_matches(['-', '~']).to_lowercase();
+		if tag.starts_with("rating:") && !tokens.contains(&token) {
+			tokens.push(token);
+		}
+	}
+	Some(tokens.join(" "))
+}
+
+/// Check a query for syntax problems and limits before it is sent.
+/// Each sub-query of a compound query is checked on its own.
+/// Tags themselves are not checked for existence.
+pub fn validate(query: &str) -> Vec<QueryWarning> {
+	split_compound(query)
+		.iter()
+		.flat_map(|part| validate_single(part))
+		.collect()
+}
+
+fn validate_single(query: &str) -> Vec<QueryWarning> {
+	let mut warnings = Vec::new();
+	let tokens: Vec<&str> = query.split_whitespace().collect();
+
+	if tokens.len() > MAX_TAGS {
+		warnings.push(QueryWarning::TooManyTags {
+			count: tokens.len(),
+		});
+	}
+
+	for token in tokens {
+		let tag = token.trim_start_matches(['-', '~']);
+		if tag.is_empty() {
+			warnings.push(QueryWarning::DanglingPrefix {
+				token: token.to_string(),
+			});
+			continue;
+		}
+
+		if let Some((name, value)) = tag.split_once(':') {
+			let name = name.to_lowercase();
+			if !KNOWN_METATAGS.contains(&name.as_str()) {
+				// Plenty of regular tags contain colons
+				continue;
+			}
+			if value.is_empty() {
+				warnings.push(QueryWarning::EmptyMetatag { name });
+			} else if !is_valid_value(&name, value) {
+				warnings.push(QueryWarning::InvalidValue {
+					name,
+					value: value.to_string(),
+				});
+			}
+			continue;
+		}
+
+		// Catch near-misses like `order;score` or `rating=s`
+		if let Some(pos) = tag.find([';', '=']) {
+			let (name, rest) = tag.split_at(pos);
+			if KNOWN_METATAGS.contains(&name.to_lowercase().as_str()) {
+				let prefix = &token[..token.len() - tag.len()];
+				warnings.push(QueryWarning::MalformedMetatag {
+					token: token.to_string(),
+					suggestion: format!("{}{}:{}", prefix, name, &rest[1..]),
+				});
+			}
+		}
+	}
+
+	warnings
+}
+
+fn is_valid_value(name: &str, value: &str) -> bool {
+	match name {
+		"rating" => matches!(
+			value.to_lowercase().as_str(),
+			"s" | "q" | "e" | "safe" | "questionable" | "explicit"
+		),
+		"randseed" => value.parse::<u64>().is_ok(),
+		"score" | "favcount" | "id" | "width" | "height" | "tagcount" | "comment_count" => {
+			is_numeric_range(value)
+		}
+		_ => true,
+	}
+}
+
+/// Accepts `10`, `>10`, `<=10`, `10..20`, `..20` and `10..`
+fn is_numeric_range(value: &str) -> bool {
+	let value = value.trim_start_matches(['<', '>', '=']);
+	if value.is_empty() {
+		return false;
+	}
+	let parts: Vec<&str> = value.split("..").collect();
+	parts.len() <= 2
+		&& parts.iter().any(|part| !part.is_empty())
+		&& parts
+			.iter()
+			.all(|part| part.is_empty() || part.parse::<i64>().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn random_queries_get_one_seed_for_all_pages() {
+		assert_eq!(
+			with_seed("wolf order:random", 42),
+			"wolf order:random randseed:42"
+		);
+		assert_eq!(
+			with_seed("wolf ORDER:Random", 42),
+			"wolf ORDER:Random randseed:42"
+		);
+		assert_eq!(with_seed("wolf order:score", 42), "wolf order:score");
+		assert_eq!(with_seed("wolf -order:random", 42), "wolf -order:random");
+
+		// A seed in the query is the user's and wins
+		let pinned = "order:random randseed:7 wolf";
+		assert_eq!(seed(pinned), Some(7));
+		assert_eq!(with_seed(pinned, 42), pinned);
+		assert_eq!(without_seed(pinned), "order:random wolf");
+	}
+
+	#[test]
+	fn safe_only_replaces_every_rating_term() {
+		assert_eq!(safe_only("wolf"), "wolf rating:s");
+		assert_eq!(
+			safe_only("wolf rating:e -Rating:s ~rating:q ~rating:explicit solo"),
+			"wolf solo rating:s"
+		);
+		assert_eq!(safe_only("  "), "rating:s");
+		// Only the metatag; tags that merely mention it are kept
+		assert_eq!(safe_only("rating_sign"), "rating_sign rating:s");
+	}
+
+	#[test]
+	fn only_a_lone_numeric_set_opens_the_set() {
+		assert_eq!(set_id("set:12345"), Some(12345));
+		assert_eq!(set_id("  SET:7 "), Some(7));
+		assert_eq!(set_id("set:my_set"), None);
+		assert_eq!(set_id("set:12345 wolf"), None);
+		assert_eq!(set_id("wolf set:12345"), None);
+		assert_eq!(set_id("set:"), None);
+
+		assert!(is_pool(&pool(123)));
+		assert!(is_pool("wolf Pool:9"));
+		assert!(!is_pool("pool:comics wolf"));
+		assert!(!is_pool("-pool"));
+	}
+
+	#[test]
+	fn plain_tags_leave_out_filters() {
+		assert_eq!(
+			plain_tags("Wolf -feral ~fox ~cat rating:s solo wol* wolf"),
+			["wolf", "solo"]
+		);
+		assert!(plain_tags("order:random -feral").is_empty());
+	}
+
+	#[test]
+	fn seed_must_be_a_number() {
+		assert!(validate("order:random randseed:12").is_empty());
+		assert_eq!(
+			validate("order:random randseed:abc"),
+			[QueryWarning::InvalidValue {
+				name: "randseed".to_string(),
+				value: "abc".to_string(),
+			}]
+		);
+		assert_eq!(seed("randseed:abc"), None);
+	}
+
+	#[test]
+	fn validate_flags_each_kind_of_problem() {
+		use QueryWarning::*;
+		let name = |s: &str| s.to_string();
+		let too_many = vec!["wolf"; MAX_TAGS + 1].join(" ");
+		let cases: Vec<(&str, Vec<QueryWarning>)> = vec![
+			(
+				&too_many,
+				vec![TooManyTags {
+					count: MAX_TAGS + 1,
+				}],
+			),
+			("wolf -", vec![DanglingPrefix { token: name("-") }]),
+			("wolf ~ ~fox", vec![DanglingPrefix { token: name("~") }]),
+			(
+				"rating:",
+				vec![EmptyMetatag {
+					name: name("rating"),
+				}],
+			),
+			(
+				"wolf order;score",
+				vec![MalformedMetatag {
+					token: name("order;score"),
+					suggestion: name("order:score"),
+				}],
+			),
+			(
+				"-rating=s",
+				vec![MalformedMetatag {
+					token: name("-rating=s"),
+					suggestion: name("-rating:s"),
+				}],
+			),
+			(
+				"rating:x",
+				vec![InvalidValue {
+					name: name("rating"),
+					value: name("x"),
+				}],
+			),
+			(
+				"score:abc",
+				vec![InvalidValue {
+					name: name("score"),
+					value: name("abc"),
+				}],
+			),
+			// Each side of a compound query is checked on its own
+			(
+				"wolf || rating:",
+				vec![EmptyMetatag {
+					name: name("rating"),
+				}],
+			),
+			// Fine as written
+			("wolf ~fox -feral rating:s score:>=10 order:score", vec![]),
+			("score:10..20 id:..500", vec![]),
+			// Plain tags with colons aren't metatags
+			("pok\u{e9}mon_(species) 3:4 re:zero artist:unknown", vec![]),
+			("tom_and_jerry:_the_movie mega_man:x", vec![]),
+		];
+		for (query, expected) in cases {
+			assert_eq!(validate(query), expected, "{}", query);
+		}
+	}
+}
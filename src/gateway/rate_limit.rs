@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// e621 asks for at most 2 requests per second
+pub const API_BURST: usize = 2;
+pub const API_WINDOW: Duration = Duration::from_secs(1);
+
+/// How often a background caller looks again while others hold the limiter
+const BACKGROUND_RECHECK: Duration = Duration::from_millis(100);
+
+/// Token bucket shared by every API call. Each of the `burst` tokens comes back
+/// `window` after it was spent, so no rolling window ever sees more than `burst`
+/// requests. Callers queue in `acquire` in arrival order instead of being dropped.
+pub struct RateLimiter {
+	/// When each spent token was taken, oldest first
+	spent: Mutex<VecDeque<Instant>>,
+	burst: usize,
+	window: Duration,
+}
+
+impl RateLimiter {
+	pub fn new(burst: usize, window: Duration) -> Self {
+		Self {
+			spent: Mutex::new(VecDeque::with_capacity(burst)),
+			burst,
+			window,
+		}
+	}
+
+	/// Wait for a token. The lock is held while waiting so later callers stay behind.
+	pub async fn acquire(&self) {
+		let mut spent = self.spent.lock().await;
+		if spent.len() >= self.burst
+			&& let Some(oldest) = spent.pop_front()
+		{
+			tokio::time::sleep_until(oldest + self.window).await;
+		}
+		spent.push_back(Instant::now());
+	}
+
+	/// Wait for a token with the lowest priority: one is taken only while every
+	/// token is free and nobody is queued, so a search right after still gets one
+	pub async fn acquire_background(&self) {
+		loop {
+			if let Ok(mut spent) = self.spent.try_lock() {
+				let now = Instant::now();
+				match spent.iter().rev().find(|at| now < **at + self.window) {
+					None => {
+						if spent.len() >= self.burst {
+							spent.pop_front();
+						}
+						spent.push_back(now);
+						return;
+					}
+					// The newest token is the last to come back
+					Some(newest) => {
+						let free_at = *newest + self.window;
+						drop(spent);
+						tokio::time::sleep_until(free_at).await;
+						continue;
+					}
+				}
+			}
+			tokio::time::sleep(BACKGROUND_RECHECK).await;
+		}
+	}
+
+	/// Tokens that could be spent right now, or None while a caller is queued
+	pub fn free_tokens(&self) -> Option<usize> {
+		let spent = self.spent.try_lock().ok()?;
+		let now = Instant::now();
+		let busy = spent.iter().filter(|at| now < **at + self.window).count();
+		Some(self.burst.saturating_sub(busy))
+	}
+}
+
+impl Default for RateLimiter {
+	fn default() -> Self {
+		Self::new(API_BURST, API_WINDOW)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+
+	#[tokio::test(start_paused = true)]
+	async fn never_more_than_burst_in_any_window() {
+		let limiter = Arc::new(RateLimiter::default());
+		let start = Instant::now();
+
+		// Bursty callers at uneven offsets, like searches, notes and votes interleaving
+		let offsets_ms = [
+			0, 0, 0, 10, 400, 450, 900, 1000, 1001, 1600, 1600, 3000, 3100,
+		];
+		let handles: Vec<_> = offsets_ms
+			.iter()
+			.map(|&ms| {
+				let limiter = limiter.clone();
+				tokio::spawn(async move {
+					tokio::time::sleep(Duration::from_millis(ms)).await;
+					limiter.acquire().await;
+					Instant::now()
+				})
+			})
+			.collect();
+		let mut sent = Vec::new();
+		for handle in handles {
+			sent.push(handle.await.unwrap() - start);
+		}
+		sent.sort();
+
+		assert_eq!(sent.len(), offsets_ms.len());
+		for (i, at) in sent.iter().enumerate() {
+			let in_window = sent[i..]
+				.iter()
+				.take_while(|later| **later - *at < API_WINDOW)
+				.count();
+			assert!(
+				in_window <= API_BURST,
+				"{} requests within 1s of {:?}",
+				in_window,
+				at
+			);
+		}
+		// Queued rather than dropped, at the full rate: 13 requests take 6 windows
+		assert_eq!(sent[sent.len() - 1], Duration::from_secs(6));
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn background_waits_until_searches_are_done() {
+		let limiter = Arc::new(RateLimiter::default());
+		let start = Instant::now();
+		limiter.acquire().await;
+
+		let background = {
+			let limiter = limiter.clone();
+			tokio::spawn(async move {
+				limiter.acquire_background().await;
+				Instant::now()
+			})
+		};
+		// A search arriving meanwhile isn't held up by it
+		tokio::time::sleep(Duration::from_millis(300)).await;
+		limiter.acquire().await;
+		assert_eq!(Instant::now() - start, Duration::from_millis(300));
+
+		let background_at = background.await.unwrap() - start;
+		assert_eq!(background_at, Duration::from_millis(1300));
+		// And leaves a token for the next search
+		limiter.acquire().await;
+		assert_eq!(Instant::now() - start, background_at);
+	}
+}
@@ -4,21 +4,56 @@ mod api;
 mod beat;
 mod breathing;
 mod browser;
+mod cli;
 mod coach;
 mod config;
+mod diagnose;
+mod export;
+mod favorites;
 mod gateway;
 mod media;
 mod reactor;
 mod settings;
+#[cfg(test)]
+mod testing;
 mod types;
 mod view;
 
+use cli::Command;
 use reactor::Reactor;
+use std::sync::Arc;
+use std::time::Duration;
 
-#[tokio::main]
-async fn main() -> eframe::Result<()> {
+/// Time given to async tasks still running after the window closed
+const RUNTIME_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn main() -> eframe::Result<()> {
 	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
+	let startup = match cli::parse(std::env::args().skip(1)) {
+		Ok(Command::Run(startup)) => startup,
+		Ok(Command::Help) => {
+			cli::attach_console();
+			println!("{}", cli::USAGE);
+			return Ok(());
+		}
+		Ok(Command::Diagnose) => {
+			cli::attach_console();
+			let passed = diagnose_from_cli();
+			std::process::exit(if passed { 0 } else { 1 });
+		}
+		Err(message) => {
+			cli::attach_console();
+			eprintln!("{}\n\n{}", message, cli::USAGE);
+			std::process::exit(2);
+		}
+	};
+
+	// Owned here rather than by #[tokio::main] so it is shut down explicitly,
+	// after the app and every egui context it handed out are gone
+	let runtime = tokio::runtime::Runtime::new().expect("Failed to start the tokio runtime");
+	let guard = runtime.enter();
+
 	let native_options = eframe::NativeOptions {
 		viewport: eframe::egui::ViewportBuilder::default()
 			.with_inner_size([1280.0, 720.0])
@@ -26,9 +61,31 @@ async fn main() -> eframe::Result<()> {
 		..Default::default()
 	};
 
-	eframe::run_native(
+	let result = eframe::run_native(
 		"Sodglumate",
 		native_options,
-		Box::new(|cc| Ok(Box::new(Reactor::new(&cc.egui_ctx)))),
-	)
+		Box::new(|cc| Ok(Box::new(Reactor::new(&cc.egui_ctx, startup)))),
+	);
+
+	drop(guard);
+	runtime.shutdown_timeout(RUNTIME_SHUTDOWN_TIMEOUT);
+	log::info!("Shutdown complete");
+	result
+}
+
+/// Run the self-check with the saved login and print its report.
+/// Returns whether every check passed.
+fn diagnose_from_cli() -> bool {
+	let runtime = tokio::runtime::Runtime::new().expect("Failed to start the tokio runtime");
+	let settings = config::load_settings();
+	let diagnostics = diagnose::Diagnostics::new(
+		Arc::new(api::E621Client::new(settings.credentials())),
+		Arc::new(gateway::rate_limit::RateLimiter::default()),
+		Arc::new(media::loader::HttpLoader::new()),
+		config::get_config_dir(),
+	);
+	let report = runtime.block_on(diagnostics.run());
+	println!("{}", report.to_text());
+	runtime.shutdown_timeout(RUNTIME_SHUTDOWN_TIMEOUT);
+	report.passed()
 }
@@ -0,0 +1,111 @@
+use eframe::egui;
+
+/// Long edge of a blurred preview in pixels. Drawn scaled up with linear
+/// filtering, so a handful of pixels is all the detail that survives.
+const PREVIEW_EDGE: usize = 24;
+
+/// A tiny, softened copy of `image` to show in its place until it is revealed
+pub fn preview(image: &egui::ColorImage) -> egui::ColorImage {
+	let [width, height] = image.size;
+	if width == 0 || height == 0 {
+		return egui::ColorImage::new([1, 1], egui::Color32::BLACK);
+	}
+	let scale = PREVIEW_EDGE as f32 / width.max(height) as f32;
+	let out_w = ((width as f32 * scale).round() as usize).clamp(1, PREVIEW_EDGE);
+	let out_h = ((height as f32 * scale).round() as usize).clamp(1, PREVIEW_EDGE);
+	let small = downscale(image, [out_w, out_h]);
+	box_blur(&small)
+}
+
+/// Average every source pixel into the output pixel it falls in
+fn downscale(image: &egui::ColorImage, size: [usize; 2]) -> egui::ColorImage {
+	let [width, height] = image.size;
+	let [out_w, out_h] = size;
+	let mut sums = vec![[0u64; 5]; out_w * out_h];
+	for y in 0..height {
+		let out_y = y * out_h / height;
+		for x in 0..width {
+			let out_x = x * out_w / width;
+			let pixel = image.pixels[y * width + x];
+			let sum = &mut sums[out_y * out_w + out_x];
+			sum[0] += u64::from(pixel.r());
+			sum[1] += u64::from(pixel.g());
+			sum[2] += u64::from(pixel.b());
+			sum[3] += u64::from(pixel.a());
+			sum[4] += 1;
+		}
+	}
+	egui::ColorImage {
+		size,
+		pixels: sums.iter().map(average).collect(),
+	}
+}
+
+/// One 3×3 box blur pass, clamping at the edges
+fn box_blur(image: &egui::ColorImage) -> egui::ColorImage {
+	let [width, height] = image.size;
+	let mut pixels = Vec::with_capacity(width * height);
+	for y in 0..height {
+		for x in 0..width {
+			let mut sum = [0u64; 5];
+			for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+				for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+					let pixel = image.pixels[ny * width + nx];
+					sum[0] += u64::from(pixel.r());
+					sum[1] += u64::from(pixel.g());
+					sum[2] += u64::from(pixel.b());
+					sum[3] += u64::from(pixel.a());
+					sum[4] += 1;
+				}
+			}
+			pixels.push(average(&sum));
+		}
+	}
+	egui::ColorImage {
+		size: image.size,
+		pixels,
+	}
+}
+
+/// Channel sums plus pixel count, back to a colour
+fn average(sum: &[u64; 5]) -> egui::Color32 {
+	let count = sum[4].max(1);
+	let channel = |i: usize| (sum[i] / count) as u8;
+	egui::Color32::from_rgba_premultiplied(channel(0), channel(1), channel(2), channel(3))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn preview_keeps_the_aspect_ratio_within_the_edge() {
+		let image = egui::ColorImage::new([400, 100], egui::Color32::from_rgb(200, 40, 10));
+		let preview = preview(&image);
+		assert_eq!(preview.size, [PREVIEW_EDGE, PREVIEW_EDGE / 4]);
+		// Blurring a flat colour changes nothing
+		assert!(
+			preview
+				.pixels
+				.iter()
+				.all(|p| *p == egui::Color32::from_rgb(200, 40, 10))
+		);
+	}
+
+	#[test]
+	fn preview_averages_away_fine_detail() {
+		// One-pixel checkerboard: nothing of it should survive
+		let mut image = egui::ColorImage::new([96, 96], egui::Color32::BLACK);
+		for y in 0..96 {
+			for x in 0..96 {
+				if (x + y) % 2 == 0 {
+					image.pixels[y * 96 + x] = egui::Color32::WHITE;
+				}
+			}
+		}
+		let preview = preview(&image);
+		for pixel in &preview.pixels {
+			assert!((pixel.r() as i32 - 127).abs() <= 1, "{:?}", pixel);
+		}
+	}
+}
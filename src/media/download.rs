@@ -0,0 +1,296 @@
+use super::loader::cdn_client;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::sync::mpsc;
+
+/// Number of background workers writing downloads to disk.
+/// Kept separate from the viewing workers so browsing stays snappy.
+const NUM_DOWNLOAD_WORKERS: usize = 2;
+
+/// A single file to write to disk
+struct DownloadJob {
+	url: String,
+	path: PathBuf,
+	/// Bytes already held by the media cache, written without re-downloading
+	cached: Option<Arc<Vec<u8>>>,
+	generation: u64,
+}
+
+struct DownloadResult {
+	url: String,
+	generation: u64,
+	result: Result<(), String>,
+}
+
+/// What every download worker shares with the manager
+#[derive(Clone)]
+struct WorkerShared {
+	paused: Arc<AtomicBool>,
+	generation: Arc<AtomicU64>,
+	writing: Arc<AtomicUsize>,
+	client: reqwest::Client,
+}
+
+/// Counters for the current batch
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DownloadProgress {
+	pub completed: usize,
+	pub failed: usize,
+	pub total: usize,
+}
+
+impl DownloadProgress {
+	pub fn is_active(&self) -> bool {
+		self.completed + self.failed < self.total
+	}
+}
+
+pub struct DownloadManager {
+	work_tx: mpsc::UnboundedSender<DownloadJob>,
+	result_rx: mpsc::Receiver<DownloadResult>,
+	paused: Arc<AtomicBool>,
+	/// Bumped on cancel; workers skip jobs from older generations
+	generation: Arc<AtomicU64>,
+	/// Files being written right now
+	writing: Arc<AtomicUsize>,
+	progress: DownloadProgress,
+}
+
+impl DownloadManager {
+	pub fn new(ctx: &egui::Context) -> Self {
+		log::info!(
+			"Initializing DownloadManager with {} workers",
+			NUM_DOWNLOAD_WORKERS
+		);
+
+		let (work_tx, work_rx) = mpsc::unbounded_channel::<DownloadJob>();
+		let (result_tx, result_rx) = mpsc::channel(100);
+		let paused = Arc::new(AtomicBool::new(false));
+		let generation = Arc::new(AtomicU64::new(0));
+		let writing = Arc::new(AtomicUsize::new(0));
+
+		let shared_rx = Arc::new(AsyncMutex::new(work_rx));
+		let shared = WorkerShared {
+			paused: paused.clone(),
+			generation: generation.clone(),
+			writing: writing.clone(),
+			client: cdn_client(),
+		};
+		for i in 0..NUM_DOWNLOAD_WORKERS {
+			Self::spawn_worker(
+				i,
+				shared_rx.clone(),
+				result_tx.clone(),
+				shared.clone(),
+				ctx.clone(),
+			);
+		}
+
+		Self {
+			work_tx,
+			result_rx,
+			paused,
+			generation,
+			writing,
+			progress: DownloadProgress::default(),
+		}
+	}
+
+	fn spawn_worker(
+		id: usize,
+		rx: Arc<AsyncMutex<mpsc::UnboundedReceiver<DownloadJob>>>,
+		result_tx: mpsc::Sender<DownloadResult>,
+		shared: WorkerShared,
+		ctx: egui::Context,
+	) {
+		let WorkerShared {
+			paused,
+			generation,
+			writing,
+			client,
+		} = shared;
+		tokio::spawn(async move {
+			log::info!("Download worker [{}] started", id);
+			loop {
+				let job = {
+					let mut rx = rx.lock().await;
+					rx.recv().await
+				};
+				let Some(job) = job else {
+					log::info!("Download worker [{}] shutting down", id);
+					break;
+				};
+
+				while paused.load(Ordering::Relaxed)
+					&& job.generation == generation.load(Ordering::Relaxed)
+				{
+					tokio::time::sleep(Duration::from_millis(200)).await;
+				}
+				if job.generation != generation.load(Ordering::Relaxed) {
+					continue;
+				}
+
+				log::info!("Download worker [{}] saving: {}", id, job.url);
+				writing.fetch_add(1, Ordering::Relaxed);
+				let result = Self::download(&client, &job).await;
+				writing.fetch_sub(1, Ordering::Relaxed);
+				let _ = result_tx
+					.send(DownloadResult {
+						url: job.url,
+						generation: job.generation,
+						result: result.map_err(|e| e.to_string()),
+					})
+					.await;
+				ctx.request_repaint();
+			}
+		});
+	}
+
+	async fn download(client: &reqwest::Client, job: &DownloadJob) -> Result<(), anyhow::Error> {
+		if let Some(parent) = job.path.parent() {
+			tokio::fs::create_dir_all(parent).await?;
+		}
+		if let Some(bytes) = &job.cached {
+			return write_complete(&job.path, bytes).await;
+		}
+		let resp = client.get(&job.url).send().await?;
+		if !resp.status().is_success() {
+			anyhow::bail!("HTTP Status: {}", resp.status());
+		}
+		let bytes = resp.bytes().await?;
+		write_complete(&job.path, &bytes).await
+	}
+
+	/// Queue a file for download, appending to the current batch
+	pub fn enqueue(&mut self, url: String, path: PathBuf, cached: Option<Arc<Vec<u8>>>) {
+		if !self.progress.is_active() {
+			self.progress = DownloadProgress::default();
+		}
+		log::debug!(
+			"Queueing download: {} -> {:?} (cached={})",
+			url,
+			path,
+			cached.is_some()
+		);
+		let job = DownloadJob {
+			url,
+			path,
+			cached,
+			generation: self.generation.load(Ordering::Relaxed),
+		};
+		if self.work_tx.send(job).is_ok() {
+			self.progress.total += 1;
+		}
+	}
+
+	pub fn set_paused(&mut self, paused: bool) {
+		self.paused.store(paused, Ordering::Relaxed);
+	}
+
+	/// Drop everything still queued; in-flight files are allowed to finish
+	pub fn cancel(&mut self) {
+		self.generation.fetch_add(1, Ordering::Relaxed);
+		self.paused.store(false, Ordering::Relaxed);
+		self.progress.total = self.progress.completed + self.progress.failed;
+		log::info!("Downloads cancelled");
+	}
+
+	/// Drop the queue and wait up to `grace` for files being written to finish,
+	/// so exiting doesn't leave them truncated. Blocks the calling thread.
+	pub fn shutdown(&mut self, grace: Duration) {
+		self.cancel();
+		let deadline = Instant::now() + grace;
+		while self.writing.load(Ordering::Relaxed) > 0 {
+			if Instant::now() >= deadline {
+				log::warn!(
+					"Giving up on {} download(s) still being written",
+					self.writing.load(Ordering::Relaxed)
+				);
+				return;
+			}
+			std::thread::sleep(Duration::from_millis(20));
+		}
+	}
+
+	pub fn poll(&mut self) {
+		let current = self.generation.load(Ordering::Relaxed);
+		while let Ok(msg) = self.result_rx.try_recv() {
+			if msg.generation != current {
+				continue;
+			}
+			match msg.result {
+				Ok(()) => {
+					log::info!("Downloaded: {}", msg.url);
+					self.progress.completed += 1;
+				}
+				Err(e) => {
+					log::error!("Download failed: {} - {}", msg.url, e);
+					self.progress.failed += 1;
+				}
+			}
+		}
+	}
+
+	/// Finished downloads waiting for `poll`
+	pub fn has_results(&self) -> bool {
+		!self.result_rx.is_empty()
+	}
+
+	pub fn progress(&self) -> DownloadProgress {
+		self.progress
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.paused.load(Ordering::Relaxed)
+	}
+}
+
+/// Where `path` is written before it's complete
+fn partial_path(path: &Path) -> PathBuf {
+	let mut partial = path.as_os_str().to_owned();
+	partial.push(".part");
+	PathBuf::from(partial)
+}
+
+/// Write `bytes` to a sibling of `path` and rename it into place once it's all
+/// there, so a failed write never leaves a truncated file under the real name
+async fn write_complete(path: &Path, bytes: &[u8]) -> Result<(), anyhow::Error> {
+	let partial = partial_path(path);
+	let written = match tokio::fs::write(&partial, bytes).await {
+		Ok(()) => tokio::fs::rename(&partial, path).await,
+		Err(e) => Err(e),
+	};
+	if written.is_err() {
+		let _ = tokio::fs::remove_file(&partial).await;
+	}
+	Ok(written?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn files_appear_under_their_name_only_once_complete() {
+		let dir = std::env::temp_dir().join(format!("sodglumate-download-{}", std::process::id()));
+		tokio::fs::create_dir_all(&dir).await.unwrap();
+		let path = dir.join("1.png");
+		write_complete(&path, b"png").await.unwrap();
+		assert_eq!(tokio::fs::read(&path).await.unwrap(), b"png");
+		assert!(!partial_path(&path).exists());
+
+		// A directory in the way: the rename fails and nothing is left behind
+		let blocked = dir.join("2.png");
+		tokio::fs::create_dir_all(blocked.join("inside"))
+			.await
+			.unwrap();
+		assert!(write_complete(&blocked, b"png").await.is_err());
+		assert!(!partial_path(&blocked).exists());
+		assert!(blocked.is_dir());
+		let _ = tokio::fs::remove_dir_all(&dir).await;
+	}
+}
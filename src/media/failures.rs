@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Transient failures of one URL before it's left alone until they age out
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Wait after the first transient failure; doubles with each one after it
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Transient failures are forgotten this long after the last one
+const FAILURE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// What went wrong loading one URL
+#[derive(Debug, Clone)]
+pub struct Failure {
+	pub attempts: u32,
+	pub last_error: String,
+	/// Retrying won't help (missing file, not an image)
+	pub permanent: bool,
+	last_at: Instant,
+}
+
+impl Failure {
+	/// Time since the last failure
+	pub fn since(&self) -> Duration {
+		self.last_at.elapsed()
+	}
+}
+
+/// Failed URLs, so prefetching the same posts over and over doesn't download a dead
+/// URL every time. Permanent failures stay until retried by hand.
+pub struct FailureMap {
+	failures: HashMap<String, Failure>,
+	retry_delay: Duration,
+}
+
+impl Default for FailureMap {
+	fn default() -> Self {
+		Self {
+			failures: HashMap::new(),
+			retry_delay: RETRY_DELAY,
+		}
+	}
+}
+
+impl FailureMap {
+	/// Start the backoff at `delay` rather than `RETRY_DELAY`
+	#[cfg(test)]
+	pub fn with_retry_delay(delay: Duration) -> Self {
+		Self {
+			retry_delay: delay,
+			..Self::default()
+		}
+	}
+
+	/// Wait after `attempts` transient failures
+	fn backoff(&self, attempts: u32) -> Duration {
+		self.retry_delay * 2u32.pow(attempts.saturating_sub(1))
+	}
+
+	/// When `url` may be loaded again after a transient failure. None when it
+	/// hasn't failed, or won't be retried until the failure ages out.
+	pub fn retry_at(&self, url: &str) -> Option<Instant> {
+		let failure = self.failures.get(url)?;
+		(!failure.permanent && failure.attempts < MAX_ATTEMPTS)
+			.then(|| failure.last_at + self.backoff(failure.attempts))
+	}
+
+	pub fn record(&mut self, url: &str, error: &str, permanent: bool) {
+		self.record_at(url, error, permanent, Instant::now());
+	}
+
+	fn record_at(&mut self, url: &str, error: &str, permanent: bool, now: Instant) {
+		let failure = self.failures.entry(url.to_string()).or_insert(Failure {
+			attempts: 0,
+			last_error: String::new(),
+			permanent: false,
+			last_at: now,
+		});
+		failure.attempts += 1;
+		failure.last_error = error.to_string();
+		failure.permanent |= permanent;
+		failure.last_at = now;
+		if !failure.permanent && failure.attempts == MAX_ATTEMPTS {
+			log::warn!(
+				"Giving up on {} for {} min after {} failures: {}",
+				url,
+				FAILURE_TTL.as_secs() / 60,
+				failure.attempts,
+				error
+			);
+		}
+	}
+
+	/// Whether `url` must not be loaded now
+	pub fn blocks(&mut self, url: &str) -> bool {
+		self.blocks_at(url, Instant::now())
+	}
+
+	fn blocks_at(&mut self, url: &str, now: Instant) -> bool {
+		let Some(failure) = self.failures.get(url) else {
+			return false;
+		};
+		if failure.permanent {
+			return true;
+		}
+		let since = now.saturating_duration_since(failure.last_at);
+		if since >= FAILURE_TTL {
+			self.failures.remove(url);
+			return false;
+		}
+		failure.attempts >= MAX_ATTEMPTS || since < self.backoff(failure.attempts)
+	}
+
+	pub fn is_permanent(&self, url: &str) -> bool {
+		self.failures.get(url).is_some_and(|f| f.permanent)
+	}
+
+	/// Allow `url` again, e.g. when the user asks for a retry
+	pub fn forget(&mut self, url: &str) {
+		self.failures.remove(url);
+	}
+
+	/// Allow every URL again
+	pub fn clear(&mut self) {
+		self.failures.clear();
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Failure)> {
+		self.failures
+			.iter()
+			.map(|(url, failure)| (url.as_str(), failure))
+	}
+
+	/// URLs currently held back: (permanent, transient)
+	pub fn counts(&self) -> (usize, usize) {
+		let permanent = self.failures.values().filter(|f| f.permanent).count();
+		(permanent, self.failures.len() - permanent)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const URL: &str = "https://static1.e621.net/data/ab/cd/abcd.png";
+
+	#[test]
+	fn transient_failures_back_off_then_give_up_until_they_age_out() {
+		let mut failures = FailureMap::default();
+		let start = Instant::now();
+		failures.record_at(URL, "timed out", false, start);
+		assert!(failures.blocks_at(URL, start + Duration::from_secs(1)));
+		assert!(!failures.blocks_at(URL, start + RETRY_DELAY));
+		assert_eq!(failures.retry_at(URL), Some(start + RETRY_DELAY));
+
+		let second = start + RETRY_DELAY;
+		failures.record_at(URL, "timed out", false, second);
+		assert!(failures.blocks_at(URL, second + RETRY_DELAY));
+		assert!(!failures.blocks_at(URL, second + RETRY_DELAY * 2));
+
+		let third = second + RETRY_DELAY * 2;
+		failures.record_at(URL, "timed out", false, third);
+		assert!(failures.blocks_at(URL, third + Duration::from_secs(60)));
+		assert_eq!(failures.retry_at(URL), None);
+		assert_eq!(failures.counts(), (0, 1));
+
+		assert!(!failures.blocks_at(URL, third + FAILURE_TTL));
+		assert_eq!(failures.counts(), (0, 0));
+	}
+
+	#[test]
+	fn permanent_failures_stay_until_forgotten() {
+		let mut failures = FailureMap::default();
+		let start = Instant::now();
+		failures.record_at(URL, "HTTP Status: 404", true, start);
+		assert!(failures.blocks_at(URL, start + FAILURE_TTL * 2));
+		assert!(failures.is_permanent(URL));
+		assert_eq!(failures.counts(), (1, 0));
+
+		failures.forget(URL);
+		assert!(!failures.blocks(URL));
+	}
+}
@@ -0,0 +1,125 @@
+use std::collections::{HashMap, HashSet};
+
+/// Which set of workers a load was sent to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+	/// The single worker for the current item's full-res
+	Priority,
+	/// Shared workers for samples and prefetch
+	General,
+}
+
+/// Loads sent to a worker that haven't reported back, by URL.
+/// Both lanes claim URLs here, so a URL is never fetched twice at once,
+/// except when the stall watchdog deliberately adds a general copy.
+#[derive(Default)]
+pub struct InFlight {
+	loads: HashMap<String, Vec<Lane>>,
+	/// General loads done downloading and waiting on the decode pool
+	decoding: HashSet<String>,
+}
+
+impl InFlight {
+	pub fn contains(&self, url: &str) -> bool {
+		self.loads.contains_key(url)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.loads.is_empty()
+	}
+
+	/// Claim `url` for `lane` if nothing is loading it yet.
+	/// `send` hands the work to the lane and reports whether it was accepted.
+	pub fn claim(&mut self, url: &str, lane: Lane, send: impl FnOnce() -> bool) -> bool {
+		if self.contains(url) || !send() {
+			return false;
+		}
+		self.loads.insert(url.to_string(), vec![lane]);
+		true
+	}
+
+	/// Add a general copy of a load that only the priority worker holds
+	pub fn claim_rescue(&mut self, url: &str, send: impl FnOnce() -> bool) -> bool {
+		match self.loads.get_mut(url) {
+			Some(lanes) if lanes == &[Lane::Priority] && send() => {
+				lanes.push(Lane::General);
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Add a priority copy of a load that only general workers hold
+	pub fn claim_promotion(&mut self, url: &str, send: impl FnOnce() -> bool) -> bool {
+		match self.loads.get_mut(url) {
+			Some(lanes) if !lanes.contains(&Lane::Priority) && send() => {
+				lanes.push(Lane::Priority);
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Whether the priority worker holds `url` and no general worker does yet
+	pub fn priority_only(&self, url: &str) -> bool {
+		self.loads
+			.get(url)
+			.is_some_and(|lanes| lanes == &[Lane::Priority])
+	}
+
+	/// Every load with the lanes holding it and whether it's waiting on the decode pool
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &[Lane], bool)> {
+		self.loads
+			.iter()
+			.map(|(url, lanes)| (url.as_str(), lanes.as_slice(), self.decoding.contains(url)))
+	}
+
+	/// Number of URLs each lane is working on, as (priority, general)
+	pub fn lane_counts(&self) -> (usize, usize) {
+		let count = |lane| {
+			self.loads
+				.values()
+				.filter(|lanes| lanes.contains(&lane))
+				.count()
+		};
+		(count(Lane::Priority), count(Lane::General))
+	}
+
+	/// The general copy of `url` was downloaded and left its worker for the decode pool
+	pub fn start_decode(&mut self, url: &str) {
+		if self
+			.loads
+			.get(url)
+			.is_some_and(|lanes| lanes.contains(&Lane::General))
+		{
+			self.decoding.insert(url.to_string());
+		}
+	}
+
+	/// General loads still holding a worker, i.e. not yet handed to the decode pool
+	pub fn general_downloads(&self) -> usize {
+		self.loads
+			.iter()
+			.filter(|(url, lanes)| lanes.contains(&Lane::General) && !self.decoding.contains(*url))
+			.count()
+	}
+
+	/// Record a result from `lane`. Returns true when no other copy is still loading.
+	pub fn finish(&mut self, url: &str, lane: Lane) -> bool {
+		let Some(lanes) = self.loads.get_mut(url) else {
+			return true;
+		};
+		if let Some(index) = lanes.iter().position(|l| *l == lane) {
+			lanes.remove(index);
+		}
+		if lane == Lane::General {
+			self.decoding.remove(url);
+		}
+		if lanes.is_empty() {
+			self.loads.remove(url);
+			true
+		} else {
+			false
+		}
+	}
+}
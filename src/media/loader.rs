@@ -0,0 +1,374 @@
+use super::timing::FetchTimings;
+use crate::types::BoxFuture;
+use eframe::egui;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Time allowed to establish a connection to the CDN
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time allowed for a whole request, body included
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A failure that retrying will not fix: the file is gone or is not an image
+#[derive(Debug)]
+pub struct PermanentError(pub String);
+
+impl fmt::Display for PermanentError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl std::error::Error for PermanentError {}
+
+/// Whether a load error is worth retrying later. Decode errors are permanent,
+/// except for IO failures while reading the image.
+pub fn is_permanent(err: &anyhow::Error) -> bool {
+	err.downcast_ref::<PermanentError>().is_some()
+		|| err
+			.downcast_ref::<image::ImageError>()
+			.is_some_and(|e| !matches!(e, image::ImageError::IoError(_)))
+}
+
+/// What `MediaLoader::fetch` got for a URL
+pub enum Fetched {
+	/// The file as downloaded, for the decode pool
+	Raw {
+		bytes: Vec<u8>,
+		timings: FetchTimings,
+	},
+	/// Loaders that can't split downloading from decoding hand back the image
+	Decoded {
+		image: egui::ColorImage,
+		bytes: Vec<u8>,
+		timings: FetchTimings,
+	},
+}
+
+/// Fetches and decodes images for the media workers.
+/// Returns the decoded image along with the raw file bytes.
+pub trait MediaLoader: Send + Sync {
+	fn load<'a>(
+		&'a self,
+		url: &'a str,
+	) -> BoxFuture<'a, anyhow::Result<(egui::ColorImage, Vec<u8>)>>;
+
+	/// `load`, also saying where the time went. Loaders that can't tell
+	/// count all of it as download.
+	fn load_timed<'a>(
+		&'a self,
+		url: &'a str,
+	) -> BoxFuture<'a, anyhow::Result<(egui::ColorImage, Vec<u8>, FetchTimings)>> {
+		Box::pin(async move {
+			let started = Instant::now();
+			let (image, bytes) = self.load(url).await?;
+			let timings = FetchTimings {
+				download: started.elapsed(),
+				..Default::default()
+			};
+			Ok((image, bytes, timings))
+		})
+	}
+
+	/// Download without decoding, so the worker can move on to the next file
+	/// while `decode` runs on the decode pool. By default it loads and decodes
+	/// at once, like `load_timed`.
+	fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, anyhow::Result<Fetched>> {
+		Box::pin(async move {
+			let (image, bytes, timings) = self.load_timed(url).await?;
+			Ok(Fetched::Decoded {
+				image,
+				bytes,
+				timings,
+			})
+		})
+	}
+
+	/// Decode a file `fetch` returned raw. Runs on a blocking thread.
+	fn decode(&self, bytes: &[u8]) -> anyhow::Result<egui::ColorImage> {
+		decode_image(bytes)
+	}
+}
+
+/// Downloads over HTTP and decodes with the `image` crate
+pub struct HttpLoader {
+	client: reqwest::Client,
+}
+
+/// Client with the CDN timeouts, so a stalled connection can't hold a worker forever
+pub(super) fn cdn_client() -> reqwest::Client {
+	reqwest::Client::builder()
+		.connect_timeout(CONNECT_TIMEOUT)
+		.timeout(REQUEST_TIMEOUT)
+		.build()
+		.expect("Failed to build reqwest client")
+}
+
+impl HttpLoader {
+	pub fn new() -> Self {
+		Self {
+			client: cdn_client(),
+		}
+	}
+
+	async fn load_image(
+		&self,
+		url: &str,
+	) -> Result<(egui::ColorImage, Vec<u8>, FetchTimings), anyhow::Error> {
+		let (bytes, mut timings) = self.download(url).await?;
+		let started = Instant::now();
+		let color_image = decode_image(&bytes)?;
+		timings.decode = started.elapsed();
+		Ok((color_image, bytes, timings))
+	}
+
+	/// The body of `url`, checked to look like an image
+	async fn download(&self, url: &str) -> Result<(Vec<u8>, FetchTimings), anyhow::Error> {
+		let started = Instant::now();
+		let resp = self.client.get(url).send().await.map_err(describe)?;
+		let headers_at = Instant::now();
+		let status = resp.status();
+		if matches!(
+			status,
+			reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::GONE
+		) {
+			return Err(PermanentError(format!("HTTP Status: {}", status)).into());
+		}
+		if !status.is_success() {
+			anyhow::bail!("HTTP Status: {}", status);
+		}
+		let content_type = resp
+			.headers()
+			.get(reqwest::header::CONTENT_TYPE)
+			.and_then(|v| v.to_str().ok())
+			.map(str::to_owned);
+		let bytes = resp.bytes().await.map_err(describe)?;
+		let body_at = Instant::now();
+		check_image_content(content_type.as_deref(), &bytes)?;
+		let timings = FetchTimings {
+			first_byte: headers_at - started,
+			download: body_at - headers_at,
+			decode: Duration::ZERO,
+		};
+		Ok((bytes.into(), timings))
+	}
+}
+
+impl MediaLoader for HttpLoader {
+	fn load<'a>(
+		&'a self,
+		url: &'a str,
+	) -> BoxFuture<'a, anyhow::Result<(egui::ColorImage, Vec<u8>)>> {
+		Box::pin(async move {
+			let (image, bytes, _) = self.load_image(url).await?;
+			Ok((image, bytes))
+		})
+	}
+
+	fn load_timed<'a>(
+		&'a self,
+		url: &'a str,
+	) -> BoxFuture<'a, anyhow::Result<(egui::ColorImage, Vec<u8>, FetchTimings)>> {
+		Box::pin(self.load_image(url))
+	}
+
+	fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, anyhow::Result<Fetched>> {
+		Box::pin(async move {
+			let (bytes, timings) = self.download(url).await?;
+			Ok(Fetched::Raw { bytes, timings })
+		})
+	}
+}
+
+impl Default for HttpLoader {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Timeouts get a readable message; they stay retryable
+fn describe(err: reqwest::Error) -> anyhow::Error {
+	if err.is_timeout() {
+		anyhow::anyhow!("Request timed out")
+	} else {
+		err.into()
+	}
+}
+
+/// Image formats recognized by their leading bytes; all but AVIF are decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageSignature {
+	Png,
+	Jpeg,
+	Gif,
+	WebP,
+	Avif,
+}
+
+impl ImageSignature {
+	fn format(self) -> image::ImageFormat {
+		match self {
+			ImageSignature::Png => image::ImageFormat::Png,
+			ImageSignature::Jpeg => image::ImageFormat::Jpeg,
+			ImageSignature::Gif => image::ImageFormat::Gif,
+			ImageSignature::WebP => image::ImageFormat::WebP,
+			ImageSignature::Avif => image::ImageFormat::Avif,
+		}
+	}
+
+	pub fn name(self) -> &'static str {
+		match self {
+			ImageSignature::Png => "PNG",
+			ImageSignature::Jpeg => "JPEG",
+			ImageSignature::Gif => "GIF",
+			ImageSignature::WebP => "WebP",
+			ImageSignature::Avif => "AVIF",
+		}
+	}
+}
+
+pub fn sniff_signature(bytes: &[u8]) -> Option<ImageSignature> {
+	if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+		Some(ImageSignature::Png)
+	} else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+		Some(ImageSignature::Jpeg)
+	} else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+		Some(ImageSignature::Gif)
+	} else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+		Some(ImageSignature::WebP)
+	} else if bytes.len() >= 12
+		&& &bytes[4..8] == b"ftyp"
+		&& matches!(&bytes[8..12], b"avif" | b"avis")
+	{
+		Some(ImageSignature::Avif)
+	} else {
+		None
+	}
+}
+
+/// Decode with the format named by the magic bytes, not the URL's extension.
+/// Animated GIF/WebP yield their first frame.
+/// AVIF is recognized but not decoded: `image` needs dav1d for it, which we don't build with.
+pub fn decode_image(bytes: &[u8]) -> anyhow::Result<egui::ColorImage> {
+	let signature = sniff_signature(bytes)
+		.ok_or_else(|| PermanentError("unrecognized image format".to_string()))?;
+	if signature == ImageSignature::Avif {
+		return Err(PermanentError("AVIF images aren't supported".to_string()).into());
+	}
+	let img = image::load_from_memory_with_format(bytes, signature.format()).map_err(|e| {
+		anyhow::Error::new(e).context(format!("{} decode failed", signature.name()))
+	})?;
+	let size = [img.width() as usize, img.height() as usize];
+	let img_buffer = img.to_rgba8();
+	let pixels = img_buffer.as_flat_samples();
+	Ok(egui::ColorImage::from_rgba_unmultiplied(
+		size,
+		pixels.as_slice(),
+	))
+}
+
+/// Reject bodies that are clearly not a decodable image before handing them to the decoder.
+/// Magic bytes win over the header, since CDNs sometimes send `application/octet-stream`.
+pub fn check_image_content(content_type: Option<&str>, bytes: &[u8]) -> anyhow::Result<()> {
+	if sniff_signature(bytes).is_some() {
+		return Ok(());
+	}
+	let mime = content_type
+		.and_then(|ct| ct.split(';').next())
+		.map(|ct| ct.trim().to_lowercase());
+	match mime.as_deref() {
+		Some(mime) if mime.starts_with("text/") => {
+			anyhow::bail!("server returned {} (possible CDN error)", mime)
+		}
+		Some(mime) if mime.starts_with("video/") => {
+			Err(PermanentError(format!("server returned {}, not an image", mime)).into())
+		}
+		Some(mime) if !mime.starts_with("image/") => {
+			anyhow::bail!("server returned {} instead of an image", mime)
+		}
+		_ => anyhow::bail!(
+			"unrecognized image data ({} bytes, starts with {:02x?})",
+			bytes.len(),
+			&bytes[..bytes.len().min(8)]
+		),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+	const HTML: &[u8] = b"<!DOCTYPE html><html><body>502 Bad Gateway</body></html>";
+	const WEBP_FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/tiny.webp");
+	const AVIF_FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/tiny.avif");
+
+	#[test]
+	fn sniffs_supported_signatures() {
+		assert_eq!(sniff_signature(PNG), Some(ImageSignature::Png));
+		assert_eq!(
+			sniff_signature(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00]),
+			Some(ImageSignature::Jpeg)
+		);
+		assert_eq!(
+			sniff_signature(b"GIF89a\x01\x00"),
+			Some(ImageSignature::Gif)
+		);
+		assert_eq!(
+			sniff_signature(b"RIFF\x24\0\0\0WEBPVP8 "),
+			Some(ImageSignature::WebP)
+		);
+		assert_eq!(sniff_signature(AVIF_FIXTURE), Some(ImageSignature::Avif));
+		assert_eq!(sniff_signature(b"RIFF\x24\0\0\0WAVEfmt "), None);
+		assert_eq!(sniff_signature(HTML), None);
+		assert_eq!(sniff_signature(&[]), None);
+	}
+
+	#[test]
+	fn html_error_page_is_reported_clearly() {
+		let err = check_image_content(Some("text/html; charset=utf-8"), HTML).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"server returned text/html (possible CDN error)"
+		);
+		assert!(!is_permanent(&err));
+	}
+
+	#[test]
+	fn video_is_rejected() {
+		let mp4 = b"\0\0\0\x20ftypisom\0\0\x02\0";
+		let err = check_image_content(Some("video/mp4"), mp4).unwrap_err();
+		assert!(err.to_string().contains("video/mp4"));
+		assert!(is_permanent(&err));
+	}
+
+	#[test]
+	fn magic_bytes_win_over_header() {
+		assert!(check_image_content(Some("application/octet-stream"), PNG).is_ok());
+		assert!(check_image_content(None, PNG).is_ok());
+		assert!(check_image_content(Some("image/png"), HTML).is_err());
+	}
+
+	#[test]
+	fn decodes_webp_fixture() {
+		let image = decode_image(WEBP_FIXTURE).unwrap();
+		assert_eq!(image.size, [4, 3]);
+	}
+
+	#[test]
+	fn io_errors_while_decoding_are_transient() {
+		let io = image::ImageError::IoError(std::io::Error::other("disk went away"));
+		assert!(!is_permanent(
+			&anyhow::Error::new(io).context("PNG decode failed")
+		));
+		let corrupt = decode_image(&WEBP_FIXTURE[..40]).unwrap_err();
+		assert!(is_permanent(&corrupt));
+	}
+
+	#[test]
+	fn corrupt_webp_names_the_format() {
+		let err = decode_image(&WEBP_FIXTURE[..40]).unwrap_err();
+		assert!(err.to_string().starts_with("WebP"), "{}", err);
+	}
+}
@@ -3,29 +3,204 @@ use crate::reactor::{ComponentResponse, Event, MediaEvent, ViewEvent};
 use crate::types::LoadedMedia;
 use eframe::egui;
 
-use indexmap::IndexMap;
-use std::collections::{HashSet, VecDeque};
-use std::sync::Arc;
+use indexmap::{IndexMap, IndexSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex as AsyncMutex;
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+mod blur;
+pub mod download;
+mod failures;
+mod inflight;
+pub mod loader;
+mod pending;
+pub mod report;
+pub mod timing;
+
+use download::{DownloadManager, DownloadProgress};
+use failures::FailureMap;
+use inflight::{InFlight, Lane};
+use loader::{Fetched, HttpLoader, ImageSignature, MediaLoader, is_permanent, sniff_signature};
+use pending::PendingQueue;
+use report::{CacheCategory, CacheReport, EntryReport, FailureReport, InFlightReport};
+use timing::{FetchTimings, LoadStats, LoadTimer, LoadTimings};
 
 /// Number of background workers for general loading
 const NUM_WORKERS: usize = 4;
 
+/// Prefetch loads handed to the general workers at once: one each. Loads gone on
+/// to the decode pool don't count. The rest wait here, nearest first, so a newer
+/// prefetch can still reorder them.
+const GENERAL_DISPATCH_DEPTH: usize = NUM_WORKERS;
+
+/// A priority load running longer than this counts as stalled; the current
+/// item is then handed to a general worker as well
+const PRIORITY_STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most decode pool threads. More than one per general worker would mostly sit idle.
+const MAX_DECODERS: usize = NUM_WORKERS;
+
+/// Downloads waiting for a decoder; general workers pause while it's full
+const DECODE_QUEUE_DEPTH: usize = 2;
+
+/// Loads waiting for room in a full work channel; the oldest are dropped past this
+const MAX_DEFERRED: usize = 32;
+
+/// Number of decoded images kept in memory
+const MAX_CACHE_SIZE: usize = 100;
+
+/// Number of cached images kept as GPU textures; covers the gallery's five
+/// visible slots plus a little slack for quick back-and-forth navigation
+const MAX_TEXTURES: usize = 8;
+
 pub enum MediaMessage {
 	ImageLoaded {
 		url: String,
 		is_sample: bool,
 		full_url: String, // Key for cache lookup
-		result: Result<egui::ColorImage, String>,
+		lane: Lane,
+		result: Result<egui::ColorImage, LoadFailure>,
+		bytes: Option<Arc<Vec<u8>>>, // Raw file, only for loads of the current item's original
+		byte_len: usize,             // Size of the downloaded file, samples too
+		format: Option<ImageSignature>, // From the file's leading bytes
+		blurred: Option<Box<egui::ColorImage>>, // Preview for reveal mode, made while it's on
+		timings: LoadTimings,
 	},
+	/// The priority worker passed over a load queued for an item no longer shown
+	Skipped { url: String, lane: Lane },
+	/// A general worker handed its download to the decode pool and is free again
+	Downloaded { url: String },
+}
+
+/// Shared with the single priority worker
+#[derive(Clone, Default)]
+struct PriorityWorker {
+	/// When its running load started, if busy; read by the stall watchdog
+	busy_since: Arc<Mutex<Option<Instant>>>,
+	/// Full-res URL of the current item; queued work for any other is skipped
+	wanted: Arc<Mutex<Option<String>>>,
+}
+
+/// Switches every worker reads before and after each load
+#[derive(Clone, Default)]
+struct WorkerFlags {
+	/// Set on exit; workers stop instead of taking more work
+	stopping: Arc<AtomicBool>,
+	/// Make a blurred preview of every decoded image
+	blur_previews: Arc<AtomicBool>,
+}
+
+pub struct LoadFailure {
+	message: String,
+	/// Retrying won't help (e.g. 404); the URL is not requested again
+	permanent: bool,
 }
 
 /// A unit of work sent to a loading worker
+#[derive(Clone)]
 struct LoadWork {
 	url: String,
 	is_sample: bool,
 	cache_key: String,
+	lane: Lane,
+	queued_at: Instant,
+	/// Send the raw file back with the image, for saving it without downloading
+	/// again. Set only for the current item's original; prefetches let go of theirs.
+	keep_bytes: bool,
+}
+
+/// A download waiting for the decode pool
+struct DecodeJob {
+	work: LoadWork,
+	bytes: Vec<u8>,
+	queue_wait: Duration,
+	fetch: FetchTimings,
+	downloaded_at: Instant,
+}
+
+impl DecodeJob {
+	/// Decode on a blocking thread, where it can't hold up the async workers
+	async fn run(self, loader: Arc<dyn MediaLoader>, flags: WorkerFlags) -> MediaMessage {
+		let work = self.work.clone();
+		let (queue_wait, fetch) = (self.queue_wait, self.fetch);
+		let decoded = {
+			let flags = flags.clone();
+			tokio::task::spawn_blocking(move || self.decode(loader.as_ref(), &flags)).await
+		};
+		decoded.unwrap_or_else(|e| {
+			// Still report back, or the load would count as in flight forever
+			log::error!("Decoding {} panicked: {}", work.url, e);
+			let failure = Err(anyhow::anyhow!("decoder crashed"));
+			finished(work, failure, queue_wait, fetch, &flags)
+		})
+	}
+
+	fn decode(self, loader: &dyn MediaLoader, flags: &WorkerFlags) -> MediaMessage {
+		let started = Instant::now();
+		let image = loader.decode(&self.bytes);
+		let fetch = FetchTimings {
+			decode: started.elapsed(),
+			..self.fetch
+		};
+		// Time spent waiting for a decoder counts as queueing
+		let queue_wait = self.queue_wait + (started - self.downloaded_at);
+		finished(
+			self.work,
+			image.map(|image| (image, self.bytes)),
+			queue_wait,
+			fetch,
+			flags,
+		)
+	}
+}
+
+/// What a worker reports for `work` once it has been loaded, or has failed
+fn finished(
+	work: LoadWork,
+	loaded: anyhow::Result<(egui::ColorImage, Vec<u8>)>,
+	queue_wait: Duration,
+	fetch: FetchTimings,
+	flags: &WorkerFlags,
+) -> MediaMessage {
+	let (byte_len, format) = loaded
+		.as_ref()
+		.map(|(_, bytes)| (bytes.len(), sniff_signature(bytes)))
+		.unwrap_or((0, None));
+	let (result, bytes) = match loaded {
+		Ok((image, bytes)) => (Ok(image), work.keep_bytes.then(|| Arc::new(bytes))),
+		Err(e) => (
+			Err(LoadFailure {
+				message: e.to_string(),
+				permanent: is_permanent(&e),
+			}),
+			None,
+		),
+	};
+	let blurred = result
+		.as_ref()
+		.ok()
+		.filter(|_| flags.blur_previews.load(Ordering::Relaxed))
+		.map(|image| Box::new(blur::preview(image)));
+	MediaMessage::ImageLoaded {
+		url: work.url,
+		is_sample: work.is_sample,
+		full_url: work.cache_key,
+		lane: work.lane,
+		result,
+		bytes,
+		byte_len,
+		format,
+		blurred,
+		timings: LoadTimings {
+			queue_wait,
+			fetch,
+			upload: None,
+		},
+	}
 }
 
 /// Represents a media item's loading state
@@ -36,75 +211,306 @@ pub struct MediaItem {
 	pub is_video: bool,
 }
 
+/// What became of loads handed to the work channels, since start
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueueStats {
+	/// Taken by a channel, first try or not
+	pub enqueued: u64,
+	/// Found their channel full and waited for the next poll
+	pub deferred: u64,
+	/// Sent on a later poll after waiting
+	pub retried: u64,
+	/// Given up on: the wait overflowed, the item stopped being shown, or the
+	/// priority worker found it stale
+	pub dropped: u64,
+}
+
+/// How far an upcoming post's image has come
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Readiness {
+	/// Nothing to show yet, loading or not
+	NotStarted,
+	SampleReady,
+	FullReady,
+	/// Every image of the post failed for good
+	Failed,
+}
+
 /// State of an item in the cache
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CacheState {
 	SampleOnly,
 	Full,
 }
 
+/// What is known about the image on screen, for the info overlay
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MediaInfo {
+	/// Pixels of the version shown, as `[width, height]`
+	pub size: [usize; 2],
+	/// Size of its file as downloaded
+	pub byte_len: usize,
+	/// None when the file's leading bytes weren't recognized
+	pub format: Option<ImageSignature>,
+	pub state: CacheState,
+}
+
+impl MediaInfo {
+	/// "2480×3508 · 8.4 MB · PNG · full"
+	pub fn summary(&self) -> String {
+		const MB: usize = 1024 * 1024;
+		let size = if self.byte_len >= MB {
+			format!("{:.1} MB", self.byte_len as f64 / MB as f64)
+		} else {
+			format!("{} KB", self.byte_len.div_ceil(1024))
+		};
+		let mut parts = vec![format!("{}×{}", self.size[0], self.size[1]), size];
+		parts.extend(self.format.map(|format| format.name().to_string()));
+		parts.push(
+			match self.state {
+				CacheState::SampleOnly => "sample",
+				CacheState::Full => "full",
+			}
+			.to_string(),
+		);
+		parts.join(" · ")
+	}
+}
+
+/// Sample and original aspect ratios further apart than this are told to the view
+const ASPECT_TOLERANCE: f32 = 0.01;
+
+/// A sample that isn't the same shape as its original, e.g. cropped a little
+/// differently, in pixels as `[width, height]`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AspectMismatch {
+	pub sample: [usize; 2],
+	pub full: [usize; 2],
+}
+
+impl AspectMismatch {
+	fn detect(sample: [usize; 2], full: [usize; 2]) -> Option<Self> {
+		let aspect = |[w, h]: [usize; 2]| w as f32 / h.max(1) as f32;
+		let off = (aspect(sample) / aspect(full) - 1.0).abs();
+		(off > ASPECT_TOLERANCE).then_some(Self { sample, full })
+	}
+
+	pub fn sample_size(&self) -> egui::Vec2 {
+		egui::vec2(self.sample[0] as f32, self.sample[1] as f32)
+	}
+}
+
+/// Decoded pixels of a cached image. Only images about to be shown are
+/// uploaded; the pixels are kept so the texture can be dropped again.
+enum CachedImage {
+	Decoded(Arc<egui::ColorImage>),
+	Uploaded {
+		image: Arc<egui::ColorImage>,
+		media: LoadedMedia,
+	},
+}
+
+/// A cache entry: the image, which version of it, and the file it came from
+struct CachedMedia {
+	image: CachedImage,
+	state: CacheState,
+	/// Size of the downloaded file
+	byte_len: usize,
+	format: Option<ImageSignature>,
+	loaded_at: Instant,
+}
+
+impl CachedImage {
+	fn image(&self) -> &Arc<egui::ColorImage> {
+		match self {
+			CachedImage::Decoded(image) | CachedImage::Uploaded { image, .. } => image,
+		}
+	}
+
+	fn media(&self) -> Option<&LoadedMedia> {
+		match self {
+			CachedImage::Decoded(_) => None,
+			CachedImage::Uploaded { media, .. } => Some(media),
+		}
+	}
+}
+
 pub struct MediaCache {
-	// Cache keyed by full_url (or sample_url if no full)
-	cache: IndexMap<String, (LoadedMedia, CacheState)>,
-	loading_set: HashSet<String>,
-	pending_set: HashSet<String>,
+	// Cache keyed by full_url (or sample_url if no full), least recently used first
+	cache: IndexMap<String, CachedMedia>,
+	max_cached: usize,
+	// Cache keys of the last prefetch request; evicted only as a last resort
+	upcoming: HashSet<String>,
+	// Cache key of the image held in the compare pane; never evicted
+	pinned: Option<String>,
+	// Keys of entries currently holding a texture, least recently shown first
+	uploaded: IndexSet<String>,
+	// Loads handed to either lane that haven't reported back
+	inflight: InFlight,
 
 	// Current item being displayed
 	current_item: Option<MediaItem>,
 
 	// Pending queues for tiered loading
-	pending_samples: VecDeque<MediaItem>, // Breadth-first samples
-	pending_full: VecDeque<MediaItem>,    // Depth-first full versions
+	pending_samples: PendingQueue, // Breadth-first samples
+	pending_full: PendingQueue,    // Depth-first full versions
 
 	// Worker channels
 	priority_tx: mpsc::Sender<LoadWork>, // Current item full-res → priority worker
 	work_tx: mpsc::Sender<LoadWork>,     // Everything else → general workers
 
+	// Shared with the workers: exit, and whether to make blurred previews
+	flags: WorkerFlags,
+	// Blurred previews for reveal mode, uploaded right away since they are tiny
+	blurred: HashMap<String, LoadedMedia>,
+
+	// Loads that found their channel full, sent again at the start of each poll
+	deferred: VecDeque<LoadWork>,
+	queue_stats: QueueStats,
+	priority: PriorityWorker,
+	priority_stall_timeout: Duration,
+
 	// Result channel
 	receiver: mpsc::Receiver<MediaMessage>,
+	// Something changed since the last poll that loading or pruning may act on
+	dirty: bool,
+
+	// URLs that failed, held back from loading again until retried or aged out
+	failures: FailureMap,
+
+	// Where the time of each cached image's latest load went, next to `cache`
+	load_timings: HashMap<String, LoadTimings>,
+	// Cache key of the most recent finished load
+	last_loaded: Option<String>,
+	load_timer: LoadTimer,
+
+	// URL and encoded file of the current item's original, for saving it without
+	// downloading it again. Dropped on navigation, so one file at most is held.
+	current_raw: Option<(String, Arc<Vec<u8>>)>,
+	downloads: DownloadManager,
+	// Shared with the workers, for loads made outside the cache
+	loader: Arc<dyn MediaLoader>,
 
 	egui_ctx: egui::Context,
 }
 
 impl MediaCache {
 	pub fn new(ctx: &egui::Context) -> Self {
+		Self::new_with_loader(ctx, Box::new(HttpLoader::new()))
+	}
+
+	/// Construct with a custom fetch/decode backend, e.g. canned images in tests
+	pub fn new_with_loader(ctx: &egui::Context, loader: Box<dyn MediaLoader>) -> Self {
+		// Decoding is CPU-bound; hyperthreads add little
+		let decoders = num_cpus::get_physical().clamp(1, MAX_DECODERS);
+		Self::new_with_decoders(ctx, loader, decoders)
+	}
+
+	fn new_with_decoders(
+		ctx: &egui::Context,
+		loader: Box<dyn MediaLoader>,
+		decoders: usize,
+	) -> Self {
+		let loader: Arc<dyn MediaLoader> = Arc::from(loader);
 		log::info!(
-			"Initializing MediaCache with {} workers + 1 priority worker",
-			NUM_WORKERS
+			"Initializing MediaCache with {} workers + 1 priority worker, {} decoders",
+			NUM_WORKERS,
+			decoders
 		);
 
 		let (result_tx, result_rx) = mpsc::channel(100);
+		let flags = WorkerFlags::default();
 
 		// Priority channel: dedicated worker for current item full-res
 		let (priority_tx, priority_rx) = mpsc::channel::<LoadWork>(8);
-		Self::spawn_worker("priority", priority_rx, result_tx.clone(), ctx.clone());
+		let priority = PriorityWorker::default();
+		Self::spawn_worker(
+			"priority",
+			priority_rx,
+			result_tx.clone(),
+			loader.clone(),
+			priority.clone(),
+			flags.clone(),
+			ctx.clone(),
+		);
 
 		// General channel: NUM_WORKERS workers for samples + prefetch
 		let (work_tx, work_rx) = mpsc::channel::<LoadWork>(128);
 		let shared_rx = Arc::new(AsyncMutex::new(work_rx));
+		let (decode_tx, decode_rx) = mpsc::channel::<DecodeJob>(DECODE_QUEUE_DEPTH);
+		Self::spawn_decoders(
+			decoders,
+			decode_rx,
+			result_tx.clone(),
+			loader.clone(),
+			flags.clone(),
+			ctx.clone(),
+		);
 		for i in 0..NUM_WORKERS {
-			Self::spawn_shared_worker(i, shared_rx.clone(), result_tx.clone(), ctx.clone());
+			Self::spawn_shared_worker(
+				i,
+				shared_rx.clone(),
+				decode_tx.clone(),
+				result_tx.clone(),
+				loader.clone(),
+				flags.clone(),
+				ctx.clone(),
+			);
 		}
 
 		Self {
 			cache: IndexMap::new(),
-			loading_set: HashSet::new(),
-			pending_set: HashSet::new(),
+			max_cached: MAX_CACHE_SIZE,
+			upcoming: HashSet::new(),
+			pinned: None,
+			uploaded: IndexSet::new(),
+			inflight: InFlight::default(),
 			current_item: None,
-			pending_samples: VecDeque::new(),
-			pending_full: VecDeque::new(),
+			pending_samples: PendingQueue::default(),
+			pending_full: PendingQueue::default(),
 			priority_tx,
 			work_tx,
+			flags,
+			blurred: HashMap::new(),
+			priority,
+			priority_stall_timeout: PRIORITY_STALL_TIMEOUT,
+			deferred: VecDeque::new(),
+			queue_stats: QueueStats::default(),
 			receiver: result_rx,
+			dirty: false,
+			failures: FailureMap::default(),
+			load_timings: HashMap::new(),
+			last_loaded: None,
+			load_timer: LoadTimer::default(),
+			current_raw: None,
+			downloads: DownloadManager::new(ctx),
+			loader,
 			egui_ctx: ctx.clone(),
 		}
 	}
 
-	/// Spawn a dedicated worker with its own receiver
+	/// The loader the workers fetch and decode with
+	pub fn loader(&self) -> Arc<dyn MediaLoader> {
+		self.loader.clone()
+	}
+
+	/// Make blurred previews for reveal mode from the start
+	pub fn with_blur_previews(self, enabled: bool) -> Self {
+		self.flags.blur_previews.store(enabled, Ordering::Relaxed);
+		self
+	}
+
+	/// Spawn a dedicated worker with its own receiver.
+	/// It records when its running load started for the watchdog, and skips
+	/// work for any other URL than the one wanted without loading it. It decodes
+	/// its own downloads, so the current image never waits behind prefetches.
 	fn spawn_worker(
 		name: &'static str,
 		rx: mpsc::Receiver<LoadWork>,
 		result_tx: mpsc::Sender<MediaMessage>,
+		loader: Arc<dyn MediaLoader>,
+		priority: PriorityWorker,
+		flags: WorkerFlags,
 		ctx: egui::Context,
 	) {
 		let rx = Arc::new(AsyncMutex::new(rx));
@@ -115,35 +521,64 @@ impl MediaCache {
 					let mut rx = rx.lock().await;
 					rx.recv().await
 				};
-				let Some(work) = work else {
+				let Some(work) = work.filter(|_| !flags.stopping.load(Ordering::Relaxed)) else {
 					log::info!("Media worker [{}] shutting down", name);
 					break;
 				};
+				if priority.wanted.lock().unwrap().as_deref() != Some(work.url.as_str()) {
+					log::debug!("Worker [{}] skipping stale load: {}", name, work.url);
+					let _ = result_tx
+						.send(MediaMessage::Skipped {
+							url: work.url,
+							lane: work.lane,
+						})
+						.await;
+					ctx.request_repaint();
+					continue;
+				}
 				log::info!(
 					"Worker [{}] loading: {} (sample={})",
 					name,
 					work.url,
 					work.is_sample
 				);
-				let result = Self::load_image(&work.url).await;
-				let _ = result_tx
-					.send(MediaMessage::ImageLoaded {
-						url: work.url,
-						is_sample: work.is_sample,
-						full_url: work.cache_key,
-						result: result.map_err(|e| e.to_string()),
-					})
-					.await;
+				let queue_wait = work.queued_at.elapsed();
+				*priority.busy_since.lock().unwrap() = Some(Instant::now());
+				let message = match loader.fetch(&work.url).await {
+					Ok(Fetched::Raw { bytes, timings }) => {
+						let job = DecodeJob {
+							work,
+							bytes,
+							queue_wait,
+							fetch: timings,
+							downloaded_at: Instant::now(),
+						};
+						job.run(loader.clone(), flags.clone()).await
+					}
+					Ok(Fetched::Decoded {
+						image,
+						bytes,
+						timings,
+					}) => finished(work, Ok((image, bytes)), queue_wait, timings, &flags),
+					Err(e) => finished(work, Err(e), queue_wait, Default::default(), &flags),
+				};
+				*priority.busy_since.lock().unwrap() = None;
+				let _ = result_tx.send(message).await;
 				ctx.request_repaint();
 			}
 		});
 	}
 
-	/// Spawn a worker that shares a receiver with other workers
+	/// Spawn a worker that shares a receiver with other workers. It hands what it
+	/// downloads to the decode pool and takes the next load; while the decode queue
+	/// is full it waits, so downloads pause instead of piling up bytes.
 	fn spawn_shared_worker(
 		id: usize,
 		rx: Arc<AsyncMutex<mpsc::Receiver<LoadWork>>>,
+		decode_tx: mpsc::Sender<DecodeJob>,
 		result_tx: mpsc::Sender<MediaMessage>,
+		loader: Arc<dyn MediaLoader>,
+		flags: WorkerFlags,
 		ctx: egui::Context,
 	) {
 		tokio::spawn(async move {
@@ -153,7 +588,7 @@ impl MediaCache {
 					let mut rx = rx.lock().await;
 					rx.recv().await
 				};
-				let Some(work) = work else {
+				let Some(work) = work.filter(|_| !flags.stopping.load(Ordering::Relaxed)) else {
 					log::info!("Media worker [general-{}] shutting down", id);
 					break;
 				};
@@ -163,63 +598,181 @@ impl MediaCache {
 					work.url,
 					work.is_sample
 				);
-				let result = Self::load_image(&work.url).await;
-				let _ = result_tx
-					.send(MediaMessage::ImageLoaded {
-						url: work.url,
-						is_sample: work.is_sample,
-						full_url: work.cache_key,
-						result: result.map_err(|e| e.to_string()),
-					})
-					.await;
+				let queue_wait = work.queued_at.elapsed();
+				let message = match loader.fetch(&work.url).await {
+					Ok(Fetched::Raw { bytes, timings }) => {
+						let Ok(permit) = decode_tx.reserve().await else {
+							log::info!("Media worker [general-{}] shutting down", id);
+							break;
+						};
+						// Told before the job is queued, so it arrives before the result
+						let _ = result_tx
+							.send(MediaMessage::Downloaded {
+								url: work.url.clone(),
+							})
+							.await;
+						permit.send(DecodeJob {
+							work,
+							bytes,
+							queue_wait,
+							fetch: timings,
+							downloaded_at: Instant::now(),
+						});
+						ctx.request_repaint();
+						continue;
+					}
+					Ok(Fetched::Decoded {
+						image,
+						bytes,
+						timings,
+					}) => finished(work, Ok((image, bytes)), queue_wait, timings, &flags),
+					Err(e) => finished(work, Err(e), queue_wait, Default::default(), &flags),
+				};
+				let _ = result_tx.send(message).await;
 				ctx.request_repaint();
 			}
 		});
 	}
 
-	/// Shared image loading logic used by all workers
-	async fn load_image(url: &str) -> Result<egui::ColorImage, anyhow::Error> {
-		let resp = reqwest::get(url).await?;
-		if !resp.status().is_success() {
-			anyhow::bail!("HTTP Status: {}", resp.status());
+	/// Spawn the decode pool: `count` tasks, each decoding one download at a time
+	/// on a blocking thread
+	fn spawn_decoders(
+		count: usize,
+		rx: mpsc::Receiver<DecodeJob>,
+		result_tx: mpsc::Sender<MediaMessage>,
+		loader: Arc<dyn MediaLoader>,
+		flags: WorkerFlags,
+		ctx: egui::Context,
+	) {
+		let rx = Arc::new(AsyncMutex::new(rx));
+		for id in 0..count {
+			let rx = rx.clone();
+			let result_tx = result_tx.clone();
+			let loader = loader.clone();
+			let flags = flags.clone();
+			let ctx = ctx.clone();
+			tokio::spawn(async move {
+				loop {
+					let job = {
+						let mut rx = rx.lock().await;
+						rx.recv().await
+					};
+					let Some(job) = job else {
+						log::info!("Decoder [{}] shutting down", id);
+						break;
+					};
+					let message = job.run(loader.clone(), flags.clone()).await;
+					let _ = result_tx.send(message).await;
+					ctx.request_repaint();
+				}
+			});
 		}
-		let bytes = resp.bytes().await?;
-		let img = image::load_from_memory(&bytes)?;
-		let size = [img.width() as usize, img.height() as usize];
-		let img_buffer = img.to_rgba8();
-		let pixels = img_buffer.as_flat_samples();
-		let color_image = egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
-		Ok(color_image)
 	}
 
 	pub fn poll(&mut self) -> ComponentResponse {
 		let mut responses = Vec::new();
 
+		self.retry_deferred();
+
 		// Process completed loads
 		while let Ok(msg) = self.receiver.try_recv() {
+			self.dirty = true;
 			match msg {
+				MediaMessage::Skipped { url, lane } => {
+					self.inflight.finish(&url, lane);
+					self.queue_stats.dropped += 1;
+				}
+				MediaMessage::Downloaded { url } => {
+					self.inflight.start_decode(&url);
+				}
 				MediaMessage::ImageLoaded {
 					url,
 					is_sample,
 					full_url,
+					lane,
 					result,
+					bytes,
+					byte_len,
+					format,
+					blurred,
+					timings,
 				} => {
-					self.loading_set.remove(&url);
+					let last_copy = self.inflight.finish(&url, lane);
+					// A sample prefetched on its own belongs to the current post by now
+					let full_url = self
+						.current_item
+						.as_ref()
+						.filter(|item| is_sample && item.sample_url.as_ref() == Some(&full_url))
+						.and_then(|item| item.full_url.clone())
+						.unwrap_or(full_url);
+					if let Some(blurred) = blurred
+						&& self.flags.blur_previews.load(Ordering::Relaxed)
+					{
+						self.upload_blurred(&full_url, *blurred);
+					}
+					// Prefetched originals are decoded and their files let go
+					if let Some(bytes) = bytes
+						&& self.is_current_original(&url)
+					{
+						self.current_raw = Some((url.clone(), bytes));
+					}
 					match result {
 						Ok(color_image) => {
-							log::info!("Image loaded: {} (sample={})", url, is_sample);
-							let texture = self.egui_ctx.load_texture(
-								&url,
-								color_image,
-								egui::TextureOptions::LINEAR,
+							log::info!(
+								"Image loaded: {} (sample={}) in {} ms",
+								url,
+								is_sample,
+								timings.total().as_millis()
 							);
+							self.load_timer.record(&url, &timings);
+							self.load_timings.insert(full_url.clone(), timings);
+							self.last_loaded = Some(full_url.clone());
+							// The original takes the place of a sample already decoded
+							let replaced = self
+								.cache
+								.get(&full_url)
+								.filter(|entry| !is_sample && entry.state == CacheState::SampleOnly)
+								.map(|sample| sample.image.image().size);
+							let mismatch = replaced.and_then(|sample| {
+								AspectMismatch::detect(sample, color_image.size)
+							});
+							if let Some(mismatch) = mismatch {
+								log::warn!(
+									"Sample of {} is {}x{} but the original is {}x{}",
+									full_url,
+									mismatch.sample[0],
+									mismatch.sample[1],
+									mismatch.full[0],
+									mismatch.full[1]
+								);
+							}
 							let state = if is_sample {
 								CacheState::SampleOnly
 							} else {
 								CacheState::Full
 							};
-							self.cache
-								.insert(full_url.clone(), (LoadedMedia::Image { texture }, state));
+							self.cache.insert(
+								full_url.clone(),
+								CachedMedia {
+									image: CachedImage::Decoded(Arc::new(color_image)),
+									state,
+									byte_len,
+									format,
+									loaded_at: Instant::now(),
+								},
+							);
+							self.touch(&full_url);
+							// Replace the texture right away if this entry is on screen
+							let is_current = self
+								.current_item
+								.as_ref()
+								.is_some_and(|item| self.get_cache_key(item) == full_url);
+							if is_current || self.uploaded.contains(&full_url) {
+								self.upload(&full_url);
+							}
+							if is_current && replaced.is_some() {
+								responses.push(Event::View(ViewEvent::SampleUpgraded { mismatch }));
+							}
 
 							let is_initial_load = if let Some(ref current) = self.current_item {
 								if is_sample {
@@ -242,19 +795,46 @@ impl MediaCache {
 								}
 							}
 						}
-						Err(error) => {
-							log::error!("Image load failed: {} - {}", url, error);
-							responses.push(Event::Media(MediaEvent::LoadError { error }));
+						Err(failure) if !last_copy && !failure.permanent => {
+							// The other lane may still get it
+							log::warn!(
+								"Image load failed on the {:?} lane, another copy is still loading: {} - {}",
+								lane,
+								url,
+								failure.message
+							);
+						}
+						Err(failure) => {
+							log::error!(
+								"Image load failed: {} - {} (permanent={})",
+								url,
+								failure.message,
+								failure.permanent
+							);
+							self.failures
+								.record(&url, &failure.message, failure.permanent);
+							let current = self.current_item_failed(&full_url, failure.permanent);
+							responses.push(Event::Media(MediaEvent::LoadError {
+								cache_key: full_url,
+								error: failure.message,
+								permanent: failure.permanent,
+								current,
+							}));
 						}
 					}
 				}
 			}
 		}
 
+		self.downloads.poll();
+
+		self.check_priority_stall();
+
 		// Process loading queue with priority logic
 		self.process_loading_queue();
 
 		self.prune_cache();
+		self.dirty = false;
 
 		if responses.is_empty() {
 			ComponentResponse::none()
@@ -270,10 +850,10 @@ impl MediaCache {
 			let (has_sample, has_full) = self
 				.cache
 				.get(&cache_key)
-				.map(|(_, state)| {
+				.map(|entry| {
 					(
 						true,
-						matches!(state, CacheState::Full), // Full implies sample content too
+						matches!(entry.state, CacheState::Full), // Full implies sample content too
 					)
 				})
 				.unwrap_or((false, false));
@@ -281,12 +861,12 @@ impl MediaCache {
 			let sample_loading = current
 				.sample_url
 				.as_ref()
-				.map(|u| self.loading_set.contains(u))
+				.map(|u| self.inflight.contains(u))
 				.unwrap_or(false);
 			let full_loading = current
 				.full_url
 				.as_ref()
-				.map(|u| self.loading_set.contains(u))
+				.map(|u| self.inflight.contains(u))
 				.unwrap_or(false);
 
 			// Kick off sample via general workers
@@ -313,44 +893,157 @@ impl MediaCache {
 			}
 		}
 
-		// Drain pending samples into general workers
-		while let Some(item) = self.pending_samples.pop_front() {
-			let cache_key = self.get_cache_key(&item);
-			if self.cache.contains_key(&cache_key) {
-				continue;
+		// Feed the general workers a few loads at a time, nearest samples first,
+		// then nearest full versions
+		while self.inflight.general_downloads() < GENERAL_DISPATCH_DEPTH {
+			if let Some((distance, item)) = self.pending_samples.pop() {
+				self.dispatch_sample(distance, item);
+			} else if let Some((_, item)) = self.pending_full.pop() {
+				self.dispatch_full(item);
+			} else {
+				break;
 			}
+		}
+	}
 
-			if let Some(ref sample_url) = item.sample_url {
-				if !self.loading_set.contains(sample_url) {
-					self.enqueue_load(sample_url.clone(), true, cache_key, false);
-					self.pending_full.push_back(item);
-				}
-			} else if let Some(ref full_url) = item.full_url {
-				if !self.loading_set.contains(full_url) {
-					self.enqueue_load(full_url.clone(), false, cache_key, false);
-				}
-			}
+	fn dispatch_sample(&mut self, distance: usize, item: MediaItem) {
+		let cache_key = self.get_cache_key(&item);
+		if self.cache.contains_key(&cache_key) {
+			return;
 		}
 
-		// Drain pending full versions into general workers
-		while let Some(item) = self.pending_full.pop_front() {
-			let cache_key = self.get_cache_key(&item);
-			let has_full = self
-				.cache
-				.get(&cache_key)
-				.map(|(_, state)| matches!(state, CacheState::Full))
-				.unwrap_or(false);
-			if has_full {
-				continue;
+		if let Some(ref sample_url) = item.sample_url {
+			if !self.inflight.contains(sample_url) {
+				self.enqueue_load(sample_url.clone(), true, cache_key, false);
+				self.pending_full.push(distance, item);
 			}
-			if let Some(ref full_url) = item.full_url {
-				if !self.loading_set.contains(full_url) {
-					self.enqueue_load(full_url.clone(), false, cache_key, false);
-				}
+		} else if let Some(ref full_url) = item.full_url {
+			if !self.inflight.contains(full_url) {
+				self.enqueue_load(full_url.clone(), false, cache_key, false);
+			}
+		}
+	}
+
+	fn dispatch_full(&mut self, item: MediaItem) {
+		let cache_key = self.get_cache_key(&item);
+		let has_full = self
+			.cache
+			.get(&cache_key)
+			.map(|entry| matches!(entry.state, CacheState::Full))
+			.unwrap_or(false);
+		if has_full {
+			return;
+		}
+		if let Some(ref full_url) = item.full_url {
+			if !self.inflight.contains(full_url) {
+				self.enqueue_load(full_url.clone(), false, cache_key, false);
 			}
 		}
 	}
 
+	/// Queue an upcoming item `distance` posts ahead, at the stage it has reached
+	fn queue_prefetch(&mut self, distance: usize, item: MediaItem) {
+		self.adopt_sample_entry(&item);
+		let cache_key = self.get_cache_key(&item);
+		let state = self.cache.get(&cache_key).map(|entry| entry.state);
+		if state == Some(CacheState::Full) || self.inflight.contains(&cache_key) {
+			return;
+		}
+		let sample_started = state.is_some()
+			|| item
+				.sample_url
+				.as_ref()
+				.is_some_and(|url| self.inflight.contains(url));
+		if !sample_started {
+			self.pending_samples.push(distance, item);
+		} else if item.full_url.is_some() {
+			self.pending_full.push(distance, item);
+		}
+	}
+
+	/// A post prefetched by its sample alone is cached under the sample's URL. Once
+	/// it's asked for with its original as well, the entry moves to the original's URL.
+	fn adopt_sample_entry(&mut self, item: &MediaItem) {
+		let (Some(sample_url), Some(full_url)) = (&item.sample_url, &item.full_url) else {
+			return;
+		};
+		if self.cache.contains_key(full_url) {
+			return;
+		}
+		let Some(entry) = self.cache.shift_remove(sample_url) else {
+			return;
+		};
+		self.cache.insert(full_url.clone(), entry);
+		if self.uploaded.shift_remove(sample_url) {
+			self.uploaded.insert(full_url.clone());
+		}
+		if let Some(timings) = self.load_timings.remove(sample_url) {
+			self.load_timings.insert(full_url.clone(), timings);
+		}
+		if let Some(blurred) = self.blurred.remove(sample_url) {
+			self.blurred.insert(full_url.clone(), blurred);
+		}
+	}
+
+	/// Watchdog for the single priority worker: if it has been stuck on one load for
+	/// too long, send the current item's full-res to a general worker as well
+	fn check_priority_stall(&mut self) {
+		let stalled_for = self
+			.priority
+			.busy_since
+			.lock()
+			.unwrap()
+			.map(|since| since.elapsed())
+			.filter(|elapsed| *elapsed >= self.priority_stall_timeout);
+		let Some(stalled_for) = stalled_for else {
+			return;
+		};
+		let Some(current) = &self.current_item else {
+			return;
+		};
+		let Some(full_url) = current.full_url.clone() else {
+			return;
+		};
+		if !self.inflight.priority_only(&full_url) {
+			return;
+		}
+		let work = LoadWork {
+			url: full_url.clone(),
+			is_sample: false,
+			cache_key: self.get_cache_key(current),
+			lane: Lane::General,
+			queued_at: Instant::now(),
+			keep_bytes: true,
+		};
+		let work_tx = &self.work_tx;
+		if self
+			.inflight
+			.claim_rescue(&full_url, || work_tx.try_send(work).is_ok())
+		{
+			log::warn!(
+				"Priority worker stalled for {:.1}s, loading {} on a general worker",
+				stalled_for.as_secs_f32(),
+				full_url
+			);
+		}
+	}
+
+	/// Whether a failure for `cache_key` leaves the current item with nothing to show.
+	/// For permanent failures, every URL of the item must have failed.
+	fn current_item_failed(&self, cache_key: &str, permanent: bool) -> bool {
+		let Some(current) = &self.current_item else {
+			return false;
+		};
+		if self.get_cache_key(current) != cache_key || self.cache.contains_key(cache_key) {
+			return false;
+		}
+		!permanent
+			|| [&current.sample_url, &current.full_url]
+				.into_iter()
+				.flatten()
+				.all(|url| self.failures.is_permanent(url))
+	}
+
 	fn get_cache_key(&self, item: &MediaItem) -> String {
 		item.full_url
 			.clone()
@@ -359,38 +1052,114 @@ impl MediaCache {
 	}
 
 	/// Enqueue a load to either the priority or general work channel.
+	/// Every path goes through here, so a URL already in flight on either lane is skipped.
 	fn enqueue_load(&mut self, url: String, is_sample: bool, cache_key: String, priority: bool) {
-		if self.loading_set.contains(&url) {
+		if self.failures.blocks(&url) {
 			return;
 		}
+		let lane = if priority {
+			Lane::Priority
+		} else {
+			Lane::General
+		};
 		let work = LoadWork {
+			keep_bytes: !is_sample && self.is_current_original(&url),
 			url: url.clone(),
 			is_sample,
 			cache_key,
+			lane,
+			queued_at: Instant::now(),
 		};
-		let tx = if priority {
-			&self.priority_tx
-		} else {
-			&self.work_tx
+		match self.send_work(work) {
+			Ok(true) => log::info!(
+				"Enqueued load: {} (sample={}, lane={:?})",
+				url,
+				is_sample,
+				lane
+			),
+			Ok(false) => {}
+			Err(work) => self.defer(work),
+		}
+	}
+
+	/// Hand `work` to its lane's channel and claim the URL. Returns false when the
+	/// URL is already loading or the workers are gone, and the work back when the
+	/// channel is full.
+	fn send_work(&mut self, work: LoadWork) -> Result<bool, LoadWork> {
+		let tx = match work.lane {
+			Lane::Priority => &self.priority_tx,
+			Lane::General => &self.work_tx,
 		};
-		match tx.try_send(work) {
-			Ok(()) => {
-				self.loading_set.insert(url.clone());
-				log::info!(
-					"Enqueued load: {} (sample={}, priority={})",
-					url,
-					is_sample,
-					priority
-				);
+		let url = work.url.clone();
+		let mut full = None;
+		let claimed = self
+			.inflight
+			.claim(&url, work.lane, || match tx.try_send(work) {
+				Ok(()) => true,
+				Err(TrySendError::Full(work)) => {
+					full = Some(work);
+					false
+				}
+				Err(TrySendError::Closed(_)) => false,
+			});
+		match full {
+			Some(work) => Err(work),
+			None => {
+				self.queue_stats.enqueued += u64::from(claimed);
+				Ok(claimed)
+			}
+		}
+	}
+
+	/// Keep work that found its channel full for the next poll
+	fn defer(&mut self, work: LoadWork) {
+		if self.deferred.iter().any(|w| w.url == work.url) {
+			return;
+		}
+		log::warn!(
+			"Work queue full, deferring: {} (lane={:?})",
+			work.url,
+			work.lane
+		);
+		self.queue_stats.deferred += 1;
+		self.deferred.push_back(work);
+		if self.deferred.len() > MAX_DEFERRED
+			&& let Some(dropped) = self.deferred.pop_front()
+		{
+			log::warn!("Too many deferred loads, dropping: {}", dropped.url);
+			self.queue_stats.dropped += 1;
+		}
+	}
+
+	/// Send deferred work again, oldest first. Priority work for an item no longer
+	/// shown is dropped; whatever still finds its channel full waits again.
+	fn retry_deferred(&mut self) {
+		if self.deferred.is_empty() {
+			return;
+		}
+		let wanted = self.priority.wanted.lock().unwrap().clone();
+		for work in std::mem::take(&mut self.deferred) {
+			let stale = work.lane == Lane::Priority && wanted.as_ref() != Some(&work.url);
+			if stale || self.failures.blocks(&work.url) {
+				log::debug!("Dropping deferred load: {}", work.url);
+				self.queue_stats.dropped += 1;
+				continue;
 			}
-			Err(e) => {
-				log::warn!("Work queue full, deferring: {} ({})", url, e);
+			let url = work.url.clone();
+			match self.send_work(work) {
+				Ok(true) => {
+					log::info!("Enqueued deferred load: {}", url);
+					self.queue_stats.retried += 1;
+				}
+				Ok(false) => {}
+				Err(work) => self.deferred.push_back(work),
 			}
 		}
 	}
 
 	pub fn handle(&mut self, event: &Event) -> ComponentResponse {
 		let mut responses = Vec::new();
+		self.dirty = true;
 
 		match event {
 			Event::Media(MediaEvent::LoadRequest {
@@ -409,39 +1178,101 @@ impl MediaCache {
 					full_url: full_url.clone(),
 					is_video: *is_video,
 				};
+				if self
+					.current_raw
+					.as_ref()
+					.is_some_and(|(url, _)| item.full_url.as_ref() != Some(url))
+				{
+					self.current_raw = None;
+				}
 				self.current_item = Some(item.clone());
+				*self.priority.wanted.lock().unwrap() = item.full_url.clone();
 
-				// Check if already cached
+				// Check if already cached; upload now so it shows on this frame
+				self.adopt_sample_entry(&item);
 				let cache_key = self.get_cache_key(&item);
 				if self.cache.contains_key(&cache_key) {
+					self.upload(&cache_key);
 					responses.push(Event::View(ViewEvent::MediaReady));
 				}
 			}
 			Event::Media(MediaEvent::Prefetch { urls }) => {
 				log::debug!("Prefetch requested for {} items", urls.len());
 
-				// Clear old pending items and reset
+				// Queued again from scratch with the new distances;
+				// loads already handed to workers carry on
 				self.pending_samples.clear();
 				self.pending_full.clear();
-				self.pending_set.clear();
+				self.upcoming.clear();
 
-				for (sample_url, full_url, is_video) in urls {
+				for (offset, (sample_url, full_url, is_video)) in urls.iter().enumerate() {
 					let item = MediaItem {
 						sample_url: sample_url.clone(),
 						full_url: full_url.clone(),
 						is_video: *is_video,
 					};
-					let cache_key = self.get_cache_key(&item);
-
-					if !self.cache.contains_key(&cache_key)
-						&& !self.loading_set.contains(&cache_key)
-						&& !self.pending_set.contains(&cache_key)
+					// Wrapping around a short list can name a post twice
+					if self.upcoming.insert(self.get_cache_key(&item)) {
+						self.queue_prefetch(offset + 1, item);
+					}
+				}
+				log::debug!(
+					"Prefetch queued: {} samples, {} full versions",
+					self.pending_samples.len(),
+					self.pending_full.len()
+				);
+			}
+			Event::Media(MediaEvent::QueueDownloads { items }) => {
+				for (url, path) in items {
+					let cached = self
+						.current_raw_bytes()
+						.filter(|(raw_url, _)| raw_url == url)
+						.map(|(_, bytes)| bytes.clone());
+					self.downloads.enqueue(url.clone(), path.clone(), cached);
+				}
+				log::info!("Download queue: {} total", self.downloads.progress().total);
+			}
+			Event::Media(MediaEvent::RetryCurrent) => {
+				if let Some(current) = &self.current_item {
+					log::info!("Retrying current item: {:?}", current.full_url);
+					for url in [&current.sample_url, &current.full_url]
+						.into_iter()
+						.flatten()
 					{
-						self.pending_set.insert(cache_key);
-						self.pending_samples.push_back(item);
+						self.failures.forget(url);
 					}
 				}
 			}
+			Event::Media(MediaEvent::ForceFullRes) => self.force_full_res(),
+			Event::Media(MediaEvent::ReloadCurrent) => self.reload_current(),
+			Event::Media(MediaEvent::ReuploadTextures) => self.reupload_textures(),
+			Event::Media(MediaEvent::Pin { cache_key }) => {
+				log::info!("Pinned for comparison: {:?}", cache_key);
+				self.pinned = cache_key.clone();
+			}
+			Event::Media(MediaEvent::Evict { cache_key }) => {
+				let current_key = self.current_item.as_ref().map(|i| self.get_cache_key(i));
+				if current_key.as_ref() == Some(cache_key) {
+					self.reload_current();
+				} else {
+					log::info!("Evicting {}", cache_key);
+					self.evict(cache_key);
+				}
+			}
+			Event::Media(MediaEvent::ClearCache { category }) => self.clear(*category),
+			Event::Media(MediaEvent::SetBlurPreviews { enabled }) => {
+				log::info!("Blurred previews {}", if *enabled { "on" } else { "off" });
+				self.flags.blur_previews.store(*enabled, Ordering::Relaxed);
+				if !enabled {
+					self.blurred.clear();
+				}
+			}
+			Event::Media(MediaEvent::SetDownloadsPaused { paused }) => {
+				self.downloads.set_paused(*paused);
+			}
+			Event::Media(MediaEvent::CancelDownloads) => {
+				self.downloads.cancel();
+			}
 			_ => {}
 		}
 
@@ -452,43 +1283,356 @@ impl MediaCache {
 		}
 	}
 
+	/// Get the current item's original onto the priority worker, retrying it if it failed
+	fn force_full_res(&mut self) {
+		let Some(current) = self.current_item.clone() else {
+			return;
+		};
+		let Some(full_url) = current.full_url.clone() else {
+			log::info!("Current item has no full-res version");
+			return;
+		};
+		if self.current_state() == Some(CacheState::Full) {
+			log::debug!("Full-res already shown: {}", full_url);
+			return;
+		}
+		self.failures.forget(&full_url);
+
+		let cache_key = self.get_cache_key(&current);
+		if !self.inflight.contains(&full_url) {
+			log::info!("Forcing full-res: {}", full_url);
+			self.enqueue_load(full_url, false, cache_key, true);
+			return;
+		}
+		let work = LoadWork {
+			url: full_url.clone(),
+			is_sample: false,
+			cache_key,
+			lane: Lane::Priority,
+			queued_at: Instant::now(),
+			keep_bytes: true,
+		};
+		let priority_tx = &self.priority_tx;
+		if self
+			.inflight
+			.claim_promotion(&full_url, || priority_tx.try_send(work).is_ok())
+		{
+			log::info!("Forcing full-res onto the priority worker: {}", full_url);
+		}
+	}
+
+	/// Forget everything held for the current item; the next poll loads it again
+	fn reload_current(&mut self) {
+		let Some(current) = self.current_item.clone() else {
+			return;
+		};
+		let cache_key = self.get_cache_key(&current);
+		log::info!("Reloading current item: {}", cache_key);
+		self.evict(&cache_key);
+		self.current_raw = None;
+		for url in [&current.sample_url, &current.full_url]
+			.into_iter()
+			.flatten()
+		{
+			self.failures.forget(url);
+		}
+	}
+
+	/// Replace every texture with a fresh upload of the same pixels, in the same order
+	fn reupload_textures(&mut self) {
+		let keys: Vec<String> = self.uploaded.drain(..).collect();
+		for key in &keys {
+			if let Some(entry) = self.cache.get_mut(key) {
+				entry.image = CachedImage::Decoded(entry.image.image().clone());
+			}
+			self.upload(key);
+		}
+		// Blurred previews are made again from the cache when next drawn
+		self.blurred.clear();
+		log::warn!("Uploaded {} textures again", keys.len());
+	}
+
+	/// Make sure the entry for `key` has a texture, marking it as most recently shown
+	fn upload(&mut self, key: &str) {
+		let Some(CachedMedia { image: entry, .. }) = self.cache.get_mut(key) else {
+			return;
+		};
+		if let CachedImage::Decoded(image) = entry {
+			let started = Instant::now();
+			let texture =
+				self.egui_ctx
+					.load_texture(key, image.clone(), egui::TextureOptions::LINEAR);
+			*entry = CachedImage::Uploaded {
+				image: image.clone(),
+				media: LoadedMedia::Image { texture },
+			};
+			let took = started.elapsed();
+			self.load_timer.record_upload(took);
+			if let Some(timings) = self.load_timings.get_mut(key) {
+				timings.upload.get_or_insert(took);
+			}
+		}
+		self.uploaded.shift_remove(key);
+		self.uploaded.insert(key.to_string());
+		// Over budget: the next poll releases textures
+		self.dirty |= self.uploaded.len() > MAX_TEXTURES;
+	}
+
+	/// Whether `poll` has anything to do: results to take in, state changed since
+	/// the last one, downloads reporting back, or a priority load to check on
+	pub fn has_pending(&self) -> bool {
+		self.dirty
+			|| !self.deferred.is_empty()
+			|| !self.receiver.is_empty()
+			|| self.downloads.has_results()
+			|| self
+				.priority
+				.busy_since
+				.lock()
+				.unwrap()
+				.is_some_and(|since| since.elapsed() >= self.priority_stall_timeout)
+			|| self.next_retry().is_some_and(|at| at <= Instant::now())
+	}
+
+	/// When a transient failure of the current item may be loaded again, so the
+	/// retry isn't left waiting for something else to poll
+	pub fn next_retry(&self) -> Option<Instant> {
+		let current = self.current_item.as_ref()?;
+		let state = self
+			.cache
+			.get(&self.get_cache_key(current))
+			.map(|entry| entry.state);
+		let sample = current
+			.sample_url
+			.as_ref()
+			.filter(|_| state.is_none() && !current.is_video);
+		let full = current
+			.full_url
+			.as_ref()
+			.filter(|_| state != Some(CacheState::Full));
+		[sample, full]
+			.into_iter()
+			.flatten()
+			.filter(|url| !self.inflight.contains(url))
+			.filter_map(|url| self.failures.retry_at(url))
+			.min()
+	}
+
+	fn upload_blurred(&mut self, key: &str, image: egui::ColorImage) {
+		let texture = self.egui_ctx.load_texture(
+			format!("{}#blurred", key),
+			image,
+			egui::TextureOptions::LINEAR,
+		);
+		self.blurred
+			.insert(key.to_string(), LoadedMedia::Image { texture });
+	}
+
+	/// Blurred preview of a post's image. Images decoded before reveal mode was
+	/// turned on get theirs here, once.
+	pub fn blurred_by_post(&mut self, post: &Post) -> Option<&LoadedMedia> {
+		let key = Self::post_cache_key(post);
+		if !self.flags.blur_previews.load(Ordering::Relaxed) {
+			return None;
+		}
+		if !self.blurred.contains_key(key) {
+			let entry = self.cache.get(key)?;
+			let preview = blur::preview(entry.image.image());
+			self.upload_blurred(key, preview);
+		}
+		self.blurred.get(key)
+	}
+
+	/// Upload a neighbour's image ahead of drawing it, e.g. in gallery mode
+	pub fn ensure_uploaded(&mut self, post: &Post) {
+		let key = Self::post_cache_key(post);
+		if self.cache.contains_key(key) {
+			self.upload(key);
+		}
+	}
+
+	/// Mark the entry for `key` as most recently used
+	fn touch(&mut self, key: &str) {
+		if let Some(index) = self.cache.get_index_of(key) {
+			let last = self.cache.len() - 1;
+			if index != last {
+				self.cache.move_index(index, last);
+			}
+		}
+	}
+
 	fn prune_cache(&mut self) {
-		const MAX_CACHE_SIZE: usize = 100;
-		if self.cache.len() > MAX_CACHE_SIZE {
-			let current_key = self.current_item.as_ref().map(|i| self.get_cache_key(i));
-			let to_remove: Vec<String> = self
-				.cache
-				.keys()
-				.filter(|k| Some(*k) != current_key.as_ref())
-				.take(self.cache.len() - MAX_CACHE_SIZE)
+		let current_key = self.current_item.as_ref().map(|i| self.get_cache_key(i));
+		let on_screen =
+			|k: &String| Some(k) == current_key.as_ref() || Some(k) == self.pinned.as_ref();
+
+		// Drop textures of images no longer on screen before evicting anything
+		if self.uploaded.len() > MAX_TEXTURES {
+			let to_downgrade: Vec<String> = self
+				.uploaded
+				.iter()
+				.filter(|k| !on_screen(k))
+				.take(self.uploaded.len() - MAX_TEXTURES)
 				.cloned()
 				.collect();
+			for key in &to_downgrade {
+				self.uploaded.shift_remove(key);
+				if let Some(entry) = self.cache.get_mut(key) {
+					entry.image = CachedImage::Decoded(entry.image.image().clone());
+				}
+			}
+			log::debug!(
+				"Released {} textures, {} of {} cached images on the GPU",
+				to_downgrade.len(),
+				self.uploaded.len(),
+				self.cache.len()
+			);
+		}
+
+		if self.cache.len() > self.max_cached {
+			let excess = self.cache.len() - self.max_cached;
+			let evictable = self.cache.keys().filter(|k| !on_screen(k));
+			// Least recently used first, sparing what is about to be shown
+			let mut to_remove: Vec<String> = evictable
+				.clone()
+				.filter(|k| !self.upcoming.contains(*k))
+				.take(excess)
+				.cloned()
+				.collect();
+			if to_remove.len() < excess {
+				let still_over = excess - to_remove.len();
+				to_remove.extend(
+					evictable
+						.filter(|k| self.upcoming.contains(*k))
+						.take(still_over)
+						.cloned(),
+				);
+			}
 
 			if !to_remove.is_empty() {
 				log::debug!("Pruning {} items from cache", to_remove.len());
 			}
 
 			for key in to_remove {
-				self.cache.shift_remove(&key);
+				self.evict(&key);
 			}
 		}
 	}
 
-	/// Get the best available media for the current item
-	pub fn get_current_media(&self) -> Option<&LoadedMedia> {
+	/// Drop a cached image along with its texture, preview and timings
+	fn evict(&mut self, key: &str) {
+		self.cache.shift_remove(key);
+		self.uploaded.shift_remove(key);
+		self.blurred.remove(key);
+		self.load_timings.remove(key);
+	}
+
+	/// Empty one part of the cache. What is on screen stays; the next prefetch
+	/// queues the upcoming posts again.
+	fn clear(&mut self, category: CacheCategory) {
+		match category {
+			CacheCategory::Images => {
+				let current_key = self.current_item.as_ref().map(|i| self.get_cache_key(i));
+				let keys: Vec<String> = self
+					.cache
+					.keys()
+					.filter(|k| {
+						Some(*k) != current_key.as_ref() && Some(*k) != self.pinned.as_ref()
+					})
+					.cloned()
+					.collect();
+				for key in &keys {
+					self.evict(key);
+				}
+			}
+			CacheCategory::Pending => {
+				self.pending_samples.clear();
+				self.pending_full.clear();
+				self.queue_stats.dropped += self.deferred.len() as u64;
+				self.deferred.clear();
+			}
+			CacheCategory::Failures => self.failures.clear(),
+		}
+		log::info!("Cleared cached {}", category.label());
+	}
+
+	/// Get the best available media for the current item, marking it as used
+	pub fn get_current_media(&mut self) -> Option<&LoadedMedia> {
 		let cache_key = self.current_item.as_ref().map(|i| self.get_cache_key(i))?;
-		self.cache.get(&cache_key).map(|(media, _)| media)
+		self.touch(&cache_key);
+		self.cache
+			.get(&cache_key)
+			.and_then(|entry| entry.image.media())
 	}
 
+	/// Media for a post, if its image has been uploaded with `ensure_uploaded`
 	pub fn get_media_by_post(&self, post: &Post) -> Option<&LoadedMedia> {
+		self.cache
+			.get(Self::post_cache_key(post))
+			.and_then(|entry| entry.image.media())
+	}
+
+	/// Key a post's image is cached under, as used by `MediaEvent::Pin`
+	pub fn post_cache_key(post: &Post) -> &str {
 		let full_url = post.file.url.as_deref();
 		let sample_url = if post.sample.has {
 			post.sample.url.as_deref()
 		} else {
 			None
 		};
-		let cache_key = full_url.or(sample_url).unwrap_or_default();
-		self.cache.get(cache_key).map(|(media, _)| media)
+		full_url.or(sample_url).unwrap_or_default()
+	}
+
+	/// Whether `post` could be shown right away, and how well
+	pub fn readiness(&self, post: &Post) -> Readiness {
+		// Or under its sample, when that was prefetched on its own
+		let sample_only = post.sample.url.as_deref().filter(|_| post.sample.has);
+		let entry = self
+			.cache
+			.get(Self::post_cache_key(post))
+			.or_else(|| sample_only.and_then(|url| self.cache.get(url)));
+		match entry {
+			Some(CachedMedia {
+				state: CacheState::Full,
+				..
+			}) => Readiness::FullReady,
+			Some(CachedMedia {
+				state: CacheState::SampleOnly,
+				..
+			}) => Readiness::SampleReady,
+			None => {
+				let sample_url = post.sample.url.as_deref().filter(|_| post.sample.has);
+				let failed = [post.file.url.as_deref(), sample_url]
+					.into_iter()
+					.flatten()
+					.all(|url| self.failures.is_permanent(url));
+				if failed {
+					Readiness::Failed
+				} else {
+					Readiness::NotStarted
+				}
+			}
+		}
+	}
+
+	/// Whether the current item is shown as its sample or its original; None until loaded
+	pub fn current_state(&self) -> Option<CacheState> {
+		let item = self.current_item.as_ref()?;
+		self.cache
+			.get(&self.get_cache_key(item))
+			.map(|entry| entry.state)
+	}
+
+	/// Size, file and version of the image on screen; None until loaded
+	pub fn current_info(&self) -> Option<MediaInfo> {
+		let item = self.current_item.as_ref()?;
+		let entry = self.cache.get(&self.get_cache_key(item))?;
+		Some(MediaInfo {
+			size: entry.image.image().size,
+			byte_len: entry.byte_len,
+			format: entry.format,
+			state: entry.state,
+		})
 	}
 
 	pub fn current_url(&self) -> Option<&str> {
@@ -497,7 +1641,814 @@ impl MediaCache {
 			.and_then(|i| i.full_url.as_deref().or(i.sample_url.as_deref()))
 	}
 
+	/// Shorten the backoff so tests don't wait for real retries
+	#[cfg(test)]
+	pub fn set_retry_delay(&mut self, delay: Duration) {
+		self.failures = FailureMap::with_retry_delay(delay);
+	}
+
+	/// Shorten the watchdog so tests don't wait for real stalls
+	#[cfg(test)]
+	pub fn set_priority_stall_timeout(&mut self, timeout: Duration) {
+		self.priority_stall_timeout = timeout;
+	}
+
+	/// Shrink the cache so tests can overflow it with a handful of images
+	#[cfg(test)]
+	pub fn set_cache_budget(&mut self, max_cached: usize) {
+		self.max_cached = max_cached;
+	}
+
+	/// Stop the workers taking new loads and let downloads being written finish,
+	/// waiting at most `grace`. Blocks the calling thread; meant for exit only.
+	pub fn shutdown(&mut self, grace: Duration) {
+		log::info!("Stopping media workers");
+		self.flags.stopping.store(true, Ordering::Relaxed);
+		self.pending_samples.clear();
+		self.pending_full.clear();
+		self.downloads.shutdown(grace);
+	}
+
 	pub fn is_loading(&self) -> bool {
-		!self.loading_set.is_empty()
+		!self.inflight.is_empty()
+	}
+
+	/// Loads in flight, as (priority worker, general workers)
+	pub fn loading_counts(&self) -> (usize, usize) {
+		self.inflight.lane_counts()
+	}
+
+	pub fn queue_stats(&self) -> QueueStats {
+		self.queue_stats
+	}
+
+	/// URLs held back after failing, as (permanent, transient)
+	pub fn failure_counts(&self) -> (usize, usize) {
+		self.failures.counts()
+	}
+
+	pub fn cache_len(&self) -> usize {
+		self.cache.len()
+	}
+
+	/// Memory held by decoded images; uploaded textures are not counted twice
+	pub fn cache_bytes(&self) -> usize {
+		self.cache
+			.values()
+			.map(|entry| entry.image.image().pixels.len() * std::mem::size_of::<egui::Color32>())
+			.sum()
+	}
+
+	/// Every cache entry, load and queue, for the cache inspector
+	pub fn report(&self) -> CacheReport {
+		let current_key = self.current_item.as_ref().map(|i| self.get_cache_key(i));
+		let entries = self
+			.cache
+			.iter()
+			.rev()
+			.map(|(key, entry)| {
+				let pixels = entry.image.image();
+				EntryReport {
+					key: key.clone(),
+					state: entry.state,
+					uploaded: entry.image.media().is_some(),
+					size: pixels.size,
+					bytes: pixels.pixels.len() * std::mem::size_of::<egui::Color32>(),
+					file_bytes: entry.byte_len,
+					format: entry.format.map(|format| format.name()),
+					loaded: entry.loaded_at.elapsed(),
+					current: Some(key) == current_key.as_ref(),
+					pinned: Some(key) == self.pinned.as_ref(),
+					upcoming: self.upcoming.contains(key),
+				}
+			})
+			.collect();
+		let mut in_flight: Vec<InFlightReport> = self
+			.inflight
+			.iter()
+			.map(|(url, lanes, decoding)| InFlightReport {
+				url: url.to_string(),
+				priority: lanes.contains(&Lane::Priority),
+				general: lanes.contains(&Lane::General),
+				decoding,
+			})
+			.collect();
+		in_flight.sort_by(|a, b| a.url.cmp(&b.url));
+		let queued = |queue: &PendingQueue, sample: bool| {
+			queue
+				.iter()
+				.filter_map(|(distance, item)| {
+					let url = if sample {
+						item.sample_url.as_ref().or(item.full_url.as_ref())
+					} else {
+						item.full_url.as_ref()
+					};
+					url.map(|url| (*distance, url.clone()))
+				})
+				.collect()
+		};
+		let mut failures: Vec<FailureReport> = self
+			.failures
+			.iter()
+			.map(|(url, failure)| FailureReport {
+				url: url.to_string(),
+				attempts: failure.attempts,
+				last_error: failure.last_error.clone(),
+				permanent: failure.permanent,
+				since: failure.since(),
+			})
+			.collect();
+		failures.sort_by_key(|failure| failure.since);
+		CacheReport {
+			entries,
+			in_flight,
+			pending_samples: queued(&self.pending_samples, true),
+			pending_full: queued(&self.pending_full, false),
+			deferred: self.deferred.iter().map(|work| work.url.clone()).collect(),
+			failures,
+		}
+	}
+
+	/// Timings of the most recent finished load, while its image is cached
+	pub fn last_load(&self) -> Option<&LoadTimings> {
+		self.load_timings.get(self.last_loaded.as_ref()?)
+	}
+
+	/// Percentiles over the last loads, per stage
+	pub fn load_stats(&self) -> LoadStats {
+		self.load_timer.stats()
+	}
+
+	pub fn download_progress(&self) -> DownloadProgress {
+		self.downloads.progress()
+	}
+
+	pub fn downloads_paused(&self) -> bool {
+		self.downloads.is_paused()
+	}
+
+	/// URL and encoded file of the current item's original, once it has loaded
+	/// while being the current item. None for samples and cache hits.
+	pub fn current_raw_bytes(&self) -> Option<(&str, &Arc<Vec<u8>>)> {
+		self.current_raw
+			.as_ref()
+			.map(|(url, bytes)| (url.as_str(), bytes))
+	}
+
+	fn is_current_original(&self, url: &str) -> bool {
+		self.current_item
+			.as_ref()
+			.and_then(|item| item.full_url.as_deref())
+			== Some(url)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::{FakeLoader, Outcome, is, original, sample};
+
+	const URL: &str = "https://example.test/1.png";
+
+	const STAGE: Duration = Duration::from_millis(50);
+
+	async fn poll_until_idle(media: &mut MediaCache) {
+		for _ in 0..100 {
+			media.poll();
+			if !media.is_loading() {
+				return;
+			}
+			tokio::time::sleep(Duration::from_millis(2)).await;
+		}
+	}
+
+	fn urls(id: u32) -> (Option<String>, Option<String>, bool) {
+		(
+			Some(format!("https://example.test/{}_sample.png", id)),
+			Some(format!("https://example.test/{}.png", id)),
+			false,
+		)
+	}
+
+	#[tokio::test]
+	async fn prefetched_post_becoming_current_is_downloaded_once() {
+		let permits = Arc::new(tokio::sync::Semaphore::new(0));
+		let loader = FakeLoader::new().gated(permits.clone());
+		let calls = loader.calls();
+		let mut media = MediaCache::new_with_loader(&egui::Context::default(), Box::new(loader));
+		let (sample_url, full_url, _) = urls(1);
+
+		media.handle(&Event::Media(MediaEvent::Prefetch {
+			urls: vec![urls(1)],
+		}));
+		for _ in 0..100 {
+			media.poll();
+			if !calls.lock().unwrap().is_empty() {
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(2)).await;
+		}
+		// Navigated there while the prefetch is still downloading
+		media.handle(&Event::Media(MediaEvent::LoadRequest {
+			sample_url: sample_url.clone(),
+			full_url: full_url.clone(),
+			is_video: false,
+		}));
+		media.poll();
+		permits.add_permits(10);
+		poll_until_idle(&mut media).await;
+
+		assert_eq!(media.current_state(), Some(CacheState::Full));
+		let calls = calls.lock().unwrap();
+		for url in [sample_url, full_url].into_iter().flatten() {
+			assert_eq!(
+				calls.iter().filter(|call| **call == url).count(),
+				1,
+				"{}",
+				url
+			);
+		}
+	}
+
+	#[tokio::test]
+	async fn next_item_survives_pruning_until_shown() {
+		let mut media =
+			MediaCache::new_with_loader(&egui::Context::default(), Box::new(FakeLoader::new()));
+		media.set_cache_budget(4);
+
+		// Shuffle-like order that comes back to posts seen a while ago
+		let order = [1, 2, 3, 4, 1, 5, 6, 2, 7];
+		for step in order.windows(3) {
+			let (sample_url, full_url, is_video) = urls(step[0]);
+			media.handle(&Event::Media(MediaEvent::LoadRequest {
+				sample_url,
+				full_url,
+				is_video,
+			}));
+			media.handle(&Event::Media(MediaEvent::Prefetch {
+				urls: vec![urls(step[1]), urls(step[2])],
+			}));
+			let next_key = urls(step[1]).1.unwrap();
+			// Once cached, the next post must stay put; a reload would hide an eviction
+			let mut next_cached = media.cache.contains_key(&next_key);
+			for _ in 0..100 {
+				media.poll();
+				media.get_current_media();
+				if next_cached {
+					assert!(
+						media.cache.contains_key(&next_key),
+						"post {} was evicted before being shown",
+						step[1]
+					);
+				}
+				next_cached = media.cache.contains_key(&next_key);
+				if !media.is_loading() {
+					break;
+				}
+				tokio::time::sleep(Duration::from_millis(2)).await;
+			}
+
+			assert!(next_cached);
+			assert!(media.cache.len() <= 4);
+		}
+	}
+
+	#[tokio::test]
+	async fn pinned_image_outlives_the_cache_budget() {
+		let mut media =
+			MediaCache::new_with_loader(&egui::Context::default(), Box::new(FakeLoader::new()));
+		media.set_cache_budget(2);
+
+		let pinned_key = urls(1).1.unwrap();
+		for id in 1..=5 {
+			let (sample_url, full_url, is_video) = urls(id);
+			media.handle(&Event::Media(MediaEvent::LoadRequest {
+				sample_url,
+				full_url,
+				is_video,
+			}));
+			poll_until_idle(&mut media).await;
+			media.get_current_media();
+			if id == 1 {
+				media.handle(&Event::Media(MediaEvent::Pin {
+					cache_key: Some(pinned_key.clone()),
+				}));
+			}
+		}
+		assert!(media.cache.contains_key(&pinned_key));
+		assert!(media.cache.len() <= 3);
+
+		media.handle(&Event::Media(MediaEvent::Pin { cache_key: None }));
+		let (sample_url, full_url, is_video) = urls(6);
+		media.handle(&Event::Media(MediaEvent::LoadRequest {
+			sample_url,
+			full_url,
+			is_video,
+		}));
+		poll_until_idle(&mut media).await;
+		assert!(!media.cache.contains_key(&pinned_key));
+	}
+
+	#[tokio::test]
+	async fn only_the_current_original_keeps_its_file() {
+		let mut media =
+			MediaCache::new_with_loader(&egui::Context::default(), Box::new(FakeLoader::new()));
+		let show = |media: &mut MediaCache, id| {
+			let (sample_url, full_url, is_video) = urls(id);
+			media.handle(&Event::Media(MediaEvent::LoadRequest {
+				sample_url,
+				full_url,
+				is_video,
+			}));
+		};
+		let held = |media: &MediaCache| media.current_raw_bytes().map(|(url, _)| url.to_string());
+
+		show(&mut media, 1);
+		poll_until_idle(&mut media).await;
+		assert_eq!(held(&media), urls(1).1);
+
+		// The next post's sample, then its original on a later hint
+		for _ in 0..2 {
+			media.handle(&Event::Media(MediaEvent::Prefetch {
+				urls: vec![urls(2)],
+			}));
+			poll_until_idle(&mut media).await;
+		}
+		let next = media
+			.cache
+			.get(&urls(2).1.unwrap())
+			.map(|entry| entry.state);
+		assert_eq!(next, Some(CacheState::Full));
+		assert_eq!(held(&media), urls(1).1);
+
+		// Moving on lets the file go, and a cache hit has none to keep
+		show(&mut media, 2);
+		poll_until_idle(&mut media).await;
+		assert_eq!(held(&media), None);
+	}
+
+	#[test]
+	fn prefetched_originals_send_no_file_back() {
+		let work = |keep_bytes| LoadWork {
+			url: "https://example.test/2.png".to_string(),
+			is_sample: false,
+			cache_key: "https://example.test/2.png".to_string(),
+			lane: Lane::General,
+			queued_at: Instant::now(),
+			keep_bytes,
+		};
+		let loaded = || {
+			Ok((
+				egui::ColorImage::new([1, 1], egui::Color32::WHITE),
+				vec![0; 16],
+			))
+		};
+		let sent = |keep_bytes| match finished(
+			work(keep_bytes),
+			loaded(),
+			Duration::ZERO,
+			FetchTimings::default(),
+			&WorkerFlags::default(),
+		) {
+			MediaMessage::ImageLoaded {
+				bytes, byte_len, ..
+			} => (bytes.map(|bytes| bytes.len()), byte_len),
+			_ => unreachable!(),
+		};
+		// The size is still told, for the info overlay
+		assert_eq!(sent(false), (None, 16));
+		assert_eq!(sent(true), (Some(16), 16));
+	}
+
+	#[tokio::test]
+	async fn current_original_gets_through_a_full_priority_queue() {
+		let permits = Arc::new(tokio::sync::Semaphore::new(0));
+		let loader = FakeLoader::new().gated(permits.clone());
+		let calls = loader.calls();
+		let mut media = MediaCache::new_with_loader(&egui::Context::default(), Box::new(loader));
+
+		// The priority worker is stuck on post 1 while quick navigation queues 8 more
+		// originals behind it; the last few find the channel full
+		for id in 1..=12 {
+			let (sample_url, full_url, is_video) = urls(id);
+			media.handle(&Event::Media(MediaEvent::LoadRequest {
+				sample_url,
+				full_url,
+				is_video,
+			}));
+			media.poll();
+			if id == 1 {
+				for _ in 0..100 {
+					if media.priority.busy_since.lock().unwrap().is_some() {
+						break;
+					}
+					tokio::time::sleep(Duration::from_millis(2)).await;
+				}
+			}
+		}
+		let stats = media.queue_stats();
+		assert_eq!(stats.deferred, 3);
+
+		permits.add_permits(100);
+		for _ in 0..200 {
+			media.poll();
+			if media.current_state() == Some(CacheState::Full) {
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(2)).await;
+		}
+		assert_eq!(media.current_state(), Some(CacheState::Full));
+
+		// Originals of the posts skipped past were never downloaded
+		let calls = calls.lock().unwrap();
+		for id in 2..=11 {
+			assert!(!calls.contains(&urls(id).1.unwrap()), "post {} loaded", id);
+		}
+		let stats = media.queue_stats();
+		assert_eq!(stats.retried, 1);
+		// 8 skipped by the worker, 2 dropped from the wait
+		assert_eq!(stats.dropped, 10);
+	}
+
+	#[tokio::test]
+	async fn nearest_prefetches_start_first_after_reprioritizing() {
+		let permits = Arc::new(tokio::sync::Semaphore::new(0));
+		let loader = FakeLoader::new().gated(permits.clone());
+		let calls = loader.calls();
+		let mut media = MediaCache::new_with_loader(&egui::Context::default(), Box::new(loader));
+		let started = |count: usize| calls.lock().unwrap().len() >= count;
+
+		media.handle(&Event::Media(MediaEvent::Prefetch {
+			urls: (1..=20).map(urls).collect(),
+		}));
+		media.poll();
+		for _ in 0..100 {
+			if started(GENERAL_DISPATCH_DEPTH) {
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(2)).await;
+		}
+		let mut first: Vec<String> = calls.lock().unwrap().clone();
+		first.sort();
+		let mut expected: Vec<String> = (1..=GENERAL_DISPATCH_DEPTH as u32)
+			.map(|id| urls(id).0.unwrap())
+			.collect();
+		expected.sort();
+		assert_eq!(first, expected);
+
+		// Jumped ahead: what hasn't started yet follows the new distances
+		media.handle(&Event::Media(MediaEvent::Prefetch {
+			urls: (13..=20).map(urls).collect(),
+		}));
+		media.poll();
+		let mut next = Vec::new();
+		for _ in 0..16 {
+			let count = calls.lock().unwrap().len();
+			permits.add_permits(1);
+			for _ in 0..100 {
+				media.poll();
+				if started(count + 1) {
+					break;
+				}
+				tokio::time::sleep(Duration::from_millis(2)).await;
+			}
+			next.push(calls.lock().unwrap()[count].clone());
+		}
+		let samples = (13..=20).map(|id| urls(id).0.unwrap());
+		let fulls = (13..=20).map(|id| urls(id).1.unwrap());
+		assert_eq!(next, samples.chain(fulls).collect::<Vec<_>>());
+	}
+
+	#[tokio::test]
+	async fn forcing_full_res_retries_a_failed_original() {
+		let mut media = MediaCache::new_with_loader(
+			&egui::Context::default(),
+			Box::new(FakeLoader::new().once(original, Outcome::Missing)),
+		);
+		let (sample_url, full_url, is_video) = urls(1);
+		media.handle(&Event::Media(MediaEvent::LoadRequest {
+			sample_url,
+			full_url,
+			is_video,
+		}));
+		poll_until_idle(&mut media).await;
+		assert_eq!(media.current_state(), Some(CacheState::SampleOnly));
+
+		media.handle(&Event::Media(MediaEvent::ForceFullRes));
+		assert_eq!(media.loading_counts(), (1, 0));
+		poll_until_idle(&mut media).await;
+		assert_eq!(media.current_state(), Some(CacheState::Full));
+	}
+
+	#[test]
+	fn media_info_reads_like_the_overlay_line() {
+		let info = MediaInfo {
+			size: [2480, 3508],
+			byte_len: 8_808_038,
+			format: Some(ImageSignature::Png),
+			state: CacheState::Full,
+		};
+		assert_eq!(info.summary(), "2480×3508 · 8.4 MB · PNG · full");
+		let sample = MediaInfo {
+			size: [850, 1202],
+			byte_len: 150_000,
+			format: None,
+			state: CacheState::SampleOnly,
+		};
+		assert_eq!(sample.summary(), "850×1202 · 147 KB · sample");
+	}
+
+	#[tokio::test]
+	async fn report_lists_entries_and_failures_until_evicted_and_cleared() {
+		let mut media = MediaCache::new_with_loader(
+			&egui::Context::default(),
+			Box::new(FakeLoader::new().once(original, Outcome::Missing)),
+		);
+		for id in [1, 2] {
+			let (sample_url, full_url, is_video) = urls(id);
+			media.handle(&Event::Media(MediaEvent::LoadRequest {
+				sample_url,
+				full_url,
+				is_video,
+			}));
+			poll_until_idle(&mut media).await;
+			media.get_current_media();
+		}
+
+		let report = media.report();
+		let entries: Vec<(&str, CacheState, bool)> = report
+			.entries
+			.iter()
+			.map(|e| (e.key.as_str(), e.state, e.current))
+			.collect();
+		assert_eq!(
+			entries,
+			[
+				("https://example.test/2.png", CacheState::Full, true),
+				("https://example.test/1.png", CacheState::SampleOnly, false),
+			]
+		);
+		assert!(report.entries[0].uploaded);
+		assert_eq!(report.entries[0].bytes, 16);
+		assert_eq!(report.entries[0].file_bytes, 16);
+		assert_eq!(
+			media.current_info(),
+			Some(MediaInfo {
+				size: [2, 2],
+				byte_len: 16,
+				format: None,
+				state: CacheState::Full,
+			})
+		);
+		assert!(report.in_flight.is_empty());
+		assert_eq!(report.failures.len(), 1);
+		assert_eq!(report.failures[0].url, "https://example.test/1.png");
+		assert!(report.failures[0].permanent);
+
+		media.handle(&Event::Media(MediaEvent::Evict {
+			cache_key: "https://example.test/1.png".to_string(),
+		}));
+		media.handle(&Event::Media(MediaEvent::ClearCache {
+			category: CacheCategory::Failures,
+		}));
+		let report = media.report();
+		assert_eq!(report.entries.len(), 1);
+		assert!(report.failures.is_empty());
+
+		// Evicting what is shown loads it again rather than leaving the screen empty
+		media.handle(&Event::Media(MediaEvent::Evict {
+			cache_key: "https://example.test/2.png".to_string(),
+		}));
+		assert_eq!(media.current_state(), None);
+		poll_until_idle(&mut media).await;
+		assert_eq!(media.current_state(), Some(CacheState::Full));
+	}
+
+	#[tokio::test]
+	async fn original_of_another_shape_is_reported_with_both_sizes() {
+		let mut media = MediaCache::new_with_loader(
+			&egui::Context::default(),
+			Box::new(
+				FakeLoader::new()
+					.on(sample, Outcome::Image([40, 30]))
+					.on(original, Outcome::Image([40, 20]))
+					.after(Duration::from_millis(20)),
+			),
+		);
+		let (sample_url, full_url, is_video) = urls(1);
+		media.handle(&Event::Media(MediaEvent::LoadRequest {
+			sample_url,
+			full_url,
+			is_video,
+		}));
+		let mut upgrades = Vec::new();
+		for _ in 0..100 {
+			for event in media.poll().events {
+				if let Event::View(ViewEvent::SampleUpgraded { mismatch }) = event {
+					upgrades.push(mismatch);
+				}
+			}
+			if !media.is_loading() {
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(2)).await;
+		}
+		assert_eq!(
+			upgrades,
+			[Some(AspectMismatch {
+				sample: [40, 30],
+				full: [40, 20],
+			})]
+		);
+
+		// Rounding in the sample's size is no mismatch
+		assert_eq!(AspectMismatch::detect([850, 1133], [1500, 2000]), None);
+	}
+
+	#[tokio::test]
+	async fn textures_are_replaced_and_the_current_item_reloaded() {
+		let mut media = MediaCache::new_with_loader(
+			&egui::Context::default(),
+			Box::new(FakeLoader::new().once(original, Outcome::Missing)),
+		);
+		let (sample_url, full_url, is_video) = urls(1);
+		media.handle(&Event::Media(MediaEvent::LoadRequest {
+			sample_url,
+			full_url,
+			is_video,
+		}));
+		poll_until_idle(&mut media).await;
+		let texture_id = |media: &mut MediaCache| {
+			media
+				.get_current_media()
+				.map(|LoadedMedia::Image { texture }| texture.id())
+		};
+		let before = texture_id(&mut media).unwrap();
+
+		media.handle(&Event::Media(MediaEvent::ReuploadTextures));
+		let after = texture_id(&mut media).unwrap();
+		assert_ne!(before, after);
+		assert_eq!(media.current_state(), Some(CacheState::SampleOnly));
+
+		// The original failed for good the first time; a reload asks for it again
+		media.handle(&Event::Media(MediaEvent::ReloadCurrent));
+		assert_eq!(media.current_state(), None);
+		poll_until_idle(&mut media).await;
+		assert_eq!(media.current_state(), Some(CacheState::Full));
+	}
+
+	#[tokio::test]
+	async fn current_image_is_retried_once_its_backoff_runs_out() {
+		let full_url = urls(1).1.unwrap();
+		let loader = FakeLoader::new().once(is(&full_url), Outcome::TimedOut);
+		let calls = loader.calls();
+		let mut media = MediaCache::new_with_loader(&egui::Context::default(), Box::new(loader));
+		media.set_retry_delay(Duration::from_millis(50));
+		media.handle(&Event::Media(MediaEvent::LoadRequest {
+			sample_url: None,
+			full_url: Some(full_url.clone()),
+			is_video: false,
+		}));
+		let failed = |events: &[Event]| {
+			events.iter().any(|e| {
+				matches!(
+					e,
+					Event::Media(MediaEvent::LoadError {
+						permanent: false,
+						current: true,
+						..
+					})
+				)
+			})
+		};
+		for _ in 0..100 {
+			if failed(&media.poll().events) {
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(2)).await;
+		}
+		assert!(media.next_retry().is_some());
+		assert!(!media.has_pending());
+
+		// Nothing else happens; the cache is only polled when it asks to be
+		for _ in 0..100 {
+			if media.has_pending() {
+				media.poll();
+			}
+			if media.current_state() == Some(CacheState::Full) {
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(5)).await;
+		}
+		assert_eq!(media.current_state(), Some(CacheState::Full));
+		assert_eq!(*calls.lock().unwrap(), vec![full_url; 2]);
+		assert_eq!(media.next_retry(), None);
+	}
+
+	#[tokio::test]
+	async fn failed_priority_copy_is_not_reloaded_while_rescue_runs() {
+		// The first load times out, later ones never finish
+		let loader = FakeLoader::new()
+			.once(is(URL), Outcome::TimedOut)
+			.after(Duration::from_millis(100))
+			.on(is(URL), Outcome::Stall);
+		let calls = loader.calls();
+		let mut media = MediaCache::new_with_loader(&egui::Context::default(), Box::new(loader));
+		media.set_priority_stall_timeout(Duration::from_millis(20));
+
+		// Priority load stalls, a general worker takes a copy, then the priority copy fails
+		media.handle(&Event::Media(MediaEvent::LoadRequest {
+			sample_url: None,
+			full_url: Some(URL.to_string()),
+			is_video: false,
+		}));
+		for _ in 0..60 {
+			media.poll();
+			tokio::time::sleep(Duration::from_millis(5)).await;
+		}
+
+		assert_eq!(*calls.lock().unwrap(), vec![URL.to_string(); 2]);
+		assert!(media.is_loading());
+	}
+
+	#[tokio::test]
+	async fn dead_url_is_not_fetched_again_by_later_prefetches() {
+		let (dead_sample, dead_full, _) = urls(2);
+		let loader = FakeLoader::new()
+			.on(is(dead_sample.as_deref().unwrap()), Outcome::Missing)
+			.on(is(dead_full.as_deref().unwrap()), Outcome::Missing);
+		let calls = loader.calls();
+		let mut media = MediaCache::new_with_loader(&egui::Context::default(), Box::new(loader));
+
+		// Stepping through posts 1..=4 keeps post 2 in every prefetch list
+		for _ in 0..4 {
+			media.handle(&Event::Media(MediaEvent::Prefetch {
+				urls: vec![urls(1), urls(2), urls(3), urls(4)],
+			}));
+			poll_until_idle(&mut media).await;
+		}
+		let calls = calls.lock().unwrap();
+		for url in [dead_sample.unwrap(), dead_full.unwrap()] {
+			assert_eq!(calls.iter().filter(|c| **c == url).count(), 1, "{}", url);
+		}
+		assert_eq!(media.failure_counts(), (2, 0));
+	}
+
+	/// Prefetch eight posts, sample and original each, and return the order the
+	/// loads finished in and how long they all took
+	async fn prefetch_burst(split: bool) -> (Vec<String>, Duration) {
+		// Every file takes one stage to download and one to decode. Split, it decodes
+		// on the decode pool; otherwise the worker does both, as loaders used to.
+		let loader = FakeLoader::new()
+			.on(|_| true, Outcome::Image([2, 2]))
+			.after(STAGE * 2);
+		let loader = if split {
+			loader.decoding_for(STAGE)
+		} else {
+			loader
+		};
+		let mut media =
+			MediaCache::new_with_decoders(&egui::Context::default(), Box::new(loader), 4);
+		let started = Instant::now();
+		media.handle(&Event::Media(MediaEvent::Prefetch {
+			urls: (1..=8).map(urls).collect(),
+		}));
+		let mut finished = Vec::new();
+		while media.is_loading() || finished.is_empty() {
+			media.poll();
+			for key in media.cache.keys() {
+				if !finished.contains(key) {
+					finished.push(key.clone());
+				}
+			}
+			assert!(
+				started.elapsed() < Duration::from_secs(5),
+				"loads never finished"
+			);
+			tokio::time::sleep(Duration::from_millis(2)).await;
+		}
+		(finished, started.elapsed())
+	}
+
+	#[tokio::test]
+	async fn decoding_on_the_pool_frees_workers_for_the_next_download() {
+		let (serial_order, serial) = prefetch_burst(false).await;
+		let (pipelined_order, pipelined) = prefetch_burst(true).await;
+
+		// Four workers, sixteen loads: four rounds of download-then-decode one
+		// after the other, against four rounds of downloads with decoding behind them
+		assert!(serial >= STAGE * 8, "serial took {:?}", serial);
+		assert!(
+			pipelined + STAGE * 2 < serial,
+			"pipelined took {:?}, serial {:?}",
+			pipelined,
+			serial
+		);
+		// Nearest first either way, just sooner
+		let nearest: HashSet<String> = (1..=4).map(|id| urls(id).1.unwrap()).collect();
+		for order in [&serial_order, &pipelined_order] {
+			assert_eq!(order.len(), 8);
+			assert_eq!(order[..4].iter().cloned().collect::<HashSet<_>>(), nearest);
+		}
 	}
 }
@@ -0,0 +1,35 @@
+use super::MediaItem;
+use std::collections::VecDeque;
+
+/// Prefetch items waiting for a general worker, nearest to the current post first.
+/// Distance is the item's offset from the current post, as the browser sent it.
+#[derive(Default)]
+pub struct PendingQueue {
+	/// Sorted by distance; equal distances keep the order they were pushed in
+	items: VecDeque<(usize, MediaItem)>,
+}
+
+impl PendingQueue {
+	pub fn push(&mut self, distance: usize, item: MediaItem) {
+		let index = self.items.partition_point(|(d, _)| *d <= distance);
+		self.items.insert(index, (distance, item));
+	}
+
+	/// The nearest item and its distance
+	pub fn pop(&mut self) -> Option<(usize, MediaItem)> {
+		self.items.pop_front()
+	}
+
+	pub fn clear(&mut self) {
+		self.items.clear();
+	}
+
+	pub fn len(&self) -> usize {
+		self.items.len()
+	}
+
+	/// Queued items with their distances, nearest first
+	pub fn iter(&self) -> impl Iterator<Item = &(usize, MediaItem)> {
+		self.items.iter()
+	}
+}
@@ -0,0 +1,80 @@
+use super::CacheState;
+use std::time::Duration;
+
+/// What the cache inspector can empty at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheCategory {
+	/// Cached images, except the current one and the pinned one
+	Images,
+	/// Prefetch queues and loads waiting for room in a work channel
+	Pending,
+	/// URLs held back after failing
+	Failures,
+}
+
+impl CacheCategory {
+	pub fn label(self) -> &'static str {
+		match self {
+			CacheCategory::Images => "images",
+			CacheCategory::Pending => "queues",
+			CacheCategory::Failures => "failures",
+		}
+	}
+}
+
+/// Everything the media cache holds and waits on, copied out so the inspector
+/// can draw it without borrowing the cache
+#[derive(Debug, Clone, Default)]
+pub struct CacheReport {
+	/// Cached images, most recently used first
+	pub entries: Vec<EntryReport>,
+	/// Loads handed to a worker that haven't reported back
+	pub in_flight: Vec<InFlightReport>,
+	/// URLs queued for a general worker, nearest post first, as (distance, url)
+	pub pending_samples: Vec<(usize, String)>,
+	pub pending_full: Vec<(usize, String)>,
+	/// Loads that found their work channel full, oldest first
+	pub deferred: Vec<String>,
+	pub failures: Vec<FailureReport>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryReport {
+	pub key: String,
+	pub state: CacheState,
+	/// Holds a texture, not just decoded pixels
+	pub uploaded: bool,
+	/// Pixels as `[width, height]`
+	pub size: [usize; 2],
+	/// Memory held by the decoded pixels
+	pub bytes: usize,
+	/// Size of the downloaded file, and its format if recognized
+	pub file_bytes: usize,
+	pub format: Option<&'static str>,
+	/// Time since it was loaded
+	pub loaded: Duration,
+	pub current: bool,
+	pub pinned: bool,
+	/// Named by the last prefetch
+	pub upcoming: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct InFlightReport {
+	pub url: String,
+	/// Held by the priority worker, general workers, or both after a rescue
+	pub priority: bool,
+	pub general: bool,
+	/// Downloaded and waiting on the decode pool
+	pub decoding: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct FailureReport {
+	pub url: String,
+	pub attempts: u32,
+	pub last_error: String,
+	pub permanent: bool,
+	/// Time since the last failure
+	pub since: Duration,
+}
@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+/// Recent loads the percentiles are taken over, per stage and per host
+const WINDOW: usize = 64;
+
+/// A host whose p90 network time passes this is reported as slow
+const SLOW_HOST_P90: Duration = Duration::from_secs(3);
+
+/// Loads from a host before its p90 is trusted
+const MIN_HOST_SAMPLES: usize = 10;
+
+/// Time the loader spent on one image
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchTimings {
+	/// Request sent until the response headers arrived
+	pub first_byte: Duration,
+	/// Headers until the last byte of the body
+	pub download: Duration,
+	pub decode: Duration,
+}
+
+/// Where the time of one load went, from being queued to being on the GPU
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadTimings {
+	/// Waiting in a worker channel
+	pub queue_wait: Duration,
+	pub fetch: FetchTimings,
+	/// Set once the image is first uploaded as a texture
+	pub upload: Option<Duration>,
+}
+
+impl LoadTimings {
+	pub fn network(&self) -> Duration {
+		self.fetch.first_byte + self.fetch.download
+	}
+
+	pub fn total(&self) -> Duration {
+		self.queue_wait + self.network() + self.fetch.decode + self.upload.unwrap_or_default()
+	}
+}
+
+/// Median and 90th percentile of one stage
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Percentiles {
+	pub p50: Duration,
+	pub p90: Duration,
+}
+
+/// Rolling percentiles over the last loads, per stage
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadStats {
+	/// Loads the figures are taken over
+	pub samples: usize,
+	pub queue_wait: Percentiles,
+	pub first_byte: Percentiles,
+	pub download: Percentiles,
+	pub decode: Percentiles,
+	pub upload: Percentiles,
+}
+
+/// The last `WINDOW` durations of something
+#[derive(Default)]
+struct Window {
+	durations: VecDeque<Duration>,
+}
+
+impl Window {
+	fn push(&mut self, duration: Duration) {
+		if self.durations.len() == WINDOW {
+			self.durations.pop_front();
+		}
+		self.durations.push_back(duration);
+	}
+
+	fn len(&self) -> usize {
+		self.durations.len()
+	}
+
+	/// Nearest-rank percentile, `p` in 0..=100
+	fn percentile(&self, p: usize) -> Duration {
+		if self.durations.is_empty() {
+			return Duration::ZERO;
+		}
+		let mut sorted: Vec<Duration> = self.durations.iter().copied().collect();
+		sorted.sort();
+		let rank = (p * sorted.len()).div_ceil(100).max(1);
+		sorted[rank - 1]
+	}
+
+	fn percentiles(&self) -> Percentiles {
+		Percentiles {
+			p50: self.percentile(50),
+			p90: self.percentile(90),
+		}
+	}
+}
+
+/// Collects the timings of finished loads for the status bar, and warns
+/// about CDN hosts that are consistently slow
+#[derive(Default)]
+pub struct LoadTimer {
+	queue_wait: Window,
+	first_byte: Window,
+	download: Window,
+	decode: Window,
+	upload: Window,
+	/// Network time per host
+	hosts: HashMap<String, Window>,
+	/// Hosts already warned about; warned again only after recovering
+	slow_hosts: HashSet<String>,
+}
+
+impl LoadTimer {
+	pub fn record(&mut self, url: &str, timings: &LoadTimings) {
+		self.queue_wait.push(timings.queue_wait);
+		self.first_byte.push(timings.fetch.first_byte);
+		self.download.push(timings.fetch.download);
+		self.decode.push(timings.fetch.decode);
+
+		let Some(host) = reqwest::Url::parse(url)
+			.ok()
+			.and_then(|url| url.host_str().map(str::to_string))
+		else {
+			return;
+		};
+		let window = self.hosts.entry(host.clone()).or_default();
+		window.push(timings.network());
+		if window.len() < MIN_HOST_SAMPLES {
+			return;
+		}
+		let p90 = window.percentile(90);
+		if p90 > SLOW_HOST_P90 {
+			if self.slow_hosts.insert(host.clone()) {
+				log::warn!(
+					"Slow media host {}: p90 network time {} ms over its last {} loads",
+					host,
+					p90.as_millis(),
+					window.len()
+				);
+			}
+		} else if self.slow_hosts.remove(&host) {
+			log::info!(
+				"Media host {} is back to normal: p90 {} ms",
+				host,
+				p90.as_millis()
+			);
+		}
+	}
+
+	pub fn record_upload(&mut self, duration: Duration) {
+		self.upload.push(duration);
+	}
+
+	pub fn stats(&self) -> LoadStats {
+		LoadStats {
+			samples: self.download.len(),
+			queue_wait: self.queue_wait.percentiles(),
+			first_byte: self.first_byte.percentiles(),
+			download: self.download.percentiles(),
+			decode: self.decode.percentiles(),
+			upload: self.upload.percentiles(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn load(network_ms: u64) -> LoadTimings {
+		LoadTimings {
+			queue_wait: Duration::from_millis(5),
+			fetch: FetchTimings {
+				first_byte: Duration::from_millis(network_ms / 2),
+				download: Duration::from_millis(network_ms / 2),
+				decode: Duration::from_millis(20),
+			},
+			upload: None,
+		}
+	}
+
+	#[test]
+	fn slow_host_is_flagged_once_and_cleared_when_it_recovers() {
+		let mut timer = LoadTimer::default();
+		let url = "https://static1.e621.net/data/ab/cd/abcd.png";
+		for _ in 0..MIN_HOST_SAMPLES - 1 {
+			timer.record(url, &load(5000));
+		}
+		assert!(timer.slow_hosts.is_empty(), "too few loads to judge");
+		timer.record(url, &load(5000));
+		assert!(timer.slow_hosts.contains("static1.e621.net"));
+		timer.record("https://other.test/1.png", &load(5000));
+		assert_eq!(timer.slow_hosts.len(), 1);
+
+		for _ in 0..WINDOW {
+			timer.record(url, &load(400));
+		}
+		assert!(timer.slow_hosts.is_empty());
+
+		let stats = timer.stats();
+		assert_eq!(stats.samples, WINDOW);
+		assert_eq!(stats.download.p90, Duration::from_millis(200));
+		assert_eq!(stats.decode.p50, Duration::from_millis(20));
+	}
+
+	#[test]
+	fn percentiles_use_the_nearest_rank() {
+		let mut window = Window::default();
+		for ms in 1..=10 {
+			window.push(Duration::from_millis(ms * 100));
+		}
+		assert_eq!(window.percentile(50), Duration::from_millis(500));
+		assert_eq!(window.percentile(90), Duration::from_millis(900));
+		assert_eq!(Window::default().percentile(90), Duration::ZERO);
+	}
+}
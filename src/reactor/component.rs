@@ -0,0 +1,261 @@
+use super::{
+	BeatEvent, BrowserEvent, ComponentResponse, Event, GatewayEvent, LookupPurpose, MediaEvent,
+	SettingsEvent, ViewEvent,
+};
+use crate::beat::SystemBeat;
+use crate::breathing::BreathingOverlay;
+use crate::browser::ContentBrowser;
+use crate::gateway::BooruGateway;
+use crate::media::MediaCache;
+use crate::view::ViewManager;
+use std::ops::BitOr;
+
+/// The kinds of events a component handles, one bit per `Event` variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EventMask(u16);
+
+impl EventMask {
+	pub const SOURCE: Self = Self(1 << 0);
+	pub const GATEWAY: Self = Self(1 << 1);
+	pub const BROWSER: Self = Self(1 << 2);
+	pub const MEDIA: Self = Self(1 << 3);
+	pub const BREATHING: Self = Self(1 << 4);
+	pub const VIEW: Self = Self(1 << 5);
+	pub const SETTINGS: Self = Self(1 << 6);
+	pub const BEAT: Self = Self(1 << 7);
+	pub const FAVORITES: Self = Self(1 << 8);
+
+	/// The bit of the event's kind
+	pub fn of(event: &Event) -> Self {
+		match event {
+			Event::Source(_) => Self::SOURCE,
+			Event::Gateway(_) => Self::GATEWAY,
+			Event::Browser(_) => Self::BROWSER,
+			Event::Media(_) => Self::MEDIA,
+			Event::Breathing(_) => Self::BREATHING,
+			Event::View(_) => Self::VIEW,
+			Event::Settings(_) => Self::SETTINGS,
+			Event::Beat(_) => Self::BEAT,
+			Event::Favorites(_) => Self::FAVORITES,
+		}
+	}
+
+	pub fn contains(self, event: &Event) -> bool {
+		self.0 & Self::of(event).0 != 0
+	}
+}
+
+impl BitOr for EventMask {
+	type Output = Self;
+
+	fn bitor(self, other: Self) -> Self {
+		Self(self.0 | other.0)
+	}
+}
+
+/// A part of the app driven by events. The reactor hands each event to the
+/// components whose `interests` include its kind and that `want` it, and polls
+/// those with results waiting from async work.
+pub trait Component {
+	fn handle(&mut self, event: &Event) -> ComponentResponse;
+
+	/// Turn finished async work into events
+	fn poll(&mut self) -> ComponentResponse {
+		ComponentResponse::none()
+	}
+
+	/// Whether `poll` has something to hand over
+	fn has_pending(&self) -> bool {
+		false
+	}
+
+	fn interests(&self) -> EventMask;
+
+	/// Narrow `interests` down to single events. Only asked about events of
+	/// the kinds in `interests`.
+	fn wants(&self, _event: &Event) -> bool {
+		true
+	}
+}
+
+/// Whether `event` is for `component`: of a kind it's interested in, and wanted
+pub fn takes(component: &dyn Component, event: &Event) -> bool {
+	component.interests().contains(event) && component.wants(event)
+}
+
+impl Component for BooruGateway {
+	fn handle(&mut self, event: &Event) -> ComponentResponse {
+		BooruGateway::handle(self, event)
+	}
+
+	fn poll(&mut self) -> ComponentResponse {
+		BooruGateway::poll(self)
+	}
+
+	fn has_pending(&self) -> bool {
+		BooruGateway::has_pending(self)
+	}
+
+	fn interests(&self) -> EventMask {
+		EventMask::GATEWAY | EventMask::SETTINGS
+	}
+
+	fn wants(&self, event: &Event) -> bool {
+		match event {
+			// Progress is only shown
+			Event::Gateway(
+				GatewayEvent::PageProgress { .. } | GatewayEvent::NewPostsAvailable { .. },
+			) => false,
+			Event::Gateway(_) => true,
+			// Later searches go out safe
+			Event::Settings(SettingsEvent::SafeModeChanged { .. }) => true,
+			_ => false,
+		}
+	}
+}
+
+impl Component for ContentBrowser {
+	fn handle(&mut self, event: &Event) -> ComponentResponse {
+		ContentBrowser::handle(self, event)
+	}
+
+	fn interests(&self) -> EventMask {
+		EventMask::BROWSER | EventMask::GATEWAY | EventMask::MEDIA | EventMask::SETTINGS
+	}
+
+	fn wants(&self, event: &Event) -> bool {
+		match event {
+			// Favorites refresh from these, against the current post
+			Event::Browser(BrowserEvent::PostsResolved {
+				purpose: LookupPurpose::RefreshFavorites,
+				..
+			}) => false,
+			Event::Browser(_) => true,
+			// The last post stops being final once the gateway looks again
+			Event::Gateway(GatewayEvent::ClearEndOfResults)
+			| Event::Media(MediaEvent::QueueDownloadRange { .. })
+			| Event::Settings(
+				SettingsEvent::SetShowPending { .. }
+				| SettingsEvent::SetWrapNavigation { .. }
+				| SettingsEvent::SetFetchAhead { .. }
+				| SettingsEvent::SafeModeChanged { .. },
+			) => true,
+			_ => false,
+		}
+	}
+}
+
+impl Component for MediaCache {
+	fn handle(&mut self, event: &Event) -> ComponentResponse {
+		MediaCache::handle(self, event)
+	}
+
+	fn poll(&mut self) -> ComponentResponse {
+		MediaCache::poll(self)
+	}
+
+	fn has_pending(&self) -> bool {
+		MediaCache::has_pending(self)
+	}
+
+	fn interests(&self) -> EventMask {
+		EventMask::MEDIA
+	}
+
+	fn wants(&self, event: &Event) -> bool {
+		// The browser knows which posts a range covers; the view and settings
+		// take failures
+		!matches!(
+			event,
+			Event::Media(MediaEvent::QueueDownloadRange { .. } | MediaEvent::LoadError { .. })
+		)
+	}
+}
+
+impl Component for BreathingOverlay {
+	fn handle(&mut self, event: &Event) -> ComponentResponse {
+		BreathingOverlay::handle(self, event)
+	}
+
+	fn interests(&self) -> EventMask {
+		EventMask::BREATHING
+	}
+}
+
+impl Component for SystemBeat {
+	fn handle(&mut self, event: &Event) -> ComponentResponse {
+		SystemBeat::handle(self, event)
+	}
+
+	fn poll(&mut self) -> ComponentResponse {
+		SystemBeat::poll(self)
+	}
+
+	fn has_pending(&self) -> bool {
+		SystemBeat::has_pending(self)
+	}
+
+	fn interests(&self) -> EventMask {
+		EventMask::BEAT
+	}
+
+	fn wants(&self, event: &Event) -> bool {
+		!matches!(event, Event::Beat(BeatEvent::SilenceDetected))
+	}
+}
+
+impl Component for ViewManager {
+	fn handle(&mut self, event: &Event) -> ComponentResponse {
+		ViewManager::handle(self, event)
+	}
+
+	fn interests(&self) -> EventMask {
+		EventMask::VIEW
+			| EventMask::GATEWAY
+			| EventMask::BROWSER
+			| EventMask::MEDIA
+			| EventMask::BEAT
+	}
+
+	fn wants(&self, event: &Event) -> bool {
+		match event {
+			// The reactor starts the search, settings hold the slideshow
+			Event::View(
+				ViewEvent::TosAccepted | ViewEvent::ModalOpened | ViewEvent::ModalClosed,
+			) => false,
+			Event::View(_) => true,
+			// Told to the user: errors, throttling, progress and what's new
+			Event::Gateway(
+				GatewayEvent::SearchError { .. }
+				| GatewayEvent::Throttled { .. }
+				| GatewayEvent::ConnectionState { .. }
+				| GatewayEvent::PageProgress { .. }
+				| GatewayEvent::NewPostsAvailable { .. },
+			)
+			| Event::Browser(BrowserEvent::EndOfResults)
+			| Event::Media(MediaEvent::LoadError { .. }) => true,
+			// Nothing to pulse to; the view lets the last beat fade
+			Event::Beat(BeatEvent::SilenceDetected) => true,
+			_ => false,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::reactor::{BeatEvent, GatewayEvent, SourceEvent};
+
+	#[test]
+	fn masks_match_events_of_their_kinds() {
+		let beat = Event::Beat(BeatEvent::SilenceDetected);
+		let gateway = Event::Gateway(GatewayEvent::FetchNextPage);
+		let source = Event::Source(SourceEvent::Reroll);
+		let mask = EventMask::BEAT | EventMask::GATEWAY;
+		assert!(mask.contains(&beat));
+		assert!(mask.contains(&gateway));
+		assert!(!mask.contains(&source));
+		assert!(!EventMask::default().contains(&beat));
+		assert_eq!(EventMask::of(&source), EventMask::SOURCE);
+	}
+}
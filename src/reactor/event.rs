@@ -1,5 +1,13 @@
-use crate::api::Post;
-use crate::types::{BreathingPhase, BreathingStyle, ImageFillMode, NavDirection};
+use crate::api::{ErrorCategory, Note, Post, Score};
+use crate::gateway::query::QueryWarning;
+use crate::media::AspectMismatch;
+use crate::media::report::CacheCategory;
+use crate::types::{
+	BreathingPhase, BreathingStyle, CreditOverlay, ImageFillMode, InfoFieldSetting, InfoVerbosity,
+	NavDirection, OverlayAnchor, PanMode, SearchWatch, SortKey, StartupSearch,
+};
+use eframe::egui;
+use std::path::PathBuf;
 use std::time::Duration;
 
 #[derive(Clone, Debug)]
@@ -12,6 +20,7 @@ pub enum Event {
 	View(ViewEvent),
 	Settings(SettingsEvent),
 	Beat(BeatEvent),
+	Favorites(FavoritesEvent),
 }
 
 impl Event {
@@ -26,7 +35,8 @@ impl Event {
 			Event::Breathing(_) => Priority::Low,
 			Event::View(_) => Priority::Normal,
 			Event::Beat(_) => Priority::Low,
-			Event::Settings(SettingsEvent::SlideshowAdvance) => Priority::Normal,
+			Event::Favorites(_) => Priority::Normal,
+			Event::Settings(SettingsEvent::SlideshowAdvance { .. }) => Priority::Normal,
 			Event::Settings(_) => Priority::Normal,
 		}
 	}
@@ -48,8 +58,30 @@ impl Priority {
 
 #[derive(Clone, Debug)]
 pub enum SourceEvent {
-	Search { query: String, page: u32 },
+	Search {
+		query: String,
+		page: u32,
+	},
 	Navigate(NavDirection),
+	/// Search for posts similar to the current one, remembering the current results
+	MoreLikeThis,
+	/// Return to the results remembered by the last `MoreLikeThis`
+	PreviousSearch,
+	/// Browse a pool's posts in order, remembering the current results like `MoreLikeThis`
+	EnterPool {
+		pool_id: u64,
+	},
+	/// Search the current `order:random` query again with a new shuffle
+	Reroll,
+	/// Browse an e621 set's posts in the set's order, replacing the results
+	OpenSet {
+		id: u64,
+	},
+	/// Search a watched query that has new posts, remembering the current results
+	/// like `MoreLikeThis`
+	OpenWatch {
+		query: String,
+	},
 }
 
 #[derive(Clone, Debug)]
@@ -61,8 +93,107 @@ pub enum GatewayEvent {
 	},
 	SearchError {
 		message: String,
+		category: ErrorCategory,
+	},
+	/// The API asked us to slow down; `retry` is sent again after `retry_after`
+	Throttled {
+		retry_after: Duration,
+		retry: Box<Event>,
+	},
+	/// Searches started or stopped reaching the server
+	ConnectionState {
+		online: bool,
 	},
 	FetchNextPage,
+	/// Forget that the current search ran out and look for a next page again
+	ClearEndOfResults,
+	/// One of the pages a new search fetches up front; dropped if `generation` is stale
+	FetchFanoutPage {
+		page: u32,
+		limit: u32,
+		generation: u64,
+	},
+	/// Set how many pages a new search fetches up front (1 turns fan-out off)
+	SetFanoutPages {
+		pages: u32,
+	},
+	/// Pages of a fanned-out search received so far; `total` is 0 when none is running
+	PageProgress {
+		loaded: u32,
+		total: u32,
+	},
+	FetchNotes {
+		post_id: u64,
+	},
+	/// Results answered without the API, e.g. `local:favorites`; ends the search there
+	LocalResults {
+		query: String,
+		posts: Vec<Post>,
+	},
+	/// Load a set's post IDs, then its posts a chunk at a time as the end comes near
+	OpenSet {
+		id: u64,
+	},
+	/// Look up pool names not asked for before this session
+	FetchPoolNames {
+		pool_ids: Vec<u64>,
+	},
+	/// Fetch posts by ID for whoever `purpose` names; answered with `PostsResolved`
+	FetchByIds {
+		ids: Vec<u64>,
+		purpose: LookupPurpose,
+	},
+	/// Load the blacklist stored on the configured account
+	FetchAccountBlacklist,
+	/// Cast a vote (+1/-1); `previous` is the vote shown before, for rollback
+	Vote {
+		post_id: u64,
+		score: i8,
+		previous: i8,
+	},
+	/// Start or stop polling `query` in the background for new posts
+	WatchSearch {
+		query: String,
+		watched: bool,
+	},
+	/// Replace every watch, e.g. from an imported settings file
+	SetWatches {
+		watches: Vec<SearchWatch>,
+	},
+	/// Minutes between polls of each watch, at least `MIN_WATCH_INTERVAL_MINS`
+	SetWatchInterval {
+		minutes: u32,
+	},
+	/// Time for a watch's next poll; dropped if `generation` is stale
+	PollWatch {
+		query: String,
+		generation: u64,
+	},
+	/// A poll of a watched search found posts newer than the last one seen
+	NewPostsAvailable {
+		query: String,
+		count: usize,
+	},
+}
+
+/// What a lookup of posts by ID is for, which decides who gets the posts
+#[derive(Clone, Debug, PartialEq)]
+pub enum LookupPurpose {
+	/// Replace the stored copies of local favorites with current ones
+	RefreshFavorites,
+	/// The next chunk of the open set, starting at `offset` into its post IDs.
+	/// Kept by the gateway, which passes the posts on as a page of results.
+	SetPage { generation: u64, offset: usize },
+}
+
+/// Which part of a compound (`a || b`) search a page of results belongs to
+#[derive(Clone, Debug)]
+pub struct PageSource {
+	pub index: usize,
+	pub count: usize,
+	pub query: String,
+	/// The sub-query came back short, so nothing follows this page
+	pub exhausted: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -71,10 +202,58 @@ pub enum BrowserEvent {
 		posts: Vec<Post>,
 		page: u32,
 		is_new: bool,
+		/// Set only for compound searches
+		source: Option<PageSource>,
 	},
 	Navigate {
 		direction: NavDirection,
 	},
+	/// Hide posts with this tag from now on, including already loaded ones
+	BlacklistTag {
+		tag: String,
+	},
+	/// Undo the most recent `BlacklistTag`, restoring the posts it removed
+	UnblacklistTag {
+		tag: String,
+	},
+	/// Replace the local blacklist, dropping loaded posts it now catches
+	SetBlacklist {
+		tags: Vec<String>,
+	},
+	/// Drop one post for the rest of the session, moving on to the next
+	HidePost {
+		id: u64,
+	},
+	/// Undo `HidePost`, putting the post back where it was if it's the last one hidden
+	UnhidePost {
+		id: u64,
+	},
+	/// The account's blacklist as stored on the site, one rule per line
+	AccountBlacklistReceived {
+		text: String,
+	},
+	/// Stop or resume applying the account blacklist, restoring what it hid
+	ToggleAccountBlacklist,
+	/// Our vote on a post changed; `score` carries server totals when known
+	VoteUpdated {
+		post_id: u64,
+		vote: i8,
+		score: Option<Score>,
+	},
+	/// The current search has no pages after the ones already received
+	EndOfResults,
+	/// Answer to `FetchByIds`, in the order asked; the loaded results are left alone
+	PostsResolved {
+		purpose: LookupPurpose,
+		posts: Vec<Post>,
+		/// Deleted or unknown IDs
+		missing: Vec<u64>,
+	},
+	/// Reorder the loaded posts, and place later pages by the same order
+	SortLoaded {
+		key: SortKey,
+		descending: bool,
+	},
 }
 
 #[derive(Clone, Debug)]
@@ -85,29 +264,219 @@ pub enum MediaEvent {
 		is_video: bool,
 	},
 	LoadError {
+		cache_key: String,
 		error: String,
+		/// Retrying will not help (missing file, not an image)
+		permanent: bool,
+		/// The displayed item failed and has nothing to show
+		current: bool,
+	},
+	/// Forget permanent failures for the current item and load it again
+	RetryCurrent,
+	/// Load the current item's original on the priority worker now, if it isn't shown yet
+	ForceFullRes,
+	/// Drop the current item from the cache and load it again from scratch
+	ReloadCurrent,
+	/// Textures may be gone from the GPU (e.g. after sleep/resume); upload them
+	/// again from the decoded pixels
+	ReuploadTextures,
+	/// Keep an entry shown in the compare pane cached and on the GPU; `None` unpins
+	Pin {
+		cache_key: Option<String>,
+	},
+	/// Drop one cached image, from the cache inspector. The current item's is
+	/// loaded again from scratch.
+	Evict {
+		cache_key: String,
 	},
+	/// Empty one part of the cache, from the cache inspector
+	ClearCache {
+		category: CacheCategory,
+	},
+	/// Make blurred previews of decoded images, for reveal mode
+	SetBlurPreviews {
+		enabled: bool,
+	},
+	/// Upcoming posts, nearest first; replaces the previous prefetch
 	Prefetch {
 		urls: Vec<(Option<String>, Option<String>, bool)>, // (sample_url, full_url, is_video)
 	},
+	/// Save `count` posts starting at `from_index` into `dest` (resolved by the browser)
+	QueueDownloadRange {
+		from_index: usize,
+		count: usize,
+		dest: PathBuf,
+	},
+	QueueDownloads {
+		items: Vec<(String, PathBuf)>, // (full_url, destination path)
+	},
+	SetDownloadsPaused {
+		paused: bool,
+	},
+	CancelDownloads,
 }
 
 #[derive(Clone, Debug)]
 pub enum BreathingEvent {
 	Toggle,
-	PhaseComplete,
-	SetIdleMultiplier { value: f32 },
-	SetStyle { style: BreathingStyle },
-	PhaseStarted(BreathingPhase),
+	/// Timer for the current phase ran out (ignored if `generation` is stale)
+	PhaseComplete {
+		generation: u64,
+	},
+	/// Start a breathing cycle now, from Idle or Release; ignored mid-cycle
+	TriggerCycle,
+	/// Cut the current phase short and move on to the next one
+	SkipPhase,
+	/// Panic key: go to Release now, then a long Idle, whatever was scheduled
+	ForceRelease,
+	/// Cap on Inhale and Hold together; longer phases are cut to fit
+	SetMaxInhaleHold {
+		secs: f32,
+	},
+	SetIdleMultiplier {
+		value: f32,
+	},
+	/// Step the idle multiplier, staying within its range
+	AdjustIdleMultiplier {
+		delta: f32,
+	},
+	SetStyle {
+		style: BreathingStyle,
+	},
+	SetPalette {
+		prepare: egui::Color32,
+		inhale: egui::Color32,
+		hold: egui::Color32,
+		release: egui::Color32,
+	},
+	SetOverlayAnchor {
+		corner: OverlayAnchor,
+	},
+	/// Size multiplier for the overlay text and bar
+	SetOverlayScale {
+		scale: f32,
+	},
+	/// Classic pulse timing and size (fraction of screen height)
+	SetPulseParams {
+		duration_secs: f32,
+		scale: f32,
+	},
+	/// How far the immersive style zooms the image in on an inhale, as a
+	/// fraction of its size; 0 leaves the image alone
+	SetImageZoom {
+		strength: f32,
+	},
+	/// A breathing phase began. Fired exactly once per transition, for the first
+	/// phase at startup and for skipped-to phases too, whether or not the overlay
+	/// is shown. `duration` is the new phase's full length.
+	PhaseStarted {
+		phase: BreathingPhase,
+		duration: Duration,
+	},
 }
 
 #[derive(Clone, Debug)]
 pub enum ViewEvent {
 	MediaReady,
+	/// The current image's original replaced its sample on screen. `mismatch` is
+	/// set when the two aren't the same shape, so a pan can't simply be scaled.
+	SampleUpgraded {
+		mismatch: Option<AspectMismatch>,
+	},
+	/// Toggle breathing, asking for the disclaimer first if it wasn't accepted yet
 	RequestBreathingToggle,
-	BeatPulse { scale: f32 },
-	SetImageFillMode { mode: ImageFillMode },
+	BeatPulse {
+		scale: f32,
+	},
+	SetImageFillMode {
+		mode: ImageFillMode,
+	},
 	ToggleImageFillMode,
+	/// Open or close the borderless mirror window
+	ToggleMirror,
+	/// Enter or leave fullscreen in the main window
+	SetFullscreen {
+		fullscreen: bool,
+	},
+	SetPanMode {
+		mode: PanMode,
+	},
+	NotesReceived {
+		post_id: u64,
+		notes: Vec<Note>,
+	},
+	/// Replace the text in the search box
+	SetSearchQuery {
+		query: String,
+	},
+	/// Problems found in the last submitted query (empty when it was accepted)
+	QueryWarnings {
+		warnings: Vec<QueryWarning>,
+	},
+	ShowToast {
+		message: String,
+	},
+	/// Toast with an Undo button that emits `undo`
+	ShowUndoToast {
+		message: String,
+		undo: Box<Event>,
+	},
+	/// Open a modal once those already open are closed
+	ShowModal {
+		kind: ModalKind,
+	},
+	/// Close the modal on screen
+	DismissModal,
+	/// A modal came up over the image, or the last one went away; the
+	/// slideshow waits meanwhile
+	ModalOpened,
+	ModalClosed,
+	/// The terms were accepted on the welcome modal; the startup search may run
+	TosAccepted,
+}
+
+/// A modal popup. Several can be requested; they are shown one at a time, oldest first.
+#[derive(Clone, Debug)]
+pub enum ModalKind {
+	Hello,
+	BreathingDisclaimer,
+	/// A question with a button per answer
+	Confirm {
+		title: String,
+		body: String,
+		buttons: Vec<ModalButton>,
+	},
+	/// Results of the self-check, with a button to copy them
+	Diagnostics {
+		report: crate::diagnose::DiagnosticsReport,
+	},
+}
+
+/// A button of a `ModalKind::Confirm`; pressing it closes the modal and sends `event`
+#[derive(Clone, Debug)]
+pub struct ModalButton {
+	pub label: String,
+	pub event: Option<Event>,
+}
+
+impl ModalKind {
+	/// Ask before doing something: Cancel does nothing, `confirm_label` sends `event`
+	pub fn confirm(title: &str, body: &str, confirm_label: &str, event: Event) -> Self {
+		ModalKind::Confirm {
+			title: title.to_string(),
+			body: body.to_string(),
+			buttons: vec![
+				ModalButton {
+					label: "Cancel".to_string(),
+					event: None,
+				},
+				ModalButton {
+					label: confirm_label.to_string(),
+					event: Some(event),
+				},
+			],
+		}
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -122,17 +491,116 @@ pub enum SettingsEvent {
 	AdjustDelay {
 		delta_secs: i64,
 	},
-	/// Timer fired, advance slideshow
-	SlideshowAdvance,
+	/// Timer fired, advance slideshow (ignored if `generation` is stale)
+	SlideshowAdvance {
+		generation: u64,
+	},
+	/// Move past an image that failed to load (ignored if `generation` is stale)
+	SkipFailed {
+		generation: u64,
+	},
 	ToggleCapByBreathing,
+	/// Hold slideshow advances while breathing in or holding
+	ToggleBreathHold,
+	/// Calm overlays down for motion-sensitive users
+	ToggleReduceMotion,
+	/// Bolder outlined text on overlays
+	ToggleHighContrast,
+	/// Fit to width and page down through posts; holds the slideshow
+	ToggleReadingMode,
+	/// A pool was entered or left. Entering one turns reading mode on, and leaving
+	/// it turns it off again if entering was what turned it on.
+	PoolReading {
+		entered: bool,
+	},
+	/// Only rating:s posts; ignored while `--sfw` locks it on
+	ToggleSafeMode,
+	/// Safe mode went on or off; the gateway rewrites queries, the browser filters
+	SafeModeChanged {
+		enabled: bool,
+	},
+	/// What to search for once the terms are accepted at startup
+	SetStartupSearch {
+		behavior: StartupSearch,
+	},
+	SetDefaultQuery {
+		query: String,
+	},
+	/// Hold off slideshow advances while the user pans, zooms or navigates
+	SetAutoPause {
+		enabled: bool,
+		idle_secs: f32,
+	},
+	/// The user just interacted; don't advance until `secs` of inactivity
+	SuppressAdvanceFor {
+		secs: f32,
+	},
+	/// Keep pending (unapproved) posts in results instead of filtering them
+	SetShowPending {
+		enabled: bool,
+	},
+	/// Let Next/Prev wrap around the ends of the result list instead of stopping there
+	SetWrapNavigation {
+		enabled: bool,
+	},
+	/// Request the next page once this few posts are left after the current one
+	SetFetchAhead {
+		posts: usize,
+	},
+	/// Which info overlay fields are shown, in display order
+	SetInfoFields {
+		fields: Vec<InfoFieldSetting>,
+	},
+	SetInfoVerbosity {
+		verbosity: InfoVerbosity,
+	},
+	/// Full → compact → hidden
+	CycleInfoVerbosity,
+	/// Artist credit overlay: shown or not, which corner, how opaque
+	SetCreditOverlay {
+		credit: CreditOverlay,
+	},
+	/// Accent color, top panel opacity and dark or light mode
+	SetTheme {
+		accent: egui::Color32,
+		panel_opacity: f32,
+		dark: bool,
+	},
+	/// Write the blacklist and general settings to a file for another machine
+	ExportConfig {
+		path: PathBuf,
+	},
+	/// Apply a file from `ExportConfig`, on top of the current settings or instead of them
+	ImportConfig {
+		path: PathBuf,
+		replace: bool,
+	},
+	/// Check the connection, audio and settings folder, and show what was found
+	RunDiagnostics,
 }
 
 #[derive(Clone, Debug)]
 pub enum BeatEvent {
-	/// A beat was detected
+	/// A beat was detected while locked onto a steady tempo
 	Beat { scale: f32 },
+	/// Detected onsets stopped being periodic, e.g. music gave way to speech
+	LockLost,
+	/// The input has been silent for several seconds; sent once until sound returns
+	SilenceDetected,
 	/// Switch capture device (None = system default)
 	SetDevice { name: Option<String> },
+	/// Re-enumerate capture devices
+	RefreshDevices,
+}
+
+#[derive(Clone, Debug)]
+pub enum FavoritesEvent {
+	/// Add the current post to the local favorites, or remove it if it's there
+	ToggleCurrent,
+	/// Write the favorites as a text file of post URLs
+	Export { path: PathBuf },
+	/// Fetch every favorite again, e.g. for current scores and tags
+	Refresh,
 }
 
 /// Response from component.handle()
@@ -169,4 +637,10 @@ impl ComponentResponse {
 			scheduled: vec![(event, delay)],
 		}
 	}
+
+	/// Add another component's response after this one's
+	pub fn merge(&mut self, other: ComponentResponse) {
+		self.events.extend(other.events);
+		self.scheduled.extend(other.scheduled);
+	}
 }
@@ -1,27 +1,71 @@
+pub mod component;
 pub mod event;
+pub mod poll_stats;
 pub mod queue;
+pub mod repaint;
 pub mod scheduler;
 
+#[cfg(test)]
+mod tests;
+
+pub use component::Component;
 pub use event::{
-	BeatEvent, BreathingEvent, BrowserEvent, ComponentResponse, Event, GatewayEvent, MediaEvent,
-	SettingsEvent, SourceEvent, ViewEvent,
+	BeatEvent, BreathingEvent, BrowserEvent, ComponentResponse, Event, FavoritesEvent,
+	GatewayEvent, LookupPurpose, MediaEvent, ModalKind, PageSource, SettingsEvent, SourceEvent,
+	ViewEvent,
 };
+pub use poll_stats::{PollCounts, PollStats};
 pub use queue::EventQueue;
+pub use repaint::{RepaintCause, RepaintCounts, RepaintStats, Repaints};
 pub use scheduler::Scheduler;
 
 use crate::beat::SystemBeat;
 use crate::breathing::BreathingOverlay;
 use crate::browser::ContentBrowser;
+use crate::cli::StartupOptions;
 use crate::coach::CoachManager;
+use crate::favorites::LocalFavorites;
 use crate::gateway::BooruGateway;
-use crate::media::MediaCache;
+use crate::media::{MediaCache, Readiness};
 use crate::settings::SettingsManager;
-use crate::view::ViewManager;
+use crate::types::{StartupSearch, Theme};
+use crate::view::{PULSE_FRAME, ViewManager};
 use eframe::egui;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long exiting waits for downloads that are mid-write
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// How often results that don't wake the UI themselves are polled for,
+/// and how often audio is drained while the window is in the background
+const BACKGROUND_POLL: Duration = Duration::from_millis(250);
+
+/// A gap between frames this long means the machine may have slept, and the
+/// driver may have dropped our textures on resume; they are uploaded again
+const RESUME_GAP: Duration = Duration::from_secs(120);
+
+/// Time one frame may spend draining the event queue. What's left waits for the
+/// next frame, except critical events, so errors still show right away.
+const EVENT_BUDGET: Duration = Duration::from_millis(3);
+
+/// Events one frame handles at most, critical ones included, in case handling them
+/// keeps queueing more faster than the budget notices
+const MAX_EVENTS_PER_FRAME: usize = 1000;
+
+/// How long a watch poll waits while a modal is open or the window is in the background
+const WATCH_POLL_RETRY: Duration = Duration::from_secs(60);
 
 pub struct Reactor {
 	queue: EventQueue,
 	scheduler: Scheduler,
+	poll_stats: PollStats,
+	repaint_stats: RepaintStats,
+	// Wall clock rather than Instant, which stops during suspend on some platforms
+	last_frame: SystemTime,
+	event_budget: Duration,
+	/// Theme last handed to egui, so visuals are only replaced when it changes
+	applied_theme: Option<Theme>,
 
 	pub gateway: BooruGateway,
 	pub browser: ContentBrowser,
@@ -30,44 +74,33 @@ pub struct Reactor {
 	pub view: ViewManager,
 	pub settings: SettingsManager,
 	pub beat: SystemBeat,
+	pub favorites: LocalFavorites,
 	pub coach: Option<CoachManager>,
+	/// Components added with `register`, in the order they were added
+	registered: Vec<Box<dyn Component>>,
 }
 
 impl Reactor {
-	pub fn new(ctx: &egui::Context) -> Self {
+	pub fn new(ctx: &egui::Context, startup: StartupOptions) -> Self {
 		log::info!("Initializing all components");
 		let settings = crate::config::load_settings();
+		let credentials = settings.credentials();
 
-		let mut reactor = Self {
-			queue: EventQueue::new(),
-			scheduler: Scheduler::new(),
-			gateway: BooruGateway::new(),
-			browser: ContentBrowser::new(),
-			media: MediaCache::new(ctx),
-			breathing: BreathingOverlay::new(
-				false, // Breathing always starts off
-				settings.breathing_idle_multiplier,
-				settings.breathing_style,
-			),
-			view: ViewManager::new(
-				settings.search_query,
-				settings.search_page_input,
-				settings.auto_pan_cycle_duration,
-				settings.beat_pulse_enabled,
-				settings.beat_pulse_scale,
-				settings.image_fill_mode,
-				settings.coach_enabled,
-				settings.coach_model.clone(),
-				settings.coach_preset.clone(),
-			),
-			settings: SettingsManager::new(
-				settings.auto_play,
-				std::time::Duration::from_secs_f32(settings.auto_play_delay_secs),
-				settings.cap_by_breathing,
-			),
-			beat: SystemBeat::new(settings.selected_audio_device),
-			coach: None,
-		};
+		if startup.sfw {
+			log::info!("Safe mode locked on from the command line");
+		}
+		let mut reactor = Self::from_parts(
+			&settings,
+			startup.sfw,
+			BooruGateway::new(credentials),
+			MediaCache::new(ctx),
+			if startup.no_audio {
+				SystemBeat::new_disabled()
+			} else {
+				SystemBeat::new(settings.selected_audio_device.clone())
+			},
+			LocalFavorites::load(),
+		);
 
 		if settings.coach_enabled {
 			if let (Some(m), Some(p), Some(mdir), Some(pdir)) = (
@@ -84,13 +117,192 @@ impl Reactor {
 			}
 		}
 
+		let (client, limiter) = reactor.gateway.api();
+		let diagnostics = crate::diagnose::Diagnostics::new(
+			client,
+			limiter,
+			reactor.media.loader(),
+			crate::config::get_config_dir(),
+		);
+		reactor.register(Box::new(crate::diagnose::DiagnosticsRunner::new(
+			diagnostics,
+			ctx,
+		)));
+
 		// Initialize all components
 		reactor.process_response(reactor.breathing.init());
+		let response = reactor.gateway.init();
+		reactor.process_response(response);
+		let response = reactor.startup_events(startup);
+		reactor.process_response(response);
 		log::info!("Initialization complete");
 
 		reactor
 	}
 
+	/// Wire components around the IO-backed ones, which are built by the caller.
+	/// `safe_mode_locked` forces safe mode on for the session, as `--sfw` does.
+	fn from_parts(
+		settings: &crate::config::SavedSettings,
+		safe_mode_locked: bool,
+		gateway: BooruGateway,
+		media: MediaCache,
+		beat: SystemBeat,
+		favorites: LocalFavorites,
+	) -> Self {
+		let safe_mode = settings.safe_mode || safe_mode_locked;
+		Self {
+			queue: EventQueue::new(),
+			scheduler: Scheduler::new(),
+			poll_stats: PollStats::new(),
+			repaint_stats: RepaintStats::new(),
+			last_frame: SystemTime::now(),
+			event_budget: EVENT_BUDGET,
+			applied_theme: None,
+			gateway: gateway
+				.with_fanout_pages(settings.search_fanout_pages)
+				.with_safe_mode(safe_mode)
+				.with_watches(settings.watches.clone(), settings.watch_interval_mins),
+			browser: ContentBrowser::new(settings.show_pending, settings.blacklist.clone())
+				.with_wrap_navigation(settings.wrap_navigation)
+				.with_fetch_ahead(settings.fetch_ahead_posts)
+				.with_safe_mode(safe_mode),
+			media: media.with_blur_previews(settings.reveal_mode),
+			breathing: BreathingOverlay::new(settings),
+			view: ViewManager::new(
+				settings.search_query.clone(),
+				settings.search_page_input.clone(),
+				settings.auto_pan_cycle_duration,
+				settings.pan_mode,
+				settings.beat_pulse_enabled,
+				settings.beat_pulse_scale,
+				settings.image_fill_mode,
+				settings.coach_enabled,
+				settings.coach_model.clone(),
+				settings.coach_preset.clone(),
+				settings.remember_view_positions,
+				settings.wheel_navigation,
+				settings.show_status_bar,
+			)
+			.with_reveal(
+				settings.reveal_mode,
+				Duration::from_secs_f32(settings.reveal_auto_secs),
+			)
+			.with_key_pan_speed(settings.key_pan_speed)
+			.with_filmstrip(settings.show_filmstrip)
+			.with_locked_safe_mode(safe_mode_locked),
+			settings: SettingsManager::new(
+				settings.auto_play,
+				std::time::Duration::from_secs_f32(settings.auto_play_delay_secs),
+				settings.cap_by_breathing,
+				settings.auto_pause_on_interaction,
+				std::time::Duration::from_secs_f32(settings.auto_pause_idle_secs),
+				settings.info_fields.clone(),
+				settings.info_verbosity,
+			)
+			.with_accessibility(settings.reduce_motion, settings.high_contrast_overlays)
+			.with_safe_mode(settings.safe_mode, safe_mode_locked)
+			.with_credit_overlay(settings.credit_overlay)
+			.with_theme(settings.theme)
+			.with_breath_hold(settings.hold_slideshow_during_breath)
+			.with_startup_search(settings.startup_search, settings.default_query.clone()),
+			beat,
+			favorites,
+			coach: None,
+			registered: Vec::new(),
+		}
+	}
+
+	/// Events that put the components in the state asked for on the command line
+	fn startup_events(&self, startup: StartupOptions) -> ComponentResponse {
+		let mut events = Vec::new();
+		if startup.fullscreen {
+			events.push(Event::View(ViewEvent::SetFullscreen { fullscreen: true }));
+		}
+		if let Some(delay) = startup.autoplay {
+			// Set first so the slideshow starts out with it
+			events.push(Event::Settings(SettingsEvent::SetDelay { duration: delay }));
+			if !self.settings.auto_play() {
+				events.push(Event::Settings(SettingsEvent::ToggleAutoPlay));
+			}
+		}
+		if let Some(query) = startup.query {
+			log::info!("Starting with query '{}', page {}", query, startup.page);
+			events.push(Event::View(ViewEvent::SetSearchQuery {
+				query: query.clone(),
+			}));
+			events.push(Event::Source(SourceEvent::Search {
+				query,
+				page: startup.page,
+			}));
+		}
+		ComponentResponse::emit_many(events)
+	}
+
+	/// The search picked for startup, once the terms are accepted.
+	/// Skipped if one already started, e.g. from the command line.
+	fn startup_search(&self) -> ComponentResponse {
+		if !self.gateway.current_query().is_empty() {
+			return ComponentResponse::none();
+		}
+		let (query, page) = match self.settings.startup_search() {
+			StartupSearch::Nothing => return ComponentResponse::none(),
+			StartupSearch::LastSession => (
+				self.view.search_query.clone(),
+				self.view
+					.search_page_input
+					.parse::<u32>()
+					.unwrap_or(1)
+					.max(1),
+			),
+			StartupSearch::DefaultQuery => (self.settings.default_query().to_string(), 1),
+		};
+		if query.trim().is_empty() {
+			return ComponentResponse::none();
+		}
+		log::info!("Startup search: '{}', page {}", query, page);
+		ComponentResponse::emit_many(vec![
+			Event::View(ViewEvent::SetSearchQuery {
+				query: query.clone(),
+			}),
+			Event::Source(SourceEvent::Search { query, page }),
+		])
+	}
+
+	/// Headless reactor with default settings and the given fakes for network IO.
+	/// Audio is disabled and nothing is read from or written to disk.
+	#[cfg(test)]
+	pub fn new_for_test(
+		client: Box<dyn crate::api::BooruClient>,
+		loader: Box<dyn crate::media::loader::MediaLoader>,
+	) -> Self {
+		let settings = crate::config::SavedSettings::default();
+		let mut reactor = Self::from_parts(
+			&settings,
+			false,
+			BooruGateway::new_with_client(client),
+			MediaCache::new_with_loader(&egui::Context::default(), loader),
+			SystemBeat::new_disabled(),
+			LocalFavorites::in_memory(),
+		);
+		reactor.process_response(reactor.breathing.init());
+		let response = reactor.gateway.init();
+		reactor.process_response(response);
+		reactor
+	}
+
+	/// Queue `events`, then run one tick without rendering.
+	/// Returns every event routed during the tick, in order.
+	#[cfg(test)]
+	pub fn tick_events(&mut self, events: Vec<Event>) -> Vec<Event> {
+		for event in events {
+			self.queue.push(event);
+		}
+		let mut routed = Vec::new();
+		self.process_events(|event| routed.push(event.clone()));
+		routed
+	}
+
 	fn process_response(&mut self, response: ComponentResponse) {
 		for e in response.events {
 			self.queue.push(e);
@@ -101,16 +313,113 @@ impl Reactor {
 	}
 
 	pub fn tick(&mut self, ctx: &egui::Context) {
+		let now = SystemTime::now();
+		if let Ok(gap) = now.duration_since(self.last_frame)
+			&& gap >= RESUME_GAP
+		{
+			log::warn!(
+				"No frame for {:.0}s, re-uploading textures in case the GPU was reset",
+				gap.as_secs_f32()
+			);
+			self.queue.push(Event::Media(MediaEvent::ReuploadTextures));
+		}
+		self.last_frame = now;
+
+		self.process_events(|_| {});
+		let theme = self.settings.theme();
+		if self.applied_theme != Some(theme) {
+			ctx.set_visuals(theme.visuals());
+			self.applied_theme = Some(theme);
+		}
+		self.view.poll_skips = self.poll_stats.skipped_per_second();
+		self.view.repaint_counts = self.repaint_stats.per_second();
+
+		// Render
+		let events = {
+			let gateway = &self.gateway;
+			let browser = &self.browser;
+			let breathing = &self.breathing;
+			let settings = &self.settings;
+			let beat = &self.beat;
+
+			self.view.render(
+				ctx,
+				gateway,
+				browser,
+				&mut self.media,
+				breathing,
+				settings,
+				beat,
+			)
+		};
+
+		// Process any events from rendering immediately
+		for event in events {
+			log::trace!("Processing render event: {:?}", event);
+			let response = self.route(&event);
+			self.process_response(response);
+		}
+
+		self.request_wakes();
+		let causes = self.view.repaints.flush(ctx);
+		self.repaint_stats.record(&causes);
+	}
+
+	/// Ask for frames needed by work that can't request one itself, next to what the
+	/// view asked for. Nothing asked lets the UI sleep until input or a finished media load.
+	fn request_wakes(&mut self) {
+		if !self.queue.is_empty() {
+			// Events carried over from a frame that ran out of budget
+			self.view.repaints.now(RepaintCause::Scheduled);
+		}
+		if let Some(at) = self.scheduler.next_due() {
+			let due = at.saturating_duration_since(Instant::now());
+			self.view.repaints.after(RepaintCause::Scheduled, due);
+		}
+		if let Some(at) = self.media.next_retry() {
+			// A current image that failed for now is loaded again once its backoff runs out
+			let due = at.saturating_duration_since(Instant::now());
+			self.view.repaints.after(RepaintCause::Scheduled, due);
+		}
+		if self.beat.is_active() && !self.view.is_backgrounded() {
+			// Beats should pulse without a noticeable delay
+			self.view.repaints.after(RepaintCause::Polling, PULSE_FRAME);
+		} else if self.beat.is_active()
+			|| self.gateway.pending_requests() > 0
+			|| self.coach.is_some()
+		{
+			self.view
+				.repaints
+				.after(RepaintCause::Polling, BACKGROUND_POLL);
+		}
+	}
+
+	/// Fire due scheduled events, poll async components and drain the queue.
+	/// `on_event` sees each event right before it is routed.
+	fn process_events(&mut self, mut on_event: impl FnMut(&Event)) {
 		// Drain scheduled events
 		self.scheduler.tick(&mut self.queue);
 
-		// Poll async components
-		let gateway_response = self.gateway.poll();
-		let media_response = self.media.poll();
-		let beat_response = self.beat.poll();
-		self.process_response(gateway_response);
-		self.process_response(media_response);
-		self.process_response(beat_response);
+		// Poll async components that have something waiting
+		self.poll_stats.record(
+			!self.gateway.has_pending(),
+			!self.media.has_pending(),
+			!self.beat.has_pending(),
+		);
+		let mut polled = Vec::new();
+		for component in self.builtin() {
+			if component.has_pending() {
+				polled.push(component.poll());
+			}
+		}
+		for component in &mut self.registered {
+			if component.has_pending() {
+				polled.push(component.poll());
+			}
+		}
+		for response in polled {
+			self.process_response(response);
+		}
 
 		if let Some(coach) = &self.coach {
 			if let Some(output) = coach.try_recv() {
@@ -157,105 +466,235 @@ impl Reactor {
 			}
 		}
 
-		// Process event queue until empty
-		let mut iterations = 0;
-		while let Some(event) = self.queue.pop() {
+		// Process the event queue until it's empty or the frame's budget is spent
+		let started = Instant::now();
+		let mut over_budget = false;
+		let mut processed = 0;
+		loop {
+			let event = if over_budget {
+				self.queue.pop_critical()
+			} else {
+				self.queue.pop()
+			};
+			let Some(event) = event else {
+				break;
+			};
 			log::trace!("Processing event: {:?}", event);
+			on_event(&event);
 			let response = self.route(&event);
 			self.process_response(response);
 
-			iterations += 1;
-			if iterations > 1000 {
-				log::warn!("Event loop exceeded 1000 iterations, breaking");
-				break;
+			processed += 1;
+			if processed >= MAX_EVENTS_PER_FRAME {
+				log::warn!(
+					"Event loop reached {} events, carrying {} over to the next frame",
+					processed,
+					self.queue.len()
+				);
+				return;
 			}
+			over_budget = over_budget || started.elapsed() >= self.event_budget;
 		}
+		if over_budget && !self.queue.is_empty() {
+			log::debug!(
+				"Processed {} events in {:.1}ms, carrying {} over to the next frame",
+				processed,
+				started.elapsed().as_secs_f64() * 1000.0,
+				self.queue.len()
+			);
+		}
+	}
 
-		// Render
-		let events = {
-			let gateway = &self.gateway;
-			let browser = &self.browser;
-			let breathing = &self.breathing;
-			let settings = &self.settings;
-			let beat = &self.beat;
+	/// Let stress tests drain the queue in smaller slices
+	#[cfg(test)]
+	pub fn set_event_budget(&mut self, budget: Duration) {
+		self.event_budget = budget;
+	}
 
-			self.view.render(
-				ctx,
-				gateway,
-				browser,
-				&mut self.media,
-				breathing,
-				settings,
-				beat,
-			)
+	/// Hand `event` to whoever handles it. Components and settings are reached
+	/// through `dispatch`; the arms before it are the events the reactor handles
+	/// itself, or that need the state of several components. Favorites are called
+	/// here, as they read the browser's current post.
+	fn route(&mut self, event: &Event) -> ComponentResponse {
+		let in_pool = crate::gateway::query::is_pool(self.gateway.current_query());
+		let mut response = match event {
+			Event::Source(e) => self.handle_source(e),
+			Event::Gateway(GatewayEvent::PollWatch { .. }) if self.view.holds_watch_polls() => {
+				// Tried again later, so a watch doesn't interrupt a dialog or spend
+				// requests while nobody is looking
+				ComponentResponse::schedule(event.clone(), WATCH_POLL_RETRY)
+			}
+			Event::Browser(BrowserEvent::PostsResolved {
+				purpose: LookupPurpose::RefreshFavorites,
+				..
+			})
+			| Event::Favorites(_) => self.favorites.handle(event, self.browser.current_post()),
+			Event::View(ViewEvent::TosAccepted) => self.startup_search(),
+			Event::Settings(SettingsEvent::ExportConfig { path }) => self.export_config(path),
+			Event::Settings(SettingsEvent::ImportConfig { path, replace }) => {
+				self.import_config(path, *replace)
+			}
+			Event::Settings(SettingsEvent::SlideshowAdvance { generation }) => {
+				let held = if self.next_image_started() {
+					None
+				} else {
+					self.settings.wait_for_next_image(*generation)
+				};
+				held.unwrap_or_else(|| self.dispatch(event))
+			}
+			_ => self.dispatch(event),
 		};
-
-		// Process any events from rendering immediately
-		for event in events {
-			log::trace!("Processing render event: {:?}", event);
-			let response = self.route(&event);
-			self.process_response(response);
+		if in_pool && !crate::gateway::query::is_pool(self.gateway.current_query()) {
+			// However the search moved on, reading mode turned on for the pool goes with it
+			response
+				.events
+				.push(Event::Settings(SettingsEvent::PoolReading {
+					entered: false,
+				}));
 		}
+		self.tell_coach(event);
+		self.dispatch_registered(event, response)
 	}
 
-	fn route(&mut self, event: &Event) -> ComponentResponse {
-		let mut response;
-
+	/// Pass navigation and breathing phases on to the coach, if one is running
+	fn tell_coach(&self, event: &Event) {
+		let Some(coach) = &self.coach else {
+			return;
+		};
 		match event {
-			Event::Source(e) => response = self.handle_source(e),
-			Event::Gateway(_) => response = self.gateway.handle(event),
-			Event::Browser(b) => {
-				response = self.browser.handle(event);
-				if let BrowserEvent::Navigate { direction } = b {
-					if let Some(coach) = &self.coach {
-						let coach_event = match direction {
-							crate::types::NavDirection::Next => crate::coach::CoachEvent::NextImage,
-							crate::types::NavDirection::Prev => crate::coach::CoachEvent::PrevImage,
-							crate::types::NavDirection::Skip(s) => {
-								if *s > 0 {
-									crate::coach::CoachEvent::NextImage
-								} else {
-									crate::coach::CoachEvent::PrevImage
-								}
-							}
-						};
-						coach.send_event(coach_event);
+			Event::Browser(BrowserEvent::Navigate { direction }) => {
+				let coach_event = match direction {
+					crate::types::NavDirection::Next => crate::coach::CoachEvent::NextImage,
+					crate::types::NavDirection::Prev => crate::coach::CoachEvent::PrevImage,
+					crate::types::NavDirection::Skip(s) => {
+						if *s > 0 {
+							crate::coach::CoachEvent::NextImage
+						} else {
+							crate::coach::CoachEvent::PrevImage
+						}
 					}
-					let settings_res = self.settings.handle(event, &self.breathing);
-					response.events.extend(settings_res.events);
-					response.scheduled.extend(settings_res.scheduled);
-				}
+					// A jump shows another image, whichever way it went
+					crate::types::NavDirection::ToPosition(_)
+					| crate::types::NavDirection::ToPage(_) => crate::coach::CoachEvent::NextImage,
+				};
+				coach.send_event(coach_event);
 			}
-			Event::Media(_) => response = self.media.handle(event),
-			Event::View(_) => response = self.view.handle(event),
-			Event::Beat(_) => response = self.beat.handle(event),
-			Event::Breathing(b) => {
-				response = self.breathing.handle(event);
-				if let BreathingEvent::PhaseStarted(p) = b {
-					if let Some(coach) = &self.coach {
-						coach.send_event(crate::coach::CoachEvent::PhaseChange(format!("{:?}", p)));
-					}
-					// Route PhaseStarted to settings as well
-					let settings_res = self.settings.handle(event, &self.breathing);
-					response.events.extend(settings_res.events);
-					response.scheduled.extend(settings_res.scheduled);
-				}
+			Event::Breathing(BreathingEvent::PhaseStarted { phase, .. }) => {
+				coach.send_event(crate::coach::CoachEvent::PhaseChange(format!(
+					"{:?}",
+					phase
+				)));
 			}
-			Event::Settings(_) => response = self.settings.handle(event, &self.breathing),
+			_ => {}
 		}
+	}
+
+	/// The built-in components, in the order they see an event
+	fn builtin(&mut self) -> [&mut dyn Component; 6] {
+		[
+			&mut self.gateway,
+			&mut self.browser,
+			&mut self.media,
+			&mut self.breathing,
+			&mut self.beat,
+			&mut self.view,
+		]
+	}
 
+	/// Hand `event` to the built-in components that take it, then to settings,
+	/// which read the breathing overlay while handling
+	fn dispatch(&mut self, event: &Event) -> ComponentResponse {
+		let mut response = ComponentResponse::none();
+		for component in self.builtin() {
+			if component::takes(component, event) {
+				response.merge(component.handle(event));
+			}
+		}
+		if self.settings.wants(event) {
+			response.merge(self.settings.handle(event, &self.breathing));
+		}
 		response
 	}
 
+	/// Hand `event` to the registered components that take it, after `response`
+	/// from everyone else
+	fn dispatch_registered(
+		&mut self,
+		event: &Event,
+		mut response: ComponentResponse,
+	) -> ComponentResponse {
+		for component in &mut self.registered {
+			if component::takes(component.as_ref(), event) {
+				response.merge(component.handle(event));
+			}
+		}
+		response
+	}
+
+	/// Add a component that gets the events it's interested in and is polled every
+	/// frame, without a field of its own
+	pub fn register(&mut self, component: Box<dyn Component>) {
+		self.registered.push(component);
+	}
+
+	/// Start `query`, remembering the current results so `PreviousSearch` can return to them
+	fn search_from_here(&mut self, query: String) -> ComponentResponse {
+		self.browser.push_snapshot();
+		self.gateway.push_snapshot();
+		ComponentResponse::emit_many(vec![
+			Event::View(ViewEvent::SetSearchQuery {
+				query: query.clone(),
+			}),
+			Event::Source(SourceEvent::Search { query, page: 1 }),
+		])
+	}
+
+	/// Whether the slideshow's next post has something to show, or never will
+	fn next_image_started(&self) -> bool {
+		self.browser
+			.peek_next(1)
+			.first()
+			.is_none_or(|post| self.media.readiness(post) != Readiness::NotStarted)
+	}
+
 	fn handle_source(&mut self, event: &SourceEvent) -> ComponentResponse {
 		match event {
 			SourceEvent::Search { query, page } => {
 				log::info!("Source search: query='{}', page={}", query, page);
-				ComponentResponse::emit(Event::Gateway(GatewayEvent::SearchRequest {
-					query: query.clone(),
-					page: *page,
-					limit: 50,
-				}))
+				if query.trim() == crate::gateway::query::LOCAL_FAVORITES {
+					return ComponentResponse::emit_many(vec![
+						Event::View(ViewEvent::QueryWarnings {
+							warnings: Vec::new(),
+						}),
+						Event::Gateway(GatewayEvent::LocalResults {
+							query: query.trim().to_string(),
+							posts: self.favorites.posts(),
+						}),
+					]);
+				}
+				if let Some(id) = crate::gateway::query::set_id(query) {
+					return ComponentResponse::emit_many(vec![
+						Event::View(ViewEvent::QueryWarnings {
+							warnings: Vec::new(),
+						}),
+						Event::Source(SourceEvent::OpenSet { id }),
+					]);
+				}
+				let warnings = crate::gateway::query::validate(query);
+				if !warnings.is_empty() {
+					log::warn!("Query rejected with {} problem(s)", warnings.len());
+					return ComponentResponse::emit(Event::View(ViewEvent::QueryWarnings {
+						warnings,
+					}));
+				}
+				ComponentResponse::emit_many(vec![
+					Event::View(ViewEvent::QueryWarnings { warnings }),
+					Event::Gateway(GatewayEvent::SearchRequest {
+						query: query.clone(),
+						page: *page,
+						limit: 50,
+					}),
+				])
 			}
 			SourceEvent::Navigate(direction) => {
 				log::debug!("Source navigate: {:?}", direction);
@@ -263,6 +702,78 @@ impl Reactor {
 					direction: *direction,
 				}))
 			}
+			SourceEvent::MoreLikeThis => {
+				let Some(post) = self.browser.current_post() else {
+					return ComponentResponse::none();
+				};
+				let Some(query) =
+					crate::gateway::query::similar_query(post, self.gateway.current_query())
+				else {
+					return ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+						message: "No artist, character or species tags to search by".to_string(),
+					}));
+				};
+				log::info!("More like post {}: '{}'", post.id, query);
+				self.search_from_here(query)
+			}
+			SourceEvent::EnterPool { pool_id } => {
+				log::info!("Entering pool {}", pool_id);
+				let mut response = self.search_from_here(crate::gateway::query::pool(*pool_id));
+				response
+					.events
+					.push(Event::Settings(SettingsEvent::PoolReading {
+						entered: true,
+					}));
+				response
+			}
+			SourceEvent::OpenWatch { query } => {
+				log::info!("Opening watched search '{}'", query);
+				self.search_from_here(query.clone())
+			}
+			SourceEvent::OpenSet { id } => {
+				log::info!("Opening set {}", id);
+				ComponentResponse::emit_many(vec![
+					Event::View(ViewEvent::SetSearchQuery {
+						query: format!("{}{}", crate::gateway::query::SET_PREFIX, id),
+					}),
+					Event::Gateway(GatewayEvent::OpenSet { id: *id }),
+				])
+			}
+			SourceEvent::Reroll => {
+				let current = self.gateway.current_query();
+				if !crate::gateway::query::is_random(current) {
+					return ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+						message: "Only order:random searches can be rerolled".to_string(),
+					}));
+				}
+				let query = crate::gateway::query::without_seed(current);
+				log::info!("Rerolling '{}'", query);
+				ComponentResponse::emit_many(vec![
+					Event::View(ViewEvent::SetSearchQuery {
+						query: query.clone(),
+					}),
+					Event::Source(SourceEvent::Search { query, page: 1 }),
+				])
+			}
+			SourceEvent::PreviousSearch => {
+				let Some(query) = self.gateway.pop_snapshot() else {
+					return ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+						message: "No previous search to go back to".to_string(),
+					}));
+				};
+				let mut response = self.browser.pop_snapshot();
+				response
+					.events
+					.push(Event::View(ViewEvent::SetSearchQuery { query }));
+				// Any fan-out of the abandoned search is dropped with it
+				response
+					.events
+					.push(Event::Gateway(GatewayEvent::PageProgress {
+						loaded: 0,
+						total: 0,
+					}));
+				response
+			}
 		}
 	}
 }
@@ -273,23 +784,257 @@ impl eframe::App for Reactor {
 	}
 
 	fn save(&mut self, _storage: &mut dyn eframe::Storage) {
-		let saved = crate::config::SavedSettings {
+		self.save_settings();
+	}
+
+	/// Runs on the UI thread after the last frame, while the tokio runtime is still up
+	fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+		log::info!("Shutting down");
+		self.media.shutdown(SHUTDOWN_GRACE);
+		self.beat.shutdown();
+		self.coach = None;
+		self.save_settings();
+	}
+}
+
+impl Reactor {
+	/// Write settings.toml synchronously
+	fn save_settings(&self) {
+		crate::config::save_settings(&self.saved_settings());
+	}
+
+	/// Every component's settings as they are right now
+	fn saved_settings(&self) -> crate::config::SavedSettings {
+		crate::config::SavedSettings {
 			search_query: self.view.search_query.clone(),
 			search_page_input: self.view.search_page_input.clone(),
+			startup_search: self.settings.startup_search(),
+			default_query: self.settings.default_query().to_string(),
 			auto_play: self.settings.auto_play(),
 			auto_play_delay_secs: self.settings.auto_play_delay().as_secs_f32(),
 			cap_by_breathing: self.settings.cap_by_breathing(),
+			hold_slideshow_during_breath: self.settings.breath_hold(),
+			auto_pause_on_interaction: self.settings.auto_pause(),
+			auto_pause_idle_secs: self.settings.auto_pause_idle().as_secs_f32(),
+			info_fields: self.settings.info_fields().to_vec(),
+			info_verbosity: self.settings.info_verbosity(),
+			credit_overlay: self.settings.credit_overlay(),
+			theme: self.settings.theme(),
 			breathing_idle_multiplier: self.breathing.idle_multiplier(),
 			breathing_style: self.breathing.style(),
+			breathing_palette: self.breathing.palette(),
+			breathing_overlay_anchor: self.breathing.anchor(),
+			breathing_overlay_scale: self.breathing.overlay_scale(),
+			breathing_pulse_duration_secs: self.breathing.pulse_duration_secs(),
+			breathing_pulse_scale: self.breathing.pulse_scale(),
+			breathing_image_zoom: self.breathing.image_zoom(),
+			breathing_max_inhale_hold_secs: self.breathing.max_inhale_hold().as_secs_f32(),
 			auto_pan_cycle_duration: self.view.auto_pan_cycle_duration,
+			key_pan_speed: self.view.key_pan_speed,
+			pan_mode: self.view.pan_mode,
 			selected_audio_device: self.beat.selected_device().clone(),
 			beat_pulse_enabled: self.view.beat_pulse_enabled,
 			beat_pulse_scale: self.view.beat_pulse_scale,
 			image_fill_mode: self.view.image_fill_mode,
+			show_pending: self.browser.show_pending(),
+			wrap_navigation: self.browser.wrap_navigation(),
+			fetch_ahead_posts: self.browser.fetch_ahead(),
+			search_fanout_pages: self.gateway.fanout_pages(),
+			blacklist: self.browser.blacklist().to_vec(),
+			watches: self.gateway.watches().to_vec(),
+			watch_interval_mins: self.gateway.watch_interval_mins(),
+			reduce_motion: self.settings.reduce_motion(),
+			high_contrast_overlays: self.settings.high_contrast(),
+			safe_mode: self.settings.safe_mode_setting(),
+			remember_view_positions: self.view.remember_view_positions,
+			wheel_navigation: self.view.wheel_navigation,
+			show_status_bar: self.view.show_status_bar,
+			show_filmstrip: self.view.show_filmstrip,
+			reveal_mode: self.view.reveal.enabled,
+			reveal_auto_secs: self.view.reveal.auto_delay.as_secs_f32(),
 			coach_enabled: self.view.coach_enabled,
 			coach_model: self.view.coach_model.clone(),
 			coach_preset: self.view.coach_preset.clone(),
+			api_username: self.gateway.credentials().map(|c| c.username.clone()),
+			api_key: self.gateway.credentials().map(|c| c.api_key.clone()),
+		}
+	}
+
+	fn export_config(&self, path: &Path) -> ComponentResponse {
+		let message = match crate::export::write(path, &self.saved_settings()) {
+			Ok(()) => {
+				log::info!("Exported settings to {}", path.display());
+				format!("Exported settings to {}", path.display())
+			}
+			Err(e) => {
+				log::warn!("Settings export failed: {:#}", e);
+				format!("Export failed: {:#}", e)
+			}
+		};
+		ComponentResponse::emit(Event::View(ViewEvent::ShowToast { message }))
+	}
+
+	fn import_config(&mut self, path: &Path, replace: bool) -> ComponentResponse {
+		let imported = match crate::export::read(path, &self.saved_settings(), replace) {
+			Ok(imported) => imported,
+			Err(e) => {
+				log::warn!("Settings import from {} failed: {:#}", path.display(), e);
+				return ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+					message: format!("Import failed: {:#}", e),
+				}));
+			}
 		};
-		crate::config::save_settings(&saved);
+		if !imported.skipped.is_empty() {
+			log::info!("Import skipped: {}", imported.skipped.join(", "));
+		}
+		let mut response = self.apply_settings(&imported.settings);
+		response.events.push(Event::View(ViewEvent::ShowToast {
+			message: imported.summary(),
+		}));
+		response
+	}
+
+	/// Bring every component in line with `saved`, through their events where they have one
+	fn apply_settings(&mut self, saved: &crate::config::SavedSettings) -> ComponentResponse {
+		let settings = &self.settings;
+		let mut events = vec![
+			Event::Settings(SettingsEvent::SetDelay {
+				duration: Duration::from_secs_f32(saved.auto_play_delay_secs),
+			}),
+			Event::Settings(SettingsEvent::SetAutoPause {
+				enabled: saved.auto_pause_on_interaction,
+				idle_secs: saved.auto_pause_idle_secs,
+			}),
+			Event::Settings(SettingsEvent::SetInfoFields {
+				fields: saved.info_fields.clone(),
+			}),
+			Event::Settings(SettingsEvent::SetInfoVerbosity {
+				verbosity: saved.info_verbosity,
+			}),
+			Event::Settings(SettingsEvent::SetCreditOverlay {
+				credit: saved.credit_overlay,
+			}),
+			Event::Settings(SettingsEvent::SetTheme {
+				accent: saved.theme.accent,
+				panel_opacity: saved.theme.panel_opacity,
+				dark: saved.theme.dark,
+			}),
+			Event::Settings(SettingsEvent::SetStartupSearch {
+				behavior: saved.startup_search,
+			}),
+			Event::Settings(SettingsEvent::SetDefaultQuery {
+				query: saved.default_query.clone(),
+			}),
+			Event::Settings(SettingsEvent::SetShowPending {
+				enabled: saved.show_pending,
+			}),
+			Event::Settings(SettingsEvent::SetWrapNavigation {
+				enabled: saved.wrap_navigation,
+			}),
+			Event::Settings(SettingsEvent::SetFetchAhead {
+				posts: saved.fetch_ahead_posts,
+			}),
+			Event::Breathing(BreathingEvent::SetIdleMultiplier {
+				value: saved.breathing_idle_multiplier,
+			}),
+			Event::Breathing(BreathingEvent::SetStyle {
+				style: saved.breathing_style,
+			}),
+			Event::Breathing(BreathingEvent::SetPalette {
+				prepare: saved.breathing_palette.prepare,
+				inhale: saved.breathing_palette.inhale,
+				hold: saved.breathing_palette.hold,
+				release: saved.breathing_palette.release,
+			}),
+			Event::Breathing(BreathingEvent::SetOverlayAnchor {
+				corner: saved.breathing_overlay_anchor,
+			}),
+			Event::Breathing(BreathingEvent::SetOverlayScale {
+				scale: saved.breathing_overlay_scale,
+			}),
+			Event::Breathing(BreathingEvent::SetPulseParams {
+				duration_secs: saved.breathing_pulse_duration_secs,
+				scale: saved.breathing_pulse_scale,
+			}),
+			Event::Breathing(BreathingEvent::SetImageZoom {
+				strength: saved.breathing_image_zoom,
+			}),
+			Event::Breathing(BreathingEvent::SetMaxInhaleHold {
+				secs: saved.breathing_max_inhale_hold_secs,
+			}),
+			Event::Gateway(GatewayEvent::SetFanoutPages {
+				pages: saved.search_fanout_pages,
+			}),
+			Event::Browser(BrowserEvent::SetBlacklist {
+				tags: saved.blacklist.clone(),
+			}),
+			Event::Gateway(GatewayEvent::SetWatchInterval {
+				minutes: saved.watch_interval_mins,
+			}),
+			Event::Gateway(GatewayEvent::SetWatches {
+				watches: saved.watches.clone(),
+			}),
+			Event::Media(MediaEvent::SetBlurPreviews {
+				enabled: saved.reveal_mode,
+			}),
+			Event::View(ViewEvent::SetImageFillMode {
+				mode: saved.image_fill_mode,
+			}),
+		];
+		let toggles = [
+			(
+				settings.auto_play(),
+				saved.auto_play,
+				SettingsEvent::ToggleAutoPlay,
+			),
+			(
+				settings.cap_by_breathing(),
+				saved.cap_by_breathing,
+				SettingsEvent::ToggleCapByBreathing,
+			),
+			(
+				settings.breath_hold(),
+				saved.hold_slideshow_during_breath,
+				SettingsEvent::ToggleBreathHold,
+			),
+			(
+				settings.reduce_motion(),
+				saved.reduce_motion,
+				SettingsEvent::ToggleReduceMotion,
+			),
+			(
+				settings.high_contrast(),
+				saved.high_contrast_overlays,
+				SettingsEvent::ToggleHighContrast,
+			),
+			(
+				settings.safe_mode_setting(),
+				saved.safe_mode,
+				SettingsEvent::ToggleSafeMode,
+			),
+		];
+		for (current, wanted, toggle) in toggles {
+			if current != wanted {
+				events.push(Event::Settings(toggle));
+			}
+		}
+
+		// The view's own settings have no events; it reads them every frame
+		let view = &mut self.view;
+		view.auto_pan_cycle_duration = saved.auto_pan_cycle_duration;
+		view.key_pan_speed = saved.key_pan_speed;
+		view.pan_mode = saved.pan_mode;
+		view.beat_pulse_enabled = saved.beat_pulse_enabled;
+		view.beat_pulse_scale = saved.beat_pulse_scale;
+		view.remember_view_positions = saved.remember_view_positions;
+		view.wheel_navigation = saved.wheel_navigation;
+		view.show_status_bar = saved.show_status_bar;
+		view.show_filmstrip = saved.show_filmstrip;
+		view.reveal.enabled = saved.reveal_mode;
+		view.reveal.auto_delay = Duration::from_secs_f32(saved.reveal_auto_secs);
+		view.coach_enabled = saved.coach_enabled;
+		view.coach_model = saved.coach_model.clone();
+		view.coach_preset = saved.coach_preset.clone();
+		ComponentResponse::emit_many(events)
 	}
 }
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+/// Polls skipped per component, over one window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PollCounts {
+	pub gateway: u32,
+	pub media: u32,
+	pub beat: u32,
+}
+
+impl PollCounts {
+	pub fn total(&self) -> u32 {
+		self.gateway + self.media + self.beat
+	}
+}
+
+/// Counts polls skipped because a component had nothing waiting,
+/// reported per second for the debug stats
+pub struct PollStats {
+	window_start: Instant,
+	counting: PollCounts,
+	last_second: PollCounts,
+}
+
+impl PollStats {
+	pub fn new() -> Self {
+		Self {
+			window_start: Instant::now(),
+			counting: PollCounts::default(),
+			last_second: PollCounts::default(),
+		}
+	}
+
+	/// Record one round of polling; each flag is true if that component was skipped
+	pub fn record(&mut self, gateway: bool, media: bool, beat: bool) {
+		if self.window_start.elapsed() >= Duration::from_secs(1) {
+			self.last_second = std::mem::take(&mut self.counting);
+			self.window_start = Instant::now();
+		}
+		self.counting.gateway += u32::from(gateway);
+		self.counting.media += u32::from(media);
+		self.counting.beat += u32::from(beat);
+	}
+
+	/// Skips counted over the last full second
+	pub fn skipped_per_second(&self) -> PollCounts {
+		self.last_second
+	}
+
+	/// Skips counted since the current second began
+	#[cfg(test)]
+	pub fn skipped_this_second(&self) -> PollCounts {
+		self.counting
+	}
+}
@@ -1,4 +1,4 @@
-use super::event::Event;
+use super::event::{Event, Priority};
 use std::collections::VecDeque;
 
 /// Priority event queue with 4 priority levels
@@ -33,6 +33,19 @@ impl EventQueue {
 		}
 		None
 	}
+
+	/// Pop a critical event, leaving the rest for later
+	pub fn pop_critical(&mut self) -> Option<Event> {
+		self.queues[Priority::Critical.as_index()].pop_front()
+	}
+
+	pub fn len(&self) -> usize {
+		self.queues.iter().map(VecDeque::len).sum()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.queues.iter().all(VecDeque::is_empty)
+	}
 }
 
 impl Default for EventQueue {
@@ -0,0 +1,159 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// Why a frame was asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepaintCause {
+	/// A scheduled event comes due, or events were left queued for the next frame
+	Scheduled,
+	/// Beat detection, API requests or the coach need polling
+	Polling,
+	/// Held pan or zoom keys and the drag glide
+	Input,
+	/// Auto-pan and the gallery slide
+	Pan,
+	/// Beat pulse on the image and the beat dot
+	Pulse,
+	Breathing,
+	/// Reveal fade, toasts and the island's fades and highlight
+	Animation,
+	/// A label that changes on its own, like a pause running out
+	Status,
+}
+
+impl RepaintCause {
+	const COUNT: usize = 8;
+
+	pub const ALL: [RepaintCause; Self::COUNT] = [
+		RepaintCause::Scheduled,
+		RepaintCause::Polling,
+		RepaintCause::Input,
+		RepaintCause::Pan,
+		RepaintCause::Pulse,
+		RepaintCause::Breathing,
+		RepaintCause::Animation,
+		RepaintCause::Status,
+	];
+
+	pub fn label(self) -> &'static str {
+		match self {
+			RepaintCause::Scheduled => "scheduled",
+			RepaintCause::Polling => "polling",
+			RepaintCause::Input => "input",
+			RepaintCause::Pan => "pan",
+			RepaintCause::Pulse => "pulse",
+			RepaintCause::Breathing => "breathing",
+			RepaintCause::Animation => "animation",
+			RepaintCause::Status => "status",
+		}
+	}
+}
+
+/// Frames asked for while handling one frame, handed to egui as a single request
+/// at the end of the tick. Workers still wake the UI themselves when a load lands.
+#[derive(Default)]
+pub struct Repaints {
+	soonest: Option<Duration>,
+	causes: [bool; RepaintCause::COUNT],
+}
+
+impl Repaints {
+	/// Ask for the next frame right away
+	pub fn now(&mut self, cause: RepaintCause) {
+		self.after(cause, Duration::ZERO);
+	}
+
+	/// Ask for a frame within `delay`
+	pub fn after(&mut self, cause: RepaintCause, delay: Duration) {
+		self.soonest = Some(self.soonest.map_or(delay, |soonest| soonest.min(delay)));
+		self.causes[cause as usize] = true;
+	}
+
+	/// Request the soonest frame of the viewport being drawn and start over.
+	/// Returns the causes that asked.
+	pub fn flush(&mut self, ctx: &egui::Context) -> Vec<RepaintCause> {
+		if let Some(delay) = self.soonest.take() {
+			ctx.request_repaint_after(delay);
+		}
+		let causes = std::mem::take(&mut self.causes);
+		RepaintCause::ALL
+			.into_iter()
+			.filter(|cause| causes[*cause as usize])
+			.collect()
+	}
+}
+
+/// Frames in which each cause asked for a repaint, over one window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RepaintCounts {
+	causes: [u32; RepaintCause::COUNT],
+	/// Frames that asked for another one, whatever the cause
+	pub frames: u32,
+}
+
+impl RepaintCounts {
+	pub fn get(&self, cause: RepaintCause) -> u32 {
+		self.causes[cause as usize]
+	}
+}
+
+/// Counts why frames asked for more frames, reported per second for the debug stats
+pub struct RepaintStats {
+	window_start: Instant,
+	counting: RepaintCounts,
+	last_second: RepaintCounts,
+}
+
+impl RepaintStats {
+	pub fn new() -> Self {
+		Self {
+			window_start: Instant::now(),
+			counting: RepaintCounts::default(),
+			last_second: RepaintCounts::default(),
+		}
+	}
+
+	/// Record the causes one frame asked for another with
+	pub fn record(&mut self, causes: &[RepaintCause]) {
+		if self.window_start.elapsed() >= Duration::from_secs(1) {
+			self.last_second = std::mem::take(&mut self.counting);
+			self.window_start = Instant::now();
+		}
+		for cause in causes {
+			self.counting.causes[*cause as usize] += 1;
+		}
+		self.counting.frames += u32::from(!causes.is_empty());
+	}
+
+	/// Counts over the last full second
+	pub fn per_second(&self) -> RepaintCounts {
+		self.last_second
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn requests_of_one_frame_become_the_soonest_one() {
+		let mut repaints = Repaints::default();
+		let mut stats = RepaintStats::new();
+		repaints.after(RepaintCause::Status, Duration::from_secs(1));
+		repaints.after(RepaintCause::Pulse, Duration::from_millis(16));
+		repaints.after(RepaintCause::Pulse, Duration::from_millis(33));
+		assert_eq!(repaints.soonest, Some(Duration::from_millis(16)));
+
+		let causes = repaints.flush(&egui::Context::default());
+		assert_eq!(causes, [RepaintCause::Pulse, RepaintCause::Status]);
+		stats.record(&causes);
+		assert_eq!(repaints.soonest, None);
+		assert_eq!(stats.counting.frames, 1);
+		assert_eq!(stats.counting.get(RepaintCause::Pulse), 1);
+		assert_eq!(stats.counting.get(RepaintCause::Status), 1);
+
+		// A frame that asks for nothing lets the UI sleep
+		stats.record(&repaints.flush(&egui::Context::default()));
+		assert_eq!(stats.counting.frames, 1);
+	}
+}
@@ -48,6 +48,11 @@ impl Scheduler {
 		});
 	}
 
+	/// When the earliest pending event is due
+	pub fn next_due(&self) -> Option<Instant> {
+		self.pending.peek().map(|scheduled| scheduled.emit_at)
+	}
+
 	/// Poll and drain ready events into the queue
 	pub fn tick(&mut self, queue: &mut EventQueue) {
 		let now = Instant::now();
@@ -0,0 +1,1754 @@
+use super::*;
+use crate::api::{ErrorCategory, Post};
+use crate::gateway::{PoolName, ResultEstimate};
+use crate::media::Readiness;
+use crate::testing::{FakeClient, FakeLoader, Outcome, is};
+use crate::types::{BreathingPhase, NavDirection, SearchWatch, SortKey, StartupSearch};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+fn post(id: u64) -> Post {
+	let mut post = Post {
+		id,
+		..Default::default()
+	};
+	post.file.ext = "png".to_string();
+	post.file.url = Some(format!("https://example.test/{}.png", id));
+	post
+}
+
+fn test_reactor(count: u64) -> Reactor {
+	test_reactor_with_loader(count, FakeLoader::new())
+}
+
+fn test_reactor_with_loader(count: u64, loader: FakeLoader) -> Reactor {
+	let client = FakeClient::new((1..=count).map(post).collect());
+	Reactor::new_for_test(Box::new(client), Box::new(loader))
+}
+
+/// Tick until `done` matches a routed event, letting async workers run in between
+async fn run_until(reactor: &mut Reactor, mut done: impl FnMut(&Event) -> bool) -> Vec<Event> {
+	let mut routed = Vec::new();
+	for _ in 0..400 {
+		let events = reactor.tick_events(Vec::new());
+		let finished = events.iter().any(&mut done);
+		routed.extend(events);
+		if finished {
+			return routed;
+		}
+		tokio::time::sleep(Duration::from_millis(5)).await;
+	}
+	panic!("condition not reached, routed: {:#?}", routed);
+}
+
+fn search(query: &str) -> Event {
+	Event::Source(SourceEvent::Search {
+		query: query.to_string(),
+		page: 1,
+	})
+}
+
+#[tokio::test]
+async fn search_loads_first_post_until_media_ready() {
+	let mut reactor = test_reactor(3);
+
+	let routed = reactor.tick_events(vec![search("wolf")]);
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Gateway(GatewayEvent::SearchRequest { query, .. }) if query == "wolf"
+	)));
+
+	let routed = run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Browser(BrowserEvent::PostsReceived { posts, is_new: true, .. }) if posts.len() == 3
+	)));
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Media(MediaEvent::LoadRequest { full_url: Some(url), .. })
+			if url == "https://example.test/1.png"
+	)));
+	assert_eq!(reactor.browser.posts_len(), 3);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(1));
+	assert!(reactor.media.get_current_media().is_some());
+}
+
+#[tokio::test]
+async fn slideshow_advances_to_next_post() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+
+	reactor.tick_events(vec![
+		Event::Settings(SettingsEvent::SetDelay {
+			duration: Duration::from_millis(20),
+		}),
+		Event::Settings(SettingsEvent::ToggleAutoPlay),
+	]);
+	run_until(&mut reactor, |e| {
+		matches!(
+			e,
+			Event::Browser(BrowserEvent::Navigate {
+				direction: NavDirection::Next
+			})
+		)
+	})
+	.await;
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+
+	// The advance reschedules itself
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::Navigate { .. }))
+	})
+	.await;
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(3));
+}
+
+#[tokio::test]
+async fn malformed_query_never_reaches_gateway() {
+	let mut reactor = test_reactor(3);
+
+	let routed = reactor.tick_events(vec![search("wolf order;score")]);
+
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::View(ViewEvent::QueryWarnings { warnings }) if !warnings.is_empty()
+	)));
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Gateway(GatewayEvent::SearchRequest { .. })))
+	);
+}
+
+#[tokio::test]
+async fn missing_current_image_is_reported_once() {
+	let mut reactor = test_reactor_with_loader(
+		3,
+		FakeLoader::new().on(is("https://example.test/1.png"), Outcome::Missing),
+	);
+	reactor.tick_events(vec![search("wolf")]);
+
+	run_until(&mut reactor, |e| {
+		matches!(
+			e,
+			Event::Media(MediaEvent::LoadError {
+				permanent: true,
+				current: true,
+				..
+			})
+		)
+	})
+	.await;
+
+	// The failed URL is not requested again, so no further errors arrive
+	for _ in 0..10 {
+		let routed = reactor.tick_events(Vec::new());
+		assert!(
+			!routed
+				.iter()
+				.any(|e| matches!(e, Event::Media(MediaEvent::LoadError { current: true, .. })))
+		);
+		tokio::time::sleep(Duration::from_millis(5)).await;
+	}
+	assert!(reactor.media.get_current_media().is_none());
+}
+
+#[tokio::test]
+async fn current_image_shows_retrying_until_it_loads() {
+	let mut reactor = test_reactor_with_loader(
+		3,
+		FakeLoader::new().once(is("https://example.test/1.png"), Outcome::TimedOut),
+	);
+	reactor.media.set_retry_delay(Duration::from_millis(50));
+	reactor.tick_events(vec![search("wolf")]);
+
+	run_until(&mut reactor, |e| {
+		matches!(
+			e,
+			Event::Media(MediaEvent::LoadError {
+				permanent: false,
+				current: true,
+				..
+			})
+		)
+	})
+	.await;
+	assert_eq!(
+		reactor.view.load_retrying.as_deref(),
+		Some("https://example.test/1.png")
+	);
+
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+	assert_eq!(reactor.view.load_retrying, None);
+}
+
+#[tokio::test]
+async fn safe_mode_drops_loaded_posts_not_rated_safe() {
+	let posts: Vec<Post> = (1..=5)
+		.map(|id| Post {
+			rating: if id % 2 == 1 { "s" } else { "e" }.to_string(),
+			..post(id)
+		})
+		.collect();
+	let mut reactor = Reactor::new_for_test(
+		Box::new(FakeClient::new(posts)),
+		Box::new(FakeLoader::new()),
+	);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+	for _ in 0..3 {
+		reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+			NavDirection::Next,
+		))]);
+	}
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(4));
+
+	// The current post goes as well, landing on the safe one after it
+	reactor.tick_events(vec![Event::Settings(SettingsEvent::SafeModeChanged {
+		enabled: true,
+	})]);
+	assert_eq!(reactor.browser.posts_len(), 3);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(5));
+	assert_eq!(reactor.browser.get_post_relative(-1).map(|p| p.id), Some(3));
+
+	// Turning it off doesn't bring the dropped posts back
+	reactor.tick_events(vec![Event::Settings(SettingsEvent::SafeModeChanged {
+		enabled: false,
+	})]);
+	assert_eq!(reactor.browser.posts_len(), 3);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(5));
+}
+
+#[tokio::test]
+async fn blacklisting_a_tag_skips_the_current_post_and_can_be_undone() {
+	let mut posts: Vec<Post> = (1..=3).map(post).collect();
+	posts[0].tags.general = vec!["mud".to_string()];
+	posts[2].tags.general = vec!["mud".to_string()];
+	let mut reactor = Reactor::new_for_test(
+		Box::new(FakeClient::new(posts)),
+		Box::new(FakeLoader::new()),
+	);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+
+	let routed = reactor.tick_events(vec![Event::Browser(BrowserEvent::BlacklistTag {
+		tag: "mud".to_string(),
+	})]);
+	assert!(
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::View(ViewEvent::ShowUndoToast { .. })))
+	);
+	assert_eq!(reactor.browser.posts_len(), 1);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+	assert_eq!(reactor.browser.blacklist(), ["mud".to_string()]);
+
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::UnblacklistTag {
+		tag: "mud".to_string(),
+	})]);
+	assert_eq!(reactor.browser.posts_len(), 3);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(1));
+	assert!(reactor.browser.blacklist().is_empty());
+}
+
+#[tokio::test]
+async fn hidden_post_stays_out_of_later_searches_until_undone() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+
+	let routed = reactor.tick_events(vec![Event::Browser(BrowserEvent::HidePost { id: 1 })]);
+	assert!(
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::View(ViewEvent::ShowUndoToast { .. })))
+	);
+	assert_eq!(loaded_ids(&reactor), [2, 3]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+	assert_eq!(reactor.browser.session_hidden(), 1);
+
+	// Searching again doesn't bring it back
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. }))
+	})
+	.await;
+	assert_eq!(loaded_ids(&reactor), [2, 3]);
+
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::HidePost { id: 3 })]);
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::UnhidePost { id: 3 })]);
+	assert_eq!(loaded_ids(&reactor), [2, 3]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(3));
+	assert_eq!(reactor.browser.session_hidden(), 1);
+}
+
+#[tokio::test]
+async fn account_blacklist_hides_posts_until_toggled_off() {
+	let mut posts: Vec<Post> = (1..=4).map(post).collect();
+	posts[0].tags.general = vec!["mud".to_string()];
+	posts[2].rating = "e".to_string();
+	posts[2].tags.general = vec!["scat".to_string()];
+	posts[3].tags.general = vec!["scat".to_string()];
+	let mut reactor = Reactor::new_for_test(
+		Box::new(FakeClient::new(posts)),
+		Box::new(FakeLoader::new()),
+	);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+
+	// Arriving after the first results, as the startup fetch may
+	reactor.tick_events(vec![Event::Browser(
+		BrowserEvent::AccountBlacklistReceived {
+			text: "# mine\nmud\nscat rating:e\n".to_string(),
+		},
+	)]);
+	assert_eq!(reactor.browser.posts_len(), 2);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+	assert_eq!(reactor.browser.blacklist_hidden(), 2);
+	assert_eq!(reactor.browser.account_blacklist_enabled(), Some(true));
+
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::ToggleAccountBlacklist)]);
+	assert_eq!(loaded_ids(&reactor), [1, 2, 3, 4]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+	assert_eq!(reactor.browser.blacklist_hidden(), 0);
+
+	// A new search counts from zero and keeps the rules
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::ToggleAccountBlacklist)]);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(
+			e,
+			Event::Browser(BrowserEvent::PostsReceived { is_new: true, .. })
+		)
+	})
+	.await;
+	assert_eq!(reactor.browser.posts_len(), 2);
+	assert_eq!(reactor.browser.blacklist_hidden(), 2);
+
+	// Posts hidden as they arrived go back in API order as well
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::ToggleAccountBlacklist)]);
+	assert_eq!(loaded_ids(&reactor), [1, 2, 3, 4]);
+}
+
+#[tokio::test]
+async fn account_blacklist_puts_queued_posts_back_in_their_sub_query() {
+	let mut reactor = test_reactor(0);
+	reactor.tick_events(vec![Event::Browser(
+		BrowserEvent::AccountBlacklistReceived {
+			text: "mud\n".to_string(),
+		},
+	)]);
+	let page = |index: usize, ids: &[u64], is_new: bool| {
+		let posts = ids
+			.iter()
+			.map(|&id| {
+				let mut post = post(id);
+				if id == 2 {
+					post.tags.general = vec!["mud".to_string()];
+				}
+				post
+			})
+			.collect();
+		Event::Browser(BrowserEvent::PostsReceived {
+			posts,
+			page: 1,
+			is_new,
+			source: Some(PageSource {
+				index,
+				count: 2,
+				query: format!("q{}", index),
+				exhausted: false,
+			}),
+		})
+	};
+	reactor.tick_events(vec![page(0, &[1, 2, 3, 4], true)]);
+	reactor.tick_events(vec![page(1, &[11], false)]);
+	assert_eq!(loaded_ids(&reactor), [1, 11]);
+
+	// 2 waits ahead of 3 in the first sub-query's queue
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::ToggleAccountBlacklist)]);
+	assert_eq!(loaded_ids(&reactor), [1, 11]);
+	reactor.tick_events(vec![page(1, &[12, 13], false)]);
+	assert_eq!(loaded_ids(&reactor), [1, 11, 2, 12, 3, 13]);
+}
+
+#[tokio::test]
+async fn more_like_this_can_go_back_without_refetching() {
+	let mut posts: Vec<Post> = (1..=3).map(post).collect();
+	posts[1].tags.artist = vec!["conditional_dnp".to_string(), "someartist".to_string()];
+	let mut reactor = Reactor::new_for_test(
+		Box::new(FakeClient::new(posts)),
+		Box::new(FakeLoader::new()),
+	);
+	reactor.tick_events(vec![search("wolf rating:s")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+	reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::Next,
+	))]);
+
+	let routed = reactor.tick_events(vec![Event::Source(SourceEvent::MoreLikeThis)]);
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Gateway(GatewayEvent::SearchRequest { query, page: 1, .. })
+			if query == "someartist rating:s"
+	)));
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. }))
+	})
+	.await;
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(1));
+
+	let routed = reactor.tick_events(vec![Event::Source(SourceEvent::PreviousSearch)]);
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Gateway(GatewayEvent::SearchRequest { .. })))
+	);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+	assert_eq!(reactor.gateway.current_query(), "wolf rating:s");
+}
+
+#[tokio::test]
+async fn stalled_priority_load_is_handed_to_a_general_worker() {
+	let mut reactor = test_reactor_with_loader(
+		3,
+		FakeLoader::new().once(is("https://example.test/1.png"), Outcome::Stall),
+	);
+	reactor
+		.media
+		.set_priority_stall_timeout(Duration::from_millis(50));
+	reactor.tick_events(vec![search("wolf")]);
+
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+	assert!(reactor.media.get_current_media().is_some());
+}
+
+#[tokio::test]
+async fn user_activity_holds_the_slideshow() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+
+	reactor.tick_events(vec![
+		Event::Settings(SettingsEvent::SetDelay {
+			duration: Duration::from_millis(20),
+		}),
+		Event::Settings(SettingsEvent::SuppressAdvanceFor { secs: 0.3 }),
+		Event::Settings(SettingsEvent::ToggleAutoPlay),
+	]);
+	for _ in 0..20 {
+		let routed = reactor.tick_events(Vec::new());
+		assert!(
+			!routed
+				.iter()
+				.any(|e| matches!(e, Event::Browser(BrowserEvent::Navigate { .. })))
+		);
+		tokio::time::sleep(Duration::from_millis(5)).await;
+	}
+	assert!(reactor.settings.advance_suppressed());
+
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::Navigate { .. }))
+	})
+	.await;
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+}
+
+#[tokio::test]
+async fn throttled_search_is_retried() {
+	let client = FakeClient::new((1..=3).map(post).collect()).throttled(1);
+	let mut reactor = Reactor::new_for_test(Box::new(client), Box::new(FakeLoader::new()));
+	reactor.tick_events(vec![search("wolf")]);
+
+	let routed = run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. }))
+	})
+	.await;
+	assert!(
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::Gateway(GatewayEvent::Throttled { .. })))
+	);
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Gateway(GatewayEvent::SearchError { .. })))
+	);
+	assert_eq!(reactor.browser.posts_len(), 3);
+}
+
+#[tokio::test]
+async fn search_throttled_for_good_is_reported_as_failed() {
+	let client = FakeClient::new((1..=3).map(post).collect()).throttled(usize::MAX);
+	let mut reactor = Reactor::new_for_test(Box::new(client), Box::new(FakeLoader::new()));
+	reactor.tick_events(vec![search("wolf")]);
+
+	let routed = run_until(&mut reactor, |e| {
+		matches!(e, Event::Gateway(GatewayEvent::SearchError { .. }))
+	})
+	.await;
+	let throttled = routed
+		.iter()
+		.filter(|e| matches!(e, Event::Gateway(GatewayEvent::Throttled { .. })))
+		.count();
+	assert_eq!(throttled, 5);
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Gateway(GatewayEvent::SearchError {
+			category: ErrorCategory::RateLimited,
+			..
+		})
+	)));
+	assert_eq!(reactor.browser.posts_len(), 0);
+}
+
+#[tokio::test]
+async fn new_search_cancels_pending_fanout_pages() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![
+		Event::Gateway(GatewayEvent::SetFanoutPages { pages: 2 }),
+		search("wolf"),
+	]);
+	let routed = reactor.tick_events(vec![search("fox")]);
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Gateway(GatewayEvent::PageProgress {
+			loaded: 0,
+			total: 2
+		})
+	)));
+
+	let routed = run_until(&mut reactor, |e| {
+		matches!(
+			e,
+			Event::Gateway(GatewayEvent::PageProgress {
+				loaded: 2,
+				total: 2
+			})
+		)
+	})
+	.await;
+	let later_pages: Vec<bool> = routed
+		.iter()
+		.filter_map(|e| match e {
+			Event::Browser(BrowserEvent::PostsReceived {
+				page: 2, is_new, ..
+			}) => Some(*is_new),
+			_ => None,
+		})
+		.collect();
+	// Only the fox search's second page, appended to its results
+	assert_eq!(later_pages, vec![false]);
+	assert_eq!(reactor.gateway.current_query(), "fox");
+}
+
+#[tokio::test]
+async fn skipped_breathing_phase_drops_its_stale_completion() {
+	let mut reactor = test_reactor(1);
+	assert_eq!(reactor.breathing.state().phase, BreathingPhase::Prepare);
+
+	// Mid-cycle, so there is nothing to trigger
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::TriggerCycle)]);
+	assert_eq!(reactor.breathing.state().phase, BreathingPhase::Prepare);
+
+	let routed = reactor.tick_events(vec![Event::Breathing(BreathingEvent::SkipPhase)]);
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Breathing(BreathingEvent::PhaseStarted {
+			phase: BreathingPhase::Inhale,
+			..
+		})
+	)));
+
+	// The completion scheduled for the skipped Prepare phase
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::PhaseComplete {
+		generation: 0,
+	})]);
+	assert_eq!(reactor.breathing.state().phase, BreathingPhase::Inhale);
+}
+
+#[tokio::test]
+async fn forced_release_rests_and_ignores_the_old_schedule() {
+	let mut reactor = test_reactor(1);
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::SetMaxInhaleHold {
+		secs: 10.0,
+	})]);
+
+	// Prepare -> Inhale (generation 1) -> Hold (generation 2), within the cap
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::SkipPhase)]);
+	assert_eq!(reactor.breathing.state().phase, BreathingPhase::Inhale);
+	let inhale = reactor.breathing.state().duration;
+	assert!(inhale <= Duration::from_secs(5), "{:?}", inhale);
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::SkipPhase)]);
+	assert_eq!(reactor.breathing.state().phase, BreathingPhase::Hold);
+
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::ForceRelease)]);
+	assert_eq!(reactor.breathing.state().phase, BreathingPhase::Release);
+	// The Hold's completion comes in late and changes nothing
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::PhaseComplete {
+		generation: 2,
+	})]);
+	assert_eq!(reactor.breathing.state().phase, BreathingPhase::Release);
+
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::PhaseComplete {
+		generation: 3,
+	})]);
+	assert_eq!(reactor.breathing.state().phase, BreathingPhase::Idle);
+	assert!(reactor.breathing.state().duration >= Duration::from_secs(60));
+}
+
+#[tokio::test]
+async fn manual_navigation_restarts_the_slideshow_interval() {
+	const DELAY: Duration = Duration::from_millis(300);
+	let mut reactor = test_reactor(5);
+	reactor.tick_events(vec![search("wolf")]);
+	// The next images are in, so no advance waits on a load
+	for _ in 0..200 {
+		reactor.tick_events(Vec::new());
+		if [2, 3]
+			.iter()
+			.all(|id| reactor.media.readiness(&post(*id)) == Readiness::FullReady)
+		{
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(2)).await;
+	}
+	reactor.tick_events(vec![
+		Event::Settings(SettingsEvent::SetDelay { duration: DELAY }),
+		Event::Settings(SettingsEvent::ToggleAutoPlay),
+	]);
+	let started = Instant::now();
+	let navigations = |routed: &[Event]| {
+		routed
+			.iter()
+			.filter(|e| matches!(e, Event::Browser(BrowserEvent::Navigate { .. })))
+			.count()
+	};
+
+	// Shortly before the first advance is due, the user moves on by hand
+	tokio::time::sleep(DELAY - Duration::from_millis(100)).await;
+	let routed = reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::Next,
+	))]);
+	assert_eq!(navigations(&routed), 1);
+	let navigated_at = Instant::now();
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+
+	// The advance scheduled before it comes due, stale, and moves nothing
+	let mut stale_seen = false;
+	let mut moved = 0;
+	while navigated_at.elapsed() < DELAY - Duration::from_millis(50) {
+		let routed = reactor.tick_events(Vec::new());
+		stale_seen |= routed.iter().any(|e| {
+			matches!(
+				e,
+				Event::Settings(SettingsEvent::SlideshowAdvance { generation: 1 })
+			)
+		});
+		moved += navigations(&routed);
+		tokio::time::sleep(Duration::from_millis(5)).await;
+	}
+	assert!(
+		started.elapsed() > DELAY,
+		"the first advance never came due"
+	);
+	assert!(stale_seen);
+	assert_eq!(moved, 0);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+
+	// A full interval after the manual step, the slideshow goes on
+	let routed = run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::Navigate { .. }))
+	})
+	.await;
+	assert!(navigated_at.elapsed() >= DELAY);
+	assert_eq!(navigations(&routed), 1);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(3));
+}
+
+#[tokio::test]
+async fn breath_hold_keeps_the_slideshow_until_the_breath_ends() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![
+		Event::Breathing(BreathingEvent::Toggle),
+		Event::Settings(SettingsEvent::ToggleBreathHold),
+		Event::Settings(SettingsEvent::ToggleAutoPlay),
+	]);
+	let advance = Event::Settings(SettingsEvent::SlideshowAdvance { generation: 1 });
+	let navigates = |routed: &[Event]| {
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::Browser(BrowserEvent::Navigate { .. })))
+	};
+
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::PhaseStarted {
+		phase: BreathingPhase::Inhale,
+		duration: Duration::from_secs(10),
+	})]);
+	assert!(!navigates(&reactor.tick_events(vec![advance.clone()])));
+
+	reactor.tick_events(vec![Event::Breathing(BreathingEvent::PhaseStarted {
+		phase: BreathingPhase::Release,
+		duration: Duration::from_secs(4),
+	})]);
+	assert!(navigates(&reactor.tick_events(vec![advance])));
+}
+
+#[tokio::test]
+async fn slideshow_stays_put_behind_a_modal_and_starts_over_after() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	// The next image is in, so the slideshow has no reason to wait for it
+	for _ in 0..100 {
+		reactor.tick_events(Vec::new());
+		if reactor.media.readiness(&post(2)) == Readiness::FullReady {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(2)).await;
+	}
+	reactor.tick_events(vec![Event::Settings(SettingsEvent::ToggleAutoPlay)]);
+	let moved = |routed: &[Event]| {
+		routed.iter().any(|e| {
+			matches!(
+				e,
+				Event::Browser(BrowserEvent::Navigate { .. })
+					| Event::Gateway(GatewayEvent::FetchNextPage)
+			)
+		})
+	};
+
+	reactor.tick_events(vec![Event::View(ViewEvent::ModalOpened)]);
+	let advance = Event::Settings(SettingsEvent::SlideshowAdvance { generation: 1 });
+	assert!(!moved(&reactor.tick_events(vec![advance.clone()])));
+	assert!(!moved(&reactor.tick_events(vec![Event::Settings(
+		SettingsEvent::SkipFailed { generation: 1 }
+	)])));
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(1));
+
+	// Closing it schedules a fresh advance; the one from before is stale
+	reactor.tick_events(vec![Event::View(ViewEvent::ModalClosed)]);
+	assert!(!moved(&reactor.tick_events(vec![advance])));
+	let routed = reactor.tick_events(vec![Event::Settings(SettingsEvent::SlideshowAdvance {
+		generation: 2,
+	})]);
+	assert!(moved(&routed));
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+}
+
+#[tokio::test]
+async fn slideshow_waits_a_little_for_the_next_image() {
+	let mut reactor = test_reactor_with_loader(
+		3,
+		FakeLoader::new().once(is("https://example.test/2.png"), Outcome::Stall),
+	);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+	for _ in 0..100 {
+		reactor.tick_events(Vec::new());
+		if reactor.media.readiness(&post(3)) == Readiness::FullReady {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(2)).await;
+	}
+	assert_eq!(reactor.media.readiness(&post(3)), Readiness::FullReady);
+	assert_eq!(reactor.media.readiness(&post(2)), Readiness::NotStarted);
+	let upcoming: Vec<u64> = reactor.browser.peek_next(3).iter().map(|p| p.id).collect();
+	assert_eq!(upcoming, [2, 3]);
+
+	reactor.tick_events(vec![Event::Settings(SettingsEvent::ToggleAutoPlay)]);
+	let advance = Event::Settings(SettingsEvent::SlideshowAdvance { generation: 1 });
+	let navigates = |routed: &[Event]| {
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::Browser(BrowserEvent::Navigate { .. })))
+	};
+	// Post 2 never loads; the slideshow holds a few times, then moves on anyway
+	for _ in 0..5 {
+		assert!(!navigates(&reactor.tick_events(vec![advance.clone()])));
+	}
+	assert!(navigates(&reactor.tick_events(vec![advance])));
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+}
+
+#[tokio::test]
+async fn navigation_stops_at_both_ends_unless_wrapping() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	// Page 1 is short, so it is also the last one
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::EndOfResults))
+	})
+	.await;
+	assert!(reactor.browser.is_end_of_results());
+	let navigate = |direction| Event::Source(SourceEvent::Navigate(direction));
+
+	let routed = reactor.tick_events(vec![navigate(NavDirection::Prev)]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(1));
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Media(MediaEvent::LoadRequest { .. })))
+	);
+
+	reactor.tick_events(vec![navigate(NavDirection::Skip(10))]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(3));
+	let routed = reactor.tick_events(vec![navigate(NavDirection::Next)]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(3));
+	assert!(!routed.iter().any(|e| matches!(
+		e,
+		Event::Media(MediaEvent::LoadRequest { .. }) | Event::Gateway(GatewayEvent::FetchNextPage)
+	)));
+	reactor.tick_events(vec![navigate(NavDirection::Skip(-10))]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(1));
+
+	reactor.tick_events(vec![Event::Settings(SettingsEvent::SetWrapNavigation {
+		enabled: true,
+	})]);
+	reactor.tick_events(vec![navigate(NavDirection::Prev)]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(3));
+	reactor.tick_events(vec![navigate(NavDirection::Next)]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(1));
+}
+
+#[tokio::test]
+async fn pool_names_are_fetched_once_and_failures_fall_back_to_ids() {
+	let client = FakeClient::default();
+	let calls = client.pool_name_calls();
+	let mut reactor = Reactor::new_for_test(Box::new(client), Box::new(FakeLoader::new()));
+	let fetch = || {
+		Event::Gateway(GatewayEvent::FetchPoolNames {
+			pool_ids: vec![4, 7],
+		})
+	};
+
+	reactor.tick_events(vec![fetch()]);
+	for _ in 0..400 {
+		if reactor.gateway.pool_name(4) != Some(&PoolName::Loading)
+			&& reactor.gateway.pool_name(7) != Some(&PoolName::Loading)
+		{
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(5)).await;
+		reactor.tick_events(Vec::new());
+	}
+	assert_eq!(
+		reactor.gateway.pool_name(4),
+		Some(&PoolName::Named("Pool 4".to_string()))
+	);
+	assert_eq!(reactor.gateway.pool_name(7), Some(&PoolName::Unknown));
+
+	// Opening the picker again, even for the failed one, costs no more requests
+	reactor.tick_events(vec![fetch()]);
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn entering_a_pool_can_go_back_to_the_previous_search() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. }))
+	})
+	.await;
+
+	let routed = reactor.tick_events(vec![Event::Source(SourceEvent::EnterPool { pool_id: 42 })]);
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Gateway(GatewayEvent::SearchRequest { query, page: 1, .. })
+			if query == "pool:42 order:id"
+	)));
+	// Pools are read page by page, so reading mode comes on with them
+	assert!(reactor.settings.reading_mode());
+
+	reactor.tick_events(vec![Event::Source(SourceEvent::PreviousSearch)]);
+	assert_eq!(reactor.gateway.current_query(), "wolf");
+	assert!(!reactor.settings.reading_mode());
+
+	// Any other search leaves the pool as well
+	reactor.tick_events(vec![Event::Source(SourceEvent::EnterPool { pool_id: 42 })]);
+	assert!(reactor.settings.reading_mode());
+	reactor.tick_events(vec![search("fox")]);
+	assert!(!reactor.settings.reading_mode());
+
+	// Turned on by hand, it stays on when leaving a pool
+	reactor.tick_events(vec![Event::Settings(SettingsEvent::ToggleReadingMode)]);
+	reactor.tick_events(vec![Event::Source(SourceEvent::EnterPool { pool_id: 42 })]);
+	reactor.tick_events(vec![Event::Source(SourceEvent::PreviousSearch)]);
+	assert!(reactor.settings.reading_mode());
+}
+
+/// IDs of all loaded posts, in browsing order
+fn loaded_ids(reactor: &Reactor) -> Vec<u64> {
+	let start = -(reactor.browser.current_index() as isize);
+	(0..reactor.browser.posts_len() as isize)
+		.filter_map(|i| reactor.browser.get_post_relative(start + i))
+		.map(|p| p.id)
+		.collect()
+}
+
+#[tokio::test]
+async fn sorting_loaded_posts_keeps_the_current_post_and_later_pages_in_order() {
+	let scored = |id, score| {
+		let mut post = post(id);
+		post.score.total = score;
+		post
+	};
+	let client = FakeClient::new(vec![
+		scored(1, 5),
+		scored(2, 20),
+		scored(3, -1),
+		scored(4, 10),
+	]);
+	let mut reactor = Reactor::new_for_test(Box::new(client), Box::new(FakeLoader::new()));
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::EndOfResults))
+	})
+	.await;
+	reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::Next,
+	))]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+
+	let routed = reactor.tick_events(vec![Event::Browser(BrowserEvent::SortLoaded {
+		key: SortKey::Score,
+		descending: true,
+	})]);
+	assert_eq!(loaded_ids(&reactor), vec![2, 4, 1, 3]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+	// Only the hints change; the current image isn't loaded again
+	assert!(
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::Media(MediaEvent::Prefetch { .. })))
+	);
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Media(MediaEvent::LoadRequest { .. })))
+	);
+
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::PostsReceived {
+		posts: vec![scored(5, 7), scored(6, 30)],
+		page: 3,
+		is_new: false,
+		source: None,
+	})]);
+	assert_eq!(loaded_ids(&reactor), vec![6, 2, 4, 5, 1, 3]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::SortLoaded {
+		key: SortKey::ApiOrder,
+		descending: true,
+	})]);
+	assert_eq!(loaded_ids(&reactor), vec![1, 2, 3, 4, 5, 6]);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+}
+
+#[tokio::test]
+async fn short_page_ends_paging_until_cleared() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	let routed = run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::EndOfResults))
+	})
+	.await;
+	assert_eq!(
+		routed
+			.iter()
+			.filter(|e| matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. })))
+			.count(),
+		1
+	);
+
+	// Neither navigating past the end nor asking directly reaches the API
+	reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::Skip(10),
+	))]);
+	let routed = reactor.tick_events(vec![
+		Event::Source(SourceEvent::Navigate(NavDirection::Next)),
+		Event::Gateway(GatewayEvent::FetchNextPage),
+	]);
+	assert!(!routed.iter().any(|e| matches!(
+		e,
+		Event::Browser(BrowserEvent::EndOfResults)
+			| Event::Gateway(GatewayEvent::SearchError { .. })
+	)));
+	assert_eq!(reactor.gateway.pending_requests(), 0);
+
+	reactor.tick_events(vec![Event::Gateway(GatewayEvent::ClearEndOfResults)]);
+	assert!(!reactor.browser.is_end_of_results());
+	assert_eq!(reactor.gateway.pending_requests(), 1);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::EndOfResults))
+	})
+	.await;
+	assert!(reactor.browser.is_end_of_results());
+}
+
+#[tokio::test]
+async fn idle_components_are_not_polled() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+	// Let the prefetch of the other posts finish
+	for _ in 0..100 {
+		reactor.tick_events(Vec::new());
+		if reactor.media.loading_counts() == (0, 0) && !reactor.media.has_pending() {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(5)).await;
+	}
+
+	let before = reactor.poll_stats.skipped_this_second();
+	for _ in 0..5 {
+		reactor.tick_events(Vec::new());
+	}
+	let after = reactor.poll_stats.skipped_this_second();
+	// Unless the one-second window rolled over in between
+	if after.total() > before.total() {
+		assert_eq!(after.gateway - before.gateway, 5);
+		assert_eq!(after.media - before.media, 5);
+		assert_eq!(after.beat - before.beat, 5);
+	}
+
+	// Skipping doesn't hold anything up once there is work again
+	let routed = reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::Next,
+	))]);
+	assert!(
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::View(ViewEvent::MediaReady)))
+	);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+}
+
+#[tokio::test]
+async fn result_size_comes_from_a_short_first_page_or_tag_counts() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. }))
+	})
+	.await;
+	assert_eq!(
+		reactor.gateway.result_estimate(),
+		Some(ResultEstimate::Exact(3))
+	);
+
+	// A full first page says nothing; the tag lookup waits for a free limiter
+	let mut reactor = test_reactor(50);
+	reactor.tick_events(vec![search("wolf solo -feral")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. }))
+	})
+	.await;
+	assert_eq!(reactor.gateway.result_estimate(), None);
+	for _ in 0..300 {
+		if reactor.gateway.result_estimate().is_some() {
+			break;
+		}
+		reactor.tick_events(Vec::new());
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	let estimate = reactor.gateway.result_estimate();
+	assert_eq!(estimate, Some(ResultEstimate::AtMost(3200)));
+	assert_eq!(estimate.unwrap().to_string(), "~3.2k posts");
+}
+
+#[tokio::test]
+async fn default_query_runs_once_the_terms_are_accepted() {
+	let mut reactor = test_reactor(2);
+	let searches = |routed: &[Event]| {
+		routed
+			.iter()
+			.filter_map(|e| match e {
+				Event::Gateway(GatewayEvent::SearchRequest { query, .. }) => Some(query.clone()),
+				_ => None,
+			})
+			.collect::<Vec<_>>()
+	};
+	let routed = reactor.tick_events(vec![Event::View(ViewEvent::TosAccepted)]);
+	assert!(searches(&routed).is_empty());
+
+	reactor.tick_events(vec![
+		Event::Settings(SettingsEvent::SetStartupSearch {
+			behavior: StartupSearch::DefaultQuery,
+		}),
+		Event::Settings(SettingsEvent::SetDefaultQuery {
+			query: "wolf".to_string(),
+		}),
+	]);
+	let routed = reactor.tick_events(vec![Event::View(ViewEvent::TosAccepted)]);
+	assert_eq!(searches(&routed), ["wolf"]);
+	assert_eq!(reactor.view.search_query, "wolf");
+
+	// A search already under way, e.g. from the command line, is left alone
+	let routed = reactor.tick_events(vec![Event::View(ViewEvent::TosAccepted)]);
+	assert!(searches(&routed).is_empty());
+}
+
+#[tokio::test]
+async fn local_favorites_load_like_a_search_without_the_api() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+	reactor.tick_events(vec![Event::Favorites(FavoritesEvent::ToggleCurrent)]);
+	reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::Next,
+	))]);
+	reactor.tick_events(vec![Event::Favorites(FavoritesEvent::ToggleCurrent)]);
+
+	let routed = reactor.tick_events(vec![search(crate::gateway::query::LOCAL_FAVORITES)]);
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Gateway(GatewayEvent::SearchRequest { .. })))
+	);
+	assert_eq!(reactor.browser.posts_len(), 2);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(2));
+	assert_eq!(
+		reactor.gateway.result_estimate(),
+		Some(ResultEstimate::Exact(2))
+	);
+
+	// Nothing to page through, even when asked to look again
+	let routed = reactor.tick_events(vec![Event::Gateway(GatewayEvent::ClearEndOfResults)]);
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. })))
+	);
+	assert!(!reactor.gateway.is_loading());
+}
+
+#[tokio::test]
+async fn refreshing_favorites_resolves_ids_in_order_and_reports_missing_ones() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![search("wolf")]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::View(ViewEvent::MediaReady))
+	})
+	.await;
+	for id in [1, 99, 3] {
+		reactor.favorites.toggle(&post(id));
+	}
+
+	reactor.tick_events(vec![Event::Favorites(FavoritesEvent::Refresh)]);
+	let routed = run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::PostsResolved { .. }))
+	})
+	.await;
+	let resolved = routed.iter().find_map(|e| match e {
+		Event::Browser(BrowserEvent::PostsResolved { posts, missing, .. }) => Some((
+			posts.iter().map(|p| p.id).collect::<Vec<_>>(),
+			missing.clone(),
+		)),
+		_ => None,
+	});
+	assert_eq!(resolved, Some((vec![1, 3], vec![99])));
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::View(ViewEvent::ShowToast { message }) if message.contains("1 no longer on e621")
+	)));
+
+	// The search being browsed is untouched
+	assert_eq!(reactor.browser.posts_len(), 3);
+	assert_eq!(reactor.browser.current_post().map(|p| p.id), Some(1));
+	assert_eq!(reactor.favorites.posts().len(), 3);
+}
+
+#[tokio::test]
+async fn set_search_pages_through_the_set_in_its_own_order() {
+	let mut reactor = test_reactor(60);
+	let routed = reactor.tick_events(vec![search(" SET:7 ")]);
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Gateway(GatewayEvent::SearchRequest { .. })))
+	);
+	assert_eq!(reactor.gateway.current_query(), "set:7");
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. }))
+	})
+	.await;
+	assert_eq!(loaded_ids(&reactor), (11..=60).rev().collect::<Vec<_>>());
+	assert_eq!(reactor.gateway.set_name(), Some("set_7"));
+	assert_eq!(
+		reactor.gateway.result_estimate(),
+		Some(ResultEstimate::Exact(61))
+	);
+
+	reactor.tick_events(vec![Event::Gateway(GatewayEvent::FetchNextPage)]);
+	run_until(&mut reactor, |e| {
+		matches!(e, Event::Browser(BrowserEvent::EndOfResults))
+	})
+	.await;
+	assert_eq!(loaded_ids(&reactor), (1..=60).rev().collect::<Vec<_>>());
+
+	// Nothing left to look up
+	let routed = reactor.tick_events(vec![Event::Gateway(GatewayEvent::ClearEndOfResults)]);
+	assert!(!reactor.gateway.is_loading());
+	assert!(
+		!routed
+			.iter()
+			.any(|e| matches!(e, Event::Browser(BrowserEvent::PostsReceived { .. })))
+	);
+
+	// A normal search leaves the set behind
+	reactor.tick_events(vec![search("wolf")]);
+	assert_eq!(reactor.gateway.set_name(), None);
+}
+
+#[tokio::test]
+async fn event_bursts_drain_over_several_frames() {
+	let mut reactor = test_reactor(0);
+	// Whatever starting up queued
+	reactor.tick_events(Vec::new());
+	// Only the event cap ends a frame
+	reactor.set_event_budget(Duration::from_secs(60));
+	let burst: Vec<Event> = (0..5_500)
+		.map(|i| {
+			Event::Breathing(BreathingEvent::SetIdleMultiplier {
+				value: 1.0 + (i % 20) as f32 / 10.0,
+			})
+		})
+		.collect();
+	let total = burst.len();
+
+	let mut per_frame = Vec::new();
+	let mut events = burst;
+	while per_frame.is_empty() || !reactor.queue.is_empty() {
+		per_frame.push(reactor.tick_events(std::mem::take(&mut events)).len());
+		assert!(per_frame.len() < 100, "the queue never drained");
+	}
+	assert_eq!(per_frame, [1000, 1000, 1000, 1000, 1000, 500]);
+	assert_eq!(per_frame.iter().sum::<usize>(), total);
+	assert_eq!(reactor.breathing.idle_multiplier(), 2.9);
+
+	// Out of budget from the first event on, a frame still shows an error
+	reactor.set_event_budget(Duration::ZERO);
+	let mut events: Vec<Event> = (0..3)
+		.map(|_| Event::Breathing(BreathingEvent::SetIdleMultiplier { value: 1.0 }))
+		.collect();
+	events.push(Event::Gateway(GatewayEvent::SearchError {
+		message: "offline".to_string(),
+		category: ErrorCategory::Network,
+	}));
+	let routed = reactor.tick_events(events);
+	assert!(matches!(
+		routed.as_slice(),
+		[Event::Gateway(GatewayEvent::SearchError { .. })]
+	));
+	// ...and the rest trickle through one a frame
+	assert_eq!(reactor.tick_events(Vec::new()).len(), 1);
+	assert_eq!(reactor.queue.len(), 2);
+}
+
+#[tokio::test]
+async fn jumps_go_to_a_position_or_the_first_post_of_a_page() {
+	let mut reactor = test_reactor(0);
+	let page = |ids: std::ops::RangeInclusive<u64>, page: u32| {
+		Event::Browser(BrowserEvent::PostsReceived {
+			posts: ids.map(post).collect(),
+			page,
+			is_new: page == 1,
+			source: None,
+		})
+	};
+	let jump = |direction| Event::Source(SourceEvent::Navigate(direction));
+	let current = |reactor: &Reactor| reactor.browser.current_post().map(|p| p.id);
+
+	reactor.tick_events(vec![page(1..=3, 1), page(4..=6, 2)]);
+	assert_eq!(reactor.browser.page_of(2), Some(1));
+	assert_eq!(reactor.browser.page_of(5), Some(2));
+
+	reactor.tick_events(vec![jump(NavDirection::ToPage(2))]);
+	assert_eq!(current(&reactor), Some(4));
+	reactor.tick_events(vec![jump(NavDirection::ToPosition(2))]);
+	assert_eq!(current(&reactor), Some(2));
+
+	// Newest first puts page 2 ahead; page 1 now starts at its newest post
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::SortLoaded {
+		key: SortKey::Newest,
+		descending: true,
+	})]);
+	assert_eq!(loaded_ids(&reactor), [6, 5, 4, 3, 2, 1]);
+	reactor.tick_events(vec![jump(NavDirection::ToPage(1))]);
+	assert_eq!(current(&reactor), Some(3));
+	assert_eq!(reactor.browser.page_of(3), Some(1));
+
+	// Nothing loaded from there: stay put and say so
+	for target in [NavDirection::ToPage(9), NavDirection::ToPosition(7)] {
+		let routed = reactor.tick_events(vec![jump(target)]);
+		assert_eq!(current(&reactor), Some(3));
+		assert!(
+			routed
+				.iter()
+				.any(|e| matches!(e, Event::View(ViewEvent::ShowToast { .. })))
+		);
+	}
+
+	// A new search starts the pages over
+	reactor.tick_events(vec![page(7..=8, 1)]);
+	assert_eq!(reactor.browser.page_of(5), None);
+	assert_eq!(reactor.browser.page_of(8), Some(1));
+}
+
+#[tokio::test]
+async fn watched_searches_report_posts_newer_than_the_last_one_seen() {
+	let mut reactor = test_reactor(3);
+	reactor.tick_events(vec![Event::Gateway(GatewayEvent::WatchSearch {
+		query: " wolf ".to_string(),
+		watched: true,
+	})]);
+	assert!(reactor.gateway.is_watched("wolf"));
+	let poll = |reactor: &mut Reactor| reactor.gateway.schedule_watches().scheduled.remove(0).0;
+
+	// The welcome modal is still open, so the poll waits
+	let first = poll(&mut reactor);
+	reactor.tick_events(vec![first.clone()]);
+	assert_eq!(reactor.gateway.pending_requests(), 0);
+
+	// The first poll only notes the newest post
+	reactor.tick_events(vec![Event::View(ViewEvent::DismissModal), first.clone()]);
+	for _ in 0..200 {
+		if reactor.gateway.watches()[0].last_seen.is_some() {
+			break;
+		}
+		let routed = reactor.tick_events(Vec::new());
+		assert!(
+			!routed
+				.iter()
+				.any(|e| matches!(e, Event::Gateway(GatewayEvent::NewPostsAvailable { .. })))
+		);
+		tokio::time::sleep(Duration::from_millis(5)).await;
+	}
+	assert_eq!(reactor.gateway.watches()[0].last_seen, Some(3));
+
+	// Seen up to post 1 last time: 2 and 3 are new
+	reactor.tick_events(vec![Event::Gateway(GatewayEvent::SetWatches {
+		watches: vec![SearchWatch {
+			query: "wolf".to_string(),
+			last_seen: Some(1),
+		}],
+	})]);
+	// Polls scheduled before the watches changed are dropped
+	reactor.tick_events(vec![first]);
+	assert_eq!(reactor.gateway.pending_requests(), 0);
+
+	let second = poll(&mut reactor);
+	reactor.tick_events(vec![second]);
+	let routed = run_until(&mut reactor, |e| {
+		matches!(e, Event::Gateway(GatewayEvent::NewPostsAvailable { .. }))
+	})
+	.await;
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::Gateway(GatewayEvent::NewPostsAvailable { query, count: 2 }) if query == "wolf"
+	)));
+	assert_eq!(reactor.gateway.watches()[0].last_seen, Some(3));
+}
+
+/// Records the events it's handed; its poll has a toast waiting once
+struct Recorder {
+	seen: Arc<std::sync::Mutex<Vec<Event>>>,
+	pending: bool,
+}
+
+impl Component for Recorder {
+	fn handle(&mut self, event: &Event) -> ComponentResponse {
+		self.seen.lock().unwrap().push(event.clone());
+		ComponentResponse::none()
+	}
+
+	fn poll(&mut self) -> ComponentResponse {
+		self.pending = false;
+		ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+			message: "recorded".to_string(),
+		}))
+	}
+
+	fn has_pending(&self) -> bool {
+		self.pending
+	}
+
+	fn interests(&self) -> component::EventMask {
+		component::EventMask::BEAT | component::EventMask::SOURCE
+	}
+}
+
+#[tokio::test]
+async fn registered_components_see_only_the_kinds_they_want() {
+	let mut reactor = test_reactor(3);
+	let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+	reactor.register(Box::new(Recorder {
+		seen: seen.clone(),
+		pending: true,
+	}));
+
+	let routed = reactor.tick_events(vec![
+		Event::Beat(BeatEvent::SilenceDetected),
+		Event::View(ViewEvent::ToggleImageFillMode),
+		search("wolf"),
+	]);
+	// Its poll ran and what it returned was routed
+	assert!(routed.iter().any(|e| matches!(
+		e,
+		Event::View(ViewEvent::ShowToast { message }) if message == "recorded"
+	)));
+	// The search still went out, and the recorder saw it next to the beat event
+	assert!(
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::Gateway(GatewayEvent::SearchRequest { .. })))
+	);
+	let seen = seen.lock().unwrap();
+	assert_eq!(seen.len(), 2);
+	assert!(matches!(seen[0], Event::Source(SourceEvent::Search { .. })));
+	assert!(matches!(seen[1], Event::Beat(BeatEvent::SilenceDetected)));
+}
+
+#[tokio::test]
+async fn filmstrip_range_stops_at_the_ends_unless_navigation_wraps() {
+	let mut reactor = test_reactor(0);
+	let ids = |reactor: &Reactor| -> Vec<u64> {
+		reactor
+			.browser
+			.peek_range(-2..=5)
+			.into_iter()
+			.map(|(_, post)| post.id)
+			.collect()
+	};
+	reactor.tick_events(vec![
+		Event::Browser(BrowserEvent::PostsReceived {
+			posts: (1..=10).map(post).collect(),
+			page: 1,
+			is_new: true,
+			source: None,
+		}),
+		Event::Source(SourceEvent::Navigate(NavDirection::ToPosition(2))),
+	]);
+	assert_eq!(ids(&reactor), [1, 2, 3, 4, 5, 6, 7]);
+	assert_eq!(reactor.browser.peek_range(-2..=5)[1].0, 1);
+
+	reactor.tick_events(vec![Event::Settings(SettingsEvent::SetWrapNavigation {
+		enabled: true,
+	})]);
+	assert_eq!(ids(&reactor), [10, 1, 2, 3, 4, 5, 6, 7]);
+
+	// Three posts, each shown once, nearest first
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::PostsReceived {
+		posts: (1..=3).map(post).collect(),
+		page: 1,
+		is_new: true,
+		source: None,
+	})]);
+	assert_eq!(ids(&reactor), [3, 1, 2]);
+}
+
+#[tokio::test]
+async fn theme_is_saved_and_restored_with_the_panel_opacity_kept_in_range() {
+	let mut reactor = test_reactor(0);
+	let violet = Theme::ACCENTS[2].1;
+	reactor.tick_events(vec![Event::Settings(SettingsEvent::SetTheme {
+		accent: violet,
+		panel_opacity: 0.0,
+		dark: false,
+	})]);
+	let theme = reactor.settings.theme();
+	assert_eq!(theme.panel_opacity, Theme::MIN_PANEL_OPACITY);
+	assert_eq!(theme.accent_name(), Some("Violet"));
+	assert_eq!(theme.visuals().selection.bg_fill, violet);
+	assert!(!theme.visuals().dark_mode);
+	assert_eq!(reactor.saved_settings().theme, theme);
+
+	let response = reactor.apply_settings(&crate::config::SavedSettings::default());
+	reactor.tick_events(response.events);
+	assert_eq!(reactor.settings.theme(), Theme::default());
+}
+
+#[tokio::test]
+async fn posts_of_an_appended_page_load_before_navigation_reaches_them() {
+	let loader = FakeLoader::new();
+	let calls = loader.calls();
+	let mut reactor = test_reactor_with_loader(0, loader);
+	let sampled = |id| {
+		let mut post = post(id);
+		post.sample.has = true;
+		post.sample.url = Some(format!("https://example.test/{}_sample.png", id));
+		post
+	};
+	let prefetched = |routed: &[Event]| -> Vec<(Option<String>, Option<String>, bool)> {
+		routed
+			.iter()
+			.rev()
+			.find_map(|e| match e {
+				Event::Media(MediaEvent::Prefetch { urls }) => Some(urls.clone()),
+				_ => None,
+			})
+			.unwrap_or_default()
+	};
+	reactor.tick_events(vec![Event::Browser(BrowserEvent::PostsReceived {
+		posts: (1..=40).map(sampled).collect(),
+		page: 1,
+		is_new: true,
+		source: None,
+	})]);
+
+	// Nine posts left is already near the end
+	let routed = reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::ToPosition(31),
+	))]);
+	assert!(
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::Gateway(GatewayEvent::FetchNextPage)))
+	);
+
+	// The next page lands while the user is back near the start
+	reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::ToPosition(1),
+	))]);
+	let routed = reactor.tick_events(vec![Event::Browser(BrowserEvent::PostsReceived {
+		posts: (41..=60).map(sampled).collect(),
+		page: 2,
+		is_new: false,
+		source: None,
+	})]);
+	let urls = prefetched(&routed);
+	assert_eq!(urls.len(), 30 + 10);
+	assert_eq!(urls[29].1, sampled(31).file.url);
+	// Only the samples of the new page's posts
+	assert_eq!(urls[30], (sampled(41).sample.url, None, false));
+	assert_eq!(urls[39], (sampled(50).sample.url, None, false));
+
+	// Jumping straight to the seam finds the first new post already loaded
+	for _ in 0..200 {
+		if reactor.media.readiness(&sampled(41)) == Readiness::SampleReady {
+			break;
+		}
+		reactor.tick_events(Vec::new());
+		tokio::time::sleep(Duration::from_millis(5)).await;
+	}
+	assert_eq!(
+		reactor.media.readiness(&sampled(41)),
+		Readiness::SampleReady
+	);
+	let original = sampled(41).file.url.unwrap();
+	assert!(!calls.lock().unwrap().contains(&original));
+	let routed = reactor.tick_events(vec![Event::Source(SourceEvent::Navigate(
+		NavDirection::ToPosition(41),
+	))]);
+	assert!(
+		routed
+			.iter()
+			.any(|e| matches!(e, Event::View(ViewEvent::MediaReady)))
+	);
+	let sample = sampled(41).sample.url.unwrap();
+	assert_eq!(
+		calls
+			.lock()
+			.unwrap()
+			.iter()
+			.filter(|url| **url == sample)
+			.count(),
+		1
+	);
+}
+
+/// Who `dispatch` hands `event` to, in order
+fn takers(reactor: &mut Reactor, event: &Event) -> Vec<&'static str> {
+	const BUILTIN: [&str; 6] = ["gateway", "browser", "media", "breathing", "beat", "view"];
+	let mut takers: Vec<_> = reactor
+		.builtin()
+		.into_iter()
+		.zip(BUILTIN)
+		.filter(|(component, _)| component::takes(*component, event))
+		.map(|(_, name)| name)
+		.collect();
+	if reactor.settings.wants(event) {
+		takers.push("settings");
+	}
+	takers
+}
+
+#[tokio::test]
+async fn dispatch_reaches_the_components_each_event_is_for() {
+	let mut reactor = test_reactor(0);
+	let table: Vec<(Event, &[&str])> = vec![
+		(
+			Event::Gateway(GatewayEvent::SearchError {
+				message: "offline".to_string(),
+				category: ErrorCategory::Network,
+			}),
+			&["gateway", "view"],
+		),
+		(
+			Event::Gateway(GatewayEvent::Throttled {
+				retry_after: Duration::from_secs(1),
+				retry: Box::new(Event::Gateway(GatewayEvent::FetchNextPage)),
+			}),
+			&["gateway", "view"],
+		),
+		(
+			Event::Gateway(GatewayEvent::ConnectionState { online: true }),
+			&["gateway", "view"],
+		),
+		(
+			Event::Gateway(GatewayEvent::PageProgress {
+				loaded: 1,
+				total: 3,
+			}),
+			&["view"],
+		),
+		(
+			Event::Gateway(GatewayEvent::NewPostsAvailable {
+				query: "wolf".to_string(),
+				count: 2,
+			}),
+			&["view"],
+		),
+		(
+			Event::Gateway(GatewayEvent::PollWatch {
+				query: "wolf".to_string(),
+				generation: 0,
+			}),
+			&["gateway"],
+		),
+		(
+			Event::Gateway(GatewayEvent::ClearEndOfResults),
+			&["gateway", "browser"],
+		),
+		(Event::Gateway(GatewayEvent::FetchNextPage), &["gateway"]),
+		(
+			Event::Browser(BrowserEvent::EndOfResults),
+			&["browser", "view"],
+		),
+		(
+			Event::Browser(BrowserEvent::Navigate {
+				direction: NavDirection::Next,
+			}),
+			&["browser", "settings"],
+		),
+		(
+			Event::Browser(BrowserEvent::PostsResolved {
+				purpose: LookupPurpose::RefreshFavorites,
+				posts: Vec::new(),
+				missing: Vec::new(),
+			}),
+			&[],
+		),
+		(
+			Event::Media(MediaEvent::QueueDownloadRange {
+				from_index: 0,
+				count: 5,
+				dest: std::path::PathBuf::from("out"),
+			}),
+			&["browser"],
+		),
+		(
+			Event::Media(MediaEvent::LoadError {
+				cache_key: "a".to_string(),
+				error: "gone".to_string(),
+				permanent: true,
+				current: true,
+			}),
+			&["view", "settings"],
+		),
+		(Event::Media(MediaEvent::RetryCurrent), &["media"]),
+		(
+			Event::Settings(SettingsEvent::SetShowPending { enabled: true }),
+			&["browser"],
+		),
+		(
+			Event::Settings(SettingsEvent::SetWrapNavigation { enabled: true }),
+			&["browser"],
+		),
+		(
+			Event::Settings(SettingsEvent::SetFetchAhead { posts: 3 }),
+			&["browser"],
+		),
+		(
+			Event::Settings(SettingsEvent::SafeModeChanged { enabled: true }),
+			&["gateway", "browser"],
+		),
+		(
+			Event::Settings(SettingsEvent::ToggleAutoPlay),
+			&["settings"],
+		),
+		(
+			Event::Settings(SettingsEvent::SlideshowAdvance { generation: 0 }),
+			&["settings"],
+		),
+		(
+			Event::Settings(SettingsEvent::ExportConfig {
+				path: std::path::PathBuf::from("out.toml"),
+			}),
+			&[],
+		),
+		(Event::View(ViewEvent::MediaReady), &["view", "settings"]),
+		(Event::View(ViewEvent::ModalOpened), &["settings"]),
+		(Event::View(ViewEvent::ModalClosed), &["settings"]),
+		(Event::View(ViewEvent::TosAccepted), &[]),
+		(Event::View(ViewEvent::ToggleImageFillMode), &["view"]),
+		(Event::Beat(BeatEvent::SilenceDetected), &["view"]),
+		(Event::Beat(BeatEvent::LockLost), &["beat"]),
+		(Event::Breathing(BreathingEvent::Toggle), &["breathing"]),
+		(
+			Event::Breathing(BreathingEvent::PhaseStarted {
+				phase: BreathingPhase::Inhale,
+				duration: Duration::from_secs(5),
+			}),
+			&["breathing", "settings"],
+		),
+		(Event::Source(SourceEvent::Reroll), &[]),
+		(Event::Favorites(FavoritesEvent::ToggleCurrent), &[]),
+	];
+	for (event, expected) in &table {
+		assert_eq!(takers(&mut reactor, event), *expected, "{:?}", event);
+	}
+}
@@ -1,24 +1,213 @@
 use crate::breathing::BreathingOverlay;
-use crate::reactor::{BreathingEvent, BrowserEvent, ComponentResponse, Event, SettingsEvent};
-use crate::types::{BreathingPhase, NavDirection};
+use crate::reactor::{
+	BreathingEvent, BrowserEvent, ComponentResponse, Event, MediaEvent, SettingsEvent, SourceEvent,
+	ViewEvent,
+};
+use crate::types::{
+	BreathingPhase, CreditOverlay, InfoFieldSetting, InfoVerbosity, NavDirection, StartupSearch,
+	Theme,
+};
 use std::time::{Duration, Instant};
 
+/// Stop skipping failed images after this many in a row; the network is likely down
+const MAX_AUTO_SKIPS: u32 = 5;
+
+/// Time to read the toast before a failed image is skipped
+const AUTO_SKIP_DELAY: Duration = Duration::from_secs(2);
+
+/// Time between checks on a next image that hasn't started showing
+const NEXT_IMAGE_WAIT: Duration = Duration::from_secs(1);
+
+/// Checks before advancing anyway, so one slow load doesn't stop the slideshow
+const MAX_NEXT_IMAGE_WAITS: u32 = 5;
+
+/// A held advance fires this long after the breath ends, so it lands after the phase change
+const BREATH_HOLD_MARGIN: Duration = Duration::from_millis(100);
+
 pub struct SettingsManager {
 	auto_play: bool,
 	auto_play_delay: Duration,
-	slideshow_scheduled: bool,
 	cap_by_breathing: bool,
-	last_advance_time: Instant,
+	/// Hold slideshow advances while breathing in or holding, without syncing to the cycle
+	breath_hold: bool,
+	/// End of the Inhale or Hold phase under way, from `PhaseStarted`
+	breath_until: Option<Instant>,
+	/// Bumped whenever the slideshow is rescheduled; stale advances are dropped
+	slideshow_generation: u64,
+	/// Failed images skipped in a row, reset when an image loads
+	auto_skips: u32,
+	/// Times the pending advance waited for the next image to load
+	next_image_waits: u32,
+	/// Hold advances after user interaction, for `auto_pause_idle`
+	auto_pause: bool,
+	auto_pause_idle: Duration,
+	/// No slideshow advance before this point
+	suppressed_until: Option<Instant>,
+	info_fields: Vec<InfoFieldSetting>,
+	info_verbosity: InfoVerbosity,
+	credit: CreditOverlay,
+	theme: Theme,
+	/// Calm every overlay down: static breathing text, instant transitions,
+	/// slower auto-pan and no beat zoom
+	reduce_motion: bool,
+	/// Pure white text with a thick solid outline on every overlay
+	high_contrast: bool,
+	/// Pages of comics and pools: fit to width, paged with Space. Holds the slideshow.
+	reading_mode: bool,
+	/// Reading mode was turned on by entering a pool, and goes off on leaving it
+	reading_for_pool: bool,
+	/// A modal is up; the slideshow stays put behind it and starts over once it closes
+	modal_open: bool,
+	/// Only rating:s posts, as chosen in the settings
+	safe_mode: bool,
+	/// Forced on by `--sfw`, so it can't be turned off from the UI
+	safe_mode_locked: bool,
+	startup_search: StartupSearch,
+	default_query: String,
 }
 
 impl SettingsManager {
-	pub fn new(auto_play: bool, auto_play_delay: Duration, cap_by_breathing: bool) -> Self {
+	pub fn new(
+		auto_play: bool,
+		auto_play_delay: Duration,
+		cap_by_breathing: bool,
+		auto_pause: bool,
+		auto_pause_idle: Duration,
+		info_fields: Vec<InfoFieldSetting>,
+		info_verbosity: InfoVerbosity,
+	) -> Self {
 		Self {
 			auto_play,
 			auto_play_delay,
-			slideshow_scheduled: false,
 			cap_by_breathing,
-			last_advance_time: Instant::now(),
+			breath_hold: false,
+			breath_until: None,
+			slideshow_generation: 0,
+			auto_skips: 0,
+			next_image_waits: 0,
+			auto_pause,
+			auto_pause_idle,
+			suppressed_until: None,
+			info_fields: InfoFieldSetting::normalize(info_fields),
+			info_verbosity,
+			credit: CreditOverlay::default(),
+			theme: Theme::default(),
+			reduce_motion: false,
+			high_contrast: false,
+			reading_mode: false,
+			reading_for_pool: false,
+			modal_open: false,
+			safe_mode: false,
+			safe_mode_locked: false,
+			startup_search: StartupSearch::Nothing,
+			default_query: String::new(),
+		}
+	}
+
+	pub fn with_breath_hold(mut self, enabled: bool) -> Self {
+		self.breath_hold = enabled;
+		self
+	}
+
+	/// Time left of the breath that holds the slideshow, if any
+	fn breath_held_for(&self, breathing: &BreathingOverlay) -> Option<Duration> {
+		if !self.breath_hold || !breathing.is_visible() {
+			return None;
+		}
+		self.breath_until
+			.map(|until| until.saturating_duration_since(Instant::now()))
+			.filter(|left| !left.is_zero())
+	}
+
+	/// Start with the reduced-motion and high-contrast preferences
+	pub fn with_accessibility(mut self, reduce_motion: bool, high_contrast: bool) -> Self {
+		self.reduce_motion = reduce_motion;
+		self.high_contrast = high_contrast;
+		self
+	}
+
+	/// Start with the safe mode setting, and whether the command line locks it on
+	pub fn with_safe_mode(mut self, enabled: bool, locked: bool) -> Self {
+		self.safe_mode = enabled;
+		self.safe_mode_locked = locked;
+		self
+	}
+
+	pub fn with_credit_overlay(mut self, credit: CreditOverlay) -> Self {
+		self.credit = credit;
+		self
+	}
+
+	pub fn with_theme(mut self, theme: Theme) -> Self {
+		self.theme = Theme {
+			panel_opacity: theme.panel_opacity.clamp(Theme::MIN_PANEL_OPACITY, 1.0),
+			..theme
+		};
+		self
+	}
+
+	/// What to search for once the terms are accepted, and the default query
+	pub fn with_startup_search(
+		mut self,
+		startup_search: StartupSearch,
+		default_query: String,
+	) -> Self {
+		self.startup_search = startup_search;
+		self.default_query = default_query;
+		self
+	}
+
+	/// Reading mode holds the slideshow; turning it off starts the countdown afresh
+	fn set_reading_mode(&mut self, enabled: bool) -> ComponentResponse {
+		self.reading_mode = enabled;
+		log::info!("Reading mode: {}", enabled);
+		if !enabled && self.auto_play {
+			return self.restart_slideshow();
+		}
+		ComponentResponse::none()
+	}
+
+	/// Time left before the slideshow may advance again after user activity
+	fn suppressed_for(&self) -> Option<Duration> {
+		self.suppressed_until
+			.map(|until| until.saturating_duration_since(Instant::now()))
+			.filter(|left| !left.is_zero())
+	}
+
+	/// Invalidate any pending advance and schedule a fresh one a full interval from now
+	fn restart_slideshow(&mut self) -> ComponentResponse {
+		self.slideshow_generation = self.slideshow_generation.wrapping_add(1);
+		self.next_image_waits = 0;
+		ComponentResponse::schedule(
+			Event::Settings(SettingsEvent::SlideshowAdvance {
+				generation: self.slideshow_generation,
+			}),
+			self.auto_play_delay,
+		)
+	}
+
+	/// Whether `event` is for the settings, beside the settings events the browser,
+	/// gateway or reactor handle instead
+	pub fn wants(&self, event: &Event) -> bool {
+		match event {
+			Event::Settings(
+				SettingsEvent::SetShowPending { .. }
+				| SettingsEvent::SetWrapNavigation { .. }
+				| SettingsEvent::SetFetchAhead { .. }
+				| SettingsEvent::SafeModeChanged { .. }
+				| SettingsEvent::ExportConfig { .. }
+				| SettingsEvent::ImportConfig { .. },
+			) => false,
+			Event::Settings(_) => true,
+			// Navigating restarts the slideshow, breathing can hold or advance it,
+			// failures skip past and loads reset the skip count
+			Event::Browser(BrowserEvent::Navigate { .. })
+			| Event::Breathing(BreathingEvent::PhaseStarted { .. })
+			| Event::Media(MediaEvent::LoadError { .. })
+			| Event::View(
+				ViewEvent::MediaReady | ViewEvent::ModalOpened | ViewEvent::ModalClosed,
+			) => true,
+			_ => false,
 		}
 	}
 
@@ -27,14 +216,7 @@ impl SettingsManager {
 			Event::Settings(SettingsEvent::ToggleAutoPlay) => {
 				self.auto_play = !self.auto_play;
 				if self.auto_play {
-					self.last_advance_time = Instant::now();
-					if !self.slideshow_scheduled {
-						self.slideshow_scheduled = true;
-						return ComponentResponse::schedule(
-							Event::Settings(SettingsEvent::SlideshowAdvance),
-							self.auto_play_delay,
-						);
-					}
+					return self.restart_slideshow();
 				}
 				ComponentResponse::none()
 			}
@@ -52,8 +234,126 @@ impl SettingsManager {
 				self.cap_by_breathing = !self.cap_by_breathing;
 				ComponentResponse::none()
 			}
-			Event::Breathing(BreathingEvent::PhaseStarted(phase)) => {
-				if self.auto_play && self.cap_by_breathing && breathing.is_visible() {
+			Event::Settings(SettingsEvent::ToggleBreathHold) => {
+				self.breath_hold = !self.breath_hold;
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetStartupSearch { behavior }) => {
+				self.startup_search = *behavior;
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetDefaultQuery { query }) => {
+				self.default_query = query.clone();
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::ToggleReduceMotion) => {
+				self.reduce_motion = !self.reduce_motion;
+				log::info!("Reduce motion: {}", self.reduce_motion);
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::ToggleSafeMode) => {
+				if self.safe_mode_locked {
+					log::warn!("Safe mode is locked on by --sfw");
+					return ComponentResponse::none();
+				}
+				self.safe_mode = !self.safe_mode;
+				log::info!("Safe mode: {}", self.safe_mode);
+				ComponentResponse::emit(Event::Settings(SettingsEvent::SafeModeChanged {
+					enabled: self.safe_mode,
+				}))
+			}
+			Event::Settings(SettingsEvent::ToggleHighContrast) => {
+				self.high_contrast = !self.high_contrast;
+				log::info!("High contrast overlays: {}", self.high_contrast);
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::ToggleReadingMode) => {
+				self.reading_for_pool = false;
+				self.set_reading_mode(!self.reading_mode)
+			}
+			Event::Settings(SettingsEvent::PoolReading { entered: true }) => {
+				if self.reading_mode {
+					return ComponentResponse::none();
+				}
+				self.reading_for_pool = true;
+				self.set_reading_mode(true)
+			}
+			Event::Settings(SettingsEvent::PoolReading { entered: false }) => {
+				if !std::mem::take(&mut self.reading_for_pool) {
+					return ComponentResponse::none();
+				}
+				self.set_reading_mode(false)
+			}
+			Event::View(ViewEvent::ModalOpened) => {
+				self.modal_open = true;
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::ModalClosed) => {
+				self.modal_open = false;
+				// A full interval from now, so the image doesn't change the moment it's seen again
+				if self.auto_play {
+					return self.restart_slideshow();
+				}
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetAutoPause { enabled, idle_secs }) => {
+				self.auto_pause = *enabled;
+				self.auto_pause_idle = Duration::from_secs_f32(idle_secs.max(1.0));
+				if !self.auto_pause {
+					self.suppressed_until = None;
+				}
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SuppressAdvanceFor { secs }) => {
+				if self.auto_pause {
+					self.suppressed_until =
+						Some(Instant::now() + Duration::from_secs_f32(secs.max(0.0)));
+				}
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetInfoFields { fields }) => {
+				self.info_fields = InfoFieldSetting::normalize(fields.clone());
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetInfoVerbosity { verbosity }) => {
+				self.info_verbosity = *verbosity;
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::CycleInfoVerbosity) => {
+				self.info_verbosity = self.info_verbosity.next();
+				log::debug!("Info overlay: {:?}", self.info_verbosity);
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetCreditOverlay { credit }) => {
+				self.credit = CreditOverlay {
+					opacity: credit.opacity.clamp(CreditOverlay::MIN_OPACITY, 1.0),
+					..*credit
+				};
+				log::debug!("Credit overlay: {:?}", self.credit);
+				ComponentResponse::none()
+			}
+			Event::Settings(SettingsEvent::SetTheme {
+				accent,
+				panel_opacity,
+				dark,
+			}) => {
+				self.theme = Theme {
+					accent: *accent,
+					panel_opacity: panel_opacity.clamp(Theme::MIN_PANEL_OPACITY, 1.0),
+					dark: *dark,
+				};
+				log::debug!("Theme: {:?}", self.theme);
+				ComponentResponse::none()
+			}
+			Event::Breathing(BreathingEvent::PhaseStarted { phase, duration }) => {
+				self.breath_until = matches!(phase, BreathingPhase::Inhale | BreathingPhase::Hold)
+					.then(|| Instant::now() + *duration);
+				if self.auto_play
+					&& self.cap_by_breathing
+					&& breathing.is_visible()
+					&& self.suppressed_for().is_none()
+					&& !self.modal_open
+				{
 					if matches!(phase, BreathingPhase::Prepare | BreathingPhase::Release) {
 						// Immediately trigger advance in these phases
 						return ComponentResponse::emit(Event::Browser(BrowserEvent::Navigate {
@@ -64,79 +364,238 @@ impl SettingsManager {
 				ComponentResponse::none()
 			}
 			Event::Browser(BrowserEvent::Navigate { .. }) => {
+				// Any navigation (manual or slideshow) restarts the full interval
 				if self.auto_play {
-					self.last_advance_time = Instant::now();
-					if !self.slideshow_scheduled {
-						self.slideshow_scheduled = true;
-						return ComponentResponse::schedule(
-							Event::Settings(SettingsEvent::SlideshowAdvance),
-							self.auto_play_delay,
-						);
-					}
+					return self.restart_slideshow();
 				}
 				ComponentResponse::none()
 			}
-			Event::Settings(SettingsEvent::SlideshowAdvance) => {
-				self.slideshow_scheduled = false;
-				if self.auto_play {
-					let elapsed = self.last_advance_time.elapsed();
-					if elapsed < self.auto_play_delay {
-						// We haven't waited long enough since the last manual navigation or advance
-						self.slideshow_scheduled = true;
+			Event::Settings(SettingsEvent::SlideshowAdvance { generation }) => {
+				if !self.auto_play || *generation != self.slideshow_generation {
+					log::debug!("Dropping stale slideshow advance (gen={})", generation);
+					return ComponentResponse::none();
+				}
+
+				// The reader turns the pages; the slideshow starts over once reading ends
+				if self.reading_mode {
+					log::debug!("Slideshow held in reading mode");
+					return ComponentResponse::none();
+				}
+
+				// Dropped rather than held; closing the modal schedules a fresh advance
+				if self.modal_open {
+					log::debug!("Slideshow held behind a modal");
+					return ComponentResponse::none();
+				}
+
+				// The user is looking around; check again once they've been idle long enough
+				if let Some(left) = self.suppressed_for() {
+					log::debug!(
+						"Slideshow held for {:.1}s of user activity",
+						left.as_secs_f32()
+					);
+					return ComponentResponse::schedule(
+						Event::Settings(SettingsEvent::SlideshowAdvance {
+							generation: *generation,
+						}),
+						left,
+					);
+				}
+
+				// Mid-breath; go on once the breath is let out
+				if let Some(left) = self.breath_held_for(breathing) {
+					log::debug!("Slideshow held for {:.1}s of breathing", left.as_secs_f32());
+					return ComponentResponse::schedule(
+						Event::Settings(SettingsEvent::SlideshowAdvance {
+							generation: *generation,
+						}),
+						left + BREATH_HOLD_MARGIN,
+					);
+				}
+
+				// Check breathing cap
+				if self.cap_by_breathing && breathing.is_visible() {
+					let phase = breathing.state().phase;
+					if matches!(phase, BreathingPhase::Inhale | BreathingPhase::Hold) {
+						// Blocked by breathing, reschedule to check again shortly
 						return ComponentResponse::schedule(
-							Event::Settings(SettingsEvent::SlideshowAdvance),
-							self.auto_play_delay - elapsed,
+							Event::Settings(SettingsEvent::SlideshowAdvance {
+								generation: *generation,
+							}),
+							Duration::from_secs(1),
 						);
 					}
+				}
 
-					// Check breathing cap
-					if self.cap_by_breathing && breathing.is_visible() {
-						let phase = breathing.state().phase;
-						if matches!(phase, BreathingPhase::Inhale | BreathingPhase::Hold) {
-							// Blocked by breathing, reschedule to check again shortly
-							self.slideshow_scheduled = true;
-							return ComponentResponse::schedule(
-								Event::Settings(SettingsEvent::SlideshowAdvance),
-								Duration::from_secs(1),
-							);
-						}
-					}
-
-					// Navigate to next and schedule another advance
-					self.slideshow_scheduled = true;
-					self.last_advance_time = Instant::now();
-					let mut response =
-						ComponentResponse::emit(Event::Browser(BrowserEvent::Navigate {
-							direction: NavDirection::Next,
-						}));
-					response.scheduled.push((
-						Event::Settings(SettingsEvent::SlideshowAdvance),
-						self.auto_play_delay,
-					));
-					return response;
+				// Navigating reschedules the next advance
+				ComponentResponse::emit(Event::Browser(BrowserEvent::Navigate {
+					direction: NavDirection::Next,
+				}))
+			}
+			Event::Media(MediaEvent::LoadError {
+				permanent: true,
+				current: true,
+				..
+			}) => {
+				if !self.auto_play {
+					return ComponentResponse::none();
+				}
+				if self.auto_skips >= MAX_AUTO_SKIPS {
+					log::warn!("Not skipping: {} failed images in a row", self.auto_skips);
+					return ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+						message: format!(
+							"{} images failed in a row, stopped skipping. Check your connection.",
+							self.auto_skips
+						),
+					}));
+				}
+				self.auto_skips += 1;
+				log::info!(
+					"Image unavailable, skip {} of {}",
+					self.auto_skips,
+					MAX_AUTO_SKIPS
+				);
+				let mut response = ComponentResponse::emit(Event::View(ViewEvent::ShowToast {
+					message: "Image unavailable, skipping...".to_string(),
+				}));
+				response.scheduled.push((
+					Event::Settings(SettingsEvent::SkipFailed {
+						generation: self.slideshow_generation,
+					}),
+					AUTO_SKIP_DELAY,
+				));
+				response
+			}
+			Event::Settings(SettingsEvent::SkipFailed { generation }) => {
+				// Any navigation in the meantime already moved past the image; behind a
+				// modal, the slideshow moves on once it closes
+				if !self.auto_play || *generation != self.slideshow_generation || self.modal_open {
+					return ComponentResponse::none();
 				}
+				ComponentResponse::emit(Event::Source(SourceEvent::Navigate(NavDirection::Next)))
+			}
+			Event::View(ViewEvent::MediaReady) => {
+				self.auto_skips = 0;
 				ComponentResponse::none()
 			}
 			_ => ComponentResponse::none(),
 		}
 	}
 
+	/// Hold a due advance whose next image has nothing to show yet, and check again
+	/// shortly. `None` once it waited long enough, when the advance is stale, or
+	/// behind a modal, where it's dropped anyway.
+	pub fn wait_for_next_image(&mut self, generation: u64) -> Option<ComponentResponse> {
+		if !self.auto_play
+			|| generation != self.slideshow_generation
+			|| self.next_image_waits >= MAX_NEXT_IMAGE_WAITS
+			|| self.modal_open
+		{
+			return None;
+		}
+		self.next_image_waits += 1;
+		log::debug!(
+			"Slideshow held for the next image ({}/{})",
+			self.next_image_waits,
+			MAX_NEXT_IMAGE_WAITS
+		);
+		Some(ComponentResponse::schedule(
+			Event::Settings(SettingsEvent::SlideshowAdvance { generation }),
+			NEXT_IMAGE_WAIT,
+		))
+	}
+
 	// Accessors for ViewManager/UI
 	pub fn auto_play(&self) -> bool {
 		self.auto_play
 	}
 
+	pub fn startup_search(&self) -> StartupSearch {
+		self.startup_search
+	}
+
+	pub fn default_query(&self) -> &str {
+		&self.default_query
+	}
+
 	pub fn cap_by_breathing(&self) -> bool {
 		self.cap_by_breathing
 	}
 
+	pub fn breath_hold(&self) -> bool {
+		self.breath_hold
+	}
+
 	pub fn auto_play_delay(&self) -> Duration {
 		self.auto_play_delay
 	}
+
+	pub fn auto_pause(&self) -> bool {
+		self.auto_pause
+	}
+
+	pub fn auto_pause_idle(&self) -> Duration {
+		self.auto_pause_idle
+	}
+
+	/// Whether auto-play is currently held back by user activity
+	pub fn advance_suppressed(&self) -> bool {
+		self.auto_play && self.suppressed_for().is_some()
+	}
+
+	pub fn info_fields(&self) -> &[InfoFieldSetting] {
+		&self.info_fields
+	}
+
+	pub fn info_verbosity(&self) -> InfoVerbosity {
+		self.info_verbosity
+	}
+
+	pub fn credit_overlay(&self) -> CreditOverlay {
+		self.credit
+	}
+
+	pub fn theme(&self) -> Theme {
+		self.theme
+	}
+
+	pub fn reduce_motion(&self) -> bool {
+		self.reduce_motion
+	}
+
+	pub fn high_contrast(&self) -> bool {
+		self.high_contrast
+	}
+
+	pub fn reading_mode(&self) -> bool {
+		self.reading_mode
+	}
+
+	/// Whether only rating:s posts are shown, by choice or by `--sfw`
+	pub fn safe_mode(&self) -> bool {
+		self.safe_mode || self.safe_mode_locked
+	}
+
+	pub fn safe_mode_locked(&self) -> bool {
+		self.safe_mode_locked
+	}
+
+	/// The setting as chosen, which is what gets saved
+	pub fn safe_mode_setting(&self) -> bool {
+		self.safe_mode
+	}
 }
 
 impl Default for SettingsManager {
 	fn default() -> Self {
-		Self::new(false, Duration::from_secs(16), false)
+		Self::new(
+			false,
+			Duration::from_secs(16),
+			false,
+			true,
+			Duration::from_secs(30),
+			InfoFieldSetting::defaults(),
+			InfoVerbosity::Full,
+		)
 	}
 }
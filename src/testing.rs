@@ -0,0 +1,352 @@
+//! Fakes of the IO-backed parts, shared by the tests of every module
+
+use crate::api::{ApiError, BooruClient, Credentials, Note, Post, PostSet, Tag, VoteResponse};
+use crate::media::loader::{Fetched, MediaLoader, PermanentError};
+use crate::media::timing::FetchTimings;
+use crate::types::BoxFuture;
+use eframe::egui;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// What a `FakeLoader` does with a URL
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome {
+	/// A solid image of this size
+	Image([usize; 2]),
+	/// HTTP 404, which retrying won't fix
+	Missing,
+	/// A timeout, worth retrying
+	TimedOut,
+	/// Never finishes, like a dead connection
+	Stall,
+}
+
+struct Rule {
+	matches: Box<dyn Fn(&str) -> bool + Send + Sync>,
+	outcome: Outcome,
+	delay: Duration,
+	/// Loads the rule still applies to; `None` for all of them
+	left: Option<usize>,
+}
+
+/// Loads URLs by the first rule matching them, and a 2x2 image when none does.
+/// Records every URL asked for.
+#[derive(Default)]
+pub struct FakeLoader {
+	rules: Mutex<Vec<Rule>>,
+	calls: Arc<Mutex<Vec<String>>>,
+	/// Each load waits for a permit before it finishes
+	permits: Option<Arc<tokio::sync::Semaphore>>,
+	/// Time `decode` takes; with it set, `fetch` hands files over undecoded
+	decode_time: Option<Duration>,
+}
+
+/// Match `url` only
+pub fn is(url: &str) -> impl Fn(&str) -> bool + Send + Sync + 'static {
+	let url = url.to_string();
+	move |other| other == url
+}
+
+/// Match samples, named `..._sample.ext` in the tests
+pub fn sample(url: &str) -> bool {
+	url.contains("_sample")
+}
+
+/// Match anything but samples
+pub fn original(url: &str) -> bool {
+	!sample(url)
+}
+
+impl FakeLoader {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// `outcome` for every load of a URL `matches` accepts
+	pub fn on(
+		self,
+		matches: impl Fn(&str) -> bool + Send + Sync + 'static,
+		outcome: Outcome,
+	) -> Self {
+		self.rule(matches, outcome, None)
+	}
+
+	/// `outcome` for the first load `matches` accepts, then the rules after it
+	pub fn once(
+		self,
+		matches: impl Fn(&str) -> bool + Send + Sync + 'static,
+		outcome: Outcome,
+	) -> Self {
+		self.rule(matches, outcome, Some(1))
+	}
+
+	fn rule(
+		self,
+		matches: impl Fn(&str) -> bool + Send + Sync + 'static,
+		outcome: Outcome,
+		left: Option<usize>,
+	) -> Self {
+		self.rules.lock().unwrap().push(Rule {
+			matches: Box::new(matches),
+			outcome,
+			delay: Duration::ZERO,
+			left,
+		});
+		self
+	}
+
+	/// Make the last rule's loads take `delay`
+	pub fn after(self, delay: Duration) -> Self {
+		if let Some(rule) = self.rules.lock().unwrap().last_mut() {
+			rule.delay = delay;
+		}
+		self
+	}
+
+	/// Finish loads one per permit added to `permits`
+	pub fn gated(mut self, permits: Arc<tokio::sync::Semaphore>) -> Self {
+		self.permits = Some(permits);
+		self
+	}
+
+	/// Download for the rule's delay less `time`, and decode on the decode pool
+	/// for `time`
+	pub fn decoding_for(mut self, time: Duration) -> Self {
+		self.decode_time = Some(time);
+		self
+	}
+
+	/// Every URL asked for so far, in order
+	pub fn calls(&self) -> Arc<Mutex<Vec<String>>> {
+		self.calls.clone()
+	}
+
+	/// Record `url` and pick what happens to it
+	fn start(&self, url: &str) -> (Outcome, Duration) {
+		self.calls.lock().unwrap().push(url.to_string());
+		let mut rules = self.rules.lock().unwrap();
+		let rule = rules
+			.iter_mut()
+			.find(|rule| rule.left != Some(0) && (rule.matches)(url));
+		match rule {
+			Some(rule) => {
+				if let Some(left) = &mut rule.left {
+					*left -= 1;
+				}
+				(rule.outcome, rule.delay)
+			}
+			None => (Outcome::Image([2, 2]), Duration::ZERO),
+		}
+	}
+
+	async fn finish(&self, outcome: Outcome, delay: Duration) -> anyhow::Result<[usize; 2]> {
+		tokio::time::sleep(delay).await;
+		if let Some(permits) = &self.permits {
+			permits.acquire().await?.forget();
+		}
+		match outcome {
+			Outcome::Image(size) => Ok(size),
+			Outcome::Missing => {
+				Err(PermanentError("HTTP Status: 404 Not Found".to_string()).into())
+			}
+			Outcome::TimedOut => anyhow::bail!("Request timed out"),
+			Outcome::Stall => std::future::pending().await,
+		}
+	}
+}
+
+impl MediaLoader for FakeLoader {
+	fn load<'a>(
+		&'a self,
+		url: &'a str,
+	) -> BoxFuture<'a, anyhow::Result<(egui::ColorImage, Vec<u8>)>> {
+		let (outcome, delay) = self.start(url);
+		Box::pin(async move {
+			let size = self.finish(outcome, delay).await?;
+			Ok((
+				egui::ColorImage::new(size, egui::Color32::WHITE),
+				vec![0; 16],
+			))
+		})
+	}
+
+	fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, anyhow::Result<Fetched>> {
+		let Some(decode_time) = self.decode_time else {
+			return Box::pin(async move {
+				let (image, bytes) = self.load(url).await?;
+				Ok(Fetched::Decoded {
+					image,
+					bytes,
+					timings: FetchTimings::default(),
+				})
+			});
+		};
+		let (outcome, delay) = self.start(url);
+		Box::pin(async move {
+			self.finish(outcome, delay.saturating_sub(decode_time))
+				.await?;
+			Ok(Fetched::Raw {
+				bytes: vec![0; 16],
+				timings: FetchTimings::default(),
+			})
+		})
+	}
+
+	fn decode(&self, _bytes: &[u8]) -> anyhow::Result<egui::ColorImage> {
+		std::thread::sleep(self.decode_time.unwrap_or_default());
+		Ok(egui::ColorImage::new([2, 2], egui::Color32::WHITE))
+	}
+}
+
+/// Serves a fixed first page and nothing after it.
+/// The first `throttles` searches are rate limited; when `down`, every
+/// call fails as if the site were.
+/// Pools are named after their ID, except odd IDs which fail.
+/// Every tag has 3200 posts.
+/// Every set holds the posts newest first, plus post 999 which no longer exists.
+#[derive(Default)]
+pub struct FakeClient {
+	posts: Vec<Post>,
+	throttles: AtomicUsize,
+	down: bool,
+	pool_name_calls: Arc<AtomicUsize>,
+}
+
+impl FakeClient {
+	pub fn new(posts: Vec<Post>) -> Self {
+		Self {
+			posts,
+			..Default::default()
+		}
+	}
+
+	/// Rate limit the first `times` searches
+	pub fn throttled(self, times: usize) -> Self {
+		self.throttles.store(times, Ordering::SeqCst);
+		self
+	}
+
+	/// Fail every call with a 502, the ping included
+	pub fn down(mut self) -> Self {
+		self.down = true;
+		self
+	}
+
+	/// Pool name lookups so far
+	pub fn pool_name_calls(&self) -> Arc<AtomicUsize> {
+		self.pool_name_calls.clone()
+	}
+
+	/// `result`, or a 502 while the site is down
+	fn reply<'a, T: Send + 'a>(
+		&self,
+		result: Result<T, ApiError>,
+	) -> BoxFuture<'a, Result<T, ApiError>> {
+		let result = if self.down {
+			Err(ApiError::Http {
+				status: reqwest::StatusCode::BAD_GATEWAY,
+				body_snippet: String::new(),
+			})
+		} else {
+			result
+		};
+		Box::pin(async move { result })
+	}
+}
+
+impl BooruClient for FakeClient {
+	fn search_posts<'a>(
+		&'a self,
+		_tags: &'a str,
+		_limit: u32,
+		page: u32,
+	) -> BoxFuture<'a, Result<Vec<Post>, ApiError>> {
+		let throttle = self
+			.throttles
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+				left.checked_sub(1)
+			});
+		if throttle.is_ok() {
+			return self.reply(Err(ApiError::RateLimited {
+				retry_after: Some(Duration::from_millis(50)),
+			}));
+		}
+		let posts = if page == 1 {
+			self.posts.clone()
+		} else {
+			Vec::new()
+		};
+		self.reply(Ok(posts))
+	}
+
+	fn get_notes(&self, _post_id: u64) -> BoxFuture<'_, Result<Vec<Note>, ApiError>> {
+		self.reply(Ok(Vec::new()))
+	}
+
+	fn vote(&self, _post_id: u64, _score: i8) -> BoxFuture<'_, Result<VoteResponse, ApiError>> {
+		self.reply(Err(ApiError::Http {
+			status: reqwest::StatusCode::NOT_IMPLEMENTED,
+			body_snippet: "not supported".to_string(),
+		}))
+	}
+
+	fn get_pool_name(&self, pool_id: u64) -> BoxFuture<'_, Result<String, ApiError>> {
+		self.pool_name_calls.fetch_add(1, Ordering::SeqCst);
+		if pool_id % 2 == 1 {
+			return self.reply(Err(ApiError::Http {
+				status: reqwest::StatusCode::NOT_FOUND,
+				body_snippet: "not found".to_string(),
+			}));
+		}
+		self.reply(Ok(format!("Pool {}", pool_id)))
+	}
+
+	fn get_tags<'a>(&'a self, names: &'a [String]) -> BoxFuture<'a, Result<Vec<Tag>, ApiError>> {
+		let tags = names
+			.iter()
+			.map(|name| Tag {
+				name: name.clone(),
+				post_count: 3200,
+			})
+			.collect();
+		self.reply(Ok(tags))
+	}
+
+	fn get_blacklist(&self) -> BoxFuture<'_, Result<String, ApiError>> {
+		self.reply(Ok(String::new()))
+	}
+
+	/// Like the API: by ID descending, unknown IDs left out
+	fn get_posts_by_ids<'a>(
+		&'a self,
+		ids: &'a [u64],
+	) -> BoxFuture<'a, Result<Vec<Post>, ApiError>> {
+		let mut posts: Vec<Post> = self
+			.posts
+			.iter()
+			.filter(|post| ids.contains(&post.id))
+			.cloned()
+			.collect();
+		posts.sort_by_key(|post| std::cmp::Reverse(post.id));
+		self.reply(Ok(posts))
+	}
+
+	fn get_set(&self, set_id: u64) -> BoxFuture<'_, Result<PostSet, ApiError>> {
+		let mut post_ids: Vec<u64> = self.posts.iter().rev().map(|post| post.id).collect();
+		post_ids.push(999);
+		self.reply(Ok(PostSet {
+			id: set_id,
+			name: format!("set_{}", set_id),
+			post_ids,
+		}))
+	}
+
+	fn credentials(&self) -> Option<&Credentials> {
+		None
+	}
+
+	fn ping(&self) -> BoxFuture<'_, Result<reqwest::StatusCode, ApiError>> {
+		self.reply(Ok(reqwest::StatusCode::OK))
+	}
+}
@@ -1,4 +1,12 @@
 use eframe::egui;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Boxed future for async methods on trait objects
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Searches remembered by "More like this" for going back
+pub const SEARCH_STACK_DEPTH: usize = 5;
 
 /// Loaded media content
 pub enum LoadedMedia {
@@ -15,6 +23,196 @@ pub enum BreathingStyle {
 	Classic, // Quick pop-in animation
 }
 
+/// Colors used by the breathing renderers for each phase
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BreathingPalette {
+	pub prepare: egui::Color32,
+	pub inhale: egui::Color32,
+	pub hold: egui::Color32,
+	pub release: egui::Color32,
+}
+
+impl BreathingPalette {
+	pub const CLASSIC: Self = Self {
+		prepare: egui::Color32::RED,
+		inhale: egui::Color32::YELLOW,
+		hold: egui::Color32::YELLOW,
+		release: egui::Color32::GREEN,
+	};
+
+	/// Blue/orange scheme that stays distinguishable with red-green color blindness
+	pub const HIGH_CONTRAST: Self = Self {
+		prepare: egui::Color32::from_rgb(230, 120, 0),
+		inhale: egui::Color32::from_rgb(86, 180, 233),
+		hold: egui::Color32::from_rgb(0, 114, 178),
+		release: egui::Color32::from_rgb(240, 240, 240),
+	};
+
+	pub const PRESETS: [(&'static str, Self); 2] = [
+		("Classic", Self::CLASSIC),
+		("High contrast", Self::HIGH_CONTRAST),
+	];
+
+	pub fn color(&self, phase: BreathingPhase) -> egui::Color32 {
+		match phase {
+			BreathingPhase::Prepare => self.prepare,
+			BreathingPhase::Inhale => self.inhale,
+			BreathingPhase::Hold => self.hold,
+			BreathingPhase::Release => self.release,
+			BreathingPhase::Idle => egui::Color32::TRANSPARENT,
+		}
+	}
+
+	/// Name of the matching preset, if any
+	pub fn preset_name(&self) -> Option<&'static str> {
+		Self::PRESETS
+			.iter()
+			.find(|(_, p)| p == self)
+			.map(|(name, _)| *name)
+	}
+}
+
+impl Default for BreathingPalette {
+	fn default() -> Self {
+		Self::CLASSIC
+	}
+}
+
+/// Look of the panels and widgets: an accent color on egui's dark or light theme
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+	/// Selections, slider fills, progress bars and the island highlight
+	pub accent: egui::Color32,
+	/// Opacity of the top panel's background, 0.2..=1
+	pub panel_opacity: f32,
+	pub dark: bool,
+}
+
+impl Theme {
+	pub const MIN_PANEL_OPACITY: f32 = 0.2;
+
+	pub const ACCENTS: [(&'static str, egui::Color32); 5] = [
+		("Blue", egui::Color32::from_rgb(70, 130, 200)),
+		("Teal", egui::Color32::from_rgb(40, 150, 140)),
+		("Violet", egui::Color32::from_rgb(140, 100, 210)),
+		("Amber", egui::Color32::from_rgb(210, 140, 40)),
+		("Rose", egui::Color32::from_rgb(200, 80, 120)),
+	];
+
+	/// Name of the preset accent, if it is one
+	pub fn accent_name(&self) -> Option<&'static str> {
+		Self::ACCENTS
+			.iter()
+			.find(|(_, accent)| *accent == self.accent)
+			.map(|(name, _)| *name)
+	}
+
+	/// egui's visuals for the mode, with the accent on everything that marks a selection or progress
+	pub fn visuals(&self) -> egui::Visuals {
+		let mut visuals = if self.dark {
+			egui::Visuals::dark()
+		} else {
+			egui::Visuals::light()
+		};
+		visuals.selection.bg_fill = self.accent;
+		visuals.hyperlink_color = self.accent;
+		visuals.slider_trailing_fill = true;
+		visuals
+	}
+
+	/// The accent lifted towards white, for outlines around accent fills
+	pub fn accent_stroke(&self) -> egui::Color32 {
+		let [r, g, b, _] = self.accent.to_array();
+		let lift = |c: u8| c + (255 - c) / 3;
+		egui::Color32::from_rgb(lift(r), lift(g), lift(b))
+	}
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Self {
+			accent: Self::ACCENTS[0].1,
+			panel_opacity: 1.0,
+			dark: true,
+		}
+	}
+}
+
+/// Where the breathing overlay sits on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OverlayAnchor {
+	TopLeft,
+	TopRight,
+	BottomLeft,
+	#[default]
+	BottomRight,
+	Center,
+}
+
+impl OverlayAnchor {
+	pub const ALL: [Self; 5] = [
+		Self::TopLeft,
+		Self::TopRight,
+		Self::BottomLeft,
+		Self::BottomRight,
+		Self::Center,
+	];
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			Self::TopLeft => "Top left",
+			Self::TopRight => "Top right",
+			Self::BottomLeft => "Bottom left",
+			Self::BottomRight => "Bottom right",
+			Self::Center => "Center",
+		}
+	}
+
+	pub fn align(&self) -> egui::Align2 {
+		match self {
+			Self::TopLeft => egui::Align2::LEFT_TOP,
+			Self::TopRight => egui::Align2::RIGHT_TOP,
+			Self::BottomLeft => egui::Align2::LEFT_BOTTOM,
+			Self::BottomRight => egui::Align2::RIGHT_BOTTOM,
+			Self::Center => egui::Align2::CENTER_CENTER,
+		}
+	}
+
+	/// Vertical position as a fraction of screen height, for full-width layouts
+	pub fn vertical_fraction(&self) -> f32 {
+		match self {
+			Self::TopLeft | Self::TopRight => 1.0 / 3.0,
+			Self::Center => 0.5,
+			Self::BottomLeft | Self::BottomRight => 2.0 / 3.0,
+		}
+	}
+}
+
+/// Artist credit burned into a corner of the view, e.g. while streaming
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CreditOverlay {
+	pub enabled: bool,
+	/// Any corner; `Center` isn't offered
+	pub corner: OverlayAnchor,
+	/// Of both the pill and its text, 0.1 to 1
+	pub opacity: f32,
+}
+
+impl CreditOverlay {
+	pub const MIN_OPACITY: f32 = 0.1;
+}
+
+impl Default for CreditOverlay {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			corner: OverlayAnchor::TopRight,
+			opacity: 0.8,
+		}
+	}
+}
+
 /// How to fill the image in the view
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum ImageFillMode {
@@ -22,6 +220,227 @@ pub enum ImageFillMode {
 	Cover,
 	Fit,
 	FitToGallery,
+	/// One image pixel per screen pixel, panned like Cover where it overflows
+	ActualSize,
+}
+
+impl ImageFillMode {
+	pub const ALL: [Self; 4] = [
+		ImageFillMode::Cover,
+		ImageFillMode::Fit,
+		ImageFillMode::ActualSize,
+		ImageFillMode::FitToGallery,
+	];
+
+	pub fn label(self) -> &'static str {
+		match self {
+			ImageFillMode::Cover => "Cover",
+			ImageFillMode::Fit => "Fit",
+			ImageFillMode::FitToGallery => "Fit to Gallery",
+			ImageFillMode::ActualSize => "Actual Size",
+		}
+	}
+
+	/// The mode after this one in `ALL`, wrapping around
+	pub fn next(self) -> Self {
+		let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+		Self::ALL[(index + 1) % Self::ALL.len()]
+	}
+}
+
+/// Path the auto-pan takes over images larger than the view
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum PanMode {
+	/// Sweep back and forth along every overflowing axis
+	#[default]
+	PingPong,
+	/// Ease from the top edge to the bottom once, then stop
+	TopToBottomOnce,
+	/// Ease from the left edge to the right once, then stop
+	LeftToRightOnce,
+	/// Slow zoom drift between random framings, once per image
+	KenBurns {
+		/// Extra zoom at the close end of the drift, e.g. 0.15 for 115%
+		zoom_range: f32,
+	},
+}
+
+impl PanMode {
+	pub const KEN_BURNS: Self = Self::KenBurns { zoom_range: 0.15 };
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			PanMode::PingPong => "Ping-pong",
+			PanMode::TopToBottomOnce => "Top to bottom",
+			PanMode::LeftToRightOnce => "Left to right",
+			PanMode::KenBurns { .. } => "Ken Burns",
+		}
+	}
+}
+
+/// A line of the post info overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InfoField {
+	Id,
+	/// Sub-query that found the post, compound searches only
+	Query,
+	/// Name of the set being browsed, shown only then
+	Set,
+	Artist,
+	Copyright,
+	Character,
+	Score,
+	Favorites,
+	Rating,
+	Resolution,
+	/// Index of the post among the loaded results
+	Position,
+	/// API page the post was delivered on
+	Page,
+	/// Pending/flagged/deleted, shown only when set
+	Status,
+	/// How many pools the post is in, shown only when it is in any
+	Pools,
+	/// Size, format and version of the file shown, once loaded
+	File,
+}
+
+impl InfoField {
+	pub const ALL: [Self; 15] = [
+		Self::Id,
+		Self::Query,
+		Self::Set,
+		Self::Artist,
+		Self::Copyright,
+		Self::Character,
+		Self::Score,
+		Self::Favorites,
+		Self::Rating,
+		Self::Resolution,
+		Self::Position,
+		Self::Page,
+		Self::Status,
+		Self::Pools,
+		Self::File,
+	];
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			Self::Id => "Post ID",
+			Self::Query => "Query",
+			Self::Set => "Set",
+			Self::Artist => "Artist",
+			Self::Copyright => "Copyright",
+			Self::Character => "Character",
+			Self::Score => "Score",
+			Self::Favorites => "Favorites",
+			Self::Rating => "Rating",
+			Self::Resolution => "Resolution",
+			Self::Position => "Position",
+			Self::Page => "Page",
+			Self::Status => "Status",
+			Self::Pools => "Pools",
+			Self::File => "File",
+		}
+	}
+
+	/// Shown unless the user turns it off
+	fn on_by_default(&self) -> bool {
+		!matches!(
+			self,
+			Self::Character | Self::Rating | Self::Resolution | Self::Position | Self::File
+		)
+	}
+}
+
+/// A field of the info overlay and whether it is shown, kept in display order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InfoFieldSetting {
+	pub field: InfoField,
+	pub enabled: bool,
+}
+
+impl InfoFieldSetting {
+	pub fn defaults() -> Vec<Self> {
+		InfoField::ALL
+			.iter()
+			.map(|&field| Self {
+				field,
+				enabled: field.on_by_default(),
+			})
+			.collect()
+	}
+
+	/// Drop duplicates and append fields missing from an older saved list, hidden
+	pub fn normalize(mut fields: Vec<Self>) -> Vec<Self> {
+		let mut seen = Vec::new();
+		fields.retain(|setting| {
+			let first = !seen.contains(&setting.field);
+			seen.push(setting.field);
+			first
+		});
+		for field in InfoField::ALL {
+			if !seen.contains(&field) {
+				fields.push(Self {
+					field,
+					enabled: false,
+				});
+			}
+		}
+		fields
+	}
+}
+
+/// What to search for once the terms are accepted at startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StartupSearch {
+	/// Wait for the user to search
+	#[default]
+	Nothing,
+	/// The query and page left in the search box last session
+	LastSession,
+	/// The default query from the settings
+	DefaultQuery,
+}
+
+impl StartupSearch {
+	pub const ALL: [Self; 3] = [Self::Nothing, Self::LastSession, Self::DefaultQuery];
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			Self::Nothing => "Don't search",
+			Self::LastSession => "Last session's search",
+			Self::DefaultQuery => "Default query",
+		}
+	}
+}
+
+/// A search polled in the background for posts newer than the last one seen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchWatch {
+	pub query: String,
+	/// Newest post ID a poll found; None until the first poll sets the baseline
+	pub last_seen: Option<u64>,
+}
+
+/// How much of the info overlay to draw
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum InfoVerbosity {
+	#[default]
+	Full,
+	/// All enabled fields on a single line, without labels
+	Compact,
+	Hidden,
+}
+
+impl InfoVerbosity {
+	pub fn next(&self) -> Self {
+		match self {
+			Self::Full => Self::Compact,
+			Self::Compact => Self::Hidden,
+			Self::Hidden => Self::Full,
+		}
+	}
 }
 
 /// Breathing timer phases
@@ -34,10 +453,47 @@ pub enum BreathingPhase {
 	Idle,
 }
 
+/// Local order of the loaded results, applied without a new search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+	/// As the API returned them
+	#[default]
+	ApiOrder,
+	Score,
+	Favorites,
+	/// By post ID, which grows with upload time
+	Newest,
+	FileSize,
+}
+
+impl SortKey {
+	pub const ALL: [Self; 5] = [
+		Self::ApiOrder,
+		Self::Score,
+		Self::Favorites,
+		Self::Newest,
+		Self::FileSize,
+	];
+
+	pub fn label(&self) -> &'static str {
+		match self {
+			Self::ApiOrder => "API order",
+			Self::Score => "Score",
+			Self::Favorites => "Favorites",
+			Self::Newest => "Newest",
+			Self::FileSize => "File size",
+		}
+	}
+}
+
 /// Navigation direction
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NavDirection {
 	Next,
 	Prev,
 	Skip(i32),
+	/// To the loaded post at this 1-based position
+	ToPosition(usize),
+	/// To the first loaded post that came from this API page
+	ToPage(u32),
 }
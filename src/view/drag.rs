@@ -0,0 +1,141 @@
+use eframe::egui;
+
+/// Shortest gap between values sent while a slider is being dragged
+const SEND_INTERVAL: f64 = 0.25;
+
+/// A drag underway, kept in egui's memory between frames
+#[derive(Clone)]
+struct Drag<T> {
+	/// Where the widget is now
+	value: T,
+	/// What the owning component was last told
+	sent: T,
+	sent_at: f64,
+}
+
+/// A slider or drag value whose changes go out as events. While it's dragged the
+/// widget follows the pointer, but the value is only returned every `SEND_INTERVAL`
+/// and once more when the drag ends, instead of on every frame. Clicks, arrow keys
+/// and typed values are returned right away.
+///
+/// `salt` names the widget across frames; `add` draws it for the value given.
+pub(crate) fn throttled<T>(
+	ui: &mut egui::Ui,
+	salt: &str,
+	current: T,
+	add: impl FnOnce(&mut egui::Ui, &mut T) -> egui::Response,
+) -> Option<T>
+where
+	T: Copy + PartialEq + Send + Sync + 'static,
+{
+	let key = egui::Id::new(("throttled_drag", salt));
+	let drag = ui.data(|d| d.get_temp::<Drag<T>>(key));
+	let mut value = drag.as_ref().map_or(current, |drag| drag.value);
+	let response = add(ui, &mut value);
+	let now = ui.input(|i| i.time);
+
+	if !response.dragged() {
+		ui.data_mut(|d| d.remove::<Drag<T>>(key));
+		return match drag {
+			// The drag ended; settle on where it was let go
+			Some(drag) => (value != drag.sent).then_some(value),
+			None => response.changed().then_some(value),
+		};
+	}
+
+	let mut drag = drag.unwrap_or(Drag {
+		value,
+		sent: current,
+		sent_at: now,
+	});
+	drag.value = value;
+	let send = value != drag.sent && now - drag.sent_at >= SEND_INTERVAL;
+	if send {
+		drag.sent = value;
+		drag.sent_at = now;
+	}
+	ui.data_mut(|d| d.insert_temp(key, drag));
+	send.then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pointer(pos: egui::Pos2, pressed: bool) -> egui::Event {
+		egui::Event::PointerButton {
+			pos,
+			button: egui::PointerButton::Primary,
+			pressed,
+			modifiers: egui::Modifiers::NONE,
+		}
+	}
+
+	/// One 60 fps frame with a slider over 0..=100, returning its rect and what was sent
+	fn frame(
+		ctx: &egui::Context,
+		frame: u32,
+		current: f32,
+		events: Vec<egui::Event>,
+	) -> (egui::Rect, Option<f32>) {
+		let mut rect = egui::Rect::NOTHING;
+		let mut sent = None;
+		let input = egui::RawInput {
+			time: Some(f64::from(frame) / 60.0),
+			events,
+			..Default::default()
+		};
+		let _ = ctx.run(input, |ctx| {
+			egui::CentralPanel::default().show(ctx, |ui| {
+				sent = throttled(ui, "test", current, |ui, value| {
+					let response = ui.add(egui::Slider::new(value, 0.0..=100.0));
+					rect = response.rect;
+					response
+				});
+			});
+		});
+		(rect, sent)
+	}
+
+	#[test]
+	fn a_drag_sends_a_few_values_and_ends_on_the_last() {
+		let ctx = egui::Context::default();
+		let mut current = 0.0;
+		let (rect, _) = frame(&ctx, 0, current, Vec::new());
+		let at = |t: f32| egui::pos2(rect.left() + rect.width() * 0.6 * t, rect.center().y);
+
+		let mut sent = Vec::new();
+		let mut shown = Vec::new();
+		let mut frame_no = 1;
+		let mut step = |events: Vec<egui::Event>, current: &mut f32| {
+			let (_, value) = frame(&ctx, frame_no, *current, events);
+			frame_no += 1;
+			if let Some(value) = value {
+				sent.push(value);
+				*current = value;
+			}
+		};
+
+		step(vec![egui::Event::PointerMoved(at(0.0))], &mut current);
+		step(vec![pointer(at(0.0), true)], &mut current);
+		// One second of dragging, a frame at a time
+		for i in 1..=60 {
+			let pos = at(i as f32 / 60.0);
+			step(vec![egui::Event::PointerMoved(pos)], &mut current);
+			let key = egui::Id::new(("throttled_drag", "test"));
+			shown.push(ctx.data(|d| d.get_temp::<Drag<f32>>(key)).map(|d| d.value));
+		}
+		step(vec![pointer(at(1.0), false)], &mut current);
+
+		// About 4 values a second, not 60
+		assert!((2..=6).contains(&sent.len()), "sent {:?}", sent);
+		// The widget kept following the pointer between sends
+		let shown: Vec<f32> = shown.into_iter().flatten().collect();
+		assert!(shown.len() > 50);
+		assert!(shown.windows(2).all(|w| w[0] <= w[1]));
+		// Letting go sends where it ended
+		let last = *sent.last().unwrap();
+		assert_eq!(last, *shown.last().unwrap());
+		assert!(last > 50.0, "ended at {}", last);
+	}
+}
@@ -0,0 +1,267 @@
+use eframe::egui;
+
+/// Who the keyboard belongs to this frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyboardOwner {
+	/// Hotkeys run
+	Browsing,
+	/// A text field has focus, so keys are text
+	Typing,
+	/// Focus left a text field before the frame started, e.g. on Escape.
+	/// The key that ended typing isn't a hotkey too.
+	LeftField,
+}
+
+/// Text fields with keyboard focus, told apart from buttons and the like, and the
+/// Tab order between the view's own fields
+#[derive(Default)]
+pub(crate) struct TextFocus {
+	/// Fields registered so far this frame, in Tab order
+	order: Vec<egui::Id>,
+	/// Focus when the frame started, before any widget moved it
+	focused_at_start: Option<egui::Id>,
+	/// A text field had focus when the last frame ended
+	was_typing: bool,
+}
+
+impl TextFocus {
+	/// Whether `id` takes typed text. Drag values count while they're being typed into.
+	fn is_text_field(ctx: &egui::Context, id: egui::Id) -> bool {
+		egui::TextEdit::load_state(ctx, id).is_some()
+	}
+
+	/// Decide who gets the keyboard, before anything is drawn
+	pub fn begin_frame(&mut self, ctx: &egui::Context) -> KeyboardOwner {
+		self.order.clear();
+		self.focused_at_start = ctx.memory(|m| m.focused());
+		let typing = self
+			.focused_at_start
+			.is_some_and(|id| Self::is_text_field(ctx, id));
+		Self::owner(typing, self.was_typing)
+	}
+
+	fn owner(typing: bool, was_typing: bool) -> KeyboardOwner {
+		if typing {
+			KeyboardOwner::Typing
+		} else if was_typing {
+			KeyboardOwner::LeftField
+		} else {
+			KeyboardOwner::Browsing
+		}
+	}
+
+	/// Take focus away from a button or the like, so Space browses instead of clicking it
+	pub fn release(&self, ctx: &egui::Context) {
+		if let Some(id) = self.focused_at_start {
+			ctx.memory_mut(|m| m.surrender_focus(id));
+		}
+	}
+
+	/// Put a text field in the Tab order, after those registered before it this frame.
+	/// The field must be built with `lock_focus(true)` so egui leaves Tab to us.
+	pub fn register(&mut self, response: &egui::Response) {
+		self.order.push(response.id);
+	}
+
+	/// Move focus along the Tab order, once everything is drawn. `exclusive` is
+	/// true while a modal is up and the fields underneath can't be reached.
+	pub fn end_frame(&mut self, ctx: &egui::Context, exclusive: bool) {
+		let (tab, backwards) = ctx.input(|i| (i.key_pressed(egui::Key::Tab), i.modifiers.shift));
+		if tab
+			&& !exclusive
+			&& let Some(target) = Self::tab_target(&self.order, self.focused_at_start, backwards)
+		{
+			ctx.memory_mut(|m| m.request_focus(target));
+		}
+		let focused = ctx.memory(|m| m.focused());
+		self.was_typing = focused.is_some_and(|id| Self::is_text_field(ctx, id));
+	}
+
+	/// Where Tab goes from `from`: around the registered fields, or into them from
+	/// nothing. Fields outside the order are left to egui.
+	fn tab_target(order: &[egui::Id], from: Option<egui::Id>, backwards: bool) -> Option<egui::Id> {
+		let len = order.len();
+		if len == 0 {
+			return None;
+		}
+		let Some(from) = from else {
+			return if backwards {
+				order.last().copied()
+			} else {
+				order.first().copied()
+			};
+		};
+		let index = order.iter().position(|id| *id == from)?;
+		let next = if backwards {
+			(index + len - 1) % len
+		} else {
+			(index + 1) % len
+		};
+		Some(order[next])
+	}
+}
+
+/// A change of Shift, which opens and closes the island
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ShiftEdge {
+	Pressed,
+	Released,
+}
+
+/// Shift as of the last frame. Updated every frame, typing or not, so Shift held
+/// for a capital isn't taken for a fresh press once typing ends.
+#[derive(Default)]
+pub(crate) struct ShiftTracker {
+	held: bool,
+}
+
+impl ShiftTracker {
+	pub fn update(&mut self, held: bool) -> Option<ShiftEdge> {
+		let edge = match (self.held, held) {
+			(false, true) => Some(ShiftEdge::Pressed),
+			(true, false) => Some(ShiftEdge::Released),
+			_ => None,
+		};
+		self.held = held;
+		edge
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn key(key: egui::Key, modifiers: egui::Modifiers) -> egui::Event {
+		egui::Event::Key {
+			key,
+			physical_key: None,
+			pressed: true,
+			repeat: false,
+			modifiers,
+		}
+	}
+
+	/// Run one frame with a search field and a button, returning who had the
+	/// keyboard and the field's and button's responses
+	fn frame(
+		ctx: &egui::Context,
+		focus: &mut TextFocus,
+		text: &mut String,
+		events: Vec<egui::Event>,
+	) -> (KeyboardOwner, egui::Response, egui::Response) {
+		let mut owner = KeyboardOwner::Browsing;
+		let mut field = None;
+		let mut button = None;
+		let input = egui::RawInput {
+			events,
+			..Default::default()
+		};
+		let _ = ctx.run(input, |ctx| {
+			owner = focus.begin_frame(ctx);
+			if owner == KeyboardOwner::Browsing {
+				focus.release(ctx);
+			}
+			egui::CentralPanel::default().show(ctx, |ui| {
+				let response = ui.add(egui::TextEdit::singleline(text).lock_focus(true));
+				focus.register(&response);
+				field = Some(response);
+				button = Some(ui.button("Search"));
+			});
+			focus.end_frame(ctx, false);
+		});
+		(owner, field.unwrap(), button.unwrap())
+	}
+
+	#[test]
+	fn only_text_fields_keep_the_keyboard_from_hotkeys() {
+		let ctx = egui::Context::default();
+		let mut focus = TextFocus::default();
+		let mut text = String::new();
+		let (owner, field, button) = frame(&ctx, &mut focus, &mut text, Vec::new());
+		assert_eq!(owner, KeyboardOwner::Browsing);
+
+		// A focused button doesn't stop Space from browsing, and loses focus so it isn't clicked
+		button.request_focus();
+		let (owner, _, button) = frame(&ctx, &mut focus, &mut text, Vec::new());
+		assert_eq!(owner, KeyboardOwner::Browsing);
+		assert!(!button.has_focus());
+
+		// Typing "c" is text, not autoplay
+		field.request_focus();
+		frame(&ctx, &mut focus, &mut text, Vec::new());
+		let (owner, field, _) = frame(
+			&ctx,
+			&mut focus,
+			&mut text,
+			vec![egui::Event::Text("c".to_string())],
+		);
+		assert_eq!(owner, KeyboardOwner::Typing);
+		assert_eq!(text, "c");
+		assert!(field.has_focus());
+
+		// Escape drops focus without also being a hotkey, then browsing resumes
+		let (owner, field, _) = frame(
+			&ctx,
+			&mut focus,
+			&mut text,
+			vec![key(egui::Key::Escape, egui::Modifiers::NONE)],
+		);
+		assert_eq!(owner, KeyboardOwner::LeftField);
+		assert!(!field.has_focus());
+		let (owner, _, _) = frame(&ctx, &mut focus, &mut text, Vec::new());
+		assert_eq!(owner, KeyboardOwner::Browsing);
+
+		// Tab goes into the search field, and Enter leaves it for browsing right away
+		frame(
+			&ctx,
+			&mut focus,
+			&mut text,
+			vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+		);
+		let (owner, field, _) = frame(&ctx, &mut focus, &mut text, Vec::new());
+		assert_eq!(owner, KeyboardOwner::Typing);
+		assert!(field.has_focus());
+		let (owner, field, _) = frame(
+			&ctx,
+			&mut focus,
+			&mut text,
+			vec![key(egui::Key::Enter, egui::Modifiers::NONE)],
+		);
+		assert_eq!(owner, KeyboardOwner::Typing);
+		assert!(field.lost_focus());
+		let (owner, _, _) = frame(&ctx, &mut focus, &mut text, Vec::new());
+		assert_eq!(owner, KeyboardOwner::Browsing);
+	}
+
+	#[test]
+	fn tab_cycles_through_registered_fields() {
+		let [query, page, other] = ["query", "page", "other"].map(egui::Id::new);
+		let order = [query, page];
+		assert_eq!(TextFocus::tab_target(&order, None, false), Some(query));
+		assert_eq!(TextFocus::tab_target(&order, None, true), Some(page));
+		assert_eq!(
+			TextFocus::tab_target(&order, Some(query), false),
+			Some(page)
+		);
+		assert_eq!(
+			TextFocus::tab_target(&order, Some(page), false),
+			Some(query)
+		);
+		assert_eq!(TextFocus::tab_target(&order, Some(query), true), Some(page));
+		assert_eq!(TextFocus::tab_target(&order, Some(other), false), None);
+		assert_eq!(TextFocus::tab_target(&[], None, false), None);
+	}
+
+	#[test]
+	fn shift_held_while_typing_is_no_press_afterwards() {
+		let mut shift = ShiftTracker::default();
+		assert_eq!(shift.update(false), None);
+		// Shift for a capital while typing; the island ignores it
+		assert_eq!(shift.update(true), Some(ShiftEdge::Pressed));
+		assert_eq!(shift.update(true), None);
+		// Enter leaves the field with Shift still down: no press to open the island on
+		assert_eq!(shift.update(true), None);
+		assert_eq!(shift.update(false), Some(ShiftEdge::Released));
+		assert_eq!(shift.update(true), Some(ShiftEdge::Pressed));
+	}
+}
@@ -1,24 +1,68 @@
-use crate::reactor::{BreathingEvent, Event, SettingsEvent, SourceEvent, ViewEvent};
-use crate::types::{BreathingStyle, NavDirection};
+use crate::beat::SystemBeat;
+use crate::reactor::{BeatEvent, BreathingEvent, Event, SettingsEvent, SourceEvent, ViewEvent};
+use crate::types::{BreathingStyle, NavDirection, PanMode, Theme};
 use eframe::egui;
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
 use std::time::{Duration, Instant};
 
+/// The fade-out plays this long after closing; clicks on the image wait for it
+const CLOSE_COOLDOWN: Duration = Duration::from_millis(280);
+
+/// Fade/slide-in duration when the overlay opens
+const OPEN_ANIMATION: Duration = Duration::from_millis(120);
+
+/// Time for the selection highlight to travel between entries
+const HIGHLIGHT_TWEEN: Duration = Duration::from_millis(80);
+
+/// Distance the overlay slides up while opening, before UI scaling
+const SLIDE_DISTANCE: f32 = 24.0;
+
+/// Devices per row in the audio island, keeping long names on screen
+const AUDIO_DEVICES_PER_ROW: usize = 2;
+
+/// Recent searches longer than this are shortened in the search island
+const MAX_QUERY_LABEL_CHARS: usize = 40;
+
+/// Cubic ease-out for `t` in 0..=1
+fn ease_out(t: f32) -> f32 {
+	1.0 - (1.0 - t).powi(3)
+}
+
 /// Action to perform when an island entry is selected
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum IslandAction {
 	/// Fire an event (via factory function)
 	Emit(fn() -> Event),
+	/// Fire a prepared event, for entries of built islands
+	Send(Event),
 	/// Push a subcategory island onto the stack
 	Push(&'static Island),
+	/// Build an island from live state and push it
+	Open(DynamicIsland),
 	/// Pop back to the parent island
 	Pop,
+	/// Type into a text field inside the overlay until Enter or Escape
+	Input,
+	/// Search for this query, typed or picked from recent searches
+	Query(String),
+}
+
+/// Islands whose entries depend on state only known when they are opened
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DynamicIsland {
+	AudioDevices,
+	Search,
 }
 
 /// A single entry in an island grid
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct IslandEntry {
-	pub label: &'static str,
+	pub label: Cow<'static, str>,
 	pub action: IslandAction,
+	/// Drawn with a check mark, e.g. the option currently in use
+	pub marked: bool,
 }
 
 /// An island is a 2D grid of entries
@@ -26,33 +70,74 @@ pub struct Island {
 	pub rows: &'static [&'static [IslandEntry]],
 }
 
-impl Island {
+/// An island assembled at open time
+pub struct BuiltIsland {
+	pub kind: DynamicIsland,
+	pub rows: Vec<Vec<IslandEntry>>,
+}
+
+/// An island on the navigation stack
+#[derive(Clone)]
+pub enum IslandRef {
+	Static(&'static Island),
+	Built(Rc<BuiltIsland>),
+}
+
+impl IslandRef {
+	fn row(&self, index: usize) -> Option<&[IslandEntry]> {
+		match self {
+			IslandRef::Static(island) => island.rows.get(index).copied(),
+			IslandRef::Built(island) => island.rows.get(index).map(Vec::as_slice),
+		}
+	}
+
+	fn rows(&self) -> impl Iterator<Item = &[IslandEntry]> {
+		(0..self.row_count()).filter_map(|index| self.row(index))
+	}
+
+	/// Stable identity across frames, used to tell islands apart
+	fn key(&self) -> usize {
+		match self {
+			IslandRef::Static(island) => *island as *const Island as usize,
+			// Built islands are rebuilt while open, so their kind identifies them
+			IslandRef::Built(island) => island.kind as usize,
+		}
+	}
+
 	/// Get the entry at (row, col), if it exists
 	pub fn get(&self, row: usize, col: usize) -> Option<&IslandEntry> {
-		self.rows.get(row).and_then(|r| r.get(col))
+		self.row(row).and_then(|r| r.get(col))
 	}
 
 	/// Get the number of rows
 	pub fn row_count(&self) -> usize {
-		self.rows.len()
+		match self {
+			IslandRef::Static(island) => island.rows.len(),
+			IslandRef::Built(island) => island.rows.len(),
+		}
 	}
 
 	/// Get the number of columns in a specific row
 	pub fn col_count(&self, row: usize) -> usize {
-		self.rows.get(row).map(|r| r.len()).unwrap_or(0)
+		self.row(row).map(|r| r.len()).unwrap_or(0)
+	}
+
+	/// Total number of entries
+	pub fn entry_count(&self) -> usize {
+		self.rows().map(|r| r.len()).sum()
 	}
 
 	/// Convert a flat index to (row, col)
 	pub fn index_to_pos(&self, index: usize) -> (usize, usize) {
 		let mut remaining = index;
-		for (row_idx, row) in self.rows.iter().enumerate() {
+		for (row_idx, row) in self.rows().enumerate() {
 			if remaining < row.len() {
 				return (row_idx, remaining);
 			}
 			remaining -= row.len();
 		}
 		// Fallback to last valid position
-		let last_row = self.rows.len().saturating_sub(1);
+		let last_row = self.row_count().saturating_sub(1);
 		let last_col = self.col_count(last_row).saturating_sub(1);
 		(last_row, last_col)
 	}
@@ -60,7 +145,7 @@ impl Island {
 	/// Convert (row, col) to a flat index
 	pub fn pos_to_index(&self, row: usize, col: usize) -> usize {
 		let mut index = 0;
-		for (r, row_entries) in self.rows.iter().enumerate() {
+		for (r, row_entries) in self.rows().enumerate() {
 			if r == row {
 				return index + col.min(row_entries.len().saturating_sub(1));
 			}
@@ -73,7 +158,7 @@ impl Island {
 /// Mutable state for the island navigation system
 pub struct IslandCtx {
 	/// Stack of (island reference, selected index when we left it)
-	stack: Vec<(&'static Island, usize)>,
+	stack: Vec<(IslandRef, usize)>,
 	/// Currently selected index in the topmost island
 	pub selected: usize,
 	/// Whether the island overlay is currently active
@@ -82,8 +167,29 @@ pub struct IslandCtx {
 	pub row_widths: Vec<f32>,
 	/// Max row width from previous frame
 	pub max_row_width: f32,
-	/// Cooldown until which input should be ignored after island close
+	/// Until when the fade-out runs after closing
 	pub cooldown_until: Option<Instant>,
+	/// Keys down when the island closed, e.g. the Space that confirmed an entry.
+	/// They don't reach the rest of the view until they're released.
+	held_at_close: HashSet<egui::Key>,
+	/// Skip open/close fades and highlight tweening
+	pub reduce_motion: bool,
+	/// Colors the highlight and the marks on chosen entries
+	pub theme: Theme,
+	/// A fade or tween is running; the view asks for the next frame
+	pub animating: bool,
+	activated_at: Instant,
+	/// Island and selection on screen when closed, drawn during the fade-out
+	closing: Option<(IslandRef, usize)>,
+	/// (island key, selection) the highlight is currently heading to
+	highlight_key: Option<(usize, usize)>,
+	/// Where the highlight started its current tween
+	highlight_from: Option<egui::Rect>,
+	/// Where the highlight was drawn last frame
+	highlight_drawn: Option<egui::Rect>,
+	highlight_started: Instant,
+	/// Text of the input entry being typed into; grid navigation is off meanwhile
+	input: Option<String>,
 }
 
 impl Default for IslandCtx {
@@ -101,29 +207,87 @@ impl IslandCtx {
 			row_widths: Vec::new(),
 			max_row_width: 0.0,
 			cooldown_until: None,
+			held_at_close: HashSet::new(),
+			reduce_motion: false,
+			theme: Theme::default(),
+			animating: false,
+			activated_at: Instant::now(),
+			closing: None,
+			highlight_key: None,
+			highlight_from: None,
+			highlight_drawn: None,
+			highlight_started: Instant::now(),
+			input: None,
 		}
 	}
 
 	/// Get the currently displayed island (topmost on stack)
-	pub fn current_island(&self) -> Option<&'static Island> {
-		self.stack.last().map(|(island, _)| *island)
+	pub fn current_island(&self) -> Option<IslandRef> {
+		self.stack.last().map(|(island, _)| island.clone())
+	}
+
+	/// Kind of the topmost island if it was built at open time
+	pub fn current_dynamic(&self) -> Option<DynamicIsland> {
+		match self.stack.last() {
+			Some((IslandRef::Built(island), _)) => Some(island.kind),
+			_ => None,
+		}
 	}
 
 	/// Activate the island overlay with the given root island and default selection
 	pub fn activate(&mut self, root: &'static Island, default_selected: usize) {
 		self.stack.clear();
-		self.stack.push((root, 0));
+		self.stack.push((IslandRef::Static(root), 0));
 		self.selected = default_selected;
 		self.active = true;
 		self.cooldown_until = None;
+		self.held_at_close.clear();
+		self.activated_at = Instant::now();
+		self.closing = None;
+		self.highlight_key = None;
+		self.highlight_drawn = None;
+		self.input = None;
 	}
 
 	/// Deactivate the island overlay entirely
-	pub fn deactivate(&mut self) {
+	pub fn deactivate(&mut self, ctx: &egui::Context) {
+		self.held_at_close = ctx.input(|i| i.keys_down.clone());
+		self.closing = self.current_island().map(|island| (island, self.selected));
+		self.input = None;
 		self.stack.clear();
 		self.selected = 0;
 		self.active = false;
-		self.cooldown_until = Some(Instant::now() + Duration::from_millis(280));
+		self.cooldown_until = Some(Instant::now() + CLOSE_COOLDOWN);
+	}
+
+	/// Hide the keys held since closing from this frame's input, forgetting each
+	/// once it's released. Other keys go through right away.
+	pub fn swallow_held_keys(&mut self, ctx: &egui::Context) {
+		if self.held_at_close.is_empty() {
+			return;
+		}
+		let held = &mut self.held_at_close;
+		ctx.input_mut(|i| {
+			if !i.focused {
+				// Releases aren't seen while the window is in the background
+				held.clear();
+				return;
+			}
+			for event in &i.events {
+				if let egui::Event::Key {
+					key,
+					pressed: false,
+					..
+				} = event
+				{
+					held.remove(key);
+				}
+			}
+			i.keys_down.retain(|key| !held.contains(key));
+			i.events.retain(
+				|event| !matches!(event, egui::Event::Key { key, pressed: true, .. } if held.contains(key)),
+			);
+		});
 	}
 
 	/// Check if we're in the cooldown period after closing
@@ -133,15 +297,48 @@ impl IslandCtx {
 			.unwrap_or(false)
 	}
 
+	/// Start typing into the text field of an input entry
+	pub fn begin_input(&mut self) {
+		self.input = Some(String::new());
+	}
+
+	/// Whether keys currently go to the text field rather than the grid
+	pub fn is_typing(&self) -> bool {
+		self.input.is_some()
+	}
+
 	/// Push a subcategory island onto the stack
 	pub fn push(&mut self, island: &'static Island) {
-		let prev_selected = self.selected;
-		if let Some((current, _)) = self.stack.last_mut() {
-			// Update the stored selection for current island
-			*self.stack.last_mut().unwrap() = (*current, prev_selected);
+		self.push_ref(IslandRef::Static(island), 0);
+	}
+
+	/// Push an island built from live state, starting at `selected` (clamped)
+	pub fn push_built(&mut self, island: BuiltIsland, selected: usize) {
+		let island = IslandRef::Built(Rc::new(island));
+		let selected = selected.min(island.entry_count().saturating_sub(1));
+		self.push_ref(island, selected);
+	}
+
+	/// Swap the topmost built island for a fresh build of the same kind,
+	/// clamping the selection if entries went away
+	pub fn replace_built(&mut self, island: BuiltIsland) {
+		if self.current_dynamic() != Some(island.kind) {
+			return;
+		}
+		let island = IslandRef::Built(Rc::new(island));
+		self.selected = self.selected.min(island.entry_count().saturating_sub(1));
+		if let Some(top) = self.stack.last_mut() {
+			top.0 = island;
 		}
-		self.stack.push((island, 0));
-		self.selected = 0;
+	}
+
+	fn push_ref(&mut self, island: IslandRef, selected: usize) {
+		// Update the stored selection for current island
+		if let Some((_, prev_selected)) = self.stack.last_mut() {
+			*prev_selected = self.selected;
+		}
+		self.stack.push((island, selected));
+		self.selected = selected;
 	}
 
 	/// Pop back to the parent island, returns false if already at root
@@ -177,10 +374,10 @@ impl IslandCtx {
 	}
 
 	/// Get the currently selected entry
-	pub fn selected_entry(&self) -> Option<&'static IslandEntry> {
+	pub fn selected_entry(&self) -> Option<IslandEntry> {
 		let island = self.current_island()?;
 		let (row, col) = island.index_to_pos(self.selected);
-		island.get(row, col)
+		island.get(row, col).cloned()
 	}
 }
 
@@ -195,25 +392,131 @@ pub enum GridDirection {
 /// Helper to create an emit entry
 const fn emit(label: &'static str, factory: fn() -> Event) -> IslandEntry {
 	IslandEntry {
-		label,
+		label: Cow::Borrowed(label),
 		action: IslandAction::Emit(factory),
+		marked: false,
 	}
 }
 
 /// Helper to create a push entry
 const fn push(label: &'static str, island: &'static Island) -> IslandEntry {
 	IslandEntry {
-		label,
+		label: Cow::Borrowed(label),
 		action: IslandAction::Push(island),
+		marked: false,
+	}
+}
+
+/// Helper to create an entry opening a built island
+const fn open(label: &'static str, kind: DynamicIsland) -> IslandEntry {
+	IslandEntry {
+		label: Cow::Borrowed(label),
+		action: IslandAction::Open(kind),
+		marked: false,
+	}
+}
+
+/// Helper to create an entry that opens a text field
+const fn input(label: &'static str) -> IslandEntry {
+	IslandEntry {
+		label: Cow::Borrowed(label),
+		action: IslandAction::Input,
+		marked: false,
 	}
 }
 
 /// Back entry for subcategories
 const BACK_ENTRY: IslandEntry = IslandEntry {
-	label: "Back",
+	label: Cow::Borrowed("Back"),
 	action: IslandAction::Pop,
+	marked: false,
 };
 
+/// Build a dynamic island from current state.
+/// Returns the island and the index of its marked entry, if any.
+pub fn build_island(
+	kind: DynamicIsland,
+	beat: &SystemBeat,
+	recent_searches: &VecDeque<String>,
+) -> (BuiltIsland, usize) {
+	match kind {
+		DynamicIsland::AudioDevices => build_audio_island(beat),
+		DynamicIsland::Search => build_search_island(recent_searches),
+	}
+}
+
+/// A text field and the local favorites, then one entry per recent search, newest first
+fn build_search_island(recent_searches: &VecDeque<String>) -> (BuiltIsland, usize) {
+	let mut rows = vec![
+		vec![input("Type a query…")],
+		vec![IslandEntry {
+			label: Cow::Borrowed("Local favorites"),
+			action: IslandAction::Query(crate::gateway::query::LOCAL_FAVORITES.to_string()),
+			marked: false,
+		}],
+	];
+	for query in recent_searches {
+		let label = if query.chars().count() > MAX_QUERY_LABEL_CHARS {
+			let short: String = query.chars().take(MAX_QUERY_LABEL_CHARS - 1).collect();
+			format!("{}…", short)
+		} else {
+			query.clone()
+		};
+		rows.push(vec![IslandEntry {
+			label: Cow::Owned(label),
+			action: IslandAction::Query(query.clone()),
+			marked: false,
+		}]);
+	}
+	rows.push(vec![BACK_ENTRY]);
+	let island = BuiltIsland {
+		kind: DynamicIsland::Search,
+		rows,
+	};
+	(island, 0)
+}
+
+/// Default device, then loopback/monitor devices, then all other inputs
+fn build_audio_island(beat: &SystemBeat) -> (BuiltIsland, usize) {
+	let device_entry = |label: &str, name: Option<String>| IslandEntry {
+		label: Cow::Owned(label.to_string()),
+		marked: *beat.selected_device() == name,
+		action: IslandAction::Send(Event::Beat(BeatEvent::SetDevice { name })),
+	};
+	let is_loopback = |name: &str| {
+		let name = name.to_lowercase();
+		name.contains("monitor") || name.contains("loopback") || name.contains("stereo mix")
+	};
+
+	let mut rows = vec![vec![device_entry("Default", None)]];
+	let (loopback, inputs): (Vec<&String>, Vec<&String>) = beat
+		.device_names()
+		.iter()
+		.partition(|name| is_loopback(name));
+	for group in [loopback, inputs] {
+		for chunk in group.chunks(AUDIO_DEVICES_PER_ROW) {
+			rows.push(
+				chunk
+					.iter()
+					.map(|name| device_entry(name, Some(name.to_string())))
+					.collect(),
+			);
+		}
+	}
+	rows.push(vec![BACK_ENTRY]);
+
+	let marked = rows
+		.iter()
+		.flatten()
+		.position(|entry| entry.marked)
+		.unwrap_or(0);
+	let island = BuiltIsland {
+		kind: DynamicIsland::AudioDevices,
+		rows,
+	};
+	(island, marked)
+}
+
 pub static AUTOPLAY_ISLAND: Island = Island {
 	rows: &[
 		&[emit("Toggle", || {
@@ -257,6 +560,59 @@ pub static BREATHING_ISLAND: Island = Island {
 				Event::Breathing(BreathingEvent::SetIdleMultiplier { value: 0.67 })
 			}),
 		],
+		&[
+			emit("Start cycle", || {
+				Event::Breathing(BreathingEvent::TriggerCycle)
+			}),
+			emit("Skip phase", || Event::Breathing(BreathingEvent::SkipPhase)),
+		],
+		&[BACK_ENTRY],
+	],
+};
+
+pub static PAN_ISLAND: Island = Island {
+	rows: &[
+		&[
+			emit("Ping-pong", || {
+				Event::View(ViewEvent::SetPanMode {
+					mode: PanMode::PingPong,
+				})
+			}),
+			emit("Ken Burns", || {
+				Event::View(ViewEvent::SetPanMode {
+					mode: PanMode::KEN_BURNS,
+				})
+			}),
+		],
+		&[
+			emit("Top to bottom", || {
+				Event::View(ViewEvent::SetPanMode {
+					mode: PanMode::TopToBottomOnce,
+				})
+			}),
+			emit("Left to right", || {
+				Event::View(ViewEvent::SetPanMode {
+					mode: PanMode::LeftToRightOnce,
+				})
+			}),
+		],
+		&[emit("Reading mode", || {
+			Event::Settings(SettingsEvent::ToggleReadingMode)
+		})],
+		&[BACK_ENTRY],
+	],
+};
+
+pub static COMFORT_ISLAND: Island = Island {
+	rows: &[
+		&[
+			emit("Reduce motion", || {
+				Event::Settings(SettingsEvent::ToggleReduceMotion)
+			}),
+			emit("High contrast", || {
+				Event::Settings(SettingsEvent::ToggleHighContrast)
+			}),
+		],
 		&[BACK_ENTRY],
 	],
 };
@@ -268,6 +624,10 @@ pub static ROOT_ISLAND: Island = Island {
 			push("Autoplay", &AUTOPLAY_ISLAND),
 			push("Breathing", &BREATHING_ISLAND),
 			emit("View", || Event::View(ViewEvent::ToggleImageFillMode)),
+			push("Pan", &PAN_ISLAND),
+			emit("Mirror", || Event::View(ViewEvent::ToggleMirror)),
+			open("Audio", DynamicIsland::AudioDevices),
+			push("Comfort", &COMFORT_ISLAND),
 		],
 		&[
 			emit("Previous image", || {
@@ -285,6 +645,15 @@ pub static ROOT_ISLAND: Island = Island {
 				Event::Source(SourceEvent::Navigate(NavDirection::Skip(10)))
 			}),
 		],
+		&[
+			emit("More like this", || {
+				Event::Source(SourceEvent::MoreLikeThis)
+			}),
+			emit("Previous search", || {
+				Event::Source(SourceEvent::PreviousSearch)
+			}),
+			open("Search…", DynamicIsland::Search),
+		],
 	],
 };
 
@@ -301,21 +670,55 @@ impl<'a> IslandWidget<'a> {
 	/// Show the island overlay. Returns the action if one was confirmed.
 	pub fn show(&mut self, egui_ctx: &egui::Context) -> Option<IslandAction> {
 		if !self.ctx.active {
+			self.render_closing(egui_ctx);
 			return None;
 		}
 
 		let island = self.ctx.current_island()?;
 
-		// Handle input first
-		let action = self.handle_input(egui_ctx, island);
+		// Handle input first; the text field handles its own keys
+		let action = if self.ctx.is_typing() {
+			self.handle_text_input(egui_ctx)
+		} else {
+			self.handle_input(egui_ctx)
+		};
 
 		// Render overlay and update width cache
-		self.render(egui_ctx, island);
+		let appear = if self.ctx.reduce_motion {
+			1.0
+		} else {
+			let t = self.ctx.activated_at.elapsed().as_secs_f32() / OPEN_ANIMATION.as_secs_f32();
+			ease_out(t.min(1.0))
+		};
+		if appear < 1.0 {
+			self.ctx.animating = true;
+		}
+		let selected = self.ctx.selected;
+		self.render(egui_ctx, &island, selected, appear);
 
 		action
 	}
 
-	fn handle_input(&mut self, ctx: &egui::Context, _island: &Island) -> Option<IslandAction> {
+	/// Fade out the last shown island while the close cooldown runs. Display only.
+	fn render_closing(&mut self, ctx: &egui::Context) {
+		let Some((island, selected)) = self.ctx.closing.clone() else {
+			return;
+		};
+		let remaining = self
+			.ctx
+			.cooldown_until
+			.map(|t| t.saturating_duration_since(Instant::now()))
+			.unwrap_or_default();
+		if self.ctx.reduce_motion || remaining.is_zero() {
+			self.ctx.closing = None;
+			return;
+		}
+		let fade = remaining.as_secs_f32() / CLOSE_COOLDOWN.as_secs_f32();
+		self.render(ctx, &island, selected, fade);
+		self.ctx.animating = true;
+	}
+
+	fn handle_input(&mut self, ctx: &egui::Context) -> Option<IslandAction> {
 		let mut confirmed_action = None;
 
 		ctx.input(|i| {
@@ -344,39 +747,76 @@ impl<'a> IslandWidget<'a> {
 		confirmed_action
 	}
 
-	fn render(&mut self, ctx: &egui::Context, island: &Island) {
+	/// Enter runs the typed query, Escape goes back to the grid
+	fn handle_text_input(&mut self, ctx: &egui::Context) -> Option<IslandAction> {
+		let (enter, escape) = ctx.input(|i| {
+			(
+				i.key_pressed(egui::Key::Enter),
+				i.key_pressed(egui::Key::Escape),
+			)
+		});
+		if escape {
+			self.ctx.input = None;
+			return None;
+		}
+		if !enter {
+			return None;
+		}
+		let query = self.ctx.input.take()?.trim().to_string();
+		(!query.is_empty()).then_some(IslandAction::Query(query))
+	}
+
+	/// `visibility` in 0..=1 drives both opacity and the slide offset
+	fn render(
+		&mut self,
+		ctx: &egui::Context,
+		island: &IslandRef,
+		selected: usize,
+		visibility: f32,
+	) {
 		let screen_rect = ctx.screen_rect();
+		let scale = (screen_rect.height() / 800.0).max(0.5);
 
 		let offset_x = screen_rect.width() * 0.15;
-		let offset_y = -screen_rect.height() * 0.2;
+		let offset_y = -screen_rect.height() * 0.2 + (1.0 - visibility) * SLIDE_DISTANCE * scale;
 
 		let ctx_ptr = self.ctx as *mut IslandCtx;
 
 		egui::Area::new(egui::Id::new("island_overlay"))
 			.anchor(egui::Align2::LEFT_BOTTOM, [offset_x, offset_y])
 			.show(ctx, |ui| {
+				ui.multiply_opacity(visibility);
 				egui::Frame::none().show(ui, |ui| {
 					// SAFETY: We're in single-threaded egui context
 					unsafe {
-						Self::render_grid_impl(&mut *ctx_ptr, ui, island);
+						Self::render_grid_impl(&mut *ctx_ptr, ui, island, selected);
 					}
 				});
 			});
 	}
 
-	fn render_grid_impl(island_ctx: &mut IslandCtx, ui: &mut egui::Ui, island: &Island) {
+	fn render_grid_impl(
+		island_ctx: &mut IslandCtx,
+		ui: &mut egui::Ui,
+		island: &IslandRef,
+		selected: usize,
+	) {
 		let screen_height = ui.ctx().screen_rect().height();
 		let scale = (screen_height / 800.0).max(0.5);
 
-		let selected_pos = island.index_to_pos(island_ctx.selected);
+		let selected_pos = island.index_to_pos(selected);
 		let cached_widths = &island_ctx.row_widths;
 		let max_width = island_ctx.max_row_width;
 
 		ui.spacing_mut().item_spacing = egui::vec2(8.0 * scale, 8.0 * scale);
 
-		let mut new_widths = Vec::with_capacity(island.rows.len());
+		// Reserve a slot behind the entries; the highlight is filled in once we know where it goes
+		let highlight_slot = ui.painter().add(egui::Shape::Noop);
+		let mut selected_rect = None;
+
+		let mut new_widths = Vec::with_capacity(island.row_count());
 
-		for (row_idx, row) in island.rows.iter().enumerate() {
+		for (row_idx, row) in island.rows().enumerate() {
 			// Get cached width for this row (0 on first frame)
 			let row_width = cached_widths.get(row_idx).copied().unwrap_or(0.0);
 			// Calculate padding to center this row
@@ -389,7 +829,16 @@ impl<'a> IslandWidget<'a> {
 				}
 				for (col_idx, entry) in row.iter().enumerate() {
 					let is_selected = (row_idx, col_idx) == selected_pos;
-					Self::render_entry_static(ui, entry, is_selected, scale);
+					let rect = Self::render_entry_static(
+						ui,
+						entry,
+						is_selected,
+						island_ctx.theme.accent,
+						scale,
+					);
+					if is_selected {
+						selected_rect = Some(rect);
+					}
 				}
 			});
 
@@ -401,9 +850,69 @@ impl<'a> IslandWidget<'a> {
 		let new_max = new_widths.iter().cloned().fold(0.0f32, f32::max);
 		island_ctx.row_widths = new_widths;
 		island_ctx.max_row_width = new_max;
+
+		if let Some(text) = &mut island_ctx.input {
+			let font_size = (16.0 * scale).max(12.0);
+			let response = ui.add(
+				egui::TextEdit::singleline(text)
+					.hint_text("Enter to search, Esc to go back")
+					.font(egui::FontId::proportional(font_size))
+					.desired_width(new_max.max(240.0 * scale)),
+			);
+			// Keep the keyboard here until Enter or Escape, even after a stray click
+			response.request_focus();
+		}
+
+		if let Some(target) = selected_rect {
+			let rect = Self::tween_highlight(island_ctx, island, selected, target);
+			ui.painter().set(
+				highlight_slot,
+				egui::epaint::RectShape::new(
+					rect,
+					6.0 * scale,
+					island_ctx.theme.accent,
+					egui::Stroke::new(2.0 * scale, island_ctx.theme.accent_stroke()),
+				),
+			);
+		}
 	}
 
-	fn render_entry_static(ui: &mut egui::Ui, entry: &IslandEntry, is_selected: bool, scale: f32) {
+	/// Slide the highlight from where it was drawn towards `target` when the selection changes
+	fn tween_highlight(
+		island_ctx: &mut IslandCtx,
+		island: &IslandRef,
+		selected: usize,
+		target: egui::Rect,
+	) -> egui::Rect {
+		let key = (island.key(), selected);
+		if island_ctx.highlight_key != Some(key) {
+			island_ctx.highlight_key = Some(key);
+			island_ctx.highlight_from = island_ctx.highlight_drawn;
+			island_ctx.highlight_started = Instant::now();
+		}
+
+		let t =
+			island_ctx.highlight_started.elapsed().as_secs_f32() / HIGHLIGHT_TWEEN.as_secs_f32();
+		let rect = match island_ctx.highlight_from {
+			Some(from) if !island_ctx.reduce_motion && t < 1.0 => {
+				island_ctx.animating = true;
+				from.lerp_towards(&target, ease_out(t))
+			}
+			_ => target,
+		};
+		island_ctx.highlight_drawn = Some(rect);
+		rect
+	}
+
+	/// Draws one entry and returns its rect. The selected entry's background is
+	/// painted separately by the highlight so it can move between entries.
+	fn render_entry_static(
+		ui: &mut egui::Ui,
+		entry: &IslandEntry,
+		is_selected: bool,
+		accent: egui::Color32,
+		scale: f32,
+	) -> egui::Rect {
 		let font_size = (16.0 * scale).max(12.0);
 		let h_margin = 16.0 * scale;
 		let v_margin = 10.0 * scale;
@@ -416,9 +925,15 @@ impl<'a> IslandWidget<'a> {
 
 		let (bg_color, text_color, stroke_color) = if is_selected {
 			(
-				egui::Color32::from_rgb(70, 130, 200),
+				egui::Color32::TRANSPARENT,
 				egui::Color32::WHITE,
-				egui::Color32::from_rgb(100, 170, 255),
+				egui::Color32::TRANSPARENT,
+			)
+		} else if entry.marked {
+			(
+				egui::Color32::from_rgb(50, 50, 60),
+				egui::Color32::from_gray(230),
+				accent,
 			)
 		} else {
 			(
@@ -428,7 +943,11 @@ impl<'a> IslandWidget<'a> {
 			)
 		};
 
-		let label = entry.label.to_string();
+		let label = if entry.marked {
+			format!("✔ {}", entry.label)
+		} else {
+			entry.label.to_string()
+		};
 
 		egui::Frame::none()
 			.fill(bg_color)
@@ -442,6 +961,81 @@ impl<'a> IslandWidget<'a> {
 						.size(font_size)
 						.strong(),
 				);
-			});
+			})
+			.response
+			.rect
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn space(pressed: bool, repeat: bool) -> egui::Event {
+		egui::Event::Key {
+			key: egui::Key::Space,
+			physical_key: None,
+			pressed,
+			repeat,
+			modifiers: egui::Modifiers::NONE,
+		}
+	}
+
+	/// One frame as the view handles it: close the island if asked, then keep
+	/// back the keys held from it. Returns whether Space would navigate.
+	fn frame(
+		egui_ctx: &egui::Context,
+		island: &mut IslandCtx,
+		close: bool,
+		events: Vec<egui::Event>,
+	) -> bool {
+		let input = egui::RawInput {
+			events,
+			..Default::default()
+		};
+		let mut navigates = false;
+		let _ = egui_ctx.run(input, |ctx| {
+			if close {
+				island.deactivate(ctx);
+			}
+			if !island.active {
+				island.swallow_held_keys(ctx);
+				navigates = ctx.input(|i| i.key_pressed(egui::Key::Space));
+			}
+		});
+		navigates
+	}
+
+	#[test]
+	fn space_that_confirmed_an_entry_does_not_also_navigate() {
+		let ctx = egui::Context::default();
+		let mut island = IslandCtx::new();
+		island.activate(&ROOT_ISLAND, 4);
+
+		// Space confirms while Shift is held, then Shift is let go before Space
+		assert!(!frame(&ctx, &mut island, false, vec![space(true, false)]));
+		assert!(!frame(&ctx, &mut island, true, Vec::new()));
+		assert!(!frame(&ctx, &mut island, false, vec![space(true, true)]));
+		assert!(!frame(&ctx, &mut island, false, vec![space(false, false)]));
+		// A fresh press after letting go navigates, cooldown or not
+		assert!(island.in_cooldown());
+		assert!(frame(&ctx, &mut island, false, vec![space(true, false)]));
+	}
+
+	#[test]
+	fn space_pressed_right_after_closing_navigates() {
+		let ctx = egui::Context::default();
+		let mut island = IslandCtx::new();
+		island.activate(&ROOT_ISLAND, 4);
+
+		// Shift is let go first, Space follows on the next frame
+		assert!(!frame(&ctx, &mut island, true, Vec::new()));
+		assert!(island.in_cooldown());
+		assert!(frame(&ctx, &mut island, false, vec![space(true, false)]));
+
+		// Both in one frame: there's no telling which came first, so it's held back
+		island.activate(&ROOT_ISLAND, 4);
+		assert!(!frame(&ctx, &mut island, false, vec![space(false, false)]));
+		assert!(!frame(&ctx, &mut island, true, vec![space(true, false)]));
 	}
 }
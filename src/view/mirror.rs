@@ -0,0 +1,165 @@
+use crate::breathing::BreathingOverlay;
+use crate::types::LoadedMedia;
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::notes;
+use super::{OverlayStyle, PAN_FRAME, ViewManager};
+use crate::reactor::Repaints;
+
+/// What the mirror window draws, refreshed by the main window every frame
+struct MirrorFrame {
+	/// Same handle as the main view, so the texture is shared rather than copied
+	texture: Option<egui::TextureHandle>,
+	/// When the current image appeared; drives the auto-pan
+	shown_since: Instant,
+	pan_cycle: f32,
+	breathing: Option<BreathingOverlay>,
+	style: OverlayStyle,
+	/// The mirror window itself was closed, e.g. with Alt+F4
+	close_requested: bool,
+}
+
+/// Borderless second window showing only the current image and the breathing overlay.
+/// It can be dragged anywhere with the mouse; double-click toggles fullscreen.
+pub struct Mirror {
+	open: bool,
+	frame: Arc<Mutex<MirrorFrame>>,
+}
+
+impl Mirror {
+	fn viewport_id() -> egui::ViewportId {
+		egui::ViewportId::from_hash_of("mirror_viewport")
+	}
+
+	pub fn toggle(&mut self) {
+		self.open = !self.open;
+		log::info!(
+			"Mirror window {}",
+			if self.open { "opened" } else { "closed" }
+		);
+	}
+
+	/// Hand the current frame to the mirror and keep its viewport alive.
+	/// Once this stops being called, egui closes the window.
+	pub fn show(
+		&mut self,
+		ctx: &egui::Context,
+		media: Option<&LoadedMedia>,
+		shown_since: Instant,
+		pan_cycle: f32,
+		breathing: &BreathingOverlay,
+		style: OverlayStyle,
+	) {
+		if !self.open {
+			return;
+		}
+		{
+			let mut frame = self.frame.lock().unwrap();
+			if frame.close_requested {
+				frame.close_requested = false;
+				frame.texture = None;
+				self.open = false;
+				return;
+			}
+			let texture = media.map(|LoadedMedia::Image { texture }| texture.clone());
+			if texture.as_ref().map(|t| t.id()) != frame.texture.as_ref().map(|t| t.id()) {
+				ctx.request_repaint_of(Self::viewport_id());
+			}
+			frame.texture = texture;
+			frame.shown_since = shown_since;
+			frame.pan_cycle = pan_cycle;
+			frame.breathing = breathing.is_visible().then(|| breathing.clone());
+			frame.style = style;
+		}
+
+		let frame = self.frame.clone();
+		ctx.show_viewport_deferred(
+			Self::viewport_id(),
+			egui::ViewportBuilder::default()
+				.with_title("Sodglumate Mirror")
+				.with_decorations(false)
+				.with_inner_size([960.0, 540.0]),
+			move |ctx, class| {
+				let mut frame = frame.lock().unwrap();
+				if ctx.input(|i| i.viewport().close_requested()) {
+					frame.close_requested = true;
+					return;
+				}
+				if class == egui::ViewportClass::Embedded {
+					// No multi-window support; show it inside the main window instead
+					egui::Window::new("Mirror")
+						.default_size([480.0, 270.0])
+						.show(ctx, |ui| Self::render_image(ui, &frame, false));
+				} else {
+					egui::CentralPanel::default()
+						.frame(egui::Frame::none().fill(egui::Color32::BLACK))
+						.show(ctx, |ui| Self::render_image(ui, &frame, true));
+					if let Some(breathing) = &frame.breathing {
+						// Its own viewport, so it asks for its own frames
+						let mut repaints = Repaints::default();
+						ViewManager::render_breathing(ctx, breathing, frame.style, &mut repaints);
+						repaints.flush(ctx);
+					}
+				}
+			},
+		);
+	}
+
+	/// Cover the window with the image, sweeping across whatever doesn't fit
+	fn render_image(ui: &mut egui::Ui, frame: &MirrorFrame, native: bool) {
+		let rect = ui.max_rect();
+		let response = ui.interact(
+			rect,
+			ui.id().with("mirror_drag"),
+			egui::Sense::click_and_drag(),
+		);
+		if native {
+			if response.drag_started() {
+				ui.ctx().send_viewport_cmd(egui::ViewportCommand::StartDrag);
+			}
+			if response.double_clicked() {
+				let fullscreen = ui.ctx().input(|i| i.viewport().fullscreen.unwrap_or(false));
+				ui.ctx()
+					.send_viewport_cmd(egui::ViewportCommand::Fullscreen(!fullscreen));
+			}
+		}
+
+		let Some(texture) = &frame.texture else {
+			return;
+		};
+		let img_size = texture.size_vec2();
+		let display_size = img_size * notes::cover_scale(img_size, rect.size());
+
+		// Same sweep as the main view's auto-pan, sized to the mirror window
+		let elapsed = frame.shown_since.elapsed().as_secs_f32();
+		let cycle = (elapsed * 2.0 * std::f32::consts::PI) / frame.pan_cycle;
+		let factor = (1.0 - cycle.cos()) * 0.5;
+		let overflow = (display_size - rect.size()).max(egui::Vec2::ZERO);
+		let image_rect = egui::Rect::from_min_size(rect.min - overflow * factor, display_size);
+
+		let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+		ui.painter_at(rect)
+			.image(texture.id(), image_rect, uv, egui::Color32::WHITE);
+		if overflow != egui::Vec2::ZERO {
+			ui.ctx().request_repaint_after(PAN_FRAME);
+		}
+	}
+}
+
+impl Default for Mirror {
+	fn default() -> Self {
+		Self {
+			open: false,
+			frame: Arc::new(Mutex::new(MirrorFrame {
+				texture: None,
+				shown_since: Instant::now(),
+				pan_cycle: 10.0,
+				breathing: None,
+				style: OverlayStyle::default(),
+				close_requested: false,
+			})),
+		}
+	}
+}
@@ -1,53 +1,336 @@
-use crate::beat::SystemBeat;
-use crate::breathing::BreathingOverlay;
-use crate::browser::ContentBrowser;
+use crate::api::{ErrorCategory, Note, Post};
+use crate::beat::{LockState, SystemBeat};
+use crate::breathing::{BreathingOverlay, IDLE_MULTIPLIER_RANGE, MAX_IMAGE_ZOOM};
+use crate::browser::{ContentBrowser, PREFETCH_AHEAD};
 use crate::coach::CoachValue;
-use crate::gateway::BooruGateway;
-use crate::media::MediaCache;
+use crate::gateway::query::QueryWarning;
+use crate::gateway::{
+	BooruGateway, MAX_FANOUT_PAGES, MAX_PAGE, MIN_WATCH_INTERVAL_MINS, PoolName, ResultEstimate,
+	WATCH_POLL_LIMIT,
+};
+use crate::media::report::CacheCategory;
+use crate::media::timing::LoadStats;
+use crate::media::{CacheState, MediaCache, Readiness};
 use crate::reactor::{
-	BeatEvent, BreathingEvent, ComponentResponse, Event, GatewayEvent, MediaEvent, SettingsEvent,
-	SourceEvent, ViewEvent,
+	BeatEvent, BreathingEvent, BrowserEvent, ComponentResponse, Event, FavoritesEvent,
+	GatewayEvent, MediaEvent, ModalKind, PollCounts, RepaintCause, RepaintCounts, Repaints,
+	SettingsEvent, SourceEvent, ViewEvent,
 };
 use crate::settings::SettingsManager;
-use crate::types::{BreathingPhase, BreathingStyle, ImageFillMode, LoadedMedia, NavDirection};
+use crate::types::{
+	BreathingPalette, BreathingPhase, BreathingStyle, CreditOverlay, ImageFillMode, InfoField,
+	InfoVerbosity, LoadedMedia, NavDirection, OverlayAnchor, PanMode, SortKey, StartupSearch,
+	Theme,
+};
 use eframe::egui::{self, ScrollArea};
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use rand::Rng;
+use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant};
 
+mod drag;
+mod focus;
 pub mod island;
+pub mod mirror;
+pub mod notes;
+mod overlay;
+mod reading;
+mod reveal;
+mod tags;
 pub mod text_utils;
 
-use island::{IslandAction, IslandCtx, IslandWidget, ROOT_ISLAND};
+use focus::{KeyboardOwner, ShiftEdge, ShiftTracker, TextFocus};
+use island::{DynamicIsland, IslandAction, IslandCtx, IslandWidget, ROOT_ISLAND, build_island};
+use mirror::Mirror;
+use notes::ImageTransform;
+use overlay::{OverlayFrame, Overlays};
+use reading::{PageTurn, Reading};
+use reveal::Reveal;
+use tags::TagCategory;
+
+/// Upper bound for the classic breathing pulse text, in points
+const MAX_PULSE_FONT_SIZE: f32 = 220.0;
+
+/// Short-lived message, optionally with a button like Undo that sends an event
+struct Toast {
+	message: String,
+	shown_at: Instant,
+	action: Option<(&'static str, Event)>,
+}
+
+/// A setting stepped from the keyboard, flashed in the middle of the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Readout {
+	Interval,
+	IdleMultiplier,
+}
+
+/// Pools of a post listed by the pool picker, kept while the slideshow moves on
+struct PoolPicker {
+	pools: Vec<u64>,
+	selected: usize,
+}
+
+/// One-key entry for the first nine pools in the pool picker
+const POOL_PICKER_KEYS: [egui::Key; 9] = [
+	egui::Key::Num1,
+	egui::Key::Num2,
+	egui::Key::Num3,
+	egui::Key::Num4,
+	egui::Key::Num5,
+	egui::Key::Num6,
+	egui::Key::Num7,
+	egui::Key::Num8,
+	egui::Key::Num9,
+];
+
+/// Keys that pan or zoom the image, counted as user activity for auto-pause
+const ACTIVITY_KEYS: &[egui::Key] = &[
+	egui::Key::W,
+	egui::Key::A,
+	egui::Key::S,
+	egui::Key::D,
+	egui::Key::ArrowUp,
+	egui::Key::ArrowDown,
+	egui::Key::ArrowLeft,
+	egui::Key::ArrowRight,
+	egui::Key::E,
+	egui::Key::Q,
+];
+
+/// Post held in the right half of the screen for comparison, zoomed and panned
+/// separately from the image being browsed
+struct PinnedPane {
+	post: Post,
+	zoom: f32,
+	pan_offset: egui::Vec2,
+}
+
+/// Controls on the top panel's row after the search field, in the order they're laid
+/// out. Those that don't fit go into the ⚙ menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TopGroup {
+	Results,
+	Slideshow,
+	Breathing,
+	Pan,
+	Display,
+	Fill,
+	Menus,
+	Audio,
+	Coach,
+}
+
+impl TopGroup {
+	const ALL: [TopGroup; 9] = [
+		TopGroup::Results,
+		TopGroup::Slideshow,
+		TopGroup::Breathing,
+		TopGroup::Pan,
+		TopGroup::Display,
+		TopGroup::Fill,
+		TopGroup::Menus,
+		TopGroup::Audio,
+		TopGroup::Coach,
+	];
+
+	/// The order groups are given room on the row; the rest are collapsed
+	const BY_PRIORITY: [TopGroup; 9] = [
+		TopGroup::Results,
+		TopGroup::Slideshow,
+		TopGroup::Menus,
+		TopGroup::Fill,
+		TopGroup::Display,
+		TopGroup::Breathing,
+		TopGroup::Audio,
+		TopGroup::Pan,
+		TopGroup::Coach,
+	];
+}
+
+/// Width egui's default separator takes in a horizontal layout
+const SEPARATOR_WIDTH: f32 = 6.0;
+
+/// Posts whose pan/zoom position is remembered for revisits
+const MAX_VIEW_MEMORY: usize = 50;
+
+/// Searches offered for one-key re-runs in the island's search launcher
+const MAX_RECENT_SEARCHES: usize = 5;
+
+/// Search box text of a session started with `--sfw`, in place of last session's
+const SAFE_MODE_QUERY: &str = "scenery order:score";
+
+/// Posts the filmstrip shows, by offset from the current one
+const FILMSTRIP_RANGE: std::ops::RangeInclusive<isize> = -2..=5;
+
+/// Where the user left an image, restored when they come back to it
+#[derive(Clone, Copy)]
+struct ViewMemory {
+	scroll_offset: egui::Vec2,
+	zoom: f32,
+	pan_offset: egui::Vec2,
+	user_panned: bool,
+}
+
+/// Minimum time between activity reports while input continues
+const ACTIVITY_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Share of the central panel's width on each side that navigates when clicked
+const EDGE_ZONE_FRACTION: f32 = 0.2;
+
+/// A glide after a drag stops below this speed, in points per second
+const PAN_GLIDE_MIN_SPEED: f32 = 20.0;
+
+/// How quickly a glide after a drag slows down, per second
+const PAN_GLIDE_FRICTION: f32 = 6.0;
+
+/// A held pan key reaches full speed after this long
+const KEY_PAN_RAMP: Duration = Duration::from_millis(200);
+
+/// Share of the full speed a pan key starts at, so a tap still moves
+const KEY_PAN_START: f32 = 0.3;
+
+/// Frame interval for slow, continuous motion like the auto-pan (~30 fps)
+pub(crate) const PAN_FRAME: Duration = Duration::from_millis(33);
+
+/// Frame interval for the short beat and breathing pulses (~60 fps)
+pub(crate) const PULSE_FRAME: Duration = Duration::from_millis(16);
+
+/// Outline width multiplier for high-contrast overlay text
+const HIGH_CONTRAST_STROKE: f32 = 2.0;
+
+/// Reduced motion never sweeps the auto-pan faster than once in this many seconds
+const CALM_PAN_SECS: f32 = 30.0;
+
+/// How overlays are drawn, from the reduced-motion and high-contrast settings.
+/// Copied into the mirror, which draws the breathing overlay on its own.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct OverlayStyle {
+	pub reduce_motion: bool,
+	pub high_contrast: bool,
+}
+
+impl OverlayStyle {
+	fn from_settings(settings: &SettingsManager) -> Self {
+		Self {
+			reduce_motion: settings.reduce_motion(),
+			high_contrast: settings.high_contrast(),
+		}
+	}
+}
+
+/// Start and end framing of a Ken Burns drift, picked at random per image.
+/// Positions are fractions of the overflow on each axis.
+#[derive(Clone, Copy)]
+struct KenBurnsPath {
+	from: egui::Vec2,
+	to: egui::Vec2,
+	zoom_in: bool,
+}
+
+impl KenBurnsPath {
+	fn random() -> Self {
+		let mut rng = rand::rng();
+		let mut point = || egui::vec2(rng.random_range(0.0..=1.0), rng.random_range(0.0..=1.0));
+		let (from, to) = (point(), point());
+		Self {
+			from,
+			to,
+			zoom_in: rand::rng().random_bool(0.5),
+		}
+	}
+}
 
-/// Content for modal popups
-#[derive(Clone)]
-pub enum ModalContent {
-	None,
-	Hello,
-	BreathingDisclaimer,
+/// Where the auto-pan is at a given moment
+struct AutoPanFrame {
+	/// Scroll offset as a fraction of the overflow on each axis
+	offset: egui::Vec2,
+	/// Extra zoom on top of the cover scale
+	zoom: f32,
+	/// False once a one-shot pan has reached its end
+	moving: bool,
 }
 
 pub struct ViewManager {
 	// Display state
 	image_load_time: Instant,
+	/// Start of the auto-pan, moved forward by time spent in the background
+	pan_started: Instant,
+	/// The window lost focus or was minimized at this point; auto-pan holds meanwhile
+	backgrounded_since: Option<Instant>,
 	user_has_panned: bool,
+	/// Auto-pan stays off on new images until this point after user activity
+	auto_pan_hold_until: Option<Instant>,
+	last_activity_reported: Option<Instant>,
 	pub(crate) auto_pan_cycle_duration: f32,
+	pub(crate) pan_mode: PanMode,
+	/// Length of a one-shot pan; follows the slideshow delay while auto-play is on
+	once_pan_duration: f32,
+	/// Arrow/WASD panning speed in points per second
+	pub(crate) key_pan_speed: f32,
+	/// When the pan keys started being held, for the ramp up
+	key_pan_since: Option<Instant>,
+	ken_burns: KenBurnsPath,
 
 	// UI state
 	pub(crate) search_query: String,
+	/// Submitted queries, newest first, without repeats
+	recent_searches: VecDeque<String>,
 	pub(crate) search_page_input: String,
+	query_warnings: Vec<QueryWarning>,
 	error_msg: Option<String>,
+	/// Set while searches fail to reach the server
+	offline: bool,
+	/// (loaded, total) pages of a search still fetching pages up front
+	page_progress: Option<(u32, u32)>,
+	/// (cache key, message) of a current image that failed for good
+	load_failure: Option<(String, String)>,
+	/// Cache key of a current image that failed for now and will be tried again
+	pub(crate) load_retrying: Option<String>,
+	toast: Option<Toast>,
+	/// Setting last stepped with [ or ], and when
+	readout: Option<(Readout, Instant)>,
+	/// Tag picker opened with X, listing the current post's tags
+	tag_picker_open: bool,
+	tag_picker_filter: String,
+	/// Go-to popup opened with Ctrl+G, and what's typed into it
+	goto_open: bool,
+	goto_input: String,
+	/// Opened with P on a post that belongs to pools
+	pool_picker: Option<PoolPicker>,
+	/// Whether settings were last told a modal is open
+	modal_reported: bool,
+	/// Cache inspector toggled with F12, listing what the media cache holds
+	cache_inspector_open: bool,
+	/// Load timing overlay toggled with F3, each loading stage's median and p90
+	load_stats_open: bool,
 	user_is_adult: bool,
 	user_accepted_tos: bool,
+	/// Read from the settings every frame. Started with `--sfw`, safe mode can't be
+	/// turned off, so the welcome modal skips the age check; a saved setting can be.
+	safe_mode_locked: bool,
 
 	// Modal state
-	modal: ModalContent,
+	/// Front one is on screen, the rest wait their turn
+	modals: VecDeque<ModalKind>,
 	breathing_disclaimer_accepted: bool,
 	breathing_disclaimer_checked: bool,
 
+	/// Reduced motion and high contrast, read from the settings every frame
+	overlay_style: OverlayStyle,
+
 	// Island navigation state
 	island_ctx: IslandCtx,
-	prev_shift_held: bool,
+	shift: ShiftTracker,
+
+	/// Tells typing from browsing, and Tabs between the top panel's fields
+	text_focus: TextFocus,
+
+	/// Everything drawn above the image, in layer order
+	overlays: Overlays,
+
+	/// Image zoom of the immersive breathing style this frame, 1 for none
+	breath_scale: f32,
 
 	// Beat debug state
 	beat_intensity: f32,
@@ -58,7 +341,10 @@ pub struct ViewManager {
 	pub(crate) beat_pulse_enabled: bool,
 	pub(crate) beat_pulse_scale: f32,
 
+	/// Preferred fill, saved with the settings
 	pub(crate) image_fill_mode: ImageFillMode,
+	/// Fill picked with Z for one post, dropped once another is shown
+	fill_override: Option<(u64, ImageFillMode)>,
 
 	pub(crate) coach_enabled: bool,
 	pub(crate) coach_model: Option<String>,
@@ -66,6 +352,13 @@ pub struct ViewManager {
 	pub(crate) coach_logs: Vec<String>,
 	pub(crate) coach_state: HashMap<String, CoachValue>,
 
+	/// Component polls the reactor skipped last second, shown in debug builds
+	pub(crate) poll_skips: PollCounts,
+	/// Frames asked for this frame, requested from egui at the end of the tick
+	pub(crate) repaints: Repaints,
+	/// Why frames asked for more, per second, for the debug stats
+	pub(crate) repaint_counts: RepaintCounts,
+
 	// Gallery animation state
 	gallery_anim_start_offset: f32,
 	gallery_anim_offset: f32,
@@ -75,6 +368,53 @@ pub struct ViewManager {
 	// Zoom and pan state
 	user_zoom: f32,
 	user_pan_offset: egui::Vec2,
+	/// Compare mode; browsing continues in the left half
+	pinned: Option<PinnedPane>,
+	/// Top panel groups as wide as they were last drawn, by `TopGroup`; 0 until then
+	top_group_widths: [f32; TopGroup::ALL.len()],
+	top_gear_width: f32,
+	/// Scroll position of the cover-mode scroll area as of last frame
+	cover_scroll_offset: egui::Vec2,
+	/// The image is being dragged with the mouse
+	dragging_image: bool,
+	/// Glide left over from the last drag, in points per second
+	pan_velocity: egui::Vec2,
+
+	// Per-post position memory, least recently shown first
+	pub(crate) remember_view_positions: bool,
+	view_memory: IndexMap<u64, ViewMemory>,
+	/// Post whose image is on screen, i.e. whose position `user_*` describes
+	displayed_post_id: Option<u64>,
+	restore_view_pending: bool,
+	pending_scroll_offset: Option<egui::Vec2>,
+	/// Size of a sample just replaced by an original of another shape; the next
+	/// frame moves the pan so the same part of the image stays in view
+	replaced_sample: Option<egui::Vec2>,
+
+	// Mouse-only browsing; the wheel steps through images instead of scrolling
+	pub(crate) wheel_navigation: bool,
+	/// Scroll not yet turned into a whole notch
+	wheel_accum: f32,
+
+	/// Borderless copy of the current image for a second monitor
+	mirror: Mirror,
+	/// Fullscreen change for the main window, sent on the next frame
+	pending_fullscreen: Option<bool>,
+	/// Bottom bar with gateway, cache and loading stats
+	pub(crate) show_status_bar: bool,
+	/// Thumbnails of the posts around the current one along the bottom
+	pub(crate) show_filmstrip: bool,
+	/// Blur-until-reveal for cautious browsing
+	pub(crate) reveal: Reveal,
+	/// Mirrors the setting each frame, with where the page is scrolled
+	reading: Reading,
+	/// The slideshow is running, so covered images reveal themselves after a while
+	auto_reveal: bool,
+
+	// Translation notes state
+	show_notes: bool,
+	notes_post_id: Option<u64>,
+	notes: Vec<Note>,
 }
 
 impl ViewManager {
@@ -82,55 +422,138 @@ impl ViewManager {
 		search_query: String,
 		search_page_input: String,
 		auto_pan_cycle_duration: f32,
+		pan_mode: PanMode,
 		beat_pulse_enabled: bool,
 		beat_pulse_scale: f32,
 		image_fill_mode: ImageFillMode,
 		coach_enabled: bool,
 		coach_model: Option<String>,
 		coach_preset: Option<String>,
+		remember_view_positions: bool,
+		wheel_navigation: bool,
+		show_status_bar: bool,
 	) -> Self {
 		Self {
 			image_load_time: Instant::now(),
+			pan_started: Instant::now(),
+			backgrounded_since: None,
 			user_has_panned: false,
+			auto_pan_hold_until: None,
+			last_activity_reported: None,
 			auto_pan_cycle_duration,
+			pan_mode,
+			once_pan_duration: auto_pan_cycle_duration,
+			key_pan_speed: 1200.0,
+			key_pan_since: None,
+			ken_burns: KenBurnsPath::random(),
 			search_query,
+			recent_searches: VecDeque::new(),
 			search_page_input,
+			query_warnings: Vec::new(),
 			error_msg: None,
+			offline: false,
+			page_progress: None,
+			load_failure: None,
+			load_retrying: None,
+			toast: None,
+			readout: None,
+			tag_picker_open: false,
+			tag_picker_filter: String::new(),
+			goto_open: false,
+			goto_input: String::new(),
+			pool_picker: None,
+			modal_reported: false,
+			cache_inspector_open: false,
+			load_stats_open: false,
 			user_is_adult: false,
 			user_accepted_tos: false,
-			modal: ModalContent::Hello,
+			safe_mode_locked: false,
+			modals: VecDeque::from([ModalKind::Hello]),
 			breathing_disclaimer_accepted: false,
 			breathing_disclaimer_checked: false,
+			overlay_style: OverlayStyle::default(),
 			island_ctx: IslandCtx::new(),
-			prev_shift_held: false,
+			shift: ShiftTracker::default(),
+			text_focus: TextFocus::default(),
+			overlays: Overlays::builtin(),
 			beat_intensity: 0.0,
+			breath_scale: 1.0,
 			last_beat_time: Instant::now(),
 			last_beat_scale: 1.0,
 			beat_pulse_enabled,
 			beat_pulse_scale,
 			image_fill_mode,
+			fill_override: None,
 			coach_enabled,
 			coach_model,
 			coach_preset,
 			coach_logs: Vec::new(),
 			coach_state: HashMap::new(),
+			poll_skips: PollCounts::default(),
+			repaints: Repaints::default(),
+			repaint_counts: RepaintCounts::default(),
 			gallery_anim_start_offset: 0.0,
 			gallery_anim_offset: 0.0,
 			gallery_anim_time: 0.0,
 			last_gallery_index: 0,
 			user_zoom: 1.0,
 			user_pan_offset: egui::Vec2::ZERO,
+			pinned: None,
+			top_group_widths: [0.0; TopGroup::ALL.len()],
+			top_gear_width: 0.0,
+			cover_scroll_offset: egui::Vec2::ZERO,
+			dragging_image: false,
+			pan_velocity: egui::Vec2::ZERO,
+			remember_view_positions,
+			view_memory: IndexMap::new(),
+			displayed_post_id: None,
+			restore_view_pending: false,
+			pending_scroll_offset: None,
+			replaced_sample: None,
+			wheel_navigation,
+			wheel_accum: 0.0,
+			mirror: Mirror::default(),
+			pending_fullscreen: None,
+			show_status_bar,
+			show_filmstrip: false,
+			reveal: Reveal::new(false, Duration::from_secs(3)),
+			reading: Reading::default(),
+			auto_reveal: false,
+			show_notes: false,
+			notes_post_id: None,
+			notes: Vec::new(),
 		}
 	}
 
 	pub fn handle(&mut self, event: &Event) -> ComponentResponse {
 		match event {
 			Event::View(ViewEvent::MediaReady) => {
+				self.remember_view();
+				self.restore_view_pending = true;
 				self.image_load_time = Instant::now();
-				self.user_has_panned = false;
+				// In the background, the pan starts once the window is back
+				self.pan_started = self.backgrounded_since.unwrap_or(self.image_load_time);
+				self.ken_burns = KenBurnsPath::random();
+				// Don't start panning under a user who is still looking around
+				self.user_has_panned = self
+					.auto_pan_hold_until
+					.is_some_and(|until| Instant::now() < until);
 				self.user_zoom = 1.0;
 				self.user_pan_offset = egui::Vec2::ZERO;
+				self.pan_velocity = egui::Vec2::ZERO;
 				self.error_msg = None;
+				self.replaced_sample = None;
+				self.load_retrying = None;
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::SampleUpgraded { mismatch }) => {
+				self.replaced_sample = mismatch.map(|mismatch| mismatch.sample_size());
+				self.load_retrying = None;
+				ComponentResponse::none()
+			}
+			Event::Beat(BeatEvent::SilenceDetected) => {
+				self.beat_intensity = 0.0;
+				self.last_beat_scale = 0.0;
 				ComponentResponse::none()
 			}
 			Event::View(ViewEvent::BeatPulse { scale }) => {
@@ -139,23 +562,143 @@ impl ViewManager {
 				self.last_beat_time = Instant::now();
 				ComponentResponse::none()
 			}
-			Event::Gateway(GatewayEvent::SearchError { message }) => {
+			Event::Gateway(GatewayEvent::SearchError { message, category }) => {
+				let message = describe_search_error(message, *category);
 				self.error_msg = Some(message.clone());
+				self.show_toast(message);
+				ComponentResponse::none()
+			}
+			Event::Gateway(GatewayEvent::Throttled { retry_after, .. }) => {
+				self.show_toast(format!(
+					"e621 is busy, retrying in {}s",
+					retry_after.as_secs_f32().ceil()
+				));
+				ComponentResponse::none()
+			}
+			Event::Gateway(GatewayEvent::PageProgress { loaded, total }) => {
+				self.page_progress = (loaded < total).then_some((*loaded, *total));
+				ComponentResponse::none()
+			}
+			Event::Gateway(GatewayEvent::ConnectionState { online }) => {
+				self.offline = !online;
+				if *online {
+					self.error_msg = None;
+					self.show_toast("Back online".to_string());
+				}
 				ComponentResponse::none()
 			}
-			Event::Media(MediaEvent::LoadError { error }) => {
-				self.error_msg = Some(format!("Failed to load: {}", error));
+			Event::Media(MediaEvent::LoadError {
+				cache_key,
+				error,
+				permanent: true,
+				current: true,
+			}) => {
+				self.load_failure = Some((cache_key.clone(), format!("Failed to load: {}", error)));
+				self.load_retrying = None;
+				ComponentResponse::none()
+			}
+			Event::Media(MediaEvent::LoadError {
+				cache_key,
+				permanent: false,
+				current: true,
+				..
+			}) => {
+				self.load_retrying = Some(cache_key.clone());
 				ComponentResponse::none()
 			}
 			Event::View(ViewEvent::SetImageFillMode { mode }) => {
 				self.image_fill_mode = *mode;
+				self.fill_override = None;
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::SetPanMode { mode }) => {
+				// The island offers Ken Burns with the default zoom; keep a tuned one
+				if !matches!(
+					(self.pan_mode, mode),
+					(PanMode::KenBurns { .. }, PanMode::KenBurns { .. })
+				) {
+					self.pan_mode = *mode;
+				}
+				self.show_toast(format!("Pan: {}", mode.label()));
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::ToggleMirror) => {
+				self.mirror.toggle();
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::SetFullscreen { fullscreen }) => {
+				self.pending_fullscreen = Some(*fullscreen);
 				ComponentResponse::none()
 			}
 			Event::View(ViewEvent::ToggleImageFillMode) => {
-				match self.image_fill_mode {
-					ImageFillMode::Cover => self.image_fill_mode = ImageFillMode::Fit,
-					ImageFillMode::Fit => self.image_fill_mode = ImageFillMode::FitToGallery,
-					ImageFillMode::FitToGallery => self.image_fill_mode = ImageFillMode::Cover,
+				self.image_fill_mode = self.image_fill_mode.next();
+				self.fill_override = None;
+				self.show_toast(format!("Fill: {}", self.image_fill_mode.label()));
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::ShowToast { message }) => {
+				self.show_toast(message.clone());
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::ShowModal { kind }) => {
+				self.show_modal(kind.clone());
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::DismissModal) => {
+				self.modals.pop_front();
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::RequestBreathingToggle) => {
+				// Breathing can't be on before the disclaimer was accepted
+				if self.breathing_disclaimer_accepted {
+					return ComponentResponse::emit(Event::Breathing(BreathingEvent::Toggle));
+				}
+				self.show_modal(ModalKind::BreathingDisclaimer);
+				ComponentResponse::none()
+			}
+			Event::Browser(BrowserEvent::EndOfResults) => {
+				self.show_toast("End of results (F5 checks for more)".to_string());
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::ShowUndoToast { message, undo }) => {
+				self.toast = Some(Toast {
+					message: message.clone(),
+					shown_at: Instant::now(),
+					action: Some(("Undo", (**undo).clone())),
+				});
+				ComponentResponse::none()
+			}
+			Event::Gateway(GatewayEvent::NewPostsAvailable { query, count }) => {
+				let shown = if *count >= WATCH_POLL_LIMIT as usize {
+					format!("{}+", count)
+				} else {
+					count.to_string()
+				};
+				let plural = if *count == 1 { "" } else { "s" };
+				self.toast = Some(Toast {
+					message: format!("{} new post{} for '{}'", shown, plural, query),
+					shown_at: Instant::now(),
+					action: Some((
+						"View now",
+						Event::Source(SourceEvent::OpenWatch {
+							query: query.clone(),
+						}),
+					)),
+				});
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::SetSearchQuery { query }) => {
+				self.search_query = query.clone();
+				self.search_page_input = "1".to_string();
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::QueryWarnings { warnings }) => {
+				self.query_warnings = warnings.clone();
+				ComponentResponse::none()
+			}
+			Event::View(ViewEvent::NotesReceived { post_id, notes }) => {
+				if self.notes_post_id == Some(*post_id) {
+					self.notes = notes.clone();
 				}
 				ComponentResponse::none()
 			}
@@ -175,20 +718,74 @@ impl ViewManager {
 		beat: &SystemBeat,
 	) -> Vec<Event> {
 		let mut events = Vec::new();
-		let modal_active = !matches!(self.modal, ModalContent::None);
+		let modal_active = !self.modals.is_empty();
+		if modal_active != self.modal_reported {
+			self.modal_reported = modal_active;
+			events.push(Event::View(if modal_active {
+				ViewEvent::ModalOpened
+			} else {
+				ViewEvent::ModalClosed
+			}));
+		}
+		self.track_background(ctx);
+		if let Some(fullscreen) = self.pending_fullscreen.take() {
+			ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(fullscreen));
+		}
+		self.auto_reveal = settings.auto_play();
+		self.overlay_style = OverlayStyle::from_settings(settings);
+		self.island_ctx.reduce_motion = settings.reduce_motion();
+		self.island_ctx.theme = settings.theme();
+		self.reading.enabled = settings.reading_mode();
+		self.reading.follow(browser.current_post().map(|p| p.id));
+		self.safe_mode_locked = settings.safe_mode_locked();
+		self.once_pan_duration = if settings.auto_play() {
+			settings.auto_play_delay().as_secs_f32()
+		} else {
+			self.auto_pan_cycle_duration
+		};
+		if settings.reduce_motion() {
+			self.once_pan_duration = self.once_pan_duration.max(CALM_PAN_SECS);
+		}
 
 		// Handle input only when no modal is active
+		let keyboard = self.text_focus.begin_frame(ctx);
+		let shift_edge = self.shift.update(ctx.input(|i| i.modifiers.shift));
 		if !modal_active {
-			let is_typing = ctx.memory(|m| m.focused().is_some());
-			if !is_typing {
-				self.handle_keyboard_input(ctx, media, &mut events);
+			match keyboard {
+				KeyboardOwner::Browsing => {
+					self.text_focus.release(ctx);
+					self.handle_keyboard_input(
+						ctx,
+						browser,
+						breathing,
+						settings,
+						shift_edge,
+						&mut events,
+					);
+				}
+				// The island's own text field handles Enter and Escape there
+				_ if self.island_ctx.is_typing() => {
+					self.handle_keyboard_input(
+						ctx,
+						browser,
+						breathing,
+						settings,
+						shift_edge,
+						&mut events,
+					);
+				}
+				KeyboardOwner::Typing | KeyboardOwner::LeftField => {}
 			}
 		}
 
+		self.request_notes(browser, &mut events);
+
 		// Top panel
 		self.render_top_panel(
 			ctx,
 			gateway,
+			browser,
+			media,
 			settings,
 			breathing,
 			beat,
@@ -196,29 +793,53 @@ impl ViewManager {
 			!modal_active,
 		);
 
-		// Central panel
-		self.render_central_panel(ctx, browser, media, gateway, !modal_active);
-
-		// Overlays
-		match breathing.style() {
-			BreathingStyle::Classic => {
-				self.render_breathing_overlay(ctx, breathing);
-				self.render_breathing_pulse(ctx, breathing);
-			}
-			BreathingStyle::Immersive => {
-				self.render_immersive_breathing_overlay(ctx, breathing);
-			}
+		// Laid out before the central panel so the image doesn't run under it
+		if self.show_status_bar {
+			Self::render_status_bar(
+				ctx,
+				gateway,
+				browser,
+				media,
+				self.poll_skips,
+				self.repaint_counts,
+			);
 		}
-		self.render_info_overlay(ctx, browser);
-
-		// Beat debug dot
-		self.render_beat_debug(ctx, beat);
 
-		// Island navigation overlay
-		self.render_island_overlay(ctx, &mut events);
+		// Central panel
+		let before = events.len();
+		self.breath_scale = if settings.reduce_motion() {
+			1.0
+		} else {
+			breathing.image_scale()
+		};
+		self.render_central_panel(ctx, browser, media, gateway, &mut events, !modal_active);
+		// Clicking or wheeling through images counts as activity, like Space does
+		if events[before..]
+			.iter()
+			.any(|e| matches!(e, Event::Source(SourceEvent::Navigate(_))))
+		{
+			self.note_user_activity(settings, &mut events);
+		}
 
-		// Modal popup (on top of everything)
-		self.render_modal(ctx, &mut events);
+		// The image pulse reads it next frame, whether or not the beat dot is drawn
+		self.decay_beat_intensity();
+
+		// Overlays, taken out while they borrow the rest of the view
+		let mut overlays = std::mem::take(&mut self.overlays);
+		events.extend(overlays.render(
+			self,
+			&mut OverlayFrame {
+				ctx,
+				gateway,
+				browser,
+				media,
+				breathing,
+				settings,
+				beat,
+			},
+		));
+		self.overlays = overlays;
+		self.text_focus.end_frame(ctx, modal_active);
 
 		events
 	}
@@ -226,1168 +847,4224 @@ impl ViewManager {
 	fn handle_keyboard_input(
 		&mut self,
 		ctx: &egui::Context,
-		_media: &mut MediaCache,
+		browser: &ContentBrowser,
+		breathing: &BreathingOverlay,
+		settings: &SettingsManager,
+		shift_edge: Option<ShiftEdge>,
 		events: &mut Vec<Event>,
 	) {
-		// Detect shift press/release edges for island activation
+		// Shift press/release edges open and close the island
 		let shift_held = ctx.input(|i| i.modifiers.shift);
-		if shift_held && !self.prev_shift_held {
-			self.island_ctx.activate(&ROOT_ISLAND, 3);
-		} else if !shift_held && self.prev_shift_held {
-			self.island_ctx.deactivate();
+		if self.island_ctx.is_typing() {
+			// Shift only makes capitals while typing; releasing it keeps the overlay open
+		} else if shift_edge == Some(ShiftEdge::Pressed) {
+			// Start on "Previous image"
+			self.island_ctx.activate(&ROOT_ISLAND, 4);
+		} else if shift_edge == Some(ShiftEdge::Released) && self.island_ctx.active {
+			self.island_ctx.deactivate(ctx);
+		} else if !shift_held
+			&& self.island_ctx.active
+			&& ctx.input(|i| i.key_pressed(egui::Key::Escape))
+		{
+			// Shift was let go while typing, so it can't close the overlay
+			self.island_ctx.deactivate(ctx);
+		}
+
+		// Shift+M: back to the results before "More like this", closing the island
+		if shift_held && !self.island_ctx.is_typing() && ctx.input(|i| i.key_pressed(egui::Key::M))
+		{
+			self.island_ctx.deactivate(ctx);
+			events.push(Event::Source(SourceEvent::PreviousSearch));
+			return;
+		}
+
+		// Shift+[ and Shift+]: idle multiplier. Shift opened the island, so this comes first.
+		let bracket = ctx.input(|i| {
+			if i.key_pressed(egui::Key::OpenBracket) {
+				Some(-1.0)
+			} else if i.key_pressed(egui::Key::CloseBracket) {
+				Some(1.0)
+			} else {
+				None
+			}
+		});
+		if shift_held
+			&& !self.island_ctx.is_typing()
+			&& let Some(sign) = bracket
+		{
+			self.island_ctx.deactivate(ctx);
+			events.push(Event::Breathing(BreathingEvent::AdjustIdleMultiplier {
+				delta: sign * 0.1,
+			}));
+			self.readout = Some((Readout::IdleMultiplier, Instant::now()));
+			return;
+		}
+
+		// Island overlay consumes all input while open. Once it's closed, only the
+		// keys still held from it are kept back, so its confirming Space doesn't
+		// navigate as well.
+		if self.island_ctx.active {
+			return;
 		}
-		self.prev_shift_held = shift_held;
+		self.island_ctx.swallow_held_keys(ctx);
 
-		// Island overlay consumes all input when active or just closed
-		if self.island_ctx.active || self.island_ctx.in_cooldown() {
+		// So does the pool picker
+		if self.pool_picker.is_some() {
+			self.handle_pool_picker_keys(ctx, events);
 			return;
 		}
 
 		let space_pressed = ctx.input(|i| i.key_pressed(egui::Key::Space));
 		let ctrl_pressed = ctx.input(|i| i.modifiers.ctrl);
 		let c_pressed = ctx.input(|i| i.key_pressed(egui::Key::C));
+		let n_pressed = ctx.input(|i| i.key_pressed(egui::Key::N));
+
+		let interacting = self.dragging_image
+			|| ctx.input(|i| {
+				ACTIVITY_KEYS.iter().any(|key| i.key_down(*key))
+					|| i.raw_scroll_delta != egui::Vec2::ZERO
+			});
+		if interacting || space_pressed {
+			self.note_user_activity(settings, events);
+		}
 
 		if c_pressed {
 			events.push(Event::Settings(SettingsEvent::ToggleAutoPlay));
 		}
 
-		if space_pressed {
+		if n_pressed {
+			self.show_notes = !self.show_notes;
+		}
+
+		if ctx.input(|i| i.key_pressed(egui::Key::H)) {
+			self.overlays.hide_all = !self.overlays.hide_all;
+		}
+
+		// [ and ]: one second off or onto the autoplay interval
+		if let Some(sign) = bracket {
+			events.push(Event::Settings(SettingsEvent::AdjustDelay {
+				delta_secs: sign as i64,
+			}));
+			self.readout = Some((Readout::Interval, Instant::now()));
+		}
+
+		if ctx.input(|i| i.key_pressed(egui::Key::M)) {
+			events.push(Event::Source(SourceEvent::MoreLikeThis));
+		}
+
+		if ctx.input(|i| i.key_pressed(egui::Key::B)) {
+			events.push(Event::Breathing(BreathingEvent::TriggerCycle));
+		}
+
+		// Esc: stop breathing in or holding right away; the tag picker closes on it instead
+		if breathing.is_visible()
+			&& !self.tag_picker_open
+			&& ctx.input(|i| i.key_pressed(egui::Key::Escape))
+		{
+			events.push(Event::Breathing(BreathingEvent::ForceRelease));
+		}
+
+		// Ctrl+R: load the current image again, e.g. if it shows as a black rectangle
+		if ctx.input(|i| i.key_pressed(egui::Key::R)) {
 			if ctrl_pressed {
-				events.push(Event::Source(SourceEvent::Navigate(NavDirection::Skip(10))));
+				events.push(Event::Media(MediaEvent::ReloadCurrent));
 			} else {
-				events.push(Event::Source(SourceEvent::Navigate(NavDirection::Next)));
+				events.push(Event::Media(MediaEvent::ForceFullRes));
 			}
 		}
-	}
 
-	fn render_top_panel(
-		&mut self,
-		ctx: &egui::Context,
-		_gateway: &BooruGateway,
-		settings: &SettingsManager,
-		breathing: &BreathingOverlay,
-		beat: &SystemBeat,
-		events: &mut Vec<Event>,
-		enabled: bool,
-	) {
-		let models_dir = crate::config::get_models_dir();
-		let presets_dir = crate::config::get_presets_dir();
-		let has_coach_deps = models_dir.as_ref().map_or(false, |d| d.exists())
-			&& presets_dir.as_ref().map_or(false, |d| d.exists());
+		// K: pin the current post beside the slideshow; on another post it takes the pin,
+		// on the pinned one (or with Ctrl) it unpins
+		if ctx.input(|i| i.key_pressed(egui::Key::K)) {
+			let current = browser.current_post().filter(|_| !ctrl_pressed);
+			self.set_pinned(current, events);
+		}
 
-		egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-			if !enabled {
-				ui.disable();
-			}
-			ui.horizontal_wrapped(|ui| {
-				ui.label("Query:");
-				let response = ui.text_edit_singleline(&mut self.search_query);
+		if ctx.input(|i| i.key_pressed(egui::Key::F)) {
+			events.push(Event::Favorites(FavoritesEvent::ToggleCurrent));
+		}
 
-				ui.label("Page:");
-				let page_response = ui.add(
-					egui::TextEdit::singleline(&mut self.search_page_input).desired_width(40.0),
-				);
+		if ctx.input(|i| i.key_pressed(egui::Key::V))
+			&& let Some(post) = browser.current_post()
+		{
+			self.reveal.reveal(post.id);
+		}
 
-				if ui.button("Search").clicked()
-					|| (response.lost_focus() && ctx.input(|i| i.key_pressed(egui::Key::Enter)))
-					|| (page_response.lost_focus()
-						&& ctx.input(|i| i.key_pressed(egui::Key::Enter)))
-				{
-					let page = self.search_page_input.parse::<u32>().unwrap_or(1).max(1);
-					events.push(Event::Source(SourceEvent::Search {
-						query: self.search_query.clone(),
-						page,
-					}));
-				}
-				ui.separator();
+		if ctx.input(|i| i.key_pressed(egui::Key::Z))
+			&& let Some(post_id) = self.displayed_post_id
+		{
+			self.cycle_fill_override(post_id);
+		}
 
-				ui.label("Quick settings:");
+		// PageUp/PageDown: step the API page, or in reading mode a screen of the page.
+		// Space pages down as well; Shift+Space can't page up, Shift opens the island.
+		let page_up = ctx.input(|i| i.key_pressed(egui::Key::PageUp));
+		let page_down = ctx.input(|i| i.key_pressed(egui::Key::PageDown));
+		if self.reading.enabled {
+			if page_up {
+				self.turn_page(false, events);
+			}
+			if page_down || (space_pressed && !ctrl_pressed) {
+				self.turn_page(true, events);
+			}
+		} else {
+			if page_up {
+				self.step_page(-1, events);
+			}
+			if page_down {
+				self.step_page(1, events);
+			}
+		}
 
-				let mut auto_play = settings.auto_play();
-				if ui.checkbox(&mut auto_play, "Auto-play").changed() {
-					events.push(Event::Settings(SettingsEvent::ToggleAutoPlay));
-				}
+		if ctx.input(|i| i.key_pressed(egui::Key::F5)) {
+			if ctrl_pressed {
+				events.push(Event::Source(SourceEvent::Reroll));
+			} else {
+				events.push(Event::Gateway(GatewayEvent::ClearEndOfResults));
+			}
+		}
 
-				let mut cap_by_breathing = settings.cap_by_breathing();
-				if ui
-					.checkbox(&mut cap_by_breathing, "Sync with Breathing")
-					.changed()
-				{
-					events.push(Event::Settings(SettingsEvent::ToggleCapByBreathing));
-				}
+		if ctx.input(|i| i.key_pressed(egui::Key::I)) {
+			events.push(Event::Settings(SettingsEvent::CycleInfoVerbosity));
+		}
 
-				if settings.auto_play() {
-					let mut seconds = settings.auto_play_delay().as_secs_f32();
-					ui.label("Interval (s)");
-					if ui
-						.add(
-							egui::DragValue::new(&mut seconds)
-								.range(1.0..=60.0)
-								.speed(1.0),
-						)
-						.changed()
-					{
-						events.push(Event::Settings(SettingsEvent::SetDelay {
-							duration: Duration::from_secs_f32(seconds),
-						}));
-					}
-				}
+		if ctx.input(|i| i.key_pressed(egui::Key::P))
+			&& let Some(post) = browser.current_post()
+		{
+			if post.pools.is_empty() {
+				self.show_toast("This post isn't in any pools".to_string());
+			} else {
+				events.push(Event::Gateway(GatewayEvent::FetchPoolNames {
+					pool_ids: post.pools.clone(),
+				}));
+				self.pool_picker = Some(PoolPicker {
+					pools: post.pools.clone(),
+					selected: 0,
+				});
+			}
+		}
 
-				ui.separator();
+		if ctx.input(|i| i.key_pressed(egui::Key::Delete))
+			&& let Some(post) = browser.current_post()
+		{
+			events.push(Event::Browser(BrowserEvent::HidePost { id: post.id }));
+		}
 
-				let mut breathing_enabled = breathing.is_visible();
+		if ctx.input(|i| i.key_pressed(egui::Key::X)) && browser.current_post().is_some() {
+			self.tag_picker_open = !self.tag_picker_open;
+			self.tag_picker_filter.clear();
+		}
 
-				if ui.checkbox(&mut breathing_enabled, "Breathing").clicked() {
-					if breathing_enabled && !self.breathing_disclaimer_accepted {
-						self.modal = ModalContent::BreathingDisclaimer;
-					} else {
-						events.push(Event::Breathing(BreathingEvent::Toggle));
-					}
-				}
+		if ctx.input(|i| i.key_pressed(egui::Key::F12)) {
+			self.cache_inspector_open = !self.cache_inspector_open;
+		}
 
-				if breathing_enabled {
-					let mut idle_mult = breathing.idle_multiplier();
-					ui.label("Idle");
-					if ui
-						.add(
-							egui::DragValue::new(&mut idle_mult)
-								.range(0.5..=3.0)
-								.speed(0.1),
-						)
-						.changed()
-					{
-						events.push(Event::Breathing(BreathingEvent::SetIdleMultiplier {
-							value: idle_mult,
-						}));
-					}
+		if ctx.input(|i| i.key_pressed(egui::Key::F3)) {
+			self.load_stats_open = !self.load_stats_open;
+		}
 
-					let current_style = breathing.style();
-					let style_label = match current_style {
-						BreathingStyle::Classic => "Classic",
-						BreathingStyle::Immersive => "Immersive",
-					};
-					egui::ComboBox::from_id_salt("breathing_style")
-						.selected_text(style_label)
-						.show_ui(ui, |ui| {
-							if ui
-								.selectable_label(
-									current_style == BreathingStyle::Classic,
-									"Classic",
-								)
-								.clicked()
-							{
-								events.push(Event::Breathing(BreathingEvent::SetStyle {
-									style: BreathingStyle::Classic,
-								}));
-							}
-							if ui
-								.selectable_label(
-									current_style == BreathingStyle::Immersive,
-									"Immersive",
-								)
-								.clicked()
-							{
-								events.push(Event::Breathing(BreathingEvent::SetStyle {
-									style: BreathingStyle::Immersive,
-								}));
-							}
-						});
-				}
+		// Ctrl+G: go to a post by position or API page
+		if ctrl_pressed && ctx.input(|i| i.key_pressed(egui::Key::G)) {
+			self.goto_open = true;
+			self.goto_input.clear();
+		}
 
-				ui.separator();
+		// T/G: upvote/downvote the current post
+		let vote = ctx.input(|i| {
+			if i.key_pressed(egui::Key::T) {
+				Some(1)
+			} else if i.key_pressed(egui::Key::G) && !ctrl_pressed {
+				Some(-1)
+			} else {
+				None
+			}
+		});
+		if let (Some(score), Some(post)) = (vote, browser.current_post()) {
+			events.push(Event::Gateway(GatewayEvent::Vote {
+				post_id: post.id,
+				score,
+				previous: browser.vote_for(post.id),
+			}));
+		}
 
-				let mut pan_speed = self.auto_pan_cycle_duration;
-				ui.label("Pan Speed (s)");
-				if ui
-					.add(
-						egui::DragValue::new(&mut pan_speed)
-							.range(10.0..=120.0)
-							.speed(1.0),
-					)
-					.changed()
-				{
-					self.auto_pan_cycle_duration = pan_speed;
-				}
-				ui.separator();
+		if space_pressed {
+			if ctrl_pressed {
+				events.push(Event::Source(SourceEvent::Navigate(NavDirection::Skip(10))));
+			} else if !self.reading.enabled {
+				events.push(Event::Source(SourceEvent::Navigate(NavDirection::Next)));
+			}
+		}
+	}
 
-				let current_fill = self.image_fill_mode;
-				let fill_label = match current_fill {
-					ImageFillMode::Cover => "Cover",
-					ImageFillMode::Fit => "Fit",
-					ImageFillMode::FitToGallery => "Fit to Gallery",
-				};
-				egui::ComboBox::from_id_salt("image_fill_mode")
-					.selected_text(fill_label)
-					.show_ui(ui, |ui| {
-						if ui
-							.selectable_label(current_fill == ImageFillMode::Cover, "Cover")
-							.clicked()
-						{
-							events.push(Event::View(ViewEvent::SetImageFillMode {
-								mode: ImageFillMode::Cover,
-							}));
-						}
-						if ui
-							.selectable_label(current_fill == ImageFillMode::Fit, "Fit")
-							.clicked()
-						{
-							events.push(Event::View(ViewEvent::SetImageFillMode {
-								mode: ImageFillMode::Fit,
-							}));
-						}
-						if ui
-							.selectable_label(
-								current_fill == ImageFillMode::FitToGallery,
-								"Fit to Gallery",
-							)
-							.clicked()
-						{
-							events.push(Event::View(ViewEvent::SetImageFillMode {
-								mode: ImageFillMode::FitToGallery,
-							}));
-						}
-					});
-
-				ui.separator();
-
-				ui.label("Audio:");
-				let selected_label = beat.selected_device_label();
-				egui::ComboBox::from_id_salt("audio_device")
-					.selected_text(selected_label)
-					.show_ui(ui, |ui| {
-						if ui
-							.selectable_label(beat.selected_device().is_none(), "Default")
-							.clicked()
-						{
-							events.push(Event::Beat(BeatEvent::SetDevice { name: None }));
-						}
-						for device_name in beat.device_names() {
-							let is_selected =
-								beat.selected_device().as_deref() == Some(device_name.as_str());
-							if ui.selectable_label(is_selected, device_name).clicked() {
-								events.push(Event::Beat(BeatEvent::SetDevice {
-									name: Some(device_name.clone()),
-								}));
-							}
-						}
-					});
-				if beat.is_active() {
-					ui.label(
-						egui::RichText::new("*")
-							.color(egui::Color32::GREEN)
-							.size(10.0),
-					);
-				} else {
-					ui.label(
-						egui::RichText::new("*")
-							.color(egui::Color32::RED)
-							.size(10.0),
-					);
-				}
-
-				ui.checkbox(&mut self.beat_pulse_enabled, "Pulse");
-				if self.beat_pulse_enabled {
-					ui.label("Scale");
-					ui.add(
-						egui::DragValue::new(&mut self.beat_pulse_scale)
-							.range(0.01..=0.15)
-							.speed(0.01),
-					);
-				}
+	/// Scroll a screen down or up the page, or go on to the next or previous post from its end
+	fn turn_page(&mut self, forward: bool, events: &mut Vec<Event>) {
+		let turn = self.reading.turn(forward);
+		self.reading.start_at_bottom = turn == PageTurn::Prev;
+		match turn {
+			PageTurn::ScrollTo(offset) => self.reading.jump = Some(offset),
+			PageTurn::Next => {
+				events.push(Event::Source(SourceEvent::Navigate(NavDirection::Next)));
+			}
+			PageTurn::Prev => {
+				events.push(Event::Source(SourceEvent::Navigate(NavDirection::Prev)));
+			}
+		}
+	}
 
-				if has_coach_deps {
-					ui.separator();
-					ui.checkbox(&mut self.coach_enabled, "Coach");
-					if self.coach_enabled {
-						// Render combo box for model
-						let models = if let Some(dir) = &models_dir {
-							std::fs::read_dir(dir)
-								.into_iter()
-								.flatten()
-								.filter_map(|e| e.ok())
-								.map(|e| e.file_name().to_string_lossy().to_string())
-								.filter(|f| f.ends_with(".gguf"))
-								.collect::<Vec<_>>()
-						} else {
-							vec![]
-						};
+	/// Store where the user left the image on screen before it is replaced
+	fn remember_view(&mut self) {
+		let Some(post_id) = self.displayed_post_id.take() else {
+			return;
+		};
+		self.view_memory.shift_remove(&post_id);
+		let untouched = !self.user_has_panned && self.user_zoom == 1.0;
+		if !self.remember_view_positions || untouched {
+			return;
+		}
+		self.view_memory.insert(
+			post_id,
+			ViewMemory {
+				scroll_offset: self.cover_scroll_offset,
+				zoom: self.user_zoom,
+				pan_offset: self.user_pan_offset,
+				user_panned: self.user_has_panned,
+			},
+		);
+		while self.view_memory.len() > MAX_VIEW_MEMORY {
+			self.view_memory.shift_remove_index(0);
+		}
+	}
 
-						let selected_model = self.coach_model.as_deref().unwrap_or("Select Model");
-						egui::ComboBox::from_id_salt("coach_model")
-							.selected_text(selected_model)
-							.show_ui(ui, |ui| {
-								for m in models {
-									if ui
-										.selectable_label(self.coach_model.as_ref() == Some(&m), &m)
-										.clicked()
-									{
-										self.coach_model = Some(m);
-									}
-								}
-							});
+	/// Put the incoming image back where the user left it, if they've seen it before.
+	/// Pan offsets are clamped to the current display size when drawn.
+	fn restore_view(&mut self, browser: &ContentBrowser) {
+		self.restore_view_pending = false;
+		self.displayed_post_id = browser.current_post().map(|p| p.id);
+		let displayed = self.displayed_post_id;
+		self.fill_override = self
+			.fill_override
+			.filter(|(post_id, _)| Some(*post_id) == displayed);
+		if !self.remember_view_positions {
+			return;
+		}
+		let Some(memory) = self
+			.displayed_post_id
+			.and_then(|id| self.view_memory.get(&id).copied())
+		else {
+			return;
+		};
+		self.user_zoom = memory.zoom.clamp(1.0, 5.0);
+		self.user_pan_offset = memory.pan_offset;
+		if memory.user_panned {
+			self.user_has_panned = true;
+			self.pending_scroll_offset = Some(memory.scroll_offset);
+		}
+	}
 
-						// Render combo box for preset
-						let presets = if let Some(dir) = &presets_dir {
-							std::fs::read_dir(dir)
-								.into_iter()
-								.flatten()
-								.filter_map(|e| e.ok())
-								.map(|e| e.file_name().to_string_lossy().to_string())
-								.filter(|f| f.ends_with(".toml"))
-								.collect::<Vec<_>>()
-						} else {
-							vec![]
-						};
+	/// How the displayed image is filled: its own override, or the preferred mode
+	fn fill_mode(&self) -> ImageFillMode {
+		match self.fill_override {
+			Some((post_id, mode)) if Some(post_id) == self.displayed_post_id => mode,
+			_ => self.image_fill_mode,
+		}
+	}
 
-						let selected_preset =
-							self.coach_preset.as_deref().unwrap_or("Select Preset");
-						egui::ComboBox::from_id_salt("coach_preset")
-							.selected_text(selected_preset)
-							.show_ui(ui, |ui| {
-								for p in presets {
-									if ui
-										.selectable_label(
-											self.coach_preset.as_ref() == Some(&p),
-											&p,
-										)
-										.clicked()
-									{
-										self.coach_preset = Some(p);
-									}
-								}
-							});
-					}
-				}
-			});
-		});
+	/// Z: step the current post's fill through the modes, without changing the preference
+	fn cycle_fill_override(&mut self, post_id: u64) {
+		let mode = self.fill_mode().next();
+		self.fill_override = Some((post_id, mode));
+		self.show_toast(format!("Fill: {} (this image)", mode.label()));
 	}
 
-	fn render_central_panel(
-		&mut self,
-		ctx: &egui::Context,
-		browser: &ContentBrowser,
-		media: &mut MediaCache,
-		gateway: &BooruGateway,
-		enabled: bool,
-	) {
-		egui::CentralPanel::default().show(ctx, |ui| {
-			if !enabled {
-				ui.disable();
-			}
-			if gateway.is_loading() && browser.is_empty() {
-				ui.centered_and_justified(|ui| {
-					ui.spinner();
-				});
-			} else if let Some(err) = &self.error_msg {
-				ui.label(egui::RichText::new(err).color(egui::Color32::RED));
-			} else if let Some(_url) = media.current_url() {
-				self.render_media(ui, ctx, media, browser);
-			} else {
-				ui.centered_and_justified(|ui| {
-					ui.label("Enter a query and search to start.");
-				});
-			}
-		});
+	/// Hold the slideshow and auto-pan while the user pans, zooms or navigates
+	fn note_user_activity(&mut self, settings: &SettingsManager, events: &mut Vec<Event>) {
+		if !settings.auto_pause() {
+			return;
+		}
+		let idle = settings.auto_pause_idle();
+		self.auto_pan_hold_until = Some(Instant::now() + idle);
+		if self
+			.last_activity_reported
+			.is_some_and(|at| at.elapsed() < ACTIVITY_REPORT_INTERVAL)
+		{
+			return;
+		}
+		self.last_activity_reported = Some(Instant::now());
+		events.push(Event::Settings(SettingsEvent::SuppressAdvanceFor {
+			secs: idle.as_secs_f32(),
+		}));
+	}
 
-		// Render Coach Overlay
-		if !self.coach_logs.is_empty() {
-			let screen_height = ctx.screen_rect().height();
-			let base_font_size = (screen_height * 0.03).max(16.0);
-			let font_size = base_font_size * 0.75;
-			let margin = (screen_height * 0.03).max(10.0);
+	pub fn with_key_pan_speed(mut self, speed: f32) -> Self {
+		self.key_pan_speed = speed;
+		self
+	}
 
-			let info_overlay_height = base_font_size * 5.0;
-			let offset_y = -margin - info_overlay_height;
+	/// Started with `--sfw`: open on a neutral query rather than whatever was searched last
+	pub fn with_locked_safe_mode(mut self, locked: bool) -> Self {
+		if locked {
+			self.search_query = SAFE_MODE_QUERY.to_string();
+			self.search_page_input = "1".to_string();
+		}
+		self
+	}
 
-			egui::Area::new(egui::Id::new("coach_overlay"))
-				.anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(margin, offset_y))
-				.interactable(false)
-				.order(egui::Order::Foreground)
-				.show(ctx, |ui| {
-					let recent_logs = self.coach_logs.iter().rev().take(20).rev();
-					let outline_color = egui::Color32::from_black_alpha(204);
-					let text_color = egui::Color32::from_rgb(180, 220, 180); // Muted terminal green
-					let font_id = egui::FontId::monospace(font_size);
-					let stroke_width = (font_size * 0.06).max(1.0).min(2.0); // Not too thick
+	pub fn with_filmstrip(mut self, enabled: bool) -> Self {
+		self.show_filmstrip = enabled;
+		self
+	}
 
-					let offsets = [
-						egui::vec2(-stroke_width, -stroke_width),
-						egui::vec2(0.0, -stroke_width),
-						egui::vec2(stroke_width, -stroke_width),
-						egui::vec2(-stroke_width, 0.0),
-						egui::vec2(stroke_width, 0.0),
-						egui::vec2(-stroke_width, stroke_width),
-						egui::vec2(0.0, stroke_width),
-						egui::vec2(stroke_width, stroke_width),
-					];
+	/// Start in reveal mode, revealing on its own after `auto_delay` during the slideshow
+	pub fn with_reveal(mut self, enabled: bool, auto_delay: Duration) -> Self {
+		self.reveal = Reveal::new(enabled, auto_delay);
+		self
+	}
 
-					for msg in recent_logs {
-						let text = msg.clone();
-						let galley =
-							ui.painter()
-								.layout_no_wrap(text.clone(), font_id.clone(), text_color);
-						let shadow_galley = ui.painter().layout_no_wrap(
-							text.clone(),
-							font_id.clone(),
-							outline_color,
-						);
-						let (rect, _) = ui.allocate_exact_size(galley.size(), egui::Sense::hover());
+	/// Search for the query in the search box, remembering it for the island
+	fn submit_search(&mut self, page: u32, events: &mut Vec<Event>) {
+		let query = self.search_query.trim().to_string();
+		if !query.is_empty() {
+			self.recent_searches.retain(|recent| *recent != query);
+			self.recent_searches.push_front(query);
+			self.recent_searches.truncate(MAX_RECENT_SEARCHES);
+		}
+		events.push(Event::Source(SourceEvent::Search {
+			query: self.search_query.clone(),
+			page,
+		}));
+	}
 
-						for offset in offsets {
-							ui.painter().galley(
-								rect.min + offset,
-								shadow_galley.clone(),
-								outline_color,
-							);
-						}
-						ui.painter().galley(rect.min, galley, text_color);
-					}
-				});
+	/// Search one page before or after the one in the page field, replacing the results
+	fn step_page(&mut self, delta: i32, events: &mut Vec<Event>) {
+		let Some(current) = parse_page(&self.search_page_input) else {
+			return;
+		};
+		let page = current.saturating_add_signed(delta).clamp(1, MAX_PAGE);
+		if page != current {
+			self.search_page_input = page.to_string();
+			self.submit_search(page, events);
 		}
+	}
 
-		if ctx.input(|i| i.key_down(egui::Key::R)) {
-			egui::Area::new(egui::Id::new("coach_debug"))
-				.anchor(egui::Align2::LEFT_TOP, egui::vec2(20.0, 20.0))
-				.interactable(false)
-				.order(egui::Order::Foreground)
-				.show(ctx, |ui| {
-					egui::Frame::window(&ctx.style())
-						.fill(egui::Color32::from_black_alpha(220))
-						.inner_margin(12.0)
-						.rounding(8.0)
-						.show(ui, |ui| {
-							ui.heading(
-								egui::RichText::new("Coach mem:")
-									.color(egui::Color32::YELLOW)
-									.strong(),
-							);
-							ui.add_space(8.0);
-							for (k, v) in &self.coach_state {
-								ui.label(
-									egui::RichText::new(format!("{}: {}", k, v))
-										.color(egui::Color32::LIGHT_GRAY)
-										.monospace(),
-								);
-							}
-						});
-				});
+	/// Hold the auto-pan while the window is unfocused or minimized,
+	/// so it picks up where it left off instead of jumping ahead
+	fn track_background(&mut self, ctx: &egui::Context) {
+		let backgrounded = ctx.input(|i| {
+			let viewport = i.viewport();
+			viewport.focused == Some(false) || viewport.minimized == Some(true)
+		});
+		match self.backgrounded_since {
+			None if backgrounded => {
+				log::debug!("Window in the background, holding auto-pan");
+				self.backgrounded_since = Some(Instant::now());
+			}
+			Some(since) if !backgrounded => {
+				log::debug!("Window back after {:?}, resuming auto-pan", since.elapsed());
+				self.pan_started += since.elapsed();
+				self.backgrounded_since = None;
+			}
+			_ => {}
 		}
 	}
 
-	fn render_media(
-		&mut self,
-		ui: &mut egui::Ui,
-		ctx: &egui::Context,
-		media: &mut MediaCache,
-		browser: &ContentBrowser,
-	) {
-		let pan_cycle = self.auto_pan_cycle_duration;
-		let load_time = self.image_load_time;
-		let mut user_panned = self.user_has_panned;
-		let island_active = self.island_ctx.active || self.island_ctx.in_cooldown();
+	/// Whether the window is unfocused or minimized
+	pub fn is_backgrounded(&self) -> bool {
+		self.backgrounded_since.is_some()
+	}
 
-		let handle_scroll_input = |ui: &mut egui::Ui, input_active: &mut bool| {
-			// Don't process scroll input when island overlay is active or just closed
-			if island_active {
-				return;
-			}
+	/// Whether watched searches should wait: a modal is open or nobody is looking
+	pub fn holds_watch_polls(&self) -> bool {
+		!self.modals.is_empty() || self.is_backgrounded()
+	}
 
-			let mut scroll_delta = egui::Vec2::ZERO;
-			let speed = 20.0;
+	/// Ping-pong cycle length, slowed down under reduced motion
+	fn pan_cycle_duration(&self) -> f32 {
+		if self.overlay_style.reduce_motion {
+			self.auto_pan_cycle_duration.max(CALM_PAN_SECS)
+		} else {
+			self.auto_pan_cycle_duration
+		}
+	}
 
-			if ui.input(|i| i.key_down(egui::Key::ArrowRight) || i.key_down(egui::Key::D)) {
-				scroll_delta.x -= speed;
-				*input_active = true;
+	/// Auto-pan position `elapsed` seconds after the image appeared
+	fn auto_pan_frame(&self, elapsed: f32) -> AutoPanFrame {
+		// One-shot modes ease in and out, then hold the final framing
+		let t = (elapsed / self.once_pan_duration.max(1.0)).clamp(0.0, 1.0);
+		let eased = t * t * (3.0 - 2.0 * t);
+		let (offset, zoom) = match self.pan_mode {
+			PanMode::PingPong => {
+				// Back and forth for as long as the image is up
+				let cycle = (elapsed * 2.0 * std::f32::consts::PI) / self.pan_cycle_duration();
+				let factor = (1.0 - cycle.cos()) * 0.5;
+				return AutoPanFrame {
+					offset: egui::Vec2::splat(factor),
+					zoom: 1.0,
+					moving: true,
+				};
 			}
-			if ui.input(|i| i.key_down(egui::Key::ArrowLeft) || i.key_down(egui::Key::A)) {
-				scroll_delta.x += speed;
-				*input_active = true;
+			PanMode::TopToBottomOnce => (egui::vec2(0.5, eased), 1.0),
+			PanMode::LeftToRightOnce => (egui::vec2(eased, 0.5), 1.0),
+			PanMode::KenBurns { zoom_range } => {
+				let path = self.ken_burns;
+				let closeness = if path.zoom_in { eased } else { 1.0 - eased };
+				(
+					path.from + (path.to - path.from) * eased,
+					1.0 + zoom_range * closeness,
+				)
 			}
-			if ui.input(|i| i.key_down(egui::Key::ArrowDown) || i.key_down(egui::Key::S)) {
-				scroll_delta.y -= speed;
-				*input_active = true;
+		};
+		AutoPanFrame {
+			offset,
+			zoom,
+			moving: t < 1.0,
+		}
+	}
+
+	/// How far the held Arrow/WASD keys move the image this frame. Time-based so the
+	/// refresh rate doesn't matter, the same speed diagonally, and easing in briefly.
+	fn key_pan_delta(&mut self, ctx: &egui::Context) -> egui::Vec2 {
+		let (direction, dt) = ctx.input(|i| {
+			let held = |a, b| i.key_down(a) || i.key_down(b);
+			let mut direction = egui::Vec2::ZERO;
+			// The image moves opposite to the way we look
+			if held(egui::Key::ArrowRight, egui::Key::D) {
+				direction.x -= 1.0;
 			}
-			if ui.input(|i| i.key_down(egui::Key::ArrowUp) || i.key_down(egui::Key::W)) {
-				scroll_delta.y += speed;
-				*input_active = true;
+			if held(egui::Key::ArrowLeft, egui::Key::A) {
+				direction.x += 1.0;
 			}
-
-			if scroll_delta != egui::Vec2::ZERO {
-				ui.scroll_with_delta(scroll_delta);
+			if held(egui::Key::ArrowDown, egui::Key::S) {
+				direction.y -= 1.0;
 			}
-		};
+			if held(egui::Key::ArrowUp, egui::Key::W) {
+				direction.y += 1.0;
+			}
+			(direction, i.stable_dt)
+		});
+		if direction == egui::Vec2::ZERO {
+			self.key_pan_since = None;
+			return egui::Vec2::ZERO;
+		}
 
-		if let Some(loaded_media) = media.get_current_media() {
-			match loaded_media {
-				LoadedMedia::Image { texture } => {
-					let available_size = ui.available_size();
-					let img_size = texture.size_vec2();
+		let held_for = self
+			.key_pan_since
+			.get_or_insert_with(Instant::now)
+			.elapsed();
+		let ramp = (held_for.as_secs_f32() / KEY_PAN_RAMP.as_secs_f32()).min(1.0);
+		let speed = self.key_pan_speed * (KEY_PAN_START + (1.0 - KEY_PAN_START) * ramp);
+		self.repaints.now(RepaintCause::Input);
+		direction.normalized() * speed * dt
+	}
 
-					if matches!(
-						self.image_fill_mode,
-						ImageFillMode::Fit | ImageFillMode::FitToGallery
-					) {
-						if !island_active {
-							let dt = ctx.input(|i| i.stable_dt);
+	/// Drag the image to pan, gliding on for a moment after release.
+	/// Returns how far the image should move this frame.
+	fn drag_pan_delta(&mut self, ui: &mut egui::Ui, island_active: bool) -> egui::Vec2 {
+		let response = ui.interact(
+			ui.available_rect_before_wrap(),
+			ui.id().with("drag_pan"),
+			egui::Sense::drag(),
+		);
+		self.dragging_image = response.dragged() && !island_active;
+		if island_active {
+			self.pan_velocity = egui::Vec2::ZERO;
+			return egui::Vec2::ZERO;
+		}
+		if response.dragged() {
+			ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+			self.pan_velocity = egui::Vec2::ZERO;
+			return response.drag_delta();
+		}
+		if response.drag_stopped() && !self.island_ctx.reduce_motion {
+			self.pan_velocity = ui.input(|i| i.pointer.velocity());
+		}
 
-							if ctx.input(|i| i.key_down(egui::Key::E)) {
-								self.user_zoom = (self.user_zoom + dt * 4.0).min(5.0);
-								ctx.request_repaint();
-							}
-							if ctx.input(|i| i.key_down(egui::Key::Q)) {
-								self.user_zoom = (self.user_zoom - dt * 4.0).max(1.0);
-								ctx.request_repaint();
-							}
+		if self.pan_velocity.length() < PAN_GLIDE_MIN_SPEED {
+			self.pan_velocity = egui::Vec2::ZERO;
+			return egui::Vec2::ZERO;
+		}
+		let dt = ui.input(|i| i.stable_dt);
+		let delta = self.pan_velocity * dt;
+		self.pan_velocity *= (-PAN_GLIDE_FRICTION * dt).exp();
+		self.repaints.now(RepaintCause::Input);
+		delta
+	}
 
-							if self.user_zoom > 1.0 {
-								let speed = 1600.0 * dt;
-								if ctx.input(|i| {
-									i.key_down(egui::Key::ArrowRight) || i.key_down(egui::Key::D)
-								}) {
-									self.user_pan_offset.x -= speed;
-									ctx.request_repaint();
-								}
-								if ctx.input(|i| {
-									i.key_down(egui::Key::ArrowLeft) || i.key_down(egui::Key::A)
-								}) {
-									self.user_pan_offset.x += speed;
-									ctx.request_repaint();
-								}
-								if ctx.input(|i| {
-									i.key_down(egui::Key::ArrowDown) || i.key_down(egui::Key::S)
-								}) {
-									self.user_pan_offset.y -= speed;
-									ctx.request_repaint();
-								}
-								if ctx.input(|i| {
-									i.key_down(egui::Key::ArrowUp) || i.key_down(egui::Key::W)
-								}) {
-									self.user_pan_offset.y += speed;
-									ctx.request_repaint();
-								}
-							} else {
-								self.user_pan_offset = egui::Vec2::ZERO;
-							}
-						}
+	/// Whether mouse navigation should stay out of the way of the island or a modal
+	fn pointer_navigation_blocked(&self, ui: &egui::Ui) -> bool {
+		!ui.is_enabled() || self.island_ctx.active || self.island_ctx.in_cooldown()
+	}
 
-						let fit_scale =
-							(available_size.x / img_size.x).min(available_size.y / img_size.y);
-						let fit_size = img_size * fit_scale * self.user_zoom;
-						let pan_limit = ((fit_size - available_size) * 0.5).max(egui::Vec2::ZERO);
-						self.user_pan_offset.x =
-							self.user_pan_offset.x.clamp(-pan_limit.x, pan_limit.x);
-						self.user_pan_offset.y =
-							self.user_pan_offset.y.clamp(-pan_limit.y, pan_limit.y);
-					} else {
-						self.user_zoom = 1.0;
-						self.user_pan_offset = egui::Vec2::ZERO;
-					}
+	/// Clicking the left or right edge of the panel goes back or forward.
+	/// Drags still pan; only clicks navigate.
+	fn render_edge_zones(
+		&self,
+		ui: &mut egui::Ui,
+		panel_rect: egui::Rect,
+		events: &mut Vec<Event>,
+	) {
+		if self.pointer_navigation_blocked(ui) {
+			return;
+		}
+		let zone_width = panel_rect.width() * EDGE_ZONE_FRACTION;
+		let zones = [
+			(
+				NavDirection::Prev,
+				egui::Rect::from_min_max(
+					panel_rect.min,
+					egui::pos2(panel_rect.left() + zone_width, panel_rect.bottom()),
+				),
+				"‹",
+			),
+			(
+				NavDirection::Next,
+				egui::Rect::from_min_max(
+					egui::pos2(panel_rect.right() - zone_width, panel_rect.top()),
+					panel_rect.max,
+				),
+				"›",
+			),
+		];
+		for (direction, rect, arrow) in zones {
+			let response = ui.interact(
+				rect,
+				ui.id().with(("edge_zone", arrow)),
+				egui::Sense::click(),
+			);
+			if response.hovered() {
+				let arrow_pos = match direction {
+					NavDirection::Prev => egui::pos2(rect.left() + 32.0, rect.center().y),
+					_ => egui::pos2(rect.right() - 32.0, rect.center().y),
+				};
+				ui.painter()
+					.circle_filled(arrow_pos, 24.0, egui::Color32::from_black_alpha(80));
+				ui.painter().text(
+					arrow_pos,
+					egui::Align2::CENTER_CENTER,
+					arrow,
+					egui::FontId::proportional(40.0),
+					egui::Color32::from_white_alpha(160),
+				);
+			}
+			if response.clicked() {
+				events.push(Event::Source(SourceEvent::Navigate(direction)));
+			}
+		}
+	}
 
-					// Apply beat pulse if enabled
-					let pulse = if self.beat_pulse_enabled && self.beat_intensity > 0.01 {
-						ctx.request_repaint();
-						1.0 + self.beat_intensity * self.beat_pulse_scale
-					} else {
-						1.0
-					};
+	/// With wheel navigation on, one notch over the panel moves one image.
+	/// Ctrl+wheel is left alone for zooming.
+	fn handle_wheel_navigation(
+		&mut self,
+		ui: &egui::Ui,
+		panel_rect: egui::Rect,
+		events: &mut Vec<Event>,
+	) {
+		if !self.wheel_navigation
+			|| self.pointer_navigation_blocked(ui)
+			|| !ui.rect_contains_pointer(panel_rect)
+		{
+			return;
+		}
+		let (delta, ctrl) = ui.input(|i| (i.raw_scroll_delta.y, i.modifiers.ctrl));
+		if ctrl {
+			return;
+		}
+		// The image must not scroll as well, not even from leftover smoothing
+		ui.ctx().input_mut(|i| {
+			i.raw_scroll_delta = egui::Vec2::ZERO;
+			i.smooth_scroll_delta = egui::Vec2::ZERO;
+		});
+		if delta == 0.0 {
+			return;
+		}
 
-					match self.image_fill_mode {
-						ImageFillMode::Cover => {
-							let width_ratio = available_size.x / img_size.x;
-							let height_ratio = available_size.y / img_size.y;
-							let scale = width_ratio.max(height_ratio);
-							let base_display_size = img_size * scale;
+		if self.wheel_accum * delta < 0.0 {
+			self.wheel_accum = 0.0;
+		}
+		self.wheel_accum += delta;
+		let notch = ui.ctx().options(|o| o.line_scroll_speed);
+		while self.wheel_accum.abs() >= notch {
+			// Wheel up goes back, like scrolling up a page
+			let direction = if self.wheel_accum > 0.0 {
+				NavDirection::Prev
+			} else {
+				NavDirection::Next
+			};
+			self.wheel_accum -= notch.copysign(self.wheel_accum);
+			events.push(Event::Source(SourceEvent::Navigate(direction)));
+		}
+	}
 
-							let mut scroll_area = egui::ScrollArea::both().scroll_bar_visibility(
-								egui::scroll_area::ScrollBarVisibility::AlwaysHidden,
-							);
+	/// Lazily fetch notes for the current post while the notes toggle is on
+	fn request_notes(&mut self, browser: &ContentBrowser, events: &mut Vec<Event>) {
+		if !self.show_notes {
+			return;
+		}
+		let Some(post) = browser.current_post() else {
+			return;
+		};
+		if self.notes_post_id == Some(post.id) {
+			return;
+		}
 
-							// Auto-pan
-							if !user_panned {
-								let elapsed = load_time.elapsed().as_secs_f32();
-								let cycle = (elapsed * 2.0 * std::f32::consts::PI) / pan_cycle;
-								let factor = (1.0 - cycle.cos()) * 0.5;
+		self.notes_post_id = Some(post.id);
+		self.notes.clear();
+		if post.has_notes {
+			events.push(Event::Gateway(GatewayEvent::FetchNotes {
+				post_id: post.id,
+			}));
+		}
+	}
 
-								let overflow = base_display_size - available_size;
-								if overflow.x > 0.0 {
-									scroll_area =
-										scroll_area.horizontal_scroll_offset(overflow.x * factor);
-								}
-								if overflow.y > 0.0 {
-									scroll_area =
-										scroll_area.vertical_scroll_offset(overflow.y * factor);
-								}
-								ctx.request_repaint();
-							}
+	/// The top panel, its background as see-through as the theme asks
+	fn top_panel(ctx: &egui::Context, settings: &SettingsManager) -> egui::TopBottomPanel {
+		let frame = egui::Frame::side_top_panel(&ctx.style())
+			.multiply_with_opacity(settings.theme().panel_opacity);
+		egui::TopBottomPanel::top("top_panel").frame(frame)
+	}
 
-							scroll_area.show(ui, |ui| {
-								handle_scroll_input(ui, &mut user_panned);
+	fn render_top_panel(
+		&mut self,
+		ctx: &egui::Context,
+		gateway: &BooruGateway,
+		browser: &ContentBrowser,
+		media: &MediaCache,
+		settings: &SettingsManager,
+		breathing: &BreathingOverlay,
+		beat: &SystemBeat,
+		events: &mut Vec<Event>,
+		enabled: bool,
+	) {
+		let models_dir = crate::config::get_models_dir();
+		let presets_dir = crate::config::get_presets_dir();
+		let has_coach_deps = models_dir.as_ref().map_or(false, |d| d.exists())
+			&& presets_dir.as_ref().map_or(false, |d| d.exists());
 
-								let (rect, _response) =
-									ui.allocate_exact_size(base_display_size, egui::Sense::hover());
+		Self::top_panel(ctx, settings).show(ctx, |ui| {
+			if !enabled {
+				ui.disable();
+			}
+			ui.horizontal_wrapped(|ui| {
+				if self.offline {
+					ui.label(egui::RichText::new("⚠ Offline").color(egui::Color32::RED))
+						.on_hover_text("Can't reach e621, searches will fail until it's back");
+				}
+				if settings.safe_mode() {
+					let (label, hint) = if settings.safe_mode_locked() {
+						(
+							"🔒 SFW",
+							"Safe mode, locked on by --sfw: only rating:s posts",
+						)
+					} else {
+						("SFW", "Safe mode: only rating:s posts (Display menu)")
+					};
+					ui.label(
+						egui::RichText::new(label)
+							.strong()
+							.color(egui::Color32::from_rgb(120, 200, 120)),
+					)
+					.on_hover_text(hint);
+				}
+				ui.label("Query:");
+				let response =
+					ui.add(egui::TextEdit::singleline(&mut self.search_query).lock_focus(true));
+				self.text_focus.register(&response);
 
-								let center = rect.center();
-								let pulsed_size = base_display_size * pulse;
-								let pulsed_rect = egui::Rect::from_center_size(center, pulsed_size);
-								let uv = egui::Rect::from_min_max(
-									egui::pos2(0.0, 0.0),
-									egui::pos2(1.0, 1.0),
-								);
+				ui.label("Page:");
+				let page = parse_page(&self.search_page_input);
+				let page_hint = match page {
+					Some(_) => format!(
+						"e621 only numbers pages up to {}; narrow the query to see further",
+						MAX_PAGE
+					),
+					None => format!("Enter a page from 1 to {}", MAX_PAGE),
+				};
+				if ui
+					.add_enabled(page.is_some_and(|p| p > 1), egui::Button::new("◀").small())
+					.on_hover_text("Previous page (PageUp)")
+					.clicked()
+				{
+					self.step_page(-1, events);
+				}
+				let mut page_edit = egui::TextEdit::singleline(&mut self.search_page_input)
+					.desired_width(40.0)
+					.lock_focus(true);
+				if page.is_none() {
+					page_edit = page_edit.text_color(egui::Color32::RED);
+				}
+				let mut page_response = ui.add(page_edit);
+				self.text_focus.register(&page_response);
+				if page.is_none() {
+					page_response = page_response.on_hover_text(&page_hint);
+				}
+				if ui
+					.add_enabled(
+						page.is_some_and(|p| p < MAX_PAGE),
+						egui::Button::new("▶").small(),
+					)
+					.on_hover_text("Next page (PageDown)")
+					.on_disabled_hover_text(&page_hint)
+					.clicked()
+				{
+					self.step_page(1, events);
+				}
+				if let Some(estimate) = gateway.result_estimate() {
+					let hint = match estimate {
+						ResultEstimate::Exact(_) => "Every post this search found",
+						ResultEstimate::AtMost(_) => {
+							"Posts with the query's rarest tag; other filters may leave fewer"
+						}
+					};
+					ui.label(egui::RichText::new(estimate.to_string()).weak())
+						.on_hover_text(hint);
+				}
 
-								ui.painter().image(
-									texture.id(),
-									pulsed_rect,
-									uv,
-									egui::Color32::WHITE,
-								);
-							});
+				if ui.button("Search").clicked()
+					|| (response.lost_focus() && ctx.input(|i| i.key_pressed(egui::Key::Enter)))
+					|| (page_response.lost_focus()
+						&& ctx.input(|i| i.key_pressed(egui::Key::Enter)))
+				{
+					// An unreadable page stays red in the field instead of searching page 1
+					if let Some(page) = page {
+						self.submit_search(page, events);
+					}
+				}
+				if gateway.random_seed().is_some()
+					&& ui
+						.button("Reroll")
+						.on_hover_text("Search again with a new random order (Ctrl+F5)")
+						.clicked()
+				{
+					events.push(Event::Source(SourceEvent::Reroll));
+				}
+				let current = gateway.current_query();
+				// Favorites and sets aren't searches the API could have new posts for
+				if !current.trim().is_empty()
+					&& current != crate::gateway::query::LOCAL_FAVORITES
+					&& crate::gateway::query::set_id(current).is_none()
+				{
+					let watched = gateway.is_watched(current);
+					if ui
+						.selectable_label(watched, "Watch")
+						.on_hover_text(
+							"Check this search in the background and say when it has new posts",
+						)
+						.clicked()
+					{
+						events.push(Event::Gateway(GatewayEvent::WatchSearch {
+							query: current.to_string(),
+							watched: !watched,
+						}));
+					}
+				}
+				// Everything after the search controls stays on this row while it fits, most
+				// important first; the rest waits in the ⚙ menu. Widths are as last drawn, so
+				// font size and which controls are showing are taken into account.
+				let shown = |group: &TopGroup| *group != TopGroup::Coach || has_coach_deps;
+				let row_end = ui.max_rect().right();
+				let row_top = ui.cursor().top();
+				let overhead = SEPARATOR_WIDTH + 2.0 * ui.spacing().item_spacing.x;
+				let mut widths = self.top_group_widths;
+				let mut gear_width = self.top_gear_width;
+				let candidates: Vec<TopGroup> =
+					TopGroup::BY_PRIORITY.into_iter().filter(shown).collect();
+				let mut x = ui.cursor().left();
+				let mut fitting = Vec::new();
+				for (i, group) in candidates.iter().enumerate() {
+					x += widths[*group as usize] + overhead;
+					let gear = if i + 1 < candidates.len() {
+						gear_width + overhead
+					} else {
+						0.0
+					};
+					if x + gear > row_end {
+						break;
+					}
+					fitting.push(*group);
+				}
+				let (inline, collapsed): (Vec<TopGroup>, Vec<TopGroup>) = TopGroup::ALL
+					.into_iter()
+					.filter(shown)
+					.partition(|group| fitting.contains(group));
+
+				let mut render_group = |ui: &mut egui::Ui, group: TopGroup| match group {
+					TopGroup::Results => {
+						let mut show_pending = browser.show_pending();
+						if ui
+							.checkbox(&mut show_pending, "Show pending")
+							.on_hover_text(
+								"Keep posts awaiting approval in results (applies to new pages)",
+							)
+							.changed()
+						{
+							events.push(Event::Settings(SettingsEvent::SetShowPending {
+								enabled: show_pending,
+							}));
 						}
-						ImageFillMode::Fit => {
-							let width_ratio = available_size.x / img_size.x;
-							let height_ratio = available_size.y / img_size.y;
-							let scale = width_ratio.min(height_ratio) * self.user_zoom;
-							let base_display_size = img_size * scale;
 
-							ui.centered_and_justified(|ui| {
-								let (rect, _response) =
-									ui.allocate_exact_size(available_size, egui::Sense::hover());
+						if let Some(mut enabled) = browser.account_blacklist_enabled()
+							&& ui
+								.checkbox(&mut enabled, "Account blacklist")
+								.on_hover_text(
+									"Hide posts matching the blacklist saved on your e621 account",
+								)
+								.changed()
+						{
+							events.push(Event::Browser(BrowserEvent::ToggleAccountBlacklist));
+						}
 
-								let center = rect.center() + self.user_pan_offset;
-								let pulsed_size = base_display_size * pulse;
-								let pulsed_rect = egui::Rect::from_center_size(center, pulsed_size);
-								let uv = egui::Rect::from_min_max(
-									egui::pos2(0.0, 0.0),
-									egui::pos2(1.0, 1.0),
-								);
+						let mut wrap_navigation = browser.wrap_navigation();
+						if ui
+							.checkbox(&mut wrap_navigation, "Wrap around")
+							.on_hover_text(
+								"Prev on the first post jumps to the last one, and Next on the last to the first",
+							)
+							.changed()
+						{
+							events.push(Event::Settings(SettingsEvent::SetWrapNavigation {
+								enabled: wrap_navigation,
+							}));
+						}
 
-								ui.painter().image(
-									texture.id(),
-									pulsed_rect,
-									uv,
-									egui::Color32::WHITE,
-								);
-							});
+						ui.label("Pages up front:");
+						if let Some(pages) = drag::throttled(
+							ui,
+							"fanout_pages",
+							gateway.fanout_pages(),
+							|ui, pages| {
+								ui.add(egui::DragValue::new(pages).range(1..=MAX_FANOUT_PAGES))
+									.on_hover_text(
+										"Fetch this many pages as soon as a new search starts",
+									)
+							},
+						) {
+							events.push(Event::Gateway(GatewayEvent::SetFanoutPages { pages }));
 						}
-						ImageFillMode::FitToGallery => {
-							let len = browser.posts_len();
-							if len > 0 {
-								let new_idx = browser.current_index();
-								if new_idx != self.last_gallery_index {
-									let mut delta =
-										new_idx as isize - self.last_gallery_index as isize;
-									let ilen = len as isize;
-									if delta > ilen / 2 {
-										delta -= ilen;
-									} else if delta < -ilen / 2 {
-										delta += ilen;
+						ui.label("Fetch ahead:");
+						if let Some(posts) = drag::throttled(
+							ui,
+							"fetch_ahead",
+							browser.fetch_ahead(),
+							|ui, posts| {
+								ui.add(egui::DragValue::new(posts).range(1..=PREFETCH_AHEAD))
+									.on_hover_text(
+										"Ask for the next page once this few posts are left",
+									)
+							},
+						) {
+							events.push(Event::Settings(SettingsEvent::SetFetchAhead { posts }));
+						}
+						if !gateway.watches().is_empty() {
+							ui.menu_button(
+								format!("Watched ({})", gateway.watches().len()),
+								|ui| {
+									ui.horizontal(|ui| {
+										ui.label("Check every");
+										if let Some(minutes) = drag::throttled(
+											ui,
+											"watch_interval",
+											gateway.watch_interval_mins(),
+											|ui, minutes| {
+												ui.add(
+													egui::DragValue::new(minutes)
+														.range(MIN_WATCH_INTERVAL_MINS..=24 * 60)
+														.suffix(" min"),
+												)
+											},
+										) {
+											events.push(Event::Gateway(
+												GatewayEvent::SetWatchInterval { minutes },
+											));
+										}
+									});
+									for watch in gateway.watches() {
+										ui.horizontal(|ui| {
+											if ui
+												.small_button("✖")
+												.on_hover_text("Stop watching")
+												.clicked()
+											{
+												events.push(Event::Gateway(
+													GatewayEvent::WatchSearch {
+														query: watch.query.clone(),
+														watched: false,
+													},
+												));
+											}
+											if ui.button(&watch.query).clicked() {
+												events.push(Event::Source(
+													SourceEvent::OpenWatch {
+														query: watch.query.clone(),
+													},
+												));
+												ui.close_menu();
+											}
+										});
 									}
+								},
+							);
+						}
+						if let Some((loaded, total)) = self.page_progress {
+							ui.spinner();
+							ui.label(format!("Loading page {}/{}", loaded + 1, total));
+						}
 
-									let visual_delta = delta.signum() as f32;
-
-									self.gallery_anim_start_offset =
-										self.gallery_anim_offset + visual_delta;
-									self.gallery_anim_offset = self.gallery_anim_start_offset;
-									self.gallery_anim_time = 0.0;
-									self.last_gallery_index = new_idx;
+						// Reorders what's loaded without searching again
+						let (sort_key, sort_descending) = browser.sort();
+						let (mut key, mut descending) = (sort_key, sort_descending);
+						ui.label("Sort:");
+						egui::ComboBox::from_id_salt("local_sort")
+							.selected_text(key.label())
+							.show_ui(ui, |ui| {
+								for option in SortKey::ALL {
+									ui.selectable_value(&mut key, option, option.label());
 								}
-							}
+							});
+						if key != SortKey::ApiOrder
+							&& ui
+								.button(if descending { "⬇" } else { "⬆" })
+								.on_hover_text("Toggle descending/ascending")
+								.clicked()
+						{
+							descending = !descending;
+						}
+						if (key, descending) != (sort_key, sort_descending) {
+							events
+								.push(Event::Browser(BrowserEvent::SortLoaded { key, descending }));
+						}
+					}
+					TopGroup::Slideshow => {
+						ui.label("Quick settings:");
 
-							let anim_duration = 0.4;
-							if self.gallery_anim_time < anim_duration {
-								let dt = ctx.input(|i| i.stable_dt);
-								self.gallery_anim_time =
-									(self.gallery_anim_time + dt).min(anim_duration);
-								let t = self.gallery_anim_time / anim_duration;
-								let ease = if t < 0.5 {
-									4.0 * t * t * t
-								} else {
-									1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
-								};
-								self.gallery_anim_offset =
-									self.gallery_anim_start_offset * (1.0 - ease);
-								ctx.request_repaint();
-							} else {
-								self.gallery_anim_offset = 0.0;
-							}
+						let mut auto_play = settings.auto_play();
+						if ui.checkbox(&mut auto_play, "Auto-play").changed() {
+							events.push(Event::Settings(SettingsEvent::ToggleAutoPlay));
+						}
 
-							ui.centered_and_justified(|ui| {
-								let (rect, _response) =
-									ui.allocate_exact_size(available_size, egui::Sense::hover());
+						let mut cap_by_breathing = settings.cap_by_breathing();
+						if ui
+							.checkbox(&mut cap_by_breathing, "Sync with Breathing")
+							.changed()
+						{
+							events.push(Event::Settings(SettingsEvent::ToggleCapByBreathing));
+						}
 
-								let center_rect =
-									egui::Rect::from_min_size(rect.min, available_size);
+						let mut breath_hold = settings.breath_hold();
+						if ui
+							.checkbox(&mut breath_hold, "Hold on breath")
+							.on_hover_text("Keep the image while breathing in and holding")
+							.changed()
+						{
+							events.push(Event::Settings(SettingsEvent::ToggleBreathHold));
+						}
 
-								let get_fitted_width = |offset: isize| -> f32 {
-									if let Some(post) = browser.get_post_relative(offset) {
-										if let Some(crate::types::LoadedMedia::Image { texture }) =
-											media.get_media_by_post(post)
-										{
-											let size = texture.size_vec2();
-											let scale = (available_size.x / size.x)
-												.min(available_size.y / size.y);
-											return size.x * scale;
-										}
-									}
-									available_size.x
-								};
+						if settings.auto_play() {
+							let seconds = settings.auto_play_delay().as_secs_f32();
+							ui.label("Interval (s)");
+							if let Some(seconds) =
+								drag::throttled(ui, "auto_play_delay", seconds, |ui, seconds| {
+									ui.add(
+										egui::DragValue::new(seconds).range(1.0..=60.0).speed(1.0),
+									)
+								}) {
+								events.push(Event::Settings(SettingsEvent::SetDelay {
+									duration: Duration::from_secs_f32(seconds),
+								}));
+							}
+							for post in browser.peek_next(3) {
+								Self::readiness_dot(ui, media.readiness(post));
+							}
 
-								let virtual_center = -self.gallery_anim_offset;
-								let vc_floor = virtual_center.floor() as isize;
-								let vc_ceil = virtual_center.ceil() as isize;
-								let vc_fract = virtual_center - vc_floor as f32;
-
-								let w1 = get_fitted_width(vc_floor);
-								let w2 = get_fitted_width(vc_ceil);
-								let main_w = w1 + (w2 - w1) * vc_fract;
-
-								let gutter_w = ((available_size.x - main_w) / 2.0).max(0.0);
-								let left_gutter = egui::Rect::from_min_size(
-									rect.min,
-									egui::vec2(gutter_w, available_size.y),
-								);
-								let right_gutter = egui::Rect::from_min_size(
-									rect.min + egui::vec2(available_size.x - gutter_w, 0.0),
-									egui::vec2(gutter_w, available_size.y),
+							let mut auto_pause = settings.auto_pause();
+							let mut idle_secs = settings.auto_pause_idle().as_secs_f32();
+							let mut changed = ui
+								.checkbox(&mut auto_pause, "Pause on activity")
+								.on_hover_text(
+									"Hold auto-play while panning, zooming or navigating",
+								)
+								.changed();
+							if auto_pause
+								&& let Some(secs) =
+									drag::throttled(ui, "auto_pause_idle", idle_secs, |ui, secs| {
+										ui.add(
+											egui::DragValue::new(secs)
+												.range(5.0..=300.0)
+												.speed(1.0)
+												.suffix("s"),
+										)
+										.on_hover_text("Idle time before auto-play resumes")
+									}) {
+								idle_secs = secs;
+								changed = true;
+							}
+							if changed {
+								events.push(Event::Settings(SettingsEvent::SetAutoPause {
+									enabled: auto_pause,
+									idle_secs,
+								}));
+							}
+							if settings.advance_suppressed() {
+								ui.label(
+									egui::RichText::new("paused (user active)").weak().italics(),
 								);
+								// Clear the indicator once the idle period runs out
+								self.repaints
+									.after(RepaintCause::Status, Duration::from_secs(1));
+							}
+						}
+					}
+					TopGroup::Breathing => {
+						let mut breathing_enabled = breathing.is_visible();
 
-								let off_left =
-									left_gutter.translate(egui::vec2(-gutter_w - 100.0, 0.0));
-								let off_right =
-									right_gutter.translate(egui::vec2(gutter_w + 100.0, 0.0));
+						if ui.checkbox(&mut breathing_enabled, "Breathing").clicked() {
+							events.push(Event::View(ViewEvent::RequestBreathingToggle));
+						}
+						if breathing_enabled && breathing.is_in_active_cycle() {
+							ui.label(
+								egui::RichText::new(format!("cycle {}", breathing.cycle_count()))
+									.weak(),
+							);
+						}
 
-								let fit_rect =
-									|img_size: egui::Vec2, space: egui::Rect| -> egui::Rect {
-										if space.width() <= 0.01 || space.height() <= 0.01 {
-											return egui::Rect::from_center_size(
-												space.center(),
-												egui::Vec2::ZERO,
-											);
-										}
-										let width_ratio = space.width() / img_size.x;
-										let height_ratio = space.height() / img_size.y;
-										let scale = width_ratio.min(height_ratio) * self.user_zoom;
-										let size = img_size * scale;
-										egui::Rect::from_center_size(
-											space.center() + self.user_pan_offset,
-											size,
+						if breathing_enabled {
+							ui.label("Idle");
+							if let Some(value) = drag::throttled(
+								ui,
+								"idle_multiplier",
+								breathing.idle_multiplier(),
+								|ui, value| {
+									ui.add(
+										egui::DragValue::new(value)
+											.range(IDLE_MULTIPLIER_RANGE)
+											.speed(0.1),
+									)
+								},
+							) {
+								events.push(Event::Breathing(BreathingEvent::SetIdleMultiplier {
+									value,
+								}));
+							}
+
+							let current_style = breathing.style();
+							let style_label = match current_style {
+								BreathingStyle::Classic => "Classic",
+								BreathingStyle::Immersive => "Immersive",
+							};
+							egui::ComboBox::from_id_salt("breathing_style")
+								.selected_text(style_label)
+								.show_ui(ui, |ui| {
+									if ui
+										.selectable_label(
+											current_style == BreathingStyle::Classic,
+											"Classic",
 										)
-									};
+										.clicked()
+									{
+										events.push(Event::Breathing(BreathingEvent::SetStyle {
+											style: BreathingStyle::Classic,
+										}));
+									}
+									if ui
+										.selectable_label(
+											current_style == BreathingStyle::Immersive,
+											"Immersive",
+										)
+										.clicked()
+									{
+										events.push(Event::Breathing(BreathingEvent::SetStyle {
+											style: BreathingStyle::Immersive,
+										}));
+									}
+								});
+							if current_style == BreathingStyle::Immersive {
+								Self::render_image_zoom_setting(ui, breathing, events);
+							}
 
-								let cover_rect =
-									|img_size: egui::Vec2, space: egui::Rect| -> egui::Rect {
-										if space.width() <= 0.01 || space.height() <= 0.01 {
-											return egui::Rect::from_center_size(
-												space.center(),
-												egui::Vec2::ZERO,
-											);
+							Self::render_palette_picker(ui, breathing.palette(), events);
+
+							ui.menu_button("Advanced", |ui| {
+								Self::render_overlay_layout_settings(ui, breathing, events);
+								ui.separator();
+								Self::render_pulse_settings(ui, breathing, events);
+								ui.separator();
+								ui.label("Longest inhale + hold (s)");
+								if let Some(secs) = drag::throttled(
+									ui,
+									"max_inhale_hold",
+									breathing.max_inhale_hold().as_secs_f32(),
+									|ui, secs| {
+										ui.add(egui::Slider::new(secs, 10.0..=60.0).step_by(1.0))
+											.on_hover_text(
+												"Longer phases are cut short; Esc ends a cycle early",
+											)
+									},
+								) {
+									events.push(Event::Breathing(
+										BreathingEvent::SetMaxInhaleHold { secs },
+									));
+								}
+							});
+						}
+					}
+					TopGroup::Pan => {
+						let mut pan_speed = self.auto_pan_cycle_duration;
+						ui.label("Pan Speed (s)");
+						if ui
+							.add(
+								egui::DragValue::new(&mut pan_speed)
+									.range(10.0..=120.0)
+									.speed(1.0),
+							)
+							.changed()
+						{
+							self.auto_pan_cycle_duration = pan_speed;
+						}
+						ui.label("Key pan");
+						ui.add(
+							egui::DragValue::new(&mut self.key_pan_speed)
+								.range(200.0..=4000.0)
+								.speed(10.0)
+								.suffix(" pt/s"),
+						)
+						.on_hover_text("How fast the arrow and WASD keys pan the image");
+						egui::ComboBox::from_id_salt("pan_mode")
+							.selected_text(self.pan_mode.label())
+							.show_ui(ui, |ui| {
+								for mode in [
+									PanMode::PingPong,
+									PanMode::TopToBottomOnce,
+									PanMode::LeftToRightOnce,
+									PanMode::KEN_BURNS,
+								] {
+									let selected = std::mem::discriminant(&self.pan_mode)
+										== std::mem::discriminant(&mode);
+									if ui.selectable_label(selected, mode.label()).clicked()
+										&& !selected
+									{
+										self.pan_mode = mode;
+									}
+								}
+							})
+							.response
+							.on_hover_text(
+								"One-shot pans last as long as the slideshow delay while auto-play is on",
+							);
+						if let PanMode::KenBurns { zoom_range } = &mut self.pan_mode {
+							ui.label("Zoom");
+							ui.add(
+								egui::DragValue::new(zoom_range)
+									.range(0.05..=0.5)
+									.speed(0.01),
+							);
+						}
+					}
+					TopGroup::Display => {
+						let mut reduce_motion = settings.reduce_motion();
+						if ui
+							.checkbox(&mut reduce_motion, "Reduce motion")
+							.on_hover_text(
+								"Static breathing text, instant transitions, slower auto-pan, no beat zoom",
+							)
+							.changed()
+						{
+							events.push(Event::Settings(SettingsEvent::ToggleReduceMotion));
+						}
+						let mut safe_mode = settings.safe_mode();
+						let response = ui.add_enabled(
+							!settings.safe_mode_locked(),
+							egui::Checkbox::new(&mut safe_mode, "Safe mode"),
+						);
+						if response
+							.on_hover_text(
+								"Only rating:s posts, whatever rating the query asks for",
+							)
+							.on_disabled_hover_text("Locked on by --sfw")
+							.changed()
+						{
+							events.push(Event::Settings(SettingsEvent::ToggleSafeMode));
+						}
+						let mut high_contrast = settings.high_contrast();
+						if ui
+							.checkbox(&mut high_contrast, "High contrast")
+							.on_hover_text("White overlay text with a thick solid outline")
+							.changed()
+						{
+							events.push(Event::Settings(SettingsEvent::ToggleHighContrast));
+						}
+						ui.checkbox(&mut self.remember_view_positions, "Remember position")
+							.on_hover_text(
+								"Return to where you left an image when going back to it",
+							);
+						ui.checkbox(&mut self.wheel_navigation, "Wheel browses")
+							.on_hover_text(
+								"Mouse wheel moves one image per notch instead of scrolling",
+							);
+						ui.checkbox(&mut self.show_status_bar, "Status bar")
+							.on_hover_text("Show loading, cache and API stats at the bottom");
+						let mut reading = settings.reading_mode();
+						if ui
+							.checkbox(&mut reading, "Reading mode")
+							.on_hover_text(
+								"Fit pages to the width; Space and PageDown scroll down, \
+								then go on to the next post. Holds the slideshow.",
+							)
+							.changed()
+						{
+							events.push(Event::Settings(SettingsEvent::ToggleReadingMode));
+						}
+						ui.checkbox(&mut self.show_filmstrip, "Filmstrip")
+							.on_hover_text(
+								"Thumbnails of the posts before and after this one; click one to go there",
+							);
+						if ui
+							.checkbox(&mut self.reveal.enabled, "Reveal mode")
+							.on_hover_text(
+								"Show new images blurred until V, hovering or the slideshow reveals them",
+							)
+							.changed()
+						{
+							events.push(Event::Media(MediaEvent::SetBlurPreviews {
+								enabled: self.reveal.enabled,
+							}));
+						}
+						if self.reveal.enabled {
+							let mut delay = self.reveal.auto_delay.as_secs_f32();
+							if ui
+								.add(
+									egui::DragValue::new(&mut delay)
+										.range(0.0..=60.0)
+										.speed(0.1)
+										.suffix("s"),
+								)
+								.on_hover_text("The slideshow reveals images after this long")
+								.changed()
+							{
+								self.reveal.auto_delay = Duration::from_secs_f32(delay);
+							}
+						}
+						ui.menu_button("Info", |ui| {
+							Self::render_info_overlay_settings(ui, settings, events);
+						})
+						.response
+						.on_hover_text("Post info overlay (I cycles full/compact/hidden)");
+						ui.menu_button("Credit", |ui| {
+							Self::render_credit_settings(ui, settings, events);
+						})
+						.response
+						.on_hover_text("Artist credit in a corner, for streaming or recording");
+						ui.menu_button("Theme", |ui| {
+							Self::render_theme_settings(ui, settings.theme(), events);
+						})
+						.response
+						.on_hover_text("Accent color, panel opacity, dark or light");
+						ui.menu_button("Overlays", |ui| {
+							ui.checkbox(&mut self.overlays.hide_all, "Hide overlays (H)")
+								.on_hover_text("Only the image, plus whatever you open yourself");
+							ui.separator();
+							ui.add_enabled_ui(!self.overlays.hide_all, |ui| {
+								for name in self.overlays.hideable_names() {
+									let mut visible = self.overlays.is_visible(name);
+									if ui.checkbox(&mut visible, name).changed() {
+										self.overlays.set_visible(name, visible);
+									}
+								}
+							});
+						})
+						.response
+						.on_hover_text("Choose what's drawn over the image");
+					}
+					TopGroup::Fill => {
+						let current_fill = self.image_fill_mode;
+						egui::ComboBox::from_id_salt("image_fill_mode")
+							.selected_text(current_fill.label())
+							.show_ui(ui, |ui| {
+								for mode in ImageFillMode::ALL {
+									if ui
+										.selectable_label(current_fill == mode, mode.label())
+										.clicked()
+									{
+										events.push(Event::View(ViewEvent::SetImageFillMode {
+											mode,
+										}));
+									}
+								}
+							});
+					}
+					TopGroup::Menus => {
+						self.render_download_controls(ui, browser, media, events);
+						Self::render_backup_menu(ui, events);
+						self.render_favorites_menu(ui, events);
+						Self::render_startup_menu(ui, settings, events);
+						Self::render_help_menu(ui, events);
+					}
+					TopGroup::Audio => {
+						ui.label("Audio:");
+						let selected_label = beat.selected_device_label();
+						egui::ComboBox::from_id_salt("audio_device")
+							.selected_text(selected_label)
+							.show_ui(ui, |ui| {
+								if ui
+									.selectable_label(beat.selected_device().is_none(), "Default")
+									.clicked()
+								{
+									events.push(Event::Beat(BeatEvent::SetDevice { name: None }));
+								}
+								for device_name in beat.device_names() {
+									let is_selected = beat.selected_device().as_deref()
+										== Some(device_name.as_str());
+									if ui.selectable_label(is_selected, device_name).clicked() {
+										events.push(Event::Beat(BeatEvent::SetDevice {
+											name: Some(device_name.clone()),
+										}));
+									}
+								}
+							});
+						if beat.is_active() && beat.is_silent() {
+							ui.label(
+								egui::RichText::new("*")
+									.color(egui::Color32::GRAY)
+									.size(10.0),
+							)
+							.on_hover_text("Nothing heard on this device for a while");
+						} else if beat.is_active() {
+							ui.label(
+								egui::RichText::new("*")
+									.color(egui::Color32::GREEN)
+									.size(10.0),
+							);
+							match beat.lock_state() {
+								LockState::Locked { bpm } => {
+									ui.label(
+										egui::RichText::new(format!("{:.0} BPM", bpm)).small(),
+									)
+									.on_hover_text("Locked onto a steady beat");
+								}
+								LockState::Locking => {
+									ui.label(egui::RichText::new("locking").small().weak())
+										.on_hover_text("Waiting for evenly spaced beats");
+								}
+								LockState::Unlocked => {}
+							}
+						} else {
+							ui.label(
+								egui::RichText::new("*")
+									.color(egui::Color32::RED)
+									.size(10.0),
+							);
+						}
+
+						ui.checkbox(&mut self.beat_pulse_enabled, "Pulse");
+						if self.beat_pulse_enabled {
+							ui.label("Scale");
+							ui.add(
+								egui::DragValue::new(&mut self.beat_pulse_scale)
+									.range(0.01..=0.15)
+									.speed(0.01),
+							);
+						}
+					}
+					TopGroup::Coach => {
+						ui.checkbox(&mut self.coach_enabled, "Coach");
+						if self.coach_enabled {
+							// Render combo box for model
+							let models = if let Some(dir) = &models_dir {
+								std::fs::read_dir(dir)
+									.into_iter()
+									.flatten()
+									.filter_map(|e| e.ok())
+									.map(|e| e.file_name().to_string_lossy().to_string())
+									.filter(|f| f.ends_with(".gguf"))
+									.collect::<Vec<_>>()
+							} else {
+								vec![]
+							};
+
+							let selected_model =
+								self.coach_model.as_deref().unwrap_or("Select Model");
+							egui::ComboBox::from_id_salt("coach_model")
+								.selected_text(selected_model)
+								.show_ui(ui, |ui| {
+									for m in models {
+										if ui
+											.selectable_label(
+												self.coach_model.as_ref() == Some(&m),
+												&m,
+											)
+											.clicked()
+										{
+											self.coach_model = Some(m);
 										}
-										let width_ratio = space.width() / img_size.x;
-										let height_ratio = space.height() / img_size.y;
-										let scale = width_ratio.max(height_ratio);
-										let size = img_size * scale;
-										egui::Rect::from_center_size(space.center(), size)
-									};
+									}
+								});
 
-								let get_rect_at = |slot: isize, size: egui::Vec2| -> egui::Rect {
-									match slot {
-										..=-2 => cover_rect(size, off_left),
-										-1 => cover_rect(size, left_gutter),
-										0 => fit_rect(size, center_rect),
-										1 => cover_rect(size, right_gutter),
-										2.. => cover_rect(size, off_right),
+							// Render combo box for preset
+							let presets = if let Some(dir) = &presets_dir {
+								std::fs::read_dir(dir)
+									.into_iter()
+									.flatten()
+									.filter_map(|e| e.ok())
+									.map(|e| e.file_name().to_string_lossy().to_string())
+									.filter(|f| f.ends_with(".toml"))
+									.collect::<Vec<_>>()
+							} else {
+								vec![]
+							};
+
+							let selected_preset =
+								self.coach_preset.as_deref().unwrap_or("Select Preset");
+							egui::ComboBox::from_id_salt("coach_preset")
+								.selected_text(selected_preset)
+								.show_ui(ui, |ui| {
+									for p in presets {
+										if ui
+											.selectable_label(
+												self.coach_preset.as_ref() == Some(&p),
+												&p,
+											)
+											.clicked()
+										{
+											self.coach_preset = Some(p);
+										}
 									}
-								};
+								});
+						}
+					}
+				};
 
-								let get_clip_at = |slot: isize| -> egui::Rect {
-									match slot {
-										..=-2 => off_left,
-										-1 => left_gutter,
-										0 => center_rect,
-										1 => right_gutter,
-										2.. => off_right,
+				let mut overflowed = false;
+				for group in &inline {
+					ui.separator();
+					let rect = ui.horizontal(|ui| render_group(ui, *group)).response.rect;
+					widths[*group as usize] = rect.width();
+					// Wrapped onto a row of its own, or past the edge
+					overflowed |= rect.right() > row_end
+						|| rect.top() >= row_top + ui.spacing().interact_size.y;
+				}
+				if !collapsed.is_empty() {
+					ui.separator();
+					let response = ui
+						.menu_button("⚙", |ui| {
+							// Rows wrap rather than run off a small screen
+							let screen = ctx.screen_rect();
+							ui.set_max_width(screen.width() * 0.9);
+							ScrollArea::vertical()
+								.max_height(screen.height() * 0.8)
+								.show(ui, |ui| {
+									for (i, group) in collapsed.iter().enumerate() {
+										if i > 0 {
+											ui.separator();
+										}
+										let rect = ui
+											.horizontal_wrapped(|ui| render_group(ui, *group))
+											.response
+											.rect;
+										widths[*group as usize] = rect.width();
 									}
-								};
+								});
+						})
+						.response
+						.on_hover_text("More controls; a wider window shows them on this row");
+					gear_width = response.rect.width();
+				}
+				if overflowed {
+					ctx.request_discard("Top panel controls overflowed");
+				}
+				self.top_group_widths = widths;
+				self.top_gear_width = gear_width;
+			});
 
-								for offset in [-2, -1, 1, 2, 0] {
-									let v = offset as f32 + self.gallery_anim_offset;
+			// Inline hint for queries rejected before sending
+			for warning in &self.query_warnings {
+				ui.label(
+					egui::RichText::new(warning.to_string())
+						.color(egui::Color32::RED)
+						.small(),
+				);
+			}
+		});
+	}
 
-									// Only draw if within visible slots roughly
-									if v < -2.5 || v > 2.5 {
-										continue;
-									}
+	/// The search run on its own once the terms are accepted
+	fn render_startup_menu(ui: &mut egui::Ui, settings: &SettingsManager, events: &mut Vec<Event>) {
+		ui.menu_button("Startup", |ui| {
+			ui.label("Once the terms are accepted:");
+			let current = settings.startup_search();
+			for behavior in StartupSearch::ALL {
+				if ui.radio(current == behavior, behavior.label()).clicked() {
+					events.push(Event::Settings(SettingsEvent::SetStartupSearch {
+						behavior,
+					}));
+				}
+			}
+			ui.separator();
+			ui.label("Default query:");
+			let mut query = settings.default_query().to_string();
+			if ui.text_edit_singleline(&mut query).changed() {
+				events.push(Event::Settings(SettingsEvent::SetDefaultQuery { query }));
+			}
+		});
+	}
 
-									if let Some(post) = browser.get_post_relative(offset) {
-										if let Some(crate::types::LoadedMedia::Image {
-											texture: off_texture,
-										}) = media.get_media_by_post(post)
-										{
-											let img_size = off_texture.size_vec2();
+	/// Checks for when something doesn't work
+	fn render_help_menu(ui: &mut egui::Ui, events: &mut Vec<Event>) {
+		ui.menu_button("Help", |ui| {
+			if ui
+				.button("Run diagnostics...")
+				.on_hover_text(
+					"Check the connection to e621, image loading, audio devices and the settings folder",
+				)
+				.clicked()
+			{
+				ui.close_menu();
+				events.push(Event::Settings(SettingsEvent::RunDiagnostics));
+			}
+		});
+	}
+
+	/// Export and import of the blacklist and settings, for moving them between machines
+	fn render_backup_menu(ui: &mut egui::Ui, events: &mut Vec<Event>) {
+		const FILE_NAME: &str = "sodglumate-settings.json";
+
+		ui.menu_button("Backup", |ui| {
+			if ui
+				.button("Export settings...")
+				.on_hover_text("Blacklist and settings; your API key is left out")
+				.clicked()
+			{
+				ui.close_menu();
+				if let Some(path) = rfd::FileDialog::new()
+					.add_filter("JSON", &["json"])
+					.set_file_name(FILE_NAME)
+					.save_file()
+				{
+					events.push(Event::Settings(SettingsEvent::ExportConfig { path }));
+				}
+			}
+			for (label, hover, replace) in [
+				(
+					"Import and merge...",
+					"Add the file's blacklist tags and take its settings",
+					false,
+				),
+				(
+					"Import and replace...",
+					"Use only the file's blacklist; settings it lacks go back to defaults",
+					true,
+				),
+			] {
+				if ui.button(label).on_hover_text(hover).clicked() {
+					ui.close_menu();
+					if let Some(path) = rfd::FileDialog::new()
+						.add_filter("JSON", &["json"])
+						.pick_file()
+					{
+						events.push(Event::Settings(SettingsEvent::ImportConfig {
+							path,
+							replace,
+						}));
+					}
+				}
+			}
+		});
+	}
+
+	/// Posts favorited with F, kept on this machine rather than on an account
+	fn render_favorites_menu(&mut self, ui: &mut egui::Ui, events: &mut Vec<Event>) {
+		ui.menu_button("Favorites", |ui| {
+			if ui
+				.button("Show local favorites")
+				.on_hover_text(format!(
+					"Browse the posts favorited with F (search `{}`)",
+					crate::gateway::query::LOCAL_FAVORITES
+				))
+				.clicked()
+			{
+				ui.close_menu();
+				self.search_query = crate::gateway::query::LOCAL_FAVORITES.to_string();
+				self.search_page_input = "1".to_string();
+				self.submit_search(1, events);
+			}
+			if ui
+				.button("Refresh from e621")
+				.on_hover_text("Fetch every favorite again for current scores and tags")
+				.clicked()
+			{
+				ui.close_menu();
+				events.push(Event::Favorites(FavoritesEvent::Refresh));
+			}
+			if ui
+				.button("Export as URL list...")
+				.on_hover_text("One e621 post link per line")
+				.clicked()
+			{
+				ui.close_menu();
+				if let Some(path) = rfd::FileDialog::new()
+					.add_filter("Text", &["txt"])
+					.set_file_name("sodglumate-favorites.txt")
+					.save_file()
+				{
+					events.push(Event::Favorites(FavoritesEvent::Export { path }));
+				}
+			}
+		});
+	}
+
+	/// Batch download launcher and progress for the current result set
+	fn render_download_controls(
+		&self,
+		ui: &mut egui::Ui,
+		browser: &ContentBrowser,
+		media: &MediaCache,
+		events: &mut Vec<Event>,
+	) {
+		const DOWNLOAD_BATCH: usize = 50;
+		/// Downloading more than this many posts at once asks first
+		const DOWNLOAD_CONFIRM_OVER: usize = 100;
+
+		ui.add_enabled_ui(!browser.is_empty(), |ui| {
+			ui.menu_button("Download", |ui| {
+				let Some(dest) = crate::config::get_downloads_dir() else {
+					ui.label("No downloads directory available");
+					return;
+				};
+				let from_index = browser.current_index();
+				let remaining = browser.posts_len().saturating_sub(from_index);
+
+				if ui.button("Current post").clicked() {
+					events.push(Event::Media(MediaEvent::QueueDownloadRange {
+						from_index,
+						count: 1,
+						dest: dest.clone(),
+					}));
+					ui.close_menu();
+				}
+				if ui
+					.button(format!("Next {} posts", DOWNLOAD_BATCH.min(remaining)))
+					.clicked()
+				{
+					events.push(Event::Media(MediaEvent::QueueDownloadRange {
+						from_index,
+						count: DOWNLOAD_BATCH,
+						dest: dest.clone(),
+					}));
+					ui.close_menu();
+				}
+				if ui
+					.button(format!("All loaded posts ({})", browser.posts_len()))
+					.clicked()
+				{
+					let download = Event::Media(MediaEvent::QueueDownloadRange {
+						from_index: 0,
+						count: browser.posts_len(),
+						dest,
+					});
+					// Big batches take a while and fill the disk; ask first
+					if browser.posts_len() > DOWNLOAD_CONFIRM_OVER {
+						events.push(Event::View(ViewEvent::ShowModal {
+							kind: ModalKind::confirm(
+								"Download all loaded posts?",
+								&format!("This queues {} files for download.", browser.posts_len()),
+								"Download",
+								download,
+							),
+						}));
+					} else {
+						events.push(download);
+					}
+					ui.close_menu();
+				}
+			});
+		});
+
+		let progress = media.download_progress();
+		if progress.is_active() {
+			let mut text = format!("{}/{}", progress.completed, progress.total);
+			if progress.failed > 0 {
+				text.push_str(&format!(" ({} failed)", progress.failed));
+			}
+			ui.label(text);
+
+			let paused = media.downloads_paused();
+			if ui
+				.small_button(if paused { "Resume" } else { "Pause" })
+				.clicked()
+			{
+				events.push(Event::Media(MediaEvent::SetDownloadsPaused {
+					paused: !paused,
+				}));
+			}
+			if ui.small_button("Cancel").clicked() {
+				events.push(Event::Media(MediaEvent::CancelDownloads));
+			}
+		}
+	}
+
+	/// Preset combo plus one swatch per phase for custom colors
+	fn render_palette_picker(
+		ui: &mut egui::Ui,
+		palette: BreathingPalette,
+		events: &mut Vec<Event>,
+	) {
+		let mut selected = None;
+		egui::ComboBox::from_id_salt("breathing_palette")
+			.selected_text(palette.preset_name().unwrap_or("Custom"))
+			.show_ui(ui, |ui| {
+				for (name, preset) in BreathingPalette::PRESETS {
+					if ui.selectable_label(palette == preset, name).clicked() {
+						selected = Some(preset);
+					}
+				}
+			});
+
+		let mut edited = palette;
+		let mut changed = false;
+		for (color, phase) in [
+			(&mut edited.prepare, "Prepare"),
+			(&mut edited.inhale, "Inhale"),
+			(&mut edited.hold, "Hold"),
+			(&mut edited.release, "Release"),
+		] {
+			changed |= ui
+				.color_edit_button_srgba(color)
+				.on_hover_text(phase)
+				.changed();
+		}
+		if changed {
+			selected = Some(edited);
+		}
+
+		if let Some(p) = selected {
+			events.push(Event::Breathing(BreathingEvent::SetPalette {
+				prepare: p.prepare,
+				inhale: p.inhale,
+				hold: p.hold,
+				release: p.release,
+			}));
+		}
+	}
+
+	/// Accent presets and picker, top panel opacity and dark mode
+	fn render_theme_settings(ui: &mut egui::Ui, theme: Theme, events: &mut Vec<Event>) {
+		let mut edited = theme;
+		ui.horizontal(|ui| {
+			egui::ComboBox::from_id_salt("theme_accent")
+				.selected_text(theme.accent_name().unwrap_or("Custom"))
+				.show_ui(ui, |ui| {
+					for (name, accent) in Theme::ACCENTS {
+						if ui.selectable_label(theme.accent == accent, name).clicked() {
+							edited.accent = accent;
+						}
+					}
+				});
+			if let Some(accent) = drag::throttled(ui, "accent_picker", edited.accent, |ui, v| {
+				let mut response = ui.color_edit_button_srgba(v).on_hover_text("Accent color");
+				// The picker's sliders are in a popup, so the button never sees them dragged
+				response.dragged |= ui.ctx().dragged_id().is_some();
+				response
+			}) {
+				edited.accent = accent;
+			}
+		});
+		if let Some(opacity) = drag::throttled(ui, "panel_opacity", theme.panel_opacity, |ui, v| {
+			ui.add(
+				egui::Slider::new(v, Theme::MIN_PANEL_OPACITY..=1.0)
+					.text("Panel opacity")
+					.fixed_decimals(2),
+			)
+		}) {
+			edited.panel_opacity = opacity;
+		}
+		ui.checkbox(&mut edited.dark, "Dark mode");
+
+		if edited != theme {
+			events.push(Event::Settings(SettingsEvent::SetTheme {
+				accent: edited.accent,
+				panel_opacity: edited.panel_opacity,
+				dark: edited.dark,
+			}));
+		}
+	}
+
+	/// Overlay corner and size
+	fn render_overlay_layout_settings(
+		ui: &mut egui::Ui,
+		breathing: &BreathingOverlay,
+		events: &mut Vec<Event>,
+	) {
+		let current = breathing.anchor();
+		ui.label("Overlay position");
+		egui::ComboBox::from_id_salt("breathing_anchor")
+			.selected_text(current.label())
+			.show_ui(ui, |ui| {
+				for corner in OverlayAnchor::ALL {
+					if ui
+						.selectable_label(current == corner, corner.label())
+						.clicked()
+					{
+						events.push(Event::Breathing(BreathingEvent::SetOverlayAnchor {
+							corner,
+						}));
+					}
+				}
+			});
+
+		ui.label("Overlay size");
+		if let Some(scale) = drag::throttled(
+			ui,
+			"overlay_scale",
+			breathing.overlay_scale(),
+			|ui, scale| ui.add(egui::Slider::new(scale, 0.5..=2.0).step_by(0.05)),
+		) {
+			events.push(Event::Breathing(BreathingEvent::SetOverlayScale { scale }));
+		}
+	}
+
+	/// How far the immersive style zooms the image in with the breath
+	fn render_image_zoom_setting(
+		ui: &mut egui::Ui,
+		breathing: &BreathingOverlay,
+		events: &mut Vec<Event>,
+	) {
+		ui.label("Zoom:");
+		if let Some(strength) = drag::throttled(
+			ui,
+			"breathing_image_zoom",
+			breathing.image_zoom(),
+			|ui, strength| {
+				ui.add(
+					egui::Slider::new(strength, 0.0..=MAX_IMAGE_ZOOM)
+						.custom_formatter(|v, _| format!("{:.1}%", v * 100.0))
+						.custom_parser(|s| {
+							s.trim_end_matches('%')
+								.parse::<f64>()
+								.ok()
+								.map(|v| v / 100.0)
+						}),
+				)
+				.on_hover_text(
+					"The image zooms in on the inhale and back out on the release; 0 turns it off",
+				)
+			},
+		) {
+			events.push(Event::Breathing(BreathingEvent::SetImageZoom { strength }));
+		}
+	}
+
+	/// Sliders for the classic style's center-screen phase pulse
+	fn render_pulse_settings(
+		ui: &mut egui::Ui,
+		breathing: &BreathingOverlay,
+		events: &mut Vec<Event>,
+	) {
+		ui.label("Pulse duration (s)");
+		let duration_secs = drag::throttled(
+			ui,
+			"pulse_duration",
+			breathing.pulse_duration_secs(),
+			|ui, secs| ui.add(egui::Slider::new(secs, 0.5..=4.0).step_by(0.1)),
+		);
+		ui.label("Pulse size (% of screen height)");
+		let scale = drag::throttled(ui, "pulse_scale", breathing.pulse_scale(), |ui, scale| {
+			ui.add(
+				egui::Slider::new(scale, 0.05..=0.30)
+					.custom_formatter(|v, _| format!("{:.0}", v * 100.0))
+					.custom_parser(|s| s.parse::<f64>().ok().map(|v| v / 100.0)),
+			)
+		});
+
+		if duration_secs.is_some() || scale.is_some() {
+			events.push(Event::Breathing(BreathingEvent::SetPulseParams {
+				duration_secs: duration_secs.unwrap_or(breathing.pulse_duration_secs()),
+				scale: scale.unwrap_or(breathing.pulse_scale()),
+			}));
+		}
+	}
+
+	fn render_central_panel(
+		&mut self,
+		ctx: &egui::Context,
+		browser: &ContentBrowser,
+		media: &mut MediaCache,
+		gateway: &BooruGateway,
+		events: &mut Vec<Event>,
+		enabled: bool,
+	) {
+		egui::CentralPanel::default().show(ctx, |ui| {
+			if !enabled {
+				ui.disable();
+			}
+			let panel_rect = ui.max_rect();
+			if gateway.is_loading() && browser.is_empty() {
+				ui.centered_and_justified(|ui| {
+					ui.spinner();
+				});
+			} else if let Some(err) = self.error_msg.as_ref().filter(|_| browser.is_empty()) {
+				ui.label(egui::RichText::new(err).color(egui::Color32::RED));
+			} else if let Some(message) = self
+				.load_failure
+				.as_ref()
+				.filter(|(key, _)| media.current_url() == Some(key.as_str()))
+				.map(|(_, message)| message.clone())
+			{
+				let mut action = None;
+				ui.vertical_centered(|ui| {
+					ui.add_space(ui.available_height() * 0.4);
+					ui.label(egui::RichText::new(message).color(egui::Color32::RED));
+					ui.horizontal(|ui| {
+						if ui.button("Skip").clicked() {
+							action = Some(Event::Source(SourceEvent::Navigate(NavDirection::Next)));
+						}
+						if ui.button("Retry").clicked() {
+							action = Some(Event::Media(MediaEvent::RetryCurrent));
+						}
+					});
+				});
+				if let Some(event) = action {
+					if matches!(event, Event::Media(MediaEvent::RetryCurrent)) {
+						self.load_failure = None;
+					}
+					events.push(event);
+				}
+			} else if let Some(_url) = media.current_url() {
+				let (media_rect, pinned_rect) = if self.pinned.is_some() {
+					let (left, right) = panel_rect.split_left_right_at_fraction(0.5);
+					(left, Some(right))
+				} else {
+					(panel_rect, None)
+				};
+				// Before the image, so its scroll area never sees a consumed wheel
+				self.handle_wheel_navigation(ui, media_rect, events);
+				match pinned_rect {
+					Some(pinned_rect) => {
+						ui.allocate_new_ui(egui::UiBuilder::new().max_rect(media_rect), |ui| {
+							// Gallery neighbours would otherwise slide under the pinned pane
+							ui.set_clip_rect(media_rect.intersect(ui.clip_rect()));
+							self.render_media(ui, ctx, media, browser);
+						});
+						ui.allocate_new_ui(egui::UiBuilder::new().max_rect(pinned_rect), |ui| {
+							self.render_pinned(ui, media);
+						});
+					}
+					None => self.render_media(ui, ctx, media, browser),
+				}
+				self.render_edge_zones(ui, media_rect, events);
+			} else {
+				ui.centered_and_justified(|ui| {
+					ui.label("Enter a query and search to start.");
+				});
+			}
+		});
+
+		// Render Coach Overlay
+		if !self.coach_logs.is_empty() {
+			let screen_height = ctx.screen_rect().height();
+			let base_font_size = (screen_height * 0.03).max(16.0);
+			let font_size = base_font_size * 0.75;
+			let margin = (screen_height * 0.03).max(10.0);
+
+			let info_overlay_height = base_font_size * 5.0;
+			let offset_y = -margin - info_overlay_height;
+
+			egui::Area::new(egui::Id::new("coach_overlay"))
+				.anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(margin, offset_y))
+				.interactable(false)
+				.order(egui::Order::Foreground)
+				.show(ctx, |ui| {
+					let recent_logs = self.coach_logs.iter().rev().take(20).rev();
+					let high_contrast = self.overlay_style.high_contrast;
+					let (outline_color, text_color) = if high_contrast {
+						(egui::Color32::BLACK, egui::Color32::WHITE)
+					} else {
+						// Muted terminal green
+						(
+							egui::Color32::from_black_alpha(204),
+							egui::Color32::from_rgb(180, 220, 180),
+						)
+					};
+					let font_id = egui::FontId::monospace(font_size);
+					let stroke_width = (font_size * 0.06).max(1.0).min(2.0) // Not too thick
+						* if high_contrast { HIGH_CONTRAST_STROKE } else { 1.0 };
+
+					let offsets = [
+						egui::vec2(-stroke_width, -stroke_width),
+						egui::vec2(0.0, -stroke_width),
+						egui::vec2(stroke_width, -stroke_width),
+						egui::vec2(-stroke_width, 0.0),
+						egui::vec2(stroke_width, 0.0),
+						egui::vec2(-stroke_width, stroke_width),
+						egui::vec2(0.0, stroke_width),
+						egui::vec2(stroke_width, stroke_width),
+					];
+
+					for msg in recent_logs {
+						let text = msg.clone();
+						let galley =
+							ui.painter()
+								.layout_no_wrap(text.clone(), font_id.clone(), text_color);
+						let shadow_galley = ui.painter().layout_no_wrap(
+							text.clone(),
+							font_id.clone(),
+							outline_color,
+						);
+						let (rect, _) = ui.allocate_exact_size(galley.size(), egui::Sense::hover());
+
+						for offset in offsets {
+							ui.painter().galley(
+								rect.min + offset,
+								shadow_galley.clone(),
+								outline_color,
+							);
+						}
+						ui.painter().galley(rect.min, galley, text_color);
+					}
+				});
+		}
+
+		// R also forces full-res, so only show this when the coach is running
+		if self.coach_enabled && ctx.input(|i| i.key_down(egui::Key::R)) {
+			egui::Area::new(egui::Id::new("coach_debug"))
+				.anchor(egui::Align2::LEFT_TOP, egui::vec2(20.0, 20.0))
+				.interactable(false)
+				.order(egui::Order::Foreground)
+				.show(ctx, |ui| {
+					egui::Frame::window(&ctx.style())
+						.fill(egui::Color32::from_black_alpha(220))
+						.inner_margin(12.0)
+						.rounding(8.0)
+						.show(ui, |ui| {
+							ui.heading(
+								egui::RichText::new("Coach mem:")
+									.color(egui::Color32::YELLOW)
+									.strong(),
+							);
+							ui.add_space(8.0);
+							for (k, v) in &self.coach_state {
+								ui.label(
+									egui::RichText::new(format!("{}: {}", k, v))
+										.color(egui::Color32::LIGHT_GRAY)
+										.monospace(),
+								);
+							}
+						});
+				});
+		}
+	}
+
+	/// Draw the blurred preview over a post that isn't revealed yet,
+	/// or a plain fill while its preview isn't made
+	fn paint_cover(
+		painter: &egui::Painter,
+		rect: egui::Rect,
+		(cover, blurred): (f32, Option<egui::TextureId>),
+	) {
+		if cover <= 0.0 {
+			return;
+		}
+		match blurred {
+			Some(id) => {
+				let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+				painter.image(id, rect, uv, egui::Color32::WHITE.gamma_multiply(cover));
+			}
+			None => {
+				painter.rect_filled(
+					rect,
+					0.0,
+					egui::Color32::from_gray(40).gamma_multiply(cover),
+				);
+			}
+		}
+	}
+
+	/// Pin `post` for comparison, or unpin with `None` or the post already pinned
+	fn set_pinned(&mut self, post: Option<&Post>, events: &mut Vec<Event>) {
+		let already_pinned = |post: &&Post| {
+			self.pinned
+				.as_ref()
+				.is_some_and(|pinned| pinned.post.id == post.id)
+		};
+		let pinned = post
+			.filter(|post| !already_pinned(post))
+			.map(|post| PinnedPane {
+				post: post.clone(),
+				zoom: 1.0,
+				pan_offset: egui::Vec2::ZERO,
+			});
+		if pinned.is_none() && self.pinned.is_none() {
+			return;
+		}
+		events.push(Event::Media(MediaEvent::Pin {
+			cache_key: pinned
+				.as_ref()
+				.map(|pinned| MediaCache::post_cache_key(&pinned.post).to_string()),
+		}));
+		self.pinned = pinned;
+	}
+
+	/// The pinned post, fitted to the right half. The wheel zooms it and dragging pans it;
+	/// the pan keys stay with the image being browsed.
+	fn render_pinned(&mut self, ui: &mut egui::Ui, media: &mut MediaCache) {
+		let Some(pinned) = self.pinned.as_mut() else {
+			return;
+		};
+		let rect = ui.max_rect();
+		ui.painter()
+			.vline(rect.left(), rect.y_range(), ui.visuals().window_stroke());
+		media.ensure_uploaded(&pinned.post);
+		let Some(LoadedMedia::Image { texture }) = media.get_media_by_post(&pinned.post) else {
+			ui.centered_and_justified(|ui| {
+				ui.spinner();
+			});
+			return;
+		};
+
+		let response = ui.interact(rect, ui.id().with("pinned_pan"), egui::Sense::drag());
+		if response.hovered() {
+			let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+			if scroll != 0.0 {
+				pinned.zoom = (pinned.zoom * (scroll * 0.005).exp()).clamp(1.0, 5.0);
+			}
+		}
+		if response.dragged() {
+			ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+			pinned.pan_offset += response.drag_delta();
+		}
+
+		let img_size = texture.size_vec2();
+		let size = img_size * notes::fit_scale(img_size, rect.size()) * pinned.zoom;
+		let pan_limit = ((size - rect.size()) * 0.5).max(egui::Vec2::ZERO);
+		pinned.pan_offset = pinned.pan_offset.clamp(-pan_limit, pan_limit);
+		let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+		ui.painter().with_clip_rect(rect).image(
+			texture.id(),
+			egui::Rect::from_center_size(rect.center() + pinned.pan_offset, size),
+			uv,
+			egui::Color32::WHITE,
+		);
+	}
+
+	fn render_media(
+		&mut self,
+		ui: &mut egui::Ui,
+		ctx: &egui::Context,
+		media: &mut MediaCache,
+		browser: &ContentBrowser,
+	) {
+		if self.restore_view_pending && media.get_current_media().is_some() {
+			self.restore_view(browser);
+		}
+
+		let pan_elapsed = self
+			.backgrounded_since
+			.unwrap_or_else(Instant::now)
+			.saturating_duration_since(self.pan_started)
+			.as_secs_f32();
+		let mut user_panned = self.user_has_panned;
+		// The pool picker takes the arrow keys as well. Keys held over from the
+		// island were already taken out of the input.
+		let island_active = self.island_ctx.active || self.pool_picker.is_some();
+
+		// Blurred previews over posts not revealed yet, by offset -2..=2 from the current one
+		let mut covers = [(0.0, None); 5];
+		if self.reveal.enabled {
+			for (slot, offset) in (-2..=2).enumerate() {
+				if let Some(post) = browser.get_post_relative(offset) {
+					let cover = match self.reveal.cover(post.id) {
+						fading if self.overlay_style.reduce_motion && fading < 1.0 => 0.0,
+						cover => cover,
+					};
+					if cover > 0.0 {
+						let blurred = media
+							.blurred_by_post(post)
+							.map(|LoadedMedia::Image { texture }| texture.id());
+						covers[slot] = (cover, blurred);
+					}
+				}
+			}
+			if let Some(post) = browser.current_post() {
+				let hovering = ui.rect_contains_pointer(ui.max_rect());
+				let moved = ui.input(|i| i.pointer.is_moving());
+				let shown_for = self.image_load_time.elapsed();
+				if let Some(left) =
+					self.reveal
+						.update(post.id, hovering, moved, shown_for, self.auto_reveal)
+				{
+					self.repaints.after(RepaintCause::Animation, left);
+				}
+			}
+			if covers[2].0 > 0.0 && covers[2].0 < 1.0 {
+				self.repaints.after(RepaintCause::Animation, PULSE_FRAME);
+			}
+		}
+
+		// Notes are positioned in original file pixels, regardless of which variant is shown.
+		// They'd give away what's under a cover, so they wait for the reveal.
+		let current_notes: Vec<Note> = if self.show_notes && covers[2].0 == 0.0 {
+			self.notes.clone()
+		} else {
+			Vec::new()
+		};
+		let notes_source_size = browser
+			.current_post()
+			.map(|p| egui::vec2(p.file.width as f32, p.file.height as f32))
+			.unwrap_or(egui::Vec2::ZERO);
+
+		// Don't process pan keys when island overlay is active
+		let key_pan = if island_active {
+			egui::Vec2::ZERO
+		} else {
+			self.key_pan_delta(ctx)
+		};
+		let handle_scroll_input = |ui: &mut egui::Ui, input_active: &mut bool| {
+			if key_pan != egui::Vec2::ZERO {
+				ui.scroll_with_delta(key_pan);
+				*input_active = true;
+			}
+		};
+
+		let fill_mode = self.fill_mode();
+		if fill_mode == ImageFillMode::FitToGallery {
+			for offset in -2..=2 {
+				if let Some(post) = browser.get_post_relative(offset) {
+					media.ensure_uploaded(post);
+				}
+			}
+		}
+
+		if let Some(loaded_media) = media.get_current_media() {
+			match loaded_media {
+				LoadedMedia::Image { texture } => {
+					let available_size = ui.available_size();
+					let img_size = texture.size_vec2();
+					let drag_blocked = island_active || self.island_ctx.in_cooldown();
+					let pan_delta = self.drag_pan_delta(ui, drag_blocked);
+					let replaced_sample = self.replaced_sample.take();
+
+					if matches!(fill_mode, ImageFillMode::Fit | ImageFillMode::FitToGallery) {
+						if !island_active {
+							let dt = ctx.input(|i| i.stable_dt);
+
+							if ctx.input(|i| i.key_down(egui::Key::E)) {
+								self.user_zoom = (self.user_zoom + dt * 4.0).min(5.0);
+								self.repaints.now(RepaintCause::Input);
+							}
+							if ctx.input(|i| i.key_down(egui::Key::Q)) {
+								self.user_zoom = (self.user_zoom - dt * 4.0).max(1.0);
+								self.repaints.now(RepaintCause::Input);
+							}
+
+							if self.user_zoom > 1.0 {
+								self.user_pan_offset += key_pan;
+							} else {
+								self.user_pan_offset = egui::Vec2::ZERO;
+							}
+						}
+
+						if self.user_zoom > 1.0 {
+							self.user_pan_offset += pan_delta;
+						}
+						let fit_scale =
+							(available_size.x / img_size.x).min(available_size.y / img_size.y);
+						let fit_size = img_size * fit_scale * self.user_zoom;
+						if let Some(sample_size) = replaced_sample {
+							// Same spot under the middle of the screen on the new shape
+							let sample_fit = sample_size
+								* notes::fit_scale(sample_size, available_size)
+								* self.user_zoom;
+							self.user_pan_offset = self.user_pan_offset * fit_size / sample_fit;
+						}
+						let pan_limit = ((fit_size - available_size) * 0.5).max(egui::Vec2::ZERO);
+						self.user_pan_offset.x =
+							self.user_pan_offset.x.clamp(-pan_limit.x, pan_limit.x);
+						self.user_pan_offset.y =
+							self.user_pan_offset.y.clamp(-pan_limit.y, pan_limit.y);
+					} else {
+						self.user_zoom = 1.0;
+						self.user_pan_offset = egui::Vec2::ZERO;
+					}
+
+					// Apply beat pulse if enabled
+					let breath_scale = self.breath_scale;
+					let pulse = if self.beat_pulse_enabled
+						&& !self.overlay_style.reduce_motion
+						&& self.beat_intensity > 0.01
+					{
+						self.repaints.after(RepaintCause::Pulse, PULSE_FRAME);
+						1.0 + self.beat_intensity * self.beat_pulse_scale
+					} else {
+						1.0
+					};
+
+					match fill_mode {
+						// Fit to width, scrolled by hand and paged with Space; no auto-pan
+						_ if self.reading.enabled => {
+							let display_size = img_size * (available_size.x / img_size.x);
+							let bottom = (display_size.y - available_size.y).max(0.0);
+							if self.reading.post_id != self.displayed_post_id {
+								// A new page starts at the top, or at the bottom when paging back
+								self.reading.post_id = self.displayed_post_id;
+								let start_at_bottom =
+									std::mem::take(&mut self.reading.start_at_bottom);
+								self.reading.jump =
+									Some(if start_at_bottom { bottom } else { 0.0 });
+							}
+
+							let mut scroll_area = egui::ScrollArea::vertical()
+								.id_salt("reading_page")
+								.scroll_bar_visibility(
+									egui::scroll_area::ScrollBarVisibility::AlwaysHidden,
+								)
+								.drag_to_scroll(false);
+							if let Some(offset) = self.reading.jump.take() {
+								scroll_area =
+									scroll_area.vertical_scroll_offset(offset.clamp(0.0, bottom));
+							} else if pan_delta.y != 0.0 {
+								scroll_area = scroll_area.vertical_scroll_offset(
+									(self.reading.offset - pan_delta.y).clamp(0.0, bottom),
+								);
+							}
+
+							let output = scroll_area.show(ui, |ui| {
+								handle_scroll_input(ui, &mut user_panned);
+
+								// Pages shorter than the window sit in the middle of it
+								let (rect, _response) = ui.allocate_exact_size(
+									egui::vec2(
+										available_size.x,
+										display_size.y.max(available_size.y),
+									),
+									egui::Sense::hover(),
+								);
+								let image_rect =
+									egui::Rect::from_center_size(rect.center(), display_size);
+								let uv = egui::Rect::from_min_max(
+									egui::pos2(0.0, 0.0),
+									egui::pos2(1.0, 1.0),
+								);
+								ui.painter().image(
+									texture.id(),
+									image_rect,
+									uv,
+									egui::Color32::WHITE,
+								);
+								Self::paint_cover(ui.painter(), image_rect, covers[2]);
+
+								if !current_notes.is_empty()
+									&& let Some(transform) =
+										ImageTransform::new(image_rect, notes_source_size)
+								{
+									notes::render_notes(ui, &current_notes, transform);
+								}
+							});
+							self.reading.offset = output.state.offset.y;
+							self.reading.content = output.content_size.y;
+							self.reading.viewport = output.inner_rect.height();
+						}
+						ImageFillMode::Cover | ImageFillMode::ActualSize => {
+							let mut frame = self.auto_pan_frame(pan_elapsed);
+							let pixels_per_point = ctx.pixels_per_point();
+							let display_scale = |size: egui::Vec2| match fill_mode {
+								ImageFillMode::ActualSize => 1.0 / pixels_per_point,
+								_ => notes::cover_scale(size, available_size),
+							};
+							if fill_mode == ImageFillMode::ActualSize {
+								// A Ken Burns zoom would blur the pixels
+								frame.zoom = 1.0;
+							}
+							let scale = display_scale(img_size) * frame.zoom;
+							let base_display_size = img_size * scale;
+
+							// Dragging is handled by `drag_pan_delta` so it can override auto-pan
+							let mut scroll_area = egui::ScrollArea::both()
+								.scroll_bar_visibility(
+									egui::scroll_area::ScrollBarVisibility::AlwaysHidden,
+								)
+								.drag_to_scroll(false);
+
+							if pan_delta != egui::Vec2::ZERO {
+								user_panned = true;
+								let overflow =
+									(base_display_size - available_size).max(egui::Vec2::ZERO);
+								scroll_area = scroll_area.scroll_offset(
+									(self.cover_scroll_offset - pan_delta)
+										.clamp(egui::Vec2::ZERO, overflow),
+								);
+							}
+
+							if let Some(sample_size) = replaced_sample {
+								let sample_display =
+									sample_size * display_scale(sample_size) * frame.zoom;
+								let offset = notes::carry_scroll_offset(
+									self.cover_scroll_offset,
+									available_size,
+									sample_display,
+									base_display_size,
+								);
+								let overflow =
+									(base_display_size - available_size).max(egui::Vec2::ZERO);
+								scroll_area = scroll_area
+									.scroll_offset(offset.clamp(egui::Vec2::ZERO, overflow));
+							}
+
+							// Revisited image; the window may have been resized since
+							if let Some(offset) = self.pending_scroll_offset.take() {
+								let overflow =
+									(base_display_size - available_size).max(egui::Vec2::ZERO);
+								scroll_area = scroll_area
+									.scroll_offset(offset.clamp(egui::Vec2::ZERO, overflow));
+							}
+
+							// Auto-pan
+							if !user_panned {
+								let overflow = base_display_size - available_size;
+								if overflow.x > 0.0 {
+									scroll_area = scroll_area
+										.horizontal_scroll_offset(overflow.x * frame.offset.x);
+								}
+								if overflow.y > 0.0 {
+									scroll_area = scroll_area
+										.vertical_scroll_offset(overflow.y * frame.offset.y);
+								}
+							}
+							// The Ken Burns zoom keeps drifting under a manual pan
+							if frame.moving
+								&& (!user_panned || frame.zoom != 1.0)
+								&& self.backgrounded_since.is_none()
+							{
+								self.repaints.after(RepaintCause::Pan, PAN_FRAME);
+							}
+
+							let output = scroll_area.show(ui, |ui| {
+								handle_scroll_input(ui, &mut user_panned);
+
+								// Actual size may leave the image smaller than the view; keep it centered
+								let (rect, _response) = ui.allocate_exact_size(
+									base_display_size.max(available_size),
+									egui::Sense::hover(),
+								);
+
+								let center = rect.center();
+								let pulsed_size = base_display_size * pulse;
+								// The breath zooms around the middle of the view, so
+								// the spot auto-pan brought there stays put
+								let pulsed_rect = notes::scale_about(
+									egui::Rect::from_center_size(center, pulsed_size),
+									ui.clip_rect().center(),
+									breath_scale,
+								);
+								let uv = egui::Rect::from_min_max(
+									egui::pos2(0.0, 0.0),
+									egui::pos2(1.0, 1.0),
+								);
+
+								ui.painter().image(
+									texture.id(),
+									pulsed_rect,
+									uv,
+									egui::Color32::WHITE,
+								);
+								Self::paint_cover(ui.painter(), pulsed_rect, covers[2]);
+
+								if !current_notes.is_empty()
+									&& let Some(transform) =
+										ImageTransform::new(pulsed_rect, notes_source_size)
+								{
+									notes::render_notes(ui, &current_notes, transform);
+								}
+							});
+							self.cover_scroll_offset = output.state.offset;
+						}
+						ImageFillMode::Fit => {
+							let scale = notes::fit_scale(img_size, available_size) * self.user_zoom;
+							let base_display_size = img_size * scale;
+
+							ui.centered_and_justified(|ui| {
+								let (rect, _response) =
+									ui.allocate_exact_size(available_size, egui::Sense::hover());
+
+								let center = rect.center() + self.user_pan_offset;
+								let pulsed_size = base_display_size * pulse;
+								let pulsed_rect = notes::scale_about(
+									egui::Rect::from_center_size(center, pulsed_size),
+									rect.center(),
+									breath_scale,
+								);
+								let uv = egui::Rect::from_min_max(
+									egui::pos2(0.0, 0.0),
+									egui::pos2(1.0, 1.0),
+								);
+
+								ui.painter().image(
+									texture.id(),
+									pulsed_rect,
+									uv,
+									egui::Color32::WHITE,
+								);
+								Self::paint_cover(ui.painter(), pulsed_rect, covers[2]);
+
+								if !current_notes.is_empty()
+									&& let Some(transform) =
+										ImageTransform::new(pulsed_rect, notes_source_size)
+								{
+									notes::render_notes(ui, &current_notes, transform);
+								}
+							});
+						}
+						ImageFillMode::FitToGallery => {
+							let len = browser.posts_len();
+							if len > 0 {
+								let new_idx = browser.current_index();
+								if new_idx != self.last_gallery_index {
+									let mut delta =
+										new_idx as isize - self.last_gallery_index as isize;
+									let ilen = len as isize;
+									if delta > ilen / 2 {
+										delta -= ilen;
+									} else if delta < -ilen / 2 {
+										delta += ilen;
+									}
+
+									let visual_delta = delta.signum() as f32;
+
+									self.gallery_anim_start_offset =
+										self.gallery_anim_offset + visual_delta;
+									self.gallery_anim_offset = self.gallery_anim_start_offset;
+									self.gallery_anim_time = 0.0;
+									self.last_gallery_index = new_idx;
+								}
+							}
+
+							let anim_duration = 0.4;
+							if self.overlay_style.reduce_motion {
+								self.gallery_anim_time = anim_duration;
+							}
+							if self.gallery_anim_time < anim_duration {
+								let dt = ctx.input(|i| i.stable_dt);
+								self.gallery_anim_time =
+									(self.gallery_anim_time + dt).min(anim_duration);
+								let t = self.gallery_anim_time / anim_duration;
+								let ease = if t < 0.5 {
+									4.0 * t * t * t
+								} else {
+									1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+								};
+								self.gallery_anim_offset =
+									self.gallery_anim_start_offset * (1.0 - ease);
+								self.repaints.now(RepaintCause::Pan);
+							} else {
+								self.gallery_anim_offset = 0.0;
+							}
+
+							ui.centered_and_justified(|ui| {
+								let (rect, _response) =
+									ui.allocate_exact_size(available_size, egui::Sense::hover());
+
+								let center_rect =
+									egui::Rect::from_min_size(rect.min, available_size);
+
+								let get_fitted_width = |offset: isize| -> f32 {
+									if let Some(post) = browser.get_post_relative(offset) {
+										if let Some(crate::types::LoadedMedia::Image { texture }) =
+											media.get_media_by_post(post)
+										{
+											let size = texture.size_vec2();
+											let scale = (available_size.x / size.x)
+												.min(available_size.y / size.y);
+											return size.x * scale;
+										}
+									}
+									available_size.x
+								};
+
+								let virtual_center = -self.gallery_anim_offset;
+								let vc_floor = virtual_center.floor() as isize;
+								let vc_ceil = virtual_center.ceil() as isize;
+								let vc_fract = virtual_center - vc_floor as f32;
+
+								let w1 = get_fitted_width(vc_floor);
+								let w2 = get_fitted_width(vc_ceil);
+								let main_w = w1 + (w2 - w1) * vc_fract;
+
+								let gutter_w = ((available_size.x - main_w) / 2.0).max(0.0);
+								let left_gutter = egui::Rect::from_min_size(
+									rect.min,
+									egui::vec2(gutter_w, available_size.y),
+								);
+								let right_gutter = egui::Rect::from_min_size(
+									rect.min + egui::vec2(available_size.x - gutter_w, 0.0),
+									egui::vec2(gutter_w, available_size.y),
+								);
+
+								let off_left =
+									left_gutter.translate(egui::vec2(-gutter_w - 100.0, 0.0));
+								let off_right =
+									right_gutter.translate(egui::vec2(gutter_w + 100.0, 0.0));
+
+								let fit_rect =
+									|img_size: egui::Vec2, space: egui::Rect| -> egui::Rect {
+										if space.width() <= 0.01 || space.height() <= 0.01 {
+											return egui::Rect::from_center_size(
+												space.center(),
+												egui::Vec2::ZERO,
+											);
+										}
+										let width_ratio = space.width() / img_size.x;
+										let height_ratio = space.height() / img_size.y;
+										let scale = width_ratio.min(height_ratio) * self.user_zoom;
+										let size = img_size * scale;
+										egui::Rect::from_center_size(
+											space.center() + self.user_pan_offset,
+											size,
+										)
+									};
+
+								let cover_rect =
+									|img_size: egui::Vec2, space: egui::Rect| -> egui::Rect {
+										if space.width() <= 0.01 || space.height() <= 0.01 {
+											return egui::Rect::from_center_size(
+												space.center(),
+												egui::Vec2::ZERO,
+											);
+										}
+										let width_ratio = space.width() / img_size.x;
+										let height_ratio = space.height() / img_size.y;
+										let scale = width_ratio.max(height_ratio);
+										let size = img_size * scale;
+										egui::Rect::from_center_size(space.center(), size)
+									};
+
+								let get_rect_at = |slot: isize, size: egui::Vec2| -> egui::Rect {
+									match slot {
+										..=-2 => cover_rect(size, off_left),
+										-1 => cover_rect(size, left_gutter),
+										0 => fit_rect(size, center_rect),
+										1 => cover_rect(size, right_gutter),
+										2.. => cover_rect(size, off_right),
+									}
+								};
+
+								let get_clip_at = |slot: isize| -> egui::Rect {
+									match slot {
+										..=-2 => off_left,
+										-1 => left_gutter,
+										0 => center_rect,
+										1 => right_gutter,
+										2.. => off_right,
+									}
+								};
+
+								for offset in [-2, -1, 1, 2, 0] {
+									let v = offset as f32 + self.gallery_anim_offset;
+
+									// Only draw if within visible slots roughly
+									if v < -2.5 || v > 2.5 {
+										continue;
+									}
+
+									if let Some(post) = browser.get_post_relative(offset) {
+										if let Some(crate::types::LoadedMedia::Image {
+											texture: off_texture,
+										}) = media.get_media_by_post(post)
+										{
+											let img_size = off_texture.size_vec2();
+
+											let v_floor = v.floor();
+											let v_ceil = v.ceil();
+											let fract = v - v_floor;
+
+											let r1 = get_rect_at(v_floor as isize, img_size);
+											let r2 = get_rect_at(v_ceil as isize, img_size);
+
+											let interpolated_center =
+												r1.center() + (r2.center() - r1.center()) * fract;
+											let interpolated_size =
+												r1.size() + (r2.size() - r1.size()) * fract;
+
+											let c1 = get_clip_at(v_floor as isize);
+											let c2 = get_clip_at(v_ceil as isize);
+
+											let clip_min = c1.min + (c2.min - c1.min) * fract;
+											let clip_max = c1.max + (c2.max - c1.max) * fract;
+											let clip_rect =
+												egui::Rect::from_min_max(clip_min, clip_max);
+
+											// apply pulse and breath to the current focus
+											let dist_from_center = v.abs().min(1.0);
+											let current_pulse = 1.0
+												+ (pulse * breath_scale - 1.0)
+													* (1.0 - 0.5 * dist_from_center);
+											let final_size = interpolated_size * current_pulse;
+
+											let final_rect = egui::Rect::from_center_size(
+												interpolated_center,
+												final_size,
+											);
+											let uv = egui::Rect::from_min_max(
+												egui::pos2(0.0, 0.0),
+												egui::pos2(1.0, 1.0),
+											);
+
+											if final_rect.width() > 0.1 && final_rect.height() > 0.1
+											{
+												let mut painter = ui.painter().clone();
+												painter.set_clip_rect(
+													clip_rect.intersect(ui.clip_rect()),
+												);
+												painter.image(
+													off_texture.id(),
+													final_rect,
+													uv,
+													egui::Color32::WHITE,
+												);
+												Self::paint_cover(
+													&painter,
+													final_rect,
+													covers[(offset + 2) as usize],
+												);
+
+												// Only annotate the settled center image
+												if offset == 0
+													&& self.gallery_anim_offset == 0.0
+													&& !current_notes.is_empty() && let Some(
+													transform,
+												) =
+													ImageTransform::new(
+														final_rect,
+														notes_source_size,
+													) {
+													notes::render_notes(
+														ui,
+														&current_notes,
+														transform,
+													);
+												}
+											}
+										}
+									}
+								}
+							});
+						}
+					}
+				}
+			}
+		} else if self
+			.load_retrying
+			.as_deref()
+			.is_some_and(|key| media.current_url() == Some(key))
+		{
+			ui.vertical_centered(|ui| {
+				ui.add_space(ui.available_height() * 0.4);
+				ui.spinner();
+				ui.label("Couldn't load the image, retrying…");
+			});
+		} else if media.is_loading() {
+			ui.centered_and_justified(|ui| {
+				ui.spinner();
+			});
+		}
+
+		self.user_has_panned = user_panned;
+	}
+
+	fn render_status_bar(
+		ctx: &egui::Context,
+		gateway: &BooruGateway,
+		browser: &ContentBrowser,
+		media: &MediaCache,
+		poll_skips: PollCounts,
+		repaints: RepaintCounts,
+	) {
+		egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+			ui.horizontal(|ui| {
+				let position = if browser.is_empty() {
+					0
+				} else {
+					browser.current_index() + 1
+				};
+				if browser.is_end_of_results() {
+					ui.label(format!("Post {}/{} (end)", position, browser.posts_len()))
+						.on_hover_text("No more results; F5 checks again");
+				} else {
+					ui.label(format!("Post {}/{}", position, browser.posts_len()));
+				}
+				let hidden = browser.blacklist_hidden();
+				if hidden > 0 {
+					ui.label(format!("({} blacklisted)", hidden))
+						.on_hover_text("Posts of this search hidden by your blacklists");
+				}
+				let hidden = browser.session_hidden();
+				if hidden > 0 {
+					ui.label(format!("({} hidden)", hidden))
+						.on_hover_text("Posts hidden with Delete, until the app is closed");
+				}
+				if let Some(seed) = gateway.random_seed() {
+					ui.label(format!("Seed: {}", seed)).on_hover_text(format!(
+						"Add randseed:{} to the query to get this order again",
+						seed
+					));
+				}
+				ui.separator();
+
+				ui.label(format!(
+					"Cache: {} images, {:.1} MB",
+					media.cache_len(),
+					media.cache_bytes() as f64 / (1024.0 * 1024.0)
+				))
+				.on_hover_text("F12 lists what is cached, loading and queued");
+				ui.separator();
+
+				let (priority, general) = media.loading_counts();
+				let queue = media.queue_stats();
+				ui.label(format!("Loading: {} + {}", priority, general))
+					.on_hover_text(format!(
+						"Current image's full file + samples and prefetch\n\
+						 Queued {}, waited for room {} ({} sent later, {} dropped)",
+						queue.enqueued, queue.deferred, queue.retried, queue.dropped
+					));
+				let (permanent, transient) = media.failure_counts();
+				if permanent + transient > 0 {
+					ui.label(format!("({} failed)", permanent + transient))
+						.on_hover_text(format!(
+							"Not loaded again: {} missing or broken, {} retried later. \
+							 Ctrl+R tries the current post again.",
+							permanent, transient
+						));
+				}
+				ui.separator();
+
+				if let Some(last) = media.last_load() {
+					ui.label(format!(
+						"Last load: {} ms ({} net / {} decode)",
+						last.total().as_millis(),
+						last.network().as_millis(),
+						last.fetch.decode.as_millis()
+					))
+					.on_hover_text("F3 shows how long each stage of recent loads took");
+					ui.separator();
+				}
+
+				match gateway.last_latency() {
+					Some(latency) => ui.label(format!("API: {} ms", latency.as_millis())),
+					None => ui.label("API: -"),
+				};
+				let pending = gateway.pending_requests();
+				match gateway.rate_limit_free() {
+					Some(free) => ui.label(format!(
+						"Rate limit: {}/{} free, {} pending",
+						free,
+						crate::gateway::rate_limit::API_BURST,
+						pending
+					)),
+					None => ui.label(
+						egui::RichText::new(format!("Rate limit: queued, {} pending", pending))
+							.color(egui::Color32::YELLOW),
+					),
+				};
+
+				if cfg!(debug_assertions) {
+					ui.separator();
+					ui.label(format!("Polls skipped: {}/s", poll_skips.total()))
+						.on_hover_text(format!(
+							"Gateway {}, media {}, beat {}",
+							poll_skips.gateway, poll_skips.media, poll_skips.beat
+						));
+					ui.separator();
+					let causes: Vec<String> = RepaintCause::ALL
+						.into_iter()
+						.filter(|cause| repaints.get(*cause) > 0)
+						.map(|cause| format!("{} {}", cause.label(), repaints.get(cause)))
+						.collect();
+					ui.label(format!("Repaints: {}/s", repaints.frames))
+						.on_hover_text(if causes.is_empty() {
+							"Idle until input".to_string()
+						} else {
+							format!("Frames asking for another: {}", causes.join(", "))
+						});
+				}
+			});
+		});
+	}
+
+	/// The screen minus the status bar, if shown. Overlays may still cover the top panel.
+	fn overlay_rect(ctx: &egui::Context) -> egui::Rect {
+		let screen = ctx.screen_rect();
+		egui::Rect::from_min_max(
+			screen.min,
+			egui::pos2(screen.max.x, ctx.available_rect().bottom()),
+		)
+	}
+
+	/// Breathing overlay in the configured style; also drawn by the mirror window
+	fn render_breathing(
+		ctx: &egui::Context,
+		breathing: &BreathingOverlay,
+		style: OverlayStyle,
+		repaints: &mut Repaints,
+	) {
+		match breathing.style() {
+			BreathingStyle::Classic => {
+				Self::render_breathing_overlay(ctx, breathing, style.high_contrast, repaints);
+				Self::render_breathing_pulse(ctx, breathing, style, repaints);
+			}
+			BreathingStyle::Immersive => {
+				Self::render_immersive_breathing_overlay(
+					ctx,
+					breathing,
+					style.high_contrast,
+					repaints,
+				);
+			}
+		}
+	}
+
+	fn render_breathing_overlay(
+		ctx: &egui::Context,
+		breathing: &BreathingOverlay,
+		high_contrast: bool,
+		repaints: &mut Repaints,
+	) {
+		if !breathing.is_visible() {
+			return;
+		}
+
+		let screen_height = ctx.screen_rect().height();
+		let font_size = (screen_height * 0.05).max(16.0) * breathing.overlay_scale();
+		let margin = (screen_height * 0.03).max(10.0);
+
+		let align = breathing.anchor().align();
+		// Push inwards from whichever edges the anchor touches
+		let offset = egui::vec2(
+			match align.x() {
+				egui::Align::Min => margin,
+				egui::Align::Center => 0.0,
+				egui::Align::Max => -margin,
+			},
+			match align.y() {
+				egui::Align::Min => margin,
+				egui::Align::Center => 0.0,
+				egui::Align::Max => -margin,
+			},
+		);
+		let layout = match align.x() {
+			egui::Align::Max => egui::Layout::right_to_left(egui::Align::Center),
+			_ => egui::Layout::left_to_right(egui::Align::Center),
+		};
+
+		egui::Area::new(egui::Id::new("breathing_overlay"))
+			.constrain_to(Self::overlay_rect(ctx))
+			.anchor(align, offset)
+			.interactable(false)
+			.order(egui::Order::Foreground)
+			.show(ctx, |ui| {
+				ui.with_layout(layout, |ui| {
+					let state = breathing.state();
+					let left = breathing.phase_remaining();
+					let remaining = left.as_secs() + 1;
+					if state.phase == BreathingPhase::Prepare {
+						// Only the countdown changes, once a second
+						repaints.after(
+							RepaintCause::Breathing,
+							Duration::from_nanos(u64::from(left.subsec_nanos()) + 1_000_000),
+						);
+					}
+
+					let color = breathing.palette().color(state.phase);
+					let text = match state.phase {
+						BreathingPhase::Prepare => format!("PREPARE {}", remaining),
+						BreathingPhase::Inhale => "INHALE".to_string(),
+						BreathingPhase::Hold => "HOLD".to_string(),
+						BreathingPhase::Release => "RELEASE".to_string(),
+						BreathingPhase::Idle => "".to_string(),
+					};
+
+					if !text.is_empty() {
+						let font_id = egui::FontId::monospace(font_size);
+						let stroke_width = (font_size * 0.05).max(1.0);
+						Self::draw_outlined_text(
+							ui,
+							&text,
+							font_id,
+							color,
+							stroke_width,
+							high_contrast,
+						);
+					}
+				});
+			});
+	}
+
+	/// Classic phase text that swells and fades in the screen center.
+	/// Under reduced motion it just sits there for the same time.
+	fn render_breathing_pulse(
+		ctx: &egui::Context,
+		breathing: &BreathingOverlay,
+		style: OverlayStyle,
+		repaints: &mut Repaints,
+	) {
+		if !breathing.is_visible() {
+			return;
+		}
+
+		let state = breathing.state();
+		let elapsed = breathing.phase_elapsed().as_secs_f32();
+		let pulse_duration = breathing.pulse_duration_secs();
+
+		if elapsed < pulse_duration {
+			let t = elapsed / pulse_duration;
+			let (opacity, scale) = if style.reduce_motion {
+				(1.0, 1.0)
+			} else {
+				(
+					(t * std::f32::consts::PI).sin(),
+					0.3 + 1.0 * (1.0 - (1.0 - t).powi(4)),
+				)
+			};
+
+			let text = match state.phase {
+				BreathingPhase::Prepare => "PREPARE",
+				BreathingPhase::Inhale => "INHALE",
+				BreathingPhase::Hold => "HOLD",
+				BreathingPhase::Release => "RELEASE",
+				BreathingPhase::Idle => return,
+			};
+			let color = breathing.palette().color(state.phase);
+
+			let screen_rect = ctx.screen_rect();
+			let center = screen_rect.center();
+			// Screen fraction, capped in points so large high-DPI screens don't get a wall of text
+			let font_size =
+				(screen_rect.height() * breathing.pulse_scale()).min(MAX_PULSE_FONT_SIZE) * scale;
+
+			egui::Area::new(egui::Id::new("breathing_pulse"))
+				.fixed_pos(center)
+				.anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+				.interactable(false)
+				.order(egui::Order::Foreground)
+				.show(ctx, |ui| {
+					let font_id = egui::FontId::proportional(font_size);
+					let (text_color, shadow_color) = if style.high_contrast {
+						(egui::Color32::WHITE, egui::Color32::BLACK)
+					} else {
+						(color, Self::outline_color_for(color))
+					};
+					let text_color = text_color.gamma_multiply(opacity);
+					let shadow_color = shadow_color.gamma_multiply(opacity);
+
+					let galley =
+						ui.painter()
+							.layout_no_wrap(text.to_string(), font_id.clone(), text_color);
+
+					// At least one physical pixel wide
+					let stroke_width = (font_size * 0.02).max(1.0 / ctx.pixels_per_point())
+						* if style.high_contrast {
+							HIGH_CONTRAST_STROKE
+						} else {
+							1.0
+						};
+					let offsets = [
+						egui::vec2(-stroke_width, -stroke_width),
+						egui::vec2(0.0, -stroke_width),
+						egui::vec2(stroke_width, -stroke_width),
+						egui::vec2(-stroke_width, 0.0),
+						egui::vec2(stroke_width, 0.0),
+						egui::vec2(-stroke_width, stroke_width),
+						egui::vec2(0.0, stroke_width),
+						egui::vec2(stroke_width, stroke_width),
+					];
+
+					let text_size = galley.size();
+					let draw_pos = center - (text_size / 2.0);
+
+					// Lay out the shadow once; galleys are cheap to share via Arc
+					let shadow_galley = ui.painter().layout_no_wrap(
+						text.to_string(),
+						font_id.clone(),
+						shadow_color,
+					);
+					for offset in offsets {
+						ui.painter()
+							.galley(draw_pos + offset, shadow_galley.clone(), shadow_color);
+					}
+					ui.painter().galley(draw_pos, galley, text_color);
+				});
+
+			if style.reduce_motion {
+				// Nothing moves; wake up only to take it down
+				repaints.after(
+					RepaintCause::Breathing,
+					Duration::from_secs_f32(pulse_duration - elapsed),
+				);
+			} else {
+				repaints.after(RepaintCause::Breathing, PULSE_FRAME);
+			}
+		}
+	}
+
+	fn render_immersive_breathing_overlay(
+		ctx: &egui::Context,
+		breathing: &BreathingOverlay,
+		high_contrast: bool,
+		repaints: &mut Repaints,
+	) {
+		if !breathing.is_visible() {
+			return;
+		}
+
+		let state = breathing.state();
+		let progress = breathing.phase_progress();
+
+		let screen_rect = ctx.screen_rect();
+		let screen_width = screen_rect.width();
+		let screen_height = screen_rect.height();
+
+		// Calculate visual properties based on phase
+		let text_color = breathing.palette().color(state.phase);
+		let (text, bar_fill, bar_bg_alpha, text_alpha) = match state.phase {
+			BreathingPhase::Prepare => {
+				// Text fades in fast, background fades in gradually
+				let text_alpha = (progress * 4.0).min(1.0);
+				let bg_alpha = progress * 0.4;
+				("PREPARE", 0.0, bg_alpha, text_alpha)
+			}
+			BreathingPhase::Inhale => {
+				// Fill bar from 0% to 100%
+				("INHALE", progress, 0.4, 1.0)
+			}
+			BreathingPhase::Hold => {
+				// Bar stays full
+				("HOLD", 1.0, 0.4, 1.0)
+			}
+			BreathingPhase::Release => {
+				// Empty the bar, fade out background and text
+				let fade = 1.0 - progress;
+				let bg_alpha = 0.4 * fade;
+				("RELEASE", fade, bg_alpha, fade)
+			}
+			BreathingPhase::Idle => {
+				// Fade everything out quickly
+				let alpha = (1.0 - progress * 2.0).max(0.0);
+				("", 0.0, 0.0, alpha)
+			}
+		};
+
+		// Skip rendering if completely transparent
+		if text_alpha <= 0.001 && bar_bg_alpha <= 0.001 {
+			return;
+		}
+
+		repaints.after(RepaintCause::Breathing, PULSE_FRAME);
+
+		// Render semi-transparent background overlay
+		egui::Area::new(egui::Id::new("immersive_breathing_bg"))
+			.fixed_pos(screen_rect.min)
+			.order(egui::Order::Foreground)
+			.interactable(false)
+			.show(ctx, |ui| {
+				let bg_alpha = (bar_bg_alpha * text_alpha * 180.0) as u8;
+				ui.painter().rect_filled(
+					screen_rect,
+					0.0,
+					egui::Color32::from_rgba_unmultiplied(0, 0, 0, bg_alpha),
+				);
+			});
+
+		// Render progress bar just below the text
+		let overlay_scale = breathing.overlay_scale();
+		let font_size = screen_height * 0.08 * overlay_scale;
+		let bar_height = screen_height * 0.015 * overlay_scale;
+		// Keep the bar clear of the status bar when anchored low
+		let lowest_text_y =
+			Self::overlay_rect(ctx).bottom() - font_size * 0.6 - bar_height - screen_height * 0.02;
+		let text_center_y =
+			(screen_height * breathing.anchor().vertical_fraction()).min(lowest_text_y);
+		let bar_y = text_center_y + (font_size * 0.6); // Small gap below text
+		let bar_width = (screen_width * 0.4 * overlay_scale).min(screen_width * 0.9);
+		let bar_x = (screen_width - bar_width) / 2.0;
+		let bar_rect =
+			egui::Rect::from_min_size(egui::pos2(bar_x, bar_y), egui::vec2(bar_width, bar_height));
+
+		if bar_bg_alpha > 0.001 {
+			egui::Area::new(egui::Id::new("immersive_breathing_bar"))
+				.fixed_pos(bar_rect.min)
+				.order(egui::Order::Foreground)
+				.interactable(false)
+				.show(ctx, |ui| {
+					let painter = ui.painter();
+					let rounding = bar_height * 0.5;
+
+					// Background track
+					let bg_alpha = (text_alpha * 100.0) as u8;
+					painter.rect_filled(
+						bar_rect,
+						rounding,
+						egui::Color32::from_rgba_unmultiplied(40, 40, 50, bg_alpha),
+					);
+
+					// Filled portion
+					if bar_fill > 0.001 {
+						let fill_width = bar_rect.width() * bar_fill;
+						let fill_rect = egui::Rect::from_min_size(
+							bar_rect.min,
+							egui::vec2(fill_width, bar_height),
+						);
+						let fill_color = text_color.gamma_multiply(text_alpha);
+						painter.rect_filled(fill_rect, rounding, fill_color);
+					}
+				});
+		}
+
+		// Render centered text
+		if !text.is_empty() {
+			egui::Area::new(egui::Id::new("immersive_breathing_text"))
+				.anchor(
+					egui::Align2::CENTER_CENTER,
+					[0.0, text_center_y - screen_height / 2.0],
+				)
+				.order(egui::Order::Foreground)
+				.interactable(false)
+				.show(ctx, |ui| {
+					let font_id = egui::FontId::proportional(font_size);
+					let display_color = text_color.gamma_multiply(text_alpha);
+					let stroke_width = (font_size * 0.03).max(1.0);
+					Self::draw_outlined_text(
+						ui,
+						text,
+						font_id,
+						display_color,
+						stroke_width,
+						high_contrast,
+					);
+				});
+		}
+	}
+
+	fn render_info_overlay(
+		&self,
+		ctx: &egui::Context,
+		gateway: &BooruGateway,
+		browser: &ContentBrowser,
+		media: &MediaCache,
+		settings: &SettingsManager,
+	) {
+		let verbosity = settings.info_verbosity();
+		if browser.is_empty() || verbosity == InfoVerbosity::Hidden {
+			return;
+		}
+		// The credit takes the info overlay's corner
+		let credit = settings.credit_overlay();
+		if credit.enabled && credit.corner == OverlayAnchor::BottomLeft {
+			return;
+		}
+
+		let post = match browser.current_post() {
+			Some(p) => p,
+			None => return,
+		};
+
+		let fields: Vec<(InfoField, String)> = settings
+			.info_fields()
+			.iter()
+			.filter(|setting| setting.enabled)
+			.filter_map(|setting| {
+				let value = Self::info_field_value(setting.field, post, gateway, browser, media)?;
+				(!value.is_empty()).then_some((setting.field, value))
+			})
+			.collect();
+		// Read every frame, so it flips as soon as the original lands
+		let badge = media.current_state().map(|state| match state {
+			CacheState::SampleOnly => ("SAMPLE", egui::Color32::from_rgb(255, 190, 90)),
+			CacheState::Full => ("FULL", egui::Color32::from_rgb(150, 230, 150)),
+		});
+		if fields.is_empty() && badge.is_none() {
+			return;
+		}
+
+		let screen_height = ctx.screen_rect().height();
+		let font_size = (screen_height * 0.02).max(12.0);
+		let margin = (screen_height * 0.03).max(10.0);
+		let stroke_width = (font_size * 0.05).max(1.0);
+		let high_contrast = settings.high_contrast();
+
+		// Interactable so the score line can show its tooltip
+		egui::Area::new(egui::Id::new("image_info_overlay"))
+			.constrain_to(Self::overlay_rect(ctx))
+			.anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(margin, -margin))
+			.interactable(true)
+			.order(egui::Order::Foreground)
+			.show(ctx, |ui| {
+				let text_color = egui::Color32::WHITE;
+				let font_id = egui::FontId::proportional(font_size);
+
+				if let Some((label, color)) = badge {
+					Self::draw_outlined_text(
+						ui,
+						label,
+						egui::FontId::monospace(font_size * 0.6),
+						color,
+						stroke_width,
+						high_contrast,
+					);
+				}
+
+				if verbosity == InfoVerbosity::Compact {
+					let line = fields
+						.iter()
+						.map(|(_, value)| value.as_str())
+						.collect::<Vec<_>>()
+						.join("  ·  ");
+					Self::draw_outlined_text(
+						ui,
+						&line,
+						font_id,
+						text_color,
+						stroke_width,
+						high_contrast,
+					);
+					return;
+				}
+
+				ui.vertical(|ui| {
+					for (field, value) in &fields {
+						let line = ui.horizontal(|ui| {
+							Self::draw_outlined_text(
+								ui,
+								&format!("{}:", field.label()),
+								font_id.clone(),
+								egui::Color32::LIGHT_GRAY,
+								stroke_width,
+								high_contrast,
+							);
+							Self::draw_outlined_text(
+								ui,
+								" ",
+								font_id.clone(),
+								egui::Color32::TRANSPARENT,
+								0.0,
+								false,
+							);
+							// High contrast draws every line white anyway
+							let color = match field {
+								InfoField::Artist => TagCategory::Artist.color(false),
+								InfoField::Copyright => TagCategory::Copyright.color(false),
+								InfoField::Character => TagCategory::Character.color(false),
+								_ => text_color,
+							};
+							Self::draw_outlined_text(
+								ui,
+								value,
+								font_id.clone(),
+								color,
+								stroke_width,
+								high_contrast,
+							);
+						});
+						if *field == InfoField::Score {
+							line.response
+								.on_hover_text(format!("+{} / {}", post.score.up, post.score.down));
+						}
+					}
+				});
+			});
+	}
 
-											let v_floor = v.floor();
-											let v_ceil = v.ceil();
-											let fract = v - v_floor;
+	/// Text for one overlay line; None or empty when there is nothing to show
+	fn info_field_value(
+		field: InfoField,
+		post: &Post,
+		gateway: &BooruGateway,
+		browser: &ContentBrowser,
+		media: &MediaCache,
+	) -> Option<String> {
+		let value = match field {
+			InfoField::Id => post.id.to_string(),
+			InfoField::Query => browser.source_query_for(post.id)?.to_string(),
+			InfoField::Artist => {
+				let artist_str = post.tags.artist.join(", ");
+				if artist_str == "invalid_artist" {
+					return None;
+				}
+				artist_str
+			}
+			InfoField::Copyright => {
+				let copyright_str = post.tags.copyright.join(", ");
+				if copyright_str == "invalid_copyright" {
+					return None;
+				}
+				copyright_str
+			}
+			InfoField::Character => post.tags.character.join(", "),
+			InfoField::Score => {
+				let vote_glyph = match browser.vote_for(post.id) {
+					1 => " ⬆",
+					-1 => " ⬇",
+					_ => "",
+				};
+				format!("{}{}", post.score.total, vote_glyph)
+			}
+			InfoField::Favorites => post.fav_count.to_string(),
+			InfoField::Rating => match post.rating.as_str() {
+				"s" => "Safe".to_string(),
+				"q" => "Questionable".to_string(),
+				"e" => "Explicit".to_string(),
+				other => other.to_string(),
+			},
+			InfoField::Resolution if post.file.width > 0 => {
+				format!("{}×{}", post.file.width, post.file.height)
+			}
+			InfoField::Resolution => return None,
+			InfoField::Position => format!(
+				"{} / {}{}",
+				browser.current_index() + 1,
+				browser.posts_len(),
+				if browser.is_end_of_results() {
+					" (end)"
+				} else {
+					""
+				}
+			),
+			InfoField::Page => browser.page_of(post.id)?.to_string(),
+			InfoField::Pools => match post.pools.len() {
+				0 => return None,
+				count => format!("×{} (P to browse)", count),
+			},
+			InfoField::Status => [
+				(post.flags.pending, "pending"),
+				(post.flags.flagged, "flagged"),
+				(post.flags.deleted, "deleted"),
+			]
+			.into_iter()
+			.filter_map(|(set, name)| set.then_some(name))
+			.collect::<Vec<_>>()
+			.join(", "),
+			InfoField::Set => gateway.set_name()?.to_string(),
+			InfoField::File => media.current_info()?.summary(),
+		};
+		Some(value)
+	}
 
-											let r1 = get_rect_at(v_floor as isize, img_size);
-											let r2 = get_rect_at(v_ceil as isize, img_size);
+	/// Toggle, corner and opacity of the artist credit
+	fn render_credit_settings(
+		ui: &mut egui::Ui,
+		settings: &SettingsManager,
+		events: &mut Vec<Event>,
+	) {
+		let current = settings.credit_overlay();
+		let mut credit = current;
+		ui.checkbox(&mut credit.enabled, "Show artist credit")
+			.on_hover_text("\"art by … — e621 #…\" over the image, updated per post");
+		ui.add_enabled_ui(credit.enabled, |ui| {
+			ui.label("Corner");
+			egui::ComboBox::from_id_salt("credit_corner")
+				.selected_text(credit.corner.label())
+				.show_ui(ui, |ui| {
+					for corner in OverlayAnchor::ALL {
+						if corner != OverlayAnchor::Center {
+							ui.selectable_value(&mut credit.corner, corner, corner.label());
+						}
+					}
+				});
+			if credit.corner == OverlayAnchor::BottomLeft {
+				ui.label(egui::RichText::new("Hides the info overlay").weak());
+			}
+			ui.label("Opacity");
+			if let Some(opacity) =
+				drag::throttled(ui, "credit_opacity", credit.opacity, |ui, opacity| {
+					ui.add(egui::Slider::new(opacity, CreditOverlay::MIN_OPACITY..=1.0))
+				}) {
+				credit.opacity = opacity;
+			}
+		});
+		if credit != current {
+			events.push(Event::Settings(SettingsEvent::SetCreditOverlay { credit }));
+		}
+	}
 
-											let interpolated_center =
-												r1.center() + (r2.center() - r1.center()) * fract;
-											let interpolated_size =
-												r1.size() + (r2.size() - r1.size()) * fract;
+	/// Dot color and tooltip for how far a post's image has loaded
+	fn readiness_look(readiness: Readiness) -> (egui::Color32, &'static str) {
+		match readiness {
+			Readiness::NotStarted => (egui::Color32::GRAY, "Not loaded yet"),
+			Readiness::SampleReady => (egui::Color32::YELLOW, "Sample loaded"),
+			Readiness::FullReady => (egui::Color32::GREEN, "Original loaded"),
+			Readiness::Failed => (egui::Color32::RED, "Failed to load"),
+		}
+	}
 
-											let c1 = get_clip_at(v_floor as isize);
-											let c2 = get_clip_at(v_ceil as isize);
+	/// A small dot for how far one upcoming post's image has loaded
+	fn readiness_dot(ui: &mut egui::Ui, readiness: Readiness) {
+		let (color, text) = Self::readiness_look(readiness);
+		let (rect, response) = ui.allocate_exact_size(egui::vec2(8.0, 8.0), egui::Sense::hover());
+		ui.painter().circle_filled(rect.center(), 3.0, color);
+		response.on_hover_text(text);
+	}
 
-											let clip_min = c1.min + (c2.min - c1.min) * fract;
-											let clip_max = c1.max + (c2.max - c1.max) * fract;
-											let clip_rect =
-												egui::Rect::from_min_max(clip_min, clip_max);
+	/// Checkboxes and reordering for the info overlay lines
+	fn render_info_overlay_settings(
+		ui: &mut egui::Ui,
+		settings: &SettingsManager,
+		events: &mut Vec<Event>,
+	) {
+		let verbosity = settings.info_verbosity();
+		ui.horizontal(|ui| {
+			for (option, label) in [
+				(InfoVerbosity::Full, "Full"),
+				(InfoVerbosity::Compact, "Compact"),
+				(InfoVerbosity::Hidden, "Hidden"),
+			] {
+				if ui.selectable_label(verbosity == option, label).clicked() {
+					events.push(Event::Settings(SettingsEvent::SetInfoVerbosity {
+						verbosity: option,
+					}));
+				}
+			}
+		});
+		ui.separator();
+
+		let mut fields = settings.info_fields().to_vec();
+		let mut changed = false;
+		let last = fields.len().saturating_sub(1);
+		for index in 0..fields.len() {
+			ui.horizontal(|ui| {
+				let label = fields[index].field.label();
+				changed |= ui.checkbox(&mut fields[index].enabled, label).changed();
+				ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+					if ui
+						.add_enabled(index < last, egui::Button::new("⏷"))
+						.clicked()
+					{
+						fields.swap(index, index + 1);
+						changed = true;
+					}
+					if ui.add_enabled(index > 0, egui::Button::new("⏶")).clicked() {
+						fields.swap(index, index - 1);
+						changed = true;
+					}
+				});
+			});
+		}
+		if changed {
+			events.push(Event::Settings(SettingsEvent::SetInfoFields { fields }));
+		}
+	}
 
-											// apply pulse to the current focus
-											let dist_from_center = v.abs().min(1.0);
-											let current_pulse = 1.0
-												+ (pulse - 1.0) * (1.0 - 0.5 * dist_from_center);
-											let final_size = interpolated_size * current_pulse;
+	fn show_toast(&mut self, message: String) {
+		self.toast = Some(Toast {
+			message,
+			shown_at: Instant::now(),
+			action: None,
+		});
+	}
 
-											let final_rect = egui::Rect::from_center_size(
-												interpolated_center,
-												final_size,
-											);
-											let uv = egui::Rect::from_min_max(
-												egui::pos2(0.0, 0.0),
-												egui::pos2(1.0, 1.0),
-											);
+	/// Short-lived message at the top of the screen
+	fn render_toast(&mut self, ctx: &egui::Context, events: &mut Vec<Event>) {
+		const TOAST_DURATION: f32 = 3.0;
+		// Give the user time to reach the button
+		const ACTION_TOAST_DURATION: f32 = 8.0;
 
-											if final_rect.width() > 0.1 && final_rect.height() > 0.1
-											{
-												let mut painter = ui.painter().clone();
-												painter.set_clip_rect(
-													clip_rect.intersect(ui.clip_rect()),
-												);
-												painter.image(
-													off_texture.id(),
-													final_rect,
-													uv,
-													egui::Color32::WHITE,
-												);
-											}
-										}
-									}
-								}
-							});
-						}
-					}
-				}
-			}
-		} else if media.is_loading() {
-			ui.centered_and_justified(|ui| {
-				ui.spinner();
+		let Some(toast) = &self.toast else {
+			return;
+		};
+		let duration = if toast.action.is_some() {
+			ACTION_TOAST_DURATION
+		} else {
+			TOAST_DURATION
+		};
+		let elapsed = toast.shown_at.elapsed().as_secs_f32();
+		if elapsed > duration {
+			self.toast = None;
+			return;
+		}
+		let opacity = if self.overlay_style.reduce_motion {
+			1.0
+		} else {
+			(duration - elapsed).min(1.0)
+		};
+
+		let mut action_clicked = false;
+		egui::Area::new(egui::Id::new("toast"))
+			.anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+			.interactable(toast.action.is_some())
+			.order(egui::Order::Foreground)
+			.show(ctx, |ui| {
+				egui::Frame::none()
+					.fill(egui::Color32::from_black_alpha(200).gamma_multiply(opacity))
+					.inner_margin(egui::Margin::symmetric(14.0, 8.0))
+					.rounding(6.0)
+					.show(ui, |ui| {
+						ui.horizontal(|ui| {
+							ui.label(
+								egui::RichText::new(&toast.message)
+									.color(egui::Color32::WHITE.gamma_multiply(opacity)),
+							);
+							if let Some((label, _)) = &toast.action
+								&& ui.button(*label).clicked()
+							{
+								action_clicked = true;
+							}
+						});
+					});
 			});
+
+		if action_clicked && let Some((_, event)) = self.toast.take().and_then(|t| t.action) {
+			events.push(event);
 		}
 
-		self.user_has_panned = user_panned;
+		if self.overlay_style.reduce_motion {
+			self.repaints.after(
+				RepaintCause::Animation,
+				Duration::from_secs_f32(duration - elapsed),
+			);
+		} else {
+			self.repaints.after(RepaintCause::Animation, PAN_FRAME);
+		}
 	}
 
-	fn render_breathing_overlay(&self, ctx: &egui::Context, breathing: &BreathingOverlay) {
-		if !breathing.is_visible() {
+	/// Thumbnails of the posts around the current one, which is outlined. Only images
+	/// already uploaded are drawn; the rest are tiles with a dot for how far they've
+	/// loaded. Nothing is drawn until at least one image is there.
+	fn render_filmstrip(
+		&self,
+		ctx: &egui::Context,
+		browser: &ContentBrowser,
+		media: &mut MediaCache,
+		events: &mut Vec<Event>,
+	) {
+		if !self.show_filmstrip {
+			return;
+		}
+		let current = browser.current_index();
+		let tiles: Vec<(usize, &Post, Readiness, Option<egui::TextureHandle>)> = browser
+			.peek_range(FILMSTRIP_RANGE)
+			.into_iter()
+			.map(|(index, post)| {
+				let texture = if self.reveal.cover(post.id) > 0.0 {
+					media.blurred_by_post(post)
+				} else {
+					media.get_media_by_post(post)
+				};
+				let texture = texture.map(|LoadedMedia::Image { texture }| texture.clone());
+				(index, post, media.readiness(post), texture)
+			})
+			.collect();
+		if tiles.iter().all(|(.., texture)| texture.is_none()) {
 			return;
 		}
 
 		let screen_height = ctx.screen_rect().height();
-		let font_size = (screen_height * 0.05).max(16.0);
-		let margin_offset = -(screen_height * 0.03).max(10.0);
-
-		egui::Area::new(egui::Id::new("breathing_overlay"))
-			.anchor(
-				egui::Align2::RIGHT_BOTTOM,
-				egui::vec2(margin_offset, margin_offset),
-			)
-			.interactable(false)
+		let size = (screen_height * 0.08).clamp(40.0, 96.0);
+		let margin = (screen_height * 0.03).max(10.0);
+		egui::Area::new(egui::Id::new("filmstrip"))
+			.constrain_to(Self::overlay_rect(ctx))
+			.anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -margin))
 			.order(egui::Order::Foreground)
 			.show(ctx, |ui| {
-				ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-					let state = breathing.state();
-					let elapsed = state.start_time.elapsed();
-					let remaining = state.duration.saturating_sub(elapsed).as_secs() + 1;
-
-					let (text, color) = match state.phase {
-						BreathingPhase::Prepare => {
-							(format!("PREPARE {}", remaining), egui::Color32::RED)
+				ui.horizontal(|ui| {
+					ui.spacing_mut().item_spacing.x = 4.0;
+					for (index, post, readiness, texture) in &tiles {
+						let (rect, response) =
+							ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::click());
+						let painter = ui.painter();
+						painter.rect_filled(rect, 3.0, egui::Color32::from_black_alpha(160));
+						match texture {
+							Some(texture) => {
+								let image_size = texture.size_vec2();
+								let fitted = egui::Rect::from_center_size(
+									rect.center(),
+									image_size * notes::fit_scale(image_size, rect.size()),
+								);
+								let uv = egui::Rect::from_min_max(
+									egui::pos2(0.0, 0.0),
+									egui::pos2(1.0, 1.0),
+								);
+								painter.image(texture.id(), fitted, uv, egui::Color32::WHITE);
+							}
+							None => {
+								let (color, _) = Self::readiness_look(*readiness);
+								painter.circle_filled(rect.center(), 3.0, color);
+							}
+						}
+						if *index == current {
+							painter.rect_stroke(
+								rect,
+								3.0,
+								egui::Stroke::new(2.0, egui::Color32::WHITE),
+							);
+						} else if response.on_hover_text(format!("#{}", post.id)).clicked() {
+							events.push(Event::Source(SourceEvent::Navigate(
+								NavDirection::ToPosition(index + 1),
+							)));
 						}
-						BreathingPhase::Inhale => ("INHALE".to_string(), egui::Color32::YELLOW),
-						BreathingPhase::Hold => ("HOLD".to_string(), egui::Color32::YELLOW),
-						BreathingPhase::Release => ("RELEASE".to_string(), egui::Color32::GREEN),
-						BreathingPhase::Idle => ("".to_string(), egui::Color32::TRANSPARENT),
-					};
-
-					if !text.is_empty() {
-						let font_id = egui::FontId::monospace(font_size);
-						let stroke_width = (font_size * 0.05).max(1.0);
-						Self::draw_outlined_text(ui, &text, font_id, color, stroke_width);
 					}
 				});
 			});
 	}
 
-	fn render_breathing_pulse(&self, ctx: &egui::Context, breathing: &BreathingOverlay) {
-		if !breathing.is_visible() {
+	/// The setting just stepped from the keyboard, fading out after a second
+	fn render_readout(
+		&mut self,
+		ctx: &egui::Context,
+		settings: &SettingsManager,
+		breathing: &BreathingOverlay,
+	) {
+		const READOUT_DURATION: f32 = 1.0;
+		const READOUT_FADE: f32 = 0.3;
+
+		let Some((readout, shown_at)) = self.readout else {
+			return;
+		};
+		let elapsed = shown_at.elapsed().as_secs_f32();
+		if elapsed > READOUT_DURATION {
+			self.readout = None;
 			return;
 		}
+		let opacity = if self.overlay_style.reduce_motion {
+			1.0
+		} else {
+			((READOUT_DURATION - elapsed) / READOUT_FADE).min(1.0)
+		};
+		let text = match readout {
+			Readout::Interval => format!("Interval: {} s", settings.auto_play_delay().as_secs()),
+			Readout::IdleMultiplier => format!("Idle: {:.1}×", breathing.idle_multiplier()),
+		};
+		let font_size = (ctx.screen_rect().height() * 0.05).max(20.0);
+		let high_contrast = self.overlay_style.high_contrast;
 
-		let state = breathing.state();
-		let elapsed = state.start_time.elapsed().as_secs_f32();
-		let pulse_duration = 1.5;
-
-		if elapsed < pulse_duration {
-			let t = elapsed / pulse_duration;
-			let opacity = (t * std::f32::consts::PI).sin();
-			let scale = 0.3 + 1.0 * (1.0 - (1.0 - t).powi(4));
-
-			let (text, color) = match state.phase {
-				BreathingPhase::Prepare => ("PREPARE", egui::Color32::RED),
-				BreathingPhase::Inhale => ("INHALE", egui::Color32::YELLOW),
-				BreathingPhase::Hold => ("HOLD", egui::Color32::YELLOW),
-				BreathingPhase::Release => ("RELEASE", egui::Color32::GREEN),
-				BreathingPhase::Idle => return,
-			};
-
-			let screen_rect = ctx.screen_rect();
-			let center = screen_rect.center();
-			let font_size = (screen_rect.height() * 0.15) * scale;
-
-			egui::Area::new(egui::Id::new("breathing_pulse"))
-				.fixed_pos(center)
-				.anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
-				.interactable(false)
-				.order(egui::Order::Foreground)
-				.show(ctx, |ui| {
-					let font_id = egui::FontId::proportional(font_size);
-					let shadow_color = egui::Color32::BLACK.gamma_multiply(opacity);
-					let text_color = color.gamma_multiply(opacity);
-
-					let galley =
-						ui.painter()
-							.layout_no_wrap(text.to_string(), font_id.clone(), text_color);
+		egui::Area::new(egui::Id::new("readout"))
+			.anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+			.interactable(false)
+			.order(egui::Order::Foreground)
+			.show(ctx, |ui| {
+				Self::draw_outlined_text(
+					ui,
+					&text,
+					egui::FontId::proportional(font_size),
+					egui::Color32::WHITE.gamma_multiply(opacity),
+					(font_size * 0.06).max(1.5) * opacity,
+					high_contrast,
+				);
+			});
 
-					let stroke_width = (font_size * 0.02).max(1.0);
-					let offsets = [
-						egui::vec2(-stroke_width, -stroke_width),
-						egui::vec2(0.0, -stroke_width),
-						egui::vec2(stroke_width, -stroke_width),
-						egui::vec2(-stroke_width, 0.0),
-						egui::vec2(stroke_width, 0.0),
-						egui::vec2(-stroke_width, stroke_width),
-						egui::vec2(0.0, stroke_width),
-						egui::vec2(stroke_width, stroke_width),
-					];
+		if self.overlay_style.reduce_motion {
+			self.repaints.after(
+				RepaintCause::Animation,
+				Duration::from_secs_f32(READOUT_DURATION - elapsed),
+			);
+		} else {
+			self.repaints.after(RepaintCause::Animation, PAN_FRAME);
+		}
+	}
 
-					let text_size = galley.size();
-					let draw_pos = center - (text_size / 2.0);
+	/// Pick one of the current post's tags to never show again
+	fn render_tag_picker(
+		&mut self,
+		ctx: &egui::Context,
+		browser: &ContentBrowser,
+		events: &mut Vec<Event>,
+	) {
+		if !self.tag_picker_open {
+			return;
+		}
+		let Some(post) = browser.current_post() else {
+			self.tag_picker_open = false;
+			return;
+		};
 
-					for offset in offsets {
-						let shadow_galley = ui.painter().layout_no_wrap(
-							text.to_string(),
-							font_id.clone(),
-							shadow_color,
-						);
-						ui.painter()
-							.galley(draw_pos + offset, shadow_galley, shadow_color);
+		let mut open = true;
+		let mut picked = None;
+		egui::Window::new("Hide posts tagged...")
+			.open(&mut open)
+			.collapsible(false)
+			.resizable(false)
+			.anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+			.show(ctx, |ui| {
+				ui.horizontal(|ui| {
+					ui.label("Filter:");
+					ui.text_edit_singleline(&mut self.tag_picker_filter)
+						.request_focus();
+				});
+				ui.separator();
+				let filter = self.tag_picker_filter.to_lowercase();
+				let high_contrast = self.overlay_style.high_contrast;
+				ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+					for (category, tag) in
+						tags::categorized(&post.tags).filter(|(_, t)| t.contains(&filter))
+					{
+						let text = egui::RichText::new(tag).color(category.color(high_contrast));
+						if ui
+							.selectable_label(false, text)
+							.on_hover_text(category.label())
+							.clicked()
+						{
+							picked = Some(tag.to_string());
+						}
 					}
-					ui.painter().galley(draw_pos, galley, text_color);
 				});
+			});
 
-			ctx.request_repaint();
+		if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+			open = false;
+		}
+		if let Some(tag) = picked {
+			events.push(Event::Browser(BrowserEvent::BlacklistTag { tag }));
+			open = false;
 		}
+		self.tag_picker_open = open;
 	}
 
-	fn render_immersive_breathing_overlay(
-		&self,
-		ctx: &egui::Context,
-		breathing: &BreathingOverlay,
-	) {
-		if !breathing.is_visible() {
+	/// Jump to a loaded post: "42" for the 42nd, "p3" for the first from page 3
+	fn render_goto(&mut self, ctx: &egui::Context, events: &mut Vec<Event>) {
+		if !self.goto_open {
 			return;
 		}
 
-		let state = breathing.state();
-		let elapsed = state.start_time.elapsed().as_secs_f32();
-		let duration = state.duration.as_secs_f32();
-		let progress = (elapsed / duration).clamp(0.0, 1.0);
-
-		let screen_rect = ctx.screen_rect();
-		let screen_width = screen_rect.width();
-		let screen_height = screen_rect.height();
+		let mut open = true;
+		let mut submitted = false;
+		egui::Window::new("Go to")
+			.open(&mut open)
+			.collapsible(false)
+			.resizable(false)
+			.anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+			.show(ctx, |ui| {
+				let response = ui.add(
+					egui::TextEdit::singleline(&mut self.goto_input)
+						.hint_text("42, or p3 for page 3"),
+				);
+				response.request_focus();
+				submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+			});
 
-		// Calculate visual properties based on phase
-		let (text, text_color, bar_fill, bar_bg_alpha, text_alpha) = match state.phase {
-			BreathingPhase::Prepare => {
-				// Text fades in fast, background fades in gradually
-				let text_alpha = (progress * 4.0).min(1.0);
-				let bg_alpha = progress * 0.4;
-				("PREPARE", egui::Color32::RED, 0.0, bg_alpha, text_alpha)
+		if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+			open = false;
+		}
+		if submitted {
+			match parse_jump(&self.goto_input) {
+				Some(direction) => {
+					events.push(Event::Source(SourceEvent::Navigate(direction)));
+					open = false;
+				}
+				None => self.show_toast("Type a position like 42 or a page like p3".to_string()),
 			}
-			BreathingPhase::Inhale => {
-				// Fill bar from 0% to 100%
-				("INHALE", egui::Color32::YELLOW, progress, 0.4, 1.0)
+		}
+		self.goto_open = open;
+	}
+
+	/// Up/Down (or W/S) to choose, Enter or 1-9 to open, Esc or P to close
+	fn handle_pool_picker_keys(&mut self, ctx: &egui::Context, events: &mut Vec<Event>) {
+		let Some(picker) = &mut self.pool_picker else {
+			return;
+		};
+		let mut open = None;
+		let mut close = false;
+		ctx.input_mut(|i| {
+			let mut pressed = |key| i.consume_key(egui::Modifiers::NONE, key);
+			if pressed(egui::Key::ArrowDown) || pressed(egui::Key::S) {
+				picker.selected = (picker.selected + 1).min(picker.pools.len() - 1);
 			}
-			BreathingPhase::Hold => {
-				// Bar stays full
-				("HOLD", egui::Color32::YELLOW, 1.0, 0.4, 1.0)
+			if pressed(egui::Key::ArrowUp) || pressed(egui::Key::W) {
+				picker.selected = picker.selected.saturating_sub(1);
 			}
-			BreathingPhase::Release => {
-				// Empty the bar, fade out background and text
-				let fade = 1.0 - progress;
-				let bg_alpha = 0.4 * fade;
-				("RELEASE", egui::Color32::GREEN, fade, bg_alpha, fade)
+			if pressed(egui::Key::Enter) {
+				open = Some(picker.selected);
 			}
-			BreathingPhase::Idle => {
-				// Fade everything out quickly
-				let alpha = (1.0 - progress * 2.0).max(0.0);
-				("", egui::Color32::TRANSPARENT, 0.0, 0.0, alpha)
+			for (index, key) in POOL_PICKER_KEYS.iter().enumerate() {
+				if index < picker.pools.len() && pressed(*key) {
+					open = Some(index);
+				}
 			}
-		};
+			close = pressed(egui::Key::Escape) || pressed(egui::Key::P);
+		});
 
-		// Skip rendering if completely transparent
-		if text_alpha <= 0.001 && bar_bg_alpha <= 0.001 {
-			return;
+		if let Some(index) = open {
+			events.push(Event::Source(SourceEvent::EnterPool {
+				pool_id: picker.pools[index],
+			}));
+			close = true;
+		}
+		if close {
+			self.pool_picker = None;
 		}
+	}
 
-		ctx.request_repaint();
+	fn render_pool_picker(
+		&mut self,
+		ctx: &egui::Context,
+		gateway: &BooruGateway,
+		events: &mut Vec<Event>,
+	) {
+		let Some(picker) = &mut self.pool_picker else {
+			return;
+		};
 
-		// Render semi-transparent background overlay
-		egui::Area::new(egui::Id::new("immersive_breathing_bg"))
-			.fixed_pos(screen_rect.min)
-			.order(egui::Order::Foreground)
-			.interactable(false)
+		let mut open = true;
+		let mut clicked = None;
+		let mut loading = false;
+		egui::Window::new("Pools")
+			.open(&mut open)
+			.collapsible(false)
+			.resizable(false)
+			.anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
 			.show(ctx, |ui| {
-				let bg_alpha = (bar_bg_alpha * text_alpha * 180.0) as u8;
-				ui.painter().rect_filled(
-					screen_rect,
-					0.0,
-					egui::Color32::from_rgba_unmultiplied(0, 0, 0, bg_alpha),
+				for (index, pool_id) in picker.pools.iter().enumerate() {
+					let name = match gateway.pool_name(*pool_id) {
+						Some(PoolName::Named(name)) => format!("{} (#{})", name, pool_id),
+						Some(PoolName::Loading) | None => {
+							loading = true;
+							format!("#{} ...", pool_id)
+						}
+						Some(PoolName::Unknown) => format!("#{}", pool_id),
+					};
+					let text = match index {
+						0..9 => format!("{}. {}", index + 1, name),
+						_ => name,
+					};
+					if ui
+						.selectable_label(index == picker.selected, text)
+						.clicked()
+					{
+						clicked = Some(index);
+					}
+				}
+				ui.separator();
+				ui.label(
+					egui::RichText::new("Up/Down to choose, Enter to open, Esc to close")
+						.small()
+						.weak(),
 				);
 			});
 
-		// Render progress bar just below the centered text
-		let font_size = screen_height * 0.08;
-		let bar_height = screen_height * 0.015;
-		let text_center_y = screen_height / 2.0;
-		let bar_y = text_center_y + (font_size * 0.6); // Small gap below text
-		let bar_width = screen_width * 0.4;
-		let bar_x = (screen_width - bar_width) / 2.0;
-		let bar_rect =
-			egui::Rect::from_min_size(egui::pos2(bar_x, bar_y), egui::vec2(bar_width, bar_height));
-
-		if bar_bg_alpha > 0.001 {
-			egui::Area::new(egui::Id::new("immersive_breathing_bar"))
-				.fixed_pos(bar_rect.min)
-				.order(egui::Order::Foreground)
-				.interactable(false)
-				.show(ctx, |ui| {
-					let painter = ui.painter();
-					let rounding = bar_height * 0.5;
-
-					// Background track
-					let bg_alpha = (text_alpha * 100.0) as u8;
-					painter.rect_filled(
-						bar_rect,
-						rounding,
-						egui::Color32::from_rgba_unmultiplied(40, 40, 50, bg_alpha),
-					);
-
-					// Filled portion
-					if bar_fill > 0.001 {
-						let fill_width = bar_rect.width() * bar_fill;
-						let fill_rect = egui::Rect::from_min_size(
-							bar_rect.min,
-							egui::vec2(fill_width, bar_height),
-						);
-						let fill_color = text_color.gamma_multiply(text_alpha);
-						painter.rect_filled(fill_rect, rounding, fill_color);
-					}
-				});
+		if loading {
+			self.repaints
+				.after(RepaintCause::Status, Duration::from_millis(100));
 		}
-
-		// Render centered text
-		if !text.is_empty() {
-			egui::Area::new(egui::Id::new("immersive_breathing_text"))
-				.anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
-				.order(egui::Order::Foreground)
-				.interactable(false)
-				.show(ctx, |ui| {
-					let font_id = egui::FontId::proportional(font_size);
-					let display_color = text_color.gamma_multiply(text_alpha);
-					let stroke_width = (font_size * 0.03).max(1.0);
-					Self::draw_outlined_text(ui, text, font_id, display_color, stroke_width);
-				});
+		if let Some(index) = clicked {
+			events.push(Event::Source(SourceEvent::EnterPool {
+				pool_id: picker.pools[index],
+			}));
+			open = false;
+		}
+		if !open {
+			self.pool_picker = None;
 		}
 	}
 
-	fn render_info_overlay(&self, ctx: &egui::Context, browser: &ContentBrowser) {
-		if browser.is_empty() {
+	/// Median and p90 of each loading stage over the last loads, to tell whether
+	/// slow browsing is down to the network, decoding or the queue
+	fn render_load_stats(&self, ctx: &egui::Context, media: &MediaCache) {
+		if !self.load_stats_open {
 			return;
 		}
-
-		let post = match browser.current_post() {
-			Some(p) => p,
-			None => return,
-		};
-
-		let screen_height = ctx.screen_rect().height();
-		let font_size = (screen_height * 0.02).max(12.0);
-		let margin = (screen_height * 0.03).max(10.0);
-		let stroke_width = (font_size * 0.05).max(1.0);
-
-		egui::Area::new(egui::Id::new("image_info_overlay"))
-			.anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(margin, -margin))
+		let stats = media.load_stats();
+		egui::Area::new(egui::Id::new("load_stats"))
+			.anchor(egui::Align2::LEFT_TOP, egui::vec2(20.0, 20.0))
 			.interactable(false)
 			.order(egui::Order::Foreground)
 			.show(ctx, |ui| {
-				let text_color = egui::Color32::WHITE;
-				let font_id = egui::FontId::proportional(font_size);
+				egui::Frame::window(&ctx.style())
+					.fill(egui::Color32::from_black_alpha(220))
+					.inner_margin(12.0)
+					.rounding(8.0)
+					.show(ui, |ui| {
+						ui.label(
+							egui::RichText::new(format!(
+								"Last {} loads, median / p90",
+								stats.samples
+							))
+							.color(egui::Color32::YELLOW)
+							.strong(),
+						);
+						ui.add_space(4.0);
+						Self::render_load_stages(ui, &stats);
+					});
+			});
+	}
 
-				let add_text_line = |ui: &mut egui::Ui, label: &str, content: &str| {
-					if !content.is_empty() {
-						ui.horizontal(|ui| {
-							Self::draw_outlined_text(
-								ui,
-								label,
-								font_id.clone(),
-								egui::Color32::LIGHT_GRAY,
-								stroke_width,
-							);
-							Self::draw_outlined_text(
-								ui,
-								" ",
-								font_id.clone(),
-								egui::Color32::TRANSPARENT,
-								0.0,
-							);
-							Self::draw_outlined_text(
-								ui,
-								content,
-								font_id.clone(),
-								text_color,
-								stroke_width,
+	fn render_load_stages(ui: &mut egui::Ui, stats: &LoadStats) {
+		egui::Grid::new("load_stats_grid").show(ui, |ui| {
+			for (stage, times) in [
+				("Queued", stats.queue_wait),
+				("First byte", stats.first_byte),
+				("Download", stats.download),
+				("Decode", stats.decode),
+				("Upload", stats.upload),
+			] {
+				ui.label(egui::RichText::new(stage).color(egui::Color32::LIGHT_GRAY));
+				ui.label(
+					egui::RichText::new(format!(
+						"{} / {} ms",
+						times.p50.as_millis(),
+						times.p90.as_millis()
+					))
+					.color(egui::Color32::LIGHT_GRAY)
+					.monospace(),
+				);
+				ui.end_row();
+			}
+		});
+	}
+
+	/// Every cache entry, load, queue and failure of the media cache, for finding
+	/// out why an image never got past its sample
+	fn render_cache_inspector(
+		&mut self,
+		ctx: &egui::Context,
+		media: &MediaCache,
+		events: &mut Vec<Event>,
+	) {
+		if !self.cache_inspector_open {
+			return;
+		}
+		let report = media.report();
+		let mb = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+		// e621 names files by their hash; the rest of the URL is the same for all
+		let file_name = |url: &str| url.rsplit('/').next().unwrap_or(url).to_string();
+		let mut open = true;
+		egui::Window::new("Media cache")
+			.open(&mut open)
+			.default_size([640.0, 480.0])
+			.show(ctx, |ui| {
+				ui.horizontal(|ui| {
+					ui.label(format!(
+						"{} images, {:.1} MB",
+						report.entries.len(),
+						mb(report.entries.iter().map(|e| e.bytes).sum())
+					));
+					ui.separator();
+					for category in [
+						CacheCategory::Images,
+						CacheCategory::Pending,
+						CacheCategory::Failures,
+					] {
+						if ui.button(format!("Clear {}", category.label())).clicked() {
+							events.push(Event::Media(MediaEvent::ClearCache { category }));
+						}
+					}
+				});
+				ui.separator();
+
+				ScrollArea::vertical().show(ui, |ui| {
+					egui::CollapsingHeader::new(format!("Cached ({})", report.entries.len()))
+						.default_open(true)
+						.show(ui, |ui| {
+							ui.label(
+								egui::RichText::new("Most recently used first")
+									.small()
+									.weak(),
 							);
+							egui::Grid::new("cache_entries")
+								.striped(true)
+								.show(ui, |ui| {
+									for entry in &report.entries {
+										ui.label(file_name(&entry.key)).on_hover_text(&entry.key);
+										ui.label(match entry.state {
+											CacheState::SampleOnly => "sample",
+											CacheState::Full => "full",
+										});
+										ui.label(if entry.uploaded {
+											"uploaded"
+										} else {
+											"decoded"
+										});
+										ui.label(format!("{}x{}", entry.size[0], entry.size[1]));
+										ui.label(format!("{:.1} MB", mb(entry.bytes)))
+											.on_hover_text("Decoded pixels");
+										ui.label(format!(
+											"{:.1} MB {}",
+											mb(entry.file_bytes),
+											entry.format.unwrap_or("?")
+										))
+										.on_hover_text("Downloaded file");
+										ui.label(format!("{} s ago", entry.loaded.as_secs()))
+											.on_hover_text("Loaded");
+										let marks: Vec<&str> = [
+											(entry.current, "current"),
+											(entry.pinned, "pinned"),
+											(entry.upcoming, "upcoming"),
+										]
+										.into_iter()
+										.filter_map(|(set, mark)| set.then_some(mark))
+										.collect();
+										ui.label(marks.join(", "));
+										if ui
+											.small_button("Evict")
+											.on_hover_text(if entry.current {
+												"Load it again from scratch"
+											} else {
+												"Drop it from the cache"
+											})
+											.clicked()
+										{
+											events.push(Event::Media(MediaEvent::Evict {
+												cache_key: entry.key.clone(),
+											}));
+										}
+										ui.end_row();
+									}
+								});
 						});
-					}
-				};
 
-				ui.vertical(|ui| {
-					add_text_line(ui, "Post ID:", &post.id.to_string());
+					egui::CollapsingHeader::new(format!("Loading ({})", report.in_flight.len()))
+						.default_open(true)
+						.show(ui, |ui| {
+							for load in &report.in_flight {
+								let lanes = match (load.priority, load.general) {
+									(true, true) => "priority + general",
+									(true, false) => "priority",
+									_ => "general",
+								};
+								let stage = if load.decoding { ", decoding" } else { "" };
+								ui.label(format!("{} ({}{})", file_name(&load.url), lanes, stage))
+									.on_hover_text(&load.url);
+							}
+						});
 
-					let artist_str = post.tags.artist.join(", ");
-					if !artist_str.is_empty() && artist_str != "invalid_artist" {
-						add_text_line(ui, "Artist:", &artist_str);
+					for (title, queue) in [
+						("Queued samples", &report.pending_samples),
+						("Queued originals", &report.pending_full),
+					] {
+						egui::CollapsingHeader::new(format!("{} ({})", title, queue.len())).show(
+							ui,
+							|ui| {
+								for (distance, url) in queue {
+									ui.label(format!("+{} {}", distance, file_name(url)))
+										.on_hover_text(url);
+								}
+							},
+						);
 					}
+					egui::CollapsingHeader::new(format!(
+						"Waiting for room ({})",
+						report.deferred.len()
+					))
+					.show(ui, |ui| {
+						for url in &report.deferred {
+							ui.label(file_name(url)).on_hover_text(url);
+						}
+					});
 
-					let copyright_str = post.tags.copyright.join(", ");
-					if !copyright_str.is_empty() && copyright_str != "invalid_copyright" {
-						add_text_line(ui, "Copyright:", &copyright_str);
-					}
+					egui::CollapsingHeader::new(format!("Failed ({})", report.failures.len()))
+						.default_open(true)
+						.show(ui, |ui| {
+							for failure in &report.failures {
+								let kind = if failure.permanent {
+									"for good".to_string()
+								} else {
+									format!("{} times", failure.attempts)
+								};
+								ui.label(format!(
+									"{}: {} ({}, {} s ago)",
+									file_name(&failure.url),
+									failure.last_error,
+									kind,
+									failure.since.as_secs()
+								))
+								.on_hover_text(&failure.url);
+							}
+						});
 				});
 			});
+
+		// Loads land without input; keep the lists moving while they're shown
+		self.repaints
+			.after(RepaintCause::Status, Duration::from_millis(250));
+		self.cache_inspector_open = open;
 	}
 
 	fn draw_outlined_text(
@@ -1396,7 +5073,17 @@ impl ViewManager {
 		font_id: egui::FontId,
 		color: egui::Color32,
 		stroke_width: f32,
+		high_contrast: bool,
 	) {
+		// Keep fades, just not the colour
+		let (color, stroke_width) = if high_contrast {
+			(
+				egui::Color32::from_white_alpha(color.a()),
+				stroke_width * HIGH_CONTRAST_STROKE,
+			)
+		} else {
+			(color, stroke_width)
+		};
 		let galley = ui
 			.painter()
 			.layout_no_wrap(text.to_string(), font_id.clone(), color);
@@ -1413,10 +5100,15 @@ impl ViewManager {
 			egui::vec2(stroke_width, stroke_width),
 		];
 
-		let num_passes = offsets.len() as f32;
-		let base_alpha = color.a() as f32;
-		let per_pass_alpha = (base_alpha / num_passes).max(1.0) as u8;
-		let shadow_color = egui::Color32::from_rgba_unmultiplied(0, 0, 0, per_pass_alpha);
+		let shadow_color = if high_contrast {
+			egui::Color32::from_black_alpha(color.a())
+		} else {
+			let num_passes = offsets.len() as f32;
+			let base_alpha = color.a() as f32;
+			let per_pass_alpha = (base_alpha / num_passes).max(1.0) as u8;
+			let [r, g, b, _] = Self::outline_color_for(color).to_array();
+			egui::Color32::from_rgba_unmultiplied(r, g, b, per_pass_alpha)
+		};
 
 		for offset in offsets {
 			let shadow_galley =
@@ -1429,28 +5121,81 @@ impl ViewManager {
 		ui.painter().galley(rect.min, galley, color);
 	}
 
-	/// Render debug beat dot, pulses on beat detection
-	fn render_beat_debug(&mut self, ctx: &egui::Context, _beat: &SystemBeat) {
+	/// Black outline for light text, white for dark text, so either stays legible
+	fn outline_color_for(color: egui::Color32) -> egui::Color32 {
+		let [r, g, b, _] = color.to_srgba_unmultiplied();
+		let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+		if luma < 80.0 {
+			egui::Color32::WHITE
+		} else {
+			egui::Color32::BLACK
+		}
+	}
+
+	/// Fade the last beat out, every frame so the zoom eases back even without the dot
+	fn decay_beat_intensity(&mut self) {
 		let elapsed = self.last_beat_time.elapsed().as_secs_f32();
 		let decay_rate = 4.6;
 		self.beat_intensity = self.last_beat_scale * (-decay_rate * elapsed).exp();
+	}
+
+	/// Second window; the mirror is covered too while revealing, though without the fade
+	fn render_mirror(
+		&mut self,
+		ctx: &egui::Context,
+		browser: &ContentBrowser,
+		media: &mut MediaCache,
+		breathing: &BreathingOverlay,
+	) {
+		let mirror_media = match browser.current_post() {
+			Some(post) if self.reveal.cover(post.id) > 0.5 => media.blurred_by_post(post),
+			_ => media.get_current_media(),
+		};
+		self.mirror.show(
+			ctx,
+			mirror_media,
+			self.image_load_time,
+			self.pan_cycle_duration(),
+			breathing,
+			self.overlay_style,
+		);
+	}
 
+	/// Render debug beat dot, pulses on beat detection
+	fn render_beat_debug(
+		&mut self,
+		ctx: &egui::Context,
+		_beat: &SystemBeat,
+		breathing: &BreathingOverlay,
+	) {
 		if self.beat_intensity < 0.01 {
 			return;
 		}
 
-		ctx.request_repaint();
+		self.repaints.after(RepaintCause::Pulse, PULSE_FRAME);
 
-		let screen_rect = ctx.screen_rect();
+		let screen_rect = Self::overlay_rect(ctx);
 		let margin = 20.0;
 		let base_radius = 6.0;
 		let bounce = 10.0;
 		let radius = base_radius + self.beat_intensity * bounce;
 
-		let center = egui::pos2(
-			screen_rect.right() - margin - base_radius,
-			screen_rect.bottom() - margin - base_radius,
-		);
+		// Move out of the way of a classic overlay in the same corner
+		let overlay_in_corner = breathing.is_visible()
+			&& breathing.style() == BreathingStyle::Classic
+			&& breathing.anchor() == OverlayAnchor::BottomRight;
+		let center = if overlay_in_corner {
+			let top = ctx.available_rect().top();
+			egui::pos2(
+				screen_rect.right() - margin - base_radius,
+				top + margin + base_radius,
+			)
+		} else {
+			egui::pos2(
+				screen_rect.right() - margin - base_radius,
+				screen_rect.bottom() - margin - base_radius,
+			)
+		};
 
 		let alpha = (self.beat_intensity * 255.0) as u8;
 		let color = egui::Color32::from_rgba_unmultiplied(0, 220, 255, alpha);
@@ -1473,39 +5218,71 @@ impl ViewManager {
 	}
 
 	/// Render island navigation overlay and handle actions
-	fn render_island_overlay(&mut self, ctx: &egui::Context, events: &mut Vec<Event>) {
-		if !matches!(self.modal, ModalContent::None) {
+	fn render_island_overlay(
+		&mut self,
+		ctx: &egui::Context,
+		beat: &SystemBeat,
+		events: &mut Vec<Event>,
+	) {
+		if !self.modals.is_empty() {
 			return;
 		}
 
-		if let Some(action) = IslandWidget::new(&mut self.island_ctx).show(ctx) {
+		// Keep built islands in sync with live state, e.g. devices coming and going
+		if let Some(kind) = self.island_ctx.current_dynamic() {
+			self.island_ctx
+				.replace_built(build_island(kind, beat, &self.recent_searches).0);
+		}
+
+		let action = IslandWidget::new(&mut self.island_ctx).show(ctx);
+		if std::mem::take(&mut self.island_ctx.animating) {
+			self.repaints.now(RepaintCause::Animation);
+		}
+		if let Some(action) = action {
 			match action {
-				IslandAction::Emit(factory) => {
-					let event = factory();
-					// Intercept breathing toggle request to check disclaimer
-					if matches!(event, Event::View(ViewEvent::RequestBreathingToggle)) {
-						if !self.breathing_disclaimer_accepted {
-							self.modal = ModalContent::BreathingDisclaimer;
-						} else {
-							events.push(Event::Breathing(BreathingEvent::Toggle));
-						}
-					} else {
-						events.push(event);
+				IslandAction::Emit(factory) => events.push(factory()),
+				IslandAction::Send(event) => events.push(event),
+				IslandAction::Push(island) => self.island_ctx.push(island),
+				IslandAction::Open(kind) => {
+					let (island, marked) = build_island(kind, beat, &self.recent_searches);
+					self.island_ctx.push_built(island, marked);
+					if kind == DynamicIsland::AudioDevices {
+						events.push(Event::Beat(BeatEvent::RefreshDevices));
 					}
 				}
-				IslandAction::Push(island) => self.island_ctx.push(island),
 				IslandAction::Pop => {
 					self.island_ctx.pop();
 				}
+				IslandAction::Input => self.island_ctx.begin_input(),
+				IslandAction::Query(query) => {
+					self.island_ctx.deactivate(ctx);
+					self.search_query = query;
+					self.search_page_input = "1".to_string();
+					self.submit_search(1, events);
+				}
 			}
 		}
 	}
 
-	/// Render modal popup overlay
+	/// Queue a modal behind any already open; a modal is never queued twice
+	fn show_modal(&mut self, kind: ModalKind) {
+		let queued = self.modals.iter().any(|m| match (m, &kind) {
+			(ModalKind::Hello, ModalKind::Hello)
+			| (ModalKind::BreathingDisclaimer, ModalKind::BreathingDisclaimer) => true,
+			(ModalKind::Confirm { title: a, .. }, ModalKind::Confirm { title: b, .. }) => a == b,
+			(ModalKind::Diagnostics { .. }, ModalKind::Diagnostics { .. }) => true,
+			_ => false,
+		});
+		if !queued {
+			self.modals.push_back(kind);
+		}
+	}
+
+	/// Render the front modal popup
 	fn render_modal(&mut self, ctx: &egui::Context, events: &mut Vec<Event>) {
-		if matches!(self.modal, ModalContent::None) {
+		let Some(modal) = self.modals.front().cloned() else {
 			return;
-		}
+		};
 
 		let screen_rect = ctx.screen_rect();
 
@@ -1531,11 +5308,13 @@ impl ViewManager {
 			.order(egui::Order::Foreground)
 			.show(ctx, |ui| {
 				ui.set_width(450.0);
-				ui.vertical_centered(|ui| match &self.modal.clone() {
-					ModalContent::Hello => {
+				ui.vertical_centered(|ui| match modal {
+					ModalKind::Hello => {
 						ui.add_space(10.0);
 						ui.heading("Welcome! Please read the Terms of Use.");
-						ui.label("Make sure you are of legal age to view this content.");
+						if !self.safe_mode_locked {
+							ui.label("Make sure you are of legal age to view this content.");
+						}
 						ui.add_space(10.0);
 
 						// Framed ScrollArea for legal text
@@ -1559,7 +5338,10 @@ impl ViewManager {
 						ui.add_space(10.0);
 
 						ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
-							ui.checkbox(&mut self.user_is_adult, "I am 18 years of age or older.");
+							// Safe mode only shows posts rated safe
+							if !self.safe_mode_locked {
+								ui.checkbox(&mut self.user_is_adult, "I am 18 years of age or older.");
+							}
 							ui.checkbox(
 								&mut self.user_accepted_tos,
 								"I have read and accept the Terms of Use.",
@@ -1573,16 +5355,17 @@ impl ViewManager {
 								std::process::exit(0);
 							}
 							ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-								if !self.user_accepted_tos || !self.user_is_adult {
+								if !self.user_accepted_tos || !(self.user_is_adult || self.safe_mode_locked) {
 									ui.disable();
 								}
 								if ui.button("   Enter   ").clicked() {
-									self.modal = ModalContent::None;
+									self.modals.pop_front();
+									events.push(Event::View(ViewEvent::TosAccepted));
 								}
 							});
 						});
 					}
-					ModalContent::BreathingDisclaimer => {
+					ModalKind::BreathingDisclaimer => {
 						ui.add_space(10.0);
 						ui.heading("Breathing Disclaimer");
 						ui.label("Please read the disclaimer below before using this functionality.");
@@ -1623,7 +5406,7 @@ impl ViewManager {
 
 						ui.horizontal(|ui| {
 							if ui.button("   Decline   ").clicked() {
-								self.modal = ModalContent::None;
+								self.modals.pop_front();
 								self.breathing_disclaimer_checked = false;
 							}
 							ui.with_layout(
@@ -1634,14 +5417,71 @@ impl ViewManager {
 									}
 									if ui.button("   Accept   ").clicked() {
 										self.breathing_disclaimer_accepted = true;
-										self.modal = ModalContent::None;
+										self.modals.pop_front();
 										events.push(Event::Breathing(BreathingEvent::Toggle));
 									}
 								},
 							);
 						});
 					},
-					ModalContent::None => {}
+					ModalKind::Confirm {
+						title,
+						body,
+						buttons,
+					} => {
+						ui.add_space(10.0);
+						ui.heading(title);
+						ui.add_space(6.0);
+						ui.label(body);
+						ui.add_space(10.0);
+						ui.horizontal(|ui| {
+							let mut pressed = None;
+							for button in buttons {
+								if ui.button(format!("   {}   ", button.label)).clicked() {
+									pressed = Some(button.event);
+								}
+							}
+							if let Some(event) = pressed {
+								self.modals.pop_front();
+								events.extend(event);
+							} else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+								// Like Cancel
+								events.push(Event::View(ViewEvent::DismissModal));
+							}
+						});
+					}
+					ModalKind::Diagnostics { report } => {
+						ui.add_space(10.0);
+						ui.heading("Diagnostics");
+						ui.add_space(6.0);
+						egui::Grid::new("diagnostics_results")
+							.num_columns(3)
+							.spacing([12.0, 4.0])
+							.show(ui, |ui| {
+								for result in &report.results {
+									let (color, detail) = match &result.outcome {
+										Ok(detail) => (egui::Color32::from_rgb(120, 200, 120), detail),
+										Err(error) => (egui::Color32::from_rgb(230, 90, 90), error),
+									};
+									ui.colored_label(color, result.check.label());
+									ui.label(format!("{} ms", result.took.as_millis()));
+									ui.add(egui::Label::new(egui::RichText::new(detail).color(color)).wrap());
+									ui.end_row();
+								}
+							});
+						ui.add_space(10.0);
+						ui.horizontal(|ui| {
+							if ui.button("   Copy report   ").clicked() {
+								ctx.copy_text(report.to_text());
+								self.show_toast("Diagnostics copied".to_string());
+							}
+							if ui.button("   Close   ").clicked()
+								|| ctx.input(|i| i.key_pressed(egui::Key::Escape))
+							{
+								events.push(Event::View(ViewEvent::DismissModal));
+							}
+						});
+					}
 				});
 			});
 	}
@@ -1653,12 +5493,88 @@ impl Default for ViewManager {
 			"~gay ~male solo abs wolf order:score".to_owned(),
 			"1".to_owned(),
 			10.0,
+			PanMode::default(),
 			false,
 			0.03,
 			ImageFillMode::default(),
 			false,
 			None,
 			None,
+			true,
+			false,
+			false,
 		)
 	}
 }
+
+/// Page number typed into the page field, if e621 can serve it
+fn parse_page(input: &str) -> Option<u32> {
+	input
+		.trim()
+		.parse::<u32>()
+		.ok()
+		.filter(|page| (1..=MAX_PAGE).contains(page))
+}
+
+/// What the go-to popup was given: a 1-based position, or a page after a "p"
+fn parse_jump(input: &str) -> Option<NavDirection> {
+	let input = input.trim();
+	if let Some(page) = input.strip_prefix(['p', 'P']) {
+		return page
+			.trim()
+			.parse::<u32>()
+			.ok()
+			.filter(|page| *page > 0)
+			.map(NavDirection::ToPage);
+	}
+	input
+		.parse::<usize>()
+		.ok()
+		.filter(|position| *position > 0)
+		.map(NavDirection::ToPosition)
+}
+
+/// Put a failed search in terms the user can act on
+fn describe_search_error(message: &str, category: ErrorCategory) -> String {
+	match category {
+		ErrorCategory::Network => "Can't reach e621, check your connection".to_string(),
+		ErrorCategory::RateLimited => "e621 is busy, try again in a moment".to_string(),
+		ErrorCategory::Http => format!("e621 refused the search: {}", message),
+		ErrorCategory::Decode => format!(
+			"Unexpected response from e621, the API may have changed. {}",
+			message
+		),
+		ErrorCategory::Credentials => message.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn jump_input_is_a_position_or_a_page() {
+		let cases = [
+			// Valid
+			("12", Some(NavDirection::ToPosition(12))),
+			("  3 ", Some(NavDirection::ToPosition(3))),
+			("p4", Some(NavDirection::ToPage(4))),
+			("P 7", Some(NavDirection::ToPage(7))),
+			// Out of range
+			("0", None),
+			("p0", None),
+			("-2", None),
+			("p99999999999", None),
+			// Malformed
+			("", None),
+			("p", None),
+			("abc", None),
+			("1.5", None),
+			("4p", None),
+			("pp2", None),
+		];
+		for (input, expected) in cases {
+			assert_eq!(parse_jump(input), expected, "input {:?}", input);
+		}
+	}
+}
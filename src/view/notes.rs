@@ -0,0 +1,143 @@
+use crate::api::Note;
+use eframe::egui;
+
+/// Scale that fits `img_size` entirely inside `space`
+pub fn fit_scale(img_size: egui::Vec2, space: egui::Vec2) -> f32 {
+	(space.x / img_size.x).min(space.y / img_size.y)
+}
+
+/// Scale that makes `img_size` cover all of `space`
+pub fn cover_scale(img_size: egui::Vec2, space: egui::Vec2) -> f32 {
+	(space.x / img_size.x).max(space.y / img_size.y)
+}
+
+/// Scroll offset that keeps the spot in the middle of `viewport` there when the
+/// displayed image goes from `from` to `to` in size, even if its shape changed
+pub fn carry_scroll_offset(
+	offset: egui::Vec2,
+	viewport: egui::Vec2,
+	from: egui::Vec2,
+	to: egui::Vec2,
+) -> egui::Vec2 {
+	let middle = (offset + viewport * 0.5) / from;
+	middle * to - viewport * 0.5
+}
+
+/// `rect` scaled by `scale` around `focus`, which stays where it is on screen
+pub fn scale_about(rect: egui::Rect, focus: egui::Pos2, scale: f32) -> egui::Rect {
+	egui::Rect::from_center_size(focus + (rect.center() - focus) * scale, rect.size() * scale)
+}
+
+/// Maps source image pixel coordinates onto the rect the image was painted to
+#[derive(Clone, Copy)]
+pub struct ImageTransform {
+	display_rect: egui::Rect,
+	source_size: egui::Vec2,
+}
+
+impl ImageTransform {
+	/// `source_size` is the size the coordinates are expressed in (the original file)
+	pub fn new(display_rect: egui::Rect, source_size: egui::Vec2) -> Option<Self> {
+		if source_size.x <= 0.0 || source_size.y <= 0.0 {
+			return None;
+		}
+		Some(Self {
+			display_rect,
+			source_size,
+		})
+	}
+
+	/// Map a rect in source pixels to screen space
+	pub fn map_rect(&self, min: egui::Pos2, size: egui::Vec2) -> egui::Rect {
+		let scale = egui::vec2(
+			self.display_rect.width() / self.source_size.x,
+			self.display_rect.height() / self.source_size.y,
+		);
+		egui::Rect::from_min_size(
+			self.display_rect.min + egui::vec2(min.x * scale.x, min.y * scale.y),
+			egui::vec2(size.x * scale.x, size.y * scale.y),
+		)
+	}
+}
+
+/// Draw note boxes over the image, showing the body as a tooltip on hover
+pub fn render_notes(ui: &mut egui::Ui, notes: &[Note], transform: ImageTransform) {
+	for note in notes {
+		let rect = transform.map_rect(
+			egui::pos2(note.x as f32, note.y as f32),
+			egui::vec2(note.width as f32, note.height as f32),
+		);
+		if !rect.intersects(ui.clip_rect()) {
+			continue;
+		}
+
+		let response = ui.interact(rect, ui.id().with(("note", note.id)), egui::Sense::hover());
+		let (fill, stroke) = if response.hovered() {
+			(
+				egui::Color32::from_rgba_unmultiplied(255, 255, 220, 90),
+				egui::Color32::from_rgb(255, 255, 220),
+			)
+		} else {
+			(
+				egui::Color32::from_rgba_unmultiplied(255, 255, 220, 40),
+				egui::Color32::from_rgba_unmultiplied(0, 0, 0, 160),
+			)
+		};
+		ui.painter().rect_filled(rect, 2.0, fill);
+		ui.painter()
+			.rect_stroke(rect, 2.0, egui::Stroke::new(1.0, stroke));
+
+		response.on_hover_text(strip_markup(&note.body));
+	}
+}
+
+/// Notes use a small subset of HTML; drop the tags for plain tooltip display
+fn strip_markup(body: &str) -> String {
+	let mut out = String::with_capacity(body.len());
+	let mut in_tag = false;
+	for ch in body.chars() {
+		match ch {
+			'<' => in_tag = true,
+			'>' if in_tag => in_tag = false,
+			_ if !in_tag => out.push(ch),
+			_ => {}
+		}
+	}
+	out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scroll_offset_follows_a_resize() {
+		let viewport = egui::vec2(200.0, 100.0);
+		let from = egui::vec2(800.0, 400.0);
+		let offset = egui::vec2(100.0, 50.0);
+		// Twice the size: the middle of the view stays a quarter of the way in
+		assert_eq!(
+			carry_scroll_offset(offset, viewport, from, from * 2.0),
+			egui::vec2(300.0, 150.0)
+		);
+		// Taller only, as when a cropped sample gives way to the original
+		assert_eq!(
+			carry_scroll_offset(offset, viewport, from, egui::vec2(800.0, 800.0)),
+			egui::vec2(100.0, 150.0)
+		);
+		assert_eq!(carry_scroll_offset(offset, viewport, from, from), offset);
+	}
+
+	#[test]
+	fn zero_viewport_scales_the_offset() {
+		assert_eq!(
+			carry_scroll_offset(
+				egui::vec2(100.0, 50.0),
+				egui::Vec2::ZERO,
+				egui::vec2(800.0, 400.0),
+				egui::vec2(400.0, 200.0),
+			),
+			egui::vec2(50.0, 25.0)
+		);
+	}
+}
@@ -0,0 +1,594 @@
+use super::ViewManager;
+use crate::api::Post;
+use crate::beat::SystemBeat;
+use crate::breathing::BreathingOverlay;
+use crate::browser::ContentBrowser;
+use crate::gateway::BooruGateway;
+use crate::gateway::query::NON_ARTIST_TAGS;
+use crate::media::MediaCache;
+use crate::reactor::Event;
+use crate::settings::SettingsManager;
+use eframe::egui;
+use std::collections::HashSet;
+
+/// What overlays get to look at while drawing one frame
+pub(crate) struct OverlayFrame<'a> {
+	pub ctx: &'a egui::Context,
+	pub gateway: &'a BooruGateway,
+	pub browser: &'a ContentBrowser,
+	pub media: &'a mut MediaCache,
+	pub breathing: &'a BreathingOverlay,
+	pub settings: &'a SettingsManager,
+	pub beat: &'a SystemBeat,
+}
+
+/// Something drawn above the image after the panels are laid out
+pub(crate) trait Overlay {
+	/// Also the label of its checkbox in the Overlays menu
+	fn name(&self) -> &'static str;
+
+	/// Overlays are drawn from the lowest layer up, so higher layers end up on top
+	fn layer(&self) -> i32;
+
+	/// Whether "Hide overlays" hides it. Whatever the user opened on purpose stays.
+	fn hideable(&self) -> bool {
+		true
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event>;
+}
+
+/// The overlays, kept in drawing order
+#[derive(Default)]
+pub(crate) struct Overlays {
+	overlays: Vec<Box<dyn Overlay>>,
+	/// Turned off one by one
+	hidden: HashSet<&'static str>,
+	/// Kiosk switch: hides every hideable overlay
+	pub(crate) hide_all: bool,
+}
+
+impl Overlays {
+	pub fn builtin() -> Self {
+		let mut overlays = Self::default();
+		overlays.register(Box::new(BreathingLayer));
+		overlays.register(Box::new(FilmstripLayer));
+		overlays.register(Box::new(InfoLayer));
+		overlays.register(Box::new(MirrorLayer));
+		overlays.register(Box::new(BeatDotLayer));
+		overlays.register(Box::new(IslandLayer));
+		overlays.register(Box::new(TagPickerLayer));
+		overlays.register(Box::new(GoToLayer));
+		overlays.register(Box::new(PoolPickerLayer));
+		overlays.register(Box::new(CacheInspectorLayer));
+		overlays.register(Box::new(LoadStatsLayer));
+		overlays.register(Box::new(ToastLayer));
+		overlays.register(Box::new(ReadoutLayer));
+		overlays.register(Box::new(CreditLayer));
+		overlays.register(Box::new(ModalLayer));
+		overlays
+	}
+
+	/// Add an overlay above those already registered on its layer
+	pub fn register(&mut self, overlay: Box<dyn Overlay>) {
+		let index = self
+			.overlays
+			.partition_point(|other| other.layer() <= overlay.layer());
+		self.overlays.insert(index, overlay);
+	}
+
+	pub fn is_visible(&self, name: &str) -> bool {
+		!self.hidden.contains(name)
+	}
+
+	pub fn set_visible(&mut self, name: &'static str, visible: bool) {
+		if visible {
+			self.hidden.remove(name);
+		} else {
+			self.hidden.insert(name);
+		}
+	}
+
+	/// Names of the overlays that can be hidden, in drawing order
+	pub fn hideable_names(&self) -> Vec<&'static str> {
+		self.overlays
+			.iter()
+			.filter(|overlay| overlay.hideable())
+			.map(|overlay| overlay.name())
+			.collect()
+	}
+
+	fn skipped(&self, overlay: &dyn Overlay) -> bool {
+		overlay.hideable() && (self.hide_all || self.hidden.contains(overlay.name()))
+	}
+
+	/// Draw every shown overlay, lowest layer first
+	pub fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		for index in 0..self.overlays.len() {
+			if self.skipped(self.overlays[index].as_ref()) {
+				continue;
+			}
+			events.extend(self.overlays[index].render(view, frame));
+		}
+		events
+	}
+}
+
+struct BreathingLayer;
+
+impl Overlay for BreathingLayer {
+	fn name(&self) -> &'static str {
+		"Breathing"
+	}
+
+	fn layer(&self) -> i32 {
+		0
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		ViewManager::render_breathing(
+			frame.ctx,
+			frame.breathing,
+			view.overlay_style,
+			&mut view.repaints,
+		);
+		Vec::new()
+	}
+}
+
+/// Below the info overlay, which may overlap it in a narrow window
+struct FilmstripLayer;
+
+impl Overlay for FilmstripLayer {
+	fn name(&self) -> &'static str {
+		"Filmstrip"
+	}
+
+	fn layer(&self) -> i32 {
+		5
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		view.render_filmstrip(frame.ctx, frame.browser, frame.media, &mut events);
+		events
+	}
+}
+
+struct InfoLayer;
+
+impl Overlay for InfoLayer {
+	fn name(&self) -> &'static str {
+		"Post info"
+	}
+
+	fn layer(&self) -> i32 {
+		10
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		view.render_info_overlay(
+			frame.ctx,
+			frame.gateway,
+			frame.browser,
+			frame.media,
+			frame.settings,
+		);
+		Vec::new()
+	}
+}
+
+/// The second window isn't drawn over the image, but it's drawn at this point of the frame
+struct MirrorLayer;
+
+impl Overlay for MirrorLayer {
+	fn name(&self) -> &'static str {
+		"Mirror"
+	}
+
+	fn layer(&self) -> i32 {
+		20
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		view.render_mirror(frame.ctx, frame.browser, frame.media, frame.breathing);
+		Vec::new()
+	}
+}
+
+struct BeatDotLayer;
+
+impl Overlay for BeatDotLayer {
+	fn name(&self) -> &'static str {
+		"Beat dot"
+	}
+
+	fn layer(&self) -> i32 {
+		30
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		view.render_beat_debug(frame.ctx, frame.beat, frame.breathing);
+		Vec::new()
+	}
+}
+
+struct IslandLayer;
+
+impl Overlay for IslandLayer {
+	fn name(&self) -> &'static str {
+		"Island"
+	}
+
+	fn layer(&self) -> i32 {
+		40
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		view.render_island_overlay(frame.ctx, frame.beat, &mut events);
+		events
+	}
+}
+
+struct TagPickerLayer;
+
+impl Overlay for TagPickerLayer {
+	fn name(&self) -> &'static str {
+		"Tag picker"
+	}
+
+	fn layer(&self) -> i32 {
+		50
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		view.render_tag_picker(frame.ctx, frame.browser, &mut events);
+		events
+	}
+}
+
+struct GoToLayer;
+
+impl Overlay for GoToLayer {
+	fn name(&self) -> &'static str {
+		"Go to"
+	}
+
+	fn layer(&self) -> i32 {
+		55
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		view.render_goto(frame.ctx, &mut events);
+		events
+	}
+}
+
+struct PoolPickerLayer;
+
+impl Overlay for PoolPickerLayer {
+	fn name(&self) -> &'static str {
+		"Pool picker"
+	}
+
+	fn layer(&self) -> i32 {
+		60
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		view.render_pool_picker(frame.ctx, frame.gateway, &mut events);
+		events
+	}
+}
+
+struct CacheInspectorLayer;
+
+impl Overlay for CacheInspectorLayer {
+	fn name(&self) -> &'static str {
+		"Cache inspector"
+	}
+
+	fn layer(&self) -> i32 {
+		65
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		view.render_cache_inspector(frame.ctx, frame.media, &mut events);
+		events
+	}
+}
+
+struct LoadStatsLayer;
+
+impl Overlay for LoadStatsLayer {
+	fn name(&self) -> &'static str {
+		"Load timing"
+	}
+
+	fn layer(&self) -> i32 {
+		65
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		view.render_load_stats(frame.ctx, frame.media);
+		Vec::new()
+	}
+}
+
+struct ToastLayer;
+
+impl Overlay for ToastLayer {
+	fn name(&self) -> &'static str {
+		"Toasts"
+	}
+
+	fn layer(&self) -> i32 {
+		70
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		view.render_toast(frame.ctx, &mut events);
+		events
+	}
+}
+
+/// Answers a key press, so "Hide overlays" doesn't swallow it
+struct ReadoutLayer;
+
+impl Overlay for ReadoutLayer {
+	fn name(&self) -> &'static str {
+		"Readout"
+	}
+
+	fn layer(&self) -> i32 {
+		80
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		view.render_readout(frame.ctx, frame.settings, frame.breathing);
+		Vec::new()
+	}
+}
+
+/// "art by ..." for screen sharing. Turned on in the settings, so "Hide overlays"
+/// leaves it be: a stream shouldn't lose the credit along with the clutter.
+struct CreditLayer;
+
+impl Overlay for CreditLayer {
+	fn name(&self) -> &'static str {
+		"Credit"
+	}
+
+	fn layer(&self) -> i32 {
+		90
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let credit = frame.settings.credit_overlay();
+		let Some(post) = frame.browser.current_post().filter(|_| credit.enabled) else {
+			return Vec::new();
+		};
+		let ctx = frame.ctx;
+		let screen_height = ctx.screen_rect().height();
+		let font_size = (screen_height * 0.018).max(12.0);
+		let margin = (screen_height * 0.03).max(10.0);
+		let align = credit.corner.align();
+		let offset = egui::vec2(
+			if align.x() == egui::Align::Min {
+				margin
+			} else {
+				-margin
+			},
+			if align.y() == egui::Align::Min {
+				margin
+			} else {
+				-margin
+			},
+		);
+		let alpha = (credit.opacity * 255.0) as u8;
+		let high_contrast = view.overlay_style.high_contrast;
+
+		egui::Area::new(egui::Id::new("credit_overlay"))
+			.constrain_to(ViewManager::overlay_rect(ctx))
+			.anchor(align, offset)
+			.interactable(false)
+			.order(egui::Order::Foreground)
+			.show(ctx, |ui| {
+				egui::Frame::none()
+					.fill(egui::Color32::from_black_alpha(
+						(credit.opacity * 160.0) as u8,
+					))
+					.rounding(font_size)
+					.inner_margin(egui::Margin::symmetric(font_size * 0.7, font_size * 0.3))
+					.show(ui, |ui| {
+						ViewManager::draw_outlined_text(
+							ui,
+							&credit_text(post),
+							egui::FontId::proportional(font_size),
+							egui::Color32::from_white_alpha(alpha),
+							(font_size * 0.05).max(1.0),
+							high_contrast,
+						);
+					});
+			});
+		Vec::new()
+	}
+}
+
+/// "art by {artists} — e621 #{id}", skipping tags that aren't really artists
+fn credit_text(post: &Post) -> String {
+	let artists: Vec<&str> = post
+		.tags
+		.artist
+		.iter()
+		.map(String::as_str)
+		.filter(|artist| !NON_ARTIST_TAGS.contains(artist))
+		.collect();
+	let artists = if artists.is_empty() {
+		"unknown artist".to_string()
+	} else {
+		artists.join(", ")
+	};
+	format!("art by {} — e621 #{}", artists, post.id)
+}
+
+/// On top of everything, and never hidden: disclaimers have to be answered
+struct ModalLayer;
+
+impl Overlay for ModalLayer {
+	fn name(&self) -> &'static str {
+		"Modal"
+	}
+
+	fn layer(&self) -> i32 {
+		100
+	}
+
+	fn hideable(&self) -> bool {
+		false
+	}
+
+	fn render(&mut self, view: &mut ViewManager, frame: &mut OverlayFrame) -> Vec<Event> {
+		let mut events = Vec::new();
+		view.render_modal(frame.ctx, &mut events);
+		events
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn drawn(overlays: &Overlays) -> Vec<&'static str> {
+		overlays
+			.overlays
+			.iter()
+			.filter(|overlay| !overlays.skipped(overlay.as_ref()))
+			.map(|overlay| overlay.name())
+			.collect()
+	}
+
+	#[test]
+	fn builtin_overlays_keep_the_old_drawing_order() {
+		let mut overlays = Overlays::builtin();
+		assert_eq!(
+			drawn(&overlays),
+			[
+				"Breathing",
+				"Filmstrip",
+				"Post info",
+				"Mirror",
+				"Beat dot",
+				"Island",
+				"Tag picker",
+				"Go to",
+				"Pool picker",
+				"Cache inspector",
+				"Load timing",
+				"Toasts",
+				"Readout",
+				"Credit",
+				"Modal"
+			]
+		);
+
+		overlays.set_visible("Beat dot", false);
+		assert!(!drawn(&overlays).contains(&"Beat dot"));
+		overlays.hide_all = true;
+		assert_eq!(
+			drawn(&overlays),
+			[
+				"Mirror",
+				"Island",
+				"Tag picker",
+				"Go to",
+				"Pool picker",
+				"Cache inspector",
+				"Load timing",
+				"Readout",
+				"Credit",
+				"Modal"
+			]
+		);
+	}
+
+	#[test]
+	fn overlays_on_the_same_layer_draw_in_registration_order() {
+		struct Named(&'static str, i32);
+
+		impl Overlay for Named {
+			fn name(&self) -> &'static str {
+				self.0
+			}
+
+			fn layer(&self) -> i32 {
+				self.1
+			}
+
+			fn render(&mut self, _: &mut ViewManager, _: &mut OverlayFrame) -> Vec<Event> {
+				Vec::new()
+			}
+		}
+
+		let mut overlays = Overlays::default();
+		overlays.register(Box::new(Named("top", 5)));
+		overlays.register(Box::new(Named("first", 1)));
+		overlays.register(Box::new(Named("second", 1)));
+		assert_eq!(drawn(&overlays), ["first", "second", "top"]);
+	}
+
+	#[test]
+	fn credit_names_real_artists_or_falls_back() {
+		let mut post = Post {
+			id: 42,
+			..Default::default()
+		};
+		post.tags.artist = vec!["conditional_dnp".to_string(), "someone".to_string()];
+		assert_eq!(credit_text(&post), "art by someone — e621 #42");
+		post.tags.artist = vec!["unknown_artist".to_string()];
+		assert_eq!(credit_text(&post), "art by unknown artist — e621 #42");
+	}
+}
@@ -0,0 +1,148 @@
+/// Part of the last screen still in view after paging, so the eye can find its place
+const PAGE_OVERLAP: f32 = 0.1;
+
+/// Offsets this close to an end count as there; layout leaves fractions of a point
+const EDGE_SLACK: f32 = 1.0;
+
+/// What paging down or up does from where the page is scrolled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PageTurn {
+	ScrollTo(f32),
+	Next,
+	Prev,
+}
+
+/// Reading mode: images fit to the window's width and scroll down a screen at a time,
+/// going on to the next post from the bottom
+#[derive(Default)]
+pub(crate) struct Reading {
+	pub enabled: bool,
+	/// Post the scroll position belongs to
+	pub post_id: Option<u64>,
+	/// Scroll position, height of the image and height of the view, as of the last frame
+	pub offset: f32,
+	pub content: f32,
+	pub viewport: f32,
+	/// Scroll here on the next frame
+	pub jump: Option<f32>,
+	/// Paged back onto the previous post, which then starts at its bottom
+	pub start_at_bottom: bool,
+}
+
+impl Reading {
+	/// Forget the last page's scroll position as soon as `current` is another post,
+	/// so paging before the new image is drawn doesn't go by the old one
+	pub fn follow(&mut self, current: Option<u64>) {
+		if self.post_id != current {
+			self.offset = 0.0;
+			self.content = 0.0;
+			self.viewport = 0.0;
+		}
+	}
+
+	/// Space or PageDown when `forward`, PageUp otherwise
+	pub fn turn(&self, forward: bool) -> PageTurn {
+		if forward {
+			page_down(self.offset, self.content, self.viewport)
+		} else {
+			page_up(self.offset, self.viewport)
+		}
+	}
+}
+
+fn bottom(content: f32, viewport: f32) -> f32 {
+	(content - viewport).max(0.0)
+}
+
+fn step(viewport: f32) -> f32 {
+	viewport * (1.0 - PAGE_OVERLAP)
+}
+
+/// One screen further down, or the next post from the bottom
+pub(crate) fn page_down(offset: f32, content: f32, viewport: f32) -> PageTurn {
+	let bottom = bottom(content, viewport);
+	if offset >= bottom - EDGE_SLACK {
+		PageTurn::Next
+	} else {
+		PageTurn::ScrollTo((offset + step(viewport)).min(bottom))
+	}
+}
+
+/// One screen further up, or the previous post from the top
+pub(crate) fn page_up(offset: f32, viewport: f32) -> PageTurn {
+	if offset <= EDGE_SLACK {
+		PageTurn::Prev
+	} else {
+		PageTurn::ScrollTo((offset - step(viewport)).max(0.0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Offsets paging down goes through from the top, until it turns to the next post
+	fn pages_down(content: f32, viewport: f32) -> Vec<f32> {
+		let mut offset = 0.0;
+		let mut offsets = vec![offset];
+		while let PageTurn::ScrollTo(next) = page_down(offset, content, viewport) {
+			assert!(next > offset, "stuck at {}", offset);
+			offset = next;
+			offsets.push(offset);
+		}
+		offsets
+	}
+
+	#[test]
+	fn paging_down_ends_at_the_bottom_before_turning() {
+		// Shorter than the view, or exactly as tall: already at the bottom
+		assert_eq!(pages_down(300.0, 800.0), [0.0]);
+		assert_eq!(pages_down(800.0, 800.0), [0.0]);
+		// A little taller: one short scroll to the bottom
+		assert_eq!(pages_down(850.0, 800.0), [0.0, 50.0]);
+		// Long strips page a screen less the overlap at a time, the last step shorter
+		assert_eq!(pages_down(3000.0, 1000.0), [0.0, 900.0, 1800.0, 2000.0]);
+		// Layout fractions just short of the bottom count as the bottom
+		assert_eq!(page_down(1999.5, 3000.0, 1000.0), PageTurn::Next);
+		assert_eq!(
+			page_down(1998.0, 3000.0, 1000.0),
+			PageTurn::ScrollTo(2000.0)
+		);
+	}
+
+	#[test]
+	fn paging_up_ends_at_the_top_before_turning_back() {
+		assert_eq!(page_up(0.0, 800.0), PageTurn::Prev);
+		assert_eq!(page_up(0.4, 1000.0), PageTurn::Prev);
+		assert_eq!(page_up(2000.0, 1000.0), PageTurn::ScrollTo(1100.0));
+		assert_eq!(page_up(500.0, 1000.0), PageTurn::ScrollTo(0.0));
+
+		let reading = Reading {
+			offset: 2000.0,
+			content: 3000.0,
+			viewport: 1000.0,
+			..Default::default()
+		};
+		assert_eq!(reading.turn(true), PageTurn::Next);
+		assert_eq!(reading.turn(false), PageTurn::ScrollTo(1100.0));
+	}
+
+	#[test]
+	fn another_post_starts_from_the_top() {
+		let mut reading = Reading {
+			post_id: Some(1),
+			offset: 1000.0,
+			content: 3000.0,
+			viewport: 1000.0,
+			..Default::default()
+		};
+		reading.follow(Some(1));
+		assert_eq!(reading.turn(true), PageTurn::ScrollTo(1900.0));
+		reading.follow(Some(2));
+		assert_eq!(reading.turn(false), PageTurn::Prev);
+		assert_eq!(
+			(reading.offset, reading.content, reading.viewport),
+			(0.0, 0.0, 0.0)
+		);
+	}
+}
@@ -0,0 +1,208 @@
+use indexmap::IndexMap;
+use std::time::{Duration, Instant};
+
+/// The pointer has to move onto a covered image and stay this long to reveal it
+const HOVER_REVEAL: Duration = Duration::from_secs(1);
+
+/// Cross-fade from the blurred preview to the real image
+const REVEAL_FADE: Duration = Duration::from_millis(400);
+
+/// Revealed posts remembered, oldest forgotten first
+const MAX_REVEALED: usize = 500;
+
+/// Blur-until-reveal: images are covered by a blurred preview until revealed
+/// with a key, by hovering, or by the slideshow after `auto_delay`
+pub struct Reveal {
+	pub enabled: bool,
+	/// How long the slideshow shows an image covered before revealing it
+	pub auto_delay: Duration,
+	/// When each post was revealed, drives the fade
+	revealed: IndexMap<u64, Instant>,
+	/// Post the pointer moved onto, and when
+	hover_since: Option<(u64, Instant)>,
+}
+
+impl Reveal {
+	pub fn new(enabled: bool, auto_delay: Duration) -> Self {
+		Self {
+			enabled,
+			auto_delay,
+			revealed: IndexMap::new(),
+			hover_since: None,
+		}
+	}
+
+	pub fn reveal(&mut self, post_id: u64) {
+		self.reveal_at(post_id, Instant::now());
+	}
+
+	fn reveal_at(&mut self, post_id: u64, now: Instant) {
+		if !self.enabled || self.revealed.contains_key(&post_id) {
+			return;
+		}
+		log::debug!("Revealing post {}", post_id);
+		self.revealed.insert(post_id, now);
+		if self.revealed.len() > MAX_REVEALED {
+			self.revealed.shift_remove_index(0);
+		}
+	}
+
+	/// Opacity of the blurred preview over the post: 1 covered, 0 revealed
+	pub fn cover(&self, post_id: u64) -> f32 {
+		self.cover_at(post_id, Instant::now())
+	}
+
+	fn cover_at(&self, post_id: u64, now: Instant) -> f32 {
+		if !self.enabled {
+			return 0.0;
+		}
+		match self.revealed.get(&post_id) {
+			Some(at) => {
+				let faded = now.saturating_duration_since(*at).as_secs_f32();
+				1.0 - (faded / REVEAL_FADE.as_secs_f32()).min(1.0)
+			}
+			None => 1.0,
+		}
+	}
+
+	/// Reveal the post once hovered long enough, or when the slideshow's delay is up.
+	/// Returns how long until that happens, so the caller can repaint then.
+	pub fn update(
+		&mut self,
+		post_id: u64,
+		hovering: bool,
+		pointer_moved: bool,
+		shown_for: Duration,
+		auto_play: bool,
+	) -> Option<Duration> {
+		self.update_at(
+			post_id,
+			hovering,
+			pointer_moved,
+			shown_for,
+			auto_play,
+			Instant::now(),
+		)
+	}
+
+	fn update_at(
+		&mut self,
+		post_id: u64,
+		hovering: bool,
+		pointer_moved: bool,
+		shown_for: Duration,
+		auto_play: bool,
+		now: Instant,
+	) -> Option<Duration> {
+		if !self.enabled || self.revealed.contains_key(&post_id) {
+			self.hover_since = None;
+			return None;
+		}
+		if !hovering || self.hover_since.is_some_and(|(id, _)| id != post_id) {
+			self.hover_since = None;
+		}
+		// A pointer merely resting over the window doesn't count
+		if hovering && pointer_moved && self.hover_since.is_none() {
+			self.hover_since = Some((post_id, now));
+		}
+
+		let hover_left = self
+			.hover_since
+			.map(|(_, since)| HOVER_REVEAL.saturating_sub(now.saturating_duration_since(since)));
+		let auto_left = auto_play.then(|| self.auto_delay.saturating_sub(shown_for));
+		let left = hover_left.into_iter().chain(auto_left).min()?;
+		if left.is_zero() {
+			self.reveal_at(post_id, now);
+			self.hover_since = None;
+			return None;
+		}
+		Some(left)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const NO_SLIDESHOW: Duration = Duration::ZERO;
+
+	#[test]
+	fn hovered_post_goes_from_covered_through_the_fade_to_shown() {
+		let mut reveal = Reveal::new(true, Duration::from_secs(3));
+		let start = Instant::now();
+		assert_eq!(reveal.cover_at(1, start), 1.0);
+
+		// Hovering starts the countdown, the post stays covered meanwhile
+		let left = reveal.update_at(1, true, true, NO_SLIDESHOW, false, start);
+		assert_eq!(left, Some(HOVER_REVEAL));
+		let halfway = start + HOVER_REVEAL / 2;
+		let left = reveal.update_at(1, true, false, NO_SLIDESHOW, false, halfway);
+		assert_eq!(left, Some(HOVER_REVEAL / 2));
+		assert_eq!(reveal.cover_at(1, halfway), 1.0);
+
+		// Time's up: revealed, and the cover fades out
+		let revealed_at = start + HOVER_REVEAL;
+		assert_eq!(
+			reveal.update_at(1, true, false, NO_SLIDESHOW, false, revealed_at),
+			None
+		);
+		assert_eq!(reveal.cover_at(1, revealed_at), 1.0);
+		let fading = reveal.cover_at(1, revealed_at + REVEAL_FADE / 2);
+		assert!((fading - 0.5).abs() < 1e-3, "cover {}", fading);
+		assert_eq!(reveal.cover_at(1, revealed_at + REVEAL_FADE), 0.0);
+		assert_eq!(
+			reveal.update_at(
+				1,
+				true,
+				true,
+				NO_SLIDESHOW,
+				false,
+				revealed_at + REVEAL_FADE
+			),
+			None
+		);
+	}
+
+	#[test]
+	fn navigating_away_restarts_the_hover() {
+		let mut reveal = Reveal::new(true, Duration::from_secs(3));
+		let start = Instant::now();
+		reveal.update_at(1, true, true, NO_SLIDESHOW, false, start);
+
+		// The next post has to be hovered from scratch
+		let later = start + HOVER_REVEAL / 2;
+		let left = reveal.update_at(2, true, false, NO_SLIDESHOW, false, later);
+		assert_eq!(left, None);
+		let left = reveal.update_at(2, true, true, NO_SLIDESHOW, false, later);
+		assert_eq!(left, Some(HOVER_REVEAL));
+		assert_eq!(reveal.cover_at(1, start + HOVER_REVEAL), 1.0);
+		assert_eq!(reveal.cover_at(2, start + HOVER_REVEAL), 1.0);
+
+		// Coming back doesn't pick up where the first hover left off
+		let back = later + HOVER_REVEAL / 2;
+		let left = reveal.update_at(1, true, true, NO_SLIDESHOW, false, back);
+		assert_eq!(left, Some(HOVER_REVEAL));
+	}
+
+	#[test]
+	fn slideshow_reveals_after_its_delay() {
+		let delay = Duration::from_secs(3);
+		let mut reveal = Reveal::new(true, delay);
+		let start = Instant::now();
+		let left = reveal.update_at(1, false, false, Duration::from_secs(1), true, start);
+		assert_eq!(left, Some(Duration::from_secs(2)));
+		assert_eq!(reveal.update_at(1, false, false, delay, true, start), None);
+		assert_eq!(reveal.cover_at(1, start + REVEAL_FADE), 0.0);
+	}
+
+	#[test]
+	fn disabled_mode_never_covers() {
+		let mut reveal = Reveal::new(false, Duration::from_secs(3));
+		let start = Instant::now();
+		assert_eq!(reveal.cover_at(1, start), 0.0);
+		assert_eq!(
+			reveal.update_at(1, true, true, NO_SLIDESHOW, true, start),
+			None
+		);
+	}
+}
@@ -0,0 +1,92 @@
+use crate::api::Tags;
+use eframe::egui;
+
+/// e621's tag categories, as colored on the site
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TagCategory {
+	General,
+	Artist,
+	Copyright,
+	Character,
+	Species,
+	Invalid,
+	Meta,
+	Lore,
+}
+
+impl TagCategory {
+	pub fn label(self) -> &'static str {
+		match self {
+			TagCategory::General => "general",
+			TagCategory::Artist => "artist",
+			TagCategory::Copyright => "copyright",
+			TagCategory::Character => "character",
+			TagCategory::Species => "species",
+			TagCategory::Invalid => "invalid",
+			TagCategory::Meta => "meta",
+			TagCategory::Lore => "lore",
+		}
+	}
+
+	/// e621's dark theme colors, or brighter ones that stay readable with high contrast on
+	pub fn color(self, high_contrast: bool) -> egui::Color32 {
+		let (r, g, b) = match (self, high_contrast) {
+			(TagCategory::General, false) => (180, 199, 217),
+			(TagCategory::Artist, false) => (242, 172, 8),
+			(TagCategory::Copyright, false) => (221, 0, 221),
+			(TagCategory::Character, false) => (0, 170, 0),
+			(TagCategory::Species, false) => (237, 93, 31),
+			(TagCategory::Invalid, false) => (255, 61, 61),
+			(TagCategory::Meta, false) => (255, 255, 255),
+			(TagCategory::Lore, false) => (64, 170, 64),
+			(TagCategory::General, true) => (255, 255, 255),
+			(TagCategory::Artist, true) => (255, 215, 0),
+			(TagCategory::Copyright, true) => (255, 128, 255),
+			(TagCategory::Character, true) => (96, 255, 96),
+			(TagCategory::Species, true) => (255, 160, 80),
+			(TagCategory::Invalid, true) => (255, 110, 110),
+			(TagCategory::Meta, true) => (200, 220, 255),
+			(TagCategory::Lore, true) => (140, 255, 140),
+		};
+		egui::Color32::from_rgb(r, g, b)
+	}
+}
+
+/// Every tag on a post with its category, in the order `Tags::iter` gives them
+pub(crate) fn categorized(tags: &Tags) -> impl Iterator<Item = (TagCategory, &str)> {
+	[
+		(TagCategory::Artist, &tags.artist),
+		(TagCategory::Copyright, &tags.copyright),
+		(TagCategory::Character, &tags.character),
+		(TagCategory::Species, &tags.species),
+		(TagCategory::General, &tags.general),
+		(TagCategory::Meta, &tags.meta),
+		(TagCategory::Lore, &tags.lore),
+		(TagCategory::Invalid, &tags.invalid),
+	]
+	.into_iter()
+	.flat_map(|(category, tags)| tags.iter().map(move |tag| (category, tag.as_str())))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tags_are_grouped_by_category() {
+		let tags = Tags {
+			artist: vec!["someone".to_string()],
+			species: vec!["fox".to_string()],
+			..Default::default()
+		};
+		let grouped: Vec<_> = categorized(&tags).collect();
+		assert_eq!(
+			grouped,
+			[
+				(TagCategory::Artist, "someone"),
+				(TagCategory::Species, "fox")
+			]
+		);
+		assert!(grouped.iter().map(|(_, tag)| *tag).eq(tags.iter()));
+	}
+}